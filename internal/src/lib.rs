@@ -11,48 +11,140 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod trace_analysis;
+pub mod verification;
+
 use im::HashMap;
 use model::base_types::VehicleTypeIdx;
 use rapid_solve::heuristics::Solver;
 use solution::transition::Transition;
+use solver::local_search::maintenance_repair::repair_maintenance_violation;
 use solver::local_search::neighborhood::swaps::SwapInfo;
 use solver::local_search::ScheduleWithInfo;
 use solver::min_cost_flow_solver::MinCostFlowSolver;
 use solver::objective;
+use solver::phase_timings::PhaseTimings;
+use solver::trace::build_trace_writer;
 
-use model::json_serialisation::load_rolling_stock_problem_instance_from_json;
+use model::json_serialisation::{
+    try_load_rolling_stock_problem_instance_from_json, ValidationError,
+};
+use solver::transition_cycle_tsp::cycle_cache::TransitionCycleTspCache;
 use solver::transition_local_search::{build_transition_local_search_solver, TransitionWithInfo};
 
+use rapid_solve::objective::{EvaluatedSolution, Objective};
+use std::fmt;
+use std::io;
 use std::sync::Arc;
 use std::time as stdtime;
 
-pub fn run(input_data: serde_json::Value) -> serde_json::Value {
+/// Why a run produced no output.
+#[derive(Debug)]
+pub enum RunError {
+    /// the input data was rejected by the loader (see
+    /// try_load_rolling_stock_problem_instance_from_json).
+    InvalidInput(Vec<ValidationError>),
+    /// the output could not be written.
+    Output(serde_json::Error),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::InvalidInput(errors) => {
+                let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                write!(f, "Invalid input data: {}", messages.join(" "))
+            }
+            RunError::Output(error) => write!(f, "Error writing JSON: {}", error),
+        }
+    }
+}
+
+impl From<serde_json::Error> for RunError {
+    fn from(error: serde_json::Error) -> RunError {
+        RunError::Output(error)
+    }
+}
+
+pub fn run(input_data: serde_json::Value) -> Result<serde_json::Value, Vec<ValidationError>> {
+    let (final_solution, objective, runtime_duration, phase_timings) =
+        compute_final_solution(input_data)?;
+    Ok(server::create_output_json(
+        &final_solution,
+        &objective,
+        runtime_duration,
+        &phase_timings,
+        &[],
+        None,
+    ))
+}
+
+/// Same as run, but the (pretty-printed) output json is written tour by tour into the writer.
+pub fn run_to_writer<W: io::Write>(
+    input_data: serde_json::Value,
+    writer: W,
+) -> Result<(), RunError> {
+    let (final_solution, objective, runtime_duration, mut phase_timings) =
+        compute_final_solution(input_data).map_err(RunError::InvalidInput)?;
+    let output_json = server::create_streamed_output_json(
+        &final_solution,
+        &objective,
+        runtime_duration,
+        &phase_timings,
+        &[],
+        None,
+    );
+    phase_timings.measure("serialization", || {
+        serde_json::to_writer_pretty(writer, &output_json)
+    })?;
+    println!("{}", phase_timings.last().unwrap());
+    Ok(())
+}
+
+type FinalSolution = (
+    EvaluatedSolution<ScheduleWithInfo>,
+    Arc<Objective<ScheduleWithInfo>>,
+    stdtime::Duration,
+    PhaseTimings,
+);
+
+fn compute_final_solution(
+    input_data: serde_json::Value,
+) -> Result<FinalSolution, Vec<ValidationError>> {
     let start_time = stdtime::Instant::now();
-    let network = load_rolling_stock_problem_instance_from_json(input_data);
+    let mut phase_timings = PhaseTimings::new();
+    let network = phase_timings.measure("loading", || {
+        try_load_rolling_stock_problem_instance_from_json(input_data)
+    })?;
     println!(
-        "Instance with {} vehicle types and {} trips loaded (elapsed time: {:0.2}sec)",
+        "Instance with {} vehicle types and {} trips loaded ({})",
         network.vehicle_types().iter().count(),
         network.size(),
-        start_time.elapsed().as_secs_f32()
+        phase_timings.last().unwrap()
     );
 
-    let objective = Arc::new(objective::build());
+    let objective = Arc::new(objective::build(&network));
 
     println!("Solve with MinCostFlowSolver:");
-    let min_cost_flow_solver = MinCostFlowSolver::initialize(network.clone());
-    let start_schedule = min_cost_flow_solver.solve();
+    let start_schedule = phase_timings.measure("minCostFlow", || {
+        MinCostFlowSolver::initialize(network.clone()).solve()
+    });
     println!(
-        "MinCostFlowSolver computed schedule (elapsed time: {:0.2}sec)",
-        start_time.elapsed().as_secs_f32()
+        "MinCostFlowSolver computed schedule ({})",
+        phase_timings.last().unwrap()
     );
 
     let start_schedule_with_info = ScheduleWithInfo::new(
-        start_schedule.improve_depots(None),
+        phase_timings.measure("depotImprovement", || start_schedule.improve_depots(None)),
         SwapInfo::NoSwap,
         "Result from min cost flow solver".to_string(),
     );
+    println!("Depots improved ({})", phase_timings.last().unwrap());
 
-    let solution = if network.maintenance_considered() {
+    let solution = if network.service_trips_mutually_unreachable() {
+        println!("\nService trips are mutually unreachable, returning MinCostFlowSolver solution as final solution");
+        objective.evaluate(start_schedule_with_info.clone())
+    } else if network.maintenance_considered() {
         println!("\nStarting local search:\n");
         println!("Initial objective value:");
         objective.print_objective_value(
@@ -62,43 +154,124 @@ pub fn run(input_data: serde_json::Value) -> serde_json::Value {
         );
         println!();
 
-        let local_search_solver = solver::local_search::build_local_search_solver(network.clone());
+        let operator_weights = solver::local_search::build_operator_weights(&network);
+        let trace_writer = build_trace_writer(&network, operator_weights.clone(), || {
+            objective.objective_value_to_json(
+                objective
+                    .evaluate(start_schedule_with_info.clone())
+                    .objective_value(),
+            )
+        });
+        let progress_sink = trace_writer
+            .as_ref()
+            .map(|trace_writer| trace_writer.progress_sink());
+        let solution = phase_timings.measure_with_iterations("localSearch", |steps| match &network
+            .config()
+            .time_partitioning
+        {
+            Some(_) => solver::local_search::solve_time_partitioned(
+                start_schedule_with_info,
+                network.clone(),
+                steps,
+                operator_weights.clone(),
+                None,
+                progress_sink.clone(),
+                None,
+            ),
+            None => {
+                let local_search_solver = solver::local_search::build_local_search_solver(
+                    network.clone(),
+                    steps,
+                    operator_weights.clone(),
+                    None,
+                    progress_sink.clone(),
+                    None,
+                );
 
-        local_search_solver.solve(start_schedule_with_info)
+                local_search_solver.solve(start_schedule_with_info)
+            }
+        });
+        if let Some(operator_weights) = &operator_weights {
+            phase_timings.add_diagnostics("operatorWeights", operator_weights.to_json());
+        }
+        if let Some(Err(error)) = trace_writer.map(|trace_writer| trace_writer.finish()) {
+            println!("Error: {}", error);
+        }
+        println!(
+            "\nLocal search finished ({})",
+            phase_timings.last().unwrap()
+        );
+        solution
     } else {
         println!("\nMaintenance is not considered, returning MinCostFlowSolver solution as final solution");
         objective.evaluate(start_schedule_with_info.clone())
     };
 
+    let solution = if network.config().maintenance.hard
+        && solution.solution().get_schedule().maintenance_violation() > 0
+    {
+        println!("\nRepairing maintenance violation:");
+        let repaired_schedule = phase_timings
+            .measure_with_iterations("maintenanceRepair", |steps| {
+                repair_maintenance_violation(solution.solution().get_schedule().clone(), steps)
+            });
+        println!(
+            "Maintenance repair finished ({})",
+            phase_timings.last().unwrap()
+        );
+        objective.evaluate(ScheduleWithInfo::new(
+            repaired_schedule,
+            SwapInfo::NoSwap,
+            "Schedule after maintenance repair".to_string(),
+        ))
+    } else {
+        solution
+    };
+
     // optimize transitions
     println!("\nOptimizing transitions:");
-    let start_time_transition_optimization = stdtime::Instant::now();
-    let mut optimized_transitions: HashMap<VehicleTypeIdx, Transition> = HashMap::new();
     let schedule = solution.solution().get_schedule();
-    let transition_local_search_solver =
-        build_transition_local_search_solver(schedule, network.clone());
-    for vehicle_type in network.vehicle_types().iter() {
-        println!(
-            "\nOptimizing transitions for vehicle type {}",
-            network.vehicle_types().get(vehicle_type).unwrap()
-        );
-        let start_transition = TransitionWithInfo::new(
-            schedule.next_day_transition_of(vehicle_type).clone(),
-            "Initial transition".to_string(),
-        );
-        let improved_transition = transition_local_search_solver
-            .solve(start_transition)
-            .unwrap()
-            .unwrap_transition();
+    let cycle_cache = Arc::new(TransitionCycleTspCache::default());
+    let optimized_transitions =
+        phase_timings.measure_with_iterations("transitionOptimization", |steps| {
+            let mut optimized_transitions: HashMap<VehicleTypeIdx, Transition> = HashMap::new();
+            let transition_local_search_solver = build_transition_local_search_solver(
+                schedule,
+                network.clone(),
+                steps,
+                cycle_cache.clone(),
+                None,
+            );
+            for vehicle_type in network.vehicle_types().iter() {
+                if schedule.vehicles_iter(vehicle_type).next().is_none() {
+                    // unused vehicle types keep their (empty) transition
+                    optimized_transitions.insert(
+                        vehicle_type,
+                        schedule.next_day_transition_of(vehicle_type).clone(),
+                    );
+                    continue;
+                }
+                println!(
+                    "\nOptimizing transitions for vehicle type {}",
+                    network.vehicle_types().get(vehicle_type).unwrap()
+                );
+                let start_transition = TransitionWithInfo::new(
+                    schedule.next_day_transition_of(vehicle_type).clone(),
+                    "Initial transition".to_string(),
+                );
+                let improved_transition = transition_local_search_solver
+                    .solve(start_transition)
+                    .unwrap()
+                    .unwrap_transition();
 
-        optimized_transitions.insert(vehicle_type, improved_transition);
-    }
+                optimized_transitions.insert(vehicle_type, improved_transition);
+            }
+            optimized_transitions
+        });
+    phase_timings.add_diagnostics("transitionCycleCache", cycle_cache.to_json());
     let schedule_with_optimized_transitions =
         schedule.set_next_day_transitions(optimized_transitions);
-    println!(
-        "Transition optimized (elapsed time: {:0.2}sec)",
-        start_time_transition_optimization.elapsed().as_secs_f32()
-    );
+    println!("Transition optimized ({})", phase_timings.last().unwrap());
     schedule_with_optimized_transitions.print_next_day_transitions();
 
     // reassign end depots to be consistent with transitions
@@ -121,16 +294,11 @@ pub fn run(input_data: serde_json::Value) -> serde_json::Value {
     println!("\nFinal schedule:");
     final_schedule.print_tours();
 
-    let overflow_depot = network.overflow_depot_idxs().0;
-    for vehicle_type in network.vehicle_types().iter() {
-        if final_schedule.number_of_vehicles_of_same_type_spawned_at(overflow_depot, vehicle_type)
-            > 0
-        {
-            println!(
-            "\x1b[93mnote:\x1b[0m vehicle type {} uses the overflow depot. Consider adding more depot capacity for this type.",
-            vehicle_type
-        );
-        }
+    for warning in server::overflow_depot_warnings(final_schedule)
+        .into_iter()
+        .chain(server::maintenance_hard_constraint_warnings(final_schedule))
+    {
+        println!("\x1b[93mnote:\x1b[0m {}", warning);
     }
 
     // println!("\n\nFinal train formations:");
@@ -147,7 +315,8 @@ pub fn run(input_data: serde_json::Value) -> serde_json::Value {
             .total_depot_balance_violation()
     );
 
+    println!("\nPhase timings:\n{}", phase_timings);
     println!("Running time: {:0.2}sec", runtime_duration.as_secs_f32());
 
-    server::create_output_json(&final_solution, &objective, runtime_duration)
+    Ok((final_solution, objective, runtime_duration, phase_timings))
 }
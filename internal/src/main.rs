@@ -11,20 +11,54 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use internal::RunError;
+use model::json_serialisation::{perturb_instance, PerturbationConfig};
+use rapid_time::Duration;
 use std::fs::{self, File};
-use std::io::Read;
+use std::io::{BufReader, BufWriter, Read};
 use std::path::Path;
+use tracing::info_span;
+use tracing_subscriber::EnvFilter;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
     if args.len() < 2 {
         println!("Usage: {} <input_file>", args[0]);
+        println!("   or: {} perturb <input_file> <seed> [options]", args[0]);
+        println!("   or: {} verify <instance_file> <output_file>", args[0]);
+        println!("   or: {} analyze-trace <trace_file>", args[0]);
         std::process::exit(1)
     }
 
+    if args[1] == "perturb" {
+        perturb(&args);
+        std::process::exit(0)
+    }
+
+    if args[1] == "verify" {
+        let passed = verify(&args);
+        std::process::exit(if passed { 0 } else { 1 })
+    }
+
+    if args[1] == "analyze-trace" {
+        analyze_trace(&args);
+        std::process::exit(0)
+    }
+
     let path = &args[1];
 
+    // human-readable console output, the log level can be configured via RUST_LOG
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .without_time()
+        .with_target(false)
+        .init();
+    let span = info_span!("run", file = %path);
+    let _guard = span.enter();
+
     let mut file = match File::open(path) {
         Ok(file) => file,
         Err(error) => {
@@ -35,18 +69,133 @@ fn main() {
 
     let mut input_data = String::new();
     file.read_to_string(&mut input_data).unwrap();
-    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let input_data: serde_json::Value = match serde_json::from_str(&input_data) {
+        Ok(input_data) => input_data,
+        Err(error) => {
+            println!("Error: {}", error);
+            std::process::exit(1)
+        }
+    };
     println!("\n---------- RUN: {} ----------", path);
 
-    let output = internal::run(input_data);
-
     // output path with sub-directory creation
     let output_dir_name = "output";
     let output_path = ensure_output_path(path, output_dir_name);
-    let file = File::create(output_path).expect("Error creating file");
-    serde_json::to_writer_pretty(file, &output).expect("Error writing JSON");
+    let file = File::create(&output_path).expect("Error creating file");
+    match internal::run_to_writer(input_data, BufWriter::new(file)) {
+        Ok(()) => std::process::exit(0),
+        Err(RunError::InvalidInput(errors)) => {
+            for error in errors {
+                println!("Error: {}", error);
+            }
+            // no partial output file for a rejected instance
+            fs::remove_file(&output_path).ok();
+            std::process::exit(1)
+        }
+        Err(error) => panic!("{}", error),
+    }
+}
+
+/// Writes a randomly perturbed copy of the instance to output/perturbed_<seed>/output_<input_file>.
+/// Options (with defaults): --shift-fraction 0.2, --maximal-shift 5 (minutes),
+/// --demand-deviation 0.1, --drop-fraction 0.02, --add-fraction 0.02
+fn perturb(args: &[String]) {
+    if args.len() < 4 || !args.len().is_multiple_of(2) {
+        println!(
+            "Usage: {} perturb <input_file> <seed> [--shift-fraction <f>] [--maximal-shift <minutes>] \
+            [--demand-deviation <f>] [--drop-fraction <f>] [--add-fraction <f>]",
+            args[0]
+        );
+        std::process::exit(1)
+    }
+    let path = &args[2];
+    let seed: u64 = args[3]
+        .parse()
+        .expect("Seed must be a non-negative integer");
 
-    std::process::exit(0)
+    let mut config = PerturbationConfig {
+        shift_fraction: 0.2,
+        maximal_shift: Duration::from_seconds(5 * 60),
+        demand_deviation: 0.1,
+        drop_fraction: 0.02,
+        add_fraction: 0.02,
+    };
+    for option in args[4..].chunks(2) {
+        let value: f64 = option[1]
+            .parse()
+            .unwrap_or_else(|_| panic!("Value of {} must be a number", option[0]));
+        match option[0].as_str() {
+            "--shift-fraction" => config.shift_fraction = value,
+            "--maximal-shift" => config.maximal_shift = Duration::from_seconds(value as u64 * 60),
+            "--demand-deviation" => config.demand_deviation = value,
+            "--drop-fraction" => config.drop_fraction = value,
+            "--add-fraction" => config.add_fraction = value,
+            unknown => {
+                println!("Unknown option: {}", unknown);
+                std::process::exit(1)
+            }
+        }
+    }
+
+    let mut input_data = String::new();
+    File::open(path)
+        .expect("Error opening input file")
+        .read_to_string(&mut input_data)
+        .unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+
+    let perturbed = perturb_instance(input_data, &config, seed);
+
+    let output_path = ensure_output_path(path, &format!("output/perturbed_{}", seed));
+    let file = File::create(&output_path).expect("Error creating file");
+    serde_json::to_writer_pretty(BufWriter::new(file), &perturbed).expect("Error writing JSON");
+    println!("Perturbed instance written to {}", output_path);
+}
+
+/// Checks an output file against its instance (see internal::verification::verify_output) and
+/// prints a pass/fail report. Returns whether all checks passed.
+fn verify(args: &[String]) -> bool {
+    if args.len() != 4 {
+        println!("Usage: {} verify <instance_file> <output_file>", args[0]);
+        std::process::exit(1)
+    }
+    let read_json = |path: &str| -> serde_json::Value {
+        let mut data = String::new();
+        File::open(path)
+            .unwrap_or_else(|error| panic!("Error opening {}: {}", path, error))
+            .read_to_string(&mut data)
+            .unwrap();
+        serde_json::from_str(&data)
+            .unwrap_or_else(|error| panic!("Invalid json {}: {}", path, error))
+    };
+    let instance = read_json(&args[2]);
+    let output = read_json(&args[3]);
+
+    println!(
+        "\n---------- VERIFY: {} against {} ----------",
+        args[3], args[2]
+    );
+    let report = internal::verification::verify_output(instance, output);
+    report.print();
+    report.passed()
+}
+
+/// Prints the summary tables of a trace written with parameters.debug.traceFile (see
+/// internal::trace_analysis).
+fn analyze_trace(args: &[String]) {
+    if args.len() != 3 {
+        println!("Usage: {} analyze-trace <trace_file>", args[0]);
+        std::process::exit(1)
+    }
+    let file = File::open(&args[2]).expect("Error opening trace file");
+    println!("\n---------- TRACE: {} ----------", args[2]);
+    match internal::trace_analysis::analyze_trace(BufReader::new(file)) {
+        Ok(summary) => summary.print(),
+        Err(message) => {
+            println!("Error: {}", message);
+            std::process::exit(1)
+        }
+    }
 }
 
 fn ensure_output_path(input_path: &str, output_dir_name: &str) -> String {
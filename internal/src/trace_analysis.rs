@@ -0,0 +1,196 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+/// Fractions of the traced time at which the incumbent is shown in the time-to-quality curve.
+const CURVE_FRACTIONS: [f64; 10] = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0];
+
+struct TraceLine {
+    iteration: u64,
+    elapsed_seconds: f64,
+    operator: Option<String>,
+    objective_value: Vec<f64>, // in the order of TraceSummary::levels
+}
+
+/// The steps of an operator and, per level of the objective, the improvement of the steps that
+/// were decided on that level (the first level that changed).
+struct OperatorSummary {
+    steps: usize,
+    improvements: BTreeMap<usize, (usize, f64)>, // level -> (steps, improvement)
+}
+
+/// Summary of a trace written by solver::trace::TraceWriter (see parameters.debug.traceFile).
+pub struct TraceSummary {
+    levels: Vec<String>,
+    lines: Vec<TraceLine>, // the first line is the start schedule
+    operators: BTreeMap<String, OperatorSummary>,
+}
+
+/// Reads a trace line by line. Fails on lines that are not valid trace lines or whose objective
+/// has other levels than the first line.
+pub fn analyze_trace(reader: impl BufRead) -> Result<TraceSummary, String> {
+    let mut levels: Vec<String> = Vec::new();
+    let mut lines: Vec<TraceLine> = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| error.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let json: serde_json::Value = serde_json::from_str(&line)
+            .map_err(|error| format!("Line {}: {}", number + 1, error))?;
+        let objective_value = json["objectiveValue"]
+            .as_object()
+            .ok_or_else(|| format!("Line {}: objectiveValue missing", number + 1))?;
+        if levels.is_empty() {
+            levels = objective_value.keys().cloned().collect();
+        }
+        if objective_value.len() != levels.len() {
+            return Err(format!("Line {}: objective levels differ", number + 1));
+        }
+        lines.push(TraceLine {
+            iteration: json["iteration"].as_u64().unwrap_or_default(),
+            elapsed_seconds: json["elapsedSeconds"].as_f64().unwrap_or_default(),
+            operator: json["operator"]
+                .as_str()
+                .map(|operator| operator.to_string()),
+            objective_value: levels
+                .iter()
+                .map(|level| {
+                    objective_value
+                        .get(level)
+                        .and_then(|value| value.as_f64())
+                        .ok_or_else(|| format!("Line {}: {} missing", number + 1, level))
+                })
+                .collect::<Result<_, _>>()?,
+        });
+    }
+    if lines.is_empty() {
+        return Err("The trace is empty".to_string());
+    }
+
+    let mut operators: BTreeMap<String, OperatorSummary> = BTreeMap::new();
+    for (previous, line) in lines.iter().zip(lines.iter().skip(1)) {
+        let summary = operators
+            .entry(
+                line.operator
+                    .clone()
+                    .unwrap_or_else(|| "noSwap".to_string()),
+            )
+            .or_insert(OperatorSummary {
+                steps: 0,
+                improvements: BTreeMap::new(),
+            });
+        summary.steps += 1;
+        let deciding_level = previous
+            .objective_value
+            .iter()
+            .zip(line.objective_value.iter())
+            .position(|(old, new)| old != new);
+        if let Some(level) = deciding_level {
+            let improvement = summary.improvements.entry(level).or_insert((0, 0.0));
+            improvement.0 += 1;
+            improvement.1 += previous.objective_value[level] - line.objective_value[level];
+        }
+    }
+
+    Ok(TraceSummary {
+        levels,
+        lines,
+        operators,
+    })
+}
+
+impl TraceSummary {
+    pub fn print(&self) {
+        let first = self.lines.first().unwrap();
+        let last = self.lines.last().unwrap();
+        let steps = self.lines.len() - 1;
+        println!(
+            "{} steps in {:0.2}sec (last iteration {})",
+            steps, last.elapsed_seconds, last.iteration
+        );
+
+        println!("\nImprovement per operator:");
+        println!(
+            "{:<34} {:>8} {:>8}  improvements (level: steps, improvement)",
+            "operator", "steps", "share"
+        );
+        for (operator, summary) in self.operators.iter() {
+            let improvements: Vec<String> = summary
+                .improvements
+                .iter()
+                .map(|(&level, (steps, improvement))| {
+                    format!("{}: {}, {}", self.levels[level], steps, improvement)
+                })
+                .collect();
+            println!(
+                "{:<34} {:>8} {:>7.1}%  {}",
+                operator,
+                summary.steps,
+                100.0 * summary.steps as f64 / steps as f64,
+                improvements.join("; ")
+            );
+        }
+
+        println!("\nTime to quality (first step reaching the final value of a level):");
+        println!(
+            "{:<34} {:>14} {:>14} {:>10} {:>10}",
+            "level", "start", "final", "iteration", "elapsed"
+        );
+        for (level, name) in self.levels.iter().enumerate() {
+            let final_value = last.objective_value[level];
+            // the value of a level can get worse in favor of a more important level
+            let reached = self
+                .lines
+                .iter()
+                .rposition(|line| line.objective_value[level] != final_value)
+                .map(|position| &self.lines[position + 1])
+                .unwrap_or(first);
+            println!(
+                "{:<34} {:>14} {:>14} {:>10} {:>9.2}s",
+                name,
+                first.objective_value[level],
+                final_value,
+                reached.iteration,
+                reached.elapsed_seconds
+            );
+        }
+
+        println!("\nIncumbent over time:");
+        print!("{:>10} {:>10}", "elapsed", "iteration");
+        for name in self.levels.iter() {
+            print!(" {:>w$}", name, w = name.len().max(10));
+        }
+        println!();
+        for fraction in CURVE_FRACTIONS {
+            let time = fraction * last.elapsed_seconds;
+            let incumbent = self
+                .lines
+                .iter()
+                .take_while(|line| line.elapsed_seconds <= time)
+                .last()
+                .unwrap_or(first);
+            print!("{:>9.2}s {:>10}", time, incumbent.iteration);
+            for (level, name) in self.levels.iter().enumerate() {
+                print!(
+                    " {:>w$}",
+                    incumbent.objective_value[level],
+                    w = name.len().max(10)
+                );
+            }
+            println!();
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! Independent check of an output json against its instance, without rerunning the solver.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use model::json_serialisation::load_rolling_stock_problem_instance_from_json;
+use rapid_solve::objective::Objective;
+use solution::json_serialisation::schedule_from_json;
+use solution::Schedule;
+use solver::local_search::neighborhood::swaps::SwapInfo;
+use solver::local_search::ScheduleWithInfo;
+use solver::objective;
+
+/// Indicators that depend on the next-day transitions or on the order of the vehicles within the
+/// formations. Neither is imported by schedule_from_json, so these indicators are not compared.
+const NOT_RECOMPUTABLE_INDICATORS: [&str; 3] = [
+    "maintenanceViolation",
+    "vehiclesWithoutMaintenance",
+    "formationOrderShunts",
+];
+
+pub struct VerificationReport {
+    checks: Vec<(&'static str, Result<(), String>)>,
+}
+
+impl VerificationReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|(_, result)| result.is_ok())
+    }
+
+    pub fn print(&self) {
+        for (name, result) in self.checks.iter() {
+            match result {
+                Ok(()) => println!("[PASS] {}", name),
+                Err(message) => println!("[FAIL] {}: {}", name, message),
+            }
+        }
+        if self.passed() {
+            println!("\nVerification passed.");
+        } else {
+            println!("\nVerification failed.");
+        }
+    }
+}
+
+/// Rebuilds the schedule of the output on the instance and checks:
+/// - vehicleCycles: each vehicle of a fleet appears in exactly one vehicle cycle of its fleet
+/// - coverage: each departure segment with passengers is served by at least one vehicle
+/// - consistency: Schedule::verify_consistency (formation limits, depot capacities, maintenance
+///   counters, transitions, costs), panics are reported as failures
+/// - objectiveValue: the recomputed objective value matches the one of the output (except for
+///   the NOT_RECOMPUTABLE_INDICATORS)
+///
+/// If the schedule cannot be rebuilt, only this failure is reported.
+pub fn verify_output(instance: serde_json::Value, output: serde_json::Value) -> VerificationReport {
+    let mut checks = vec![];
+    let network = load_rolling_stock_problem_instance_from_json(instance);
+
+    let schedule = match schedule_from_json(output["schedule"].clone(), network) {
+        Ok((schedule, _)) => {
+            checks.push(("schedule", Ok(())));
+            schedule
+        }
+        Err(message) => {
+            checks.push(("schedule", Err(message)));
+            return VerificationReport { checks };
+        }
+    };
+
+    checks.push(("vehicleCycles", check_vehicle_cycles(&output["schedule"])));
+    checks.push(("coverage", check_coverage(&schedule)));
+    checks.push((
+        "consistency",
+        panic::catch_unwind(AssertUnwindSafe(|| schedule.verify_consistency()))
+            .map_err(|payload| panic_message(payload.as_ref())),
+    ));
+    checks.push((
+        "objectiveValue",
+        check_objective_value(schedule, &output["objectiveValue"]),
+    ));
+
+    VerificationReport { checks }
+}
+
+fn check_vehicle_cycles(schedule_json: &serde_json::Value) -> Result<(), String> {
+    let fleets = schedule_json["fleet"]
+        .as_array()
+        .ok_or("schedule.fleet is missing")?;
+    for fleet in fleets {
+        let mut vehicles: Vec<&str> = fleet["vehicles"]
+            .as_array()
+            .ok_or("fleet.vehicles is missing")?
+            .iter()
+            .filter_map(|vehicle| vehicle["id"].as_str())
+            .collect();
+        let mut vehicles_in_cycles: Vec<&str> = fleet["vehicleCycles"]
+            .as_array()
+            .ok_or("fleet.vehicleCycles is missing")?
+            .iter()
+            .flat_map(|cycle| cycle.as_array().into_iter().flatten())
+            .filter_map(|vehicle| vehicle.as_str())
+            .collect();
+        vehicles.sort_unstable();
+        vehicles_in_cycles.sort_unstable();
+        if vehicles != vehicles_in_cycles {
+            return Err(format!(
+                "the vehicle cycles of vehicle type {} do not contain each vehicle exactly once",
+                fleet["vehicleType"]
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn check_coverage(schedule: &Schedule) -> Result<(), String> {
+    let network = schedule.get_network();
+    let uncovered: Vec<&str> = network
+        .all_service_nodes()
+        .filter(|&node| {
+            network.node(node).as_service_trip().passengers() > 0
+                && schedule.train_formation_of(node).vehicle_count() == 0
+        })
+        .map(|node| network.node(node).id())
+        .collect();
+    if uncovered.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "departure segments with passengers but without vehicles: {}",
+            uncovered.join(", ")
+        ))
+    }
+}
+
+fn check_objective_value(
+    schedule: Schedule,
+    objective_value_json: &serde_json::Value,
+) -> Result<(), String> {
+    let network = schedule.get_network();
+    let objective: Objective<ScheduleWithInfo> = objective::build(&network);
+    let evaluated = objective.evaluate(ScheduleWithInfo::new(
+        schedule,
+        SwapInfo::NoSwap,
+        "Schedule imported for verification".to_string(),
+    ));
+    let recomputed = objective.objective_value_to_json(evaluated.objective_value());
+
+    let reported = objective_value_json
+        .as_object()
+        .ok_or("objectiveValue is missing")?;
+    let mismatches: Vec<String> = reported
+        .iter()
+        .filter(|(name, _)| !NOT_RECOMPUTABLE_INDICATORS.contains(&name.as_str()))
+        .filter(|(name, value)| recomputed.get(name.as_str()) != Some(*value))
+        .map(|(name, value)| {
+            format!(
+                "{} (reported {}, recomputed {})",
+                name,
+                value,
+                recomputed
+                    .get(name.as_str())
+                    .unwrap_or(&serde_json::Value::Null)
+            )
+        })
+        .collect();
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches.join(", "))
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "verify_consistency panicked".to_string()),
+    }
+}
@@ -16,9 +16,11 @@ use derive_more::From;
 
 pub mod distance;
 pub mod location;
+pub mod time_span;
 
 pub use distance::Distance;
 pub use location::Location;
+pub use time_span::checked_duration_between;
 
 pub type Idx = u16;
 
@@ -108,4 +110,11 @@ pub type Cost = u64;
 pub const INF_DISTANCE: Meter = 10_000_000; // distance for Infinity (used for maintenance counter
                                             // from the overhead depot)
 pub const MAX_DISTANCE: Meter = 1_000_000; // dead head distances larger than this are reduced to this value
+
+// Maxima of the input accepted by the loader (larger values are rejected). They keep the sums
+// over formations, tours and all service trips far from an overflow.
+pub const MAX_VEHICLE_CAPACITY: PassengerCount = 100_000; // capacity and seats of a vehicle type
+pub const MAX_PASSENGERS: PassengerCount = 1_000_000; // passengers and seated of a segment
+pub const MAX_TOTAL_PASSENGERS: PassengerCount = PassengerCount::MAX; // over all departure segments
+pub const MAX_SEGMENT_DISTANCE: Meter = MAX_DISTANCE; // distance of a route segment
 pub type MaintenanceCounter = i64;
@@ -0,0 +1,32 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use rapid_time::{DateTime, Duration};
+
+/// Returns end - start. Unlike the subtraction of rapid_time, this fails with an error (instead of
+/// panicking or wrapping) if end is before start or if one of them is DateTime::Earliest or
+/// DateTime::Latest. Callers are expected to add the context (e.g., the id of the trip).
+pub fn checked_duration_between(start: DateTime, end: DateTime) -> Result<Duration, String> {
+    for time in [start, end] {
+        if time == DateTime::Earliest || time == DateTime::Latest {
+            return Err(format!(
+                "time span between {} and {} is unbounded",
+                start, end
+            ));
+        }
+    }
+    if end < start {
+        return Err(format!("{} is before {}", end, start));
+    }
+    Ok(end - start)
+}
@@ -11,18 +11,61 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use rapid_time::Duration;
+use std::collections::{HashMap, HashSet};
 
-use crate::base_types::{Cost, Distance};
+use rapid_time::{DateTime, Duration};
+
+use crate::base_types::{Cost, Distance, VehicleCount, VehicleTypeIdx};
 
 pub struct Config {
     pub forbid_dead_head_trip: bool,
+    pub maximal_dead_head_duration: Option<Duration>, // longer dead-head trips are infeasible
     pub day_limit_threshold: Duration,
     pub shunting: ShuntingConfig,
     pub maintenance: MaintenanceConfig,
     pub costs: CostsConfig,
+    pub time_partitioning: Option<TimePartitioningConfig>,
+    pub fixed_fleet: HashMap<VehicleTypeIdx, VehicleCount>, // types without entry are not fixed
+    pub check_costs: bool, // recompute the costs from scratch after each local search step
+    pub formation_order_continuity: bool, // penalize transitions needing a shunt due to positions
+    pub dead_head_pairing_tolerance: Duration, // for suggesting pairs of dead-head trips
+    pub collapse_location_groups: bool, // see locations::LocationGroup
+    pub memory_budget: Option<u64>, // in megabytes, see solver::local_search::neighborhood
+    pub planning_window: Option<(DateTime, DateTime)>, // horizon, None means taken from the data
+    pub adaptive_operator_weights: Option<AdaptiveOperatorWeightsConfig>,
+    pub output_level: OutputLevel,
+    pub crowding_thresholds: CrowdingThresholds,
+    pub contract_dead_head_allocation: ContractDeadHeadAllocation,
+    pub webhook: Option<WebhookConfig>,
+    pub milestones: Vec<Duration>, // ascending elapsed times at which the incumbent is snapshotted
+    pub out_of_service_hard: bool, // see VehicleType::out_of_service_windows
+    pub depot_assignment: DepotAssignmentConfig,
+    pub hints: Vec<Hint>, // applied to the start schedule before the local search
+    pub active_vehicle_types: Option<HashSet<VehicleTypeIdx>>, // None means all types are active
+    pub trace_file: Option<String>, // see solver::trace
+    pub time_limit: Option<Duration>, // local search and transitions together, None: unlimited
+    pub number_of_threads: Option<usize>, // of a dedicated pool of the solve, None: global pool
+    pub two_phase: bool,  // fix the fleet size before optimizing the quality of the schedule
+    pub infeasibility_check: Option<InfeasibilityCheckConfig>,
+}
+
+/// If present, the start schedule (of the min-cost flow solver) is assessed before the local
+/// search: if the share of the passenger demand that is not covered by vehicles of regular depots
+/// exceeds the threshold, the instance is likely infeasible (see
+/// solution::overflow_analysis::uncovered_demand).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InfeasibilityCheckConfig {
+    pub threshold: f64, // in [0, 1]
+    pub policy: InfeasibilityPolicy,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InfeasibilityPolicy {
+    Warn,  // continue with the local search, but warn (default)
+    Abort, // return the start schedule without local search
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct ShuntingConfig {
     pub minimal: Duration,
     pub dead_head_trip: Duration,
@@ -30,6 +73,30 @@ pub struct ShuntingConfig {
 
 pub struct MaintenanceConfig {
     pub maximal_distance: Distance,
+    pub depot_credit: DepotMaintenanceCredit, // for tours ending at a depot providing maintenance
+    pub hard: bool, // the final schedule must not violate maintenance (see the local search)
+}
+
+/// How the start and end depots of a tour are chosen (see solution::Schedule::improve_depots).
+/// Only depots with capacity left are considered.
+pub struct DepotAssignmentConfig {
+    pub policy: DepotAssignmentPolicy,
+    pub distance_tolerance: Distance, // depots farther than the nearest one plus this are ignored
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepotAssignmentPolicy {
+    Nearest,      // the nearest depot (default)
+    BalanceAware, // the depot that reduces the depot balance violation, ties by distance
+    CostAware,    // as BalanceAware, but ties by spawn costs plus costs of the dead-head trip
+}
+
+/// How much of the maintenance counter is credited to a tour ending at a depot that provides
+/// maintenance (a light maintenance visit overnight).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepotMaintenanceCredit {
+    Full,            // like visiting a maintenance slot (maximal_distance is credited)
+    Fixed(Distance), // the given distance is credited
 }
 
 pub struct CostsConfig {
@@ -38,6 +105,145 @@ pub struct CostsConfig {
     pub maintenance: Cost,
     pub dead_head_trip: Cost,
     pub idle: Cost,
+    pub line_purity: Cost,          // per distinct vehicle serving a route
+    pub hitch_hike_bonus: Cost,     // per second a vehicle hitch-hikes on a service trip
+    pub dead_head_trip_count: Cost, // per dead-head trip (see Tour::dead_head_trip_count)
+    pub duty_length_variance: Cost, // per minute² (see Schedule::duty_length_variance)
+    pub staff_time_factors: Vec<StaffTimeFactor>, // surcharges on dead-head time by time of day
+}
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// Dead-head time (which includes the staff, see costs.dead_head_trip) within a daily time window
+/// is charged factor times (e.g. night or early-morning shifts). The window is given in seconds
+/// since midnight and wraps midnight if start > end (e.g. from 22:00 to 05:00).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StaffTimeFactor {
+    pub start: u64,
+    pub end: u64,
+    pub factor: f64, // at least 1.0
+}
+
+impl StaffTimeFactor {
+    /// seconds of the movement [start, start + duration) that lie within the window on any day.
+    /// start is given in seconds since some midnight.
+    pub fn overlap(&self, start: u64, duration: u64) -> u64 {
+        // shifted by a day, such that the window opened on the day before is representable
+        let start = start + SECONDS_PER_DAY;
+        let end = start + duration;
+        let window_length = if self.start < self.end {
+            self.end - self.start
+        } else {
+            SECONDS_PER_DAY - self.start + self.end
+        };
+        // the window opened on the day before the movement might still be open
+        (start / SECONDS_PER_DAY - 1..=end / SECONDS_PER_DAY)
+            .map(|day| {
+                let window_start = day * SECONDS_PER_DAY + self.start;
+                let window_end = window_start + window_length;
+                end.min(window_end).saturating_sub(start.max(window_start))
+            })
+            .sum()
+    }
+}
+
+impl CostsConfig {
+    /// extra costs of the staff time factors for a dead-head trip of the given duration (in
+    /// seconds) departing at start (in seconds since some midnight): each second within a window
+    /// is charged (factor - 1) * dead_head_trip on top. Overlapping windows add up.
+    pub fn staff_time_surcharge(&self, start: u64, duration: u64) -> Cost {
+        let surcharged_seconds: f64 = self
+            .staff_time_factors
+            .iter()
+            .map(|factor| factor.overlap(start, duration) as f64 * (factor.factor - 1.0))
+            .sum();
+        (surcharged_seconds * self.dead_head_trip as f64).round() as Cost
+    }
+
+    /// the largest factor a second of dead-head time can be charged with (at least 1.0).
+    pub fn maximal_staff_time_factor(&self) -> f64 {
+        1.0 + self
+            .staff_time_factors
+            .iter()
+            .map(|factor| factor.factor - 1.0)
+            .sum::<f64>()
+    }
+}
+
+/// If present, the local search is run on overlapping time windows of the planning horizon one
+/// after another, followed by a global polish (see solver::local_search).
+pub struct TimePartitioningConfig {
+    pub window_length: Duration,
+    pub overlap: Duration,
+    pub polish_time_limit: Option<Duration>, // None means unlimited
+}
+
+/// If present, the local search samples the neighbors of each operator with a share that is
+/// adapted to the recent success rate of the operator (see
+/// solver::local_search::neighborhood::operator_weights).
+pub struct AdaptiveOperatorWeightsConfig {
+    pub interval: u32, // number of local search steps between two adaptions
+    pub minimal_share: f64,
+    pub maximal_share: f64,
+    pub reaction_factor: f64, // 0: shares are never changed, 1: shares follow the last interval
+    pub initial_weights: HashMap<String, f64>, // operator name -> share (default: maximal_share)
+}
+
+/// Which sections the output contains. The schedule is always computed completely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputLevel {
+    Full,          // everything (default)
+    Summary,       // no schedule (i.e., no tours)
+    ObjectiveOnly, // only info and objective value
+}
+
+/// How the distance of a dead-head trip is allocated to the contracts of the service trips around
+/// it in the vehicle-km accounting (see solution::contract_accounting).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContractDeadHeadAllocation {
+    FollowingTrip, // all to the following service trip (default, the preceding one at a tour end)
+    Split,         // half to the preceding and half to the following service trip
+}
+
+/// If present, the server posts notifications about the solve to the url (see server::webhook).
+pub struct WebhookConfig {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub min_interval: Duration, // between two improvement notifications
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookEvent {
+    Improvement, // the local search found a better schedule
+    Finished,
+    Failed,
+}
+
+/// Lower bounds of the crowding classes (as load factors, i.e., passengers per capacity).
+/// Below moderate a trip is low, from high on it is high and if not all passengers are served
+/// it is overcrowded.
+pub struct CrowdingThresholds {
+    pub moderate: f64,
+    pub high: f64,
+}
+
+/// A move suggested by the user that is applied (best-effort) to the start schedule before the
+/// local search (see solution::Schedule::apply_hint). Vehicles are referred to by their ids in
+/// the output (e.g., "veh_3"), trips by the id of their departure segment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hint {
+    MergeTours {
+        vehicle: String,
+        into_vehicle: String,
+    }, // all trips of vehicle to into_vehicle
+    MoveTrip {
+        trip: String,
+        to_vehicle: String,
+    },
+    AssignDepot {
+        vehicle: String,
+        depot: String,
+    }, // as start and end depot
 }
 
 impl Config {
@@ -53,9 +259,42 @@ impl Config {
         costs_maintenance: Cost,
         costs_dead_head_trip: Cost,
         costs_idle: Cost,
+        costs_line_purity: Cost,
+        time_partitioning: Option<TimePartitioningConfig>,
+        fixed_fleet: HashMap<VehicleTypeIdx, VehicleCount>,
+        check_costs: bool,
+        formation_order_continuity: bool,
+        dead_head_pairing_tolerance: Duration,
+        adaptive_operator_weights: Option<AdaptiveOperatorWeightsConfig>,
+        output_level: OutputLevel,
+        crowding_thresholds: CrowdingThresholds,
+        maintenance_depot_credit: DepotMaintenanceCredit,
+        maintenance_hard: bool,
+        costs_hitch_hike_bonus: Cost,
+        collapse_location_groups: bool,
+        hints: Vec<Hint>,
+        memory_budget: Option<u64>,
+        planning_window: Option<(DateTime, DateTime)>,
+        maximal_dead_head_duration: Option<Duration>,
+        contract_dead_head_allocation: ContractDeadHeadAllocation,
+        webhook: Option<WebhookConfig>,
+        depot_assignment_policy: DepotAssignmentPolicy,
+        depot_assignment_distance_tolerance: Distance,
+        milestones: Vec<Duration>,
+        out_of_service_hard: bool,
+        active_vehicle_types: Option<HashSet<VehicleTypeIdx>>,
+        trace_file: Option<String>,
+        time_limit: Option<Duration>,
+        costs_dead_head_trip_count: Cost,
+        costs_duty_length_variance: Cost,
+        number_of_threads: Option<usize>,
+        two_phase: bool,
+        costs_staff_time_factors: Vec<StaffTimeFactor>,
+        infeasibility_check: Option<InfeasibilityCheckConfig>,
     ) -> Config {
         Config {
             forbid_dead_head_trip,
+            maximal_dead_head_duration,
             day_limit_threshold,
             shunting: ShuntingConfig {
                 minimal: shunting_minimal,
@@ -63,6 +302,8 @@ impl Config {
             },
             maintenance: MaintenanceConfig {
                 maximal_distance: maintenance_maximal_distance,
+                depot_credit: maintenance_depot_credit,
+                hard: maintenance_hard,
             },
             costs: CostsConfig {
                 staff: costs_staff,
@@ -70,7 +311,47 @@ impl Config {
                 maintenance: costs_maintenance,
                 dead_head_trip: costs_dead_head_trip,
                 idle: costs_idle,
+                line_purity: costs_line_purity,
+                hitch_hike_bonus: costs_hitch_hike_bonus,
+                dead_head_trip_count: costs_dead_head_trip_count,
+                duty_length_variance: costs_duty_length_variance,
+                staff_time_factors: costs_staff_time_factors,
+            },
+            time_partitioning,
+            fixed_fleet,
+            check_costs,
+            formation_order_continuity,
+            dead_head_pairing_tolerance,
+            collapse_location_groups,
+            memory_budget,
+            planning_window,
+            adaptive_operator_weights,
+            output_level,
+            crowding_thresholds,
+            contract_dead_head_allocation,
+            webhook,
+            milestones,
+            out_of_service_hard,
+            depot_assignment: DepotAssignmentConfig {
+                policy: depot_assignment_policy,
+                distance_tolerance: depot_assignment_distance_tolerance,
             },
+            hints,
+            active_vehicle_types,
+            trace_file,
+            time_limit,
+            number_of_threads,
+            two_phase,
+            infeasibility_check,
+        }
+    }
+
+    /// Only active vehicle types are optimized by the local search. The tours of inactive types
+    /// and the assignment of their service trips are kept as in the start schedule.
+    pub fn is_active_vehicle_type(&self, vehicle_type: VehicleTypeIdx) -> bool {
+        match &self.active_vehicle_types {
+            None => true,
+            Some(active_vehicle_types) => active_vehicle_types.contains(&vehicle_type),
         }
     }
 }
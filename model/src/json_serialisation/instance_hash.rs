@@ -0,0 +1,64 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use serde_json::{Map, Value};
+
+// the sections of the input that define the instance (the trips are given by routes and
+// departures), the parameters are not part of the instance
+const HASHED_SECTIONS: [&str; 4] = ["vehicleTypes", "locations", "routes", "departures"];
+
+// FNV-1a (64 bit), stable across platforms and compiler versions
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Canonical content hash of the instance (vehicle types, locations, routes and departures) as 16
+/// hex digits. Keys of json objects are sorted before hashing, so the hash does not depend on the
+/// key order. Missing sections are hashed as null.
+/// A schedule carries the hash of its instance (info.instanceHash of the output), so that it is
+/// not accidentally evaluated on another instance.
+pub fn instance_hash(input_data: &Value) -> String {
+    let sections: Map<String, Value> = HASHED_SECTIONS
+        .iter()
+        .map(|&section| {
+            (
+                section.to_string(),
+                canonical(input_data.get(section).unwrap_or(&Value::Null)),
+            )
+        })
+        .collect();
+    let hash = Value::Object(sections)
+        .to_string()
+        .bytes()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+    format!("{:016x}", hash)
+}
+
+/// Copy of the value with the keys of all objects sorted.
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), canonical(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(array) => Value::Array(array.iter().map(canonical).collect()),
+        _ => value.clone(),
+    }
+}
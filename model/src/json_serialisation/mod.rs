@@ -11,27 +11,47 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+mod instance_hash;
+mod perturbation;
 #[cfg(test)]
 mod tests;
 
+pub use instance_hash::instance_hash;
+pub use perturbation::{perturb_instance, PerturbationConfig};
+
+use rapid_time::{DateTime, Duration};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::sync::Arc;
-use rapid_time::{DateTime, Duration};
+use tracing::{error, warn};
 
 use crate::base_types::{
-    DepotIdx, Distance, Idx, LocationIdx, Meter, PassengerCount, VehicleCount, VehicleTypeIdx,
-    MAX_DISTANCE,
+    checked_duration_between, DepotIdx, Distance, Idx, LocationIdx, Meter, PassengerCount,
+    VehicleCount, VehicleTypeIdx, MAX_DISTANCE, MAX_PASSENGERS, MAX_SEGMENT_DISTANCE,
+    MAX_TOTAL_PASSENGERS, MAX_VEHICLE_CAPACITY,
+};
+use crate::config::{
+    AdaptiveOperatorWeightsConfig, Config,
+    ContractDeadHeadAllocation as ModelContractDeadHeadAllocation,
+    CrowdingThresholds as ModelCrowdingThresholds,
+    DepotAssignmentPolicy as ModelDepotAssignmentPolicy, DepotMaintenanceCredit, Hint as ModelHint,
+    InfeasibilityCheckConfig, InfeasibilityPolicy as ModelInfeasibilityPolicy,
+    OutputLevel as ModelOutputLevel, StaffTimeFactor as ModelStaffTimeFactor,
+    TimePartitioningConfig, WebhookConfig, WebhookEvent as ModelWebhookEvent,
 };
-use crate::config::Config;
-use crate::locations::{DeadHeadTrip, Locations};
-use crate::network::depot::Depot as ModelDepot;
+use crate::locations::{
+    DeadHeadTimeFactor as ModelDeadHeadTimeFactor, DeadHeadTrip,
+    LocationGroup as ModelLocationGroup, Locations, PlatformIdleLimits,
+};
+use crate::network::depot::{Depot as ModelDepot, DepotCapacities};
 use crate::network::nodes::MaintenanceSlot as ModelMaintenanceSlot;
 use crate::network::nodes::Node;
 use crate::network::nodes::ServiceTrip as ModelServiceTrip;
 use crate::network::Network;
 use crate::vehicle_types::VehicleType as ModelVehicleType;
 use crate::vehicle_types::VehicleTypes;
+use crate::warnings::{Severity, Warning};
 
 type IdType = String;
 type Integer = u64;
@@ -42,11 +62,14 @@ type DateTimeString = String;
 struct JsonInput {
     vehicle_types: Vec<VehicleType>,
     locations: Vec<Location>,
+    location_groups: Option<Vec<LocationGroup>>,
     depots: Option<Vec<Depot>>,
     routes: Vec<Route>,
     departures: Vec<Departures>,
     maintenance_slots: Option<Vec<MaintenanceSlots>>,
     dead_head_trips: DeadHeadTrips,
+    dead_head_time_factors: Option<Vec<DeadHeadTimeFactor>>,
+    hints: Option<Vec<Hint>>,
     parameters: Parameters,
 }
 
@@ -57,6 +80,30 @@ struct VehicleType {
     capacity: Integer,
     seats: Integer,
     maximal_formation_count: Option<Integer>,
+    out_of_service_windows: Option<Vec<TimeWindow>>,
+    minimum_in_service: Option<Vec<MinimumInService>>,
+    shunting: Option<VehicleTypeShunting>, // overrides parameters.shunting for this type
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct VehicleTypeShunting {
+    minimal_duration: Option<Integer>,
+    dead_head_trip_duration: Option<Integer>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TimeWindow {
+    start: DateTimeString,
+    end: DateTimeString,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct MinimumInService {
+    window: TimeWindow,
+    count: Integer,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -64,6 +111,17 @@ struct VehicleType {
 struct Location {
     id: IdType,
     day_limit: Option<Integer>,
+    allowed_vehicle_types: Option<Vec<IdType>>,
+    platform_idle_limit: Option<Integer>, // in seconds
+    is_siding: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LocationGroup {
+    id: IdType,
+    members: Vec<IdType>,
+    transfer_duration: Integer,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -72,13 +130,19 @@ struct Depot {
     id: IdType,
     location: IdType,
     capacity: Integer,
+    spawn_capacity: Option<Integer>, // capacity is used if not present
+    despawn_capacity: Option<Integer>, // capacity is used if not present
     allowed_types: Vec<TypeCapacities>,
+    provides_maintenance: Option<bool>,
+    spawn_cost: Option<Integer>,
 }
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct TypeCapacities {
     vehicle_type: IdType,
     capacity: Option<Integer>,
+    spawn_capacity: Option<Integer>,
+    despawn_capacity: Option<Integer>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -87,6 +151,8 @@ struct Route {
     id: IdType,
     vehicle_type: IdType,
     segments: Vec<RouteSegment>,
+    contract: Option<IdType>,
+    allowed_split_points: Option<Vec<IdType>>, // locations where the formation may change
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -99,17 +165,28 @@ struct RouteSegment {
     distance: Integer,
     duration: Integer,
     maximal_formation_count: Option<Integer>,
+    minimal_formation_count: Option<Integer>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 struct Departures {
     id: IdType,
     route: IdType,
     segments: Vec<DepartureSegment>,
+    headway_repetitions: Option<HeadwayRepetitions>,
+    #[serde(skip)]
+    trip_family: Option<(IdType, u32)>, // set by expand_headway_repetitions
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HeadwayRepetitions {
+    count: Integer,
+    interval: Integer,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 struct DepartureSegment {
     id: IdType,
@@ -125,8 +202,35 @@ struct MaintenanceSlots {
     id: IdType,
     location: IdType,
     start: DateTimeString,
-    end: DateTimeString,
+    end: Option<DateTimeString>, // until the end of the planning horizon if not present
     track_count: Integer,
+    service_duration: Option<Integer>, // in seconds, the whole window is one visit if not present
+    recurrence: Option<Recurrence>,
+}
+
+impl MaintenanceSlots {
+    /// only valid after resolve_open_maintenance_slot_ends
+    fn end(&self) -> DateTime {
+        DateTime::new(
+            self.end
+                .as_ref()
+                .expect("open end of maintenance slot not resolved"),
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Recurrence {
+    frequency: RecurrenceFrequency,
+    count: Integer,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -137,157 +241,1693 @@ struct DeadHeadTrips {
     distances: Vec<Vec<Integer>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct Parameters {
-    forbid_dead_head_trips: Option<bool>,
-    day_limit_threshold: Option<Integer>,
-    shunting: Shunting,
-    maintenance: Option<Maintenance>,
-    costs: Costs,
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DeadHeadTimeFactor {
+    origins: Vec<IdType>,
+    destinations: Vec<IdType>,
+    start: DateTimeString,
+    end: DateTimeString,
+    duration_factor: f64,
+    extra_cost: Option<Integer>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+enum Hint {
+    #[serde(rename_all = "camelCase")]
+    MergeTours {
+        vehicle: IdType,
+        into_vehicle: IdType,
+    },
+    #[serde(rename_all = "camelCase")]
+    MoveTrip { trip: IdType, to_vehicle: IdType },
+    #[serde(rename_all = "camelCase")]
+    AssignDepot { vehicle: IdType, depot: IdType },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Parameters {
+    forbid_dead_head_trips: Option<bool>,
+    maximal_dead_head_duration: Option<Integer>,
+    day_limit_threshold: Option<Integer>,
+    shunting: Shunting,
+    maintenance: Option<Maintenance>,
+    costs: Costs,
+    time_partitioning: Option<TimePartitioning>,
+    fixed_fleet: Option<HashMap<IdType, Integer>>,
+    strict_input: Option<bool>,
+    split_broken_departures: Option<bool>,
+    check_costs: Option<bool>,
+    formation_order_continuity: Option<bool>,
+    dead_head_pairing_tolerance: Option<Integer>,
+    collapse_location_groups: Option<bool>,
+    memory_budget: Option<Integer>,
+    planning_window: Option<PlanningWindow>,
+    adaptive_operator_weights: Option<AdaptiveOperatorWeights>,
+    output: Option<Output>,
+    webhook: Option<Webhook>,
+    depot_assignment: Option<DepotAssignment>,
+    milestones: Option<Vec<Integer>>, // in seconds since the start of the solve
+    out_of_service: Option<OutOfService>,
+    active_vehicle_types: Option<Vec<IdType>>, // None means all vehicle types are optimized
+    debug: Option<DebugParameters>,
+    time_limit: Option<Integer>,        // in seconds, 0 means unlimited
+    number_of_threads: Option<Integer>, // 0 means the global pool
+    solver: Option<SolverParameters>,
+    infeasibility_threshold: Option<f64>, // share of the passengers, no check if not present
+    infeasibility_policy: Option<InfeasibilityPolicy>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum InfeasibilityPolicy {
+    Warn,
+    Abort,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DebugParameters {
+    trace_file: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SolverParameters {
+    two_phase: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct OutOfService {
+    hard: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PlanningWindow {
+    start: DateTimeString,
+    end: DateTimeString,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Shunting {
+    minimal_duration: Integer,
+    dead_head_trip_duration: Integer,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Maintenance {
+    maximal_distance: Integer,
+    depot_credit: Option<Integer>, // in meter, full credit if not present
+    hard: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TimePartitioning {
+    window_length: Integer,
+    overlap: Integer,
+    polish_time_limit: Option<Integer>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AdaptiveOperatorWeights {
+    interval: Option<Integer>,
+    minimal_share: Option<f64>,
+    maximal_share: Option<f64>,
+    reaction_factor: Option<f64>,
+    initial_weights: Option<HashMap<IdType, f64>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DepotAssignment {
+    policy: Option<DepotAssignmentPolicy>,
+    distance_tolerance: Option<Integer>, // in meter
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum DepotAssignmentPolicy {
+    Nearest,
+    BalanceAware,
+    CostAware,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Webhook {
+    url: String,
+    events: Option<Vec<WebhookEvent>>, // all events if not present
+    min_interval_seconds: Option<Integer>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum WebhookEvent {
+    Improvement,
+    Finished,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Output {
+    level: Option<OutputLevel>,
+    crowding_thresholds: Option<CrowdingThresholds>,
+    contract_dead_head_allocation: Option<ContractDeadHeadAllocation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum ContractDeadHeadAllocation {
+    FollowingTrip,
+    Split,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CrowdingThresholds {
+    moderate: Option<f64>,
+    high: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum OutputLevel {
+    Full,
+    Summary,
+    ObjectiveOnly,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Costs {
+    staff: Integer,
+    service_trip: Integer,
+    maintenance: Option<Integer>,
+    dead_head_trip: Integer,
+    idle: Integer,
+    line_purity: Option<Integer>,
+    hitch_hike_bonus: Option<Integer>,
+    dead_head_trip_count: Option<Integer>,
+    duty_length_variance: Option<Integer>,
+    staff_time_factors: Option<Vec<StaffTimeFactor>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct StaffTimeFactor {
+    window: TimeOfDayWindow,
+    factor: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TimeOfDayWindow {
+    start: Integer, // in seconds since midnight
+    end: Integer,
+}
+
+/// Panics if the input data is invalid (see try_load_rolling_stock_problem_instance_from_json).
+pub fn load_rolling_stock_problem_instance_from_json(
+    input_data: serde_json::Value,
+) -> Arc<Network> {
+    try_load_rolling_stock_problem_instance_from_json(input_data).unwrap_or_else(|errors| {
+        panic!(
+            "Invalid input data: {}",
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        )
+    })
+}
+
+/// Invalid data is returned as errors. Data problems that the loader can repair (e.g., too long
+/// dead-head trips) are reported as warnings. With parameters.strictInput they are returned as
+/// errors instead.
+pub fn try_load_rolling_stock_problem_instance_from_json(
+    input_data: serde_json::Value,
+) -> Result<Arc<Network>, Vec<ValidationError>> {
+    let instance_hash = instance_hash(&input_data);
+    let mut json_input: JsonInput = serde_json::from_value(input_data).map_err(|error| {
+        let error = ValidationError {
+            code: "malformedInput",
+            message: format!("Input data is not in the correct format: {}.", error),
+        };
+        error!("{}", error);
+        vec![error]
+    })?;
+    // everything below looks the referenced elements up by id
+    let mut errors = check_references(&json_input);
+    errors.extend(check_repetitions(&json_input));
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            error!("{}", error);
+        }
+        return Err(errors);
+    }
+    let trip_family_intervals = expand_headway_repetitions(&mut json_input);
+    expand_maintenance_slot_recurrences(&mut json_input);
+    resolve_open_maintenance_slot_ends(&mut json_input);
+
+    // warnings that are reported to the user in the output
+    let mut warnings: Vec<Warning> = Vec::new();
+    // repaired data problems, these are errors in strict mode
+    let mut repairs: Vec<ValidationError> = Vec::new();
+
+    apply_planning_window(&mut json_input, &mut warnings, &mut repairs);
+    let split_broken_departures = json_input
+        .parameters
+        .split_broken_departures
+        .unwrap_or(false);
+    if split_broken_departures {
+        split_departures_at_broken_links(&mut json_input, &mut warnings);
+    }
+
+    let (vehicle_types, vehicle_type_lookup) = create_vehicle_types(&json_input);
+    let (locations, location_lookup) =
+        create_locations(&json_input, &vehicle_type_lookup, &mut repairs);
+
+    let mut errors = check_vehicle_types_allowed_on_routes(
+        &json_input,
+        &locations,
+        &location_lookup,
+        &vehicle_type_lookup,
+    );
+    errors.extend(check_route_chains(
+        &json_input,
+        split_broken_departures,
+        &mut warnings,
+    ));
+    errors.extend(check_time_spans(&json_input));
+    errors.extend(check_departure_chains(&json_input));
+    errors.extend(check_split_points(&json_input));
+    errors.extend(check_location_groups(&json_input));
+    errors.extend(check_value_ranges(&json_input));
+    errors.extend(check_parameters(&json_input.parameters));
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            error!("{}", error);
+        }
+        return Err(errors);
+    }
+
+    let config = create_config(&json_input, &vehicle_type_lookup, &mut warnings);
+    let service_trips = create_service_trips(
+        &json_input,
+        &locations,
+        &vehicle_types,
+        &location_lookup,
+        &vehicle_type_lookup,
+        &mut repairs,
+    );
+
+    let errors =
+        check_out_of_service_windows(&service_trips, &vehicle_types, &config, &mut warnings);
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            error!("{}", error);
+        }
+        return Err(errors);
+    }
+
+    if json_input.parameters.strict_input.unwrap_or(false) && !repairs.is_empty() {
+        for error in repairs.iter() {
+            error!("{}", error);
+        }
+        return Err(repairs);
+    }
+    for repair in repairs {
+        let warning = Warning::new(repair.code, Severity::Warning, repair.message);
+        warn!("{}", warning);
+        warnings.push(warning);
+    }
+
+    Ok(Arc::new(create_network(
+        &json_input,
+        locations,
+        vehicle_types,
+        config,
+        service_trips,
+        location_lookup,
+        vehicle_type_lookup,
+        trip_family_intervals,
+        instance_hash,
+        warnings,
+    )))
+}
+
+/// Service trips overlapping an out-of-service window of their vehicle type cannot be served
+/// without violating the window: with parameters.outOfService.hard they are errors, otherwise
+/// they are reported as warnings (and as violations in the output).
+fn check_out_of_service_windows(
+    service_trips: &HashMap<VehicleTypeIdx, Vec<ModelServiceTrip>>,
+    vehicle_types: &VehicleTypes,
+    config: &Config,
+    warnings: &mut Vec<Warning>,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for vehicle_type in vehicle_types.iter() {
+        for &window in vehicle_types.out_of_service_windows_of(vehicle_type) {
+            let conflicting_trips: Vec<&ModelServiceTrip> = service_trips[&vehicle_type]
+                .iter()
+                .filter(|service_trip| service_trip.overlaps(window))
+                .collect();
+            let Some(first_trip) = conflicting_trips.first() else {
+                continue;
+            };
+            let message = format!(
+                "Vehicle type {} is out of service from {} to {}, but {} of its service trips \
+                overlap this window (e.g., {}).",
+                vehicle_types.get(vehicle_type).unwrap().id(),
+                window.0.as_iso(),
+                window.1.as_iso(),
+                conflicting_trips.len(),
+                first_trip.id()
+            );
+            if config.out_of_service_hard {
+                errors.push(ValidationError {
+                    code: "outOfServiceConflict",
+                    message,
+                });
+            } else {
+                let warning = Warning::new("outOfServiceConflict", Severity::Warning, message)
+                    .with_context(
+                        conflicting_trips
+                            .iter()
+                            .map(|service_trip| service_trip.id().to_string())
+                            .collect(),
+                    );
+                warn!("{}", warning);
+                warnings.push(warning);
+            }
+        }
+    }
+    errors
+}
+
+/// A problem in the input data. Invalid data (e.g., a route through a location that does not
+/// admit its vehicle type) always aborts the loading, data that can be repaired only with
+/// parameters.strictInput.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+fn create_locations(
+    json_input: &JsonInput,
+    vehicle_type_lookup: &HashMap<IdType, VehicleTypeIdx>,
+    repairs: &mut Vec<ValidationError>,
+) -> (Locations, HashMap<IdType, LocationIdx>) {
+    let planning_days = determine_planning_days(json_input);
+    let mut stations: HashMap<LocationIdx, (String, Option<VehicleCount>)> = HashMap::new(); // PpRF: use vec instead
+    let mut dead_head_trips: HashMap<LocationIdx, HashMap<LocationIdx, DeadHeadTrip>> =
+        HashMap::new();
+
+    let mut location_lookup: HashMap<IdType, LocationIdx> = HashMap::new();
+
+    // add stations
+    for (idx, location_json) in json_input.locations.iter().enumerate() {
+        let location_idx = LocationIdx::from(idx as Idx);
+        stations.insert(
+            location_idx,
+            (
+                location_json.id.clone(),
+                location_json.day_limit.map(|x| x as VehicleCount),
+            ),
+        );
+        location_lookup.insert(location_json.id.clone(), location_idx);
+    }
+
+    // add vehicle type restrictions
+    let allowed_vehicle_types: HashMap<LocationIdx, Vec<VehicleTypeIdx>> = json_input
+        .locations
+        .iter()
+        .filter_map(|location_json| {
+            location_json.allowed_vehicle_types.as_ref().map(|types| {
+                // unknown vehicle types are reported by check_references
+                let allowed = types
+                    .iter()
+                    .map(|vehicle_type_id| vehicle_type_lookup[vehicle_type_id])
+                    .collect();
+                (location_lookup[&location_json.id], allowed)
+            })
+        })
+        .collect();
+
+    // add platform idle limits and sidings
+    let platform_idle_limits: HashMap<LocationIdx, Duration> = json_input
+        .locations
+        .iter()
+        .filter_map(|location_json| {
+            location_json.platform_idle_limit.map(|limit| {
+                (
+                    location_lookup[&location_json.id],
+                    Duration::from_seconds(limit),
+                )
+            })
+        })
+        .collect();
+    let sidings: Vec<LocationIdx> = json_input
+        .locations
+        .iter()
+        .filter(|location_json| location_json.is_siding.unwrap_or(false))
+        .map(|location_json| location_lookup[&location_json.id])
+        .collect();
+
+    // add dead head trips
+    let mut duration_warning_printed = false;
+    let mut distance_warning_printed = false;
+    let mut locations_with_non_zero_diagonal: Vec<&IdType> = Vec::new();
+    for (i, origin_json) in json_input.dead_head_trips.indices.iter().enumerate() {
+        let origin_station = location_lookup[origin_json];
+        let mut destination_map: HashMap<LocationIdx, DeadHeadTrip> = HashMap::new();
+        for (j, destination_json) in json_input.dead_head_trips.indices.iter().enumerate() {
+            if i == j {
+                // consecutive activities at the same location are connected without dead-head
+                // trip (only the minimal shunting applies)
+                if json_input.dead_head_trips.durations[i][j] != 0
+                    || json_input.dead_head_trips.distances[i][j] != 0
+                {
+                    locations_with_non_zero_diagonal.push(origin_json);
+                }
+                destination_map.insert(
+                    origin_station,
+                    DeadHeadTrip::new(Distance::ZERO, Duration::ZERO),
+                );
+                continue;
+            }
+            let mut duration = Duration::from_seconds(json_input.dead_head_trips.durations[i][j]);
+            if duration > planning_days {
+                if !duration_warning_printed {
+                    repairs.push(ValidationError {
+                        code: "deadHeadTripDurationTooLong",
+                        message: format!(
+                            "Some dead head trip durations exceed planning duration of {} day(s). \
+                            Taking planning duration instead.",
+                            planning_days.in_min().unwrap() / 1440
+                        ),
+                    });
+                    duration_warning_printed = true;
+                }
+                duration = planning_days;
+            }
+            let mut distance = Distance::from_meter(json_input.dead_head_trips.distances[i][j]);
+            if distance > Distance::from_meter(MAX_DISTANCE) {
+                if !distance_warning_printed {
+                    repairs.push(ValidationError {
+                        code: "deadHeadTripDistanceTooLong",
+                        message: format!(
+                            "Some dead head trip distances exceed {}m. \
+                            This might be a mistake. Distance reduced to {}m.",
+                            MAX_DISTANCE, MAX_DISTANCE
+                        ),
+                    });
+                    distance_warning_printed = true;
+                }
+                distance = Distance::from_meter(MAX_DISTANCE);
+            }
+            destination_map.insert(
+                location_lookup[destination_json],
+                DeadHeadTrip::new(distance, duration),
+            );
+        }
+        dead_head_trips.insert(origin_station, destination_map);
+    }
+    if !locations_with_non_zero_diagonal.is_empty() {
+        repairs.push(ValidationError {
+            code: "deadHeadTripToSameLocation",
+            message: format!(
+                "Dead head trips from a location to itself must have zero duration and distance, \
+                but they have not for {}. Taking zero instead.",
+                locations_with_non_zero_diagonal
+                    .iter()
+                    .map(|id| id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        });
+    }
+
+    let dead_head_time_factors = json_input
+        .dead_head_time_factors
+        .as_ref()
+        .map(|factors| {
+            factors
+                .iter()
+                .map(|factor| create_dead_head_time_factor(factor, &location_lookup))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // unknown members are reported by check_location_groups
+    let location_groups = json_input
+        .location_groups
+        .iter()
+        .flatten()
+        .map(|location_group| {
+            ModelLocationGroup::new(
+                location_group.id.clone(),
+                location_group
+                    .members
+                    .iter()
+                    .filter_map(|member| location_lookup.get(member).copied())
+                    .collect(),
+                Duration::from_seconds(location_group.transfer_duration),
+            )
+        })
+        .collect();
+
+    (
+        Locations::new(
+            stations,
+            dead_head_trips,
+            allowed_vehicle_types,
+            dead_head_time_factors,
+            location_groups,
+            json_input
+                .parameters
+                .collapse_location_groups
+                .unwrap_or(false),
+            PlatformIdleLimits::new(platform_idle_limits, sidings),
+        ),
+        location_lookup,
+    )
+}
+
+fn create_dead_head_time_factor(
+    factor: &DeadHeadTimeFactor,
+    location_lookup: &HashMap<IdType, LocationIdx>,
+) -> ModelDeadHeadTimeFactor {
+    // unknown locations are reported by check_references
+    let lookup = |location_ids: &Vec<IdType>| -> Vec<LocationIdx> {
+        location_ids
+            .iter()
+            .map(|location_id| location_lookup[location_id])
+            .collect()
+    };
+    ModelDeadHeadTimeFactor::new(
+        lookup(&factor.origins),
+        lookup(&factor.destinations),
+        DateTime::new(&factor.start),
+        DateTime::new(&factor.end),
+        factor.duration_factor,
+        factor.extra_cost.unwrap_or(0),
+    )
+}
+
+/// Ids must be unique (per kind, route segments per route) and every reference must point to an
+/// existing element. The dead-head matrices must have a row and a column for each index.
+fn check_references(json_input: &JsonInput) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let vehicle_type_ids = unique_ids(
+        "Vehicle type",
+        json_input
+            .vehicle_types
+            .iter()
+            .map(|vehicle_type| &vehicle_type.id),
+        &mut errors,
+    );
+    let location_ids = unique_ids(
+        "Location",
+        json_input.locations.iter().map(|location| &location.id),
+        &mut errors,
+    );
+    unique_ids(
+        "Depot",
+        json_input.depots.iter().flatten().map(|depot| &depot.id),
+        &mut errors,
+    );
+    unique_ids(
+        "Route",
+        json_input.routes.iter().map(|route| &route.id),
+        &mut errors,
+    );
+    let segment_ids_of_route: HashMap<&IdType, HashSet<&IdType>> = json_input
+        .routes
+        .iter()
+        .map(|route| {
+            let segment_ids = unique_ids(
+                &format!("Route {}: segment", route.id),
+                route.segments.iter().map(|segment| &segment.id),
+                &mut errors,
+            );
+            (&route.id, segment_ids)
+        })
+        .collect();
+    unique_ids(
+        "Departure",
+        json_input.departures.iter().map(|departure| &departure.id),
+        &mut errors,
+    );
+    unique_ids(
+        "Departure segment",
+        json_input
+            .departures
+            .iter()
+            .flat_map(|departure| departure.segments.iter().map(|segment| &segment.id)),
+        &mut errors,
+    );
+    unique_ids(
+        "Maintenance slot",
+        json_input
+            .maintenance_slots
+            .iter()
+            .flatten()
+            .map(|maintenance_slot| &maintenance_slot.id),
+        &mut errors,
+    );
+
+    let mut check = |exists: bool, referrer: String, kind: &str, id: &IdType| {
+        if !exists {
+            errors.push(ValidationError {
+                code: "unknownReference",
+                message: format!("{} references unknown {} {}.", referrer, kind, id),
+            });
+        }
+    };
+    for location in json_input.locations.iter() {
+        for vehicle_type in location.allowed_vehicle_types.iter().flatten() {
+            check(
+                vehicle_type_ids.contains(vehicle_type),
+                format!("Location {}", location.id),
+                "vehicle type",
+                vehicle_type,
+            );
+        }
+    }
+    for depot in json_input.depots.iter().flatten() {
+        check(
+            location_ids.contains(&depot.location),
+            format!("Depot {}", depot.id),
+            "location",
+            &depot.location,
+        );
+        for allowed_type in depot.allowed_types.iter() {
+            check(
+                vehicle_type_ids.contains(&allowed_type.vehicle_type),
+                format!("Depot {}", depot.id),
+                "vehicle type",
+                &allowed_type.vehicle_type,
+            );
+        }
+    }
+    for route in json_input.routes.iter() {
+        check(
+            vehicle_type_ids.contains(&route.vehicle_type),
+            format!("Route {}", route.id),
+            "vehicle type",
+            &route.vehicle_type,
+        );
+        for segment in route.segments.iter() {
+            for location in [&segment.origin, &segment.destination] {
+                check(
+                    location_ids.contains(location),
+                    format!("Route segment {} of route {}", segment.id, route.id),
+                    "location",
+                    location,
+                );
+            }
+        }
+    }
+    for departure in json_input.departures.iter() {
+        let Some(segment_ids) = segment_ids_of_route.get(&departure.route) else {
+            check(
+                false,
+                format!("Departure {}", departure.id),
+                "route",
+                &departure.route,
+            );
+            continue;
+        };
+        for segment in departure.segments.iter() {
+            check(
+                segment_ids.contains(&segment.route_segment),
+                format!("Segment {} of departure {}", segment.id, departure.id),
+                "route segment",
+                &segment.route_segment,
+            );
+        }
+    }
+    for maintenance_slot in json_input.maintenance_slots.iter().flatten() {
+        check(
+            location_ids.contains(&maintenance_slot.location),
+            format!("Maintenance slot {}", maintenance_slot.id),
+            "location",
+            &maintenance_slot.location,
+        );
+    }
+    for location in json_input.dead_head_trips.indices.iter() {
+        check(
+            location_ids.contains(location),
+            "deadHeadTrips".to_string(),
+            "location",
+            location,
+        );
+    }
+    for factor in json_input.dead_head_time_factors.iter().flatten() {
+        for location in factor.origins.iter().chain(factor.destinations.iter()) {
+            check(
+                location_ids.contains(location),
+                "A dead-head time factor".to_string(),
+                "location",
+                location,
+            );
+        }
+    }
+    for vehicle_type in json_input
+        .parameters
+        .fixed_fleet
+        .iter()
+        .flat_map(|fleet| fleet.keys())
+    {
+        check(
+            vehicle_type_ids.contains(vehicle_type),
+            "parameters.fixedFleet".to_string(),
+            "vehicle type",
+            vehicle_type,
+        );
+    }
+    for vehicle_type in json_input.parameters.active_vehicle_types.iter().flatten() {
+        check(
+            vehicle_type_ids.contains(vehicle_type),
+            "parameters.activeVehicleTypes".to_string(),
+            "vehicle type",
+            vehicle_type,
+        );
+    }
+
+    let dead_head_trips = &json_input.dead_head_trips;
+    let size = dead_head_trips.indices.len();
+    for (name, matrix) in [
+        ("durations", &dead_head_trips.durations),
+        ("distances", &dead_head_trips.distances),
+    ] {
+        if matrix.len() != size || matrix.iter().any(|row| row.len() != size) {
+            errors.push(ValidationError {
+                code: "deadHeadMatrixNotSquare",
+                message: format!(
+                    "deadHeadTrips.{} must be a {}x{} matrix (a row and a column for each of \
+                    the {} indices), but it has rows of length {:?}.",
+                    name,
+                    size,
+                    size,
+                    size,
+                    matrix.iter().map(|row| row.len()).collect::<Vec<_>>()
+                ),
+            });
+        }
+    }
+    errors
+}
+
+/// Headway repetitions of departures and recurrences of maintenance slots are expanded before
+/// anything else, so they are checked together with the references.
+fn check_repetitions(json_input: &JsonInput) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for departure in json_input.departures.iter() {
+        if let Some(headway_repetitions) = &departure.headway_repetitions {
+            if headway_repetitions.count == 0 || headway_repetitions.interval == 0 {
+                errors.push(ValidationError {
+                    code: "invalidRepetition",
+                    message: format!(
+                        "headwayRepetitions of departure {} need a positive count and interval.",
+                        departure.id
+                    ),
+                });
+            }
+        }
+    }
+    for maintenance_slot in json_input.maintenance_slots.iter().flatten() {
+        if let Some(recurrence) = &maintenance_slot.recurrence {
+            if recurrence.count == 0 || maintenance_slot.end.is_none() {
+                errors.push(ValidationError {
+                    code: "invalidRepetition",
+                    message: format!(
+                        "Recurring maintenance slot {} needs a positive count and an end.",
+                        maintenance_slot.id
+                    ),
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// The set of the ids, each id occurring more than once is reported (once) as an error.
+fn unique_ids<'a>(
+    kind: &str,
+    ids: impl Iterator<Item = &'a IdType>,
+    errors: &mut Vec<ValidationError>,
+) -> HashSet<&'a IdType> {
+    let mut unique_ids = HashSet::new();
+    let mut duplicate_ids = HashSet::new();
+    for id in ids {
+        if !unique_ids.insert(id) && duplicate_ids.insert(id) {
+            errors.push(ValidationError {
+                code: "duplicateId",
+                message: format!("{} {} is defined more than once.", kind, id),
+            });
+        }
+    }
+    unique_ids
+}
+
+/// every route segment must start and end at locations that admit the vehicle type of the route.
+fn check_vehicle_types_allowed_on_routes(
+    json_input: &JsonInput,
+    locations: &Locations,
+    location_lookup: &HashMap<IdType, LocationIdx>,
+    vehicle_type_lookup: &HashMap<IdType, VehicleTypeIdx>,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for route in json_input.routes.iter() {
+        let vehicle_type = vehicle_type_lookup[&route.vehicle_type];
+        for segment in route.segments.iter() {
+            for location_id in [&segment.origin, &segment.destination] {
+                let location = locations.get(location_lookup[location_id]).unwrap();
+                if !locations.is_vehicle_type_allowed_at(location, vehicle_type) {
+                    errors.push(ValidationError {
+                        code: "vehicleTypeNotAllowedAtLocation",
+                        message: format!(
+                            "Route segment {} of route {} uses location {} which does not admit \
+                            vehicle type {}.",
+                            segment.id, route.id, location_id, route.vehicle_type
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// Location groups must have distinct ids and consist of existing locations, each location being
+/// a member of at most one group.
+fn check_location_groups(json_input: &JsonInput) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let mut group_of_member: HashMap<&IdType, &IdType> = HashMap::new();
+    let mut group_ids: Vec<&IdType> = Vec::new();
+    for location_group in json_input.location_groups.iter().flatten() {
+        if group_ids.contains(&&location_group.id) {
+            errors.push(ValidationError {
+                code: "duplicateLocationGroup",
+                message: format!("Location group {} is defined twice.", location_group.id),
+            });
+        }
+        group_ids.push(&location_group.id);
+        for member in location_group.members.iter() {
+            if !json_input
+                .locations
+                .iter()
+                .any(|location| &location.id == member)
+            {
+                errors.push(ValidationError {
+                    code: "unknownLocationGroupMember",
+                    message: format!(
+                        "Location {} of location group {} does not exist.",
+                        member, location_group.id
+                    ),
+                });
+            }
+            if let Some(other_group) = group_of_member.insert(member, &location_group.id) {
+                errors.push(ValidationError {
+                    code: "locationInSeveralGroups",
+                    message: format!(
+                        "Location {} is a member of location groups {} and {}.",
+                        member, other_group, location_group.id
+                    ),
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// Capacities, passengers and distances must not exceed the maxima of base_types. Otherwise, they
+/// would be truncated when converted or the sums over formations and service trips could
+/// overflow.
+fn check_value_ranges(json_input: &JsonInput) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for factor in json_input.dead_head_time_factors.iter().flatten() {
+        if factor.duration_factor <= 0.0 {
+            errors.push(ValidationError {
+                code: "deadHeadTimeFactorInvalid",
+                message: "The duration factor of dead-head time factors must be positive."
+                    .to_string(),
+            });
+        }
+    }
+    for vehicle_type in json_input.vehicle_types.iter() {
+        if vehicle_type.capacity > MAX_VEHICLE_CAPACITY as Integer
+            || vehicle_type.seats > MAX_VEHICLE_CAPACITY as Integer
+        {
+            errors.push(ValidationError {
+                code: "vehicleCapacityTooLarge",
+                message: format!(
+                    "Capacity and seats of vehicle type {} must not exceed {}.",
+                    vehicle_type.id, MAX_VEHICLE_CAPACITY
+                ),
+            });
+        }
+    }
+    for route in json_input.routes.iter() {
+        for segment in route.segments.iter() {
+            if segment.distance > MAX_SEGMENT_DISTANCE {
+                errors.push(ValidationError {
+                    code: "routeSegmentDistanceTooLong",
+                    message: format!(
+                        "Distance of route segment {} of route {} must not exceed {}m.",
+                        segment.id, route.id, MAX_SEGMENT_DISTANCE
+                    ),
+                });
+            }
+        }
+    }
+    let mut total_passengers: Option<Integer> = Some(0);
+    for departure in json_input.departures.iter() {
+        for segment in departure.segments.iter() {
+            if segment.passengers > MAX_PASSENGERS as Integer
+                || segment.seated > MAX_PASSENGERS as Integer
+            {
+                errors.push(ValidationError {
+                    code: "passengersTooLarge",
+                    message: format!(
+                        "Passengers and seated of departure segment {} must not exceed {}.",
+                        segment.id, MAX_PASSENGERS
+                    ),
+                });
+            }
+            total_passengers = total_passengers
+                .and_then(|total| total.checked_add(segment.passengers))
+                .filter(|&total| total <= MAX_TOTAL_PASSENGERS as Integer);
+        }
+    }
+    if total_passengers.is_none() {
+        errors.push(ValidationError {
+            code: "totalPassengersTooLarge",
+            message: format!(
+                "The passengers of all departure segments must not exceed {} in total.",
+                MAX_TOTAL_PASSENGERS
+            ),
+        });
+    }
+    errors
+}
+
+/// Parameters that the solver cannot work with (e.g., time windows that do not advance) are
+/// invalid input.
+fn check_parameters(parameters: &Parameters) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let mut check = |valid: bool, message: &str| {
+        if !valid {
+            errors.push(ValidationError {
+                code: "invalidParameter",
+                message: message.to_string(),
+            });
+        }
+    };
+    if let Some(time_partitioning) = &parameters.time_partitioning {
+        check(
+            time_partitioning.overlap < time_partitioning.window_length,
+            "The overlap of the time partitioning must be smaller than the window length.",
+        );
+    }
+    check(
+        parameters
+            .milestones
+            .iter()
+            .flatten()
+            .all(|&milestone| milestone > 0),
+        "Milestones must be positive.",
+    );
+    if let Some(webhook) = &parameters.webhook {
+        check(
+            webhook.url.starts_with("http://") || webhook.url.starts_with("https://"),
+            "The url of the webhook must start with http:// or https://.",
+        );
+    }
+    if let Some(adaptive_operator_weights) = &parameters.adaptive_operator_weights {
+        let config = create_adaptive_operator_weights_config(adaptive_operator_weights);
+        check(
+            config.interval > 0,
+            "The interval of the adaptive operator weights must be positive.",
+        );
+        check(
+            0.0 < config.minimal_share
+                && config.minimal_share <= config.maximal_share
+                && config.maximal_share <= 1.0,
+            "The shares of the adaptive operator weights must satisfy 0 < minimalShare <= \
+            maximalShare <= 1.",
+        );
+        check(
+            (0.0..=1.0).contains(&config.reaction_factor),
+            "The reaction factor of the adaptive operator weights must be in [0, 1].",
+        );
+    }
+    let crowding_thresholds = create_crowding_thresholds(
+        parameters
+            .output
+            .as_ref()
+            .and_then(|output| output.crowding_thresholds.as_ref()),
+    );
+    check(
+        0.0 <= crowding_thresholds.moderate
+            && crowding_thresholds.moderate <= crowding_thresholds.high,
+        "The crowding thresholds must satisfy 0 <= moderate <= high.",
+    );
+    for factor in parameters.costs.staff_time_factors.iter().flatten() {
+        check(
+            factor.window.start < 86400 && factor.window.end <= 86400,
+            "The window of staff time factors must be given in seconds since midnight.",
+        );
+        check(
+            factor.window.start != factor.window.end,
+            "The window of staff time factors must not be empty.",
+        );
+        check(
+            factor.factor >= 1.0,
+            "The factor of staff time factors must be at least 1.",
+        );
+    }
+    if let Some(threshold) = parameters.infeasibility_threshold {
+        check(
+            (0.0..=1.0).contains(&threshold),
+            "The infeasibility threshold must be between 0 and 1.",
+        );
+    }
+    errors
+}
+
+/// Every maintenance slot must end after it starts (and its service duration must fit into it).
+/// Otherwise, durations computed from these times would be negative. The same holds for the
+/// planning window, the out-of-service windows and the minimum-in-service windows.
+fn check_time_spans(json_input: &JsonInput) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    if let Some(planning_window) = &json_input.parameters.planning_window {
+        let start = DateTime::new(&planning_window.start);
+        let end = DateTime::new(&planning_window.end);
+        if end <= start {
+            errors.push(ValidationError {
+                code: "planningWindowInvalid",
+                message: format!(
+                    "Planning window ends before it starts ({} - {}).",
+                    planning_window.start, planning_window.end
+                ),
+            });
+        }
+    }
+    for vehicle_type in json_input.vehicle_types.iter() {
+        for window in vehicle_type.out_of_service_windows.iter().flatten() {
+            if DateTime::new(&window.end) <= DateTime::new(&window.start) {
+                errors.push(ValidationError {
+                    code: "outOfServiceWindowInvalid",
+                    message: format!(
+                        "Out-of-service window of vehicle type {} ends before it starts \
+                        ({} - {}).",
+                        vehicle_type.id, window.start, window.end
+                    ),
+                });
+            }
+        }
+        for minimum_in_service in vehicle_type.minimum_in_service.iter().flatten() {
+            let window = &minimum_in_service.window;
+            if DateTime::new(&window.end) <= DateTime::new(&window.start) {
+                errors.push(ValidationError {
+                    code: "minimumInServiceWindowInvalid",
+                    message: format!(
+                        "Minimum-in-service window of vehicle type {} ends before it starts \
+                        ({} - {}).",
+                        vehicle_type.id, window.start, window.end
+                    ),
+                });
+            }
+        }
+    }
+    for maintenance_slot in json_input.maintenance_slots.iter().flatten() {
+        let start = DateTime::new(&maintenance_slot.start);
+        let end = maintenance_slot.end();
+        match checked_duration_between(start, end) {
+            Err(error) => errors.push(ValidationError {
+                code: "maintenanceSlotEndsBeforeStart",
+                message: format!(
+                    "Maintenance slot {} ends before it starts: {}.",
+                    maintenance_slot.id, error
+                ),
+            }),
+            Ok(window) => {
+                if let Some(service_duration) = maintenance_slot.service_duration {
+                    if service_duration == 0 || Duration::from_seconds(service_duration) > window {
+                        errors.push(ValidationError {
+                            code: "maintenanceServiceDurationInvalid",
+                            message: format!(
+                                "Service duration of maintenance slot {} must be positive and \
+                                must not exceed the slot.",
+                                maintenance_slot.id
+                            ),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// The segments of a route (ordered by their order) must form a chain: no two segments share
+/// the same order (overlap) and each segment starts where the previous one ends (gap). With
+/// parameters.splitBrokenDepartures, gaps are only warnings as the departures running across them
+/// are split (see split_departures_at_broken_links).
+fn check_route_chains(
+    json_input: &JsonInput,
+    split_broken_departures: bool,
+    warnings: &mut Vec<Warning>,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for route in json_input.routes.iter() {
+        let mut segments: Vec<&RouteSegment> = route.segments.iter().collect();
+        segments.sort_by_key(|segment| segment.order);
+        for window in segments.windows(2) {
+            let (previous, next) = (window[0], window[1]);
+            if previous.order == next.order {
+                errors.push(ValidationError {
+                    code: "routeSegmentOverlap",
+                    message: format!(
+                        "Route {}: segments {} and {} have the same order {}.",
+                        route.id, previous.id, next.id, next.order
+                    ),
+                });
+            } else if previous.destination != next.origin {
+                let message = format!(
+                    "Route {}: segment {} ends at {}, but the next segment {} starts at {} \
+                    (expected {}).",
+                    route.id,
+                    previous.id,
+                    previous.destination,
+                    next.id,
+                    next.origin,
+                    previous.destination
+                );
+                if split_broken_departures {
+                    let warning = Warning::new("routeSegmentGap", Severity::Info, message)
+                        .with_context(vec![route.id.clone()]);
+                    warn!("{}", warning);
+                    warnings.push(warning);
+                } else {
+                    errors.push(ValidationError {
+                        code: "routeSegmentGap",
+                        message,
+                    });
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// Consecutive segments of a departure (ordered by the order of their route segments) must be
+/// servable by one vehicle without a dead-head trip: the next segment starts at the destination
+/// of the previous one and does not depart before the previous one arrives.
+fn check_departure_chains(json_input: &JsonInput) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for departure in json_input.departures.iter() {
+        let Some(segments) = ordered_departure_segments(json_input, departure) else {
+            continue;
+        };
+        for window in segments.windows(2) {
+            errors.extend(broken_link(departure, window[0], window[1]));
+        }
+    }
+    errors
+}
+
+/// Each allowed split point of a route must be a location where two consecutive segments of the
+/// route meet, i.e., the destination of a segment that is not the last one.
+fn check_split_points(json_input: &JsonInput) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for route in json_input.routes.iter() {
+        let Some(split_points) = &route.allowed_split_points else {
+            continue;
+        };
+        let last_order = route.segments.iter().map(|segment| segment.order).max();
+        for split_point in split_points.iter() {
+            if !route.segments.iter().any(|segment| {
+                &segment.destination == split_point && Some(segment.order) != last_order
+            }) {
+                errors.push(ValidationError {
+                    code: "invalidSplitPoint",
+                    message: format!(
+                        "Route {}: split point {} is not a location between two consecutive \
+                        segments of the route.",
+                        route.id, split_point
+                    ),
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// The pairs (before, after) of consecutive departure segments that meet at an allowed split point
+/// of their route. Different formations may serve the two segments (see Network::split_successor).
+fn create_split_points(json_input: &JsonInput) -> Vec<(IdType, IdType)> {
+    let mut split_points = Vec::new();
+    for departure in json_input.departures.iter() {
+        let Some(allowed_split_points) = json_input
+            .routes
+            .iter()
+            .find(|route| route.id == departure.route)
+            .and_then(|route| route.allowed_split_points.as_ref())
+        else {
+            continue;
+        };
+        let Some(segments) = ordered_departure_segments(json_input, departure) else {
+            continue;
+        };
+        for window in segments.windows(2) {
+            let ((previous, before), (next, after)) = (window[0], window[1]);
+            if previous.destination == next.origin
+                && allowed_split_points.contains(&previous.destination)
+            {
+                split_points.push((before.id.clone(), after.id.clone()));
+            }
+        }
+    }
+    split_points
+}
+
+/// With parameters.splitBrokenDepartures, each departure is split at every broken link (see
+/// check_departure_chains) into independent departures. The first part keeps the id, the k-th part
+/// (k >= 1) gets the suffix #k. The segments keep their ids.
+fn split_departures_at_broken_links(json_input: &mut JsonInput, warnings: &mut Vec<Warning>) {
+    let mut departures = Vec::with_capacity(json_input.departures.len());
+    for departure in json_input.departures.iter() {
+        let Some(segments) = ordered_departure_segments(json_input, departure) else {
+            departures.push(departure.clone());
+            continue;
+        };
+        let mut parts: Vec<Vec<DepartureSegment>> = vec![Vec::new()];
+        for (idx, &(_, departure_segment)) in segments.iter().enumerate() {
+            if idx > 0 {
+                if let Some(error) = broken_link(departure, segments[idx - 1], segments[idx]) {
+                    let warning = Warning::new(
+                        error.code,
+                        Severity::Info,
+                        format!("{} The departure is split.", error.message),
+                    )
+                    .with_context(vec![departure.id.clone()]);
+                    warn!("{}", warning);
+                    warnings.push(warning);
+                    parts.push(Vec::new());
+                }
+            }
+            parts.last_mut().unwrap().push(departure_segment.clone());
+        }
+        // segments of unknown route segments are left to the validation
+        parts[0].extend(
+            departure
+                .segments
+                .iter()
+                .filter(|departure_segment| {
+                    !segments
+                        .iter()
+                        .any(|(_, segment)| segment.id == departure_segment.id)
+                })
+                .cloned(),
+        );
+        for (idx, part) in parts.into_iter().enumerate() {
+            let mut split_departure = departure.clone();
+            if idx > 0 {
+                split_departure.id = format!("{}#{}", departure.id, idx);
+            }
+            split_departure.segments = part;
+            departures.push(split_departure);
+        }
+    }
+    json_input.departures = departures;
+}
+
+/// The segments of the departure with their route segments, ordered by the order of the route
+/// segments. None if the route of the departure does not exist (left to the validation), segments
+/// with unknown route segments are omitted.
+fn ordered_departure_segments<'a>(
+    json_input: &'a JsonInput,
+    departure: &'a Departures,
+) -> Option<Vec<(&'a RouteSegment, &'a DepartureSegment)>> {
+    let route = json_input
+        .routes
+        .iter()
+        .find(|route| route.id == departure.route)?;
+    let mut segments: Vec<(&RouteSegment, &DepartureSegment)> = departure
+        .segments
+        .iter()
+        .filter_map(|departure_segment| {
+            route
+                .segments
+                .iter()
+                .find(|segment| segment.id == departure_segment.route_segment)
+                .map(|segment| (segment, departure_segment))
+        })
+        .collect();
+    segments.sort_by_key(|(segment, _)| segment.order);
+    Some(segments)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct Shunting {
-    minimal_duration: Integer,
-    dead_head_trip_duration: Integer,
+/// The error if one vehicle cannot serve the next segment of the departure right after the
+/// previous one.
+fn broken_link(
+    departure: &Departures,
+    (previous_segment, previous): (&RouteSegment, &DepartureSegment),
+    (next_segment, next): (&RouteSegment, &DepartureSegment),
+) -> Option<ValidationError> {
+    if previous_segment.destination != next_segment.origin {
+        return Some(ValidationError {
+            code: "departureSegmentGap",
+            message: format!(
+                "Departure {} on route {}: segment {} ends at {}, but the next segment {} starts \
+                at {} (expected {}).",
+                departure.id,
+                departure.route,
+                previous.id,
+                previous_segment.destination,
+                next.id,
+                next_segment.origin,
+                previous_segment.destination
+            ),
+        });
+    }
+    let arrival =
+        DateTime::new(&previous.departure) + Duration::from_seconds(previous_segment.duration);
+    checked_duration_between(arrival, DateTime::new(&next.departure))
+        .err()
+        .map(|error| ValidationError {
+            code: "departureBeforeArrival",
+            message: format!(
+                "Segment {} of departure {} departs before the previous segment {} arrives: {} \
+                (expected a departure at {} or later, actual {}).",
+                next.id,
+                departure.id,
+                previous.id,
+                error,
+                arrival.as_iso(),
+                next.departure
+            ),
+        })
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct Maintenance {
-    maximal_distance: Integer,
+/// Each departure with headwayRepetitions is replaced by count repetitions, the k-th one shifted by
+/// k * interval (in seconds). The first repetition keeps the ids, the k-th (k >= 1) gets the suffix
+/// _k on the ids of the departure and of its segments. Returns the interval of each trip family
+/// (keyed by the id of the repeated departure).
+fn expand_headway_repetitions(json_input: &mut JsonInput) -> HashMap<IdType, Duration> {
+    let mut trip_family_intervals = HashMap::new();
+    let mut departures = Vec::with_capacity(json_input.departures.len());
+    for departure in json_input.departures.drain(..) {
+        let headway_repetitions = match departure.headway_repetitions.clone() {
+            Some(headway_repetitions) => headway_repetitions,
+            None => {
+                departures.push(departure);
+                continue;
+            }
+        };
+        trip_family_intervals.insert(
+            departure.id.clone(),
+            Duration::from_seconds(headway_repetitions.interval),
+        );
+        for repetition in 0..headway_repetitions.count {
+            let mut repeated_departure = departure.clone();
+            repeated_departure.headway_repetitions = None;
+            repeated_departure.trip_family = Some((departure.id.clone(), repetition as u32));
+            if repetition > 0 {
+                let shift = Duration::from_seconds(repetition * headway_repetitions.interval);
+                repeated_departure.id = format!("{}_{}", departure.id, repetition);
+                for segment in repeated_departure.segments.iter_mut() {
+                    segment.id = format!("{}_{}", segment.id, repetition);
+                    segment.departure = (DateTime::new(&segment.departure) + shift).as_iso();
+                }
+            }
+            departures.push(repeated_departure);
+        }
+    }
+    json_input.departures = departures;
+    trip_family_intervals
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-struct Costs {
-    staff: Integer,
-    service_trip: Integer,
-    maintenance: Option<Integer>,
-    dead_head_trip: Integer,
-    idle: Integer,
+/// Each maintenance slot with a recurrence is replaced by count occurrences, the k-th one shifted
+/// by k days (daily) or k weeks (weekly). The first occurrence keeps the id, the k-th (k >= 1) gets
+/// the suffix _k. Occurrences outside the planning window are dropped by apply_planning_window.
+fn expand_maintenance_slot_recurrences(json_input: &mut JsonInput) {
+    let Some(maintenance_slots) = json_input.maintenance_slots.take() else {
+        return;
+    };
+    let mut expanded_slots = Vec::with_capacity(maintenance_slots.len());
+    for maintenance_slot in maintenance_slots {
+        let recurrence = match maintenance_slot.recurrence.clone() {
+            Some(recurrence) => recurrence,
+            None => {
+                expanded_slots.push(maintenance_slot);
+                continue;
+            }
+        };
+        // a positive count and the end are checked by check_repetitions
+        let end = maintenance_slot.end.as_ref().unwrap();
+        let interval = match recurrence.frequency {
+            RecurrenceFrequency::Daily => 86400,
+            RecurrenceFrequency::Weekly => 7 * 86400,
+        };
+        for occurrence in 0..recurrence.count {
+            let shift = Duration::from_seconds(occurrence * interval);
+            expanded_slots.push(MaintenanceSlots {
+                id: if occurrence == 0 {
+                    maintenance_slot.id.clone()
+                } else {
+                    format!("{}_{}", maintenance_slot.id, occurrence)
+                },
+                location: maintenance_slot.location.clone(),
+                start: (DateTime::new(&maintenance_slot.start) + shift).as_iso(),
+                end: Some((DateTime::new(end) + shift).as_iso()),
+                track_count: maintenance_slot.track_count,
+                service_duration: maintenance_slot.service_duration,
+                recurrence: None,
+            });
+        }
+    }
+    json_input.maintenance_slots = Some(expanded_slots);
 }
 
-pub fn load_rolling_stock_problem_instance_from_json(
-    input_data: serde_json::Value,
-) -> Arc<Network> {
-    let json_input = serde_json::from_value(input_data).expect(
-        "Could not parse input data. Please check if the input data is in the correct format",
-    );
-
-    let (locations, location_lookup) = create_locations(&json_input);
-    let (vehicle_types, vehicle_type_lookup) = create_vehicle_types(&json_input);
-    let config = create_config(&json_input);
-
-    Arc::new(create_network(
-        &json_input,
-        locations,
-        vehicle_types,
-        config,
-        location_lookup,
-        vehicle_type_lookup,
-    ))
+/// Maintenance slots without end last until the end of the planning horizon: the end of
+/// parameters.planningWindow if given, otherwise the latest arrival of all departures and the
+/// latest end of all other maintenance slots (or the start of the slot if there is none).
+fn resolve_open_maintenance_slot_ends(json_input: &mut JsonInput) {
+    let Some(maintenance_slots) = &json_input.maintenance_slots else {
+        return;
+    };
+    if maintenance_slots.iter().all(|slot| slot.end.is_some()) {
+        return;
+    }
+    let horizon_end = match &json_input.parameters.planning_window {
+        Some(planning_window) => DateTime::new(&planning_window.end),
+        None => {
+            let latest_arrival = json_input
+                .departures
+                .iter()
+                .flat_map(|departure| {
+                    let route = json_input
+                        .routes
+                        .iter()
+                        .find(|route| route.id == departure.route);
+                    departure.segments.iter().map(move |departure_segment| {
+                        // unknown routes and segments are left to the validation
+                        let duration = route
+                            .and_then(|route| {
+                                route
+                                    .segments
+                                    .iter()
+                                    .find(|segment| segment.id == departure_segment.route_segment)
+                            })
+                            .map(|segment| segment.duration)
+                            .unwrap_or(0);
+                        DateTime::new(&departure_segment.departure)
+                            + Duration::from_seconds(duration)
+                    })
+                })
+                .max()
+                .unwrap_or(DateTime::Earliest);
+            maintenance_slots
+                .iter()
+                .filter_map(|slot| slot.end.as_ref().map(|end| DateTime::new(end)))
+                .fold(latest_arrival, DateTime::max)
+        }
+    };
+    for maintenance_slot in json_input.maintenance_slots.iter_mut().flatten() {
+        if maintenance_slot.end.is_none() {
+            let start = DateTime::new(&maintenance_slot.start);
+            maintenance_slot.end = Some(horizon_end.max(start).as_iso());
+        }
+    }
 }
 
-fn create_locations(json_input: &JsonInput) -> (Locations, HashMap<IdType, LocationIdx>) {
-    let planning_days = determine_planning_days(json_input);
-    let mut stations: HashMap<LocationIdx, (String, Option<VehicleCount>)> = HashMap::new(); // PpRF: use vec instead
-    let mut dead_head_trips: HashMap<LocationIdx, HashMap<LocationIdx, DeadHeadTrip>> =
-        HashMap::new();
-
-    let mut location_lookup: HashMap<IdType, LocationIdx> = HashMap::new();
+/// With parameters.planningWindow, departure segments that do not lie completely inside the window
+/// are dropped (a repair, so an error in strict mode), departures without segments left are
+/// dropped as well. Maintenance slots reaching over the window are clipped to it, slots without a
+/// usable part inside the window (at least one service duration) are dropped.
+fn apply_planning_window(
+    json_input: &mut JsonInput,
+    warnings: &mut Vec<Warning>,
+    repairs: &mut Vec<ValidationError>,
+) {
+    let (start, end) = match &json_input.parameters.planning_window {
+        Some(planning_window) => (
+            DateTime::new(&planning_window.start),
+            DateTime::new(&planning_window.end),
+        ),
+        None => return,
+    };
 
-    // add stations
-    for (idx, location_json) in json_input.locations.iter().enumerate() {
-        let location_idx = LocationIdx::from(idx as Idx);
-        stations.insert(
-            location_idx,
-            (
-                location_json.id.clone(),
-                location_json.day_limit.map(|x| x as VehicleCount),
+    let routes = &json_input.routes;
+    let mut dropped_segments: Vec<IdType> = Vec::new();
+    for departure in json_input.departures.iter_mut() {
+        let route = routes.iter().find(|route| route.id == departure.route);
+        departure.segments.retain(|departure_segment| {
+            // unknown routes and segments are left to the validation
+            let duration = route
+                .and_then(|route| {
+                    route
+                        .segments
+                        .iter()
+                        .find(|segment| segment.id == departure_segment.route_segment)
+                })
+                .map(|segment| segment.duration)
+                .unwrap_or(0);
+            let departure_time = DateTime::new(&departure_segment.departure);
+            let inside =
+                departure_time >= start && departure_time + Duration::from_seconds(duration) <= end;
+            if !inside {
+                dropped_segments.push(departure_segment.id.clone());
+            }
+            inside
+        });
+    }
+    json_input
+        .departures
+        .retain(|departure| !departure.segments.is_empty());
+    if !dropped_segments.is_empty() {
+        repairs.push(ValidationError {
+            code: "tripOutsidePlanningWindow",
+            message: format!(
+                "{} departure segments do not lie inside the planning window and are dropped: {}.",
+                dropped_segments.len(),
+                dropped_segments.join(", ")
             ),
-        );
-        location_lookup.insert(location_json.id.clone(), location_idx);
+        });
     }
 
-    // add dead head trips
-    let mut duration_warning_printed = false;
-    let mut distance_warning_printed = false;
-    for (i, origin_json) in json_input.dead_head_trips.indices.iter().enumerate() {
-        let origin_station = location_lookup[origin_json];
-        let mut destination_map: HashMap<LocationIdx, DeadHeadTrip> = HashMap::new();
-        for (j, destination_json) in json_input.dead_head_trips.indices.iter().enumerate() {
-            let mut duration = Duration::from_seconds(json_input.dead_head_trips.durations[i][j]);
-            if duration > planning_days {
-                if !duration_warning_printed {
-                    println!(
-                        "\x1b[93mwarning:\x1b[0m Some dead head trip durations exceed planning duration of {} day(s). \
-                        Taking planning duration instead.",
-                        planning_days.in_min().unwrap() / 1440
-                    );
-                    duration_warning_printed = true;
-                }
-                duration = planning_days;
+    if let Some(maintenance_slots) = &mut json_input.maintenance_slots {
+        maintenance_slots.retain_mut(|maintenance_slot| {
+            let slot_start = DateTime::new(&maintenance_slot.start);
+            let slot_end = maintenance_slot.end();
+            // invalid slots are left to the validation
+            if (slot_start >= start && slot_end <= end) || slot_end < slot_start {
+                return true;
             }
-            let mut distance = Distance::from_meter(json_input.dead_head_trips.distances[i][j]);
-            if distance > Distance::from_meter(MAX_DISTANCE) {
-                if !distance_warning_printed {
-                    println!(
-                        "\x1b[93mwarning:\x1b[0m Some dead head trip distances exceed {}m. \
-                        This might be a mistake. Distance reduced to {}m.",
-                        MAX_DISTANCE, MAX_DISTANCE
-                    );
-                    distance_warning_printed = true;
-                }
-                distance = Distance::from_meter(MAX_DISTANCE);
+            let clipped_start = slot_start.max(start);
+            let clipped_end = slot_end.min(end);
+            let minimal_duration =
+                Duration::from_seconds(maintenance_slot.service_duration.unwrap_or(1));
+            let usable = checked_duration_between(clipped_start, clipped_end)
+                .is_ok_and(|duration| duration >= minimal_duration);
+            if !usable {
+                warnings.push(
+                    Warning::new(
+                        "maintenanceSlotOutsidePlanningWindow",
+                        Severity::Info,
+                        format!(
+                            "Maintenance slot {} lies outside the planning window and is dropped.",
+                            maintenance_slot.id
+                        ),
+                    )
+                    .with_context(vec![maintenance_slot.id.clone()]),
+                );
+                return false;
             }
-            destination_map.insert(
-                location_lookup[destination_json],
-                DeadHeadTrip::new(distance, duration),
+            warnings.push(
+                Warning::new(
+                    "maintenanceSlotClipped",
+                    Severity::Info,
+                    format!(
+                        "Maintenance slot {} is clipped to the planning window ({} - {}).",
+                        maintenance_slot.id,
+                        clipped_start.as_iso(),
+                        clipped_end.as_iso()
+                    ),
+                )
+                .with_context(vec![maintenance_slot.id.clone()]),
             );
-        }
-        dead_head_trips.insert(origin_station, destination_map);
+            maintenance_slot.start = clipped_start.as_iso();
+            maintenance_slot.end = Some(clipped_end.as_iso());
+            true
+        });
     }
-
-    (Locations::new(stations, dead_head_trips), location_lookup)
 }
 
+/// The planning window if given, otherwise the span of all departures and maintenance slots,
+/// rounded up to a multiple of days.
 fn determine_planning_days(json_input: &JsonInput) -> Duration {
     let mut earliest_datetime = DateTime::Latest;
     let mut latest_datetime = DateTime::Earliest;
 
-    if let Some(maintenance_slots) = &json_input.maintenance_slots {
-        for maintenance_slot in maintenance_slots {
-            earliest_datetime = earliest_datetime.min(DateTime::new(&maintenance_slot.start));
-            latest_datetime = latest_datetime.max(DateTime::new(&maintenance_slot.end));
+    if let Some(planning_window) = &json_input.parameters.planning_window {
+        earliest_datetime = DateTime::new(&planning_window.start);
+        latest_datetime = DateTime::new(&planning_window.end);
+    } else {
+        if let Some(maintenance_slots) = &json_input.maintenance_slots {
+            for maintenance_slot in maintenance_slots {
+                earliest_datetime = earliest_datetime.min(DateTime::new(&maintenance_slot.start));
+                latest_datetime = latest_datetime.max(maintenance_slot.end());
+            }
         }
-    }
 
-    for departure in &json_input.departures {
-        for departure_segment in &departure.segments {
-            let departure_time = DateTime::new(&departure_segment.departure);
-            let arrival_time = departure_time
-                + Duration::from_seconds(
-                    json_input
-                        .routes
-                        .iter()
-                        .find(|route| route.id == departure.route)
-                        .unwrap()
-                        .segments
-                        .iter()
-                        .find(|segment| segment.id == departure_segment.route_segment)
-                        .unwrap()
-                        .duration,
-                );
-            earliest_datetime = earliest_datetime.min(departure_time);
-            latest_datetime = latest_datetime.max(arrival_time);
+        for departure in &json_input.departures {
+            for departure_segment in &departure.segments {
+                let departure_time = DateTime::new(&departure_segment.departure);
+                let arrival_time = departure_time
+                    + Duration::from_seconds(
+                        json_input
+                            .routes
+                            .iter()
+                            .find(|route| route.id == departure.route)
+                            .unwrap()
+                            .segments
+                            .iter()
+                            .find(|segment| segment.id == departure_segment.route_segment)
+                            .unwrap()
+                            .duration,
+                    );
+                earliest_datetime = earliest_datetime.min(departure_time);
+                latest_datetime = latest_datetime.max(arrival_time);
+            }
         }
     }
-    // round to multiple of day
+    // round to multiple of day (instances without trips and maintenance slots have no days)
     Duration::from_seconds(
-        (latest_datetime - earliest_datetime)
+        checked_duration_between(earliest_datetime, latest_datetime)
+            .unwrap_or(Duration::ZERO)
             .in_sec()
             .unwrap()
             .div_ceil(86400)
@@ -313,18 +1953,60 @@ fn create_vehicle_types(json_input: &JsonInput) -> (VehicleTypes, HashMap<IdType
                     .maximal_formation_count
                     .map(|x| x as VehicleCount),
             )
+            .with_out_of_service_windows(
+                vehicle_type
+                    .out_of_service_windows
+                    .iter()
+                    .flatten()
+                    .map(|window| (DateTime::new(&window.start), DateTime::new(&window.end)))
+                    .collect(),
+            )
+            .with_minimum_in_service(
+                vehicle_type
+                    .minimum_in_service
+                    .iter()
+                    .flatten()
+                    .map(|minimum_in_service| {
+                        (
+                            DateTime::new(&minimum_in_service.window.start),
+                            DateTime::new(&minimum_in_service.window.end),
+                            minimum_in_service.count as VehicleCount,
+                        )
+                    })
+                    .collect(),
+            )
+            .with_shunting(
+                vehicle_type
+                    .shunting
+                    .as_ref()
+                    .and_then(|shunting| shunting.minimal_duration)
+                    .map(Duration::from_seconds),
+                vehicle_type
+                    .shunting
+                    .as_ref()
+                    .and_then(|shunting| shunting.dead_head_trip_duration)
+                    .map(Duration::from_seconds),
+            )
         })
         .collect();
 
     (VehicleTypes::new(vehicle_types), vehicle_type_lookup)
 }
 
-fn create_config(json_input: &JsonInput) -> Config {
-    if json_input.parameters.costs.dead_head_trip <= json_input.parameters.costs.service_trip {
-        println!(
-            "\x1b[93mwarning:\x1b[0m Dead head trip costs are lower than service trip costs. \
-            Vehicle will not hitch-hike on service trips."
-        );
+fn create_config(
+    json_input: &JsonInput,
+    vehicle_type_lookup: &HashMap<IdType, VehicleTypeIdx>,
+    warnings: &mut Vec<Warning>,
+) -> Config {
+    let costs = &json_input.parameters.costs;
+    if costs.dead_head_trip + costs.hitch_hike_bonus.unwrap_or(0) <= costs.service_trip {
+        warnings.push(Warning::new(
+            "deadHeadTripCostsTooLow",
+            Severity::Warning,
+            "Dead head trip costs (plus the hitch-hike bonus) are not higher than service trip \
+            costs. Vehicles will rather dead-head than hitch-hike on service trips."
+                .to_string(),
+        ));
     }
     Config::new(
         json_input
@@ -347,25 +2029,323 @@ fn create_config(json_input: &JsonInput) -> Config {
         json_input.parameters.costs.maintenance.unwrap_or(0),
         json_input.parameters.costs.dead_head_trip,
         json_input.parameters.costs.idle,
+        json_input.parameters.costs.line_purity.unwrap_or(0),
+        json_input
+            .parameters
+            .time_partitioning
+            .as_ref()
+            .map(create_time_partitioning_config),
+        json_input
+            .parameters
+            .fixed_fleet
+            .as_ref()
+            .map(|fixed_fleet| create_fixed_fleet(fixed_fleet, vehicle_type_lookup))
+            .unwrap_or_default(),
+        json_input.parameters.check_costs.unwrap_or(false),
+        json_input
+            .parameters
+            .formation_order_continuity
+            .unwrap_or(false),
+        Duration::from_seconds(
+            json_input
+                .parameters
+                .dead_head_pairing_tolerance
+                .unwrap_or(300),
+        ),
+        json_input
+            .parameters
+            .adaptive_operator_weights
+            .as_ref()
+            .map(create_adaptive_operator_weights_config),
+        match json_input
+            .parameters
+            .output
+            .as_ref()
+            .and_then(|output| output.level)
+        {
+            None | Some(OutputLevel::Full) => ModelOutputLevel::Full,
+            Some(OutputLevel::Summary) => ModelOutputLevel::Summary,
+            Some(OutputLevel::ObjectiveOnly) => ModelOutputLevel::ObjectiveOnly,
+        },
+        create_crowding_thresholds(
+            json_input
+                .parameters
+                .output
+                .as_ref()
+                .and_then(|output| output.crowding_thresholds.as_ref()),
+        ),
+        match json_input
+            .parameters
+            .maintenance
+            .as_ref()
+            .and_then(|m| m.depot_credit)
+        {
+            None => DepotMaintenanceCredit::Full,
+            Some(depot_credit) => DepotMaintenanceCredit::Fixed(Distance::from_meter(depot_credit)),
+        },
+        json_input
+            .parameters
+            .maintenance
+            .as_ref()
+            .and_then(|m| m.hard)
+            .unwrap_or(false),
+        json_input.parameters.costs.hitch_hike_bonus.unwrap_or(0),
+        json_input
+            .parameters
+            .collapse_location_groups
+            .unwrap_or(false),
+        json_input
+            .hints
+            .as_ref()
+            .map(|hints| hints.iter().map(create_hint).collect())
+            .unwrap_or_default(),
+        json_input.parameters.memory_budget,
+        json_input
+            .parameters
+            .planning_window
+            .as_ref()
+            .map(|window| (DateTime::new(&window.start), DateTime::new(&window.end))),
+        json_input
+            .parameters
+            .maximal_dead_head_duration
+            .map(Duration::from_seconds),
+        match json_input
+            .parameters
+            .output
+            .as_ref()
+            .and_then(|output| output.contract_dead_head_allocation)
+        {
+            None | Some(ContractDeadHeadAllocation::FollowingTrip) => {
+                ModelContractDeadHeadAllocation::FollowingTrip
+            }
+            Some(ContractDeadHeadAllocation::Split) => ModelContractDeadHeadAllocation::Split,
+        },
+        json_input
+            .parameters
+            .webhook
+            .as_ref()
+            .map(create_webhook_config),
+        match json_input
+            .parameters
+            .depot_assignment
+            .as_ref()
+            .and_then(|depot_assignment| depot_assignment.policy)
+        {
+            None | Some(DepotAssignmentPolicy::Nearest) => ModelDepotAssignmentPolicy::Nearest,
+            Some(DepotAssignmentPolicy::BalanceAware) => ModelDepotAssignmentPolicy::BalanceAware,
+            Some(DepotAssignmentPolicy::CostAware) => ModelDepotAssignmentPolicy::CostAware,
+        },
+        Distance::from_meter(
+            json_input
+                .parameters
+                .depot_assignment
+                .as_ref()
+                .and_then(|depot_assignment| depot_assignment.distance_tolerance)
+                .unwrap_or(20000),
+        ),
+        json_input
+            .parameters
+            .milestones
+            .as_deref()
+            .map(create_milestones)
+            .unwrap_or_default(),
+        json_input
+            .parameters
+            .out_of_service
+            .as_ref()
+            .and_then(|out_of_service| out_of_service.hard)
+            .unwrap_or(true),
+        json_input
+            .parameters
+            .active_vehicle_types
+            .as_ref()
+            .map(|active_vehicle_types| {
+                create_active_vehicle_types(active_vehicle_types, vehicle_type_lookup)
+            }),
+        json_input
+            .parameters
+            .debug
+            .as_ref()
+            .and_then(|debug| debug.trace_file.clone()),
+        json_input
+            .parameters
+            .time_limit
+            .filter(|&time_limit| time_limit > 0)
+            .map(Duration::from_seconds),
+        json_input
+            .parameters
+            .costs
+            .dead_head_trip_count
+            .unwrap_or(0),
+        json_input
+            .parameters
+            .costs
+            .duty_length_variance
+            .unwrap_or(0),
+        json_input
+            .parameters
+            .number_of_threads
+            .filter(|&number_of_threads| number_of_threads > 0)
+            .map(|number_of_threads| number_of_threads as usize),
+        json_input
+            .parameters
+            .solver
+            .as_ref()
+            .and_then(|solver| solver.two_phase)
+            .unwrap_or(false),
+        json_input
+            .parameters
+            .costs
+            .staff_time_factors
+            .iter()
+            .flatten()
+            .map(create_staff_time_factor)
+            .collect(),
+        json_input
+            .parameters
+            .infeasibility_threshold
+            .map(|threshold| InfeasibilityCheckConfig {
+                threshold,
+                policy: match json_input.parameters.infeasibility_policy {
+                    None | Some(InfeasibilityPolicy::Warn) => ModelInfeasibilityPolicy::Warn,
+                    Some(InfeasibilityPolicy::Abort) => ModelInfeasibilityPolicy::Abort,
+                },
+            }),
     )
 }
 
+fn create_staff_time_factor(factor: &StaffTimeFactor) -> ModelStaffTimeFactor {
+    ModelStaffTimeFactor {
+        start: factor.window.start,
+        end: factor.window.end,
+        factor: factor.factor,
+    }
+}
+
+fn create_milestones(milestones: &[Integer]) -> Vec<Duration> {
+    let mut milestones = milestones.to_vec();
+    milestones.sort();
+    milestones.dedup();
+    milestones.into_iter().map(Duration::from_seconds).collect()
+}
+
+fn create_fixed_fleet(
+    fixed_fleet: &HashMap<IdType, Integer>,
+    vehicle_type_lookup: &HashMap<IdType, VehicleTypeIdx>,
+) -> HashMap<VehicleTypeIdx, VehicleCount> {
+    fixed_fleet
+        .iter()
+        .map(|(vehicle_type_id, count)| {
+            (vehicle_type_lookup[vehicle_type_id], *count as VehicleCount)
+        })
+        .collect()
+}
+
+fn create_active_vehicle_types(
+    active_vehicle_types: &[IdType],
+    vehicle_type_lookup: &HashMap<IdType, VehicleTypeIdx>,
+) -> HashSet<VehicleTypeIdx> {
+    active_vehicle_types
+        .iter()
+        .map(|vehicle_type_id| vehicle_type_lookup[vehicle_type_id])
+        .collect()
+}
+
+/// Hints are not resolved here, as they refer to vehicles of the start schedule. Unknown ids
+/// only let the hint fail, they do not reject the instance.
+fn create_hint(hint: &Hint) -> ModelHint {
+    match hint {
+        Hint::MergeTours {
+            vehicle,
+            into_vehicle,
+        } => ModelHint::MergeTours {
+            vehicle: vehicle.clone(),
+            into_vehicle: into_vehicle.clone(),
+        },
+        Hint::MoveTrip { trip, to_vehicle } => ModelHint::MoveTrip {
+            trip: trip.clone(),
+            to_vehicle: to_vehicle.clone(),
+        },
+        Hint::AssignDepot { vehicle, depot } => ModelHint::AssignDepot {
+            vehicle: vehicle.clone(),
+            depot: depot.clone(),
+        },
+    }
+}
+
+fn create_time_partitioning_config(time_partitioning: &TimePartitioning) -> TimePartitioningConfig {
+    TimePartitioningConfig {
+        window_length: Duration::from_seconds(time_partitioning.window_length),
+        overlap: Duration::from_seconds(time_partitioning.overlap),
+        polish_time_limit: time_partitioning
+            .polish_time_limit
+            .map(Duration::from_seconds),
+    }
+}
+
+fn create_webhook_config(webhook: &Webhook) -> WebhookConfig {
+    WebhookConfig {
+        url: webhook.url.clone(),
+        events: match &webhook.events {
+            None => vec![
+                ModelWebhookEvent::Improvement,
+                ModelWebhookEvent::Finished,
+                ModelWebhookEvent::Failed,
+            ],
+            Some(events) => events
+                .iter()
+                .map(|event| match event {
+                    WebhookEvent::Improvement => ModelWebhookEvent::Improvement,
+                    WebhookEvent::Finished => ModelWebhookEvent::Finished,
+                    WebhookEvent::Failed => ModelWebhookEvent::Failed,
+                })
+                .collect(),
+        },
+        min_interval: Duration::from_seconds(webhook.min_interval_seconds.unwrap_or(10)),
+    }
+}
+
+fn create_adaptive_operator_weights_config(
+    adaptive_operator_weights: &AdaptiveOperatorWeights,
+) -> AdaptiveOperatorWeightsConfig {
+    AdaptiveOperatorWeightsConfig {
+        interval: adaptive_operator_weights.interval.unwrap_or(100) as u32,
+        minimal_share: adaptive_operator_weights.minimal_share.unwrap_or(0.1),
+        maximal_share: adaptive_operator_weights.maximal_share.unwrap_or(1.0),
+        reaction_factor: adaptive_operator_weights.reaction_factor.unwrap_or(0.5),
+        initial_weights: adaptive_operator_weights
+            .initial_weights
+            .clone()
+            .unwrap_or_default(),
+    }
+}
+
+fn create_crowding_thresholds(
+    crowding_thresholds: Option<&CrowdingThresholds>,
+) -> ModelCrowdingThresholds {
+    ModelCrowdingThresholds {
+        moderate: crowding_thresholds
+            .and_then(|thresholds| thresholds.moderate)
+            .unwrap_or(0.6),
+        high: crowding_thresholds
+            .and_then(|thresholds| thresholds.high)
+            .unwrap_or(0.85),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn create_network(
     json_input: &JsonInput,
     locations: Locations,
     vehicle_types: VehicleTypes,
     config: Config,
+    service_trips: HashMap<VehicleTypeIdx, Vec<ModelServiceTrip>>,
     location_lookup: HashMap<IdType, LocationIdx>,
     vehicle_type_lookup: HashMap<IdType, VehicleTypeIdx>,
+    trip_family_intervals: HashMap<IdType, Duration>,
+    instance_hash: String,
+    warnings: Vec<Warning>,
 ) -> Network {
-    let service_trips = create_service_trips(
-        json_input,
-        &locations,
-        &vehicle_types,
-        &location_lookup,
-        &vehicle_type_lookup,
-    );
-
     let number_of_service_trips: VehicleCount = service_trips
         .values()
         .map(|trips| trips.len() as VehicleCount)
@@ -387,6 +2367,10 @@ fn create_network(
         config,
         locations,
         vehicle_types,
+        trip_family_intervals,
+        create_split_points(json_input),
+        instance_hash,
+        warnings,
     )
 }
 
@@ -400,11 +2384,13 @@ fn create_depots(
     match &json_input.depots {
         None => {
             // add a depot at every location with unlimited capacity for each type
-            let allowed_vehicle_types: HashMap<VehicleTypeIdx, Option<VehicleCount>> =
-                vehicle_type_lookup
-                    .values()
-                    .map(|vehicle_type_idx| (*vehicle_type_idx, None))
-                    .collect();
+            let allowed_vehicle_types: HashMap<
+                VehicleTypeIdx,
+                (Option<VehicleCount>, Option<VehicleCount>),
+            > = vehicle_type_lookup
+                .values()
+                .map(|vehicle_type_idx| (*vehicle_type_idx, (None, None)))
+                .collect();
             loc.iter()
                 .enumerate()
                 .map(|(idx, location)| {
@@ -412,8 +2398,13 @@ fn create_depots(
                         DepotIdx::from(idx as Idx),
                         format!("depot_{}", loc.get_id(location).unwrap()),
                         location,
-                        VehicleCount::from(vehicle_upper_limit),
-                        allowed_vehicle_types.clone(),
+                        DepotCapacities {
+                            spawn: VehicleCount::from(vehicle_upper_limit),
+                            despawn: VehicleCount::from(vehicle_upper_limit),
+                            allowed_types: allowed_vehicle_types.clone(),
+                        },
+                        false,
+                        0,
                     )
                 })
                 .collect()
@@ -424,21 +2415,40 @@ fn create_depots(
             .map(|(idx, depot)| {
                 let idx = DepotIdx::from(idx as Idx);
                 let location = loc.get(location_lookup[&depot.location]).unwrap();
-                let capacity: VehicleCount = depot.capacity as VehicleCount;
-                let mut allowed_types: HashMap<VehicleTypeIdx, Option<VehicleCount>> =
-                    HashMap::new();
+                // spawnCapacity and despawnCapacity fall back to capacity
+                let spawn_capacity = depot.spawn_capacity.unwrap_or(depot.capacity) as VehicleCount;
+                let despawn_capacity =
+                    depot.despawn_capacity.unwrap_or(depot.capacity) as VehicleCount;
+                let mut allowed_types: HashMap<
+                    VehicleTypeIdx,
+                    (Option<VehicleCount>, Option<VehicleCount>),
+                > = HashMap::new();
                 for allowed_type in &depot.allowed_types {
                     allowed_types.insert(
                         vehicle_type_lookup[&allowed_type.vehicle_type],
-                        allowed_type.capacity.map(|x| x as VehicleCount),
+                        (
+                            allowed_type
+                                .spawn_capacity
+                                .or(allowed_type.capacity)
+                                .map(|x| x as VehicleCount),
+                            allowed_type
+                                .despawn_capacity
+                                .or(allowed_type.capacity)
+                                .map(|x| x as VehicleCount),
+                        ),
                     );
                 }
                 ModelDepot::new(
                     idx,
                     depot.id.clone(),
                     location,
-                    capacity,
-                    allowed_types.clone(),
+                    DepotCapacities {
+                        spawn: spawn_capacity,
+                        despawn: despawn_capacity,
+                        allowed_types,
+                    },
+                    depot.provides_maintenance.unwrap_or(false),
+                    depot.spawn_cost.unwrap_or(0),
                 )
             })
             .collect(),
@@ -451,6 +2461,7 @@ fn create_service_trips(
     vehicle_types: &VehicleTypes,
     location_lookup: &HashMap<IdType, LocationIdx>,
     vehicle_type_lookup: &HashMap<IdType, VehicleTypeIdx>,
+    repairs: &mut Vec<ValidationError>,
 ) -> HashMap<VehicleTypeIdx, Vec<ModelServiceTrip>> {
     let mut service_trips: HashMap<VehicleTypeIdx, Vec<ModelServiceTrip>> = HashMap::new();
     for vehicle_type in vehicle_types.iter() {
@@ -489,9 +2500,12 @@ fn create_service_trips(
             if passengers == 0 {
                 passengers = 1;
                 if !warnings_printed {
-                    println!(
-                    "\x1b[93mwarning:\x1b[0m Some service trips have no passengers. Setting passengers to 1, so that at least one vehicle is needed.",
-                );
+                    repairs.push(ValidationError {
+                        code: "noPassengers",
+                        message: "Some service trips have no passengers. Setting passengers to 1, \
+                            so that at least one vehicle is needed."
+                            .to_string(),
+                    });
                     warnings_printed = true;
                 }
             }
@@ -499,9 +2513,13 @@ fn create_service_trips(
             let maximal_formation_count = route_segment
                 .maximal_formation_count
                 .map(|x| x as VehicleCount);
+            let minimal_formation_count = route_segment
+                .minimal_formation_count
+                .map(|x| x as VehicleCount);
 
             let service_trip = Node::create_service_trip(
                 id,
+                route.id.clone(),
                 vehicle_type,
                 origin,
                 destination,
@@ -511,6 +2529,9 @@ fn create_service_trips(
                 passengers,
                 seated,
                 maximal_formation_count,
+                minimal_formation_count,
+                departure.trip_family.clone(),
+                route.contract.clone(),
             );
             service_trips
                 .get_mut(&vehicle_type)
@@ -521,6 +2542,10 @@ fn create_service_trips(
     service_trips
 }
 
+/// A maintenance slot with a service duration is partitioned into consecutive sub-slots of that
+/// duration (ids "<id>#<k>", k starting at 0), each with the track count of the slot. Hence,
+/// vehicles visiting one after another share a track. A remainder shorter than the service
+/// duration is dropped.
 fn create_maintenance_slots(
     json_input: &JsonInput,
     locations: &Locations,
@@ -530,21 +2555,43 @@ fn create_maintenance_slots(
         None => Vec::new(),
         Some(maintenance_slots) => maintenance_slots
             .iter()
-            .map(|maintenance_slot| {
+            .flat_map(|maintenance_slot| {
                 let location = locations
                     .get(location_lookup[&maintenance_slot.location])
                     .unwrap();
                 let start = DateTime::new(&maintenance_slot.start);
-                let end = DateTime::new(&maintenance_slot.end);
-                let id = maintenance_slot.id.clone();
+                let end = maintenance_slot.end();
+                let track_count = maintenance_slot.track_count as VehicleCount;
 
-                Node::create_maintenance(
-                    id,
-                    location,
-                    start,
-                    end,
-                    maintenance_slot.track_count as VehicleCount,
-                )
+                match maintenance_slot.service_duration {
+                    None => vec![Node::create_maintenance(
+                        maintenance_slot.id.clone(),
+                        location,
+                        start,
+                        end,
+                        track_count,
+                    )],
+                    Some(service_duration) => {
+                        let service_duration = Duration::from_seconds(service_duration);
+                        let sub_slot_count =
+                            (end - start).in_sec().unwrap() / service_duration.in_sec().unwrap();
+                        (0..sub_slot_count)
+                            .map(|k| {
+                                let sub_slot_start = start
+                                    + Duration::from_seconds(
+                                        k * service_duration.in_sec().unwrap(),
+                                    );
+                                Node::create_maintenance(
+                                    format!("{}#{}", maintenance_slot.id, k),
+                                    location,
+                                    sub_slot_start,
+                                    sub_slot_start + service_duration,
+                                    track_count,
+                                )
+                            })
+                            .collect()
+                    }
+                }
             })
             .collect(),
     }
@@ -0,0 +1,112 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rapid_time::{DateTime, Duration};
+
+use super::{Departures, Integer, JsonInput};
+
+/// Parameters for a random perturbation of an instance (see perturb_instance).
+pub struct PerturbationConfig {
+    pub shift_fraction: f64,     // fraction of departures that are shifted
+    pub maximal_shift: Duration, // departures are shifted by up to ± this duration (in whole minutes)
+    pub demand_deviation: f64, // demand is scaled by a random factor in [1 - deviation, 1 + deviation]
+    pub drop_fraction: f64,    // fraction of departures that are removed
+    pub add_fraction: f64,     // fraction of departures that are duplicated (shifted, with new ids)
+}
+
+/// Produces a randomly perturbed copy of the instance (e.g., for robustness studies). The same
+/// seed always produces the same copy.
+/// A departure is shifted as a whole, so that the arrival times (derived from the route segments)
+/// stay consistent. Ids of existing departures are kept, added departures get new ids.
+pub fn perturb_instance(
+    input_data: serde_json::Value,
+    config: &PerturbationConfig,
+    seed: u64,
+) -> serde_json::Value {
+    for fraction in [
+        config.shift_fraction,
+        config.demand_deviation,
+        config.drop_fraction,
+        config.add_fraction,
+    ] {
+        assert!(
+            (0.0..=1.0).contains(&fraction),
+            "Perturbation fractions must be between 0 and 1."
+        );
+    }
+
+    let mut json_input: JsonInput = serde_json::from_value(input_data).expect(
+        "Could not parse input data. Please check if the input data is in the correct format",
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    json_input
+        .departures
+        .retain(|_| !rng.gen_bool(config.drop_fraction));
+
+    let mut added_departures: Vec<Departures> = Vec::new();
+    if !json_input.departures.is_empty() {
+        let number_of_added_departures =
+            (json_input.departures.len() as f64 * config.add_fraction).round() as usize;
+        for counter in 0..number_of_added_departures {
+            let original = &json_input.departures[rng.gen_range(0..json_input.departures.len())];
+            let mut departure = original.clone();
+            departure.id = format!("{}_added_{}", original.id, counter);
+            for segment in departure.segments.iter_mut() {
+                segment.id = format!("{}_added_{}", segment.id, counter);
+            }
+            shift_departure(&mut departure, random_shift(&mut rng, config));
+            added_departures.push(departure);
+        }
+    }
+
+    for departure in json_input.departures.iter_mut() {
+        if rng.gen_bool(config.shift_fraction) {
+            shift_departure(departure, random_shift(&mut rng, config));
+        }
+        for segment in departure.segments.iter_mut() {
+            let factor =
+                rng.gen_range(1.0 - config.demand_deviation..=1.0 + config.demand_deviation);
+            segment.passengers = scale(segment.passengers, factor);
+            segment.seated = scale(segment.seated, factor).min(segment.passengers);
+        }
+    }
+    json_input.departures.extend(added_departures);
+
+    serde_json::to_value(json_input).unwrap()
+}
+
+/// shift in minutes
+fn random_shift(rng: &mut StdRng, config: &PerturbationConfig) -> i64 {
+    let maximal_shift = config.maximal_shift.in_min().unwrap() as i64;
+    rng.gen_range(-maximal_shift..=maximal_shift)
+}
+
+fn shift_departure(departure: &mut Departures, shift_in_minutes: i64) {
+    let shift = Duration::from_seconds(shift_in_minutes.unsigned_abs() * 60);
+    for segment in departure.segments.iter_mut() {
+        let departure_time = DateTime::new(&segment.departure);
+        let shifted = if shift_in_minutes >= 0 {
+            departure_time + shift
+        } else {
+            departure_time - shift
+        };
+        segment.departure = shifted.as_iso();
+    }
+}
+
+fn scale(value: Integer, factor: f64) -> Integer {
+    (value as f64 * factor).round() as Integer
+}
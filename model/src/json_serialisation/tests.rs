@@ -16,11 +16,18 @@ use std::{fs::File, io::Read};
 use rapid_time::{DateTime, Duration};
 
 use crate::{
-    base_types::{DepotIdx, Distance, Location, LocationIdx, NodeIdx, VehicleTypeIdx},
-    json_serialisation::load_rolling_stock_problem_instance_from_json,
+    base_types::{
+        DepotIdx, Distance, Location, LocationIdx, NodeIdx, VehicleTypeIdx, MAX_PASSENGERS,
+        MAX_SEGMENT_DISTANCE, MAX_VEHICLE_CAPACITY,
+    },
+    json_serialisation::{
+        instance_hash, load_rolling_stock_problem_instance_from_json, perturb_instance,
+        try_load_rolling_stock_problem_instance_from_json, PerturbationConfig,
+    },
     locations::Locations,
-    network::nodes::Node,
+    network::{nodes::Node, Network},
     vehicle_types::VehicleType,
+    warnings::Severity,
 };
 
 //add a test that reads a json file
@@ -34,12 +41,1513 @@ fn test_load_from_json_with_null() {
     test_load_from_json("resources/small_test_input_with_null_values.json");
 }
 
-fn test_load_from_json(path: &str) {
+#[test]
+fn test_load_minimal_formation_count_from_json() {
+    // ARRANGE
+    let mut input_data = read_json("resources/small_test_input.json");
+    input_data["routes"][0]["segments"][1]["minimalFormationCount"] = 2.into();
+
     // ACT
-    let mut file = File::open(path).unwrap();
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    assert_eq!(
+        network.minimal_formation_count_for(NodeIdx::service_from(8)),
+        None
+    );
+    assert_eq!(
+        network.minimal_formation_count_for(NodeIdx::service_from(9)),
+        Some(2)
+    );
+}
+
+#[test]
+fn test_stranded_maintenance_slot_is_excluded() {
+    // ACT
+    let input_data = read_json("resources/small_test_input_with_stranded_maintenance_slot.json");
+
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let stranded_slot = NodeIdx::maintenance_from(12);
+    assert_eq!(
+        network.node(stranded_slot).id(),
+        "maintenance_slot_stranded"
+    );
+    assert_eq!(
+        network.maintenance_nodes().collect::<Vec<_>>(),
+        vec![NodeIdx::maintenance_from(10), NodeIdx::maintenance_from(11)]
+    );
+    assert!(!network.coverable_nodes().any(|n| n == stranded_slot));
+    assert!(!network
+        .successors(VehicleTypeIdx::from(0), NodeIdx::start_depot_from(0))
+        .any(|n| n == stranded_slot));
+    assert!(network
+        .warnings()
+        .iter()
+        .any(|w| w.code == "maintenanceSlotIgnored"
+            && w.context == vec!["maintenance_slot_stranded".to_string()]));
+}
+
+#[test]
+fn test_strict_input() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // planning duration is one day, trip_1b_seg_0 has already no passengers
+    input_data["deadHeadTrips"]["durations"][0][1] = 200000.into();
+    input_data["deadHeadTrips"]["distances"][0][1] = 2000000.into();
+
+    let mut strict_input_data = input_data.clone();
+    strict_input_data["parameters"]["strictInput"] = true.into();
+
+    let mut repaired_strict_input_data = strict_input_data.clone();
+    repaired_strict_input_data["deadHeadTrips"]["durations"][0][1] = 600.into();
+    repaired_strict_input_data["deadHeadTrips"]["distances"][0][1] = 1000.into();
+    repaired_strict_input_data["departures"][2]["segments"][0]["passengers"] = 1.into();
+
+    // ACT
+    let network = try_load_rolling_stock_problem_instance_from_json(input_data);
+    let strict_result = try_load_rolling_stock_problem_instance_from_json(strict_input_data);
+    let repaired_strict_result =
+        try_load_rolling_stock_problem_instance_from_json(repaired_strict_input_data);
+
+    // ASSERT
+    let network = network.ok().unwrap();
+    for (code, warning) in [
+        (
+            "deadHeadTripDurationTooLong",
+            "Some dead head trip durations exceed planning duration",
+        ),
+        (
+            "deadHeadTripDistanceTooLong",
+            "Some dead head trip distances exceed",
+        ),
+        ("noPassengers", "Some service trips have no passengers"),
+    ] {
+        assert!(network
+            .warnings()
+            .iter()
+            .any(|w| w.code == code && w.message.starts_with(warning)));
+    }
+
+    let errors = strict_result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec![
+            "deadHeadTripDurationTooLong",
+            "deadHeadTripDistanceTooLong",
+            "noPassengers"
+        ]
+    );
+
+    assert!(repaired_strict_result.is_ok());
+}
+
+#[test]
+fn test_dead_head_trips_to_same_location_are_zero() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
     let mut input_data = String::new();
     file.read_to_string(&mut input_data).unwrap();
     let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let mut dirty_input_data = input_data.clone();
+    let number_of_indices = input_data["deadHeadTrips"]["indices"]
+        .as_array()
+        .unwrap()
+        .len();
+    for i in 0..number_of_indices {
+        dirty_input_data["deadHeadTrips"]["durations"][i][i] = 1800.into();
+        dirty_input_data["deadHeadTrips"]["distances"][i][i] = 5000.into();
+    }
+    let mut strict_dirty_input_data = dirty_input_data.clone();
+    strict_dirty_input_data["parameters"]["strictInput"] = true.into();
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+    let dirty_network = load_rolling_stock_problem_instance_from_json(dirty_input_data);
+    let strict_result = try_load_rolling_stock_problem_instance_from_json(strict_dirty_input_data);
+
+    // ASSERT
+    for location in dirty_network.locations().iter() {
+        assert_eq!(
+            dirty_network.locations().travel_time(location, location),
+            Duration::ZERO
+        );
+        assert_eq!(
+            dirty_network.locations().distance(location, location),
+            Distance::ZERO
+        );
+    }
+    for node1 in network.all_nodes() {
+        for node2 in network.all_nodes() {
+            assert_eq!(
+                dirty_network.can_reach(node1, node2),
+                network.can_reach(node1, node2)
+            );
+            assert_eq!(
+                dirty_network.dead_head_time_between(node1, node2),
+                network.dead_head_time_between(node1, node2)
+            );
+            assert_eq!(
+                dirty_network.idle_time_between(node1, node2),
+                network.idle_time_between(node1, node2)
+            );
+        }
+    }
+    let warning = dirty_network
+        .warnings()
+        .iter()
+        .find(|w| w.code == "deadHeadTripToSameLocation")
+        .unwrap();
+    assert!(warning.message.contains("BN"));
+    assert!(!network
+        .warnings()
+        .iter()
+        .any(|w| w.code == "deadHeadTripToSameLocation"));
+    assert!(strict_result
+        .err()
+        .unwrap()
+        .iter()
+        .any(|e| e.code == "deadHeadTripToSameLocation"));
+}
+
+#[test]
+fn test_route_through_location_not_admitting_its_vehicle_type() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // route_0 (IR) passes LU, route_1 (IC) ends at LU
+    input_data["locations"][2]["allowedVehicleTypes"] = serde_json::json!(["IC"]);
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|e| e.code == "vehicleTypeNotAllowedAtLocation"));
+    assert!(errors[0].message.contains("route_0_seg_0"));
+    assert!(errors[1].message.contains("route_0_seg_1"));
+}
+
+#[test]
+fn test_maintenance_slot_with_service_duration() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // maintenance_slot_0 lasts from 6:00 to 12:00 and has 2 tracks
+    input_data["maintenanceSlots"][0]["serviceDuration"] = 7200.into();
+    let mut invalid_input_data = input_data.clone();
+    invalid_input_data["maintenanceSlots"][0]["serviceDuration"] = 30000.into();
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+    let invalid_result = try_load_rolling_stock_problem_instance_from_json(invalid_input_data);
+
+    // ASSERT
+    let sub_slots: Vec<_> = network
+        .maintenance_nodes()
+        .map(|node| network.node(node))
+        .filter(|node| node.id().starts_with("maintenance_slot_0"))
+        .map(|node| {
+            (
+                node.id().to_string(),
+                node.start_time(),
+                node.end_time(),
+                node.as_maintenance_slot().track_count(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        sub_slots,
+        vec![
+            (
+                "maintenance_slot_0#0".to_string(),
+                DateTime::new("2023-07-24T06:00:00"),
+                DateTime::new("2023-07-24T08:00:00"),
+                2
+            ),
+            (
+                "maintenance_slot_0#1".to_string(),
+                DateTime::new("2023-07-24T08:00:00"),
+                DateTime::new("2023-07-24T10:00:00"),
+                2
+            ),
+            (
+                "maintenance_slot_0#2".to_string(),
+                DateTime::new("2023-07-24T10:00:00"),
+                DateTime::new("2023-07-24T12:00:00"),
+                2
+            ),
+        ]
+    );
+    // maintenance_slot_1 is excluded as unusable (no service trip can reach it)
+    assert_eq!(network.maintenance_nodes().count(), 3);
+
+    let errors = invalid_result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["maintenanceServiceDurationInvalid"]
+    );
+}
+
+/// id, start and end of all maintenance nodes (including stranded ones), sorted by start
+fn maintenance_slots_of(network: &crate::network::Network) -> Vec<(String, DateTime, DateTime)> {
+    let mut slots: Vec<_> = network
+        .all_nodes()
+        .map(|node| network.node(node))
+        .filter(|node| node.is_maintenance())
+        .map(|node| (node.id().to_string(), node.start_time(), node.end_time()))
+        .collect();
+    slots.sort_by_key(|(id, start, _)| (*start, id.clone()));
+    slots
+}
+
+#[test]
+fn test_maintenance_slot_recurrence() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // maintenance_slot_0 lasts from 6:00 to 12:00, maintenance_slot_1 from 12:00 to 18:00
+    input_data["maintenanceSlots"][0]["recurrence"] =
+        serde_json::json!({"frequency": "daily", "count": 3});
+    input_data["maintenanceSlots"][1]["recurrence"] =
+        serde_json::json!({"frequency": "weekly", "count": 2});
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let slots = maintenance_slots_of(&network);
+    assert_eq!(slots.len(), 5);
+    assert_eq!(
+        slots,
+        vec![
+            (
+                "maintenance_slot_0".to_string(),
+                DateTime::new("2023-07-24T06:00:00"),
+                DateTime::new("2023-07-24T12:00:00")
+            ),
+            (
+                "maintenance_slot_1".to_string(),
+                DateTime::new("2023-07-24T12:00:00"),
+                DateTime::new("2023-07-24T18:00:00")
+            ),
+            (
+                "maintenance_slot_0_1".to_string(),
+                DateTime::new("2023-07-25T06:00:00"),
+                DateTime::new("2023-07-25T12:00:00")
+            ),
+            (
+                "maintenance_slot_0_2".to_string(),
+                DateTime::new("2023-07-26T06:00:00"),
+                DateTime::new("2023-07-26T12:00:00")
+            ),
+            (
+                "maintenance_slot_1_1".to_string(),
+                DateTime::new("2023-07-31T12:00:00"),
+                DateTime::new("2023-07-31T18:00:00")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_maintenance_slot_recurrence_with_planning_window() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // every morning from 6:00 to 12:00 for a week
+    input_data["maintenanceSlots"][0]["recurrence"] =
+        serde_json::json!({"frequency": "daily", "count": 7});
+    input_data["parameters"]["planningWindow"] = serde_json::json!({
+        "start": "2023-07-24T08:00:00",
+        "end": "2023-07-26T10:00:00"
+    });
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    // the first occurrence is clipped at the start, the third at the end and the last four are
+    // dropped
+    let slots = maintenance_slots_of(&network);
+    assert_eq!(slots.len(), 4);
+    assert_eq!(
+        slots
+            .iter()
+            .filter(|(id, _, _)| id.starts_with("maintenance_slot_0"))
+            .cloned()
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                "maintenance_slot_0".to_string(),
+                DateTime::new("2023-07-24T08:00:00"),
+                DateTime::new("2023-07-24T12:00:00")
+            ),
+            (
+                "maintenance_slot_0_1".to_string(),
+                DateTime::new("2023-07-25T06:00:00"),
+                DateTime::new("2023-07-25T12:00:00")
+            ),
+            (
+                "maintenance_slot_0_2".to_string(),
+                DateTime::new("2023-07-26T06:00:00"),
+                DateTime::new("2023-07-26T10:00:00")
+            ),
+        ]
+    );
+    assert_eq!(
+        network
+            .warnings()
+            .iter()
+            .filter(|w| w.code == "maintenanceSlotOutsidePlanningWindow")
+            .count(),
+        4
+    );
+}
+
+#[test]
+fn test_maintenance_slot_without_end() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // maintenance_slot_1 starts at 12:00
+    input_data["maintenanceSlots"][1]
+        .as_object_mut()
+        .unwrap()
+        .remove("end");
+    let mut input_data_with_window = input_data.clone();
+    input_data_with_window["parameters"]["planningWindow"] = serde_json::json!({
+        "start": "2023-07-24T00:00:00",
+        "end": "2023-07-25T00:00:00"
+    });
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+    let network_with_window = load_rolling_stock_problem_instance_from_json(input_data_with_window);
+
+    // ASSERT
+    // without planning window the slot lasts until the last arrival (maintenance_slot_0 ends
+    // before)
+    let last_arrival = network
+        .all_service_nodes()
+        .map(|node| network.node(node).end_time())
+        .max()
+        .unwrap();
+    let slots = maintenance_slots_of(&network);
+    assert_eq!(slots.len(), 2);
+    assert_eq!(
+        slots[1],
+        (
+            "maintenance_slot_1".to_string(),
+            DateTime::new("2023-07-24T12:00:00"),
+            last_arrival
+        )
+    );
+    assert_eq!(
+        maintenance_slots_of(&network_with_window)[1],
+        (
+            "maintenance_slot_1".to_string(),
+            DateTime::new("2023-07-24T12:00:00"),
+            DateTime::new("2023-07-25T00:00:00")
+        )
+    );
+}
+
+#[test]
+fn test_planning_window() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // a stray departure five days after all other data
+    let mut stray_departure = input_data["departures"][2].clone();
+    stray_departure["id"] = "stray".into();
+    stray_departure["segments"][0]["id"] = "stray_seg_0".into();
+    stray_departure["segments"][0]["departure"] = "2023-07-29T14:00:00".into();
+    input_data["departures"]
+        .as_array_mut()
+        .unwrap()
+        .push(stray_departure);
+    // maintenance_slot_0 lasts from 6:00 to 12:00
+    input_data["parameters"]["planningWindow"] = serde_json::json!({
+        "start": "2023-07-24T08:00:00",
+        "end": "2023-07-25T00:00:00"
+    });
+    let mut strict_input_data = input_data.clone();
+    strict_input_data["parameters"]["strictInput"] = true.into();
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+    let strict_result = try_load_rolling_stock_problem_instance_from_json(strict_input_data);
+
+    // ASSERT
+    assert_eq!(network.planning_days(), Duration::from_seconds(86400));
+    assert!(network
+        .all_service_nodes()
+        .all(|node| network.node(node).id() != "stray_seg_0"));
+    assert_eq!(network.all_service_nodes().count(), 4);
+    assert!(network
+        .warnings()
+        .iter()
+        .any(|w| w.code == "tripOutsidePlanningWindow" && w.message.contains("stray_seg_0")));
+
+    let maintenance_slot_0 = network
+        .maintenance_nodes()
+        .map(|node| network.node(node))
+        .find(|node| node.id() == "maintenance_slot_0")
+        .unwrap();
+    assert_eq!(
+        maintenance_slot_0.start_time(),
+        DateTime::new("2023-07-24T08:00:00")
+    );
+    assert_eq!(
+        maintenance_slot_0.end_time(),
+        DateTime::new("2023-07-24T12:00:00")
+    );
+    assert!(network
+        .warnings()
+        .iter()
+        .any(|w| w.code == "maintenanceSlotClipped"
+            && w.context == vec!["maintenance_slot_0".to_string()]));
+
+    // the small test input has trips without passengers, which are repaired as well
+    let errors = strict_result.err().unwrap();
+    assert!(errors
+        .iter()
+        .any(|error| error.code == "tripOutsidePlanningWindow"));
+}
+
+#[test]
+fn test_location_groups() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["locationGroups"] = serde_json::json!([
+        {"id": "BN_LU", "members": ["BN", "LU"], "transferDuration": 900}
+    ]);
+    let mut collapsed_input_data = input_data.clone();
+    collapsed_input_data["parameters"]["collapseLocationGroups"] = true.into();
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+    let collapsed_network = load_rolling_stock_problem_instance_from_json(collapsed_input_data);
+
+    // ASSERT
+    let locations = network.locations();
+    let zh = locations.get(LocationIdx::from(0)).unwrap();
+    let bn = locations.get(LocationIdx::from(1)).unwrap();
+    let lu = locations.get(LocationIdx::from(2)).unwrap();
+
+    // bidirectional mapping
+    assert!(locations.group_of(zh).is_none());
+    assert_eq!(locations.group_of(bn).unwrap().id(), "BN_LU");
+    assert_eq!(locations.group_of(lu).unwrap().id(), "BN_LU");
+    assert_eq!(locations.members_of("BN_LU").unwrap(), vec![bn, lu]);
+    assert!(locations.members_of("ZH").is_none());
+
+    // not collapsed: dead-head trips as given
+    assert!(!network.config().collapse_location_groups);
+    assert_travel_time(bn, lu, 400, locations);
+    assert_travel_time(zh, bn, 600, locations);
+    assert_travel_distance(bn, lu, 700, locations);
+
+    // collapsed: internal transfers within the group, maxima between groups
+    let collapsed_locations = collapsed_network.locations();
+    assert!(collapsed_network.config().collapse_location_groups);
+    assert_eq!(
+        collapsed_locations
+            .group_of(bn)
+            .unwrap()
+            .transfer_duration(),
+        Duration::from_seconds(900)
+    );
+    assert_travel_time(bn, lu, 900, collapsed_locations);
+    assert_travel_time(lu, bn, 900, collapsed_locations);
+    assert_travel_time(bn, bn, 0, collapsed_locations);
+    assert_travel_time(zh, bn, 600, collapsed_locations);
+    assert_travel_time(zh, lu, 600, collapsed_locations);
+    assert_travel_time(bn, zh, 6000, collapsed_locations);
+    assert_travel_time(lu, zh, 6000, collapsed_locations);
+    assert_travel_distance(bn, lu, 0, collapsed_locations);
+    assert_travel_distance(zh, lu, 1000, collapsed_locations);
+    assert_travel_distance(lu, zh, 10000, collapsed_locations);
+
+    // nodes keep the original locations
+    let trip_1a_seg_0 = collapsed_network
+        .all_service_nodes()
+        .find(|&node| collapsed_network.node(node).id() == "trip_1a_seg_0")
+        .unwrap();
+    let node = collapsed_network.node(trip_1a_seg_0);
+    assert_eq!(
+        collapsed_locations.get_id(node.start_location()).unwrap(),
+        "BN"
+    );
+    assert_eq!(
+        collapsed_locations.get_id(node.end_location()).unwrap(),
+        "LU"
+    );
+}
+
+#[test]
+fn test_invalid_location_groups() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["locationGroups"] = serde_json::json!([
+        {"id": "ZH_BN", "members": ["ZH", "BN"], "transferDuration": 600},
+        {"id": "BN_XY", "members": ["BN", "XY"], "transferDuration": 600}
+    ]);
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["locationInSeveralGroups", "unknownLocationGroupMember"]
+    );
+}
+
+#[test]
+fn test_time_spans_ending_before_they_start() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // maintenance_slot_0 starts at 6:00
+    input_data["maintenanceSlots"][0]["end"] = serde_json::json!("2023-07-24T05:00:00");
+    // trip_0_seg_0 arrives at 12:30
+    input_data["departures"][0]["segments"][1]["departure"] =
+        serde_json::json!("2023-07-24T12:20:00");
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["maintenanceSlotEndsBeforeStart", "departureBeforeArrival"]
+    );
+    assert!(errors[0].message.contains("maintenance_slot_0"));
+    assert!(errors[1].message.contains("trip_0_seg_1"));
+}
+
+#[test]
+fn test_out_of_service_windows() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // IR serves trip_0_seg_0 (12:00 - 12:30), maintenance_slot_0 (6:00 - 12:00) is usable by IC
+    input_data["vehicleTypes"][1]["outOfServiceWindows"] = serde_json::json!([
+        {"start": "2023-07-24T12:10:00", "end": "2023-07-24T12:20:00"}
+    ]);
+    input_data["vehicleTypes"][0]["outOfServiceWindows"] = serde_json::json!([
+        {"start": "2023-07-24T07:00:00", "end": "2023-07-24T08:00:00"}
+    ]);
+    let mut soft_input_data = input_data.clone();
+    soft_input_data["parameters"]["outOfService"] = serde_json::json!({"hard": false});
+    let mut input_data_without_conflicts = input_data.clone();
+    input_data_without_conflicts["vehicleTypes"][1]["outOfServiceWindows"] = serde_json::json!([]);
+
+    // ACT
+    let hard_result = try_load_rolling_stock_problem_instance_from_json(input_data);
+    let soft_network = load_rolling_stock_problem_instance_from_json(soft_input_data);
+    let hard_network = load_rolling_stock_problem_instance_from_json(input_data_without_conflicts);
+
+    // ASSERT
+    let errors = hard_result.err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "outOfServiceConflict");
+    assert!(errors[0].message.contains("1 of its service trips"));
+    assert!(errors[0].message.contains("trip_0_seg_0"));
+
+    assert!(soft_network
+        .warnings()
+        .iter()
+        .any(|warning| warning.code == "outOfServiceConflict"
+            && warning.context == vec!["trip_0_seg_0".to_string()]));
+    let conflicts = soft_network.out_of_service_conflicts();
+    let vt_ir = VehicleTypeIdx::from(1);
+    let conflicting_trips: Vec<&str> = conflicts
+        .iter()
+        .filter(|(vehicle_type, _, _)| *vehicle_type == vt_ir)
+        .flat_map(|(_, _, trips)| trips.iter().map(|&trip| soft_network.node(trip).id()))
+        .collect();
+    assert_eq!(conflicting_trips, vec!["trip_0_seg_0"]);
+    let maintenance_slot_0 = soft_network
+        .maintenance_nodes()
+        .find(|&node| soft_network.node(node).id() == "maintenance_slot_0")
+        .unwrap();
+    let vt_ic = VehicleTypeIdx::from(0);
+    assert!(soft_network
+        .out_of_service_window_of(maintenance_slot_0, vt_ic)
+        .is_some());
+    assert!(soft_network
+        .out_of_service_window_of(maintenance_slot_0, vt_ir)
+        .is_none());
+    // only reported in soft mode
+    assert!(soft_network.compatible_with_vehicle_type(maintenance_slot_0, vt_ic));
+    assert!(!hard_network.compatible_with_vehicle_type(maintenance_slot_0, vt_ic));
+    assert!(hard_network.compatible_with_vehicle_type(maintenance_slot_0, vt_ir));
+}
+
+#[test]
+fn test_allowed_split_points() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // route_0 runs ZH -> LU -> BN
+    input_data["routes"][0]["allowedSplitPoints"] = serde_json::json!(["LU"]);
+    let mut invalid_input_data = input_data.clone();
+    invalid_input_data["routes"][0]["allowedSplitPoints"] = serde_json::json!(["LU", "BN"]);
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+    let invalid_result = try_load_rolling_stock_problem_instance_from_json(invalid_input_data);
+
+    // ASSERT
+    let service_trip = |id: &str| {
+        network
+            .all_service_nodes()
+            .find(|&node| network.node(node).id() == id)
+            .unwrap()
+    };
+    let (before, after) = (service_trip("trip_0_seg_0"), service_trip("trip_0_seg_1"));
+    assert!(network.has_split_points());
+    assert_eq!(network.split_successor(before), Some(after));
+    assert_eq!(network.split_predecessor(after), Some(before));
+    assert_eq!(network.split_successor(after), None);
+    assert_eq!(
+        network.split_points().collect::<Vec<_>>(),
+        vec![(before, after)]
+    );
+
+    let errors = invalid_result.err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "invalidSplitPoint");
+    assert!(errors[0].message.contains("BN"));
+}
+
+#[test]
+fn test_instance_hash() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let reordered_input_data = reverse_key_order(&input_data);
+    let mut changed_parameters = input_data.clone();
+    changed_parameters["parameters"]["forbidDeadHeadTrips"] = serde_json::json!(true);
+    let mut changed_departure = input_data.clone();
+    changed_departure["departures"][0]["segments"][0]["passengers"] = serde_json::json!(1);
+
+    // ACT
+    let hash = instance_hash(&input_data);
+
+    // ASSERT
+    assert_eq!(hash.len(), 16);
+    assert_eq!(hash, instance_hash(&reordered_input_data));
+    assert_eq!(hash, instance_hash(&changed_parameters));
+    assert_ne!(hash, instance_hash(&changed_departure));
+    assert_eq!(
+        load_rolling_stock_problem_instance_from_json(input_data).instance_hash(),
+        hash
+    );
+}
+
+/// Copy of the json with the keys of all objects in reversed order.
+fn reverse_key_order(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => serde_json::Value::Object(
+            object
+                .iter()
+                .rev()
+                .map(|(key, value)| (key.clone(), reverse_key_order(value)))
+                .collect(),
+        ),
+        serde_json::Value::Array(array) => {
+            serde_json::Value::Array(array.iter().map(reverse_key_order).collect())
+        }
+        _ => value.clone(),
+    }
+}
+
+/// Sets capacity and seats of the vehicle types, passengers and seated of trip_0_seg_0 and the
+/// distance of route_0_seg_0 to the given values.
+fn input_with_values(
+    capacity: u64,
+    seats: u64,
+    passengers: u64,
+    seated: u64,
+    distance: u64,
+) -> serde_json::Value {
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["vehicleTypes"][0]["capacity"] = capacity.into();
+    input_data["vehicleTypes"][1]["seats"] = seats.into();
+    input_data["departures"][0]["segments"][0]["passengers"] = passengers.into();
+    input_data["departures"][0]["segments"][0]["seated"] = seated.into();
+    input_data["routes"][0]["segments"][0]["distance"] = distance.into();
+    input_data
+}
+
+#[test]
+fn test_value_ranges_at_boundaries() {
+    // ARRANGE
+    let capacity = MAX_VEHICLE_CAPACITY as u64;
+    let passengers = MAX_PASSENGERS as u64;
+    let at_maxima = input_with_values(
+        capacity,
+        capacity,
+        passengers,
+        passengers,
+        MAX_SEGMENT_DISTANCE,
+    );
+    let above_maxima = input_with_values(
+        capacity + 1,
+        capacity + 1,
+        passengers + 1,
+        passengers + 1,
+        MAX_SEGMENT_DISTANCE + 1,
+    );
+    let seated_above_maximum =
+        input_with_values(100, 50, passengers, passengers + 1, MAX_SEGMENT_DISTANCE);
+
+    // ACT
+    let at_maxima_result = try_load_rolling_stock_problem_instance_from_json(at_maxima);
+    let above_maxima_result = try_load_rolling_stock_problem_instance_from_json(above_maxima);
+    let seated_above_maximum_result =
+        try_load_rolling_stock_problem_instance_from_json(seated_above_maximum);
+
+    // ASSERT
+    let network = at_maxima_result.ok().unwrap();
+    assert_eq!(
+        network
+            .vehicle_types()
+            .get(VehicleTypeIdx::from(0))
+            .unwrap()
+            .capacity(),
+        MAX_VEHICLE_CAPACITY
+    );
+
+    let errors = above_maxima_result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec![
+            "vehicleCapacityTooLarge",
+            "vehicleCapacityTooLarge",
+            "routeSegmentDistanceTooLong",
+            "passengersTooLarge",
+        ]
+    );
+    assert!(errors[0].message.contains("IC"));
+    assert!(errors[1].message.contains("IR"));
+    assert!(errors[2].message.contains("route_0_seg_0"));
+    assert!(errors[3].message.contains("trip_0_seg_0"));
+
+    let errors = seated_above_maximum_result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["passengersTooLarge"]
+    );
+}
+
+#[test]
+fn test_route_segment_gap() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // route_0_seg_0 ends at LU
+    input_data["routes"][0]["segments"][1]["origin"] = serde_json::json!("ZH");
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["routeSegmentGap", "departureSegmentGap"]
+    );
+    assert_eq!(
+        errors[0].message,
+        "Route route_0: segment route_0_seg_0 ends at LU, but the next segment route_0_seg_1 \
+        starts at ZH (expected LU)."
+    );
+    assert_eq!(
+        errors[1].message,
+        "Departure trip_0 on route route_0: segment trip_0_seg_0 ends at LU, but the next segment \
+        trip_0_seg_1 starts at ZH (expected LU)."
+    );
+}
+
+#[test]
+fn test_route_segment_overlap() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["routes"][0]["segments"][1]["order"] = 0.into();
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["routeSegmentOverlap"]
+    );
+    assert_eq!(
+        errors[0].message,
+        "Route route_0: segments route_0_seg_0 and route_0_seg_1 have the same order 0."
+    );
+}
+
+#[test]
+fn test_unknown_references() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["departures"][0]["route"] = serde_json::json!("route_5");
+    input_data["depots"][0]["location"] = serde_json::json!("GE");
+    input_data["deadHeadTrips"]["indices"][0] = serde_json::json!("GE");
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert!(errors.iter().all(|error| error.code == "unknownReference"));
+    let messages: Vec<&str> = errors.iter().map(|error| error.message.as_str()).collect();
+    assert!(messages.contains(&"Departure trip_0 references unknown route route_5."));
+    assert!(messages.contains(&"deadHeadTrips references unknown location GE."));
+    assert_eq!(messages.len(), 3);
+}
+
+#[test]
+fn test_duplicate_ids() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["departures"][0]["segments"][1]["id"] = serde_json::json!("trip_0_seg_0");
+    let location = input_data["locations"][0].clone();
+    input_data["locations"]
+        .as_array_mut()
+        .unwrap()
+        .push(location.clone());
+    input_data["locations"]
+        .as_array_mut()
+        .unwrap()
+        .push(location);
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["duplicateId", "duplicateId"]
+    );
+    assert_eq!(
+        errors[1].message,
+        "Departure segment trip_0_seg_0 is defined more than once."
+    );
+}
+
+#[test]
+fn test_dead_head_matrix_not_square() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["deadHeadTrips"]["durations"][1]
+        .as_array_mut()
+        .unwrap()
+        .pop();
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["deadHeadMatrixNotSquare"]
+    );
+    assert!(errors[0]
+        .message
+        .starts_with("deadHeadTrips.durations must be a 3x3 matrix"));
+}
+
+#[test]
+fn test_malformed_input() {
+    // ARRANGE
+    let input_data = serde_json::json!({"vehicleTypes": "IC"});
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["malformedInput"]
+    );
+}
+
+#[test]
+fn test_invalid_parameters() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["timePartitioning"] =
+        serde_json::json!({"windowLength": 3600, "overlap": 3600});
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["invalidParameter"]
+    );
+    assert_eq!(
+        errors[0].message,
+        "The overlap of the time partitioning must be smaller than the window length."
+    );
+}
+
+#[test]
+fn test_invalid_parameters_are_collected() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["milestones"] = serde_json::json!([0, 60]);
+    input_data["parameters"]["webhook"] = serde_json::json!({"url": "ftp://example.org"});
+    input_data["parameters"]["adaptiveOperatorWeights"] = serde_json::json!({"minimalShare": 0.0});
+    input_data["parameters"]["output"] =
+        serde_json::json!({"crowdingThresholds": {"moderate": 0.9, "high": 0.8}});
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "Milestones must be positive.",
+            "The url of the webhook must start with http:// or https://.",
+            "The shares of the adaptive operator weights must satisfy 0 < minimalShare <= \
+            maximalShare <= 1.",
+            "The crowding thresholds must satisfy 0 <= moderate <= high.",
+        ]
+    );
+    assert!(errors.iter().all(|error| error.code == "invalidParameter"));
+}
+
+#[test]
+fn test_invalid_repetitions() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["departures"][0]["headwayRepetitions"] =
+        serde_json::json!({"count": 0, "interval": 1800});
+    input_data["maintenanceSlots"][0]["recurrence"] =
+        serde_json::json!({"frequency": "daily", "count": 0});
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+        vec!["invalidRepetition", "invalidRepetition"]
+    );
+    assert_eq!(
+        errors[0].message,
+        "headwayRepetitions of departure trip_0 need a positive count and interval."
+    );
+}
+
+#[test]
+fn test_split_broken_departures() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["splitBrokenDepartures"] = true.into();
+    let mut gap_input_data = input_data.clone();
+    gap_input_data["routes"][0]["segments"][1]["origin"] = serde_json::json!("ZH");
+    let mut time_input_data = input_data.clone();
+    // trip_0_seg_0 arrives at 12:30
+    time_input_data["departures"][0]["segments"][1]["departure"] =
+        serde_json::json!("2023-07-24T12:20:00");
+
+    // ACT
+    let gap_network = try_load_rolling_stock_problem_instance_from_json(gap_input_data).unwrap();
+    let time_network = try_load_rolling_stock_problem_instance_from_json(time_input_data).unwrap();
+
+    // ASSERT
+    assert_eq!(gap_network.all_service_nodes().count(), 4);
+    assert!(gap_network
+        .warnings()
+        .iter()
+        .any(|warning| warning.code == "routeSegmentGap"
+            && warning
+                .message
+                .starts_with("Route route_0: segment route_0_seg_0 ends at LU")));
+    assert!(gap_network
+        .warnings()
+        .iter()
+        .any(|warning| warning.code == "departureSegmentGap"
+            && warning.message.starts_with(
+                "Departure trip_0 on route route_0: segment trip_0_seg_0 ends at LU"
+            )
+            && warning.message.ends_with("The departure is split.")));
+
+    assert_eq!(time_network.all_service_nodes().count(), 4);
+    assert!(time_network
+        .warnings()
+        .iter()
+        .any(|warning| warning.code == "departureBeforeArrival"
+            && warning.message.starts_with(
+                "Segment trip_0_seg_1 of departure trip_0 departs before the previous segment \
+            trip_0_seg_0 arrives"
+            )
+            && warning.message.contains("actual 2023-07-24T12:20:00")));
+}
+
+#[test]
+fn test_checked_duration_of_trip_arriving_before_departure() {
+    // ARRANGE
+    let service_trip = Node::create_service_trip(
+        String::from("backwards_trip"),
+        String::from("route"),
+        VehicleTypeIdx::from(0),
+        Location::Nowhere,
+        Location::Nowhere,
+        DateTime::new("2023-07-24T12:00:00"),
+        DateTime::new("2023-07-24T11:00:00"),
+        Distance::ZERO,
+        0,
+        0,
+        None,
+        None,
+        None,
+        None,
+    );
+    let node = Node::create_service_trip_node(0, service_trip);
+
+    // ACT
+    let result = node.checked_duration();
+
+    // ASSERT
+    let error = result.err().unwrap();
+    assert!(error.contains("arrival before departure on trip backwards_trip"));
+}
+
+#[test]
+fn test_spawn_and_despawn_capacities() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["depots"][0]["despawnCapacity"] = serde_json::json!(2);
+    input_data["depots"][1]["allowedTypes"][0] =
+        serde_json::json!({"vehicleType": "IC", "capacity": 100, "spawnCapacity": 50});
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let (depot_zh, depot_bn) = (DepotIdx::from(0), DepotIdx::from(1));
+    let ic = VehicleTypeIdx::from(0);
+    assert_eq!(network.total_capacity_of(depot_zh), 5);
+    assert_eq!(network.total_despawn_capacity_of(depot_zh), 2);
+    assert_eq!(network.capacity_of(depot_zh, ic), 5);
+    assert_eq!(network.despawn_capacity_of(depot_zh, ic), 2);
+    assert_eq!(network.capacity_of(depot_bn, ic), 50);
+    assert_eq!(network.despawn_capacity_of(depot_bn, ic), 100);
+}
+
+#[test]
+fn test_mutually_unreachable_service_trips() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let network_with_short_shunting =
+        load_rolling_stock_problem_instance_from_json(input_data.clone());
+    // shunting takes 30 days, so no trip can be followed by another one
+    input_data["parameters"]["shunting"] =
+        serde_json::json!({"minimalDuration": 2592000, "deadHeadTripDuration": 2592000});
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    assert!(!network_with_short_shunting.service_trips_mutually_unreachable());
+    assert!(network.service_trips_mutually_unreachable());
+    assert!(network
+        .warnings()
+        .iter()
+        .any(|w| w.code == "mutuallyUnreachableServiceTrips"));
+}
+
+#[test]
+fn test_headway_repetitions() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let network_without_repetitions =
+        load_rolling_stock_problem_instance_from_json(input_data.clone());
+    // trip_1a departs at 12:00, repeated at 12:30 and 13:00
+    input_data["departures"][1]["headwayRepetitions"] =
+        serde_json::json!({"count": 3, "interval": 1800});
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    assert_eq!(
+        network.number_of_service_nodes(),
+        network_without_repetitions.number_of_service_nodes() + 2
+    );
+    let trip_families: Vec<_> = network.trip_families().collect();
+    assert_eq!(trip_families.len(), 1);
+    let (family, trip_family) = trip_families[0];
+    assert_eq!(family, "trip_1a");
+    assert_eq!(trip_family.interval(), Duration::from_seconds(1800));
+    assert_eq!(trip_family.number_of_repetitions(), 3);
+    for (repetition, (service_trips, (expected_id, expected_departure))) in trip_family
+        .repetitions()
+        .zip([
+            ("trip_1a_seg_0", "2023-07-24T12:00:00"),
+            ("trip_1a_seg_0_1", "2023-07-24T12:30:00"),
+            ("trip_1a_seg_0_2", "2023-07-24T13:00:00"),
+        ])
+        .enumerate()
+    {
+        assert_eq!(service_trips.len(), 1);
+        let node = network.node(service_trips[0]);
+        assert_eq!(node.id(), expected_id);
+        assert_eq!(node.start_time(), DateTime::new(expected_departure));
+        assert_eq!(
+            node.as_service_trip().trip_family(),
+            Some((&String::from("trip_1a"), repetition as u32))
+        );
+    }
+    assert_eq!(network_without_repetitions.trip_families().count(), 0);
+}
+
+#[test]
+fn test_minimal_number_of_vehicles() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // trip_0_seg_0 (IR, capacity 80, formation limit 1) with 500 passengers
+    input_data["departures"][0]["segments"][0]["passengers"] = serde_json::json!(500);
+    // trip_0_seg_1 (minimal formation count 2) with only 10 passengers
+    input_data["routes"][0]["segments"][1]["minimalFormationCount"] = serde_json::json!(2);
+    input_data["departures"][0]["segments"][1]["passengers"] = serde_json::json!(10);
+    input_data["departures"][0]["segments"][1]["seated"] = serde_json::json!(10);
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let service_trip = |id: &str| {
+        network
+            .all_service_nodes()
+            .find(|&n| network.node(n).id() == id)
+            .unwrap()
+    };
+    let ir = VehicleTypeIdx::from(1);
+    let ic = VehicleTypeIdx::from(0);
+
+    let overcrowded = service_trip("trip_0_seg_0");
+    assert_eq!(
+        network.number_of_vehicles_required_to_serve(ir, overcrowded),
+        7
+    );
+    assert_eq!(network.minimal_number_of_vehicles_for(overcrowded), 1);
+    assert!(network.is_structurally_unservable(overcrowded));
+
+    let small_demand = service_trip("trip_0_seg_1");
+    assert_eq!(
+        network.number_of_vehicles_required_to_serve(ir, small_demand),
+        1
+    );
+    assert_eq!(network.minimal_number_of_vehicles_for(small_demand), 2);
+    assert!(!network.is_structurally_unservable(small_demand));
+
+    // 80 seated passengers on IC (50 seats)
+    let seated_demand = service_trip("trip_1a_seg_0");
+    assert_eq!(
+        network.number_of_vehicles_required_to_serve(ic, seated_demand),
+        2
+    );
+    assert_eq!(network.minimal_number_of_vehicles_for(seated_demand), 2);
+    assert!(!network.is_structurally_unservable(seated_demand));
+
+    // without passengers, the loader repairs the demand to one passenger
+    let no_demand = service_trip("trip_1b_seg_0");
+    assert_eq!(network.minimal_number_of_vehicles_for(no_demand), 1);
+}
+
+#[test]
+fn test_perturb_instance() {
+    // ARRANGE
+    let input_data = read_json("resources/small_test_input.json");
+    let config = PerturbationConfig {
+        shift_fraction: 1.0,
+        maximal_shift: Duration::from_seconds(600),
+        demand_deviation: 0.2,
+        drop_fraction: 0.0,
+        add_fraction: 0.5,
+    };
+
+    // ACT
+    let perturbed = perturb_instance(input_data.clone(), &config, 42);
+    let perturbed_again = perturb_instance(input_data.clone(), &config, 42);
+
+    // ASSERT
+    assert_eq!(perturbed, perturbed_again);
+
+    let departures = perturbed["departures"].as_array().unwrap();
+    assert_eq!(departures.len(), 3 + 2);
+    // existing ids are kept and come first
+    for (original, departure) in input_data["departures"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .zip(departures)
+    {
+        assert_eq!(original["id"], departure["id"]);
+        // all segments of a departure are shifted by the same offset (at most 10 minutes)
+        let shifts: Vec<Duration> = original["segments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .zip(departure["segments"].as_array().unwrap())
+            .map(|(s1, s2)| {
+                let t1 = DateTime::new(s1["departure"].as_str().unwrap());
+                let t2 = DateTime::new(s2["departure"].as_str().unwrap());
+                if t1 <= t2 {
+                    t2 - t1
+                } else {
+                    t1 - t2
+                }
+            })
+            .collect();
+        assert!(shifts.iter().all(|&shift| shift == shifts[0]));
+        assert!(shifts[0] <= Duration::from_seconds(600));
+
+        for segment in departure["segments"].as_array().unwrap() {
+            assert!(segment["seated"].as_u64() <= segment["passengers"].as_u64());
+        }
+    }
+    assert!(departures[3]["id"].as_str().unwrap().contains("_added_0"));
+    assert!(departures[4]["id"].as_str().unwrap().contains("_added_1"));
+
+    // the perturbed instance is still valid
+    let number_of_segments: usize = departures
+        .iter()
+        .map(|d| d["segments"].as_array().unwrap().len())
+        .sum();
+    let network = load_rolling_stock_problem_instance_from_json(perturbed);
+    assert_eq!(network.all_service_nodes().count(), number_of_segments);
+}
+
+#[test]
+fn test_active_vehicle_types() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let network_without_restriction =
+        load_rolling_stock_problem_instance_from_json(input_data.clone());
+    input_data["parameters"]["activeVehicleTypes"] = serde_json::json!(["IR"]);
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let vt_ic = VehicleTypeIdx::from(0);
+    let vt_ir = VehicleTypeIdx::from(1);
+    assert!(!network.config().is_active_vehicle_type(vt_ic));
+    assert!(network.config().is_active_vehicle_type(vt_ir));
+    assert!(network_without_restriction
+        .config()
+        .is_active_vehicle_type(vt_ic));
+    assert!(network_without_restriction
+        .config()
+        .is_active_vehicle_type(vt_ir));
+}
+
+#[test]
+fn test_warnings_of_maintenance_coverage_and_planning_duration() {
+    // ARRANGE
+    let input_data = read_json("resources/small_test_input.json");
+    let mut low_coverage = input_data.clone();
+    low_coverage["parameters"]["maintenance"]["maximalDistance"] = serde_json::json!(1);
+    let mut long_duration = input_data.clone();
+    long_duration["maintenanceSlots"]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!({
+            "id": "maintenance_slot_late",
+            "location": "ZH",
+            "start": "2023-08-03T06:00:00",
+            "end": "2023-08-03T12:00:00",
+            "trackCount": 1
+        }));
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+    let low_coverage_network = load_rolling_stock_problem_instance_from_json(low_coverage);
+    let long_duration_network = load_rolling_stock_problem_instance_from_json(long_duration);
+
+    // ASSERT
+    let codes = |network: &Network| -> Vec<&'static str> {
+        network.warnings().iter().map(|w| w.code).collect()
+    };
+    assert!(!codes(&network).contains(&"insufficientMaintenanceCoverage"));
+    assert!(!codes(&network).contains(&"longPlanningDuration"));
+    let warning = low_coverage_network
+        .warnings()
+        .iter()
+        .find(|w| w.code == "insufficientMaintenanceCoverage")
+        .unwrap();
+    assert_eq!(warning.severity, Severity::Warning);
+    let warning = long_duration_network
+        .warnings()
+        .iter()
+        .find(|w| w.code == "longPlanningDuration")
+        .unwrap();
+    assert_eq!(warning.severity, Severity::Info);
+    assert!(warning.message.contains("11 days"));
+}
+
+#[test]
+fn test_vehicle_type_dependent_shunting() {
+    // ARRANGE
+    // trip_0_seg_0 (IR) arrives at LU at 12:30, trip_0_seg_1 departs from LU at 12:40
+    let input_data = read_json("resources/small_test_input.json");
+    let with_ir_shunting = |minimal_duration: u64| {
+        let mut input_data = input_data.clone();
+        input_data["vehicleTypes"][1]["shunting"] =
+            serde_json::json!({ "minimalDuration": minimal_duration });
+        input_data
+    };
+
+    // ACT
+    let short_unit_network = load_rolling_stock_problem_instance_from_json(with_ir_shunting(180));
+    let long_unit_network = load_rolling_stock_problem_instance_from_json(with_ir_shunting(720));
+
+    // ASSERT
+    let vt_ic = VehicleTypeIdx::from(0);
+    let vt_ir = VehicleTypeIdx::from(1);
+    let node_of = |network: &Network, id: &str| {
+        network
+            .all_service_nodes()
+            .find(|&node| network.node(node).id() == id)
+            .unwrap()
+    };
+    let can_connect = |network: &Network| {
+        network.can_reach(
+            node_of(network, "trip_0_seg_0"),
+            node_of(network, "trip_0_seg_1"),
+        )
+    };
+    assert!(can_connect(&short_unit_network));
+    assert!(!can_connect(&long_unit_network));
+    assert_eq!(
+        long_unit_network.shunting_of(vt_ir).minimal,
+        Duration::from_seconds(720)
+    );
+    // the other type and the dead-head shunting keep the global durations
+    assert_eq!(
+        long_unit_network.shunting_of(vt_ic).minimal,
+        Duration::from_seconds(120)
+    );
+    assert_eq!(
+        long_unit_network.shunting_of(vt_ir).dead_head_trip,
+        Duration::from_seconds(300)
+    );
+}
+
+fn read_json(path: &str) -> serde_json::Value {
+    let mut file = File::open(path).unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    serde_json::from_str(&input_data).unwrap()
+}
+
+fn test_load_from_json(path: &str) {
+    // ACT
+    let input_data = read_json(path);
 
     let network = load_rolling_stock_problem_instance_from_json(input_data);
     let vehicle_types = network.vehicle_types();
@@ -118,6 +1626,7 @@ fn test_load_from_json(path: &str) {
             6,
             Node::create_service_trip(
                 String::from("trip_1a_seg_0"),
+                String::from("route_1"),
                 vt0,
                 loc2,
                 loc3,
@@ -126,6 +1635,9 @@ fn test_load_from_json(path: &str) {
                 Distance::from_meter(2000),
                 80,
                 80,
+                None,
+                None,
+                None,
                 None
             )
         )
@@ -137,6 +1649,7 @@ fn test_load_from_json(path: &str) {
             7,
             Node::create_service_trip(
                 String::from("trip_1b_seg_0"),
+                String::from("route_1"),
                 vt0,
                 loc2,
                 loc3,
@@ -145,6 +1658,9 @@ fn test_load_from_json(path: &str) {
                 Distance::from_meter(2000),
                 1,
                 0,
+                None,
+                None,
+                None,
                 None
             )
         )
@@ -156,6 +1672,7 @@ fn test_load_from_json(path: &str) {
             8,
             Node::create_service_trip(
                 String::from("trip_0_seg_0"),
+                String::from("route_0"),
                 vt1,
                 loc0,
                 loc3,
@@ -164,11 +1681,23 @@ fn test_load_from_json(path: &str) {
                 Distance::from_meter(600),
                 50,
                 40,
-                Some(1)
+                Some(1),
+                None,
+                None,
+                None
             )
         )
     );
 
+    assert_eq!(
+        network.minimal_formation_count_for(NodeIdx::service_from(8)),
+        None
+    );
+    assert_eq!(
+        network.minimal_formation_count_for(NodeIdx::service_from(9)),
+        None
+    );
+
     assert_eq!(
         *network.node(NodeIdx::maintenance_from(10)),
         Node::create_maintenance_node(
@@ -230,6 +1759,7 @@ fn test_load_from_json(path: &str) {
     assert_eq!(config.costs.maintenance, 0);
     assert_eq!(config.costs.dead_head_trip, 500);
     assert_eq!(config.costs.idle, 20);
+    assert_eq!(config.costs.line_purity, 0);
 }
 
 fn assert_travel_time(from: Location, to: Location, expected: u64, locations: &Locations) {
@@ -253,3 +1783,94 @@ fn assert_travel_distance(from: Location, to: Location, expected: u64, locations
         expected
     );
 }
+
+#[test]
+fn test_staff_time_factors_with_window_wrapping_midnight() {
+    // ARRANGE
+    // deadHeadTrip costs are 500 per second
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["costs"]["staffTimeFactors"] = serde_json::json!([
+        { "window": { "start": 79200, "end": 18000 }, "factor": 1.5 }, // 22:00 - 05:00
+    ]);
+    let hour = 3600;
+    let day = 24 * hour;
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let costs = &network.config().costs;
+    let factor = costs.staff_time_factors[0];
+    // before, after and within the window
+    assert_eq!(factor.overlap(day + 12 * hour, 2 * hour), 0);
+    assert_eq!(factor.overlap(day + 23 * hour, hour), hour);
+    // across the start, across midnight and across the end of the window
+    assert_eq!(factor.overlap(day + 21 * hour, 2 * hour), hour);
+    assert_eq!(factor.overlap(day + 23 * hour, 2 * hour), 2 * hour);
+    assert_eq!(factor.overlap(day + 4 * hour, 2 * hour), hour);
+    // the window opened on the day before (also on the very first day)
+    assert_eq!(factor.overlap(2 * hour, hour), hour);
+    // spanning the whole window and more
+    assert_eq!(factor.overlap(day + 20 * hour, 10 * hour), 7 * hour);
+    assert_eq!(factor.overlap(day + 4 * hour, 2 * day), 14 * hour);
+
+    assert_eq!(
+        costs.staff_time_surcharge(day + 21 * hour, 2 * hour),
+        900000
+    );
+    assert_eq!(costs.staff_time_surcharge(day + 12 * hour, 2 * hour), 0);
+    assert_eq!(costs.maximal_staff_time_factor(), 1.5);
+}
+
+#[test]
+fn test_overlapping_staff_time_factors_add_up() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["costs"]["staffTimeFactors"] = serde_json::json!([
+        { "window": { "start": 0, "end": 86400 }, "factor": 1.1 }, // the whole day
+        { "window": { "start": 21600, "end": 25200 }, "factor": 2.0 }, // 06:00 - 07:00
+    ]);
+
+    // ACT
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let costs = &network.config().costs;
+    // 06:30 - 07:30: 3600 seconds * 0.1 + 1800 seconds * 1.0, times 500
+    assert_eq!(costs.staff_time_surcharge(23400, 3600), 1080000);
+    assert!((costs.maximal_staff_time_factor() - 2.1).abs() < 1e-9);
+}
+
+#[test]
+fn test_invalid_staff_time_factors() {
+    // ARRANGE
+    let mut file = File::open("resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["costs"]["staffTimeFactors"] = serde_json::json!([
+        { "window": { "start": 3600, "end": 3600 }, "factor": 0.5 },
+    ]);
+
+    // ACT
+    let result = try_load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ASSERT
+    let errors = result.err().unwrap();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| e.message.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "The window of staff time factors must not be empty.",
+            "The factor of staff time factors must be at least 1.",
+        ]
+    );
+}
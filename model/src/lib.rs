@@ -17,3 +17,4 @@ pub mod json_serialisation;
 pub mod locations;
 pub mod network;
 pub mod vehicle_types;
+pub mod warnings;
@@ -13,10 +13,10 @@
 
 use std::collections::HashMap;
 
-use rapid_time::Duration;
+use rapid_time::{DateTime, Duration};
 
-use crate::base_types::VehicleCount;
-use crate::base_types::{Distance, Location, LocationIdx};
+use crate::base_types::{Cost, Distance, Location, LocationIdx};
+use crate::base_types::{VehicleCount, VehicleTypeIdx};
 
 /// a type for storing the pair-wise distances and travel times between all stations.
 /// Distances are stored as a Vec<Vec<Distance>>-matrix.
@@ -35,6 +35,13 @@ use crate::base_types::{Distance, Location, LocationIdx};
 pub struct Locations {
     stations: HashMap<LocationIdx, (String, Option<VehicleCount>)>, // values: (id, daylimit)
     dead_head_trips: HashMap<LocationIdx, HashMap<LocationIdx, DeadHeadTrip>>,
+    allowed_vehicle_types: HashMap<LocationIdx, Vec<VehicleTypeIdx>>, // only for restricted stations
+    dead_head_time_factors: Vec<DeadHeadTimeFactor>,
+    location_groups: Vec<LocationGroup>,
+    group_of: HashMap<LocationIdx, usize>, // member -> index in location_groups
+    platform_idle_limits: HashMap<LocationIdx, Duration>,
+    sidings: Vec<LocationIdx>,
+    nearest_sidings: HashMap<LocationIdx, (LocationIdx, Duration)>, // only for limited locations
 }
 
 pub struct DeadHeadTrip {
@@ -51,6 +58,98 @@ impl DeadHeadTrip {
     }
 }
 
+/// Time-dependent penalty for dead-head trips from one set of stations to another (e.g. through a
+/// congested corridor during peak hours). It applies to a dead-head trip that departs within
+/// [start, end): its travel time is multiplied by duration_factor and extra_cost is added to its
+/// costs.
+pub struct DeadHeadTimeFactor {
+    origins: Vec<LocationIdx>,
+    destinations: Vec<LocationIdx>,
+    start: DateTime,
+    end: DateTime,
+    duration_factor: f64,
+    extra_cost: Cost,
+}
+
+impl DeadHeadTimeFactor {
+    pub fn new(
+        origins: Vec<LocationIdx>,
+        destinations: Vec<LocationIdx>,
+        start: DateTime,
+        end: DateTime,
+        duration_factor: f64,
+        extra_cost: Cost,
+    ) -> DeadHeadTimeFactor {
+        DeadHeadTimeFactor {
+            origins,
+            destinations,
+            start,
+            end,
+            duration_factor,
+            extra_cost,
+        }
+    }
+
+    fn applies_to(&self, a: LocationIdx, b: LocationIdx, departure: DateTime) -> bool {
+        self.start <= departure
+            && departure < self.end
+            && self.origins.contains(&a)
+            && self.destinations.contains(&b)
+    }
+}
+
+/// A station with several yards (the members) that are connected by internal transfers of the
+/// given duration.
+/// If the groups are collapsed, all members behave like one location during the optimization:
+/// dead-head trips between two members take the transfer duration (without distance) and
+/// dead-head trips between different groups take the maximal duration and distance of all
+/// member pairs. The nodes keep the original member locations, so the output still shows the
+/// original location ids.
+pub struct LocationGroup {
+    id: String,
+    members: Vec<LocationIdx>,
+    transfer_duration: Duration,
+}
+
+impl LocationGroup {
+    pub fn new(
+        id: String,
+        members: Vec<LocationIdx>,
+        transfer_duration: Duration,
+    ) -> LocationGroup {
+        LocationGroup {
+            id,
+            members,
+            transfer_duration,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn members(&self) -> &[LocationIdx] {
+        &self.members
+    }
+
+    pub fn transfer_duration(&self) -> Duration {
+        self.transfer_duration
+    }
+}
+
+/// Maximal idle times at platforms of some locations and the sidings that a vehicle exceeding
+/// such a limit is moved to.
+pub struct PlatformIdleLimits {
+    limits: HashMap<LocationIdx, Duration>,
+    sidings: Vec<LocationIdx>,
+}
+
+impl PlatformIdleLimits {
+    pub fn new(limits: HashMap<LocationIdx, Duration>, sidings: Vec<LocationIdx>) -> Self {
+        PlatformIdleLimits { limits, sidings }
+    }
+}
+
 /////////////////////////////////////////////////////////////////////
 ////////////////////////////// Locations ////////////////////////////
 /////////////////////////////////////////////////////////////////////
@@ -60,11 +159,57 @@ impl Locations {
     pub fn new(
         stations: HashMap<LocationIdx, (String, Option<VehicleCount>)>,
         dead_head_trips: HashMap<LocationIdx, HashMap<LocationIdx, DeadHeadTrip>>,
+        allowed_vehicle_types: HashMap<LocationIdx, Vec<VehicleTypeIdx>>,
+        dead_head_time_factors: Vec<DeadHeadTimeFactor>,
+        location_groups: Vec<LocationGroup>,
+        collapse_location_groups: bool,
+        platform_idle_limits: PlatformIdleLimits,
     ) -> Locations {
-        Locations {
+        // consecutive activities at the same location are connected without dead-head trip
+        assert!(
+            dead_head_trips
+                .iter()
+                .all(|(station, destinations)| match destinations.get(station) {
+                    Some(trip) =>
+                        trip.travel_time == Duration::ZERO && trip.distance == Distance::ZERO,
+                    None => true,
+                }),
+            "Dead-head trips from a location to itself must have zero duration and distance."
+        );
+        let group_of = location_groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group, location_group)| {
+                location_group
+                    .members
+                    .iter()
+                    .map(move |&member| (member, group))
+            })
+            .collect();
+        let mut locations = Locations {
             stations,
             dead_head_trips,
+            allowed_vehicle_types,
+            dead_head_time_factors,
+            location_groups,
+            group_of,
+            platform_idle_limits: platform_idle_limits.limits,
+            sidings: platform_idle_limits.sidings,
+            nearest_sidings: HashMap::new(),
+        };
+        if collapse_location_groups {
+            locations.collapse_location_groups();
         }
+        locations.nearest_sidings = locations
+            .platform_idle_limits
+            .keys()
+            .filter_map(|&location| {
+                locations
+                    .nearest_siding_of(location)
+                    .map(|nearest_siding| (location, nearest_siding))
+            })
+            .collect();
+        locations
     }
 }
 
@@ -94,10 +239,106 @@ impl Locations {
         }
     }
 
+    /// stations without a whitelist of vehicle types admit all types (as does Nowhere).
+    pub fn is_vehicle_type_allowed_at(
+        &self,
+        location: Location,
+        vehicle_type: VehicleTypeIdx,
+    ) -> bool {
+        match location {
+            Location::Nowhere => true,
+            Location::Station(idx) => self
+                .allowed_vehicle_types
+                .get(&idx)
+                .is_none_or(|allowed| allowed.contains(&vehicle_type)),
+        }
+    }
+
+    /// the maximal time a vehicle may idle at a platform of the location (None for Nowhere and
+    /// locations without limit).
+    pub fn platform_idle_limit(&self, location: Location) -> Option<Duration> {
+        match location {
+            Location::Nowhere => None,
+            Location::Station(idx) => self.platform_idle_limits.get(&idx).copied(),
+        }
+    }
+
+    pub fn has_platform_idle_limits(&self) -> bool {
+        !self.platform_idle_limits.is_empty()
+    }
+
+    pub fn is_siding(&self, location: Location) -> bool {
+        match location {
+            Location::Nowhere => false,
+            Location::Station(idx) => self.sidings.contains(&idx),
+        }
+    }
+
+    /// the siding with the shortest round trip (there and back, without time factors) from a
+    /// location with a platform idle limit, together with the duration of the round trip. None if
+    /// the location has no limit or no siding can be reached.
+    pub fn nearest_siding(&self, location: Location) -> Option<(Location, Duration)> {
+        match location {
+            Location::Nowhere => None,
+            Location::Station(idx) => self
+                .nearest_sidings
+                .get(&idx)
+                .map(|&(siding, round_trip)| (Location::Station(siding), round_trip)),
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = Location> + '_ {
         self.stations.keys().map(|idx| Location::Station(*idx))
     }
 
+    /// stations without a dead-head trip to or from some other station (i.e., the dead-head
+    /// matrix of the input does not cover them).
+    pub fn stations_with_missing_dead_head_trips(&self) -> Vec<Location> {
+        let has_dead_head_trip = |a: &LocationIdx, b: &LocationIdx| {
+            self.dead_head_trips
+                .get(a)
+                .is_some_and(|destinations| destinations.contains_key(b))
+        };
+        self.stations
+            .keys()
+            .filter(|a| {
+                self.stations
+                    .keys()
+                    .any(|b| !has_dead_head_trip(a, b) || !has_dead_head_trip(b, a))
+            })
+            .map(|idx| Location::Station(*idx))
+            .collect()
+    }
+
+    pub fn location_groups(&self) -> impl Iterator<Item = &LocationGroup> + '_ {
+        self.location_groups.iter()
+    }
+
+    /// the group the location is a member of (None for Nowhere and locations without group).
+    pub fn group_of(&self, location: Location) -> Option<&LocationGroup> {
+        match location {
+            Location::Nowhere => None,
+            Location::Station(idx) => self
+                .group_of
+                .get(&idx)
+                .map(|&group| &self.location_groups[group]),
+        }
+    }
+
+    /// the member locations of the group with the given id (None if there is no such group).
+    pub fn members_of(&self, group_id: &str) -> Option<Vec<Location>> {
+        self.location_groups
+            .iter()
+            .find(|location_group| location_group.id == group_id)
+            .map(|location_group| {
+                location_group
+                    .members
+                    .iter()
+                    .map(|&member| Location::Station(member))
+                    .collect()
+            })
+    }
+
     pub fn distance(&self, a: Location, b: Location) -> Distance {
         match self.get_dead_head_trip(a, b) {
             Some(d) => d.distance,
@@ -124,6 +365,135 @@ impl Locations {
         }
     }
 
+    /// travel time of a dead-head trip departing at the given time. If several time factors apply,
+    /// the first one is taken.
+    pub fn travel_time_departing_at(
+        &self,
+        a: Location,
+        b: Location,
+        departure: DateTime,
+    ) -> Duration {
+        let travel_time = self.travel_time(a, b);
+        match self.dead_head_time_factor(a, b, departure) {
+            Some(factor) => Duration::from_seconds(
+                (travel_time.in_sec().unwrap() as f64 * factor.duration_factor).round() as u64,
+            ),
+            None => travel_time,
+        }
+    }
+
+    /// extra costs of a dead-head trip departing at the given time (0 if no time factor applies).
+    pub fn extra_cost_departing_at(&self, a: Location, b: Location, departure: DateTime) -> Cost {
+        self.dead_head_time_factor(a, b, departure)
+            .map_or(0, |factor| factor.extra_cost)
+    }
+
+    /// the largest duration factor of all time factors (at least 1.0).
+    pub fn maximal_duration_factor(&self) -> f64 {
+        self.dead_head_time_factors
+            .iter()
+            .map(|factor| factor.duration_factor)
+            .fold(1.0, f64::max)
+    }
+
+    /// the largest extra cost of all time factors (0 if there are none).
+    pub fn maximal_extra_cost(&self) -> Cost {
+        self.dead_head_time_factors
+            .iter()
+            .map(|factor| factor.extra_cost)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn dead_head_time_factor(
+        &self,
+        a: Location,
+        b: Location,
+        departure: DateTime,
+    ) -> Option<&DeadHeadTimeFactor> {
+        match (a, b) {
+            (Location::Station(station_a), Location::Station(station_b))
+                if station_a != station_b =>
+            {
+                self.dead_head_time_factors
+                    .iter()
+                    .find(|factor| factor.applies_to(station_a, station_b, departure))
+            }
+            _ => None,
+        }
+    }
+
+    /// Replaces the dead-head trips between members of the same group by the internal transfer
+    /// and the dead-head trips between different groups by the maximum over all member pairs (a
+    /// location without group forms a group on its own). Hence, the schedule stays feasible for
+    /// each member of the group.
+    fn collapse_location_groups(&mut self) {
+        let collapsed: HashMap<LocationIdx, HashMap<LocationIdx, DeadHeadTrip>> = self
+            .dead_head_trips
+            .iter()
+            .map(|(&a, destinations)| {
+                let row = destinations
+                    .keys()
+                    .map(|&b| (b, self.collapsed_dead_head_trip(a, b)))
+                    .collect();
+                (a, row)
+            })
+            .collect();
+        self.dead_head_trips = collapsed;
+    }
+
+    fn collapsed_dead_head_trip(&self, a: LocationIdx, b: LocationIdx) -> DeadHeadTrip {
+        let trip = &self.dead_head_trips[&a][&b];
+        if a == b {
+            return DeadHeadTrip::new(trip.distance, trip.travel_time);
+        }
+        let group_a = self.group_of.get(&a).copied();
+        let group_b = self.group_of.get(&b).copied();
+        if let (Some(group_a), Some(group_b)) = (group_a, group_b) {
+            if group_a == group_b {
+                return DeadHeadTrip::new(
+                    Distance::ZERO,
+                    self.location_groups[group_a].transfer_duration,
+                );
+            }
+        }
+        let members = |location: LocationIdx, group: Option<usize>| match group {
+            Some(group) => self.location_groups[group].members.clone(),
+            None => vec![location],
+        };
+        let members_b = members(b, group_b);
+        let member_trips: Vec<&DeadHeadTrip> = members(a, group_a)
+            .iter()
+            .flat_map(|m| members_b.iter().map(move |n| (*m, *n)))
+            .filter_map(|(m, n)| self.dead_head_trips.get(&m).and_then(|row| row.get(&n)))
+            .collect();
+        DeadHeadTrip::new(
+            member_trips
+                .iter()
+                .map(|member_trip| member_trip.distance)
+                .max()
+                .unwrap_or(trip.distance),
+            member_trips
+                .iter()
+                .map(|member_trip| member_trip.travel_time)
+                .max()
+                .unwrap_or(trip.travel_time),
+        )
+    }
+
+    fn nearest_siding_of(&self, location: LocationIdx) -> Option<(LocationIdx, Duration)> {
+        self.sidings
+            .iter()
+            .map(|&siding| {
+                let round_trip = self
+                    .travel_time(Location::Station(location), Location::Station(siding))
+                    + self.travel_time(Location::Station(siding), Location::Station(location));
+                (siding, round_trip)
+            })
+            .filter(|&(_, round_trip)| round_trip != Duration::Infinity)
+            .min_by_key(|&(siding, round_trip)| (round_trip, siding))
+    }
+
     fn get_dead_head_trip(&self, a: Location, b: Location) -> Option<&DeadHeadTrip> {
         match a {
             Location::Station(station_a) => match b {
@@ -13,18 +13,22 @@
 
 pub mod depot;
 pub mod nodes;
+pub mod trip_family;
 
-use depot::Depot;
+use depot::{Depot, DepotCapacities};
 use nodes::Node;
 use nodes::{MaintenanceSlot, ServiceTrip};
 use rapid_time::{DateTime, Duration};
+use trip_family::TripFamily;
 
 use crate::base_types::{
-    DepotIdx, Distance, Idx, Location, Meter, NodeIdx, PassengerCount, VehicleCount, VehicleTypeIdx,
+    checked_duration_between, Cost, DepotIdx, Distance, Idx, Location, Meter, NodeIdx,
+    PassengerCount, VehicleCount, VehicleTypeIdx,
 };
-use crate::config::Config;
+use crate::config::{Config, ShuntingConfig};
 use crate::locations::Locations;
 use crate::vehicle_types::VehicleTypes;
+use crate::warnings::{Severity, Warning};
 
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
@@ -32,9 +36,24 @@ use std::fmt;
 use std::iter::Iterator;
 
 use std::sync::Arc;
+use tracing::{info, warn};
 
 type SortedNodes = BTreeMap<(DateTime, NodeIdx), NodeIdx>;
 
+/// maximal number of service trips per vehicle type that are checked first when detecting whether
+/// all service trips are mutually unreachable.
+const REACHABILITY_SAMPLE_SIZE: usize = 100;
+
+/// How a vehicle spends an idle time that exceeds the platform idle limit of its location (see
+/// Network::platform_idle_between).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlatformIdle {
+    /// the vehicle moves to the siding and back, the round trip takes the given duration
+    MovedToSiding(Location, Duration),
+    /// no siding can be reached (and left again) within the idle time
+    Violation,
+}
+
 pub struct Network {
     nodes: HashMap<NodeIdx, Node>,
     depots: HashMap<DepotIdx, (Depot, NodeIdx, NodeIdx)>, // depot, start_node, end_node
@@ -43,6 +62,11 @@ pub struct Network {
     // nodes are by default sorted by start_time (ties are broken by end_time then id)
     service_nodes: HashMap<VehicleTypeIdx, Vec<NodeIdx>>,
     maintenance_nodes: Vec<NodeIdx>,
+    service_nodes_with_minimal_formation_count: Vec<NodeIdx>,
+    service_nodes_by_route: BTreeMap<String, Vec<NodeIdx>>, // route id -> service trips
+    trip_families: BTreeMap<String, TripFamily>, // id of the repeated departure -> family
+    split_successors: BTreeMap<NodeIdx, NodeIdx>, // service trips before -> after a split point
+    split_predecessors: HashMap<NodeIdx, NodeIdx>, // service trips after -> before a split point
     start_depot_nodes: Vec<NodeIdx>,
     end_depot_nodes: Vec<NodeIdx>,
 
@@ -54,10 +78,18 @@ pub struct Network {
     config: Arc<Config>,
     locations: Arc<Locations>,
     vehicle_types: Arc<VehicleTypes>,
+    shunting_per_vehicle_type: HashMap<VehicleTypeIdx, ShuntingConfig>, // see shunting_of
+    shunting_without_vehicle_type: ShuntingConfig, // the longest durations over all types
 
     // redundant information
     number_of_service_nodes: usize,
     planning_days: Duration, // planning duration as a multiple of days
+    staff_time_midnight: Option<DateTime>, // time-of-day reference of costs.staff_time_factors
+
+    instance_hash: String, // see json_serialisation::instance_hash
+    vehicle_types_without_depot: Vec<VehicleTypeIdx>,
+    warnings: Vec<Warning>, // warnings about the instance that are reported to the user
+    service_trips_mutually_unreachable: bool,
 }
 
 // methods
@@ -84,6 +116,33 @@ impl Network {
         self.planning_days
     }
 
+    /// return the fixed number of vehicles of the given type (None if the fleet size of this type
+    /// is a decision of the optimization).
+    pub fn fixed_fleet_size_of(&self, vehicle_type: VehicleTypeIdx) -> Option<VehicleCount> {
+        self.config.fixed_fleet.get(&vehicle_type).copied()
+    }
+
+    /// return true if the fleet size of at least one vehicle type is fixed.
+    pub fn has_fixed_fleet(&self) -> bool {
+        !self.config.fixed_fleet.is_empty()
+    }
+
+    /// canonical content hash of the instance (see json_serialisation::instance_hash).
+    pub fn instance_hash(&self) -> &str {
+        &self.instance_hash
+    }
+
+    /// vehicle types with service trips that no regular depot can spawn (no depot allows them or
+    /// only with a capacity of zero). All their vehicles use the overflow depot.
+    pub fn vehicle_types_without_depot(&self) -> &[VehicleTypeIdx] {
+        &self.vehicle_types_without_depot
+    }
+
+    /// return the warnings that came up while loading the instance.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
     pub fn service_nodes(
         &self,
         vehicle_type: VehicleTypeIdx,
@@ -98,6 +157,24 @@ impl Network {
             .copied()
     }
 
+    /// service trips (sorted by start time) departing in [from, to), empty if from is not before
+    /// to.
+    pub fn service_nodes_departing_between(
+        &self,
+        from: DateTime,
+        to: DateTime,
+    ) -> impl Iterator<Item = NodeIdx> + '_ {
+        (from < to)
+            .then(|| {
+                self.nodes_sorted_by_start
+                    .range((from, NodeIdx::smallest())..(to, NodeIdx::smallest()))
+                    .map(|(_, &node)| node)
+                    .filter(move |&node| self.node(node).is_service())
+            })
+            .into_iter()
+            .flatten()
+    }
+
     pub fn number_of_service_nodes(&self) -> usize {
         self.number_of_service_nodes
     }
@@ -106,6 +183,49 @@ impl Network {
         self.maintenance_nodes.iter().copied()
     }
 
+    /// service trips (sorted by start time) whose route segment requires a minimal formation count.
+    pub fn service_nodes_with_minimal_formation_count(&self) -> impl Iterator<Item = NodeIdx> + '_ {
+        self.service_nodes_with_minimal_formation_count
+            .iter()
+            .copied()
+    }
+
+    /// service trips (sorted by start time) grouped by the route of their departure. Routes are
+    /// sorted by id.
+    pub fn service_nodes_by_route(&self) -> impl Iterator<Item = (&String, &[NodeIdx])> + '_ {
+        self.service_nodes_by_route
+            .iter()
+            .map(|(route, nodes)| (route, nodes.as_slice()))
+    }
+
+    /// the next service trip of the same departure if the two meet at an allowed split point of
+    /// their route, i.e., the formation may change between them.
+    pub fn split_successor(&self, service_trip: NodeIdx) -> Option<NodeIdx> {
+        self.split_successors.get(&service_trip).copied()
+    }
+
+    /// the previous service trip of the same departure if the two meet at an allowed split point
+    /// of their route (see split_successor).
+    pub fn split_predecessor(&self, service_trip: NodeIdx) -> Option<NodeIdx> {
+        self.split_predecessors.get(&service_trip).copied()
+    }
+
+    /// all pairs (before, after) of service trips meeting at an allowed split point.
+    pub fn split_points(&self) -> impl Iterator<Item = (NodeIdx, NodeIdx)> + '_ {
+        self.split_successors
+            .iter()
+            .map(|(&before, &after)| (before, after))
+    }
+
+    pub fn has_split_points(&self) -> bool {
+        !self.split_successors.is_empty()
+    }
+
+    /// departures with headwayRepetitions, sorted by the id of the repeated departure.
+    pub fn trip_families(&self) -> impl Iterator<Item = (&String, &TripFamily)> + '_ {
+        self.trip_families.iter()
+    }
+
     pub fn nodes_of_vehicle_type_sorted_by_start(
         &self,
         vehicle_type: VehicleTypeIdx,
@@ -120,8 +240,27 @@ impl Network {
         maintenance_node.track_count()
     }
 
+    /// true if there are maintenance slots or depots providing maintenance.
     pub fn maintenance_considered(&self) -> bool {
         !self.maintenance_nodes.is_empty()
+            || self
+                .depots
+                .values()
+                .any(|(depot, _, _)| depot.provides_maintenance())
+    }
+
+    /// true if the given node is the end depot node of a depot providing maintenance.
+    pub fn is_maintenance_end_depot(&self, node: NodeIdx) -> bool {
+        self.node(node).is_end_depot()
+            && self
+                .get_depot(self.get_depot_idx(node))
+                .provides_maintenance()
+    }
+
+    /// return true if no service trip can be followed by another service trip of the same
+    /// vehicle (see detect_mutually_unreachable_service_trips). In this case each trip needs its own vehicle and the local search cannot improve anything.
+    pub fn service_trips_mutually_unreachable(&self) -> bool {
+        self.service_trips_mutually_unreachable
     }
 
     pub fn start_depot_nodes(&self) -> impl Iterator<Item = NodeIdx> + '_ {
@@ -141,6 +280,12 @@ impl Network {
         self.overflow_depot_idxs
     }
 
+    /// true iff the node is the start or end node of the overflow depot. Dead-head trips from or
+    /// to such a node are synthetic (the overflow depot is located Nowhere).
+    pub fn is_overflow_depot_node(&self, node: NodeIdx) -> bool {
+        node == self.overflow_depot_idxs.1 || node == self.overflow_depot_idxs.2
+    }
+
     /// service and maintenance_nodes
     pub fn coverable_nodes(&self) -> impl Iterator<Item = NodeIdx> + '_ {
         self.all_service_nodes().chain(self.maintenance_nodes())
@@ -162,6 +307,20 @@ impl Network {
         self.depots[&depot_idx].0.total_capacity()
     }
 
+    pub fn despawn_capacity_of(
+        &self,
+        depot_idx: DepotIdx,
+        vehicle_type_idx: VehicleTypeIdx,
+    ) -> VehicleCount {
+        self.depots[&depot_idx]
+            .0
+            .despawn_capacity_for(vehicle_type_idx)
+    }
+
+    pub fn total_despawn_capacity_of(&self, depot_idx: DepotIdx) -> VehicleCount {
+        self.depots[&depot_idx].0.total_despawn_capacity()
+    }
+
     pub fn vehicle_type_for(&self, service_trip: NodeIdx) -> VehicleTypeIdx {
         self.node(service_trip).as_service_trip().vehicle_type()
     }
@@ -171,11 +330,60 @@ impl Network {
         node: NodeIdx,
         vehicle_type: VehicleTypeIdx,
     ) -> bool {
-        if self.node(node).is_service() {
-            self.vehicle_type_for(node) == vehicle_type
-        } else {
-            true
+        let n = self.node(node);
+        if n.is_service() && self.vehicle_type_for(node) != vehicle_type {
+            return false;
+        }
+        if self.config.out_of_service_hard
+            && self.out_of_service_window_of(node, vehicle_type).is_some()
+        {
+            return false;
         }
+        // the vehicle type must be admitted at all locations of the node
+        self.locations
+            .is_vehicle_type_allowed_at(n.start_location(), vehicle_type)
+            && self
+                .locations
+                .is_vehicle_type_allowed_at(n.end_location(), vehicle_type)
+    }
+
+    /// The first out-of-service window of the vehicle type that the node overlaps (None for
+    /// depots). Nodes overlapping a window are incompatible with the vehicle type if
+    /// parameters.outOfService.hard is set, otherwise they are only reported.
+    pub fn out_of_service_window_of(
+        &self,
+        node: NodeIdx,
+        vehicle_type: VehicleTypeIdx,
+    ) -> Option<(DateTime, DateTime)> {
+        let n = self.node(node);
+        self.vehicle_types
+            .out_of_service_windows_of(vehicle_type)
+            .iter()
+            .find(|&&window| n.overlaps(window))
+            .copied()
+    }
+
+    /// For each out-of-service window (grouped by vehicle type) the service trips of the vehicle
+    /// type overlapping it. These trips cannot be served without violating the window.
+    pub fn out_of_service_conflicts(
+        &self,
+    ) -> Vec<(VehicleTypeIdx, (DateTime, DateTime), Vec<NodeIdx>)> {
+        self.vehicle_types
+            .iter()
+            .flat_map(|vehicle_type| {
+                self.vehicle_types
+                    .out_of_service_windows_of(vehicle_type)
+                    .iter()
+                    .map(|&window| {
+                        let conflicting_trips = self
+                            .service_nodes(vehicle_type)
+                            .filter(|&node| self.node(node).overlaps(window))
+                            .collect();
+                        (vehicle_type, window, conflicting_trips)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
     pub fn passengers_of(&self, service_trip: NodeIdx) -> PassengerCount {
@@ -200,6 +408,9 @@ impl Network {
         // .max(1) // one vehicle is always required
     }
 
+    /// Maximal number of vehicles allowed in the formation of the given service trip.
+    /// Both the vehicle type of the trip and the route segment can impose a limit, the tighter
+    /// one wins. None if neither is limited.
     pub fn maximal_formation_count_for(&self, service_trip: NodeIdx) -> Option<VehicleCount> {
         let limit_of_type = self
             .vehicle_types()
@@ -210,7 +421,61 @@ impl Network {
             .node(service_trip)
             .as_service_trip()
             .maximal_formation_count();
-        limit_of_type.map(|l| l.min(limit_of_node.unwrap_or(l)))
+        match (limit_of_type, limit_of_node) {
+            (Some(type_limit), Some(node_limit)) => Some(type_limit.min(node_limit)),
+            (Some(limit), None) | (None, Some(limit)) => Some(limit),
+            (None, None) => None,
+        }
+    }
+
+    /// Minimal number of vehicles required in the formation of the given service trip
+    /// (independent of the passenger demand). None if the route segment has no minimum.
+    pub fn minimal_formation_count_for(&self, service_trip: NodeIdx) -> Option<VehicleCount> {
+        self.node(service_trip)
+            .as_service_trip()
+            .minimal_formation_count()
+    }
+
+    /// Number of vehicles (of the vehicle type of the trip) a schedule must provide for the
+    /// given service trip: the vehicles required by the demand (or the minimal formation count
+    /// if larger) capped by the maximal formation count.
+    pub fn minimal_number_of_vehicles_for(&self, service_trip: NodeIdx) -> VehicleCount {
+        let required = self
+            .number_of_vehicles_required_to_serve(self.vehicle_type_for(service_trip), service_trip)
+            .max(self.minimal_formation_count_for(service_trip).unwrap_or(0));
+        match self.maximal_formation_count_for(service_trip) {
+            Some(limit) => required.min(limit),
+            None => required,
+        }
+    }
+
+    /// True if the demand of the service trip requires more vehicles than the formation limit
+    /// allows, i.e., no schedule can serve all passengers of this trip.
+    pub fn is_structurally_unservable(&self, service_trip: NodeIdx) -> bool {
+        self.maximal_formation_count_for(service_trip)
+            .is_some_and(|limit| {
+                self.number_of_vehicles_required_to_serve(
+                    self.vehicle_type_for(service_trip),
+                    service_trip,
+                ) > limit
+            })
+    }
+
+    /// True if a vehicle of the type of the service trip can be spawned at the depot, reach the
+    /// service trip and return to the same depot afterwards. Uses the can_reach semantics of the
+    /// spawning of vehicles, furthermore the depot must admit the vehicle type (spawn and despawn
+    /// capacity) and both dead-head trips must be finite (which excludes the overflow depot).
+    pub fn is_accessible_from_depot(&self, depot: DepotIdx, service_trip: NodeIdx) -> bool {
+        let vehicle_type = self.vehicle_type_for(service_trip);
+        let start_depot = self.get_start_depot_node(depot);
+        let end_depot = self.get_end_depot_node(depot);
+        self.capacity_of(depot, vehicle_type) > 0
+            && self.despawn_capacity_of(depot, vehicle_type) > 0
+            && self.compatible_with_vehicle_type(start_depot, vehicle_type)
+            && self.can_reach(start_depot, service_trip)
+            && self.can_reach(service_trip, end_depot)
+            && self.dead_head_distance_between(start_depot, service_trip) != Distance::Infinity
+            && self.dead_head_distance_between(service_trip, end_depot) != Distance::Infinity
     }
 
     pub fn get_depot_idx(&self, node_idx: NodeIdx) -> DepotIdx {
@@ -238,18 +503,115 @@ impl Network {
         if idle_start <= idle_end {
             idle_end - idle_start
         } else {
-            println!("negative idle time!");
+            warn!(
+                "negative idle time between {} and {}!",
+                self.node(node1),
+                self.node(node2)
+            );
             Duration::ZERO
         }
     }
 
+    /// the dead-head trip is assumed to depart at the end of node1.
+    /// Between activities at the same station there is no dead-head trip, the vehicle only idles
+    /// (after the minimal shunting, see minimal_duration_between_nodes).
     pub fn dead_head_time_between(&self, node1: NodeIdx, node2: NodeIdx) -> Duration {
-        self.locations.travel_time(
-            self.node(node1).end_location(),
-            self.node(node2).start_location(),
+        let end_location = self.node(node1).end_location();
+        let start_location = self.node(node2).start_location();
+        if let Location::Station(_) = end_location {
+            if end_location == start_location {
+                return Duration::ZERO;
+            }
+        }
+        self.locations.travel_time_departing_at(
+            end_location,
+            start_location,
+            self.node(node1).end_time(),
         )
     }
 
+    /// costs of the dead-head trip between node1 and node2 (without idle costs), including the
+    /// extra costs of time factors and the surcharge of staff time factors.
+    pub fn dead_head_costs_between(&self, node1: NodeIdx, node2: NodeIdx) -> Cost {
+        self.dead_head_time_between(node1, node2)
+            .in_sec()
+            .unwrap_or(self.planning_days.in_sec().unwrap())
+            * self.config.costs.dead_head_trip
+            + self.locations.extra_cost_departing_at(
+                self.node(node1).end_location(),
+                self.node(node2).start_location(),
+                self.node(node1).end_time(),
+            )
+            + self.staff_time_surcharge_between(node1, node2)
+    }
+
+    /// part of dead_head_costs_between charged by the staff time factors (see
+    /// config::StaffTimeFactor). The dead-head trip is timed as in the output: it departs at the
+    /// end of node1, or arrives at the start of node2 if node1 is a depot.
+    /// A round trip to a siding (see idle_costs_between) is charged at the base rate.
+    pub fn staff_time_surcharge_between(&self, node1: NodeIdx, node2: NodeIdx) -> Cost {
+        let Some(midnight) = self.staff_time_midnight else {
+            return 0;
+        };
+        let dead_head_time = self.dead_head_time_between(node1, node2);
+        let Ok(duration) = dead_head_time.in_sec() else {
+            return 0;
+        };
+        if duration == 0 || (self.node(node1).is_depot() && self.node(node2).is_depot()) {
+            return 0;
+        }
+        let departure = if self.node(node1).is_depot() {
+            self.node(node2).start_time() - dead_head_time
+        } else {
+            self.node(node1).end_time()
+        };
+        match checked_duration_between(midnight, departure) {
+            Ok(since_midnight) => self
+                .config
+                .costs
+                .staff_time_surcharge(since_midnight.in_sec().unwrap(), duration),
+            Err(_) => 0, // unbounded or long before the planning horizon
+        }
+    }
+
+    /// A vehicle idles between node1 and node2 at the start location of node2. If the location has a
+    /// platform idle limit and the idle time exceeds it, the vehicle moves to the nearest siding
+    /// and back (or it is a violation if the round trip does not fit into the idle time).
+    /// None if the limit is not exceeded (or there is none).
+    pub fn platform_idle_between(&self, node1: NodeIdx, node2: NodeIdx) -> Option<PlatformIdle> {
+        let location = self.node(node2).start_location();
+        let limit = self.locations.platform_idle_limit(location)?;
+        let idle_time = self.idle_time_between(node1, node2);
+        if idle_time <= limit {
+            return None;
+        }
+        match self.locations.nearest_siding(location) {
+            Some((siding, round_trip)) if round_trip <= idle_time => {
+                Some(PlatformIdle::MovedToSiding(siding, round_trip))
+            }
+            _ => Some(PlatformIdle::Violation),
+        }
+    }
+
+    /// costs of the idle time between node1 and node2. If the vehicle moves to a siding (see
+    /// platform_idle_between), the round trip is costed as dead-head trip and only the rest of
+    /// the idle time as idle.
+    pub fn idle_costs_between(&self, node1: NodeIdx, node2: NodeIdx) -> Cost {
+        let in_sec = |duration: Duration| {
+            duration
+                .in_sec()
+                .unwrap_or(self.planning_days.in_sec().unwrap())
+        };
+        let idle_time = self.idle_time_between(node1, node2);
+        match self.platform_idle_between(node1, node2) {
+            Some(PlatformIdle::MovedToSiding(_, round_trip)) => {
+                in_sec(round_trip) * self.config.costs.dead_head_trip
+                    + in_sec(idle_time - round_trip) * self.config.costs.idle
+            }
+            _ => in_sec(idle_time) * self.config.costs.idle,
+        }
+    }
+
     pub fn dead_head_distance_between(&self, node1: NodeIdx, node2: NodeIdx) -> Distance {
         self.locations.distance(
             self.node(node1).end_location(),
@@ -261,7 +623,9 @@ impl Network {
     // TODO connected by a route service trip can always be reached
 
     /// returns True iff node1 can reach node2
-    /// but always False from start depot to start depot and end depot to end depot
+    /// but always False from start depot to start depot and end depot to end depot.
+    /// Dead-head trips longer than config.maximal_dead_head_duration are infeasible (the trips
+    /// from and to depots are not restricted).
     pub fn can_reach(&self, node1: NodeIdx, node2: NodeIdx) -> bool {
         let n1 = self.nodes.get(&node1).unwrap();
         let n2 = self.nodes.get(&node2).unwrap();
@@ -278,8 +642,20 @@ impl Network {
             return true;
         }
 
-        if self.config.forbid_dead_head_trip && n1.end_location() != n2.start_location() {
-            return false;
+        if n1.end_location() != n2.start_location() {
+            if self.config.forbid_dead_head_trip {
+                return false;
+            }
+            if let Some(maximal_duration) = self.config.maximal_dead_head_duration {
+                if self.locations.travel_time_departing_at(
+                    n1.end_location(),
+                    n2.start_location(),
+                    n1.end_time(),
+                ) > maximal_duration
+                {
+                    return false;
+                }
+            }
         }
 
         n1.end_time() + self.minimal_duration_between_nodes_as_ref(n1, n2) <= n2.start_time()
@@ -333,9 +709,29 @@ impl Network {
             self.shunting_duration_between_activities_if_no_dead_head_trip(n1, n2)
         } else {
             // dead_head_trip
-            self.locations
-                .travel_time(n1.end_location(), n2.start_location())
-                + self.shunting_duration_between_activities_if_dead_head_trip(n1, n2)
+            self.locations.travel_time_departing_at(
+                n1.end_location(),
+                n2.start_location(),
+                n1.end_time(),
+            ) + self.shunting_duration_between_activities_if_dead_head_trip(n1, n2)
+        }
+    }
+
+    /// the shunting durations for vehicles of the given type, i.e., vehicleTypes.shunting with
+    /// parameters.shunting as fallback.
+    pub fn shunting_of(&self, vehicle_type: VehicleTypeIdx) -> &ShuntingConfig {
+        &self.shunting_per_vehicle_type[&vehicle_type]
+    }
+
+    /// Service trips can only be served by their own vehicle type, so it determines the shunting
+    /// durations. Between two maintenance slots (or depots), the longest durations of all types
+    /// are used, so that the connection is feasible for every type.
+    fn shunting_between(&self, n1: &Node, n2: &Node) -> &ShuntingConfig {
+        match (n1, n2) {
+            (Node::Service((_, service_trip)), _) | (_, Node::Service((_, service_trip))) => {
+                self.shunting_of(service_trip.vehicle_type())
+            }
+            _ => &self.shunting_without_vehicle_type,
         }
     }
 
@@ -344,11 +740,12 @@ impl Network {
         n1: &Node,
         n2: &Node,
     ) -> Duration {
+        let shunting = self.shunting_between(n1, n2);
         match (n1, n2) {
-            (Node::Service(_), Node::Service(_)) => self.config.shunting.minimal,
-            (Node::Service(_), Node::Maintenance(_)) => self.config.shunting.minimal,
-            (Node::Maintenance(_), Node::Service(_)) => self.config.shunting.minimal,
-            (Node::Maintenance(_), Node::Maintenance(_)) => self.config.shunting.minimal,
+            (Node::Service(_), Node::Service(_)) => shunting.minimal,
+            (Node::Service(_), Node::Maintenance(_)) => shunting.minimal,
+            (Node::Maintenance(_), Node::Service(_)) => shunting.minimal,
+            (Node::Maintenance(_), Node::Maintenance(_)) => shunting.minimal,
             _ => Duration::ZERO,
         }
     }
@@ -358,15 +755,16 @@ impl Network {
         n1: &Node,
         n2: &Node,
     ) -> Duration {
+        let shunting = self.shunting_between(n1, n2);
         let previous: Duration = match n1 {
-            Node::Service(_) => self.config.shunting.dead_head_trip,
-            Node::Maintenance(_) => self.config.shunting.dead_head_trip,
+            Node::Service(_) => shunting.dead_head_trip,
+            Node::Maintenance(_) => shunting.dead_head_trip,
             _ => Duration::ZERO,
         };
 
         let next: Duration = match n2 {
-            Node::Service(_) => self.config.shunting.dead_head_trip,
-            Node::Maintenance(_) => self.config.shunting.dead_head_trip,
+            Node::Service(_) => shunting.dead_head_trip,
+            Node::Maintenance(_) => shunting.dead_head_trip,
             _ => Duration::ZERO,
         };
 
@@ -400,6 +798,9 @@ impl Network {
     /// create a new network from the given data.
     /// The nodes idx must be in such a way that service_trips flattened and then maintenance
     /// nodes as vec gives the index within the vector.
+    /// split_points are pairs of ids of service trips (before, after) meeting at an allowed split
+    /// point, pairs with unknown ids are ignored.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         mut depots: Vec<Depot>,
         mut service_trips: HashMap<VehicleTypeIdx, Vec<ServiceTrip>>,
@@ -407,6 +808,10 @@ impl Network {
         config: Config,
         locations: Locations,
         vehicle_types: VehicleTypes,
+        trip_family_intervals: HashMap<String, Duration>,
+        split_points: Vec<(String, String)>,
+        instance_hash: String,
+        mut warnings: Vec<Warning>,
     ) -> Network {
         let mut nodes = HashMap::new();
         let mut depots_lookup = HashMap::new();
@@ -439,8 +844,13 @@ impl Network {
             overflow_depot_id,
             String::from("OVERFLOW_DEPOT"),
             Location::Nowhere,
-            overflow_capacity,
-            vehicle_types.iter().map(|vt| (vt, None)).collect(),
+            DepotCapacities {
+                spawn: overflow_capacity,
+                despawn: overflow_capacity,
+                allowed_types: vehicle_types.iter().map(|vt| (vt, (None, None))).collect(),
+            },
+            false,
+            0,
         );
         depots.push(overflow_depot);
 
@@ -527,6 +937,23 @@ impl Network {
                 .cmp_start_time(nodes.get(&n2).unwrap())
         });
 
+        for vehicle_type in vehicle_types.iter() {
+            if service_nodes[&vehicle_type].is_empty() {
+                let vehicle_type = vehicle_types.get(vehicle_type).unwrap();
+                let warning = Warning::new(
+                    "unusedVehicleType",
+                    Severity::Info,
+                    format!(
+                        "vehicle type {} is not used by any service trip. It is ignored.",
+                        vehicle_type
+                    ),
+                )
+                .with_context(vec![vehicle_type.id().clone()]);
+                warn!("{}", warning);
+                warnings.push(warning);
+            }
+        }
+
         if !maintenance_nodes.is_empty() {
             let maintenance_coverage = maintenance_nodes
                 .iter()
@@ -539,11 +966,17 @@ impl Network {
                 .map(|n| nodes.get(n).unwrap().travel_distance().in_meter().unwrap())
                 .sum::<Meter>();
             if maintenance_coverage < total_service_trip_distance {
-                println!(
-                    "\x1b[93mwarning:\x1b[0m maintenance coverage is less than the total service trip distance: {}m < {}m ({} thousand km < {} thousand km).",
-                    maintenance_coverage, total_service_trip_distance,
-                    maintenance_coverage / 1_000_000, total_service_trip_distance / 1_000_000
+                let warning = Warning::new(
+                    "insufficientMaintenanceCoverage",
+                    Severity::Warning,
+                    format!(
+                        "maintenance coverage is less than the total service trip distance: {}m < {}m ({} thousand km < {} thousand km).",
+                        maintenance_coverage, total_service_trip_distance,
+                        maintenance_coverage / 1_000_000, total_service_trip_distance / 1_000_000
+                    ),
                 );
+                warn!("{}", warning);
+                warnings.push(warning);
             }
         }
 
@@ -590,41 +1023,142 @@ impl Network {
             })
             .collect();
 
+        // a planning window given in the input defines the horizon
+        if let Some((start, end)) = config.planning_window {
+            earliest_datetime = start;
+            latest_datetime = end;
+        }
+
+        // instances without nodes have an empty planning horizon
         let planning_days = Duration::from_seconds(
-            (latest_datetime - earliest_datetime)
+            checked_duration_between(earliest_datetime, latest_datetime)
+                .unwrap_or(Duration::ZERO)
                 .in_sec()
                 .unwrap()
                 .div_ceil(86400)
                 * 86400,
         );
 
+        // the staff time factors are given by time of day, which is measured from a midnight a
+        // week before the planning horizon (dead-head trips from depots may depart before it)
+        let staff_time_midnight = if config.costs.staff_time_factors.is_empty()
+            || earliest_datetime == DateTime::Latest
+        {
+            None
+        } else {
+            let date = &earliest_datetime.as_iso()[..10];
+            Some(DateTime::new(&format!("{}T00:00:00", date)) - Duration::from_seconds(7 * 86400))
+        };
+
         let days = planning_days.in_min().unwrap() / 1440;
-        println!(
+        info!(
             "Earliest datetime: {}, Latest datetime: {} -> Planning days: {}",
             earliest_datetime, latest_datetime, days
         );
 
         if days > 7 {
-            println!(
-                "\x1b[93mwarning:\x1b[0m planning duration is very long: {} days. Optimization might take very long.",
-                days
+            let warning = Warning::new(
+                "longPlanningDuration",
+                Severity::Info,
+                format!(
+                    "planning duration is very long: {} days. Optimization might take very long.",
+                    days
+                ),
             );
+            warn!("{}", warning);
+            warnings.push(warning);
         }
 
         let number_of_service_nodes = service_nodes.values().map(|v| v.len()).sum();
 
+        let service_nodes_with_minimal_formation_count: Vec<NodeIdx> = nodes_sorted_by_start
+            .values()
+            .filter(|&n| {
+                let node = nodes.get(n).unwrap();
+                node.is_service() && node.as_service_trip().minimal_formation_count().is_some()
+            })
+            .copied()
+            .collect();
+
+        let mut service_nodes_by_route: BTreeMap<String, Vec<NodeIdx>> = BTreeMap::new();
+        let mut trip_families: BTreeMap<String, TripFamily> = BTreeMap::new();
+        for &n in nodes_sorted_by_start.values() {
+            let node = nodes.get(&n).unwrap();
+            if node.is_service() {
+                let service_trip = node.as_service_trip();
+                service_nodes_by_route
+                    .entry(service_trip.route().clone())
+                    .or_default()
+                    .push(n);
+                if let Some((family, repetition)) = service_trip.trip_family() {
+                    trip_families
+                        .entry(family.clone())
+                        .or_insert_with(|| TripFamily::new(trip_family_intervals[family]))
+                        .add_service_trip(repetition, n);
+                }
+            }
+        }
+
+        let service_trip_lookup: HashMap<&str, NodeIdx> = service_nodes
+            .values()
+            .flatten()
+            .map(|&n| (nodes.get(&n).unwrap().id(), n))
+            .collect();
+        let split_successors: BTreeMap<NodeIdx, NodeIdx> = split_points
+            .iter()
+            .filter_map(|(before, after)| {
+                Some((
+                    *service_trip_lookup.get(before.as_str())?,
+                    *service_trip_lookup.get(after.as_str())?,
+                ))
+            })
+            .collect();
+        let split_predecessors: HashMap<NodeIdx, NodeIdx> = split_successors
+            .iter()
+            .map(|(&before, &after)| (after, before))
+            .collect();
+
         let overflow_depot_ids = (
             overflow_depot_id,
             depots_lookup[&overflow_depot_id].1,
             depots_lookup[&overflow_depot_id].2,
         );
 
-        Network {
+        let shunting_per_vehicle_type: HashMap<VehicleTypeIdx, ShuntingConfig> = vehicle_types
+            .iter()
+            .map(|vehicle_type_idx| {
+                let vehicle_type = vehicle_types.get(vehicle_type_idx).unwrap();
+                let shunting = ShuntingConfig {
+                    minimal: vehicle_type
+                        .shunting_minimal()
+                        .unwrap_or(config.shunting.minimal),
+                    dead_head_trip: vehicle_type
+                        .shunting_dead_head_trip()
+                        .unwrap_or(config.shunting.dead_head_trip),
+                };
+                (vehicle_type_idx, shunting)
+            })
+            .collect();
+        let longest = |a: Duration, b: Duration| if a < b { b } else { a };
+        let shunting_without_vehicle_type = shunting_per_vehicle_type.values().fold(
+            config.shunting,
+            |longest_shunting, shunting| ShuntingConfig {
+                minimal: longest(longest_shunting.minimal, shunting.minimal),
+                dead_head_trip: longest(longest_shunting.dead_head_trip, shunting.dead_head_trip),
+            },
+        );
+
+        let mut network = Network {
             nodes,
             depots: depots_lookup,
             overflow_depot_idxs: overflow_depot_ids,
             service_nodes,
             maintenance_nodes,
+            service_nodes_with_minimal_formation_count,
+            service_nodes_by_route,
+            trip_families,
+            split_successors,
+            split_predecessors,
             start_depot_nodes,
             end_depot_nodes,
             nodes_sorted_by_start,
@@ -633,10 +1167,233 @@ impl Network {
             config: Arc::new(config),
             locations: Arc::new(locations),
             vehicle_types: Arc::new(vehicle_types),
+            shunting_per_vehicle_type,
+            shunting_without_vehicle_type,
             number_of_service_nodes,
             planning_days,
+            staff_time_midnight,
+            instance_hash,
+            vehicle_types_without_depot: Vec::new(),
+            warnings,
+            service_trips_mutually_unreachable: false,
+        };
+        network.exclude_unusable_maintenance_slots();
+        network.detect_mutually_unreachable_service_trips();
+        network.report_service_trips_isolated_by_maximal_dead_head_duration();
+        network.report_vehicle_types_without_depot();
+        network
+    }
+
+    /// vehicle types (with service trips) that cannot be spawned at any regular depot silently
+    /// fall back to the overflow depot, so they are reported.
+    fn report_vehicle_types_without_depot(&mut self) {
+        let overflow_depot = self.overflow_depot_idxs.0;
+        self.vehicle_types_without_depot = self
+            .vehicle_types
+            .iter()
+            .filter(|vehicle_type| !self.service_nodes[vehicle_type].is_empty())
+            .filter(|&vehicle_type| {
+                self.depots
+                    .iter()
+                    .filter(|(&depot_idx, _)| depot_idx != overflow_depot)
+                    .all(|(_, (depot, _, _))| depot.capacity_for(vehicle_type) == 0)
+            })
+            .collect();
+        for &vehicle_type in self.vehicle_types_without_depot.iter() {
+            let vehicle_type_id = self.vehicle_types.get(vehicle_type).unwrap().id().clone();
+            let warning = Warning::new(
+                "vehicleTypeWithoutDepot",
+                Severity::Warning,
+                format!(
+                    "vehicle type {} cannot spawn at any real depot, all its vehicles use the \
+                    overflow depot.",
+                    vehicle_type_id
+                ),
+            )
+            .with_context(vec![vehicle_type_id]);
+            warn!("{}", warning);
+            self.warnings.push(warning);
+        }
+    }
+
+    /// checks whether the reachability graph has no edge among service trips. To stay cheap on
+    /// huge instances, first only up to REACHABILITY_SAMPLE_SIZE evenly spread service trips per
+    /// vehicle type are checked for a service trip among their successors or predecessors. Only
+    /// if the sample has no such edge, all service trips are checked to confirm.
+    fn detect_mutually_unreachable_service_trips(&mut self) {
+        if self.number_of_service_nodes < 2 {
+            return;
+        }
+        let has_service_neighbor = |vehicle_type: VehicleTypeIdx, node: NodeIdx| {
+            self.successors(vehicle_type, node)
+                .chain(self.predecessors(vehicle_type, node))
+                .any(|other| self.node(other).is_service())
+        };
+        let edge_found_in_sample = self.vehicle_types.iter().any(|vehicle_type| {
+            let service_nodes = &self.service_nodes[&vehicle_type];
+            let step = service_nodes
+                .len()
+                .div_ceil(REACHABILITY_SAMPLE_SIZE)
+                .max(1);
+            service_nodes
+                .iter()
+                .step_by(step)
+                .any(|&node| has_service_neighbor(vehicle_type, node))
+        });
+        if edge_found_in_sample
+            || self.vehicle_types.iter().any(|vehicle_type| {
+                self.service_nodes[&vehicle_type]
+                    .iter()
+                    .any(|&node| has_service_neighbor(vehicle_type, node))
+            })
+        {
+            return;
+        }
+        self.service_trips_mutually_unreachable = true;
+        let warning = Warning::new(
+            "mutuallyUnreachableServiceTrips",
+            Severity::Warning,
+            "no service trip can reach another service trip, so every trip needs its own \
+            vehicle. Check the dead-head durations and the shunting durations. The local search is \
+            skipped."
+                .to_string(),
+        );
+        warn!("{}", warning);
+        self.warnings.push(warning);
+    }
+
+    /// reports service trips that cannot be connected to any other service trip or maintenance slot
+    /// because of config.maximal_dead_head_duration, although they could be without the cap.
+    /// Such trips need their own vehicle, which can make a fixed fleet infeasible.
+    fn report_service_trips_isolated_by_maximal_dead_head_duration(&mut self) {
+        if self.config.maximal_dead_head_duration.is_none() {
+            return;
+        }
+        let mut isolated_service_trips: Vec<NodeIdx> = Vec::new();
+        for vehicle_type in self.vehicle_types.iter() {
+            for &node in self.service_nodes[&vehicle_type].iter() {
+                if isolated_service_trips.contains(&node) {
+                    continue;
+                }
+                let is_connected = |other: NodeIdx| {
+                    let other_node = self.node(other);
+                    other_node.is_service() || other_node.is_maintenance()
+                };
+                if self
+                    .successors(vehicle_type, node)
+                    .chain(self.predecessors(vehicle_type, node))
+                    .any(is_connected)
+                {
+                    continue;
+                }
+                let connected_without_cap = self.vehicle_type_nodes_sorted_by_start[&vehicle_type]
+                    .values()
+                    .filter(|&&other| other != node && is_connected(other))
+                    .any(|&other| {
+                        self.can_reach_without_dead_head_cap(node, other)
+                            || self.can_reach_without_dead_head_cap(other, node)
+                    });
+                if connected_without_cap {
+                    isolated_service_trips.push(node);
+                }
+            }
+        }
+        for node in isolated_service_trips {
+            let service_trip_id = self.node(node).id().to_string();
+            let warning = Warning::new(
+                "serviceTripIsolatedByDeadHeadCap",
+                Severity::Warning,
+                format!(
+                    "service trip {} cannot be connected to any other service trip or maintenance \
+                    slot within the maximal dead-head duration, so it needs its own vehicle.",
+                    service_trip_id
+                ),
+            )
+            .with_context(vec![service_trip_id]);
+            warn!("{}", warning);
+            self.warnings.push(warning);
         }
     }
+
+    /// like can_reach for two non-depot nodes, but ignoring config.maximal_dead_head_duration.
+    fn can_reach_without_dead_head_cap(&self, node1: NodeIdx, node2: NodeIdx) -> bool {
+        let n1 = self.node(node1);
+        let n2 = self.node(node2);
+        if self.config.forbid_dead_head_trip && n1.end_location() != n2.start_location() {
+            return false;
+        }
+        n1.end_time() + self.minimal_duration_between_nodes_as_ref(n1, n2) <= n2.start_time()
+    }
+
+    /// removes maintenance slots that no vehicle can use within the planning horizon from
+    /// maintenance_nodes() and reports them as warnings.
+    /// The nodes themselves stay in the network.
+    fn exclude_unusable_maintenance_slots(&mut self) {
+        let unusable_maintenance_nodes: Vec<(NodeIdx, String)> = self
+            .maintenance_nodes
+            .iter()
+            .filter_map(|&m| {
+                self.reason_maintenance_slot_is_unusable(m)
+                    .map(|reason| (m, reason))
+            })
+            .collect();
+
+        for (maintenance_node, reason) in unusable_maintenance_nodes {
+            let node = self.nodes.get(&maintenance_node).unwrap();
+            let warning = Warning::new(
+                "maintenanceSlotIgnored",
+                Severity::Info,
+                format!("maintenance slot {} is ignored: {}.", node.id(), reason),
+            )
+            .with_context(vec![node.id().to_string()]);
+            warn!("{}", warning);
+            self.warnings.push(warning);
+
+            let start_key = (node.start_time(), maintenance_node);
+            let end_key = (node.end_time(), maintenance_node);
+            for sorted_nodes in self.vehicle_type_nodes_sorted_by_start.values_mut() {
+                sorted_nodes.remove(&start_key);
+            }
+            for sorted_nodes in self.vehicle_type_nodes_sorted_by_end.values_mut() {
+                sorted_nodes.remove(&end_key);
+            }
+            self.maintenance_nodes.retain(|&n| n != maintenance_node);
+        }
+    }
+
+    /// a maintenance slot is usable if some vehicle type is admitted at its location, it is
+    /// connected to at least one service trip (either before or after the slot) and a regular
+    /// depot can be reached from it.
+    fn reason_maintenance_slot_is_unusable(&self, maintenance_node: NodeIdx) -> Option<String> {
+        if !self
+            .vehicle_types
+            .iter()
+            .any(|vt| self.compatible_with_vehicle_type(maintenance_node, vt))
+        {
+            return Some(String::from("no vehicle type is admitted at its location"));
+        }
+
+        let connected_to_service_trip = self
+            .all_service_nodes()
+            .any(|s| self.can_reach(s, maintenance_node) || self.can_reach(maintenance_node, s));
+        if !connected_to_service_trip {
+            return Some(String::from(
+                "no service trip can reach it and it cannot reach any service trip",
+            ));
+        }
+
+        let overflow_end_depot = self.overflow_depot_idxs.2;
+        let can_return_to_depot = self.end_depot_nodes.iter().any(|&d| {
+            d != overflow_end_depot
+                && self.can_reach(maintenance_node, d)
+                && self.dead_head_distance_between(maintenance_node, d) != Distance::Infinity
+        });
+        if !can_return_to_depot {
+            return Some(String::from("no depot can be reached from it"));
+        }
+
+        None
+    }
 }
 
 impl fmt::Display for Network {
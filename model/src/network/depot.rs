@@ -13,15 +13,26 @@
 
 use std::collections::HashMap;
 
-use crate::base_types::{DepotIdx, Location, VehicleCount, VehicleTypeIdx};
+use crate::base_types::{Cost, DepotIdx, Location, VehicleCount, VehicleTypeIdx};
 
 pub struct Depot {
     idx: DepotIdx,
     id: String,
     location: Location,
-    total_capacity: VehicleCount,
-    allowed_types: HashMap<VehicleTypeIdx, Option<VehicleCount>>, // number of vehicles that can be
-                                                                 // spawned. None means no limit.
+    total_capacity: VehicleCount, // number of vehicles that can be spawned
+    total_despawn_capacity: VehicleCount, // number of vehicles that can be despawned
+    // number of vehicles that can be spawned and despawned. None means no limit.
+    allowed_types: HashMap<VehicleTypeIdx, (Option<VehicleCount>, Option<VehicleCount>)>,
+    provides_maintenance: bool, // ending a tour here counts as a light maintenance visit
+    spawn_cost: Cost, // per spawned vehicle, only for the costAware depot assignment policy
+}
+
+/// The spawn and despawn capacities of a depot: in total and per allowed vehicle type (None means
+/// no limit beyond the total). Vehicle types without entry are not allowed.
+pub struct DepotCapacities {
+    pub spawn: VehicleCount,
+    pub despawn: VehicleCount,
+    pub allowed_types: HashMap<VehicleTypeIdx, (Option<VehicleCount>, Option<VehicleCount>)>,
 }
 
 // methods
@@ -38,16 +49,49 @@ impl Depot {
         self.location
     }
 
+    /// the number of vehicles that can be spawned
     pub fn total_capacity(&self) -> VehicleCount {
         self.total_capacity
     }
 
+    pub fn total_despawn_capacity(&self) -> VehicleCount {
+        self.total_despawn_capacity
+    }
+
+    /// true if vehicles ending their tour at this depot receive a maintenance credit (see
+    /// config.maintenance.depot_credit)
+    pub fn provides_maintenance(&self) -> bool {
+        self.provides_maintenance
+    }
+
+    /// costs of spawning a vehicle at this depot (e.g., stabling fees). They are not part of the
+    /// objective, only the costAware depot assignment policy takes them into account.
+    pub fn spawn_cost(&self) -> Cost {
+        self.spawn_cost
+    }
+
+    /// true if the vehicle type is listed in the allowed types of the depot (even with a capacity
+    /// of zero)
+    pub fn allows(&self, vehicle_type_idx: VehicleTypeIdx) -> bool {
+        self.allowed_types.contains_key(&vehicle_type_idx)
+    }
+
     /// takes the minimum of vehicle specific capacity (None means no limit) and depot capacity
+    /// for spawning
     pub fn capacity_for(&self, vehicle_type_idx: VehicleTypeIdx) -> VehicleCount {
         match self.allowed_types.get(&vehicle_type_idx) {
-            Some(Some(capacity)) => VehicleCount::min(*capacity, self.total_capacity),
-            Some(None) => self.total_capacity, // no vehicle specific limit
-            None => 0,                         // vehicle type not allowed
+            Some((Some(capacity), _)) => VehicleCount::min(*capacity, self.total_capacity),
+            Some((None, _)) => self.total_capacity, // no vehicle specific limit
+            None => 0,                              // vehicle type not allowed
+        }
+    }
+
+    /// same as capacity_for but for despawning
+    pub fn despawn_capacity_for(&self, vehicle_type_idx: VehicleTypeIdx) -> VehicleCount {
+        match self.allowed_types.get(&vehicle_type_idx) {
+            Some((_, Some(capacity))) => VehicleCount::min(*capacity, self.total_despawn_capacity),
+            Some((_, None)) => self.total_despawn_capacity, // no vehicle specific limit
+            None => 0,                                      // vehicle type not allowed
         }
     }
 }
@@ -58,15 +102,19 @@ impl Depot {
         depot_idx: DepotIdx,
         name: String,
         location: Location,
-        total_capacity: VehicleCount,
-        allowed_types: HashMap<VehicleTypeIdx, Option<VehicleCount>>,
+        capacities: DepotCapacities,
+        provides_maintenance: bool,
+        spawn_cost: Cost,
     ) -> Self {
         Self {
             idx: depot_idx,
             id: name,
             location,
-            total_capacity,
-            allowed_types,
+            total_capacity: capacities.spawn,
+            total_despawn_capacity: capacities.despawn,
+            allowed_types: capacities.allowed_types,
+            provides_maintenance,
+            spawn_cost,
         }
     }
 }
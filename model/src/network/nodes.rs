@@ -14,7 +14,8 @@
 use rapid_time::{DateTime, Duration};
 
 use crate::base_types::{
-    DepotIdx, Distance, Idx, Location, NodeIdx, PassengerCount, VehicleCount, VehicleTypeIdx,
+    checked_duration_between, DepotIdx, Distance, Idx, Location, NodeIdx, PassengerCount,
+    VehicleCount, VehicleTypeIdx,
 };
 
 use core::cmp::Ordering;
@@ -45,6 +46,7 @@ impl DepotNode {
 #[derive(Debug, PartialEq, Eq)]
 pub struct ServiceTrip {
     id: String,
+    route: String, // id of the route this trip (i.e., departure segment) belongs to
     vehicle_type: VehicleTypeIdx,
     origin: Location,
     destination: Location,
@@ -54,6 +56,9 @@ pub struct ServiceTrip {
     passengers: PassengerCount,
     seated: PassengerCount,
     maximal_formation_count: Option<VehicleCount>,
+    minimal_formation_count: Option<VehicleCount>,
+    trip_family: Option<(String, u32)>, // id of the repeated departure and index of the repetition
+    contract: Option<String>,           // transport contract of the route (for accounting only)
 }
 
 impl ServiceTrip {
@@ -61,6 +66,10 @@ impl ServiceTrip {
         &self.id
     }
 
+    pub fn route(&self) -> &String {
+        &self.route
+    }
+
     pub fn vehicle_type(&self) -> VehicleTypeIdx {
         self.vehicle_type
     }
@@ -76,6 +85,27 @@ impl ServiceTrip {
     pub fn maximal_formation_count(&self) -> Option<VehicleCount> {
         self.maximal_formation_count
     }
+
+    pub fn minimal_formation_count(&self) -> Option<VehicleCount> {
+        self.minimal_formation_count
+    }
+
+    /// Some if the departure of this trip has headwayRepetitions: the id of the trip family (i.e.,
+    /// the id of the repeated departure) and the index of the repetition (starting at 0).
+    pub fn trip_family(&self) -> Option<(&String, u32)> {
+        self.trip_family
+            .as_ref()
+            .map(|(family, repetition)| (family, *repetition))
+    }
+
+    pub fn contract(&self) -> Option<&String> {
+        self.contract.as_ref()
+    }
+
+    /// whether the trip runs during some time of the window (touching it does not count).
+    pub fn overlaps(&self, (start, end): (DateTime, DateTime)) -> bool {
+        self.departure < end && start < self.arrival
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -146,11 +176,25 @@ impl Node {
         }
     }
 
+    /// Panics with a descriptive message if the node ends before it starts (see
+    /// checked_duration).
     pub fn duration(&self) -> Duration {
+        self.checked_duration()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as duration, but an error is returned if the node ends before it starts.
+    pub fn checked_duration(&self) -> Result<Duration, String> {
         match self {
-            Node::StartDepot(_) => Duration::ZERO,
-            Node::EndDepot(_) => Duration::ZERO,
-            _ => self.end_time() - self.start_time(),
+            Node::StartDepot(_) => Ok(Duration::ZERO),
+            Node::EndDepot(_) => Ok(Duration::ZERO),
+            Node::Service((_, s)) => checked_duration_between(s.departure, s.arrival)
+                .map_err(|error| format!("arrival before departure on trip {}: {}", s.id, error)),
+            Node::Maintenance((_, m)) => {
+                checked_duration_between(m.start, m.end).map_err(|error| {
+                    format!("maintenance slot {} ends before it starts: {}", m.id, error)
+                })
+            }
         }
     }
 
@@ -179,6 +223,12 @@ impl Node {
         }
     }
 
+    /// whether the node takes some time of the window (touching it does not count). Depots never
+    /// overlap a window.
+    pub fn overlaps(&self, (start, end): (DateTime, DateTime)) -> bool {
+        !self.is_depot() && self.start_time() < end && start < self.end_time()
+    }
+
     pub fn as_service_trip(&self) -> &ServiceTrip {
         match self {
             Node::Service((_, s)) => s,
@@ -258,6 +308,7 @@ impl Node {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn create_service_trip(
         id: String,
+        route: String,
         vehicle_type: VehicleTypeIdx,
         origin: Location,
         destination: Location,
@@ -267,9 +318,13 @@ impl Node {
         passengers: PassengerCount,
         seated: PassengerCount,
         maximal_formation_count: Option<VehicleCount>,
+        minimal_formation_count: Option<VehicleCount>,
+        trip_family: Option<(String, u32)>,
+        contract: Option<String>,
     ) -> ServiceTrip {
         ServiceTrip {
             id,
+            route,
             vehicle_type,
             origin,
             destination,
@@ -279,6 +334,9 @@ impl Node {
             passengers,
             seated,
             maximal_formation_count,
+            minimal_formation_count,
+            trip_family,
+            contract,
         }
     }
 
@@ -339,8 +397,29 @@ impl Node {
     }
 }
 
+/// The name of the node in logs and error messages: the id of the input (i.e., the id of the
+/// departure segment or maintenance slot) with the route and departure of a service trip or the
+/// time span of a maintenance slot, so that it can be correlated with the input.
+/// (The output uses the id only, see Node::id.)
 impl fmt::Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.id())
+        match self {
+            Node::Service((_, s)) => write!(
+                f,
+                "trip {} (route {}, departure {})",
+                s.id,
+                s.route,
+                s.departure.as_iso()
+            ),
+            Node::Maintenance((_, m)) => write!(
+                f,
+                "maintenance slot {} ({} - {})",
+                m.id,
+                m.start.as_iso(),
+                m.end.as_iso()
+            ),
+            Node::StartDepot((_, d)) => write!(f, "start depot node {}", d.id),
+            Node::EndDepot((_, d)) => write!(f, "end depot node {}", d.id),
+        }
     }
 }
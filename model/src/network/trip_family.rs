@@ -0,0 +1,59 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use rapid_time::Duration;
+
+use crate::base_types::NodeIdx;
+
+/// A departure that repeats with a fixed headway (see headwayRepetitions in the input). Each
+/// repetition is an individual set of service trips, the family only remembers which trips belong
+/// together.
+pub struct TripFamily {
+    interval: Duration,
+    repetitions: Vec<Vec<NodeIdx>>, // service trips of each repetition (sorted by start time)
+}
+
+// methods
+impl TripFamily {
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn number_of_repetitions(&self) -> usize {
+        self.repetitions.len()
+    }
+
+    /// service trips of each repetition (ordered by repetition index).
+    pub fn repetitions(&self) -> impl Iterator<Item = &[NodeIdx]> + '_ {
+        self.repetitions.iter().map(|trips| trips.as_slice())
+    }
+}
+
+// static functions
+impl TripFamily {
+    pub(crate) fn new(interval: Duration) -> TripFamily {
+        TripFamily {
+            interval,
+            repetitions: Vec::new(),
+        }
+    }
+
+    /// service trips have to be added in order of their start time.
+    pub(crate) fn add_service_trip(&mut self, repetition: u32, service_trip: NodeIdx) {
+        let repetition = repetition as usize;
+        if self.repetitions.len() <= repetition {
+            self.repetitions.resize(repetition + 1, Vec::new());
+        }
+        self.repetitions[repetition].push(service_trip);
+    }
+}
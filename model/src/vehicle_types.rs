@@ -13,6 +13,8 @@
 
 use std::{collections::HashMap, fmt, sync::Arc};
 
+use rapid_time::{DateTime, Duration};
+
 use crate::base_types::{PassengerCount, VehicleCount, VehicleTypeIdx};
 
 pub struct VehicleTypes {
@@ -40,6 +42,26 @@ impl VehicleTypes {
         self.vehicle_types.get(&idx).cloned()
     }
 
+    /// Same as get(idx).out_of_service_windows(), but without cloning the vehicle type.
+    pub fn out_of_service_windows_of(&self, idx: VehicleTypeIdx) -> &[(DateTime, DateTime)] {
+        self.vehicle_types[&idx].out_of_service_windows()
+    }
+
+    /// Same as get(idx).minimum_in_service(), but without cloning the vehicle type.
+    pub fn minimum_in_service_of(
+        &self,
+        idx: VehicleTypeIdx,
+    ) -> &[(DateTime, DateTime, VehicleCount)] {
+        self.vehicle_types[&idx].minimum_in_service()
+    }
+
+    /// true if some vehicle type has a minimum-in-service requirement.
+    pub fn has_minimum_in_service(&self) -> bool {
+        self.vehicle_types
+            .values()
+            .any(|vehicle_type| !vehicle_type.minimum_in_service.is_empty())
+    }
+
     /// Returns an iterator over all vehicle types, sorted by seat count.
     pub fn iter(&self) -> impl Iterator<Item = VehicleTypeIdx> + '_ {
         self.ids_sorted.iter().cloned()
@@ -64,6 +86,10 @@ pub struct VehicleType {
     seats: PassengerCount,
     capacity: PassengerCount,
     maximal_formation_count: Option<VehicleCount>,
+    out_of_service_windows: Vec<(DateTime, DateTime)>, // vehicles must be in a depot
+    minimum_in_service: Vec<(DateTime, DateTime, VehicleCount)>, // window and vehicle count
+    shunting_minimal: Option<Duration>,                // None means config.shunting.minimal
+    shunting_dead_head_trip: Option<Duration>,         // None means config.shunting.dead_head_trip
 }
 
 impl VehicleType {
@@ -80,6 +106,48 @@ impl VehicleType {
             seats: number_of_seats,
             capacity: capacity_of_passengers,
             maximal_formation_count,
+            out_of_service_windows: Vec::new(),
+            minimum_in_service: Vec::new(),
+            shunting_minimal: None,
+            shunting_dead_head_trip: None,
+        }
+    }
+
+    /// Vehicles of this type must not serve any node overlapping one of the windows (e.g., during
+    /// a fleet-wide software update), see Network::out_of_service_window_of.
+    pub fn with_out_of_service_windows(
+        self,
+        out_of_service_windows: Vec<(DateTime, DateTime)>,
+    ) -> VehicleType {
+        VehicleType {
+            out_of_service_windows,
+            ..self
+        }
+    }
+
+    /// Throughout each window [start, end), at least the given number of vehicles of this type
+    /// must be in service (e.g., contractual availability), see Schedule::in_service_curve.
+    pub fn with_minimum_in_service(
+        self,
+        minimum_in_service: Vec<(DateTime, DateTime, VehicleCount)>,
+    ) -> VehicleType {
+        VehicleType {
+            minimum_in_service,
+            ..self
+        }
+    }
+
+    /// Overrides the shunting durations of the config for this type (e.g., long units need more
+    /// time than short ones), see Network::shunting_of.
+    pub fn with_shunting(
+        self,
+        shunting_minimal: Option<Duration>,
+        shunting_dead_head_trip: Option<Duration>,
+    ) -> VehicleType {
+        VehicleType {
+            shunting_minimal,
+            shunting_dead_head_trip,
+            ..self
         }
     }
 
@@ -102,6 +170,22 @@ impl VehicleType {
     pub fn maximal_formation_count(&self) -> Option<VehicleCount> {
         self.maximal_formation_count
     }
+
+    pub fn out_of_service_windows(&self) -> &[(DateTime, DateTime)] {
+        &self.out_of_service_windows
+    }
+
+    pub fn minimum_in_service(&self) -> &[(DateTime, DateTime, VehicleCount)] {
+        &self.minimum_in_service
+    }
+
+    pub fn shunting_minimal(&self) -> Option<Duration> {
+        self.shunting_minimal
+    }
+
+    pub fn shunting_dead_head_trip(&self) -> Option<Duration> {
+        self.shunting_dead_head_trip
+    }
 }
 
 impl fmt::Display for VehicleType {
@@ -0,0 +1,55 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// A problem that does not prevent solving but is reported to the user in the output (see
+/// Network::warnings for the warnings about the instance). The code is stable, the message is
+/// meant for humans and may change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Warning {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub context: Vec<String>, // ids of the affected objects (e.g., service trips or vehicle types)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Info,    // the input was adjusted as documented, the schedule is not affected otherwise
+    Warning, // the schedule is likely worse than expected or the input should be fixed
+}
+
+impl Warning {
+    pub fn new(code: &'static str, severity: Severity, message: String) -> Warning {
+        Warning {
+            code,
+            severity,
+            message,
+            context: Vec::new(),
+        }
+    }
+
+    pub fn with_context(self, context: Vec<String>) -> Warning {
+        Warning { context, ..self }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
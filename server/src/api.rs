@@ -0,0 +1,870 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use im::HashMap;
+use model::base_types::VehicleTypeIdx;
+use model::config::{Hint, InfeasibilityPolicy};
+use model::network::Network;
+use model::warnings::Warning;
+use rapid_solve::heuristics::Solver;
+use rapid_solve::objective::{EvaluatedSolution, Objective};
+use rapid_time::DateTime;
+use solution::overflow_analysis::{uncovered_demand, UncoveredDemand};
+use solution::transition::Transition;
+use solution::Schedule;
+use solver::local_search::maintenance_repair::repair_maintenance_violation;
+use solver::local_search::neighborhood::swaps::SwapInfo;
+use solver::local_search::ScheduleWithInfo;
+use solver::min_cost_flow_solver::MinCostFlowSolver;
+use solver::objective;
+use solver::phase_timings::PhaseTimings;
+use solver::progress::ProgressSink;
+use solver::trace::build_trace_writer;
+use solver::transition_cycle_tsp::cycle_cache::TransitionCycleTspCache;
+use solver::transition_local_search::build_transition_local_search_solver;
+use solver::transition_local_search::TransitionWithInfo;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time as stdtime;
+use tracing::{debug, info, warn};
+
+use crate::convergence::{ConvergenceRecorder, MAX_CONVERGENCE_POINTS};
+use crate::milestones::{MilestoneRecorder, MilestoneSnapshot};
+use crate::warm_start::WarmStart;
+use crate::{
+    collect_warnings, create_output_json, maintenance_hard_constraint_warnings,
+    missing_depot_capacities_to_json, overflow_depot_warnings,
+};
+
+/// Options of a typed solve (see solve). The default options give the same result as
+/// solve_instance.
+#[derive(Clone, Debug, Default)]
+pub struct SolveOptions {
+    /// time limit of the local search and the transition optimization together, counted from
+    /// the start of the local search (None means unlimited). The smaller one of this and
+    /// parameters.timeLimit applies. The maintenance repair is not limited.
+    pub time_limit: Option<stdtime::Duration>,
+    pub mode: SolveMode,
+    /// result of a previous solve of a similar instance (e.g., the base job of a differential
+    /// solve). The schedule built from it replaces the min-cost flow schedule if it is better.
+    pub warm_start: Option<Arc<WarmStart>>,
+    /// schedule to start the local search from (e.g., initialSchedule of the input). If it covers
+    /// all service trips, the min-cost flow solver is skipped. Otherwise, the min-cost flow
+    /// schedule is computed as well and the better one of both is taken.
+    pub initial_schedule: Option<Schedule>,
+    /// the local search only modifies nodes that lie completely inside this window (e.g., around
+    /// a new resource, see what_if). Ignored with parameters.timePartitioning.
+    pub focus_window: Option<(DateTime, DateTime)>,
+    /// receives the incumbents of the local search (e.g., for webhook notifications).
+    pub progress_sink: Option<ProgressSink>,
+    /// once set, the local search returns the best schedule found so far and the transitions of
+    /// the remaining vehicle types are not optimized (see POST /cancel). The output has
+    /// info.cancelled set.
+    pub cancellation: Option<Arc<AtomicBool>>,
+    /// records the objective value of the incumbents over time (see info.convergence), e.g., to
+    /// read it while the solve is running. A new recorder is used if None.
+    pub convergence: Option<Arc<ConvergenceRecorder>>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SolveMode {
+    /// min-cost flow, local search (if maintenance is considered), maintenance repair (if
+    /// maintenance is hard) and transition optimization.
+    #[default]
+    Full,
+    /// only the schedule of the min-cost flow solver with improved depots (e.g., for a quick
+    /// feasibility check of many scenarios).
+    StartSchedule,
+}
+
+/// The result of a typed solve: the final schedule together with its evaluation, the objective
+/// it was evaluated with and the run log (phase timings and warnings).
+pub struct SolveResult {
+    solution: EvaluatedSolution<ScheduleWithInfo>,
+    objective: Arc<Objective<ScheduleWithInfo>>,
+    runtime_duration: stdtime::Duration,
+    phase_timings: PhaseTimings,
+    milestones: Vec<MilestoneSnapshot>,
+    convergence: Arc<ConvergenceRecorder>,
+}
+
+impl SolveResult {
+    fn new(
+        solution: EvaluatedSolution<ScheduleWithInfo>,
+        objective: Arc<Objective<ScheduleWithInfo>>,
+        start_time: stdtime::Instant,
+        phase_timings: PhaseTimings,
+        milestones: Vec<MilestoneSnapshot>,
+        convergence: Arc<ConvergenceRecorder>,
+    ) -> SolveResult {
+        SolveResult {
+            solution,
+            objective,
+            runtime_duration: start_time.elapsed(),
+            phase_timings,
+            milestones,
+            convergence,
+        }
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        self.solution.solution().get_schedule()
+    }
+
+    pub fn solution(&self) -> &EvaluatedSolution<ScheduleWithInfo> {
+        &self.solution
+    }
+
+    pub fn objective(&self) -> &Arc<Objective<ScheduleWithInfo>> {
+        &self.objective
+    }
+
+    pub fn runtime_duration(&self) -> stdtime::Duration {
+        self.runtime_duration
+    }
+
+    pub fn phase_timings(&self) -> &PhaseTimings {
+        &self.phase_timings
+    }
+
+    /// the snapshots of parameters.milestones reached during the solve.
+    pub fn milestones(&self) -> &[MilestoneSnapshot] {
+        &self.milestones
+    }
+
+    /// the objective value of the incumbents over time (see ConvergenceRecorder).
+    pub fn convergence(&self) -> &ConvergenceRecorder {
+        &self.convergence
+    }
+
+    /// warnings about the instance and the final schedule (as in the output json).
+    pub fn warnings(&self) -> Vec<Warning> {
+        collect_warnings(self.schedule())
+    }
+
+    /// the output json (see solve_instance).
+    pub fn to_json(&self) -> serde_json::Value {
+        create_output_json(
+            &self.solution,
+            &self.objective,
+            self.runtime_duration,
+            &self.phase_timings,
+            &self.milestones,
+            Some(&self.convergence),
+        )
+    }
+}
+
+/// Solves the given instance without a round-trip through json (e.g., for embedding the solver
+/// or running many scenarios).
+pub fn solve(network: Arc<Network>, options: SolveOptions) -> SolveResult {
+    solve_with_phase_timings(
+        network,
+        options,
+        stdtime::Instant::now(),
+        PhaseTimings::new(),
+    )
+}
+
+/// Same as solve, but the timings of phases before the solve (e.g., loading) are kept and the
+/// runtime is measured from start_time.
+/// With parameters.numberOfThreads, the whole solve runs in a dedicated rayon pool of that size
+/// (the parallel neighborhood evaluation then only uses these threads), otherwise in the global
+/// pool.
+pub(crate) fn solve_with_phase_timings(
+    network: Arc<Network>,
+    options: SolveOptions,
+    start_time: stdtime::Instant,
+    phase_timings: PhaseTimings,
+) -> SolveResult {
+    match network.config().number_of_threads {
+        None => solve_in_current_pool(network, options, start_time, phase_timings),
+        Some(number_of_threads) => {
+            info!("Solving in a pool of {} threads", number_of_threads);
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(number_of_threads)
+                .build()
+                .expect("Cannot build the thread pool.")
+                .install(|| solve_in_current_pool(network, options, start_time, phase_timings))
+        }
+    }
+}
+
+fn solve_in_current_pool(
+    network: Arc<Network>,
+    options: SolveOptions,
+    start_time: stdtime::Instant,
+    mut phase_timings: PhaseTimings,
+) -> SolveResult {
+    info!(
+        "Instance with {} vehicle types and {} trips",
+        network.vehicle_types().iter().count(),
+        network.size(),
+    );
+    if let Some(loading) = phase_timings.last() {
+        info!("Instance loaded ({})", loading);
+    }
+
+    let objective = Arc::new(objective::build(&network));
+
+    let start_schedule = match options.initial_schedule.clone() {
+        None => min_cost_flow_schedule(&network, &mut phase_timings),
+        Some(initial_schedule) => {
+            let covers_all_service_trips = network
+                .all_service_nodes()
+                .all(|service_trip| initial_schedule.is_fully_covered(service_trip));
+            let min_cost_flow_schedule = (!covers_all_service_trips)
+                .then(|| min_cost_flow_schedule(&network, &mut phase_timings));
+            let initial_schedule_is_better = phase_timings.measure("initialSchedule", || {
+                min_cost_flow_schedule
+                    .as_ref()
+                    .is_none_or(|min_cost_flow_schedule| {
+                        is_better(&objective, &initial_schedule, min_cost_flow_schedule)
+                    })
+            });
+            phase_timings.add_diagnostics(
+                "coversAllServiceTrips",
+                serde_json::json!(covers_all_service_trips),
+            );
+            phase_timings.add_diagnostics("used", serde_json::json!(initial_schedule_is_better));
+            info!(
+                "Initial schedule with {} vehicles {}, {}",
+                initial_schedule.number_of_vehicles(),
+                if covers_all_service_trips {
+                    "covers all service trips"
+                } else {
+                    "does not cover all service trips"
+                },
+                if initial_schedule_is_better {
+                    "starting from the initial schedule"
+                } else {
+                    "keeping the MinCostFlowSolver schedule"
+                }
+            );
+            match min_cost_flow_schedule {
+                Some(min_cost_flow_schedule) if !initial_schedule_is_better => {
+                    min_cost_flow_schedule
+                }
+                _ => initial_schedule,
+            }
+        }
+    };
+
+    let start_schedule = match &options.warm_start {
+        None => start_schedule,
+        Some(warm_start) => {
+            info!(
+                "Building warm start from {} tours:",
+                warm_start.number_of_tours()
+            );
+            let (warm_start_schedule, skipped_nodes) =
+                phase_timings.measure("warmStart", || warm_start.build_schedule(network.clone()));
+            let warm_start_is_better = is_better(&objective, &warm_start_schedule, &start_schedule);
+            phase_timings.add_diagnostics("skippedNodes", serde_json::json!(skipped_nodes));
+            phase_timings.add_diagnostics("used", serde_json::json!(warm_start_is_better));
+            info!(
+                "Warm start built ({}), {} nodes skipped, {}",
+                phase_timings.last().unwrap(),
+                skipped_nodes,
+                if warm_start_is_better {
+                    "replacing the MinCostFlowSolver schedule"
+                } else {
+                    "keeping the MinCostFlowSolver schedule"
+                }
+            );
+            if warm_start_is_better {
+                warm_start_schedule
+            } else {
+                start_schedule
+            }
+        }
+    };
+
+    let config = network.config();
+    let hints = &config.hints;
+    let start_schedule = if hints.is_empty() {
+        start_schedule
+    } else {
+        info!("Applying {} hints:", hints.len());
+        let (schedule, outcomes) =
+            phase_timings.measure("hints", || start_schedule.apply_hints(hints));
+        for (hint, outcome) in hints.iter().zip(outcomes.iter()) {
+            match outcome {
+                Ok(()) => info!("Hint {:?} applied", hint),
+                Err(reason) => warn!("Hint {:?} failed: {}", hint, reason),
+            }
+        }
+        phase_timings.add_diagnostics("outcomes", hint_outcomes_to_json(hints, &outcomes));
+        info!("Hints applied ({})", phase_timings.last().unwrap());
+        schedule
+    };
+
+    // an under-resourced instance is reported before the local search burns its time limit
+    let aborted_as_infeasible = match config.infeasibility_check {
+        None => false,
+        Some(infeasibility_check) => {
+            let uncovered_demand = phase_timings.measure("feasibilityAssessment", || {
+                uncovered_demand(&start_schedule)
+            });
+            let likely_infeasible = uncovered_demand.share() > infeasibility_check.threshold;
+            let abort =
+                likely_infeasible && infeasibility_check.policy == InfeasibilityPolicy::Abort;
+            phase_timings.add_diagnostics(
+                "uncoveredDemand",
+                uncovered_demand_to_json(&start_schedule, &uncovered_demand),
+            );
+            phase_timings.add_diagnostics("likelyInfeasible", serde_json::json!(likely_infeasible));
+            phase_timings.add_diagnostics("aborted", serde_json::json!(abort));
+            if likely_infeasible {
+                phase_timings.add_diagnostics(
+                    "bottlenecks",
+                    bottlenecks_to_json(&start_schedule, &uncovered_demand),
+                );
+                warn!(
+                    "LIKELY INFEASIBLE: {:.1}% of the passengers are not carried by vehicles of \
+                     regular depots in the start schedule (threshold {:.1}%), {}",
+                    uncovered_demand.share() * 100.0,
+                    infeasibility_check.threshold * 100.0,
+                    if abort {
+                        "aborting"
+                    } else {
+                        "continuing with the local search"
+                    }
+                );
+            }
+            info!("Feasibility assessed ({})", phase_timings.last().unwrap());
+            abort
+        }
+    };
+
+    let start_schedule_with_info = ScheduleWithInfo::new(
+        start_schedule,
+        SwapInfo::NoSwap,
+        "Result from min cost flow solver".to_string(),
+    );
+
+    let convergence = options
+        .convergence
+        .clone()
+        .unwrap_or_else(|| ConvergenceRecorder::new(MAX_CONVERGENCE_POINTS));
+    let milestone_recorder = (!config.milestones.is_empty())
+        .then(|| MilestoneRecorder::new(&config.milestones, start_time));
+    if let Some(milestone_recorder) = &milestone_recorder {
+        milestone_recorder.incumbent(
+            start_schedule_with_info.get_schedule(),
+            objective.objective_value_to_json(
+                objective
+                    .evaluate(start_schedule_with_info.clone())
+                    .objective_value(),
+            ),
+        );
+    }
+    let milestones = || {
+        milestone_recorder
+            .as_ref()
+            .map(|milestone_recorder| milestone_recorder.finish())
+            .unwrap_or_default()
+    };
+
+    if options.mode == SolveMode::StartSchedule || aborted_as_infeasible {
+        if aborted_as_infeasible {
+            info!("Likely infeasible, returning MinCostFlowSolver solution as final solution");
+        } else {
+            info!("Start schedule mode, returning MinCostFlowSolver solution as final solution");
+        }
+        let solution = objective.evaluate(start_schedule_with_info);
+        return SolveResult::new(
+            solution,
+            objective,
+            start_time,
+            phase_timings,
+            milestones(),
+            convergence,
+        );
+    }
+
+    let progress_sink = match (&options.progress_sink, &milestone_recorder) {
+        (Some(progress_sink), Some(milestone_recorder)) => Some(
+            progress_sink
+                .clone()
+                .and(milestone_recorder.progress_sink()),
+        ),
+        (Some(progress_sink), None) => Some(progress_sink.clone()),
+        (None, Some(milestone_recorder)) => Some(milestone_recorder.progress_sink()),
+        (None, None) => None,
+    };
+    // the convergence is always recorded
+    let progress_sink = Some(match progress_sink {
+        Some(progress_sink) => progress_sink.and(convergence.progress_sink(start_time)),
+        None => convergence.progress_sink(start_time),
+    });
+
+    // the local search and the transition optimization share the time limit
+    let time_limit = options
+        .time_limit
+        .into_iter()
+        .chain(
+            config
+                .time_limit
+                .map(|time_limit| stdtime::Duration::from_secs(time_limit.in_sec().unwrap())),
+        )
+        .min();
+    let deadline = time_limit.map(|time_limit| stdtime::Instant::now() + time_limit);
+    let is_cancelled = || {
+        options
+            .cancellation
+            .as_ref()
+            .is_some_and(|cancellation| cancellation.load(Ordering::Relaxed))
+    };
+
+    let solution = if network.service_trips_mutually_unreachable() {
+        info!(
+            "Service trips are mutually unreachable, returning MinCostFlowSolver solution as final solution"
+        );
+        objective.evaluate(start_schedule_with_info.clone())
+    } else if network.maintenance_considered() {
+        info!("Starting local search:");
+        let initial_objective_value = objective.objective_value_to_json(
+            objective
+                .evaluate(start_schedule_with_info.clone())
+                .objective_value(),
+        );
+        info!("Initial objective value: {}", initial_objective_value);
+        convergence.record("localSearch", start_time.elapsed(), initial_objective_value);
+
+        let operator_weights = solver::local_search::build_operator_weights(&network);
+        let trace_writer = build_trace_writer(&network, operator_weights.clone(), || {
+            objective.objective_value_to_json(
+                objective
+                    .evaluate(start_schedule_with_info.clone())
+                    .objective_value(),
+            )
+        });
+        let progress_sink = match (progress_sink.clone(), &trace_writer) {
+            (Some(progress_sink), Some(trace_writer)) => {
+                Some(progress_sink.and(trace_writer.progress_sink()))
+            }
+            (None, Some(trace_writer)) => Some(trace_writer.progress_sink()),
+            (progress_sink, None) => progress_sink,
+        };
+        // twoPhase is ignored with timePartitioning or a focus window
+        let mut fleet_phase_schedule = None;
+        let solution = phase_timings.measure_with_iterations("localSearch", |steps| match &network
+            .config()
+            .time_partitioning
+        {
+            Some(_) => solver::local_search::solve_time_partitioned(
+                start_schedule_with_info,
+                network.clone(),
+                steps,
+                operator_weights.clone(),
+                time_limit,
+                progress_sink.clone(),
+                options.cancellation.clone(),
+            ),
+            None if network.config().two_phase && options.focus_window.is_none() => {
+                let (solution, schedule) = solver::local_search::solve_two_phase(
+                    start_schedule_with_info,
+                    network.clone(),
+                    steps,
+                    operator_weights.clone(),
+                    time_limit,
+                    progress_sink.clone(),
+                    options.cancellation.clone(),
+                );
+                fleet_phase_schedule = Some(schedule);
+                solution
+            }
+            None => {
+                let local_search_solver = match options.focus_window {
+                    Some(focus_window) => solver::local_search::build_focused_local_search_solver(
+                        network.clone(),
+                        steps,
+                        operator_weights.clone(),
+                        time_limit,
+                        focus_window,
+                        progress_sink.clone(),
+                        options.cancellation.clone(),
+                    ),
+                    None => solver::local_search::build_local_search_solver(
+                        network.clone(),
+                        steps,
+                        operator_weights.clone(),
+                        time_limit,
+                        progress_sink.clone(),
+                        options.cancellation.clone(),
+                    ),
+                };
+
+                local_search_solver.solve(start_schedule_with_info)
+            }
+        });
+        if let Some(operator_weights) = &operator_weights {
+            phase_timings.add_diagnostics("operatorWeights", operator_weights.to_json());
+        }
+        if let Some(fleet_phase_schedule) = fleet_phase_schedule {
+            phase_timings.add_diagnostics(
+                "twoPhase",
+                two_phase_to_json(&objective, fleet_phase_schedule, &solution),
+            );
+        }
+        if let Some(Err(error)) = trace_writer.map(|trace_writer| trace_writer.finish()) {
+            warn!("{}", error);
+        }
+        if let Some(time_limit) = time_limit {
+            let time_limit_reached = phase_timings.last().unwrap().duration() >= time_limit;
+            phase_timings
+                .add_diagnostics("timeLimitReached", serde_json::json!(time_limit_reached));
+        }
+        if options.cancellation.is_some() {
+            phase_timings.add_diagnostics("cancelled", serde_json::json!(is_cancelled()));
+        }
+        info!("Local search finished ({})", phase_timings.last().unwrap());
+        solution
+    } else {
+        info!(
+            "Maintenance is not considered, returning MinCostFlowSolver solution as final solution"
+        );
+        objective.evaluate(start_schedule_with_info.clone())
+    };
+
+    let solution = if network.config().maintenance.hard
+        && solution.solution().get_schedule().maintenance_violation() > 0
+    {
+        info!("Repairing maintenance violation:");
+        let repaired_schedule = phase_timings
+            .measure_with_iterations("maintenanceRepair", |steps| {
+                repair_maintenance_violation(solution.solution().get_schedule().clone(), steps)
+            });
+        info!(
+            "Maintenance repair finished ({})",
+            phase_timings.last().unwrap()
+        );
+        objective.evaluate(ScheduleWithInfo::new(
+            repaired_schedule,
+            SwapInfo::NoSwap,
+            "Schedule after maintenance repair".to_string(),
+        ))
+    } else {
+        solution
+    };
+
+    // optimize transitions
+    info!("Optimizing transitions:");
+    let schedule = solution.solution().get_schedule();
+    let cycle_cache = Arc::new(TransitionCycleTspCache::default());
+    let optimized_transitions =
+        phase_timings.measure_with_iterations("transitionOptimization", |steps| {
+            let mut optimized_transitions: HashMap<VehicleTypeIdx, Transition> = HashMap::new();
+            for vehicle_type in network.vehicle_types().iter() {
+                let remaining_time = deadline
+                    .map(|deadline| deadline.saturating_duration_since(stdtime::Instant::now()));
+                if schedule.vehicles_iter(vehicle_type).next().is_none()
+                    || remaining_time == Some(stdtime::Duration::ZERO)
+                    || is_cancelled()
+                {
+                    // unused vehicle types keep their (empty) transition, after the time limit
+                    // or the cancellation the transitions of the start schedule are kept
+                    optimized_transitions.insert(
+                        vehicle_type,
+                        schedule.next_day_transition_of(vehicle_type).clone(),
+                    );
+                    continue;
+                }
+                info!(
+                    "Optimizing transitions for vehicle type {}",
+                    network.vehicle_types().get(vehicle_type).unwrap()
+                );
+                let start_transition = TransitionWithInfo::new(
+                    schedule.next_day_transition_of(vehicle_type).clone(),
+                    "Initial transition".to_string(),
+                );
+                // built per vehicle type, as each solve gets the remaining time
+                let improved_transition = build_transition_local_search_solver(
+                    schedule,
+                    network.clone(),
+                    steps.clone(),
+                    cycle_cache.clone(),
+                    remaining_time,
+                )
+                .solve(start_transition)
+                .unwrap()
+                .unwrap_transition();
+
+                optimized_transitions.insert(vehicle_type, improved_transition);
+
+                // the types that are not optimized yet keep the transitions of the schedule
+                let transitions = network
+                    .vehicle_types()
+                    .iter()
+                    .map(|vehicle_type| {
+                        let transition = optimized_transitions.get(&vehicle_type).cloned();
+                        (
+                            vehicle_type,
+                            transition.unwrap_or_else(|| {
+                                schedule.next_day_transition_of(vehicle_type).clone()
+                            }),
+                        )
+                    })
+                    .collect();
+                convergence.record(
+                    "transitionOptimization",
+                    start_time.elapsed(),
+                    objective.objective_value_to_json(
+                        objective
+                            .evaluate(ScheduleWithInfo::new(
+                                schedule.set_next_day_transitions(transitions),
+                                SwapInfo::NoSwap,
+                                String::new(),
+                            ))
+                            .objective_value(),
+                    ),
+                );
+            }
+            optimized_transitions
+        });
+    phase_timings.add_diagnostics("transitionCycleCache", cycle_cache.to_json());
+    if let Some(deadline) = deadline {
+        phase_timings.add_diagnostics(
+            "timeLimitReached",
+            serde_json::json!(stdtime::Instant::now() >= deadline),
+        );
+    }
+    if options.cancellation.is_some() {
+        phase_timings.add_diagnostics("cancelled", serde_json::json!(is_cancelled()));
+    }
+    let schedule_with_optimized_transitions =
+        schedule.set_next_day_transitions(optimized_transitions);
+    info!("Transition optimized ({})", phase_timings.last().unwrap());
+    for vehicle_type in network.vehicle_types().iter() {
+        let cycles: Vec<Vec<String>> = schedule_with_optimized_transitions
+            .next_day_transition_of(vehicle_type)
+            .cycles_iter()
+            .map(|cycle| cycle.iter().map(|vehicle| vehicle.to_string()).collect())
+            .collect();
+        debug!(
+            "Next day transition cycles for vehicle type {}: {:?}",
+            network.vehicle_types().get(vehicle_type).unwrap(),
+            cycles
+        );
+    }
+
+    // reassign end depots to be consistent with transitions
+    let final_schedule =
+        schedule_with_optimized_transitions.reassign_end_depots_consistent_with_transitions();
+    let final_schedule_with_info = ScheduleWithInfo::new(
+        final_schedule,
+        SwapInfo::NoSwap,
+        "Final schedule after reassigning end depots".to_string(),
+    );
+    let final_solution = objective.evaluate(final_schedule_with_info);
+
+    let result = SolveResult::new(
+        final_solution,
+        objective,
+        start_time,
+        phase_timings,
+        milestones(),
+        convergence,
+    );
+
+    for warning in overflow_depot_warnings(result.schedule())
+        .into_iter()
+        .chain(maintenance_hard_constraint_warnings(result.schedule()))
+    {
+        warn!("{}", warning);
+    }
+
+    info!(
+        "Objective value: {}",
+        result
+            .objective()
+            .objective_value_to_json(result.solution().objective_value())
+    );
+
+    info!("Phase timings: {}", result.phase_timings().to_json());
+    info!(
+        "Running time: {:0.2}sec",
+        result.runtime_duration().as_secs_f32()
+    );
+
+    result
+}
+
+/// For each hint: the hint as given in the input, whether it was applied and otherwise the
+/// reason.
+/// The schedule of the min-cost flow solver with improved depots.
+fn min_cost_flow_schedule(network: &Arc<Network>, phase_timings: &mut PhaseTimings) -> Schedule {
+    info!("Solve with MinCostFlowSolver:");
+    let start_schedule = phase_timings.measure("minCostFlow", || {
+        MinCostFlowSolver::initialize(network.clone()).solve()
+    });
+    info!(
+        "MinCostFlowSolver computed schedule ({})",
+        phase_timings.last().unwrap()
+    );
+
+    let start_schedule =
+        phase_timings.measure("depotImprovement", || start_schedule.improve_depots(None));
+    info!("Depots improved ({})", phase_timings.last().unwrap());
+    start_schedule
+}
+
+/// true if schedule has a smaller objective value than other.
+fn is_better(
+    objective: &Objective<ScheduleWithInfo>,
+    schedule: &Schedule,
+    other: &Schedule,
+) -> bool {
+    objective
+        .evaluate(ScheduleWithInfo::new(
+            schedule.clone(),
+            SwapInfo::NoSwap,
+            String::new(),
+        ))
+        .objective_value()
+        < objective
+            .evaluate(ScheduleWithInfo::new(
+                other.clone(),
+                SwapInfo::NoSwap,
+                String::new(),
+            ))
+            .objective_value()
+}
+
+/// Diagnostics of parameters.solver.twoPhase: the objective values (of the usual objective)
+/// after the fleet phase and after the quality phase, and the fleet frozen after the fleet phase.
+fn two_phase_to_json(
+    objective: &Objective<ScheduleWithInfo>,
+    fleet_phase_schedule: Schedule,
+    solution: &EvaluatedSolution<ScheduleWithInfo>,
+) -> serde_json::Value {
+    let network = fleet_phase_schedule.get_network().clone();
+    let frozen_fleet_per_vehicle_type: serde_json::Map<String, serde_json::Value> = network
+        .vehicle_types()
+        .iter()
+        .map(|vehicle_type| {
+            (
+                network
+                    .vehicle_types()
+                    .get(vehicle_type)
+                    .unwrap()
+                    .id()
+                    .clone(),
+                serde_json::json!(fleet_phase_schedule.vehicles_iter(vehicle_type).count()),
+            )
+        })
+        .collect();
+    serde_json::json!({
+        "frozenFleetSize": fleet_phase_schedule.number_of_vehicles(),
+        "frozenFleetSizePerVehicleType": frozen_fleet_per_vehicle_type,
+        "fleetPhase": objective.objective_value_to_json(
+            objective
+                .evaluate(ScheduleWithInfo::new(
+                    fleet_phase_schedule,
+                    SwapInfo::NoSwap,
+                    String::new(),
+                ))
+                .objective_value(),
+        ),
+        "qualityPhase": objective.objective_value_to_json(solution.objective_value()),
+    })
+}
+
+/// Diagnostics of parameters.infeasibilityThreshold: the passengers that are not carried by
+/// vehicles of regular depots in the start schedule.
+fn uncovered_demand_to_json(
+    schedule: &Schedule,
+    uncovered_demand: &UncoveredDemand,
+) -> serde_json::Value {
+    let network = schedule.get_network();
+    serde_json::json!({
+        "share": uncovered_demand.share(),
+        "passengers": uncovered_demand.passengers,
+        "totalPassengers": uncovered_demand.total_passengers,
+        "serviceTripCount": uncovered_demand.service_trips.len(),
+        "serviceTrips": uncovered_demand
+            .service_trips
+            .iter()
+            .map(|&service_trip| network.node(service_trip).id())
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// The bottlenecks of a likely infeasible instance: the regular depots that lack capacity (see
+/// missing_capacities) and, per vehicle type with uncovered passengers, the vehicles placed at the
+/// overflow depot and the fixed fleet size (if any). The former are fixed by depot capacity, the
+/// latter by a larger fleet.
+fn bottlenecks_to_json(
+    schedule: &Schedule,
+    uncovered_demand: &UncoveredDemand,
+) -> serde_json::Value {
+    let network = schedule.get_network();
+    let overflow_vehicles = schedule.vehicles_using_overflow_depot();
+    let vehicle_types: Vec<serde_json::Value> = uncovered_demand
+        .per_vehicle_type
+        .iter()
+        .map(|(&vehicle_type, &passengers)| {
+            serde_json::json!({
+                "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+                "uncoveredPassengers": passengers,
+                "overflowVehicles": overflow_vehicles
+                    .iter()
+                    .filter(|&&vehicle| schedule.vehicle_type_of(vehicle).unwrap() == vehicle_type)
+                    .count(),
+                "fixedFleetSize": network.fixed_fleet_size_of(vehicle_type),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "depots": missing_depot_capacities_to_json(schedule),
+        "vehicleTypes": vehicle_types,
+    })
+}
+
+fn hint_outcomes_to_json(hints: &[Hint], outcomes: &[Result<(), String>]) -> serde_json::Value {
+    serde_json::Value::Array(
+        hints
+            .iter()
+            .zip(outcomes.iter())
+            .map(|(hint, outcome)| {
+                let mut json = serde_json::json!({
+                    "hint": hint_to_json(hint),
+                    "applied": outcome.is_ok(),
+                });
+                if let Err(reason) = outcome {
+                    json["reason"] = serde_json::json!(reason);
+                }
+                json
+            })
+            .collect(),
+    )
+}
+
+fn hint_to_json(hint: &Hint) -> serde_json::Value {
+    match hint {
+        Hint::MergeTours {
+            vehicle,
+            into_vehicle,
+        } => serde_json::json!({
+            "mergeTours": {"vehicle": vehicle, "intoVehicle": into_vehicle}
+        }),
+        Hint::MoveTrip { trip, to_vehicle } => serde_json::json!({
+            "moveTrip": {"trip": trip, "toVehicle": to_vehicle}
+        }),
+        Hint::AssignDepot { vehicle, depot } => serde_json::json!({
+            "assignDepot": {"vehicle": vehicle, "depot": depot}
+        }),
+    }
+}
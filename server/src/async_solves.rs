@@ -0,0 +1,238 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use solver::progress::{Improvement, ProgressSink};
+
+use crate::convergence::{ConvergenceRecorder, MAX_CONVERGENCE_POINTS};
+use crate::problem::Problem;
+use crate::SolveOptions;
+
+#[derive(Clone)]
+enum SolveState {
+    Queued,
+    Running,
+    Finished(Arc<Vec<u8>>), // the serialized output
+    Failed(Problem),
+}
+
+/// The latest improvement of the local search of a solve.
+struct Progress {
+    iteration: u32,
+    objective_value: serde_json::Value, // see Objective::objective_value_to_json
+    last_swap: String,
+}
+
+impl From<Improvement> for Progress {
+    fn from(improvement: Improvement) -> Progress {
+        Progress {
+            iteration: improvement.iteration,
+            objective_value: improvement.objective_value,
+            last_swap: improvement.description,
+        }
+    }
+}
+
+struct Entry {
+    state: SolveState,
+    submitted: Instant,
+    ended: Option<Instant>,
+    cancellation: Arc<AtomicBool>, // passed to the solve (see SolveOptions::cancellation)
+    progress: Arc<Mutex<Option<Progress>>>, // written by the progress sink of the solve
+    convergence: Arc<ConvergenceRecorder>, // passed to the solve (see SolveOptions::convergence)
+}
+
+/// Solves submitted to /solve_async by their job id. A solve is queued until its background
+/// task starts, its result (or problem) is kept until it is deleted.
+#[derive(Default)]
+pub struct AsyncSolveStore {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl AsyncSolveStore {
+    pub fn new() -> AsyncSolveStore {
+        AsyncSolveStore::default()
+    }
+
+    /// Returns the options for the solve: the cancellation flag that is set by cancel, the
+    /// progress sink that records the improvements for progress and the convergence recorder
+    /// read by convergence.
+    pub fn queue(&self, job_id: &str) -> SolveOptions {
+        self.queue_at(job_id, Instant::now())
+    }
+
+    pub fn start(&self, job_id: &str) {
+        self.set_state(job_id, SolveState::Running, None)
+    }
+
+    pub fn finish(&self, job_id: &str, result: Result<Vec<u8>, Problem>) {
+        self.finish_at(job_id, result, Instant::now())
+    }
+
+    /// {"jobId": String, "status": "queued" | "running" | "finished" | "failed", "elapsedSeconds":
+    /// Float (since the submission, until the end for finished and failed solves)}
+    pub fn status(&self, job_id: &str) -> Result<serde_json::Value, Problem> {
+        self.status_at(job_id, Instant::now())
+    }
+
+    /// The status (see status) together with the latest improvement of the local search:
+    /// {"iteration": Int, "objectiveValue": Object | null, "lastSwap": String | null} (iteration 0
+    /// and null before the first improvement).
+    pub fn progress(&self, job_id: &str) -> Result<serde_json::Value, Problem> {
+        self.progress_at(job_id, Instant::now())
+    }
+
+    /// The status (see status) together with the objective value of the incumbents so far:
+    /// {"convergence": Array} as info.convergence of the output.
+    pub fn convergence(&self, job_id: &str) -> Result<serde_json::Value, Problem> {
+        let mut convergence = self.status(job_id)?;
+        let entries = self.entries.lock().unwrap();
+        // the solve might have been deleted in the meantime
+        let entry = entries.get(job_id).ok_or_else(|| unknown_job(job_id))?;
+        convergence["convergence"] = entry.convergence.to_json();
+        Ok(convergence)
+    }
+
+    /// The output of a finished solve, the problem of a failed solve. Not found if the solve
+    /// does not exist or has not ended yet.
+    pub fn result(&self, job_id: &str) -> Result<Arc<Vec<u8>>, Problem> {
+        let entries = self.entries.lock().unwrap();
+        match entries.get(job_id).map(|entry| &entry.state) {
+            None => Err(unknown_job(job_id)),
+            Some(SolveState::Queued) | Some(SolveState::Running) => {
+                Err(Problem::not_found(format!(
+                    "Job {} has not finished yet, see /status/{}.",
+                    job_id, job_id
+                )))
+            }
+            Some(SolveState::Finished(body)) => Ok(body.clone()),
+            Some(SolveState::Failed(problem)) => Err(problem.clone()),
+        }
+    }
+
+    /// Requests the cancellation of a queued or running solve: the solve returns the best
+    /// schedule found so far (with info.cancelled set). Repeated cancellations are accepted, ended
+    /// solves cannot be cancelled.
+    pub fn cancel(&self, job_id: &str) -> Result<(), Problem> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(job_id).ok_or_else(|| unknown_job(job_id))?;
+        match entry.state {
+            SolveState::Queued | SolveState::Running => {
+                entry.cancellation.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            SolveState::Finished(_) | SolveState::Failed(_) => Err(Problem::solve_ended(job_id)),
+        }
+    }
+
+    /// Only ended solves can be deleted.
+    pub fn remove(&self, job_id: &str) -> Result<(), Problem> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(job_id).map(|entry| &entry.state) {
+            None => Err(unknown_job(job_id)),
+            Some(SolveState::Queued) | Some(SolveState::Running) => {
+                Err(Problem::solve_in_progress(job_id))
+            }
+            Some(SolveState::Finished(_)) | Some(SolveState::Failed(_)) => {
+                entries.remove(job_id);
+                Ok(())
+            }
+        }
+    }
+
+    fn queue_at(&self, job_id: &str, now: Instant) -> SolveOptions {
+        let cancellation = Arc::new(AtomicBool::new(false));
+        let progress: Arc<Mutex<Option<Progress>>> = Arc::new(Mutex::new(None));
+        let convergence = ConvergenceRecorder::new(MAX_CONVERGENCE_POINTS);
+        self.entries.lock().unwrap().insert(
+            job_id.to_string(),
+            Entry {
+                state: SolveState::Queued,
+                submitted: now,
+                ended: None,
+                cancellation: cancellation.clone(),
+                progress: progress.clone(),
+                convergence: convergence.clone(),
+            },
+        );
+        SolveOptions {
+            cancellation: Some(cancellation),
+            convergence: Some(convergence),
+            progress_sink: Some(ProgressSink::new(move |improvement: Improvement, _| {
+                *progress.lock().unwrap() = Some(Progress::from(improvement));
+            })),
+            ..SolveOptions::default()
+        }
+    }
+
+    fn finish_at(&self, job_id: &str, result: Result<Vec<u8>, Problem>, now: Instant) {
+        let state = match result {
+            Ok(body) => SolveState::Finished(Arc::new(body)),
+            Err(problem) => SolveState::Failed(problem),
+        };
+        self.set_state(job_id, state, Some(now))
+    }
+
+    fn set_state(&self, job_id: &str, state: SolveState, ended: Option<Instant>) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(job_id) {
+            entry.state = state;
+            entry.ended = ended;
+        }
+    }
+
+    fn status_at(&self, job_id: &str, now: Instant) -> Result<serde_json::Value, Problem> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(job_id).ok_or_else(|| unknown_job(job_id))?;
+        Ok(serde_json::json!({
+            "jobId": job_id,
+            "status": match entry.state {
+                SolveState::Queued => "queued",
+                SolveState::Running => "running",
+                SolveState::Finished(_) => "finished",
+                SolveState::Failed(_) => "failed",
+            },
+            "elapsedSeconds": entry
+                .ended
+                .unwrap_or(now)
+                .duration_since(entry.submitted)
+                .as_secs_f64(),
+        }))
+    }
+
+    fn progress_at(&self, job_id: &str, now: Instant) -> Result<serde_json::Value, Problem> {
+        let mut progress = self.status_at(job_id, now)?;
+        let entries = self.entries.lock().unwrap();
+        // the solve might have been deleted in the meantime
+        let entry = entries.get(job_id).ok_or_else(|| unknown_job(job_id))?;
+        let latest = entry.progress.lock().unwrap();
+        progress["iteration"] =
+            serde_json::json!(latest.as_ref().map_or(0, |latest| latest.iteration));
+        progress["objectiveValue"] = latest.as_ref().map_or(serde_json::Value::Null, |latest| {
+            latest.objective_value.clone()
+        });
+        progress["lastSwap"] =
+            serde_json::json!(latest.as_ref().map(|latest| latest.last_swap.as_str()));
+        Ok(progress)
+    }
+}
+
+fn unknown_job(job_id: &str) -> Problem {
+    Problem::not_found(format!("Job {} does not exist (or was deleted).", job_id))
+}
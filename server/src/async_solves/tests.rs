@@ -0,0 +1,201 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use axum::http::StatusCode;
+
+use solver::local_search::neighborhood::swaps::SwapInfo;
+use solver::progress::Improvement;
+
+use super::{AsyncSolveStore, Progress};
+use crate::problem::Problem;
+
+#[test]
+fn async_solve_goes_through_its_states() {
+    // ARRANGE
+    let store = AsyncSolveStore::new();
+    let now = Instant::now();
+
+    // ACT
+    store.queue_at("1", now);
+    let queued = store.status_at("1", now + Duration::from_secs(1)).unwrap();
+    let result_while_queued = store.result("1");
+    store.start("1");
+    let running = store.status_at("1", now + Duration::from_secs(2)).unwrap();
+    store.finish_at("1", Ok(b"{}".to_vec()), now + Duration::from_secs(5));
+    let finished = store.status_at("1", now + Duration::from_secs(60)).unwrap();
+
+    // ASSERT
+    assert_eq!(queued["status"], "queued");
+    assert_eq!(queued["elapsedSeconds"], 1.0);
+    assert_eq!(
+        result_while_queued.unwrap_err().status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(running["status"], "running");
+    assert_eq!(finished["status"], "finished");
+    assert_eq!(finished["elapsedSeconds"], 5.0);
+    assert_eq!(*store.result("1").unwrap(), b"{}".to_vec());
+    assert_eq!(
+        store.status("2").unwrap_err().status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn failed_async_solve_returns_its_problem() {
+    // ARRANGE
+    let store = AsyncSolveStore::new();
+    store.queue("1");
+    store.start("1");
+
+    // ACT
+    store.finish(
+        "1",
+        Err(Problem::internal("The solver panicked.".to_string())),
+    );
+
+    // ASSERT
+    assert_eq!(store.status("1").unwrap()["status"], "failed");
+    assert_eq!(
+        store.result("1").unwrap_err().status(),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[test]
+fn only_ended_async_solves_can_be_deleted() {
+    // ARRANGE
+    let store = AsyncSolveStore::new();
+    store.queue("1");
+    store.start("1");
+
+    // ACT
+    let removed_while_running = store.remove("1");
+    store.finish("1", Ok(b"{}".to_vec()));
+    let removed_after_finish = store.remove("1");
+
+    // ASSERT
+    assert_eq!(
+        removed_while_running.unwrap_err().status(),
+        StatusCode::CONFLICT
+    );
+    assert!(removed_after_finish.is_ok());
+    assert_eq!(
+        store.result("1").unwrap_err().status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        store.remove("1").unwrap_err().status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn only_queued_or_running_async_solves_can_be_cancelled() {
+    // ARRANGE
+    let store = AsyncSolveStore::new();
+    let cancellation = store.queue("1").cancellation.unwrap();
+    store.start("1");
+
+    // ACT
+    let cancelled_while_running = store.cancel("1");
+    let flag_after_cancel = cancellation.load(Ordering::Relaxed);
+    store.finish("1", Ok(b"{}".to_vec()));
+    let cancelled_after_finish = store.cancel("1");
+
+    // ASSERT
+    assert!(cancelled_while_running.is_ok());
+    assert!(flag_after_cancel);
+    assert_eq!(
+        cancelled_after_finish.unwrap_err().status(),
+        StatusCode::CONFLICT
+    );
+    assert_eq!(
+        store.cancel("2").unwrap_err().status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn progress_reports_the_latest_improvement() {
+    // ARRANGE
+    let store = AsyncSolveStore::new();
+    let now = Instant::now();
+    store.queue_at("1", now);
+    store.start("1");
+
+    // ACT
+    let before_improvement = store
+        .progress_at("1", now + Duration::from_secs(1))
+        .unwrap();
+    // the sink of the solve options does the same, but needs a schedule
+    *store.entries.lock().unwrap()["1"].progress.lock().unwrap() =
+        Some(Progress::from(Improvement {
+            iteration: 7,
+            objective_value: serde_json::json!({ "costs": 93 }),
+            swap_info: SwapInfo::NoSwap,
+            description: "PathExchange".to_string(),
+        }));
+    let after_improvement = store
+        .progress_at("1", now + Duration::from_secs(2))
+        .unwrap();
+
+    // ASSERT
+    assert_eq!(before_improvement["status"], "running");
+    assert_eq!(before_improvement["iteration"], 0);
+    assert_eq!(
+        before_improvement["objectiveValue"],
+        serde_json::Value::Null
+    );
+    assert_eq!(before_improvement["lastSwap"], serde_json::Value::Null);
+    assert_eq!(after_improvement["elapsedSeconds"], 2.0);
+    assert_eq!(after_improvement["iteration"], 7);
+    assert_eq!(after_improvement["objectiveValue"]["costs"], 93);
+    assert_eq!(after_improvement["lastSwap"], "PathExchange");
+    assert_eq!(
+        store.progress("2").unwrap_err().status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn convergence_is_readable_while_running() {
+    // ARRANGE
+    let store = AsyncSolveStore::new();
+    let options = store.queue("1");
+    store.start("1");
+
+    // ACT
+    let before_improvement = store.convergence("1").unwrap();
+    options.convergence.unwrap().record(
+        "localSearch",
+        Duration::from_secs(3),
+        serde_json::json!({ "costs": 93 }),
+    );
+    let after_improvement = store.convergence("1").unwrap();
+
+    // ASSERT
+    assert_eq!(before_improvement["status"], "running");
+    assert_eq!(before_improvement["convergence"], serde_json::json!([]));
+    let points = &after_improvement["convergence"][0]["points"];
+    assert_eq!(after_improvement["convergence"][0]["phase"], "localSearch");
+    assert_eq!(points[0]["elapsedSeconds"], 3.0);
+    assert_eq!(points[0]["objectiveValue"]["costs"], 93);
+    assert_eq!(
+        store.convergence("2").unwrap_err().status(),
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,124 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use solver::progress::ProgressSink;
+
+/// Maximal number of points of a phase in info.convergence (beyond, the points are thinned out).
+pub const MAX_CONVERGENCE_POINTS: usize = 500;
+
+#[derive(Debug)]
+struct ConvergencePoint {
+    elapsed: Duration, // since the start of the solve
+    objective_value: serde_json::Value,
+}
+
+#[derive(Debug)]
+struct ConvergenceSegment {
+    phase: &'static str,
+    points: Vec<ConvergencePoint>,
+}
+
+/// Records the objective value of the incumbent over time (e.g., for time-to-quality plots)
+/// without keeping any schedules. The points are grouped into segments of consecutive points of
+/// the same phase (e.g., localSearch and transitionOptimization).
+/// A segment keeps at most max_points points: once it has more, every second point is dropped
+/// (the first and the last point are always kept), so the points remain spread over the whole
+/// phase.
+#[derive(Debug)]
+pub struct ConvergenceRecorder {
+    max_points: usize,
+    segments: Mutex<Vec<ConvergenceSegment>>,
+}
+
+impl ConvergenceRecorder {
+    pub fn new(max_points: usize) -> Arc<ConvergenceRecorder> {
+        Arc::new(ConvergenceRecorder {
+            max_points: max_points.max(2),
+            segments: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Records the improvements of the local search (phase localSearch), the elapsed time is
+    /// measured from start_time.
+    pub fn progress_sink(self: &Arc<Self>, start_time: Instant) -> ProgressSink {
+        let recorder = self.clone();
+        ProgressSink::new(move |improvement, _| {
+            recorder.record(
+                "localSearch",
+                start_time.elapsed(),
+                improvement.objective_value,
+            )
+        })
+    }
+
+    pub fn record(
+        &self,
+        phase: &'static str,
+        elapsed: Duration,
+        objective_value: serde_json::Value,
+    ) {
+        let mut segments = self.segments.lock().unwrap();
+        if segments.last().is_none_or(|segment| segment.phase != phase) {
+            segments.push(ConvergenceSegment {
+                phase,
+                points: Vec::new(),
+            });
+        }
+        let points = &mut segments.last_mut().unwrap().points;
+        points.push(ConvergencePoint {
+            elapsed,
+            objective_value,
+        });
+        if points.len() > self.max_points {
+            let last = points.len() - 1;
+            let mut index = 0;
+            points.retain(|_| {
+                let keep = index % 2 == 0 || index == last;
+                index += 1;
+                keep
+            });
+        }
+    }
+
+    /// [{"phase": String, "points": [{"elapsedSeconds": Float, "objectiveValue": Object}]}]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Array(
+            self.segments
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|segment| {
+                    serde_json::json!({
+                        "phase": segment.phase,
+                        "points": segment
+                            .points
+                            .iter()
+                            .map(|point| {
+                                serde_json::json!({
+                                    "elapsedSeconds": point.elapsed.as_secs_f64(),
+                                    "objectiveValue": point.objective_value,
+                                })
+                            })
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect(),
+        )
+    }
+}
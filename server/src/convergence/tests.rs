@@ -0,0 +1,74 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use super::ConvergenceRecorder;
+
+#[test]
+fn points_are_grouped_by_phase() {
+    // ARRANGE
+    let recorder = ConvergenceRecorder::new(10);
+
+    // ACT
+    recorder.record("localSearch", Duration::from_secs(1), serde_json::json!(5));
+    recorder.record("localSearch", Duration::from_secs(2), serde_json::json!(4));
+    recorder.record(
+        "transitionOptimization",
+        Duration::from_secs(3),
+        serde_json::json!(3),
+    );
+
+    // ASSERT
+    assert_eq!(
+        recorder.to_json(),
+        serde_json::json!([
+            {
+                "phase": "localSearch",
+                "points": [
+                    { "elapsedSeconds": 1.0, "objectiveValue": 5 },
+                    { "elapsedSeconds": 2.0, "objectiveValue": 4 },
+                ]
+            },
+            {
+                "phase": "transitionOptimization",
+                "points": [{ "elapsedSeconds": 3.0, "objectiveValue": 3 }]
+            },
+        ])
+    );
+}
+
+#[test]
+fn long_phases_are_thinned_out() {
+    // ARRANGE
+    let recorder = ConvergenceRecorder::new(4);
+
+    // ACT
+    for second in 0..6 {
+        recorder.record(
+            "localSearch",
+            Duration::from_secs(second),
+            serde_json::json!(100 - second),
+        );
+    }
+
+    // ASSERT
+    let elapsed: Vec<f64> = recorder.to_json()[0]["points"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|point| point["elapsedSeconds"].as_f64().unwrap())
+        .collect();
+    // 0..4 is thinned out to 0, 2, 4 when the fifth point arrives
+    assert_eq!(elapsed, vec![0.0, 2.0, 4.0, 5.0]);
+}
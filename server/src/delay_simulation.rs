@@ -0,0 +1,141 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+
+use model::base_types::{NodeIdx, VehicleIdx};
+use model::json_serialisation::{
+    try_load_rolling_stock_problem_instance_from_json, ValidationError,
+};
+use rapid_time::Duration;
+use serde::{Deserialize, Serialize};
+use solution::delay_propagation::propagate_delays;
+use solution::json_serialisation::schedule_from_json;
+
+use crate::check_instance_hash;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulationInput {
+    instance: serde_json::Value,
+    schedule: serde_json::Value,
+    instance_hash: Option<String>, // info.instanceHash of the output the schedule was taken from
+    #[serde(default)]
+    force: bool, // use the schedule even if instance_hash does not match the instance
+    delays: Vec<PrimaryDelay>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrimaryDelay {
+    id: String, // departure segment or maintenance slot
+    delay: u64, // in seconds
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExpectedDelay {
+    id: String,
+    scheduled_start: String,
+    delay: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonBrokenConnection {
+    vehicle: String,
+    from: String,
+    to: String,
+    arrival_delay: u64,
+    buffer: u64,
+}
+
+/// Propagates primary delays through a schedule (see propagate_delays).
+/// The input consists of the instance, the schedule (as in the output of /solve, only the fleet
+/// is used) and the primary delays: [{"id": departure segment or maintenance slot, "delay":
+/// seconds}]. With the optional instanceHash (info.instanceHash of the output of /solve) the
+/// schedule is checked to belong to the instance (see check_instance_hash).
+/// Returns the expected delay of each delayed activity and the broken connections, where the
+/// vehicle ids are the ones of the given schedule. Nothing is optimized.
+pub fn simulate_delays(
+    input_data: serde_json::Value,
+) -> Result<serde_json::Value, Vec<ValidationError>> {
+    let input: SimulationInput = serde_json::from_value(input_data)
+        .map_err(|e| vec![simulation_error("invalidSimulationInput", e.to_string())])?;
+    let network = try_load_rolling_stock_problem_instance_from_json(input.instance)?;
+    check_instance_hash(&network, input.instance_hash.as_deref(), input.force)
+        .map_err(|error| vec![error])?;
+    let (schedule, vehicle_ids) = schedule_from_json(input.schedule, network.clone())
+        .map_err(|message| vec![simulation_error("invalidSchedule", message)])?;
+
+    let nodes: HashMap<&str, NodeIdx> = network
+        .coverable_nodes()
+        .map(|node| (network.node(node).id(), node))
+        .collect();
+    let mut primary_delays = Vec::new();
+    let mut errors = Vec::new();
+    for primary_delay in input.delays.iter() {
+        match nodes.get(primary_delay.id.as_str()) {
+            Some(&node) => primary_delays.push((node, Duration::from_seconds(primary_delay.delay))),
+            None => errors.push(simulation_error(
+                "unknownActivity",
+                format!(
+                    "Unknown departure segment or maintenance slot {}.",
+                    primary_delay.id
+                ),
+            )),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let propagation = propagate_delays(&schedule, &primary_delays);
+
+    let vehicle_ids: HashMap<VehicleIdx, String> = vehicle_ids
+        .into_iter()
+        .map(|(id, vehicle)| (vehicle, id))
+        .collect();
+    let node_id = |node: NodeIdx| network.node(node).id().to_string();
+    let delays: Vec<ExpectedDelay> = propagation
+        .delays
+        .iter()
+        .map(|&(node, delay)| ExpectedDelay {
+            id: node_id(node),
+            scheduled_start: network.node(node).start_time().as_iso(),
+            delay: delay.in_sec().unwrap(),
+        })
+        .collect();
+    let broken_connections: Vec<JsonBrokenConnection> = propagation
+        .broken_connections
+        .iter()
+        .map(|connection| JsonBrokenConnection {
+            vehicle: vehicle_ids[&connection.vehicle].clone(),
+            from: node_id(connection.from),
+            to: node_id(connection.to),
+            arrival_delay: connection.arrival_delay.in_sec().unwrap(),
+            buffer: connection.buffer.in_sec().unwrap(),
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "delays": delays,
+        "brokenConnections": broken_connections,
+    }))
+}
+
+fn simulation_error(code: &'static str, message: String) -> ValidationError {
+    ValidationError { code, message }
+}
@@ -0,0 +1,154 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fs::File, io::Read};
+
+use crate::solve_instance;
+
+use super::simulate_delays;
+
+fn small_test_input() -> serde_json::Value {
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    serde_json::from_str(&input_data).unwrap()
+}
+
+/// The id and the first two departure segments of the first vehicle serving at least two
+/// departure segments.
+fn vehicle_with_two_departure_segments(schedule: &serde_json::Value) -> (String, String, String) {
+    schedule["fleet"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|fleet| fleet["vehicles"].as_array().unwrap())
+        .find_map(|vehicle| {
+            let segments = vehicle["departureSegments"].as_array().unwrap();
+            (segments.len() >= 2).then(|| {
+                (
+                    vehicle["id"].as_str().unwrap().to_string(),
+                    segments[0]["departureSegment"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                    segments[1]["departureSegment"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                )
+            })
+        })
+        .unwrap()
+}
+
+#[test]
+fn delay_is_propagated_to_next_departure_segment() {
+    // ARRANGE
+    let instance = small_test_input();
+    let schedule = solve_instance(instance.clone())["schedule"].clone();
+    let (vehicle, first, second) = vehicle_with_two_departure_segments(&schedule);
+    // a delay of one day cannot be absorbed by any buffer
+    let input = serde_json::json!({
+        "instance": instance,
+        "schedule": schedule,
+        "delays": [{"id": first, "delay": 86400}],
+    });
+
+    // ACT
+    let result = simulate_delays(input).unwrap();
+
+    // ASSERT
+    let delays = result["delays"].as_array().unwrap();
+    assert_eq!(delays[0]["id"], first);
+    assert_eq!(delays[0]["delay"], 86400);
+    let second_delay = delays.iter().find(|delay| delay["id"] == second).unwrap();
+    assert!(second_delay["delay"].as_u64().unwrap() > 0);
+    assert!(result["brokenConnections"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|connection| connection["vehicle"] == vehicle
+            && connection["from"] == first
+            && connection["to"] == second
+            && connection["arrivalDelay"] == 86400));
+}
+
+#[test]
+fn no_delays_without_primary_delays() {
+    // ARRANGE
+    let instance = small_test_input();
+    let schedule = solve_instance(instance.clone())["schedule"].clone();
+    let input = serde_json::json!({
+        "instance": instance,
+        "schedule": schedule,
+        "delays": [],
+    });
+
+    // ACT
+    let result = simulate_delays(input).unwrap();
+
+    // ASSERT
+    assert_eq!(
+        result,
+        serde_json::json!({"delays": [], "brokenConnections": []})
+    );
+}
+
+#[test]
+fn unknown_activity_is_rejected() {
+    // ARRANGE
+    let instance = small_test_input();
+    let schedule = solve_instance(instance.clone())["schedule"].clone();
+    let input = serde_json::json!({
+        "instance": instance,
+        "schedule": schedule,
+        "delays": [{"id": "unknown", "delay": 600}],
+    });
+
+    // ACT
+    let errors = simulate_delays(input).unwrap_err();
+
+    // ASSERT
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "unknownActivity");
+    assert!(errors[0].message.contains("unknown"));
+}
+
+#[test]
+fn schedule_of_another_instance_is_rejected() {
+    // ARRANGE
+    let instance = small_test_input();
+    let output = solve_instance(instance.clone());
+    let mut other_instance = instance.clone();
+    other_instance["departures"][0]["segments"][0]["passengers"] = serde_json::json!(1);
+    let input = |instance: &serde_json::Value, force: bool| {
+        serde_json::json!({
+            "instance": instance,
+            "schedule": output["schedule"],
+            "instanceHash": output["info"]["instanceHash"],
+            "force": force,
+            "delays": [],
+        })
+    };
+
+    // ACT
+    let matching_result = simulate_delays(input(&instance, false));
+    let mismatch_errors = simulate_delays(input(&other_instance, false)).unwrap_err();
+    let forced_result = simulate_delays(input(&other_instance, true));
+
+    // ASSERT
+    assert!(matching_result.is_ok());
+    assert_eq!(mismatch_errors.len(), 1);
+    assert_eq!(mismatch_errors[0].code, "instanceHashMismatch");
+    assert!(forced_result.is_ok());
+}
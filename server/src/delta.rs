@@ -0,0 +1,198 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashSet;
+
+use model::json_serialisation::ValidationError;
+use serde_json::{Map, Value};
+
+/// Applies a delta to a previously submitted instance and returns the effective instance.
+/// The delta is an object with the optional fields
+/// - departures, depots, maintenanceSlots: {"added": [..], "removed": [id, ..], "modified": [..]}.
+///   Added elements must have a new id, removed ids must exist and modified elements are JSON
+///   merge patches (RFC 7396) of the element with the same id. Removals are applied first, so an
+///   element can be replaced by removing and adding it.
+/// - parameters: a JSON merge patch of the parameters.
+///
+/// The untouched sections of the base instance are taken over as they are (they are not parsed
+/// again). All problems of the delta are reported at once.
+pub fn apply_delta(base: &Value, delta: &Value) -> Result<Value, Vec<ValidationError>> {
+    let delta = delta.as_object().ok_or_else(|| {
+        vec![invalid_delta(
+            "The delta must be a JSON object.".to_string(),
+        )]
+    })?;
+    let mut instance = base.clone();
+    let mut errors = Vec::new();
+    for (section, section_delta) in delta {
+        match section.as_str() {
+            "departures" | "depots" | "maintenanceSlots" => {
+                apply_section_delta(&mut instance, section, section_delta, &mut errors)
+            }
+            "parameters" => merge_patch(&mut instance["parameters"], section_delta),
+            _ => errors.push(invalid_delta(format!(
+                "Unknown section {} (only departures, depots, maintenanceSlots and parameters can \
+                be changed).",
+                section
+            ))),
+        }
+    }
+    if errors.is_empty() {
+        Ok(instance)
+    } else {
+        Err(errors)
+    }
+}
+
+/// JSON merge patch (RFC 7396): objects are merged recursively, null removes a field, all other
+/// values replace the target.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            let target = target.as_object_mut().unwrap();
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        _ => *target = patch.clone(),
+    }
+}
+
+fn apply_section_delta(
+    instance: &mut Value,
+    section: &str,
+    section_delta: &Value,
+    errors: &mut Vec<ValidationError>,
+) {
+    let Some(section_delta) = section_delta.as_object() else {
+        errors.push(invalid_delta(format!(
+            "The delta of {} must be an object with added, removed and modified.",
+            section
+        )));
+        return;
+    };
+    for key in section_delta.keys() {
+        if !["added", "removed", "modified"].contains(&key.as_str()) {
+            errors.push(invalid_delta(format!(
+                "Unknown field {} in the delta of {} (only added, removed and modified).",
+                key, section
+            )));
+        }
+    }
+    if instance[section].is_null() {
+        instance[section] = Value::Array(Vec::new());
+    }
+    let Some(elements) = instance[section].as_array_mut() else {
+        errors.push(invalid_delta(format!(
+            "The base instance has no list of {}.",
+            section
+        )));
+        return;
+    };
+
+    // removed
+    let removed: HashSet<&str> = list_of(section_delta, "removed", section, errors)
+        .iter()
+        .filter_map(|id| match id.as_str() {
+            Some(id) => Some(id),
+            None => {
+                errors.push(invalid_delta(format!(
+                    "Removed {} must be given by their ids.",
+                    section
+                )));
+                None
+            }
+        })
+        .collect();
+    for &id in removed.iter() {
+        if !elements.iter().any(|element| id_of(element) == Some(id)) {
+            errors.push(invalid_delta(format!(
+                "Cannot remove {} {}: unknown id.",
+                section, id
+            )));
+        }
+    }
+    elements.retain(|element| !id_of(element).is_some_and(|id| removed.contains(id)));
+
+    // modified
+    for patch in list_of(section_delta, "modified", section, errors) {
+        let position = id_of(patch).and_then(|id| {
+            elements
+                .iter()
+                .position(|element| id_of(element) == Some(id))
+        });
+        match position {
+            Some(position) => merge_patch(&mut elements[position], patch),
+            None => errors.push(invalid_delta(format!(
+                "Cannot modify {} {}: unknown id.",
+                section,
+                id_of(patch).unwrap_or("without id")
+            ))),
+        }
+    }
+
+    // added
+    for element in list_of(section_delta, "added", section, errors) {
+        match id_of(element) {
+            Some(id) if elements.iter().any(|existing| id_of(existing) == Some(id)) => {
+                errors.push(invalid_delta(format!(
+                    "Cannot add {} {}: the id already exists.",
+                    section, id
+                )))
+            }
+            Some(_) => elements.push(element.clone()),
+            None => errors.push(invalid_delta(format!("Added {} must have an id.", section))),
+        }
+    }
+}
+
+/// The list of the given field of the section delta (empty if not present).
+fn list_of<'a>(
+    section_delta: &'a Map<String, Value>,
+    field: &str,
+    section: &str,
+    errors: &mut Vec<ValidationError>,
+) -> &'a [Value] {
+    match section_delta.get(field) {
+        None => &[],
+        Some(Value::Array(list)) => list,
+        Some(_) => {
+            errors.push(invalid_delta(format!(
+                "{} of {} must be a list.",
+                field, section
+            )));
+            &[]
+        }
+    }
+}
+
+fn id_of(element: &Value) -> Option<&str> {
+    element.get("id").and_then(Value::as_str)
+}
+
+fn invalid_delta(message: String) -> ValidationError {
+    ValidationError {
+        code: "invalidDelta",
+        message,
+    }
+}
@@ -0,0 +1,101 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use serde_json::json;
+
+use super::{apply_delta, merge_patch};
+
+fn base_instance() -> serde_json::Value {
+    json!({
+        "vehicleTypes": [{"id": "vt1", "capacity": 100, "seats": 50}],
+        "departures": [
+            {"id": "dep1", "vehicleType": "vt1", "segments": []},
+            {"id": "dep2", "vehicleType": "vt1", "segments": []}
+        ],
+        "parameters": {"forbidDeadHeadTrips": false, "costs": {"staff": 100, "serviceTrip": 50}}
+    })
+}
+
+#[test]
+fn merge_patch_test() {
+    // ARRANGE
+    let mut target = json!({"a": 1, "b": {"c": 2, "d": 3}, "e": [1, 2]});
+
+    // ACT
+    merge_patch(
+        &mut target,
+        &json!({"a": null, "b": {"c": 4}, "e": [3], "f": "new"}),
+    );
+
+    // ASSERT
+    assert_eq!(target, json!({"b": {"c": 4, "d": 3}, "e": [3], "f": "new"}));
+}
+
+#[test]
+fn apply_delta_test() {
+    // ARRANGE
+    let base = base_instance();
+    let delta = json!({
+        "departures": {
+            "removed": ["dep1"],
+            "modified": [{"id": "dep2", "vehicleType": "vt2"}],
+            "added": [{"id": "dep3", "vehicleType": "vt1", "segments": []}]
+        },
+        "depots": {"added": [{"id": "depot1", "location": "loc1", "capacity": 3}]},
+        "parameters": {"costs": {"staff": 200}}
+    });
+
+    // ACT
+    let instance = apply_delta(&base, &delta).unwrap();
+
+    // ASSERT
+    assert_eq!(
+        instance["departures"],
+        json!([
+            {"id": "dep2", "vehicleType": "vt2", "segments": []},
+            {"id": "dep3", "vehicleType": "vt1", "segments": []}
+        ])
+    );
+    assert_eq!(
+        instance["depots"],
+        json!([{"id": "depot1", "location": "loc1", "capacity": 3}])
+    );
+    assert_eq!(
+        instance["parameters"],
+        json!({"forbidDeadHeadTrips": false, "costs": {"staff": 200, "serviceTrip": 50}})
+    );
+    assert_eq!(instance["vehicleTypes"], base["vehicleTypes"]);
+}
+
+#[test]
+fn invalid_delta_reports_all_errors() {
+    // ARRANGE
+    let base = base_instance();
+    let delta = json!({
+        "departures": {
+            "removed": ["unknown"],
+            "modified": [{"id": "dep3"}],
+            "added": [{"id": "dep1"}],
+            "renamed": []
+        },
+        "locations": {"added": []}
+    });
+
+    // ACT
+    let errors = apply_delta(&base, &delta).unwrap_err();
+
+    // ASSERT
+    assert_eq!(errors.len(), 5);
+    assert!(errors.iter().all(|error| error.code == "invalidDelta"));
+    assert!(apply_delta(&base, &json!([])).is_err());
+}
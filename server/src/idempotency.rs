@@ -0,0 +1,99 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub enum Submission<J> {
+    /// first submission of the key (or the previous one has expired), a new job was created.
+    New(J),
+    /// repeated submission of the key with the same body, the existing job is returned.
+    Replay(J),
+    /// the key was already used for a different body.
+    Conflict,
+}
+
+struct Entry<J> {
+    body_hash: u64,
+    job: J,
+    submitted: Instant,
+}
+
+/// Retry-safe submission of solve jobs. Clients can attach a key to a request (Idempotency-Key
+/// header or requestId field), a repeated submission of the same key and body then returns the
+/// existing job instead of starting a new solve.
+/// Entries are dropped after the retention period.
+pub struct IdempotencyStore<J> {
+    retention: Duration,
+    entries: Mutex<HashMap<String, Entry<J>>>,
+}
+
+impl<J: Clone> IdempotencyStore<J> {
+    pub fn new(retention: Duration) -> IdempotencyStore<J> {
+        IdempotencyStore {
+            retention,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// create_job is only called if the key is new.
+    pub fn submit(
+        &self,
+        key: &str,
+        body_hash: u64,
+        create_job: impl FnOnce() -> J,
+    ) -> Submission<J> {
+        self.submit_at(key, body_hash, Instant::now(), create_job)
+    }
+
+    fn submit_at(
+        &self,
+        key: &str,
+        body_hash: u64,
+        now: Instant,
+        create_job: impl FnOnce() -> J,
+    ) -> Submission<J> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| now.duration_since(entry.submitted) < self.retention);
+
+        match entries.get(key) {
+            Some(entry) if entry.body_hash == body_hash => Submission::Replay(entry.job.clone()),
+            Some(_) => Submission::Conflict,
+            None => {
+                let job = create_job();
+                entries.insert(
+                    key.to_string(),
+                    Entry {
+                        body_hash,
+                        job: job.clone(),
+                        submitted: now,
+                    },
+                );
+                Submission::New(job)
+            }
+        }
+    }
+}
+
+/// Hash of the request body to recognize identical submissions.
+pub fn hash_body(input_data: &serde_json::Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    input_data.to_string().hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,74 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+
+use super::{hash_body, IdempotencyStore, Submission};
+
+const RETENTION: Duration = Duration::from_secs(3600);
+
+#[test]
+fn replay_returns_existing_job() {
+    // ARRANGE
+    let store: IdempotencyStore<u32> = IdempotencyStore::new(RETENTION);
+    let body = serde_json::json!({"requestId": "abc", "value": 1});
+    let now = Instant::now();
+
+    // ACT
+    let first = store.submit_at("abc", hash_body(&body), now, || 1);
+    let second = store.submit_at("abc", hash_body(&body), now + Duration::from_secs(60), || 2);
+    let other_key = store.submit_at("xyz", hash_body(&body), now, || 3);
+
+    // ASSERT
+    assert!(matches!(first, Submission::New(1)));
+    assert!(matches!(second, Submission::Replay(1)));
+    assert!(matches!(other_key, Submission::New(3)));
+}
+
+#[test]
+fn same_key_different_body_is_conflict() {
+    // ARRANGE
+    let store: IdempotencyStore<u32> = IdempotencyStore::new(RETENTION);
+    let body = serde_json::json!({"value": 1});
+    let other_body = serde_json::json!({"value": 2});
+    let now = Instant::now();
+
+    // ACT
+    let first = store.submit_at("abc", hash_body(&body), now, || 1);
+    let conflict = store.submit_at("abc", hash_body(&other_body), now, || 2);
+    let replay = store.submit_at("abc", hash_body(&body), now, || 3);
+
+    // ASSERT
+    assert!(matches!(first, Submission::New(1)));
+    assert!(matches!(conflict, Submission::Conflict));
+    assert!(matches!(replay, Submission::Replay(1)));
+}
+
+#[test]
+fn expired_key_creates_new_job() {
+    // ARRANGE
+    let store: IdempotencyStore<u32> = IdempotencyStore::new(RETENTION);
+    let body = serde_json::json!({"value": 1});
+    let other_body = serde_json::json!({"value": 2});
+    let now = Instant::now();
+
+    // ACT
+    let first = store.submit_at("abc", hash_body(&body), now, || 1);
+    let after_expiry = store.submit_at("abc", hash_body(&other_body), now + RETENTION, || 2);
+    let replay = store.submit_at("abc", hash_body(&other_body), now + RETENTION, || 3);
+
+    // ASSERT
+    assert!(matches!(first, Submission::New(1)));
+    assert!(matches!(after_expiry, Submission::New(2)));
+    assert!(matches!(replay, Submission::Replay(2)));
+}
@@ -0,0 +1,72 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use model::json_serialisation::{
+    try_load_rolling_stock_problem_instance_from_json, ValidationError,
+};
+use model::network::Network;
+
+/// Loads the instance and reports its size without solving it: the numbers of service trips,
+/// vehicle types, depots (without the overflow depot) and maintenance slots, the total depot
+/// capacity per vehicle type, whether the dead-head matrix covers all locations and the warnings
+/// of the loader (e.g., repaired dead-head trips or trips without passengers).
+/// Invalid input data is returned as the validation errors of the loader.
+pub fn validate_instance(
+    input_data: serde_json::Value,
+) -> Result<serde_json::Value, Vec<ValidationError>> {
+    let network = try_load_rolling_stock_problem_instance_from_json(input_data)?;
+    Ok(create_validation_report_json(&network))
+}
+
+fn create_validation_report_json(network: &Network) -> serde_json::Value {
+    let overflow_depot = network.overflow_depot_idxs().0;
+    let depots: Vec<_> = network
+        .depots_iter()
+        .filter(|&depot| depot != overflow_depot)
+        .map(|depot| network.get_depot(depot))
+        .collect();
+    let depot_capacities: Vec<serde_json::Value> = network
+        .vehicle_types()
+        .iter()
+        .map(|vehicle_type| {
+            serde_json::json!({
+                "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+                "capacity": depots
+                    .iter()
+                    .map(|depot| depot.capacity_for(vehicle_type) as u64)
+                    .sum::<u64>(),
+            })
+        })
+        .collect();
+    let mut locations_without_dead_head_trips: Vec<String> = network
+        .locations()
+        .stations_with_missing_dead_head_trips()
+        .into_iter()
+        .map(|location| network.locations().get_id(location).unwrap())
+        .collect();
+    locations_without_dead_head_trips.sort();
+
+    serde_json::json!({
+        "serviceTrips": network.number_of_service_nodes(),
+        "vehicleTypes": network.vehicle_types().iter().count(),
+        "depots": depots.len(),
+        "maintenanceSlots": network.maintenance_nodes().count(),
+        "depotCapacities": depot_capacities,
+        "deadHeadMatrixComplete": locations_without_dead_head_trips.is_empty(),
+        "locationsWithoutDeadHeadTrips": locations_without_dead_head_trips,
+        "warnings": network.warnings(),
+    })
+}
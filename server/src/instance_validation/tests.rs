@@ -0,0 +1,88 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fs::File, io::Read};
+
+use super::validate_instance;
+
+fn small_test_input() -> serde_json::Value {
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    serde_json::from_str(&input_data).unwrap()
+}
+
+#[test]
+fn report_counts_the_instance() {
+    // ARRANGE
+    let input_data = small_test_input();
+
+    // ACT
+    let report = validate_instance(input_data).unwrap();
+
+    // ASSERT
+    assert_eq!(report["serviceTrips"], 4);
+    assert_eq!(report["vehicleTypes"], 2);
+    assert_eq!(report["depots"], 2); // without the overflow depot
+                                     // maintenance_slot_1 is excluded as unusable (no service trip can reach it)
+    assert_eq!(report["maintenanceSlots"], 1);
+    assert_eq!(
+        report["depotCapacities"],
+        serde_json::json!([
+            {"vehicleType": "IC", "capacity": 505},
+            {"vehicleType": "IR", "capacity": 5},
+        ])
+    );
+    assert_eq!(report["deadHeadMatrixComplete"], true);
+    assert_eq!(
+        report["locationsWithoutDeadHeadTrips"],
+        serde_json::json!([])
+    );
+    assert!(report["warnings"].is_array());
+}
+
+#[test]
+fn report_lists_the_warnings_of_the_loader() {
+    // ARRANGE
+    let mut input_data = small_test_input();
+    input_data["departures"][0]["segments"][0]["passengers"] = 0.into();
+    input_data["departures"][0]["segments"][0]["seated"] = 0.into();
+
+    // ACT
+    let report = validate_instance(input_data).unwrap();
+
+    // ASSERT
+    let codes: Vec<&str> = report["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| warning["code"].as_str().unwrap())
+        .collect();
+    assert!(codes.contains(&"noPassengers"));
+}
+
+#[test]
+fn invalid_instance_returns_the_validation_errors() {
+    // ARRANGE
+    let mut input_data = small_test_input();
+    input_data["departures"][0]["route"] = serde_json::json!("route_5");
+
+    // ACT
+    let errors = validate_instance(input_data).unwrap_err();
+
+    // ASSERT
+    assert_eq!(
+        errors.iter().map(|error| error.code).collect::<Vec<_>>(),
+        vec!["unknownReference"]
+    );
+}
@@ -0,0 +1,110 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use model::json_serialisation::load_rolling_stock_problem_instance_from_json;
+use model::network::Network;
+
+use crate::convergence::ConvergenceRecorder;
+use crate::warm_start::WarmStart;
+
+/// A finished job that can be the base of a differential solve: the effective instance (as
+/// submitted, after applying the delta) and its result.
+#[derive(Clone)]
+pub struct StoredJob {
+    pub instance: Arc<serde_json::Value>,
+    pub result: Arc<WarmStart>,
+    network: Arc<OnceLock<Arc<Network>>>, // loaded on first use, shared by the clones
+    convergence: Option<Arc<ConvergenceRecorder>>,
+}
+
+impl StoredJob {
+    pub fn new(instance: Arc<serde_json::Value>, result: Arc<WarmStart>) -> StoredJob {
+        StoredJob {
+            instance,
+            result,
+            network: Arc::new(OnceLock::new()),
+            convergence: None,
+        }
+    }
+
+    /// Keeps the convergence of the solve (see SolveOptions::convergence) for
+    /// GET /jobs/{id}/convergence.
+    pub fn with_convergence(self, convergence: Arc<ConvergenceRecorder>) -> StoredJob {
+        StoredJob {
+            convergence: Some(convergence),
+            ..self
+        }
+    }
+
+    pub fn convergence(&self) -> Option<&ConvergenceRecorder> {
+        self.convergence.as_deref()
+    }
+
+    /// The network of the instance as the solver saw it (e.g., for inspecting it). It is only
+    /// loaded on the first call, the instance was already loaded successfully by the solve.
+    pub fn network(&self) -> Arc<Network> {
+        self.network
+            .get_or_init(|| load_rolling_stock_problem_instance_from_json((*self.instance).clone()))
+            .clone()
+    }
+}
+
+struct Entry {
+    job: StoredJob,
+    finished: Instant,
+}
+
+/// Finished jobs by their id. Entries are dropped after the retention period.
+pub struct JobStore {
+    retention: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl JobStore {
+    pub fn new(retention: Duration) -> JobStore {
+        JobStore {
+            retention,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn insert(&self, job_id: &str, job: StoredJob) {
+        self.insert_at(job_id, job, Instant::now())
+    }
+
+    /// None if there is no such job or it has expired.
+    pub fn get(&self, job_id: &str) -> Option<StoredJob> {
+        self.get_at(job_id, Instant::now())
+    }
+
+    fn insert_at(&self, job_id: &str, job: StoredJob, now: Instant) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| now.duration_since(entry.finished) < self.retention);
+        entries.insert(job_id.to_string(), Entry { job, finished: now });
+    }
+
+    fn get_at(&self, job_id: &str, now: Instant) -> Option<StoredJob> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(job_id)
+            .filter(|entry| now.duration_since(entry.finished) < self.retention)
+            .map(|entry| entry.job.clone())
+    }
+}
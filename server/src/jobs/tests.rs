@@ -0,0 +1,54 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::warm_start::WarmStart;
+
+use super::{JobStore, StoredJob};
+
+const RETENTION: Duration = Duration::from_secs(3600);
+
+fn stored_job(value: u32) -> StoredJob {
+    StoredJob::new(
+        Arc::new(serde_json::json!({ "value": value })),
+        Arc::new(WarmStart::default()),
+    )
+}
+
+#[test]
+fn stored_jobs_expire() {
+    // ARRANGE
+    let store = JobStore::new(RETENTION);
+    let now = Instant::now();
+
+    // ACT
+    store.insert_at("1", stored_job(1), now);
+    store.insert_at("2", stored_job(2), now + Duration::from_secs(1800));
+
+    // ASSERT
+    assert_eq!(
+        store
+            .get_at("1", now + Duration::from_secs(60))
+            .unwrap()
+            .instance["value"],
+        1
+    );
+    assert!(store.get_at("3", now).is_none());
+    assert!(store.get_at("1", now + RETENTION).is_none());
+    assert_eq!(
+        store.get_at("2", now + RETENTION).unwrap().instance["value"],
+        2
+    );
+}
@@ -11,145 +11,800 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use im::HashMap;
-use model::base_types::VehicleTypeIdx;
-use model::json_serialisation::load_rolling_stock_problem_instance_from_json;
-use rapid_solve::heuristics::Solver;
+pub mod api;
+pub mod async_solves;
+pub mod convergence;
+pub mod delay_simulation;
+pub mod delta;
+pub mod idempotency;
+pub mod instance_validation;
+pub mod jobs;
+pub mod milestones;
+pub mod network_inspection;
+pub mod problem;
+pub mod run_archive;
+pub mod size_limits;
+pub mod solve_load;
+pub mod streamed_body;
+#[cfg(test)]
+mod tests;
+pub mod transition_evaluation;
+pub mod warm_start;
+pub mod webhook;
+pub mod what_if;
+
+use model::base_types::{Cost, VehicleCount, VehicleTypeIdx};
+use model::config::{
+    ContractDeadHeadAllocation, DepotAssignmentPolicy, DepotMaintenanceCredit, InfeasibilityPolicy,
+    OutputLevel,
+};
+use model::json_serialisation::{
+    load_rolling_stock_problem_instance_from_json,
+    try_load_rolling_stock_problem_instance_from_json, ValidationError,
+};
+use model::network::Network;
+use model::warnings::{Severity, Warning};
 use rapid_solve::objective::EvaluatedSolution;
 use rapid_solve::objective::Objective;
 use rapid_time::{DateTime, Duration};
-use solution::json_serialisation::schedule_to_json;
-use solution::transition::Transition;
-use solver::local_search::neighborhood::swaps::SwapInfo;
+use serde::Serialize;
+use solution::contract_accounting::vehicle_kilometers_per_contract;
+use solution::json_serialisation::{schedule_from_json, schedule_to_json, StreamedScheduleJson};
+use solution::overflow_analysis::{missing_capacities, uncovered_demand};
+use solution::Schedule;
 use solver::local_search::ScheduleWithInfo;
-use solver::min_cost_flow_solver::MinCostFlowSolver;
 use solver::objective;
-use solver::transition_local_search::build_transition_local_search_solver;
-use solver::transition_local_search::TransitionWithInfo;
+use solver::phase_timings::PhaseTimings;
 
 use gethostname::gethostname;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
 use std::sync::Arc;
 use std::time as stdtime;
+use tracing::{error, info, warn};
+
+use api::solve_with_phase_timings;
+pub use api::{solve, SolveMode, SolveOptions, SolveResult};
+use convergence::ConvergenceRecorder;
+use milestones::{milestones_to_json, MilestoneSnapshot};
+use warm_start::WarmStart;
+use webhook::WebhookNotifier;
 
 pub fn solve_instance(input_data: serde_json::Value) -> serde_json::Value {
     let start_time = stdtime::Instant::now();
-    let network = load_rolling_stock_problem_instance_from_json(input_data);
-    println!(
-        "Instance with {} vehicle types and {} trips loaded (elapsed time: {:0.2}sec)",
-        network.vehicle_types().iter().count(),
-        network.size(),
-        start_time.elapsed().as_secs_f32()
-    );
+    let mut phase_timings = PhaseTimings::new();
+    let initial_schedule = input_data.get("initialSchedule").cloned();
+    let network = phase_timings.measure("loading", || {
+        load_rolling_stock_problem_instance_from_json(input_data)
+    });
+    let options = SolveOptions {
+        initial_schedule: load_initial_schedule(initial_schedule, &network)
+            .unwrap_or_else(|error| panic!("Invalid input data: {}", error)),
+        ..SolveOptions::default()
+    };
+    solve_with_phase_timings(network, options, start_time, phase_timings).to_json()
+}
+
+/// Same as solve_instance, but the output json is written directly into the writer without
+/// building the json tree in memory (useful for very large schedules).
+pub fn solve_instance_to_writer<W: io::Write>(
+    input_data: serde_json::Value,
+    writer: W,
+) -> serde_json::Result<()> {
+    let start_time = stdtime::Instant::now();
+    let mut phase_timings = PhaseTimings::new();
+    let initial_schedule = input_data.get("initialSchedule").cloned();
+    let network = phase_timings.measure("loading", || {
+        load_rolling_stock_problem_instance_from_json(input_data)
+    });
+    let options = SolveOptions {
+        initial_schedule: load_initial_schedule(initial_schedule, &network)
+            .unwrap_or_else(|error| panic!("Invalid input data: {}", error)),
+        ..SolveOptions::default()
+    };
+    write_final_solution(network, options, start_time, phase_timings, writer)?;
+    Ok(())
+}
+
+/// Same as solve_instance_to_writer, but invalid input data (e.g., data repairs with
+/// parameters.strictInput) is returned as errors instead of panicking. In this case nothing
+/// is written.
+pub fn try_solve_instance_to_writer<W: io::Write>(
+    input_data: serde_json::Value,
+    writer: W,
+) -> Result<(), SolveError> {
+    try_solve_instance_with_warm_start_to_writer(input_data, None, writer)?;
+    Ok(())
+}
+
+/// Same as try_solve_instance_to_writer, but the solve can start from the result of a previous
+/// solve of a similar instance (see SolveOptions::warm_start).
+/// Returns the result as warm start for follow-up solves.
+pub fn try_solve_instance_with_warm_start_to_writer<W: io::Write>(
+    input_data: serde_json::Value,
+    warm_start: Option<Arc<WarmStart>>,
+    writer: W,
+) -> Result<WarmStart, SolveError> {
+    let options = SolveOptions {
+        warm_start,
+        ..SolveOptions::default()
+    };
+    try_solve_to_writer(input_data, options, None, writer)
+}
+
+/// Same as try_solve_instance_with_warm_start_to_writer, but the webhook of parameters.webhook
+/// (if present) is notified about the solve under the given job id (see webhook).
+/// The options (e.g., the warm start, a cancellation flag or a progress sink) are passed to the
+/// solve, a given progress sink receives the improvements together with the webhook.
+pub fn try_solve_job_to_writer<W: io::Write>(
+    job_id: &str,
+    input_data: serde_json::Value,
+    options: SolveOptions,
+    writer: W,
+) -> Result<WarmStart, SolveError> {
+    try_solve_to_writer(input_data, options, Some(job_id), writer)
+}
+
+fn try_solve_to_writer<W: io::Write>(
+    input_data: serde_json::Value,
+    mut options: SolveOptions,
+    job_id: Option<&str>,
+    writer: W,
+) -> Result<WarmStart, SolveError> {
+    let start_time = stdtime::Instant::now();
+    let mut phase_timings = PhaseTimings::new();
+    let initial_schedule = input_data.get("initialSchedule").cloned();
+    let network = phase_timings.measure("loading", || {
+        try_load_rolling_stock_problem_instance_from_json(input_data)
+    })?;
+    if let Some(initial_schedule) =
+        load_initial_schedule(initial_schedule, &network).map_err(|error| vec![error])?
+    {
+        options.initial_schedule = Some(initial_schedule);
+    }
+    let notifier = job_id.and_then(|job_id| {
+        network
+            .config()
+            .webhook
+            .as_ref()
+            .map(|webhook| WebhookNotifier::spawn(webhook, job_id))
+    });
+    options.progress_sink = match (
+        options.progress_sink.take(),
+        notifier.as_ref().map(|notifier| notifier.progress_sink()),
+    ) {
+        (Some(progress_sink), Some(webhook_sink)) => Some(progress_sink.and(webhook_sink)),
+        (progress_sink, webhook_sink) => progress_sink.or(webhook_sink),
+    };
+    let (result, warnings) =
+        write_final_solution(network, options, start_time, phase_timings, writer)?;
+    if let Some(notifier) = notifier {
+        notifier.finished(result.objective_value(), &warnings);
+    }
+    Ok(result)
+}
+
+/// Why a solve with invalid input data (try_solve_...) failed.
+#[derive(Debug)]
+pub enum SolveError {
+    /// The input data is invalid, nothing has been written.
+    InvalidInput(Vec<ValidationError>),
+    /// The output could not be written (e.g., the connection was closed), it may be incomplete.
+    Output(serde_json::Error),
+}
+
+impl From<Vec<ValidationError>> for SolveError {
+    fn from(errors: Vec<ValidationError>) -> Self {
+        SolveError::InvalidInput(errors)
+    }
+}
+
+impl From<serde_json::Error> for SolveError {
+    fn from(error: serde_json::Error) -> Self {
+        SolveError::Output(error)
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::InvalidInput(errors) => {
+                let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                write!(f, "Invalid input: {}", errors.join(", "))
+            }
+            SolveError::Output(error) => write!(f, "Error writing JSON: {}", error),
+        }
+    }
+}
 
-    let objective = Arc::new(objective::build());
+/// Rebuilds initialSchedule of the input (a schedule as in the output, e.g., the result of the
+/// previous day) on the network, see schedule_from_json. None if the input has no initialSchedule.
+fn load_initial_schedule(
+    initial_schedule: Option<serde_json::Value>,
+    network: &Arc<Network>,
+) -> Result<Option<Schedule>, ValidationError> {
+    let Some(initial_schedule) = initial_schedule else {
+        return Ok(None);
+    };
+    match schedule_from_json(initial_schedule, network.clone()) {
+        Ok((schedule, _)) => Ok(Some(schedule)),
+        Err(message) => {
+            let error = ValidationError {
+                code: "invalidInitialSchedule",
+                message: format!("initialSchedule cannot be used: {}", message),
+            };
+            error!("{}", error);
+            Err(error)
+        }
+    }
+}
 
-    println!("Solve with MinCostFlowSolver:");
-    let min_cost_flow_solver = MinCostFlowSolver::initialize(network.clone());
-    let start_schedule = min_cost_flow_solver.solve();
-    println!(
-        "MinCostFlowSolver computed schedule (elapsed time: {:0.2}sec)",
-        start_time.elapsed().as_secs_f32()
+/// Analyzes the demand of the instance without solving it: for each service trip the demand,
+/// the capacity of its vehicle type and the implied minimal number of vehicles, as well as
+/// histograms per vehicle type and the trips that are structurally unservable (more vehicles
+/// required than the formation limit allows).
+/// Furthermore, the accessibility of the service trips from the depots (with the lists of
+/// accessible trips if list_trips is set).
+pub fn analyze_instance(
+    input_data: serde_json::Value,
+    list_trips: bool,
+) -> Result<serde_json::Value, Vec<ValidationError>> {
+    let network = try_load_rolling_stock_problem_instance_from_json(input_data)?;
+    let mut analysis = create_demand_analysis_json(&network);
+    analysis["depotAccessibility"] = create_depot_accessibility_json(&network, list_trips);
+    Ok(analysis)
+}
+
+/// The schedule is serialized while it is written, so the serialization time is only logged
+/// (it cannot be part of the info of the output).
+/// Returns the final schedule as warm start for follow-up solves and the warnings of the output.
+fn write_final_solution<W: io::Write>(
+    network: Arc<Network>,
+    options: SolveOptions,
+    start_time: stdtime::Instant,
+    phase_timings: PhaseTimings,
+    writer: W,
+) -> serde_json::Result<(WarmStart, Vec<Warning>)> {
+    let result = solve_with_phase_timings(network, options, start_time, phase_timings);
+    let output_json = create_streamed_output_json(
+        result.solution(),
+        result.objective(),
+        result.runtime_duration(),
+        result.phase_timings(),
+        result.milestones(),
+        Some(result.convergence()),
     );
+    let mut phase_timings = result.phase_timings().clone();
+    phase_timings.measure("serialization", || {
+        serde_json::to_writer(writer, &output_json)
+    })?;
+    info!("{}", phase_timings.last().unwrap());
+    Ok((
+        WarmStart::from_schedule(result.schedule()).with_objective_value(
+            result
+                .objective()
+                .objective_value_to_json(result.solution().objective_value()),
+        ),
+        result.warnings(),
+    ))
+}
 
-    let start_schedule_with_info = ScheduleWithInfo::new(
-        start_schedule.improve_depots(None),
-        SwapInfo::NoSwap,
-        "Result from min cost flow solver".to_string(),
+pub fn create_output_json(
+    final_solution: &EvaluatedSolution<ScheduleWithInfo>,
+    objective: &Objective<ScheduleWithInfo>,
+    runtime_duration: stdtime::Duration,
+    phase_timings: &PhaseTimings,
+    milestones: &[MilestoneSnapshot],
+    convergence: Option<&ConvergenceRecorder>,
+) -> serde_json::Value {
+    let schedule = final_solution.solution().get_schedule();
+    let output_level = schedule.get_network().config().output_level;
+    let mut phase_timings = phase_timings.clone();
+    // the schedule is only serialized for the full output
+    let json_output = phase_timings.measure("serialization", || {
+        (output_level == OutputLevel::Full).then(|| schedule_to_json(schedule))
+    });
+    let output = create_output(
+        final_solution,
+        objective,
+        runtime_duration,
+        &phase_timings,
+        milestones,
+        convergence,
+        json_output,
     );
+    serde_json::to_value(output).unwrap()
+}
 
-    let solution = if network.maintenance_considered() {
-        println!("\nStarting local search:\n");
-        println!("Initial objective value:");
-        objective.print_objective_value(
-            objective
-                .evaluate(start_schedule_with_info.clone())
-                .objective_value(),
-        );
-        println!();
+/// The output of a solve, the schedule is either a json value (see schedule_to_json) or
+/// serialized tour by tour (see StreamedScheduleJson).
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputJson<S> {
+    info: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warnings: Option<Vec<Warning>>,
+    objective_value: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maintenance_hard_constraint: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    statistics: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggestions: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    milestones: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schedule: Option<S>,
+}
+
+/// Same content as create_output_json, but the schedule is serialized tour by tour when
+/// the result is written (e.g. with serde_json::to_writer). Sections omitted by the output level
+/// are not computed. The schedules of the milestones are not streamed.
+pub fn create_streamed_output_json<'a>(
+    final_solution: &'a EvaluatedSolution<ScheduleWithInfo>,
+    objective: &Objective<ScheduleWithInfo>,
+    runtime_duration: stdtime::Duration,
+    phase_timings: &PhaseTimings,
+    milestones: &[MilestoneSnapshot],
+    convergence: Option<&ConvergenceRecorder>,
+) -> OutputJson<StreamedScheduleJson<'a>> {
+    let schedule = final_solution.solution().get_schedule();
+    let output_level = schedule.get_network().config().output_level;
+    create_output(
+        final_solution,
+        objective,
+        runtime_duration,
+        phase_timings,
+        milestones,
+        convergence,
+        (output_level == OutputLevel::Full).then(|| StreamedScheduleJson::new(schedule)),
+    )
+}
 
-        let local_search_solver = solver::local_search::build_local_search_solver(network.clone());
+/// The sections of the output depend on the output level, the schedule is only given for the
+/// full output.
+fn create_output<S>(
+    final_solution: &EvaluatedSolution<ScheduleWithInfo>,
+    objective: &Objective<ScheduleWithInfo>,
+    runtime_duration: stdtime::Duration,
+    phase_timings: &PhaseTimings,
+    milestones: &[MilestoneSnapshot],
+    convergence: Option<&ConvergenceRecorder>,
+    schedule_json: Option<S>,
+) -> OutputJson<S> {
+    let schedule = final_solution.solution().get_schedule();
+    let network = schedule.get_network();
+    let with_summary = network.config().output_level != OutputLevel::ObjectiveOnly;
+    OutputJson {
+        info: create_info_json(runtime_duration, phase_timings, &network, convergence),
+        warnings: with_summary.then(|| collect_warnings(schedule)),
+        objective_value: objective.objective_value_to_json(final_solution.objective_value()),
+        maintenance_hard_constraint: create_maintenance_hard_constraint_json(schedule),
+        statistics: with_summary.then(|| create_statistics_json(schedule)),
+        suggestions: with_summary.then(|| create_suggestions_json(schedule)),
+        milestones: (!network.config().milestones.is_empty())
+            .then(|| milestones_to_json(milestones, network.config().output_level)),
+        schedule: schedule_json,
+    }
+}
+
+/// Warnings about the instance (from loading) and the final schedule that are reported to the
+/// user in the output.
+pub fn collect_warnings(schedule: &Schedule) -> Vec<Warning> {
+    let mut warnings = schedule.get_network().warnings().to_vec();
+    warnings.extend(overflow_depot_warnings(schedule));
+    warnings.extend(maintenance_hard_constraint_warnings(schedule));
+    warnings.extend(infeasibility_warnings(schedule));
+    warnings
+}
 
-        local_search_solver.solve(start_schedule_with_info)
+pub fn overflow_depot_warnings(schedule: &Schedule) -> Vec<Warning> {
+    let network = schedule.get_network();
+    let overflow_vehicles = schedule.vehicles_using_overflow_depot();
+    network
+        .vehicle_types()
+        .iter()
+        .filter_map(|vehicle_type| {
+            let vehicle_count = overflow_vehicles
+                .iter()
+                .filter(|&&vehicle| schedule.vehicle_type_of(vehicle).unwrap() == vehicle_type)
+                .count();
+            (vehicle_count > 0).then(|| {
+                let vehicle_type_id = network
+                    .vehicle_types()
+                    .get(vehicle_type)
+                    .unwrap()
+                    .id()
+                    .clone();
+                Warning::new(
+                    "overflowDepotUsed",
+                    Severity::Warning,
+                    format!(
+                        "vehicle type {} uses the overflow depot ({} vehicles). Consider adding more depot capacity for this type (see suggestions.missingDepotCapacities).",
+                        vehicle_type_id,
+                        vehicle_count
+                    ),
+                )
+                .with_context(vec![vehicle_type_id])
+            })
+        })
+        .collect()
+}
+
+pub fn maintenance_hard_constraint_warnings(schedule: &Schedule) -> Vec<Warning> {
+    if schedule.get_network().config().maintenance.hard && schedule.maintenance_violation() > 0 {
+        vec![Warning::new(
+            "maintenanceHardConstraintViolated",
+            Severity::Warning,
+            format!(
+                "maintenance is a hard constraint, but the final schedule still violates maintenance by {}. See maintenanceHardConstraint.",
+                schedule.maintenance_violation()
+            ),
+        )]
     } else {
-        println!("\nMaintenance is not considered, returning MinCostFlowSolver solution as final solution");
-        objective.evaluate(start_schedule_with_info.clone())
-    };
+        vec![]
+    }
+}
 
-    // optimize transitions
-    println!("\nOptimizing transitions:");
-    let start_time_transition_optimization = stdtime::Instant::now();
-    let mut optimized_transitions: HashMap<VehicleTypeIdx, Transition> = HashMap::new();
-    let schedule = solution.solution().get_schedule();
-    let transition_local_search_solver =
-        build_transition_local_search_solver(schedule, network.clone());
-    for vehicle_type in network.vehicle_types().iter() {
-        println!(
-            "\nOptimizing transitions for vehicle type {}",
-            network.vehicle_types().get(vehicle_type).unwrap()
-        );
-        let start_transition = TransitionWithInfo::new(
-            schedule.next_day_transition_of(vehicle_type).clone(),
-            "Initial transition".to_string(),
-        );
-        let improved_transition = transition_local_search_solver
-            .solve(start_transition)
-            .unwrap()
-            .unwrap_transition();
+/// Only with parameters.infeasibilityThreshold: the share of the passengers that are not carried
+/// by vehicles of regular depots exceeds the threshold (see uncovered_demand).
+pub fn infeasibility_warnings(schedule: &Schedule) -> Vec<Warning> {
+    let network = schedule.get_network();
+    let Some(infeasibility_check) = network.config().infeasibility_check else {
+        return vec![];
+    };
+    let uncovered_demand = uncovered_demand(schedule);
+    if uncovered_demand.share() <= infeasibility_check.threshold {
+        return vec![];
+    }
+    let vehicle_type_ids = uncovered_demand
+        .per_vehicle_type
+        .keys()
+        .map(|&vehicle_type| {
+            network
+                .vehicle_types()
+                .get(vehicle_type)
+                .unwrap()
+                .id()
+                .clone()
+        })
+        .collect();
+    vec![Warning::new(
+        "likelyInfeasible",
+        Severity::Warning,
+        format!(
+            "{:.1}% of the passengers are not carried by vehicles of regular depots (threshold {:.1}%), the instance is likely infeasible. See info.phaseTimings.feasibilityAssessment for the bottlenecks.",
+            uncovered_demand.share() * 100.0,
+            infeasibility_check.threshold * 100.0
+        ),
+    )
+    .with_context(vehicle_type_ids)]
+}
 
-        optimized_transitions.insert(vehicle_type, improved_transition);
+/// Only with parameters.maintenance.hard: whether the final schedule satisfies maintenance. If
+/// not, the transition cycles that still violate maintenance and the maintenance slots that have
+/// free tracks left are reported.
+fn create_maintenance_hard_constraint_json(schedule: &Schedule) -> Option<serde_json::Value> {
+    let network = schedule.get_network();
+    if !network.config().maintenance.hard {
+        return None;
     }
-    let schedule_with_optimized_transitions =
-        schedule.set_next_day_transitions(optimized_transitions);
-    println!(
-        "Transition optimized (elapsed time: {:0.2}sec)",
-        start_time_transition_optimization.elapsed().as_secs_f32()
-    );
-    schedule_with_optimized_transitions.print_next_day_transitions();
-
-    // reassign end depots to be consistent with transitions
-    let final_schedule = solution
-        .solution()
-        .get_schedule()
-        .reassign_end_depots_consistent_with_transitions();
-    let final_schedule_with_info = ScheduleWithInfo::new(
-        final_schedule,
-        SwapInfo::NoSwap,
-        "Final schedule after reassigning end depots".to_string(),
-    );
-    let final_solution = objective.evaluate(final_schedule_with_info);
+    let violating_cycles: Vec<serde_json::Value> = network
+        .vehicle_types()
+        .iter()
+        .flat_map(|vehicle_type| {
+            let vehicle_type_id = network.vehicle_types().get(vehicle_type).unwrap().id().clone();
+            schedule
+                .next_day_transition_of(vehicle_type)
+                .cycles_iter()
+                .filter(|cycle| cycle.maintenance_counter() > 0)
+                .map(move |cycle| {
+                    serde_json::json!({
+                        "vehicleType": vehicle_type_id,
+                        "vehicles": cycle.iter().map(|vehicle| vehicle.to_string()).collect::<Vec<_>>(),
+                        "violation": cycle.maintenance_counter(),
+                    })
+                })
+        })
+        .collect();
+    let maintenance_slots_with_free_tracks: Vec<String> = network
+        .maintenance_nodes()
+        .filter(|&maintenance_slot| {
+            schedule
+                .train_formation_of(maintenance_slot)
+                .vehicle_count()
+                < network.track_count_of_maintenance_slot(maintenance_slot)
+        })
+        .map(|maintenance_slot| network.node(maintenance_slot).id().to_string())
+        .collect();
+    Some(serde_json::json!({
+        "satisfied": schedule.maintenance_violation() == 0,
+        "maintenanceViolation": schedule.maintenance_violation(),
+        "violatingCycles": violating_cycles,
+        "maintenanceSlotsWithFreeTracks": maintenance_slots_with_free_tracks,
+    }))
+}
 
-    let end_time = stdtime::Instant::now();
-    let runtime_duration = end_time.duration_since(start_time);
+fn create_statistics_json(schedule: &Schedule) -> serde_json::Value {
+    let network = schedule.get_network();
+    let trips_below_minimal_formation_count: Vec<serde_json::Value> = schedule
+        .service_trips_below_minimal_formation_count()
+        .map(|service_trip| {
+            serde_json::json!({
+                "departureSegment": network.node(service_trip).id(),
+                "minimalFormationCount": network.minimal_formation_count_for(service_trip),
+                "vehicleCount": schedule.train_formation_of(service_trip).vehicle_count(),
+            })
+        })
+        .collect();
+    let fixed_fleet: Vec<serde_json::Value> = network
+        .vehicle_types()
+        .iter()
+        .filter_map(|vehicle_type| {
+            network.fixed_fleet_size_of(vehicle_type).map(|fleet_size| {
+                create_fixed_fleet_coverage_json(schedule, vehicle_type, fleet_size)
+            })
+        })
+        .collect();
+    let vehicles_per_route: Vec<serde_json::Value> = network
+        .service_nodes_by_route()
+        .map(|(route, service_trips)| {
+            serde_json::json!({
+                "route": route,
+                "vehicleCount": schedule.distinct_vehicle_count(service_trips),
+            })
+        })
+        .collect();
+    let vehicle_kilometers_per_contract: Vec<serde_json::Value> =
+        vehicle_kilometers_per_contract(schedule)
+            .into_iter()
+            .map(|contract_kilometers| {
+                serde_json::json!({
+                    "contract": contract_kilometers.contract,
+                    "serviceDistance": contract_kilometers.service_distance.in_meter().unwrap(),
+                    "deadHeadDistance": contract_kilometers.dead_head_distance.in_meter().unwrap(),
+                    "vehicleCount": contract_kilometers.vehicle_count,
+                })
+            })
+            .collect();
+    serde_json::json!({
+        "hitchHikerCount": schedule.count_hitch_hikers(),
+        "tripsBelowMinimalFormationCount": trips_below_minimal_formation_count,
+        "fixedFleet": fixed_fleet,
+        "linePurity": schedule.line_purity(),
+        "vehiclesPerRoute": vehicles_per_route,
+        "vehicleKilometersPerContract": vehicle_kilometers_per_contract,
+        "deadHeadDistance": schedule.dead_head_distance_without_synthetic_legs().in_meter().ok(),
+        "deadHeadTripCount": schedule.dead_head_trip_count(),
+        "syntheticDistance": schedule.synthetic_dead_head_distance().in_meter().ok(),
+        "unplacedVehicleCount": schedule.vehicles_using_overflow_depot().len(),
+        "vehiclesWithoutMaintenance": create_vehicles_without_maintenance_json(schedule),
+        "outOfServiceViolations": create_out_of_service_violations_json(schedule),
+        "peakVehiclesInService": create_peak_vehicles_in_service_json(schedule),
+        "dutyLengths": create_duty_lengths_json(schedule),
+        "minimumInService": create_minimum_in_service_json(schedule),
+    })
+}
 
-    let final_schedule = final_solution.solution().get_schedule();
+/// For each vehicle type the largest number of its vehicles in service at the same time (see
+/// Schedule::in_service_curve).
+fn create_peak_vehicles_in_service_json(schedule: &Schedule) -> Vec<serde_json::Value> {
+    let network = schedule.get_network();
+    network
+        .vehicle_types()
+        .iter()
+        .map(|vehicle_type| {
+            let (peak, time) = schedule.peak_in_service(vehicle_type);
+            serde_json::json!({
+                "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+                "peak": peak,
+                "time": time.map(|time| time.as_iso()),
+            })
+        })
+        .collect()
+}
 
-    let overflow_depot = network.overflow_depot_idxs().0;
+/// For each vehicle type the variance of the duty lengths of its vehicles (in minutes², see
+/// Schedule::duty_length_variance) and a histogram of the duty lengths by full hours (only
+/// non-empty bins).
+fn create_duty_lengths_json(schedule: &Schedule) -> Vec<serde_json::Value> {
+    let network = schedule.get_network();
+    let duty_lengths_per_vehicle_type = schedule.duty_lengths_per_vehicle_type();
+    network
+        .vehicle_types()
+        .iter()
+        .map(|vehicle_type| {
+            let duty_lengths = duty_lengths_per_vehicle_type
+                .get(&vehicle_type)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut histogram: BTreeMap<u64, usize> = BTreeMap::new();
+            for duty_length in duty_lengths {
+                *histogram
+                    .entry(duty_length.in_sec().unwrap() / 3600)
+                    .or_default() += 1;
+            }
+            serde_json::json!({
+                "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+                "variance": Schedule::variance_in_minutes(duty_lengths),
+                "histogram": histogram
+                    .into_iter()
+                    .map(|(hours, count)| serde_json::json!({"hours": hours, "count": count}))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect()
+}
+
+/// Each minimum-in-service requirement of a vehicle type with its shortfall and the in-service
+/// curve within its window.
+fn create_minimum_in_service_json(schedule: &Schedule) -> Vec<serde_json::Value> {
+    let network = schedule.get_network();
+    let vehicle_types = network.vehicle_types();
+    let mut minimum_in_service = Vec::new();
+    for vehicle_type in vehicle_types.iter() {
+        for &(start, end, required) in vehicle_types.minimum_in_service_of(vehicle_type) {
+            let curve: Vec<serde_json::Value> = schedule
+                .in_service_curve_within(vehicle_type, start, end)
+                .into_iter()
+                .map(|(time, count)| {
+                    serde_json::json!({
+                        "time": time.as_iso(),
+                        "inService": count,
+                        "shortfall": required.saturating_sub(count),
+                    })
+                })
+                .collect();
+            let shortfall =
+                schedule.in_service_shortfall_within(vehicle_type, start, end, required);
+            minimum_in_service.push(serde_json::json!({
+                "vehicleType": vehicle_types.get(vehicle_type).unwrap().id(),
+                "windowStart": start.as_iso(),
+                "windowEnd": end.as_iso(),
+                "count": required,
+                "shortfall": shortfall,
+                "curve": curve,
+            }));
+        }
+    }
+    minimum_in_service
+}
+
+/// Each node of a vehicle overlapping an out-of-service window of its vehicle type (always empty
+/// with parameters.outOfService.hard).
+fn create_out_of_service_violations_json(schedule: &Schedule) -> Vec<serde_json::Value> {
+    let network = schedule.get_network();
+    schedule
+        .out_of_service_violations()
+        .into_iter()
+        .map(|(vehicle, node)| {
+            let vehicle_type = schedule.vehicle_type_of(vehicle).unwrap();
+            let (start, end) = network
+                .out_of_service_window_of(node, vehicle_type)
+                .unwrap();
+            serde_json::json!({
+                "vehicle": vehicle.to_string(),
+                "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+                "node": network.node(node).id(),
+                "windowStart": start.as_iso(),
+                "windowEnd": end.as_iso(),
+            })
+        })
+        .collect()
+}
+
+/// Each vehicle whose transition cycle contains no maintenance visit, with the cycle and the
+/// distance accumulated in one pass through the cycle (empty if maintenance is not considered).
+fn create_vehicles_without_maintenance_json(schedule: &Schedule) -> Vec<serde_json::Value> {
+    let network = schedule.get_network();
+    let mut vehicles_without_maintenance = Vec::new();
+    if !network.maintenance_considered() {
+        return vehicles_without_maintenance;
+    }
     for vehicle_type in network.vehicle_types().iter() {
-        if final_schedule.number_of_vehicles_of_same_type_spawned_at(overflow_depot, vehicle_type)
-            > 0
+        let vehicle_type_id = network
+            .vehicle_types()
+            .get(vehicle_type)
+            .unwrap()
+            .id()
+            .clone();
+        for cycle in schedule
+            .next_day_transition_of(vehicle_type)
+            .cycles_without_maintenance(schedule.get_tours())
         {
-            println!(
-            "\x1b[93mnote:\x1b[0m vehicle type {} uses the overflow depot. Consider adding more depot capacity for this type.",
-            vehicle_type
-        );
+            let vehicles: Vec<String> = cycle.iter().map(|vehicle| vehicle.to_string()).collect();
+            for vehicle in vehicles.iter() {
+                vehicles_without_maintenance.push(serde_json::json!({
+                    "vehicle": vehicle,
+                    "vehicleType": vehicle_type_id,
+                    "cycle": vehicles,
+                    "accumulatedDistance": cycle.maintenance_counter(),
+                }));
+            }
         }
     }
+    vehicles_without_maintenance
+}
 
-    println!("\nObjective value:");
-    objective.print_objective_value(final_solution.objective_value());
+/// Possible improvements of the final schedule that are not part of the optimization: pairs of
+/// dead-head trips that could be coupled (see Schedule::dead_head_pairs). As the coupled vehicles
+/// run as one train formation, each pair saves the staff costs of one formation.
+fn create_suggestions_json(schedule: &Schedule) -> serde_json::Value {
+    let network = schedule.get_network();
+    let config = network.config();
+    let dead_head_pairs: Vec<serde_json::Value> = schedule
+        .dead_head_pairs(config.dead_head_pairing_tolerance)
+        .into_iter()
+        .map(|(first, second)| {
+            serde_json::json!({
+                "origin": network.locations().get_id(first.origin()).unwrap(),
+                "destination": network.locations().get_id(first.destination()).unwrap(),
+                "vehicles": [first.vehicle().to_string(), second.vehicle().to_string()],
+                "departures": [first.departure().as_iso(), second.departure().as_iso()],
+            })
+        })
+        .collect();
+    let potential_savings = dead_head_pairs.len() as Cost * config.costs.staff;
+    serde_json::json!({
+        "deadHeadPairs": dead_head_pairs,
+        "deadHeadPairSavings": potential_savings,
+        "missingDepotCapacities": missing_depot_capacities_to_json(schedule),
+    })
+}
 
-    println!("Running time: {:0.2}sec", runtime_duration.as_secs_f32());
+/// The extra depot capacities that would have avoided the overflow depot (see
+/// missing_capacities).
+pub(crate) fn missing_depot_capacities_to_json(schedule: &Schedule) -> Vec<serde_json::Value> {
+    let network = schedule.get_network();
+    missing_capacities(schedule)
+        .into_iter()
+        .map(|missing_capacity| {
+            serde_json::json!({
+                "depot": network.get_depot(missing_capacity.depot).id(),
+                "vehicleType": network.vehicle_types().get(missing_capacity.vehicle_type).unwrap().id(),
+                "spawnCapacity": missing_capacity.spawn,
+                "despawnCapacity": missing_capacity.despawn,
+            })
+        })
+        .collect()
+}
 
-    create_output_json(&final_solution, &objective, runtime_duration)
+/// Coverage that is achieved with the fixed fleet of the given vehicle type.
+fn create_fixed_fleet_coverage_json(
+    schedule: &Schedule,
+    vehicle_type: VehicleTypeIdx,
+    fleet_size: VehicleCount,
+) -> serde_json::Value {
+    let network = schedule.get_network();
+    let (passengers, seated) =
+        network
+            .service_nodes(vehicle_type)
+            .fold((0, 0), |(passengers, seated), service_trip| {
+                (
+                    passengers + network.passengers_of(service_trip),
+                    seated + network.seated_passengers_of(service_trip),
+                )
+            });
+    let (unserved_passengers, unserved_seated) = schedule.unserved_passengers_of_type(vehicle_type);
+    serde_json::json!({
+        "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+        "fixedCount": fleet_size,
+        "vehicleCount": schedule.vehicles_iter(vehicle_type).count(),
+        "overflowVehicleCount": schedule.fixed_fleet_violation_of(vehicle_type),
+        "passengers": passengers,
+        "unservedPassengers": unserved_passengers,
+        "seatedPassengers": seated,
+        "unservedSeatedPassengers": unserved_seated,
+    })
 }
 
-pub fn create_output_json(
-    final_solution: &EvaluatedSolution<ScheduleWithInfo>,
-    objective: &Objective<ScheduleWithInfo>,
+fn create_info_json(
     runtime_duration: stdtime::Duration,
+    phase_timings: &PhaseTimings,
+    network: &Network,
+    convergence: Option<&ConvergenceRecorder>,
 ) -> serde_json::Value {
-    let json_output = schedule_to_json(final_solution.solution().get_schedule());
-    let json_objective_value = objective.objective_value_to_json(final_solution.objective_value());
     let today = DateTime::new("1970-01-01T00:00:00")
         + Duration::from_seconds(
             stdtime::SystemTime::now()
@@ -157,14 +812,410 @@ pub fn create_output_json(
                 .unwrap()
                 .as_secs(),
         );
+    let mut info = serde_json::json!({
+        "runningTime": format!("{:0.2}sec", runtime_duration.as_secs_f32()),
+        "phaseTimings": phase_timings.to_json(),
+        // the solve runs in a pool of this size (see api::solve_with_phase_timings)
+        "numberOfThreads": network
+            .config()
+            .number_of_threads
+            .unwrap_or_else(rayon::current_num_threads),
+        "timestampUTC": today.as_iso(),
+        "hostname": gethostname().into_string().unwrap_or("unknown".to_string()),
+        "configuration": create_configuration_json(network),
+        "instanceHash": network.instance_hash(),
+        "timeLimitReached": phase_timings.iter().any(|phase| {
+            phase.diagnostics().get("timeLimitReached") == Some(&serde_json::Value::Bool(true))
+        }),
+        "cancelled": phase_timings.iter().any(|phase| {
+            phase.diagnostics().get("cancelled") == Some(&serde_json::Value::Bool(true))
+        }),
+    });
+    if let Some(convergence) = convergence {
+        info["convergence"] = convergence.to_json();
+    }
+    info
+}
+
+/// Checks that a schedule given in a request belongs to the given instance. instance_hash is the
+/// info.instanceHash of the output the schedule was taken from, nothing is checked without it.
+/// With force, a mismatch is only logged.
+pub fn check_instance_hash(
+    network: &Network,
+    instance_hash: Option<&str>,
+    force: bool,
+) -> Result<(), ValidationError> {
+    let Some(instance_hash) = instance_hash else {
+        return Ok(());
+    };
+    if instance_hash == network.instance_hash() {
+        return Ok(());
+    }
+    let message = format!(
+        "The schedule was computed for the instance with hash {}, but the given instance has hash \
+        {}. Pass the matching instance (or force: true to use the schedule anyway).",
+        instance_hash,
+        network.instance_hash()
+    );
+    if force {
+        warn!("{}", message);
+        return Ok(());
+    }
+    Err(ValidationError {
+        code: "instanceHashMismatch",
+        message,
+    })
+}
+
+/// The effective configuration the schedule was computed with. It is created from the parsed
+/// structs (not from the request), so defaults are visible. Durations are given in seconds and
+/// distances in meters.
+pub fn create_configuration_json(network: &Network) -> serde_json::Value {
+    let config = network.config();
+    let fixed_fleet: serde_json::Map<String, serde_json::Value> = network
+        .vehicle_types()
+        .iter()
+        .filter_map(|vehicle_type| {
+            network.fixed_fleet_size_of(vehicle_type).map(|fleet_size| {
+                (
+                    network
+                        .vehicle_types()
+                        .get(vehicle_type)
+                        .unwrap()
+                        .id()
+                        .to_string(),
+                    serde_json::json!(fleet_size),
+                )
+            })
+        })
+        .collect();
+    let time_partitioning = config.time_partitioning.as_ref().map(|time_partitioning| {
+        serde_json::json!({
+            "windowLength": time_partitioning.window_length.in_sec().unwrap(),
+            "overlap": time_partitioning.overlap.in_sec().unwrap(),
+            "polishTimeLimit": time_partitioning
+                .polish_time_limit
+                .map(|limit| limit.in_sec().unwrap()),
+        })
+    });
+    let adaptive_operator_weights =
+        config
+            .adaptive_operator_weights
+            .as_ref()
+            .map(|adaptive_operator_weights| {
+                serde_json::json!({
+                    "interval": adaptive_operator_weights.interval,
+                    "minimalShare": adaptive_operator_weights.minimal_share,
+                    "maximalShare": adaptive_operator_weights.maximal_share,
+                    "reactionFactor": adaptive_operator_weights.reaction_factor,
+                    "initialWeights": adaptive_operator_weights.initial_weights,
+                })
+            });
+    let active_vehicle_types = config
+        .active_vehicle_types
+        .as_ref()
+        .map(|active_vehicle_types| {
+            network
+                .vehicle_types()
+                .iter()
+                .filter(|vehicle_type| active_vehicle_types.contains(vehicle_type))
+                .map(|vehicle_type| {
+                    network
+                        .vehicle_types()
+                        .get(vehicle_type)
+                        .unwrap()
+                        .id()
+                        .clone()
+                })
+                .collect::<Vec<_>>()
+        });
+    // the url is not echoed, as it might contain a secret
+    let webhook = config.webhook.as_ref().map(|webhook| {
+        serde_json::json!({
+            "events": webhook
+                .events
+                .iter()
+                .map(|&event| webhook::event_name(event))
+                .collect::<Vec<_>>(),
+            "minIntervalSeconds": webhook.min_interval.in_sec().unwrap(),
+        })
+    });
     serde_json::json!({
-        "info": {
-            "runningTime": format!("{:0.2}sec", runtime_duration.as_secs_f32()),
-            "numberOfThreads": rayon::current_num_threads(),
-            "timestampUTC": today.as_iso(),
-            "hostname": gethostname().into_string().unwrap_or("unknown".to_string()),
+        "solverVersion": env!("CARGO_PKG_VERSION"),
+        "parameters": {
+            "forbidDeadHeadTrips": config.forbid_dead_head_trip,
+            "maximalDeadHeadDuration": config
+                .maximal_dead_head_duration
+                .map(|duration| duration.in_sec().unwrap()),
+            "dayLimitThreshold": config.day_limit_threshold.in_sec().unwrap(),
+            "shunting": {
+                "minimalDuration": config.shunting.minimal.in_sec().unwrap(),
+                "deadHeadTripDuration": config.shunting.dead_head_trip.in_sec().unwrap(),
+            },
+            "maintenance": {
+                "maximalDistance": config.maintenance.maximal_distance.in_meter().unwrap(),
+                "depotCredit": match config.maintenance.depot_credit {
+                    DepotMaintenanceCredit::Full => serde_json::Value::Null,
+                    DepotMaintenanceCredit::Fixed(credit) => {
+                        serde_json::json!(credit.in_meter().unwrap())
+                    }
+                },
+                "hard": config.maintenance.hard,
+            },
+            "costs": {
+                "staff": config.costs.staff,
+                "serviceTrip": config.costs.service_trip,
+                "maintenance": config.costs.maintenance,
+                "deadHeadTrip": config.costs.dead_head_trip,
+                "idle": config.costs.idle,
+                "linePurity": config.costs.line_purity,
+                "hitchHikeBonus": config.costs.hitch_hike_bonus,
+                "deadHeadTripCount": config.costs.dead_head_trip_count,
+                "dutyLengthVariance": config.costs.duty_length_variance,
+                "staffTimeFactors": config
+                    .costs
+                    .staff_time_factors
+                    .iter()
+                    .map(|factor| {
+                        serde_json::json!({
+                            "window": {"start": factor.start, "end": factor.end},
+                            "factor": factor.factor,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            },
+            "timePartitioning": time_partitioning,
+            "fixedFleet": fixed_fleet,
+            "checkCosts": config.check_costs,
+            "formationOrderContinuity": config.formation_order_continuity,
+            "deadHeadPairingTolerance": config.dead_head_pairing_tolerance.in_sec().unwrap(),
+            "collapseLocationGroups": config.collapse_location_groups,
+            "memoryBudget": config.memory_budget,
+            "planningWindow": config.planning_window.map(|(start, end)| serde_json::json!({
+                "start": start.as_iso(),
+                "end": end.as_iso(),
+            })),
+            "adaptiveOperatorWeights": adaptive_operator_weights,
+            "webhook": webhook,
+            "milestones": config
+                .milestones
+                .iter()
+                .map(|milestone| milestone.in_sec().unwrap())
+                .collect::<Vec<_>>(),
+            "outOfService": {
+                "hard": config.out_of_service_hard,
+            },
+            "activeVehicleTypes": active_vehicle_types,
+            "debug": {
+                "traceFile": config.trace_file,
+            },
+            "timeLimit": config.time_limit.map(|time_limit| time_limit.in_sec().unwrap()),
+            "numberOfThreads": config.number_of_threads,
+            "solver": {
+                "twoPhase": config.two_phase,
+            },
+            "infeasibilityThreshold": config
+                .infeasibility_check
+                .map(|infeasibility_check| infeasibility_check.threshold),
+            "infeasibilityPolicy": config.infeasibility_check.map(|infeasibility_check| {
+                match infeasibility_check.policy {
+                    InfeasibilityPolicy::Warn => "warn",
+                    InfeasibilityPolicy::Abort => "abort",
+                }
+            }),
+            "depotAssignment": {
+                "policy": match config.depot_assignment.policy {
+                    DepotAssignmentPolicy::Nearest => "nearest",
+                    DepotAssignmentPolicy::BalanceAware => "balanceAware",
+                    DepotAssignmentPolicy::CostAware => "costAware",
+                },
+                "distanceTolerance": config.depot_assignment.distance_tolerance.in_meter().unwrap(),
+            },
+            "output": {
+                "level": match config.output_level {
+                    OutputLevel::Full => "full",
+                    OutputLevel::Summary => "summary",
+                    OutputLevel::ObjectiveOnly => "objectiveOnly",
+                },
+                "crowdingThresholds": {
+                    "moderate": config.crowding_thresholds.moderate,
+                    "high": config.crowding_thresholds.high,
+                },
+                "contractDeadHeadAllocation": match config.contract_dead_head_allocation {
+                    ContractDeadHeadAllocation::FollowingTrip => "followingTrip",
+                    ContractDeadHeadAllocation::Split => "split",
+                },
+            },
         },
-        "objectiveValue": json_objective_value,
-        "schedule": json_output,
+        "maintenanceConsidered": network.maintenance_considered(),
+        "objective": objective::definition_to_json(network),
+        "neighborhood": solver::local_search::neighborhood_settings_to_json(),
+    })
+}
+
+/// The demand analysis of the service trips (see analyze_instance).
+pub fn create_demand_analysis_json(network: &Network) -> serde_json::Value {
+    let mut histograms: BTreeMap<VehicleTypeIdx, BTreeMap<VehicleCount, usize>> = network
+        .vehicle_types()
+        .iter()
+        .map(|vehicle_type| (vehicle_type, BTreeMap::new()))
+        .collect();
+    let mut structurally_unservable_trips: Vec<&String> = Vec::new();
+
+    let trips: Vec<serde_json::Value> = network
+        .all_service_nodes()
+        .map(|service_trip| {
+            let node = network.node(service_trip);
+            let trip = node.as_service_trip();
+            let vehicle_type = network.vehicle_types().get(trip.vehicle_type()).unwrap();
+            let required_vehicles =
+                network.number_of_vehicles_required_to_serve(vehicle_type.idx(), service_trip);
+            let structurally_unservable = network.is_structurally_unservable(service_trip);
+            *histograms
+                .get_mut(&vehicle_type.idx())
+                .unwrap()
+                .entry(required_vehicles)
+                .or_insert(0) += 1;
+            if structurally_unservable {
+                structurally_unservable_trips.push(trip.id());
+            }
+            serde_json::json!({
+                "id": trip.id(),
+                "route": trip.route(),
+                "departure": node.start_time().as_iso(),
+                "vehicleType": vehicle_type.id(),
+                "passengers": trip.passengers(),
+                "seated": trip.seated(),
+                "capacity": vehicle_type.capacity(),
+                "seats": vehicle_type.seats(),
+                "requiredVehicles": required_vehicles,
+                "minimalFormationCount": network.minimal_formation_count_for(service_trip),
+                "maximalFormationCount": network.maximal_formation_count_for(service_trip),
+                "minimalNumberOfVehicles": network.minimal_number_of_vehicles_for(service_trip),
+                "structurallyUnservable": structurally_unservable,
+            })
+        })
+        .collect();
+
+    let histograms: Vec<serde_json::Value> = histograms
+        .into_iter()
+        .map(|(vehicle_type, histogram)| {
+            let vehicle_type_id = network
+                .vehicle_types()
+                .get(vehicle_type)
+                .unwrap()
+                .id()
+                .clone();
+            let histogram: Vec<serde_json::Value> = histogram
+                .into_iter()
+                .map(|(vehicles, trips)| {
+                    serde_json::json!({"requiredVehicles": vehicles, "numberOfTrips": trips})
+                })
+                .collect();
+            serde_json::json!({
+                "vehicleType": vehicle_type_id,
+                "histogram": histogram,
+            })
+        })
+        .collect();
+
+    let out_of_service_conflicts: Vec<serde_json::Value> = network
+        .out_of_service_conflicts()
+        .into_iter()
+        .map(|(vehicle_type, (start, end), conflicting_trips)| {
+            serde_json::json!({
+                "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+                "windowStart": start.as_iso(),
+                "windowEnd": end.as_iso(),
+                "conflictingTripCount": conflicting_trips.len(),
+                "conflictingTrips": conflicting_trips
+                    .iter()
+                    .map(|&service_trip| network.node(service_trip).id())
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "trips": trips,
+        "histograms": histograms,
+        "structurallyUnservableTrips": structurally_unservable_trips,
+        "outOfServiceConflicts": out_of_service_conflicts,
+    })
+}
+
+/// For each regular depot and vehicle type the number of service trips of this type that are
+/// accessible from the depot (see Network::is_accessible_from_depot), with totals per depot and
+/// per vehicle type. Trips that are accessible from no depot always force the usage of the
+/// overflow depot.
+pub fn create_depot_accessibility_json(network: &Network, list_trips: bool) -> serde_json::Value {
+    let overflow_depot = network.overflow_depot_idxs().0;
+    let mut depots: Vec<_> = network
+        .depots_iter()
+        .filter(|&depot| depot != overflow_depot)
+        .collect();
+    depots.sort_by_key(|&depot| network.get_depot(depot).id().to_string());
+
+    let mut matrix = Vec::new();
+    let mut depot_totals = Vec::new();
+    for &depot in depots.iter() {
+        let depot_id = network.get_depot(depot).id();
+        let mut depot_total = 0;
+        for vehicle_type in network.vehicle_types().iter() {
+            let accessible_trips: Vec<&String> = network
+                .service_nodes(vehicle_type)
+                .filter(|&service_trip| network.is_accessible_from_depot(depot, service_trip))
+                .map(|service_trip| network.node(service_trip).as_service_trip().id())
+                .collect();
+            depot_total += accessible_trips.len();
+            let mut entry = serde_json::json!({
+                "depot": depot_id,
+                "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+                "accessibleTripCount": accessible_trips.len(),
+            });
+            if list_trips {
+                entry["accessibleTrips"] = serde_json::json!(accessible_trips);
+            }
+            matrix.push(entry);
+        }
+        depot_totals.push(serde_json::json!({
+            "depot": depot_id,
+            "accessibleTripCount": depot_total,
+        }));
+    }
+
+    let mut vehicle_type_totals = Vec::new();
+    let mut inaccessible_trips = Vec::new();
+    for vehicle_type in network.vehicle_types().iter() {
+        let mut trip_count = 0;
+        let mut accessible_trip_count = 0;
+        for service_trip in network.service_nodes(vehicle_type) {
+            trip_count += 1;
+            if depots
+                .iter()
+                .any(|&depot| network.is_accessible_from_depot(depot, service_trip))
+            {
+                accessible_trip_count += 1;
+            } else {
+                inaccessible_trips.push(network.node(service_trip).as_service_trip().id());
+            }
+        }
+        vehicle_type_totals.push(serde_json::json!({
+            "vehicleType": network.vehicle_types().get(vehicle_type).unwrap().id(),
+            "tripCount": trip_count,
+            "accessibleTripCount": accessible_trip_count,
+        }));
+    }
+
+    serde_json::json!({
+        "matrix": matrix,
+        "totalsPerDepot": depot_totals,
+        "totalsPerVehicleType": vehicle_type_totals,
+        "tripsAccessibleFromNoDepot": inaccessible_trips,
+        "vehicleTypesWithoutDepot": network
+            .vehicle_types_without_depot()
+            .iter()
+            .map(|&vehicle_type| network.vehicle_types().get(vehicle_type).unwrap().id().clone())
+            .collect::<Vec<_>>(),
     })
 }
@@ -12,27 +12,190 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use std::env;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-use axum::extract::DefaultBodyLimit;
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{DefaultBodyLimit, Json, Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderName, StatusCode};
+use axum::response::{IntoResponse, Response};
+use model::json_serialisation::instance_hash;
+use server::async_solves::AsyncSolveStore;
+use server::convergence::{ConvergenceRecorder, MAX_CONVERGENCE_POINTS};
+use server::delta::apply_delta;
+use server::idempotency::{hash_body, IdempotencyStore, Submission};
+use server::jobs::{JobStore, StoredJob};
+use server::network_inspection::{network_listing, NetworkQuery};
+use server::problem::{catch_panic, Problem};
+use server::run_archive::RunArchive;
+use server::size_limits::SizeLimits;
+use server::solve_load::SolveLoad;
+use server::streamed_body::stream_output;
+use server::SolveOptions;
+use tokio::sync::OnceCell;
+use tracing::{error, info, info_span, Instrument};
+use tracing_subscriber::EnvFilter;
+
+static REQUEST_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// a job is the response (serialized output or problem), it is computed once and shared by
+// repeated submissions
+type Job = Arc<OnceCell<Result<Vec<u8>, Problem>>>;
+
+// how long repeated submissions of an idempotency key return the existing job
+const IDEMPOTENCY_RETENTION: Duration = Duration::from_secs(24 * 3600);
+
+// how long a finished job can be used as the base of a differential solve
+const JOB_RETENTION: Duration = Duration::from_secs(24 * 3600);
+
+pub struct AppState {
+    idempotency_store: IdempotencyStore<Job>,
+    job_store: JobStore,
+    async_solve_store: Arc<AsyncSolveStore>,
+    solve_load: Arc<SolveLoad>,
+    size_limits: SizeLimits,
+    run_archive: Option<Arc<RunArchive>>, // None without --output-dir
+}
+
+/// A solve that is stored in the run archive once it has ended. The input is copied before it is
+/// consumed by the solve.
+struct ArchivedRun {
+    run_archive: Arc<RunArchive>,
+    run_id: String,
+    input_data: serde_json::Value,
+}
+
+impl ArchivedRun {
+    /// None without --output-dir.
+    fn start(state: &AppState, job_id: &str, input_data: &serde_json::Value) -> Option<Self> {
+        state.run_archive.as_ref().map(|run_archive| ArchivedRun {
+            run_archive: run_archive.clone(),
+            run_id: RunArchive::run_id(job_id),
+            input_data: input_data.clone(),
+        })
+    }
+
+    /// Writes the input and the response (output or problem) on a blocking task. A failure is
+    /// only logged, the response is not affected.
+    fn finish(self, response: Result<&[u8], &Problem>) {
+        let output = match response {
+            Ok(body) => body.to_vec(),
+            Err(problem) => serde_json::to_vec(problem).unwrap(),
+        };
+        let span = tracing::Span::current();
+        tokio::task::spawn_blocking(move || {
+            let _guard = span.enter();
+            match self
+                .run_archive
+                .store(&self.run_id, &self.input_data, &output)
+            {
+                Ok(()) => info!("Run {} stored", self.run_id),
+                Err(message) => error!("Run {} could not be stored: {}", self.run_id, message),
+            }
+        });
+    }
+}
+
+/// Writer that keeps a copy of everything it passes on (if there is a copy).
+struct CopyingWriter<'a, W: io::Write> {
+    writer: W,
+    copy: Option<&'a mut Vec<u8>>,
+}
+
+impl<W: io::Write> io::Write for CopyingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        if let Some(copy) = self.copy.as_mut() {
+            copy.extend_from_slice(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
 
 #[tokio::main]
 pub async fn main() {
-    // Parse command line arguments to get the port number
-    let args: Vec<String> = env::args().collect();
-    let port: u16 = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(3000);
+    // Parse command line arguments: server [port] [--output-dir <path>]
+    let mut port: u16 = 3000;
+    let mut output_dir = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--output-dir" {
+            output_dir = Some(args.next().expect("--output-dir requires a path"));
+        } else if let Ok(parsed_port) = arg.parse() {
+            port = parsed_port;
+        }
+    }
+
+    // log level can be configured via RUST_LOG (e.g. RUST_LOG=debug), default is info
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let size_limits = SizeLimits::from_env().unwrap_or_else(|error| panic!("{}", error));
+    info!("Size limits: {}", size_limits.to_json());
+    let run_archive = output_dir.map(|output_dir| {
+        info!("Runs are stored in {}", output_dir);
+        Arc::new(RunArchive::new(output_dir).unwrap_or_else(|error| panic!("{}", error)))
+    });
 
     let app = axum::Router::new()
-        .fallback(axum::routing::get(|| async {
-            "No route! Use /health or /solve."
-        }))
-        .route("/health", axum::routing::get(healthy))
+        .fallback(|| async {
+            Problem::not_found(
+                "No route! Use /health, /solve, /solve_async, /status, /progress, /cancel, \
+                 /result, /jobs, /what-if, /validate, /analyze, /simulate-delay, \
+                 /evaluate-transition or /runs."
+                    .to_string(),
+            )
+        })
+        .route("/health", axum::routing::get(health))
+        .route("/health/simple", axum::routing::get(healthy))
         .route("/solve", axum::routing::post(solve))
-        .layer(DefaultBodyLimit::disable());
+        .route("/solve_async", axum::routing::post(solve_async))
+        .route("/status/:id", axum::routing::get(status))
+        .route("/progress/:id", axum::routing::get(progress))
+        .route("/cancel/:id", axum::routing::post(cancel))
+        .route(
+            "/result/:id",
+            axum::routing::get(result).delete(delete_result),
+        )
+        .route("/jobs", axum::routing::post(submit_job))
+        .route("/jobs/:id/convergence", axum::routing::get(job_convergence))
+        .route(
+            "/jobs/:id/network/:listing",
+            axum::routing::get(job_network),
+        )
+        .route("/what-if", axum::routing::post(what_if))
+        .route("/validate", axum::routing::post(validate))
+        .route("/analyze", axum::routing::post(analyze))
+        .route("/simulate-delay", axum::routing::post(simulate_delay))
+        .route(
+            "/evaluate-transition",
+            axum::routing::post(evaluate_transition),
+        )
+        .route("/runs", axum::routing::get(runs))
+        .route("/runs/:id/output", axum::routing::get(run_output))
+        .layer(DefaultBodyLimit::max(size_limits.max_body_size()))
+        .with_state(Arc::new(AppState {
+            idempotency_store: IdempotencyStore::new(IDEMPOTENCY_RETENTION),
+            job_store: JobStore::new(JOB_RETENTION),
+            async_solve_store: Arc::new(AsyncSolveStore::new()),
+            solve_load: SolveLoad::new(),
+            size_limits,
+            run_archive,
+        }));
 
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port))
         .await
         .unwrap();
-    println!(
+    info!(
         "Server running on port {} (http://localhost:{}/health)",
         port, port
     );
@@ -40,14 +203,508 @@ pub async fn main() {
 }
 
 pub async fn healthy() -> &'static str {
-    println!("Healthy");
+    info!("Healthy");
     "Healthy"
 }
 
+/// Version and load of the server (see SolveLoad::health) with its size limits (see
+/// SizeLimits).
+pub async fn health(State(state): State<Arc<AppState>>) -> Response {
+    info!("Healthy");
+    let mut health = state.solve_load.health();
+    health["limits"] = state.size_limits.to_json();
+    (StatusCode::OK, Json(health)).into_response()
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeOptions {
+    #[serde(default)]
+    list_trips: bool, // list the accessible trips per depot and vehicle type (not only count them)
+}
+
+/// Size report and warnings of the instance without solving it.
+pub async fn validate(
+    State(state): State<Arc<AppState>>,
+    input_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Response, Problem> {
+    info!("New validate request");
+    let Json(input_data) = input_data?;
+    state.size_limits.check(&input_data)?;
+    let report = catch_panic(|| {
+        server::instance_validation::validate_instance(input_data).map_err(Problem::invalid_input)
+    })?;
+    Ok((StatusCode::OK, Json(report)).into_response())
+}
+
+/// Demand analysis of the instance without solving it.
+pub async fn analyze(
+    State(state): State<Arc<AppState>>,
+    options: Result<Query<AnalyzeOptions>, QueryRejection>,
+    input_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Response, Problem> {
+    info!("New analyze request");
+    let Query(options) = options?;
+    let Json(input_data) = input_data?;
+    state.size_limits.check(&input_data)?;
+    let analysis = catch_panic(|| {
+        server::analyze_instance(input_data, options.list_trips).map_err(Problem::invalid_input)
+    })?;
+    Ok((StatusCode::OK, Json(analysis)).into_response())
+}
+
+/// Propagation of primary delays through a given schedule (nothing is solved).
+pub async fn simulate_delay(
+    State(state): State<Arc<AppState>>,
+    input_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Response, Problem> {
+    info!("New delay simulation request");
+    let Json(input_data) = input_data?;
+    state.size_limits.check(&input_data["instance"])?;
+    let simulation = catch_panic(|| {
+        server::delay_simulation::simulate_delays(input_data).map_err(Problem::invalid_input)
+    })?;
+    Ok((StatusCode::OK, Json(simulation)).into_response())
+}
+
+/// Evaluation of the maintenance violations of a given schedule under given next-period
+/// transitions (nothing is solved).
+pub async fn evaluate_transition(
+    State(state): State<Arc<AppState>>,
+    input_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Response, Problem> {
+    info!("New transition evaluation request");
+    let Json(input_data) = input_data?;
+    state.size_limits.check(&input_data["instance"])?;
+    let evaluation = catch_panic(|| {
+        server::transition_evaluation::evaluate_transition(input_data)
+            .map_err(Problem::invalid_input)
+    })?;
+    Ok((StatusCode::OK, Json(evaluation)).into_response())
+}
+
 pub async fn solve(
-    axum::extract::Json(input_data): axum::extract::Json<serde_json::Value>,
-) -> axum::response::Json<serde_json::Value> {
-    println!("\n\n-------------------- New Request --------------------\n");
-    let output = server::solve_instance(input_data);
-    axum::response::Json(output)
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    input_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Response, Problem> {
+    // all events of this request are logged within a span carrying the job id
+    let job_id = REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let span = info_span!("solve", job_id);
+    async move {
+        info!("New request");
+        let Json(input_data) = input_data?;
+        state.size_limits.check(&input_data)?;
+        let archived_run = ArchivedRun::start(&state, &job_id.to_string(), &input_data);
+
+        let idempotency_key = headers
+            .get("Idempotency-Key")
+            .and_then(|value| value.to_str().ok())
+            .or_else(|| input_data.get("requestId").and_then(|id| id.as_str()))
+            .map(String::from);
+
+        let job = match idempotency_key {
+            None => return stream_solve(state, job_id, input_data, archived_run).await,
+            Some(key) => {
+                match state
+                    .idempotency_store
+                    .submit(&key, hash_body(&input_data), Job::default)
+                {
+                    Submission::New(job) => job,
+                    Submission::Replay(job) => {
+                        info!("Repeated submission of key {}, returning existing job", key);
+                        job
+                    }
+                    Submission::Conflict => {
+                        info!("Key {} was already used for a different request", key);
+                        return Err(Problem::idempotency_conflict(&key));
+                    }
+                }
+            }
+        };
+
+        // the output of a keyed submission is kept for the repeated submissions, so it is
+        // serialized into memory (still without building the json tree)
+        let span = tracing::Span::current();
+        let response = job
+            .get_or_init(|| async move {
+                let _running = state.solve_load.start();
+                tokio::task::spawn_blocking(move || {
+                    let _guard = span.enter();
+                    catch_panic(|| {
+                        let mut body: Vec<u8> = Vec::new();
+                        server::try_solve_job_to_writer(
+                            &job_id.to_string(),
+                            input_data,
+                            SolveOptions::default(),
+                            &mut body,
+                        )?;
+                        Ok(body)
+                    })
+                })
+                .await
+                .unwrap()
+            })
+            .await
+            .clone();
+        if let Some(archived_run) = archived_run {
+            archived_run.finish(response.as_deref());
+        }
+        let body = response?;
+        Ok((
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/json")],
+            body,
+        )
+            .into_response())
+    }
+    .instrument(span)
+    .await
+}
+
+// the output is serialized directly into the body while it is sent, the solve runs on a blocking
+// thread within the same span
+async fn stream_solve(
+    state: Arc<AppState>,
+    job_id: u64,
+    input_data: serde_json::Value,
+    archived_run: Option<ArchivedRun>,
+) -> Result<Response, Problem> {
+    let span = tracing::Span::current();
+    let body = stream_output(move |writer| {
+        let _guard = span.enter();
+        let _running = state.solve_load.start();
+        // the run archive needs a copy of the output
+        let mut output = archived_run.as_ref().map(|_| Vec::new());
+        let result = catch_panic(|| {
+            let writer = CopyingWriter {
+                writer,
+                copy: output.as_mut(),
+            };
+            // once the output has started, the error is only visible in the log
+            server::try_solve_job_to_writer(
+                &job_id.to_string(),
+                input_data,
+                SolveOptions::default(),
+                writer,
+            )
+            .inspect_err(|error| info!("Solve failed: {}", error))?;
+            Ok(())
+        });
+        if let Some(archived_run) = archived_run {
+            archived_run.finish(
+                result
+                    .as_ref()
+                    .map(|()| output.as_deref().unwrap_or_default()),
+            );
+        }
+        result
+    })
+    .await?;
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        body,
+    )
+        .into_response())
+}
+
+/// Same solve as /solve, but it runs in the background: the response (202 Accepted) only carries
+/// the id of the job, its state is reported by /status/{id}, its progress by /progress/{id} and
+/// its output by /result/{id}.
+pub async fn solve_async(
+    State(state): State<Arc<AppState>>,
+    input_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Response, Problem> {
+    let job_id = REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let span = info_span!("solve_async", job_id);
+    let Json(input_data) = input_data?;
+    state.size_limits.check(&input_data)?;
+    let job_id = job_id.to_string();
+    span.in_scope(|| info!("New asynchronous request"));
+    let archived_run = ArchivedRun::start(&state, &job_id, &input_data);
+
+    let store = state.async_solve_store.clone();
+    let options = store.queue(&job_id);
+    let queued = state.solve_load.queue();
+    let response = Json(serde_json::json!({ "jobId": job_id }));
+    tokio::task::spawn_blocking(move || {
+        let _guard = span.enter();
+        let _running = queued.start();
+        store.start(&job_id);
+        let result = catch_panic(|| {
+            let mut body: Vec<u8> = Vec::new();
+            server::try_solve_job_to_writer(&job_id, input_data, options, &mut body)?;
+            Ok(body)
+        });
+        info!(
+            "Asynchronous solve {}",
+            if result.is_ok() { "finished" } else { "failed" }
+        );
+        if let Some(archived_run) = archived_run {
+            archived_run.finish(result.as_deref());
+        }
+        store.finish(&job_id, result);
+    });
+    Ok((StatusCode::ACCEPTED, response).into_response())
+}
+
+/// Status of an asynchronous solve (see AsyncSolveStore::status).
+pub async fn status(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Response, Problem> {
+    let status = state.async_solve_store.status(&job_id)?;
+    Ok((StatusCode::OK, Json(status)).into_response())
+}
+
+/// Progress of an asynchronous solve (see AsyncSolveStore::progress).
+pub async fn progress(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Response, Problem> {
+    let progress = state.async_solve_store.progress(&job_id)?;
+    Ok((StatusCode::OK, Json(progress)).into_response())
+}
+
+/// Output of a finished asynchronous solve (the same as the response of /solve).
+pub async fn result(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Response, Problem> {
+    let body = state.async_solve_store.result(&job_id)?;
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        (*body).clone(),
+    )
+        .into_response())
+}
+
+/// Requests the cancellation of an asynchronous solve (see AsyncSolveStore::cancel). The
+/// output is available at /result/{id} once the solve has returned.
+pub async fn cancel(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Response, Problem> {
+    state.async_solve_store.cancel(&job_id)?;
+    info!("Cancellation of asynchronous solve {} requested", job_id);
+    Ok(StatusCode::ACCEPTED.into_response())
+}
+
+pub async fn delete_result(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Response, Problem> {
+    state.async_solve_store.remove(&job_id)?;
+    info!("Asynchronous solve {} deleted", job_id);
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobOptions {
+    base_job: Option<String>, // id of a finished job, the body is then a delta to its instance
+}
+
+/// Solves the instance and keeps it (with its result) for later differential solves.
+/// With baseJob, the body is a delta that is applied to the instance of the base job and the
+/// result of the base job is used as warm start.
+/// The response carries the id of the new job and the hash of the effective instance.
+pub async fn submit_job(
+    State(state): State<Arc<AppState>>,
+    options: Result<Query<JobOptions>, QueryRejection>,
+    input_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Response, Problem> {
+    let job_id = REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let span = info_span!("job", job_id);
+    async move {
+        let Query(options) = options?;
+        let Json(input_data) = input_data?;
+
+        let (instance, warm_start) = match options.base_job {
+            None => {
+                info!("New job");
+                (input_data, None)
+            }
+            Some(base_job_id) => {
+                info!("New job based on job {}", base_job_id);
+                let base_job = state.job_store.get(&base_job_id).ok_or_else(|| {
+                    Problem::not_found(format!(
+                        "Job {} does not exist (or has expired).",
+                        base_job_id
+                    ))
+                })?;
+                let instance =
+                    apply_delta(&base_job.instance, &input_data).map_err(Problem::invalid_input)?;
+                (instance, Some(base_job.result))
+            }
+        };
+        // a delta can enlarge the instance of the base job
+        state.size_limits.check(&instance)?;
+        let instance_hash = instance_hash(&instance);
+        let archived_run = ArchivedRun::start(&state, &job_id.to_string(), &instance);
+        let instance = Arc::new(instance);
+
+        let convergence = ConvergenceRecorder::new(MAX_CONVERGENCE_POINTS);
+        let _running = state.solve_load.start();
+        let solved = catch_panic(|| {
+            let mut body: Vec<u8> = Vec::new();
+            let result = server::try_solve_job_to_writer(
+                &job_id.to_string(),
+                (*instance).clone(),
+                SolveOptions {
+                    warm_start,
+                    convergence: Some(convergence.clone()),
+                    ..SolveOptions::default()
+                },
+                &mut body,
+            )?;
+            Ok((body, result))
+        });
+        if let Some(archived_run) = archived_run {
+            archived_run.finish(solved.as_ref().map(|(body, _)| body.as_slice()));
+        }
+        let (body, result) = solved?;
+        state.job_store.insert(
+            &job_id.to_string(),
+            StoredJob::new(instance, Arc::new(result)).with_convergence(convergence),
+        );
+
+        Ok((
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "application/json".to_string()),
+                (HeaderName::from_static("job-id"), job_id.to_string()),
+                (HeaderName::from_static("instance-hash"), instance_hash),
+            ],
+            body,
+        )
+            .into_response())
+    }
+    .instrument(span)
+    .await
+}
+
+/// The objective value of the incumbents of an asynchronous solve (also while it is running, see
+/// AsyncSolveStore::convergence) or of a finished job (as info.convergence of its output).
+pub async fn job_convergence(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+) -> Result<Response, Problem> {
+    let convergence = match state.async_solve_store.convergence(&job_id) {
+        Ok(convergence) => convergence,
+        Err(problem) => {
+            let job = state.job_store.get(&job_id).ok_or(problem)?;
+            serde_json::json!({
+                "jobId": job_id,
+                "status": "finished",
+                "convergence": job
+                    .convergence()
+                    .map_or(serde_json::json!([]), |convergence| convergence.to_json()),
+            })
+        }
+    };
+    Ok((StatusCode::OK, Json(convergence)).into_response())
+}
+
+/// A page of the locations, trips, depots or maintenance slots of the network of a job (see
+/// network_inspection::network_listing).
+pub async fn job_network(
+    State(state): State<Arc<AppState>>,
+    Path((job_id, listing)): Path<(String, String)>,
+    query: Result<Query<NetworkQuery>, QueryRejection>,
+) -> Result<Response, Problem> {
+    let Query(query) = query?;
+    let job = state.job_store.get(&job_id).ok_or_else(|| {
+        Problem::not_found(format!("Job {} does not exist (or has expired).", job_id))
+    })?;
+    let page = catch_panic(|| network_listing(&job.network(), &listing, &query))?;
+    Ok((StatusCode::OK, Json(page)).into_response())
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhatIfOptions {
+    base_job: String,        // id of a finished job
+    time_limit: Option<u64>, // of the local search in seconds (see what_if::DEFAULT_TIME_LIMIT)
+}
+
+/// Bounded re-optimization of a finished job with a single hypothetical resource addition (see
+/// what_if::evaluate_what_if). The changed instance is kept as a new job.
+pub async fn what_if(
+    State(state): State<Arc<AppState>>,
+    options: Result<Query<WhatIfOptions>, QueryRejection>,
+    input_data: Result<Json<serde_json::Value>, JsonRejection>,
+) -> Result<Response, Problem> {
+    let job_id = REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let span = info_span!("what_if", job_id);
+    async move {
+        let Query(options) = options?;
+        let Json(input_data) = input_data?;
+        info!("New what-if based on job {}", options.base_job);
+        let base_job = state.job_store.get(&options.base_job).ok_or_else(|| {
+            Problem::not_found(format!(
+                "Job {} does not exist (or has expired).",
+                options.base_job
+            ))
+        })?;
+        let time_limit = options
+            .time_limit
+            .map(Duration::from_secs)
+            .unwrap_or(server::what_if::DEFAULT_TIME_LIMIT);
+
+        let (report, job) = catch_panic(|| {
+            server::what_if::evaluate_what_if(&base_job, input_data, time_limit)
+                .map_err(Problem::invalid_input)
+        })?;
+        state.job_store.insert(&job_id.to_string(), job);
+
+        Ok((
+            StatusCode::OK,
+            [(HeaderName::from_static("job-id"), job_id.to_string())],
+            Json(report),
+        )
+            .into_response())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Ids of the runs stored in the output directory (see RunArchive), oldest first.
+pub async fn runs(State(state): State<Arc<AppState>>) -> Result<Response, Problem> {
+    let run_archive = stored_runs(&state)?;
+    let run_ids = tokio::task::spawn_blocking(move || run_archive.run_ids())
+        .await
+        .map_err(|error| Problem::internal(error.to_string()))?
+        .map_err(Problem::internal)?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "runs": run_ids }))).into_response())
+}
+
+/// Output of a run stored in the output directory (see RunArchive).
+pub async fn run_output(
+    State(state): State<Arc<AppState>>,
+    Path(run_id): Path<String>,
+) -> Result<Response, Problem> {
+    let run_archive = stored_runs(&state)?;
+    let id = run_id.clone();
+    let output = tokio::task::spawn_blocking(move || run_archive.output(&id))
+        .await
+        .map_err(|error| Problem::internal(error.to_string()))?
+        .map_err(Problem::internal)?
+        .ok_or_else(|| Problem::not_found(format!("Run {} does not exist.", run_id)))?;
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        output,
+    )
+        .into_response())
+}
+
+fn stored_runs(state: &AppState) -> Result<Arc<RunArchive>, Problem> {
+    state.run_archive.clone().ok_or_else(|| {
+        Problem::not_found(
+            "Runs are not stored, start the server with --output-dir <path>.".to_string(),
+        )
+    })
 }
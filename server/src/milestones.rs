@@ -0,0 +1,166 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use model::config::OutputLevel;
+use solution::json_serialisation::schedule_to_json;
+use solution::Schedule;
+use solver::progress::ProgressSink;
+
+/// The best schedule known at a milestone (see parameters.milestones).
+pub struct MilestoneSnapshot {
+    pub label: String,
+    pub milestone: Duration,
+    pub found_after: Duration, // elapsed time at which the schedule became the incumbent
+    pub objective_value: serde_json::Value,
+    pub schedule: Schedule,
+}
+
+struct Incumbent {
+    schedule: Schedule,
+    objective_value: serde_json::Value,
+    found_after: Duration,
+}
+
+struct MilestoneState {
+    pending: VecDeque<Duration>, // ascending
+    incumbent: Option<Incumbent>,
+    snapshots: Vec<MilestoneSnapshot>,
+}
+
+/// Keeps the incumbent of a solve and snapshots it at each milestone (elapsed time since the
+/// start of the solve). A milestone is only recognized with the next incumbent after it (or at
+/// the end of the solve), the snapshot is the incumbent before. Later improvements never replace
+/// a snapshot.
+/// If no incumbent exists yet at a milestone (e.g., the min-cost flow is still running), the
+/// first incumbent is taken.
+pub struct MilestoneRecorder {
+    start_time: Instant,
+    state: Mutex<MilestoneState>,
+}
+
+impl MilestoneRecorder {
+    pub fn new(milestones: &[rapid_time::Duration], start_time: Instant) -> Arc<MilestoneRecorder> {
+        Arc::new(MilestoneRecorder {
+            start_time,
+            state: Mutex::new(MilestoneState {
+                pending: milestones
+                    .iter()
+                    .map(|milestone| Duration::from_secs(milestone.in_sec().unwrap()))
+                    .collect(),
+                incumbent: None,
+                snapshots: Vec::new(),
+            }),
+        })
+    }
+
+    /// Records the improvements of the local search as incumbents.
+    pub fn progress_sink(self: &Arc<Self>) -> ProgressSink {
+        let recorder = self.clone();
+        ProgressSink::new(move |improvement, schedule| {
+            recorder.incumbent(schedule, improvement.objective_value)
+        })
+    }
+
+    pub fn incumbent(&self, schedule: &Schedule, objective_value: serde_json::Value) {
+        self.incumbent_at(schedule, objective_value, self.start_time.elapsed())
+    }
+
+    /// Snapshots the incumbent for all milestones reached by now and returns all snapshots in
+    /// the order of the milestones. Milestones after the end of the solve have no snapshot.
+    pub fn finish(&self) -> Vec<MilestoneSnapshot> {
+        self.finish_at(self.start_time.elapsed())
+    }
+
+    fn incumbent_at(
+        &self,
+        schedule: &Schedule,
+        objective_value: serde_json::Value,
+        elapsed: Duration,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.snapshot_reached_milestones(elapsed);
+        if state.pending.is_empty() {
+            return; // no snapshot needs the incumbent anymore
+        }
+        state.incumbent = Some(Incumbent {
+            schedule: schedule.clone(),
+            objective_value,
+            found_after: elapsed,
+        });
+    }
+
+    fn finish_at(&self, elapsed: Duration) -> Vec<MilestoneSnapshot> {
+        let mut state = self.state.lock().unwrap();
+        state.snapshot_reached_milestones(elapsed);
+        state.pending.clear();
+        state.incumbent = None;
+        std::mem::take(&mut state.snapshots)
+    }
+}
+
+impl MilestoneState {
+    fn snapshot_reached_milestones(&mut self, elapsed: Duration) {
+        let Some(incumbent) = &self.incumbent else {
+            return;
+        };
+        while let Some(&milestone) = self.pending.front() {
+            if milestone > elapsed {
+                break;
+            }
+            self.pending.pop_front();
+            self.snapshots.push(MilestoneSnapshot {
+                label: milestone_label(milestone),
+                milestone,
+                found_after: incumbent.found_after,
+                objective_value: incumbent.objective_value.clone(),
+                schedule: incumbent.schedule.clone(),
+            });
+        }
+    }
+}
+
+/// E.g., "300s" for a milestone after five minutes.
+pub fn milestone_label(milestone: Duration) -> String {
+    format!("{}s", milestone.as_secs())
+}
+
+/// The milestones section of the output. The schedules are only serialized for the full output.
+pub fn milestones_to_json(
+    snapshots: &[MilestoneSnapshot],
+    output_level: OutputLevel,
+) -> serde_json::Value {
+    serde_json::Value::Array(
+        snapshots
+            .iter()
+            .map(|snapshot| {
+                let mut json = serde_json::json!({
+                    "label": snapshot.label,
+                    "milestoneSeconds": snapshot.milestone.as_secs(),
+                    "foundAfterSeconds": snapshot.found_after.as_secs_f64(),
+                    "objectiveValue": snapshot.objective_value,
+                });
+                if output_level == OutputLevel::Full {
+                    json["schedule"] = schedule_to_json(&snapshot.schedule);
+                }
+                json
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,110 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+use std::{fs::File, io::Read};
+
+use model::json_serialisation::load_rolling_stock_problem_instance_from_json;
+use solution::Schedule;
+
+use crate::solve_instance;
+
+use super::MilestoneRecorder;
+
+fn small_test_input() -> serde_json::Value {
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    serde_json::from_str(&input_data).unwrap()
+}
+
+fn empty_schedule() -> Schedule {
+    Schedule::empty(load_rolling_stock_problem_instance_from_json(
+        small_test_input(),
+    ))
+}
+
+fn milestones(seconds: &[u64]) -> Vec<rapid_time::Duration> {
+    seconds
+        .iter()
+        .map(|&second| rapid_time::Duration::from_seconds(second))
+        .collect()
+}
+
+#[test]
+fn snapshots_keep_the_incumbent_of_each_milestone() {
+    // ARRANGE
+    let schedule = empty_schedule();
+    let recorder = MilestoneRecorder::new(&milestones(&[10, 20, 30, 100]), Instant::now());
+
+    // ACT
+    recorder.incumbent_at(&schedule, serde_json::json!(5), Duration::from_secs(1));
+    recorder.incumbent_at(&schedule, serde_json::json!(4), Duration::from_secs(8));
+    recorder.incumbent_at(&schedule, serde_json::json!(3), Duration::from_secs(25));
+    recorder.incumbent_at(&schedule, serde_json::json!(2), Duration::from_secs(27));
+    let snapshots = recorder.finish_at(Duration::from_secs(40));
+
+    // ASSERT
+    let summary: Vec<(String, u64, serde_json::Value)> = snapshots
+        .iter()
+        .map(|snapshot| {
+            (
+                snapshot.label.clone(),
+                snapshot.found_after.as_secs(),
+                snapshot.objective_value.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("10s".to_string(), 8, serde_json::json!(4)),
+            ("20s".to_string(), 8, serde_json::json!(4)),
+            ("30s".to_string(), 27, serde_json::json!(2)),
+        ]
+    );
+}
+
+#[test]
+fn milestone_before_the_first_incumbent_takes_the_first_incumbent() {
+    // ARRANGE
+    let schedule = empty_schedule();
+    let recorder = MilestoneRecorder::new(&milestones(&[1]), Instant::now());
+
+    // ACT
+    recorder.incumbent_at(&schedule, serde_json::json!(5), Duration::from_secs(3));
+    recorder.incumbent_at(&schedule, serde_json::json!(4), Duration::from_secs(4));
+    let snapshots = recorder.finish_at(Duration::from_secs(5));
+
+    // ASSERT
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].objective_value, serde_json::json!(5));
+    assert_eq!(snapshots[0].found_after, Duration::from_secs(3));
+}
+
+#[test]
+fn output_contains_only_reached_milestones() {
+    // ARRANGE
+    let mut input_data = small_test_input();
+    input_data["parameters"]["milestones"] = serde_json::json!([100000, 100000]);
+
+    // ACT
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    assert_eq!(output["milestones"], serde_json::json!([]));
+    assert_eq!(
+        output["info"]["configuration"]["parameters"]["milestones"],
+        serde_json::json!([100000])
+    );
+}
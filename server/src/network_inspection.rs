@@ -0,0 +1,217 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use axum::http::StatusCode;
+use model::base_types::NodeIdx;
+use model::network::Network;
+use rapid_time::DateTime;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::problem::{catch_panic, Problem};
+
+const DEFAULT_LIMIT: usize = 100;
+const MAXIMAL_LIMIT: usize = 1000;
+
+/// Query of a network listing (see network_listing). from, to and type only apply to trips.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkQuery {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>, // default DEFAULT_LIMIT, at most MAXIMAL_LIMIT
+    pub from: Option<String>, // trips departing at or after this time
+    pub to: Option<String>,   // trips departing before this time
+    #[serde(rename = "type")]
+    pub vehicle_type: Option<String>,
+}
+
+/// One page of a listing of the network as the solver saw it (after data repairs, splits and
+/// clipping): {"total": Int, "offset": Int, "limit": Int, "items": [...]}.
+/// The listings are "locations" (sorted by id), "trips" (the departure segments, sorted by
+/// departure), "depots" (sorted by id, without the overflow depot) and "maintenanceSlots" (sorted
+/// by start).
+pub fn network_listing(
+    network: &Network,
+    listing: &str,
+    query: &NetworkQuery,
+) -> Result<Value, Problem> {
+    let items = match listing {
+        "locations" => locations_to_json(network),
+        "trips" => trips_to_json(network, query)?,
+        "depots" => depots_to_json(network),
+        "maintenanceSlots" => maintenance_slots_to_json(network),
+        _ => {
+            return Err(Problem::not_found(format!(
+                "No network listing {}! Use locations, trips, depots or maintenanceSlots.",
+                listing
+            )))
+        }
+    };
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAXIMAL_LIMIT);
+    Ok(serde_json::json!({
+        "total": items.len(),
+        "offset": offset,
+        "limit": limit,
+        "items": items.into_iter().skip(offset).take(limit).collect::<Vec<_>>(),
+    }))
+}
+
+fn locations_to_json(network: &Network) -> Vec<Value> {
+    let locations = network.locations();
+    let mut json: Vec<Value> = locations
+        .iter()
+        .map(|location| {
+            serde_json::json!({
+                "id": locations.get_id(location).unwrap(),
+                "dayLimit": locations.get_daylimit(location).unwrap(),
+                "group": locations.group_of(location).map(|group| group.id()),
+                "siding": locations.is_siding(location),
+                "platformIdleLimit": locations
+                    .platform_idle_limit(location)
+                    .map(|limit| limit.in_sec().unwrap()),
+            })
+        })
+        .collect();
+    json.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    json
+}
+
+fn trips_to_json(network: &Network, query: &NetworkQuery) -> Result<Vec<Value>, Problem> {
+    let from = query
+        .from
+        .as_deref()
+        .map(|from| parse_date_time("from", from))
+        .transpose()?;
+    let to = query
+        .to
+        .as_deref()
+        .map(|to| parse_date_time("to", to))
+        .transpose()?;
+    let vehicle_type = query
+        .vehicle_type
+        .as_ref()
+        .map(|id| {
+            network
+                .vehicle_types()
+                .iter()
+                .find(|&vehicle_type| network.vehicle_types().get(vehicle_type).unwrap().id() == id)
+                .ok_or_else(|| {
+                    Problem::malformed_request(
+                        StatusCode::BAD_REQUEST,
+                        format!("Unknown vehicle type {}.", id),
+                    )
+                })
+        })
+        .transpose()?;
+
+    let trips: Box<dyn Iterator<Item = NodeIdx>> = match (from, to) {
+        (None, None) => Box::new(network.all_service_nodes()),
+        (from, to) => Box::new(network.service_nodes_departing_between(
+            from.unwrap_or(DateTime::Earliest),
+            to.unwrap_or(DateTime::Latest),
+        )),
+    };
+    Ok(trips
+        .filter(|&trip| {
+            vehicle_type.is_none_or(|vehicle_type| network.vehicle_type_for(trip) == vehicle_type)
+        })
+        .map(|trip| {
+            let node = network.node(trip);
+            let service_trip = node.as_service_trip();
+            let locations = network.locations();
+            serde_json::json!({
+                "id": service_trip.id(),
+                "route": service_trip.route(),
+                "vehicleType": network
+                    .vehicle_types()
+                    .get(service_trip.vehicle_type())
+                    .unwrap()
+                    .id(),
+                "origin": locations.get_id(node.start_location()).unwrap(),
+                "destination": locations.get_id(node.end_location()).unwrap(),
+                "departure": node.start_time().as_iso(),
+                "arrival": node.end_time().as_iso(),
+                "distance": node.travel_distance().in_meter().unwrap(),
+                "passengers": service_trip.passengers(),
+                "seated": service_trip.seated(),
+                "minimalFormationCount": service_trip.minimal_formation_count(),
+                "maximalFormationCount": service_trip.maximal_formation_count(),
+            })
+        })
+        .collect())
+}
+
+fn depots_to_json(network: &Network) -> Vec<Value> {
+    let (overflow_depot, _, _) = network.overflow_depot_idxs();
+    let vehicle_types = network.vehicle_types();
+    let mut json: Vec<Value> = network
+        .depots_iter()
+        .filter(|&depot_idx| depot_idx != overflow_depot)
+        .map(|depot_idx| {
+            let depot = network.get_depot(depot_idx);
+            serde_json::json!({
+                "id": depot.id(),
+                "location": network.locations().get_id(depot.location()).unwrap(),
+                "capacity": depot.total_capacity(),
+                "despawnCapacity": depot.total_despawn_capacity(),
+                "providesMaintenance": depot.provides_maintenance(),
+                "spawnCost": depot.spawn_cost(),
+                "allowedTypes": vehicle_types
+                    .iter()
+                    .filter(|&vehicle_type| depot.allows(vehicle_type))
+                    .map(|vehicle_type| serde_json::json!({
+                        "vehicleType": vehicle_types.get(vehicle_type).unwrap().id(),
+                        "capacity": depot.capacity_for(vehicle_type),
+                        "despawnCapacity": depot.despawn_capacity_for(vehicle_type),
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    json.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    json
+}
+
+fn maintenance_slots_to_json(network: &Network) -> Vec<Value> {
+    let mut slots: Vec<NodeIdx> = network.maintenance_nodes().collect();
+    slots.sort_by(|&a, &b| network.node(a).cmp_start_time(network.node(b)));
+    slots
+        .into_iter()
+        .map(|slot| {
+            let node = network.node(slot);
+            serde_json::json!({
+                "id": node.id(),
+                "location": network.locations().get_id(node.start_location()).unwrap(),
+                "start": node.start_time().as_iso(),
+                "end": node.end_time().as_iso(),
+                "trackCount": network.track_count_of_maintenance_slot(slot),
+            })
+        })
+        .collect()
+}
+
+fn parse_date_time(name: &str, value: &str) -> Result<DateTime, Problem> {
+    catch_panic(|| Ok(DateTime::new(value))).map_err(|_| {
+        Problem::malformed_request(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Invalid {}: {} (expected e.g. 2023-07-24T12:00:00).",
+                name, value
+            ),
+        )
+    })
+}
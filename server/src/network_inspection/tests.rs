@@ -0,0 +1,114 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::{fs::File, io::Read};
+
+use axum::http::StatusCode;
+use model::json_serialisation::load_rolling_stock_problem_instance_from_json;
+use model::network::Network;
+
+use super::{network_listing, NetworkQuery};
+
+fn small_test_network() -> Arc<Network> {
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    load_rolling_stock_problem_instance_from_json(serde_json::from_str(&input_data).unwrap())
+}
+
+fn ids(page: &serde_json::Value) -> Vec<&str> {
+    page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["id"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn trips_are_filtered_by_departure_and_vehicle_type() {
+    // ARRANGE
+    let network = small_test_network();
+    let time_range = NetworkQuery {
+        from: Some("2023-07-24T12:30:00".to_string()),
+        to: Some("2023-07-24T14:00:00".to_string()),
+        ..NetworkQuery::default()
+    };
+    let vehicle_type = NetworkQuery {
+        vehicle_type: Some("IC".to_string()),
+        ..NetworkQuery::default()
+    };
+
+    // ACT
+    let all_trips = network_listing(&network, "trips", &NetworkQuery::default()).unwrap();
+    let trips_in_range = network_listing(&network, "trips", &time_range).unwrap();
+    let trips_of_type = network_listing(&network, "trips", &vehicle_type).unwrap();
+
+    // ASSERT
+    assert_eq!(all_trips["total"], 4);
+    assert_eq!(ids(&trips_in_range), vec!["trip_0_seg_1"]);
+    assert_eq!(trips_in_range["items"][0]["origin"], "LU");
+    assert_eq!(trips_in_range["items"][0]["vehicleType"], "IR");
+    assert_eq!(ids(&trips_of_type), vec!["trip_1a_seg_0", "trip_1b_seg_0"]);
+}
+
+#[test]
+fn network_listings_are_paginated() {
+    // ARRANGE
+    let network = small_test_network();
+    let page = NetworkQuery {
+        offset: Some(1),
+        limit: Some(1),
+        ..NetworkQuery::default()
+    };
+
+    // ACT
+    let locations = network_listing(&network, "locations", &page).unwrap();
+    let depots = network_listing(&network, "depots", &NetworkQuery::default()).unwrap();
+    let maintenance_slots =
+        network_listing(&network, "maintenanceSlots", &NetworkQuery::default()).unwrap();
+
+    // ASSERT
+    assert_eq!(locations["total"], 3);
+    assert_eq!(ids(&locations), vec!["LU"]);
+    // without the overflow depot
+    assert_eq!(ids(&depots), vec!["depot_BN", "depot_ZH"]);
+    assert_eq!(depots["items"][1]["allowedTypes"][0]["capacity"], 5);
+    // maintenance_slot_1 is excluded as unusable (no service trip can reach it)
+    assert_eq!(ids(&maintenance_slots), vec!["maintenance_slot_0"]);
+}
+
+#[test]
+fn invalid_network_queries_are_rejected() {
+    // ARRANGE
+    let network = small_test_network();
+    let unknown_type = NetworkQuery {
+        vehicle_type: Some("S-Bahn".to_string()),
+        ..NetworkQuery::default()
+    };
+    let invalid_from = NetworkQuery {
+        from: Some("noon".to_string()),
+        ..NetworkQuery::default()
+    };
+
+    // ACT
+    let unknown_listing = network_listing(&network, "routes", &NetworkQuery::default());
+    let unknown_type = network_listing(&network, "trips", &unknown_type);
+    let invalid_from = network_listing(&network, "trips", &invalid_from);
+
+    // ASSERT
+    assert_eq!(unknown_listing.unwrap_err().status(), StatusCode::NOT_FOUND);
+    assert_eq!(unknown_type.unwrap_err().status(), StatusCode::BAD_REQUEST);
+    assert_eq!(invalid_from.unwrap_err().status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,209 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use model::json_serialisation::ValidationError;
+use serde::Serialize;
+
+use crate::SolveError;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// An error answer of the server in the format of RFC 9457 (application/problem+json).
+/// All handlers report their errors with it, the errors array is only filled for invalid input
+/// data (the validation errors of the loader).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    problem_type: &'static str,
+    title: &'static str,
+    status: u16,
+    detail: String,
+    errors: Vec<ValidationError>,
+}
+
+impl Problem {
+    fn new(
+        problem_type: &'static str,
+        title: &'static str,
+        status: StatusCode,
+        detail: String,
+    ) -> Problem {
+        Problem {
+            problem_type,
+            title,
+            status: status.as_u16(),
+            detail,
+            errors: Vec::new(),
+        }
+    }
+
+    /// the input data was rejected by the loader (see
+    /// try_load_rolling_stock_problem_instance_from_json).
+    pub fn invalid_input(errors: Vec<ValidationError>) -> Problem {
+        Problem {
+            errors,
+            ..Problem::new(
+                "urn:rssched:problem:invalid-input",
+                "Invalid input data",
+                StatusCode::BAD_REQUEST,
+                "The input data contains errors, see errors.".to_string(),
+            )
+        }
+    }
+
+    /// the body or the query of the request could not be parsed.
+    pub fn malformed_request(status: StatusCode, detail: String) -> Problem {
+        Problem::new(
+            "urn:rssched:problem:malformed-request",
+            "Malformed request",
+            status,
+            detail,
+        )
+    }
+
+    /// an idempotency key was reused for a different request.
+    pub fn idempotency_conflict(key: &str) -> Problem {
+        Problem::new(
+            "urn:rssched:problem:idempotency-conflict",
+            "Idempotency key reused",
+            StatusCode::CONFLICT,
+            format!(
+                "Idempotency key {} was already used for a different request.",
+                key
+            ),
+        )
+    }
+
+    /// the body or the instance of the request exceeds a limit of the server (see SizeLimits).
+    pub fn too_large(detail: String) -> Problem {
+        Problem::new(
+            "urn:rssched:problem:too-large",
+            "Request too large",
+            StatusCode::PAYLOAD_TOO_LARGE,
+            detail,
+        )
+    }
+
+    /// the requested route (or job) does not exist.
+    pub fn not_found(detail: String) -> Problem {
+        Problem::new(
+            "urn:rssched:problem:not-found",
+            "Not found",
+            StatusCode::NOT_FOUND,
+            detail,
+        )
+    }
+
+    /// an asynchronous solve cannot be deleted before it has ended.
+    pub fn solve_in_progress(job_id: &str) -> Problem {
+        Problem::new(
+            "urn:rssched:problem:solve-in-progress",
+            "Solve in progress",
+            StatusCode::CONFLICT,
+            format!("Job {} has not finished yet and cannot be deleted.", job_id),
+        )
+    }
+
+    /// an asynchronous solve cannot be cancelled after it has ended.
+    pub fn solve_ended(job_id: &str) -> Problem {
+        Problem::new(
+            "urn:rssched:problem:solve-ended",
+            "Solve ended",
+            StatusCode::CONFLICT,
+            format!("Job {} has already ended and cannot be cancelled.", job_id),
+        )
+    }
+
+    /// the solver panicked (a bug or an input the loader did not catch) or its output could not
+    /// be written.
+    pub fn internal(detail: String) -> Problem {
+        Problem::new(
+            "urn:rssched:problem:internal-error",
+            "Internal server error",
+            StatusCode::INTERNAL_SERVER_ERROR,
+            detail,
+        )
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        (
+            self.status(),
+            [(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+            serde_json::to_vec(&self).unwrap(),
+        )
+            .into_response()
+    }
+}
+
+impl From<SolveError> for Problem {
+    fn from(error: SolveError) -> Problem {
+        match error {
+            SolveError::InvalidInput(errors) => Problem::invalid_input(errors),
+            SolveError::Output(error) => Problem::internal(error.to_string()),
+        }
+    }
+}
+
+impl From<JsonRejection> for Problem {
+    fn from(rejection: JsonRejection) -> Problem {
+        if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            return Problem::too_large(
+                "The request body exceeds the maximum body size of the server \
+                 (RSSCHED_MAX_BODY_SIZE_MB, see /health)."
+                    .to_string(),
+            );
+        }
+        Problem::malformed_request(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for Problem {
+    fn from(rejection: QueryRejection) -> Problem {
+        Problem::malformed_request(rejection.status(), rejection.body_text())
+    }
+}
+
+/// Runs f and turns a panic into an internal problem (the panic message is the detail).
+pub fn catch_panic<T>(f: impl FnOnce() -> Result<T, Problem>) -> Result<T, Problem> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(Problem::internal(panic_message(payload.as_ref()))))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "The solver panicked.".to_string()),
+    }
+}
@@ -0,0 +1,150 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fs::File, io::Read};
+
+use axum::body::Body;
+use axum::extract::{FromRequest, Json, Request};
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+
+use super::{catch_panic, Problem, PROBLEM_CONTENT_TYPE};
+
+/// Renders the problem as the server would and checks the RFC 9457 members.
+async fn assert_problem_schema(problem: Problem, status: StatusCode) -> serde_json::Value {
+    let response = problem.into_response();
+    assert_eq!(response.status(), status);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        PROBLEM_CONTENT_TYPE
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        body.as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>(),
+        vec!["type", "title", "status", "detail", "errors"]
+    );
+    assert!(body["type"]
+        .as_str()
+        .unwrap()
+        .starts_with("urn:rssched:problem:"));
+    assert!(body["title"].is_string());
+    assert_eq!(body["status"], status.as_u16());
+    assert!(body["detail"].is_string());
+    assert!(body["errors"].is_array());
+    body
+}
+
+#[tokio::test]
+async fn invalid_input_lists_validation_errors() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // route_0 (IR) passes LU
+    input_data["locations"][2]["allowedVehicleTypes"] = serde_json::json!(["IC"]);
+
+    // ACT
+    let problem =
+        catch_panic(|| crate::analyze_instance(input_data, false).map_err(Problem::invalid_input))
+            .unwrap_err();
+
+    // ASSERT
+    let body = assert_problem_schema(problem, StatusCode::BAD_REQUEST).await;
+    assert_eq!(body["type"], "urn:rssched:problem:invalid-input");
+    assert_eq!(body["errors"][0]["code"], "vehicleTypeNotAllowedAtLocation");
+    assert!(body["errors"][0]["message"].is_string());
+}
+
+#[tokio::test]
+async fn unknown_reference_is_invalid_input() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["departures"][0]["route"] = serde_json::json!("route_5");
+
+    // ACT
+    let problem =
+        catch_panic(|| crate::analyze_instance(input_data, false).map_err(Problem::invalid_input))
+            .unwrap_err();
+
+    // ASSERT
+    let body = assert_problem_schema(problem, StatusCode::BAD_REQUEST).await;
+    assert_eq!(body["errors"][0]["code"], "unknownReference");
+    assert_eq!(
+        body["errors"][0]["message"],
+        "Departure trip_0 references unknown route route_5."
+    );
+}
+
+#[tokio::test]
+async fn malformed_body_is_rejected() {
+    // ARRANGE
+    let request = Request::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{\"vehicleTypes\": ["))
+        .unwrap();
+
+    // ACT
+    let rejection = Json::<serde_json::Value>::from_request(request, &())
+        .await
+        .unwrap_err();
+
+    // ASSERT
+    let status = rejection.status();
+    assert!(status.is_client_error());
+    let body = assert_problem_schema(Problem::from(rejection), status).await;
+    assert_eq!(body["type"], "urn:rssched:problem:malformed-request");
+    assert_eq!(body["errors"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn reused_idempotency_key_is_conflict() {
+    // ACT
+    let problem = Problem::idempotency_conflict("abc");
+
+    // ASSERT
+    let body = assert_problem_schema(problem, StatusCode::CONFLICT).await;
+    assert_eq!(body["type"], "urn:rssched:problem:idempotency-conflict");
+    assert!(body["detail"].as_str().unwrap().contains("abc"));
+}
+
+#[tokio::test]
+async fn unknown_route_is_not_found() {
+    // ACT
+    let problem = Problem::not_found("No route! Use /health, /solve or /analyze.".to_string());
+
+    // ASSERT
+    let body = assert_problem_schema(problem, StatusCode::NOT_FOUND).await;
+    assert_eq!(body["type"], "urn:rssched:problem:not-found");
+}
+
+#[tokio::test]
+async fn panic_is_internal_error() {
+    // ACT
+    let problem = catch_panic::<()>(|| panic!("Could not parse input data")).unwrap_err();
+
+    // ASSERT
+    let body = assert_problem_schema(problem, StatusCode::INTERNAL_SERVER_ERROR).await;
+    assert_eq!(body["type"], "urn:rssched:problem:internal-error");
+    assert_eq!(body["detail"], "Could not parse input data");
+}
@@ -0,0 +1,127 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Keeps the input and the response of each solve as json files in a directory (see the
+/// --output-dir argument of the server), for auditability.
+/// A run is identified by <timestamp>_<job id> (the timestamp in seconds since the Unix epoch),
+/// its files are input_<run id>.json and output_<run id>.json, pretty-printed as by the internal
+/// binary. The output of a failed solve is the problem of the response.
+pub struct RunArchive {
+    directory: PathBuf,
+}
+
+impl RunArchive {
+    /// The directory is created if it does not exist.
+    pub fn new(directory: impl Into<PathBuf>) -> Result<RunArchive, String> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory).map_err(|error| {
+            format!(
+                "Output directory {} cannot be created: {}",
+                directory.display(),
+                error
+            )
+        })?;
+        Ok(RunArchive { directory })
+    }
+
+    /// the id of a run of the given job starting now.
+    pub fn run_id(job_id: &str) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_secs());
+        format!("{}_{}", timestamp, job_id)
+    }
+
+    /// Writes the input and the output (the body of the response) of the run. Blocking, the
+    /// server calls it on a blocking task.
+    pub fn store(
+        &self,
+        run_id: &str,
+        input: &serde_json::Value,
+        output: &[u8],
+    ) -> Result<(), String> {
+        let output: serde_json::Value = serde_json::from_slice(output)
+            .map_err(|error| format!("Output of run {} is no json: {}", run_id, error))?;
+        self.write(&format!("input_{}.json", run_id), input)?;
+        self.write(&format!("output_{}.json", run_id), &output)
+    }
+
+    /// the ids of the stored runs (those with an output file), oldest first.
+    pub fn run_ids(&self) -> Result<Vec<String>, String> {
+        let entries = fs::read_dir(&self.directory).map_err(|error| {
+            format!(
+                "Output directory {} cannot be read: {}",
+                self.directory.display(),
+                error
+            )
+        })?;
+        let mut run_ids: Vec<String> = entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|file_name| {
+                let run_id = file_name.strip_prefix("output_")?.strip_suffix(".json")?;
+                is_valid_run_id(run_id).then(|| run_id.to_string())
+            })
+            .collect();
+        // sorted by the timestamp, then by the job id
+        run_ids.sort_by_key(|run_id| {
+            let (timestamp, job_id) = run_id.split_once('_').unwrap_or((run_id, ""));
+            (
+                timestamp.parse::<u64>().unwrap_or(u64::MAX),
+                job_id.to_string(),
+            )
+        });
+        Ok(run_ids)
+    }
+
+    /// the stored output of the run, None if there is none (or the id is not a run id).
+    pub fn output(&self, run_id: &str) -> Result<Option<Vec<u8>>, String> {
+        if !is_valid_run_id(run_id) {
+            return Ok(None);
+        }
+        match fs::read(self.directory.join(format!("output_{}.json", run_id))) {
+            Ok(output) => Ok(Some(output)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(format!(
+                "Output of run {} cannot be read: {}",
+                run_id, error
+            )),
+        }
+    }
+
+    fn write(&self, file_name: &str, json: &serde_json::Value) -> Result<(), String> {
+        let path = self.directory.join(file_name);
+        let file = File::create(&path)
+            .map_err(|error| format!("{} cannot be created: {}", path.display(), error))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, json)
+            .map_err(io::Error::from)
+            .and_then(|_| writer.flush())
+            .map_err(|error| format!("{} cannot be written: {}", path.display(), error))
+    }
+}
+
+/// run ids consist of digits, letters, '_' and '-' only (no path can be smuggled in).
+fn is_valid_run_id(run_id: &str) -> bool {
+    !run_id.is_empty()
+        && run_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
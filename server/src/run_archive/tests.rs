@@ -0,0 +1,100 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::PathBuf;
+
+use super::RunArchive;
+
+fn empty_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "rssched_run_archive_{}_{}",
+        std::process::id(),
+        name
+    ));
+    fs::remove_dir_all(&directory).ok();
+    directory
+}
+
+#[test]
+fn stored_runs_are_listed_and_read_back() {
+    // ARRANGE
+    let directory = empty_directory("store");
+    let archive = RunArchive::new(&directory).unwrap();
+    let input = serde_json::json!({"vehicleTypes": [], "parameters": {}});
+    let output = serde_json::json!({"schedule": {"fleet": []}, "info": {"jobId": "7"}});
+
+    // ACT
+    archive
+        .store(
+            "1700000100_7",
+            &input,
+            &serde_json::to_vec(&output).unwrap(),
+        )
+        .unwrap();
+    archive
+        .store("1700000005_12", &input, br#"{"status": 400}"#)
+        .unwrap();
+    let run_ids = archive.run_ids().unwrap();
+    let stored_output = archive.output("1700000100_7").unwrap().unwrap();
+
+    // ASSERT
+    assert_eq!(run_ids, vec!["1700000005_12", "1700000100_7"]);
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&stored_output).unwrap(),
+        output
+    );
+    // pretty-printed as by the internal binary
+    assert_eq!(
+        String::from_utf8(stored_output).unwrap(),
+        serde_json::to_string_pretty(&output).unwrap()
+    );
+    let stored_input = fs::read_to_string(directory.join("input_1700000100_7.json")).unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&stored_input).unwrap(),
+        input
+    );
+    fs::remove_dir_all(&directory).ok();
+}
+
+#[test]
+fn unknown_and_invalid_run_ids_have_no_output() {
+    // ARRANGE
+    let directory = empty_directory("unknown");
+    let archive = RunArchive::new(&directory).unwrap();
+    fs::write(directory.join("notes.txt"), "not a run").unwrap();
+
+    // ACT
+    let run_ids = archive.run_ids().unwrap();
+    let unknown = archive.output("1700000000_1").unwrap();
+    let invalid = archive.output("../output_1700000000_1").unwrap();
+    let not_json = archive.store("1700000000_1", &serde_json::json!({}), b"not json");
+
+    // ASSERT
+    assert!(run_ids.is_empty());
+    assert!(unknown.is_none());
+    assert!(invalid.is_none());
+    assert!(not_json.is_err());
+    fs::remove_dir_all(&directory).ok();
+}
+
+#[test]
+fn run_ids_carry_the_job_id() {
+    // ACT
+    let run_id = RunArchive::run_id("42");
+
+    // ASSERT
+    let (timestamp, job_id) = run_id.split_once('_').unwrap();
+    assert!(timestamp.parse::<u64>().unwrap() > 1_600_000_000);
+    assert_eq!(job_id, "42");
+}
@@ -0,0 +1,142 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::env;
+
+use crate::problem::Problem;
+
+const DEFAULT_MAX_BODY_SIZE_MEGABYTES: usize = 256;
+const DEFAULT_MAX_SERVICE_TRIPS: usize = 50_000;
+const DEFAULT_MAX_LOCATIONS: usize = 5_000;
+const DEFAULT_MAX_DEPOTS: usize = 1_000;
+
+/// Limits on the requests of the server, so that a huge request cannot exhaust the memory of the
+/// process. The body size is enforced while the body is read, the instance size is checked on
+/// the raw json, i.e., before the network (with its dead-head matrices) is built.
+/// The limits are configured via environment variables (see from_env) and reported by /health.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeLimits {
+    max_body_size: usize, // in bytes
+    max_service_trips: usize,
+    max_locations: usize,
+    max_depots: usize,
+}
+
+impl Default for SizeLimits {
+    fn default() -> SizeLimits {
+        SizeLimits {
+            max_body_size: DEFAULT_MAX_BODY_SIZE_MEGABYTES * 1024 * 1024,
+            max_service_trips: DEFAULT_MAX_SERVICE_TRIPS,
+            max_locations: DEFAULT_MAX_LOCATIONS,
+            max_depots: DEFAULT_MAX_DEPOTS,
+        }
+    }
+}
+
+impl SizeLimits {
+    /// Reads RSSCHED_MAX_BODY_SIZE_MB, RSSCHED_MAX_SERVICE_TRIPS, RSSCHED_MAX_LOCATIONS and
+    /// RSSCHED_MAX_DEPOTS, unset variables keep their default.
+    pub fn from_env() -> Result<SizeLimits, String> {
+        SizeLimits::from_variables(|name| env::var(name).ok())
+    }
+
+    fn from_variables(variable: impl Fn(&str) -> Option<String>) -> Result<SizeLimits, String> {
+        let limit = |name: &str, default: usize| match variable(name) {
+            None => Ok(default),
+            Some(value) => value
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&limit| limit > 0)
+                .ok_or_else(|| format!("{} must be a positive integer, not {}.", name, value)),
+        };
+        Ok(SizeLimits {
+            max_body_size: limit("RSSCHED_MAX_BODY_SIZE_MB", DEFAULT_MAX_BODY_SIZE_MEGABYTES)?
+                * 1024
+                * 1024,
+            max_service_trips: limit("RSSCHED_MAX_SERVICE_TRIPS", DEFAULT_MAX_SERVICE_TRIPS)?,
+            max_locations: limit("RSSCHED_MAX_LOCATIONS", DEFAULT_MAX_LOCATIONS)?,
+            max_depots: limit("RSSCHED_MAX_DEPOTS", DEFAULT_MAX_DEPOTS)?,
+        })
+    }
+
+    /// in bytes
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Counts the service trips (departure segments, including the headway repetitions),
+    /// locations and depots of the raw input data. The first exceeded limit is reported as
+    /// problem with status 413.
+    /// Malformed input data passes, it is rejected by the loader.
+    pub fn check(&self, input_data: &serde_json::Value) -> Result<(), Problem> {
+        let length_of = |key: &str| input_data[key].as_array().map_or(0, |array| array.len());
+        let service_trips: usize = input_data["departures"].as_array().map_or(0, |departures| {
+            departures
+                .iter()
+                .map(|departure| {
+                    let segments = departure["segments"]
+                        .as_array()
+                        .map_or(0, |segments| segments.len());
+                    let repetitions = departure["headwayRepetitions"]["count"]
+                        .as_u64()
+                        .filter(|&count| count > 0)
+                        .unwrap_or(1);
+                    segments.saturating_mul(repetitions as usize)
+                })
+                .fold(0, usize::saturating_add)
+        });
+        for (count, limit, what, variable) in [
+            (
+                service_trips,
+                self.max_service_trips,
+                "service trips",
+                "RSSCHED_MAX_SERVICE_TRIPS",
+            ),
+            (
+                length_of("locations"),
+                self.max_locations,
+                "locations",
+                "RSSCHED_MAX_LOCATIONS",
+            ),
+            (
+                length_of("depots"),
+                self.max_depots,
+                "depots",
+                "RSSCHED_MAX_DEPOTS",
+            ),
+        ] {
+            if count > limit {
+                return Err(Problem::too_large(format!(
+                    "The instance has {} {}, but the server accepts at most {} ({}).",
+                    count, what, limit, variable
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// {"maxBodySize": Int (in bytes), "maxServiceTrips": Int, "maxLocations": Int,
+    /// "maxDepots": Int}
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "maxBodySize": self.max_body_size,
+            "maxServiceTrips": self.max_service_trips,
+            "maxLocations": self.max_locations,
+            "maxDepots": self.max_depots,
+        })
+    }
+}
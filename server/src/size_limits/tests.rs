@@ -0,0 +1,100 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+
+use axum::http::StatusCode;
+
+use super::SizeLimits;
+
+fn small_test_input() -> serde_json::Value {
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    serde_json::from_str(&input_data).unwrap()
+}
+
+#[test]
+fn limits_are_read_from_the_variables() {
+    // ARRANGE
+    let variables = HashMap::from([
+        ("RSSCHED_MAX_BODY_SIZE_MB", "2"),
+        ("RSSCHED_MAX_DEPOTS", " 7 "),
+    ]);
+    let invalid_variables = HashMap::from([("RSSCHED_MAX_LOCATIONS", "0")]);
+
+    // ACT
+    let limits =
+        SizeLimits::from_variables(|name| variables.get(name).map(|value| value.to_string()))
+            .unwrap();
+    let defaults = SizeLimits::from_variables(|_| None).unwrap();
+    let error = SizeLimits::from_variables(|name| {
+        invalid_variables.get(name).map(|value| value.to_string())
+    })
+    .unwrap_err();
+
+    // ASSERT
+    assert_eq!(limits.max_body_size(), 2 * 1024 * 1024);
+    assert_eq!(limits.to_json()["maxDepots"], 7);
+    assert_eq!(
+        limits.to_json()["maxServiceTrips"],
+        defaults.to_json()["maxServiceTrips"]
+    );
+    assert_eq!(defaults, SizeLimits::default());
+    assert!(error.contains("RSSCHED_MAX_LOCATIONS"));
+}
+
+#[test]
+fn instance_exceeding_a_limit_is_rejected_with_413() {
+    // ARRANGE
+    let input_data = small_test_input();
+    let departure_segments: usize = input_data["departures"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|departure| departure["segments"].as_array().unwrap().len())
+        .sum();
+    let exact_limits = SizeLimits {
+        max_service_trips: departure_segments,
+        ..SizeLimits::default()
+    };
+    let mut repeated_input_data = input_data.clone();
+    repeated_input_data["departures"][0]["headwayRepetitions"] =
+        serde_json::json!({"count": 2, "interval": 3600});
+    let depot_limits = SizeLimits {
+        max_depots: 1,
+        ..SizeLimits::default()
+    };
+
+    // ACT
+    let within_limits = exact_limits.check(&input_data);
+    let with_repetitions = exact_limits.check(&repeated_input_data);
+    let too_many_depots = depot_limits.check(&input_data).unwrap_err();
+    let malformed = exact_limits.check(&serde_json::json!({"departures": 3}));
+
+    // ASSERT
+    assert!(within_limits.is_ok());
+    let too_many_trips = with_repetitions.unwrap_err();
+    assert_eq!(too_many_trips.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(serde_json::to_value(&too_many_trips).unwrap()["detail"]
+        .as_str()
+        .unwrap()
+        .contains("service trips"));
+    assert!(serde_json::to_value(&too_many_depots).unwrap()["detail"]
+        .as_str()
+        .unwrap()
+        .contains("RSSCHED_MAX_DEPOTS"));
+    assert!(malformed.is_ok());
+}
@@ -0,0 +1,103 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use gethostname::gethostname;
+
+/// Counts the solves of the server (for /health): solves waiting for a thread (e.g., queued
+/// asynchronous solves), running solves and finished (or failed) solves since the start.
+/// The counts are kept by the guards returned by queue and start, so a panicking solve is
+/// counted correctly as well.
+pub struct SolveLoad {
+    started: Instant,
+    queued: AtomicUsize,
+    running: AtomicUsize,
+    served: AtomicU64,
+}
+
+/// A solve that waits for a thread. It is no longer counted once dropped or started.
+pub struct QueuedSolve {
+    load: Arc<SolveLoad>,
+}
+
+/// A running solve. It is counted as served once dropped.
+pub struct RunningSolve {
+    load: Arc<SolveLoad>,
+}
+
+impl SolveLoad {
+    pub fn new() -> Arc<SolveLoad> {
+        Arc::new(SolveLoad {
+            started: Instant::now(),
+            queued: AtomicUsize::new(0),
+            running: AtomicUsize::new(0),
+            served: AtomicU64::new(0),
+        })
+    }
+
+    pub fn queue(self: &Arc<Self>) -> QueuedSolve {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        QueuedSolve { load: self.clone() }
+    }
+
+    pub fn start(self: &Arc<Self>) -> RunningSolve {
+        self.queue().start()
+    }
+
+    /// {"status": "healthy", "version": String, "runningSolves": Int, "queuedSolves": Int,
+    /// "solvesServed": Int, "uptimeSeconds": Float, "hostname": String}
+    pub fn health(&self) -> serde_json::Value {
+        self.health_at(Instant::now())
+    }
+
+    fn health_at(&self, now: Instant) -> serde_json::Value {
+        serde_json::json!({
+            "status": "healthy",
+            "version": env!("CARGO_PKG_VERSION"),
+            "runningSolves": self.running.load(Ordering::Relaxed),
+            "queuedSolves": self.queued.load(Ordering::Relaxed),
+            "solvesServed": self.served.load(Ordering::Relaxed),
+            "uptimeSeconds": now.duration_since(self.started).as_secs_f64(),
+            "hostname": gethostname().into_string().unwrap_or("unknown".to_string()),
+        })
+    }
+}
+
+impl QueuedSolve {
+    pub fn start(self) -> RunningSolve {
+        self.load.running.fetch_add(1, Ordering::Relaxed);
+        RunningSolve {
+            load: self.load.clone(),
+        }
+        // self is dropped here, so the solve is no longer queued
+    }
+}
+
+impl Drop for QueuedSolve {
+    fn drop(&mut self) {
+        self.load.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Drop for RunningSolve {
+    fn drop(&mut self) {
+        self.load.running.fetch_sub(1, Ordering::Relaxed);
+        self.load.served.fetch_add(1, Ordering::Relaxed);
+    }
+}
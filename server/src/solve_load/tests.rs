@@ -0,0 +1,74 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use super::SolveLoad;
+
+#[test]
+fn solves_are_counted_through_their_states() {
+    // ARRANGE
+    let load = SolveLoad::new();
+
+    // ACT
+    let queued = load.queue();
+    let running = load.start();
+    let while_both = load.health();
+    let started = queued.start();
+    drop(running);
+    let after_first = load.health();
+    drop(started);
+    let after_both = load.health();
+
+    // ASSERT
+    assert_eq!(while_both["queuedSolves"], 1);
+    assert_eq!(while_both["runningSolves"], 1);
+    assert_eq!(while_both["solvesServed"], 0);
+    assert_eq!(after_first["queuedSolves"], 0);
+    assert_eq!(after_first["runningSolves"], 1);
+    assert_eq!(after_first["solvesServed"], 1);
+    assert_eq!(after_both["runningSolves"], 0);
+    assert_eq!(after_both["solvesServed"], 2);
+}
+
+#[test]
+fn panicking_solve_is_counted_as_served() {
+    // ARRANGE
+    let load = SolveLoad::new();
+
+    // ACT
+    let result = std::panic::catch_unwind(|| {
+        let _running = load.start();
+        panic!("solve failed");
+    });
+
+    // ASSERT
+    assert!(result.is_err());
+    assert_eq!(load.health()["runningSolves"], 0);
+    assert_eq!(load.health()["solvesServed"], 1);
+}
+
+#[test]
+fn health_reports_version_and_uptime() {
+    // ARRANGE
+    let load = SolveLoad::new();
+
+    // ACT
+    let health = load.health_at(load.started + Duration::from_secs(90));
+
+    // ASSERT
+    assert_eq!(health["status"], "healthy");
+    assert_eq!(health["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(health["uptimeSeconds"], 90.0);
+    assert!(health["hostname"].is_string());
+}
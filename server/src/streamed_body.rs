@@ -0,0 +1,118 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use axum::body::Body;
+use futures_util::{stream, StreamExt};
+use std::io;
+use tokio::sync::mpsc;
+
+// the body is sent in chunks of (at least) this size, only the last chunk can be smaller
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// number of chunks that can wait for the client before the writer blocks
+const PENDING_CHUNKS: usize = 16;
+
+/// Writer whose output is sent as the body of a response (see stream_output).
+pub struct BodyWriter {
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    started: bool, // whether a chunk has been sent
+}
+
+impl BodyWriter {
+    fn send_chunk(&mut self) -> io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_SIZE));
+        self.started = true;
+        self.sender.blocking_send(Ok(chunk)).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the client closed the connection",
+            )
+        })
+    }
+
+    /// Ends the body with an error, so that the client does not take the output as complete.
+    fn abort(&mut self) {
+        let error = io::Error::other("the output was aborted");
+        self.sender.blocking_send(Err(error)).ok();
+    }
+}
+
+impl io::Write for BodyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.chunk.extend_from_slice(buf);
+        if self.chunk.len() >= CHUNK_SIZE {
+            self.send_chunk()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_chunk()
+    }
+}
+
+impl Drop for BodyWriter {
+    fn drop(&mut self) {
+        if std::thread::panicking() && self.started {
+            self.abort();
+        }
+    }
+}
+
+/// Runs write_output on a blocking thread and streams everything it writes into the returned
+/// body, so the output is never held in memory as a whole.
+/// The body is returned as soon as the first chunk is written. If write_output fails before, its
+/// error is returned instead (e.g., to answer invalid input with an error status). A later error
+/// aborts the body.
+pub async fn stream_output<E: Send + 'static>(
+    write_output: impl FnOnce(&mut BodyWriter) -> Result<(), E> + Send + 'static,
+) -> Result<Body, E> {
+    let (sender, mut receiver) = mpsc::channel(PENDING_CHUNKS);
+    let writing = tokio::task::spawn_blocking(move || {
+        let mut writer = BodyWriter {
+            sender,
+            chunk: Vec::with_capacity(CHUNK_SIZE),
+            started: false,
+        };
+        let result = write_output(&mut writer);
+        if result.is_ok() {
+            // the last chunk can only fail if the client is gone, so nobody is left to tell
+            writer.send_chunk().ok();
+        } else if writer.started {
+            writer.abort();
+        }
+        result
+    });
+    match receiver.recv().await {
+        Some(first_chunk) => {
+            let chunks = stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|chunk| (chunk, receiver))
+            });
+            Ok(Body::from_stream(
+                stream::once(async { first_chunk }).chain(chunks),
+            ))
+        }
+        // nothing was written
+        None => match writing.await {
+            Ok(result) => result.map(|()| Body::empty()),
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        },
+    }
+}
@@ -0,0 +1,64 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::io::Write;
+
+use super::*;
+
+#[tokio::test]
+async fn output_is_streamed_into_the_body() {
+    // ARRANGE
+    let output: Vec<u8> = (0..3 * CHUNK_SIZE).map(|i| (i % 251) as u8).collect();
+    let written = output.clone();
+
+    // ACT
+    let body = stream_output(move |writer| {
+        for part in written.chunks(1000) {
+            writer.write_all(part)?;
+        }
+        Ok::<(), io::Error>(())
+    })
+    .await
+    .unwrap();
+
+    // ASSERT
+    let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+    assert_eq!(bytes.to_vec(), output);
+}
+
+#[tokio::test]
+async fn error_before_the_first_chunk_is_returned() {
+    // ACT
+    let result = stream_output(|writer| {
+        writer.write_all(b"{\"partial\": ").unwrap();
+        Err("invalid input")
+    })
+    .await;
+
+    // ASSERT
+    assert_eq!(result.err(), Some("invalid input"));
+}
+
+#[tokio::test]
+async fn error_after_the_first_chunk_aborts_the_body() {
+    // ACT
+    let body = stream_output(|writer| {
+        writer.write_all(&vec![b' '; CHUNK_SIZE]).unwrap();
+        Err("writing failed")
+    })
+    .await
+    .unwrap();
+
+    // ASSERT
+    assert!(axum::body::to_bytes(body, usize::MAX).await.is_err());
+}
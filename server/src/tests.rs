@@ -0,0 +1,973 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::{fs::File, io::Read};
+
+use model::json_serialisation::load_rolling_stock_problem_instance_from_json;
+use model::warnings::Warning;
+
+use crate::{
+    analyze_instance, create_configuration_json, solve, solve_instance,
+    try_solve_instance_to_writer, try_solve_instance_with_warm_start_to_writer, SolveError,
+    SolveMode, SolveOptions,
+};
+
+/// Replaces all leaves by their type, such that only the structure of the json remains.
+fn schema_of(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), schema_of(value)))
+                .collect(),
+        ),
+        serde_json::Value::Array(array) => array.iter().map(schema_of).collect(),
+        serde_json::Value::Null => "null".into(),
+        serde_json::Value::Bool(_) => "boolean".into(),
+        serde_json::Value::Number(_) => "number".into(),
+        serde_json::Value::String(_) => "string".into(),
+    }
+}
+
+#[test]
+fn configuration_json_schema_is_stable() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["timePartitioning"] =
+        serde_json::json!({"windowLength": 21600, "overlap": 3600});
+    input_data["parameters"]["fixedFleet"] = serde_json::json!({"IC": 2});
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ACT
+    let configuration = create_configuration_json(&network);
+
+    // ASSERT
+    assert_eq!(
+        schema_of(&configuration),
+        serde_json::json!({
+            "solverVersion": "string",
+            "parameters": {
+                "forbidDeadHeadTrips": "boolean",
+                "maximalDeadHeadDuration": "null",
+                "dayLimitThreshold": "number",
+                "shunting": {
+                    "minimalDuration": "number",
+                    "deadHeadTripDuration": "number",
+                },
+                "maintenance": {
+                    "maximalDistance": "number",
+                    "depotCredit": "null",
+                    "hard": "boolean",
+                },
+                "costs": {
+                    "staff": "number",
+                    "serviceTrip": "number",
+                    "maintenance": "number",
+                    "deadHeadTrip": "number",
+                    "idle": "number",
+                    "linePurity": "number",
+                    "hitchHikeBonus": "number",
+                    "deadHeadTripCount": "number",
+                    "dutyLengthVariance": "number",
+                    "staffTimeFactors": [],
+                },
+                "timePartitioning": {
+                    "windowLength": "number",
+                    "overlap": "number",
+                    "polishTimeLimit": "null",
+                },
+                "fixedFleet": {
+                    "IC": "number",
+                },
+                "checkCosts": "boolean",
+                "formationOrderContinuity": "boolean",
+                "deadHeadPairingTolerance": "number",
+                "collapseLocationGroups": "boolean",
+                "memoryBudget": "null",
+                "planningWindow": "null",
+                "adaptiveOperatorWeights": "null",
+                "webhook": "null",
+                "milestones": [],
+                "outOfService": {
+                    "hard": "boolean",
+                },
+                "activeVehicleTypes": "null",
+                "debug": {
+                    "traceFile": "null",
+                },
+                "timeLimit": "null",
+                "numberOfThreads": "null",
+                "solver": {
+                    "twoPhase": "boolean",
+                },
+                "infeasibilityThreshold": "null",
+                "infeasibilityPolicy": "null",
+                "depotAssignment": {
+                    "policy": "string",
+                    "distanceTolerance": "number",
+                },
+                "output": {
+                    "level": "string",
+                    "crowdingThresholds": {
+                        "moderate": "number",
+                        "high": "number",
+                    },
+                    "contractDeadHeadAllocation": "string",
+                },
+            },
+            "maintenanceConsidered": "boolean",
+            "objective": [
+                ["string"],
+                ["string"],
+                ["string"],
+                ["string"],
+                ["string"],
+                ["string"],
+            ],
+            "neighborhood": {
+                "segmentLengthLimit": "number",
+                "overheadThreshold": "number",
+                "dummyCompactionThreshold": "number",
+            },
+        })
+    );
+
+    // defaults are visible
+    assert_eq!(configuration["parameters"]["costs"]["linePurity"], 0);
+    assert_eq!(
+        configuration["objective"],
+        serde_json::json!([
+            ["fixedFleetViolation"],
+            ["unservedPassengers"],
+            ["formationShortfall"],
+            ["vehiclesWithoutMaintenance"],
+            ["maintenanceViolation"],
+            ["costs"],
+        ])
+    );
+}
+
+#[test]
+fn analyze_instance_reports_demand_and_unservable_trips() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    // trip_0_seg_0 (IR, capacity 80, formation limit 1) with 500 passengers
+    input_data["departures"][0]["segments"][0]["passengers"] = serde_json::json!(500);
+
+    // ACT
+    let analysis = analyze_instance(input_data, false).unwrap();
+
+    // ASSERT
+    let trips = analysis["trips"].as_array().unwrap();
+    assert_eq!(trips.len(), 4);
+    let overcrowded = trips
+        .iter()
+        .find(|trip| trip["id"] == "trip_0_seg_0")
+        .unwrap();
+    assert_eq!(overcrowded["vehicleType"], "IR");
+    assert_eq!(overcrowded["capacity"], 80);
+    assert_eq!(overcrowded["requiredVehicles"], 7);
+    assert_eq!(overcrowded["maximalFormationCount"], 1);
+    assert_eq!(overcrowded["minimalNumberOfVehicles"], 1);
+    assert_eq!(overcrowded["structurallyUnservable"], true);
+    assert_eq!(
+        analysis["structurallyUnservableTrips"],
+        serde_json::json!(["trip_0_seg_0"])
+    );
+    assert_eq!(
+        analysis["histograms"],
+        serde_json::json!([
+            {
+                "vehicleType": "IC",
+                "histogram": [
+                    // trip_1b_seg_0 has no passengers, which the loader repairs to one
+                    {"requiredVehicles": 1, "numberOfTrips": 1},
+                    {"requiredVehicles": 2, "numberOfTrips": 1},
+                ],
+            },
+            {
+                "vehicleType": "IR",
+                "histogram": [
+                    {"requiredVehicles": 3, "numberOfTrips": 1},
+                    {"requiredVehicles": 7, "numberOfTrips": 1},
+                ],
+            },
+        ])
+    );
+}
+
+#[test]
+fn analyze_instance_reports_depot_accessibility() {
+    // ARRANGE
+    // depot_ZH no longer admits IR, so the trip_0 segments (IR) are accessible from no depot
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["depots"][0]["allowedTypes"] =
+        serde_json::json!([{"vehicleType": "IC", "upperBound": 7}]);
+
+    // ACT
+    let analysis = analyze_instance(input_data, true).unwrap();
+
+    // ASSERT
+    let accessibility = &analysis["depotAccessibility"];
+    let entry = |depot: &str, vehicle_type: &str| {
+        accessibility["matrix"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["depot"] == depot && entry["vehicleType"] == vehicle_type)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(accessibility["matrix"].as_array().unwrap().len(), 4);
+    assert_eq!(entry("depot_ZH", "IC")["accessibleTripCount"], 2);
+    assert_eq!(entry("depot_BN", "IC")["accessibleTripCount"], 2);
+    assert_eq!(entry("depot_ZH", "IR")["accessibleTripCount"], 0);
+    assert_eq!(
+        entry("depot_ZH", "IR")["accessibleTrips"],
+        serde_json::json!([])
+    );
+    assert_eq!(
+        accessibility["totalsPerDepot"],
+        serde_json::json!([
+            {"depot": "depot_BN", "accessibleTripCount": 2},
+            {"depot": "depot_ZH", "accessibleTripCount": 2},
+        ])
+    );
+    assert_eq!(
+        accessibility["totalsPerVehicleType"],
+        serde_json::json!([
+            {"vehicleType": "IC", "tripCount": 2, "accessibleTripCount": 2},
+            {"vehicleType": "IR", "tripCount": 2, "accessibleTripCount": 0},
+        ])
+    );
+    assert_eq!(
+        accessibility["tripsAccessibleFromNoDepot"],
+        serde_json::json!(["trip_0_seg_0", "trip_0_seg_1"])
+    );
+}
+
+#[test]
+fn output_level_omits_sections() {
+    for (level, expected_sections) in [
+        ("objectiveOnly", vec!["info", "objectiveValue"]),
+        (
+            "summary",
+            vec![
+                "info",
+                "warnings",
+                "objectiveValue",
+                "statistics",
+                "suggestions",
+            ],
+        ),
+    ] {
+        // ARRANGE
+        let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+        let mut input_data = String::new();
+        file.read_to_string(&mut input_data).unwrap();
+        let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+        input_data["parameters"]["output"] = serde_json::json!({ "level": level });
+
+        // ACT
+        let output = solve_instance(input_data);
+
+        // ASSERT
+        let sections: Vec<&str> = output
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|key| key.as_str())
+            .collect();
+        assert_eq!(sections, expected_sections);
+        assert_eq!(
+            output["info"]["configuration"]["parameters"]["output"]["level"],
+            level
+        );
+    }
+}
+
+#[test]
+fn unsatisfiable_hard_maintenance_is_reported() {
+    // ARRANGE
+    // every kilometer needs a maintenance visit, which the two maintenance slots cannot provide
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["maintenance"] =
+        serde_json::json!({ "maximalDistance": 1, "hard": true });
+
+    // ACT
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    let maintenance_hard_constraint = &output["maintenanceHardConstraint"];
+    assert_eq!(maintenance_hard_constraint["satisfied"], false);
+    assert_eq!(
+        maintenance_hard_constraint["maintenanceViolation"],
+        output["objectiveValue"]["maintenanceViolation"]
+    );
+    assert!(!maintenance_hard_constraint["violatingCycles"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+    assert!(output["info"]["phaseTimings"]["maintenanceRepair"].is_object());
+    assert!(output["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(
+            |warning| warning["code"] == "maintenanceHardConstraintViolated"
+                && warning["severity"] == "warning"
+        ));
+    assert_eq!(
+        output["info"]["configuration"]["parameters"]["maintenance"]["hard"],
+        true
+    );
+}
+
+#[test]
+fn collapsed_location_groups_keep_original_location_ids() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["locationGroups"] = serde_json::json!([
+        {"id": "BN_LU", "members": ["BN", "LU"], "transferDuration": 900}
+    ]);
+    input_data["parameters"]["collapseLocationGroups"] = true.into();
+
+    // ACT
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    assert_eq!(
+        output["info"]["configuration"]["parameters"]["collapseLocationGroups"],
+        true
+    );
+    assert!(output["schedule"]["depotLoads"].is_array());
+    assert!(!output["schedule"].to_string().contains("BN_LU"));
+    assert!(output["schedule"].to_string().contains("\"LU\""));
+}
+
+#[test]
+fn time_limit_of_zero_is_unlimited() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let mut input_data_with_zero_limit = input_data.clone();
+    input_data_with_zero_limit["parameters"]["timeLimit"] = 0.into();
+
+    // ACT
+    let output = solve_instance(input_data);
+    let output_with_zero_limit = solve_instance(input_data_with_zero_limit);
+
+    // ASSERT
+    assert_eq!(
+        output_with_zero_limit["info"]["configuration"]["parameters"]["timeLimit"],
+        serde_json::Value::Null
+    );
+    assert_eq!(output_with_zero_limit["info"]["timeLimitReached"], false);
+    assert_eq!(output["info"]["timeLimitReached"], false);
+    assert_eq!(
+        output_with_zero_limit["objectiveValue"],
+        output["objectiveValue"]
+    );
+}
+
+#[test]
+fn memory_budget_does_not_change_the_result() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let mut input_data_with_budget = input_data.clone();
+    // far too small, so only one neighbor is materialized at a time
+    input_data_with_budget["parameters"]["memoryBudget"] = 0.into();
+
+    // ACT
+    let output = solve_instance(input_data);
+    let output_with_budget = solve_instance(input_data_with_budget);
+
+    // ASSERT
+    assert_eq!(
+        output_with_budget["info"]["configuration"]["parameters"]["memoryBudget"],
+        0
+    );
+    assert_eq!(
+        output_with_budget["objectiveValue"],
+        output["objectiveValue"]
+    );
+}
+
+#[test]
+fn failing_hints_are_reported_without_aborting() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["hints"] = serde_json::json!([
+        {"mergeTours": {"vehicle": "veh_9999", "intoVehicle": "veh_0"}},
+        {"moveTrip": {"trip": "unknown_trip", "toVehicle": "veh_0"}}
+    ]);
+
+    // ACT
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    let outcomes = &output["info"]["phaseTimings"]["hints"]["outcomes"];
+    assert_eq!(
+        outcomes[0]["hint"],
+        serde_json::json!({"mergeTours": {"vehicle": "veh_9999", "intoVehicle": "veh_0"}})
+    );
+    assert_eq!(outcomes[0]["applied"], false);
+    assert_eq!(outcomes[0]["reason"], "Unknown vehicle veh_9999.");
+    assert_eq!(outcomes[1]["applied"], false);
+    assert_eq!(outcomes[1]["reason"], "Unknown trip unknown_trip.");
+    assert!(output["schedule"].is_object());
+}
+
+#[test]
+fn warm_start_from_previous_result_is_rebuilt_completely() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let warm_start =
+        try_solve_instance_with_warm_start_to_writer(input_data.clone(), None, std::io::sink())
+            .unwrap();
+
+    // ACT
+    let mut body: Vec<u8> = Vec::new();
+    try_solve_instance_with_warm_start_to_writer(
+        input_data,
+        Some(std::sync::Arc::new(warm_start)),
+        &mut body,
+    )
+    .unwrap();
+    let output: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    // ASSERT
+    let warm_start_phase = &output["info"]["phaseTimings"]["warmStart"];
+    assert_eq!(warm_start_phase["skippedNodes"], 0);
+    assert!(output["schedule"].is_object());
+}
+
+#[test]
+fn number_of_threads_limits_the_pool_of_the_solve() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let mut input_data_with_one_thread = input_data.clone();
+    input_data_with_one_thread["parameters"]["numberOfThreads"] = 1.into();
+
+    // ACT
+    let output = solve_instance(input_data);
+    let output_with_one_thread = solve_instance(input_data_with_one_thread);
+
+    // ASSERT
+    assert_eq!(output_with_one_thread["info"]["numberOfThreads"], 1);
+    assert_eq!(
+        output_with_one_thread["info"]["configuration"]["parameters"]["numberOfThreads"],
+        1
+    );
+    // the local search is deterministic, whatever the number of threads
+    assert_eq!(
+        output_with_one_thread["objectiveValue"],
+        output["objectiveValue"]
+    );
+}
+
+#[test]
+fn two_phase_keeps_the_fleet_of_the_fleet_phase() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["parameters"]["solver"] = serde_json::json!({"twoPhase": true});
+
+    // ACT
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    assert_eq!(
+        output["info"]["configuration"]["parameters"]["solver"]["twoPhase"],
+        true
+    );
+    let two_phase = &output["info"]["phaseTimings"]["localSearch"]["twoPhase"];
+    assert_eq!(
+        two_phase["fleetPhase"]["vehicleCount"],
+        two_phase["frozenFleetSize"]
+    );
+    assert_eq!(
+        two_phase["qualityPhase"]["vehicleCount"],
+        two_phase["frozenFleetSize"]
+    );
+    assert_eq!(
+        output["objectiveValue"]["vehicleCount"],
+        two_phase["frozenFleetSize"]
+    );
+    assert!(two_phase["frozenFleetSizePerVehicleType"].is_object());
+}
+
+#[test]
+fn convergence_has_a_segment_per_phase() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+
+    // ACT
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    let convergence = output["info"]["convergence"].as_array().unwrap();
+    let phases: Vec<&str> = convergence
+        .iter()
+        .map(|segment| segment["phase"].as_str().unwrap())
+        .collect();
+    assert_eq!(phases, vec!["localSearch", "transitionOptimization"]);
+    // the local search starts with the objective value of the start schedule
+    let first_point = &convergence[0]["points"][0];
+    assert!(first_point["objectiveValue"].is_object());
+    let last_point = convergence[1]["points"].as_array().unwrap().last().unwrap();
+    assert!(
+        last_point["elapsedSeconds"].as_f64().unwrap()
+            >= first_point["elapsedSeconds"].as_f64().unwrap()
+    );
+}
+
+#[test]
+fn initial_schedule_covering_all_trips_replaces_min_cost_flow() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["initialSchedule"] = solve_instance(input_data.clone())["schedule"].clone();
+
+    // ACT
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    let phase_timings = &output["info"]["phaseTimings"];
+    assert!(phase_timings["minCostFlow"].is_null());
+    assert_eq!(
+        phase_timings["initialSchedule"]["coversAllServiceTrips"],
+        true
+    );
+    assert_eq!(phase_timings["initialSchedule"]["used"], true);
+    assert!(output["schedule"].is_object());
+}
+
+#[test]
+fn initial_schedule_with_unknown_departure_segment_is_rejected() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let mut initial_schedule = solve_instance(input_data.clone())["schedule"].clone();
+    initial_schedule["fleet"][0]["vehicles"][0]["departureSegments"][0]["departureSegment"] =
+        serde_json::json!("unknown_segment");
+    input_data["initialSchedule"] = initial_schedule;
+
+    // ACT
+    let result = try_solve_instance_to_writer(input_data, std::io::sink());
+
+    // ASSERT
+    let Err(SolveError::InvalidInput(errors)) = result else {
+        panic!("initialSchedule should be rejected");
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "invalidInitialSchedule");
+    assert!(errors[0].message.contains("unknown_segment"));
+}
+
+/// Two locations A and B (one hour apart), one depot at A. trip_ab brings one vehicle to B, where
+/// trip_ba1 and trip_ba2 depart in parallel. Hence, the second vehicle either dead-heads from the
+/// depot to B or hitch-hikes on trip_ab. Dead-head trips are cheaper than service trips.
+fn hitch_hiking_instance(hitch_hike_bonus: u64) -> serde_json::Value {
+    let segment = |id: &str, route_segment: &str, departure: &str| {
+        serde_json::json!({
+            "id": id,
+            "routeSegment": route_segment,
+            "departure": departure,
+            "passengers": 10,
+            "seated": 0,
+        })
+    };
+    let route = |id: &str, origin: &str, destination: &str| {
+        serde_json::json!({
+            "id": id,
+            "vehicleType": "IC",
+            "segments": [{
+                "id": format!("{}_seg", id),
+                "order": 0,
+                "origin": origin,
+                "destination": destination,
+                "distance": 100000,
+                "duration": 3600,
+            }],
+        })
+    };
+    serde_json::json!({
+        "vehicleTypes": [{"id": "IC", "capacity": 100, "seats": 50, "maximalFormationCount": 2}],
+        "locations": [{"id": "A"}, {"id": "B"}],
+        "depots": [{
+            "id": "depot_A",
+            "location": "A",
+            "capacity": 10,
+            "allowedTypes": [{"vehicleType": "IC"}],
+            "providesMaintenance": true,
+        }],
+        "routes": [route("route_ab", "A", "B"), route("route_ba", "B", "A")],
+        "departures": [
+            {"id": "trip_ab", "route": "route_ab", "segments": [segment("trip_ab_seg", "route_ab_seg", "2023-07-24T08:00:00")]},
+            {"id": "trip_ba1", "route": "route_ba", "segments": [segment("trip_ba1_seg", "route_ba_seg", "2023-07-24T09:30:00")]},
+            {"id": "trip_ba2", "route": "route_ba", "segments": [segment("trip_ba2_seg", "route_ba_seg", "2023-07-24T09:40:00")]},
+        ],
+        "deadHeadTrips": {
+            "indices": ["A", "B"],
+            "durations": [[0, 3600], [3600, 0]],
+            "distances": [[0, 100000], [100000, 0]],
+        },
+        "parameters": {
+            "shunting": {"minimalDuration": 0, "deadHeadTripDuration": 0},
+            "maintenance": {"maximalDistance": 30000000},
+            "costs": {
+                "staff": 0,
+                "serviceTrip": 2,
+                "deadHeadTrip": 1,
+                "idle": 0,
+                "hitchHikeBonus": hitch_hike_bonus,
+            },
+        },
+    })
+}
+
+#[test]
+fn hitch_hike_bonus_makes_hitch_hiking_worthwhile() {
+    // ARRANGE
+    // per second, hitch-hiking costs 2 (service trip) and dead-heading 1
+    let without_bonus = hitch_hiking_instance(0);
+    let with_bonus = hitch_hiking_instance(2);
+
+    // ACT
+    let output_without_bonus = solve_instance(without_bonus);
+    let output_with_bonus = solve_instance(with_bonus);
+
+    // ASSERT
+    assert_eq!(output_without_bonus["statistics"]["hitchHikerCount"], 0);
+    assert!(output_without_bonus["objectiveValue"]["costs + hitchHikeBonus"].is_null());
+    assert!(output_without_bonus["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|warning| warning["code"] == "deadHeadTripCostsTooLow"));
+
+    assert_eq!(output_with_bonus["statistics"]["hitchHikerCount"], 1);
+    assert_eq!(output_with_bonus["objectiveValue"]["vehicleCount"], 2);
+    // the bonus is part of the costs level: four service trip hours minus one hitch-hiked hour
+    assert_eq!(
+        output_with_bonus["objectiveValue"]["costs + hitchHikeBonus"],
+        2 * 4 * 3600 - 2 * 3600
+    );
+    assert!(!output_with_bonus["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|warning| warning["code"] == "deadHeadTripCostsTooLow"));
+}
+
+#[test]
+fn typed_solve_matches_json_solve() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let network = load_rolling_stock_problem_instance_from_json(input_data.clone());
+
+    // ACT
+    let result = solve(network.clone(), SolveOptions::default());
+    let start_schedule_result = solve(
+        network,
+        SolveOptions {
+            mode: SolveMode::StartSchedule,
+            ..SolveOptions::default()
+        },
+    );
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    let result_json = result.to_json();
+    assert_eq!(result_json["objectiveValue"], output["objectiveValue"]);
+    assert_eq!(serde_json::json!(result.warnings()), output["warnings"]);
+
+    let phases: Vec<&str> = start_schedule_result
+        .phase_timings()
+        .iter()
+        .map(|phase| phase.name())
+        .collect();
+    assert_eq!(phases, vec!["minCostFlow", "depotImprovement"]);
+}
+
+#[test]
+fn cancelled_solve_is_marked_in_the_output() {
+    // ARRANGE
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let network = load_rolling_stock_problem_instance_from_json(input_data);
+
+    // ACT
+    let cancelled_result = solve(
+        network.clone(),
+        SolveOptions {
+            cancellation: Some(Arc::new(AtomicBool::new(true))),
+            ..SolveOptions::default()
+        },
+    );
+    let result = solve(
+        network,
+        SolveOptions {
+            cancellation: Some(Arc::new(AtomicBool::new(false))),
+            ..SolveOptions::default()
+        },
+    );
+
+    // ASSERT
+    assert_eq!(cancelled_result.to_json()["info"]["cancelled"], true);
+    assert_eq!(result.to_json()["info"]["cancelled"], false);
+}
+
+/// Two locations A and B (one hour apart), one depot at A. trip_1 and trip_2 both run from A to B,
+/// two hours apart. A single vehicle can serve both by dead-heading from B back to A in between.
+fn dead_head_cap_instance(maximal_dead_head_duration: Option<u64>) -> serde_json::Value {
+    let departure = |id: &str, departure: &str| {
+        serde_json::json!({
+            "id": id,
+            "route": "route_ab",
+            "segments": [{
+                "id": format!("{}_seg", id),
+                "routeSegment": "route_ab_seg",
+                "departure": departure,
+                "passengers": 10,
+                "seated": 0,
+            }],
+        })
+    };
+    serde_json::json!({
+        "vehicleTypes": [{"id": "IC", "capacity": 100, "seats": 50, "maximalFormationCount": 2}],
+        "locations": [{"id": "A"}, {"id": "B"}],
+        "depots": [{
+            "id": "depot_A",
+            "location": "A",
+            "capacity": 10,
+            "allowedTypes": [{"vehicleType": "IC"}],
+        }],
+        "routes": [{
+            "id": "route_ab",
+            "vehicleType": "IC",
+            "segments": [{
+                "id": "route_ab_seg",
+                "order": 0,
+                "origin": "A",
+                "destination": "B",
+                "distance": 100000,
+                "duration": 3600,
+            }],
+        }],
+        "departures": [
+            departure("trip_1", "2023-07-24T08:00:00"),
+            departure("trip_2", "2023-07-24T11:00:00"),
+        ],
+        "deadHeadTrips": {
+            "indices": ["A", "B"],
+            "durations": [[0, 3600], [3600, 0]],
+            "distances": [[0, 100000], [100000, 0]],
+        },
+        "parameters": {
+            "maximalDeadHeadDuration": maximal_dead_head_duration,
+            "shunting": {"minimalDuration": 0, "deadHeadTripDuration": 0},
+            "costs": {"staff": 100, "serviceTrip": 2, "deadHeadTrip": 1, "idle": 0},
+        },
+    })
+}
+
+#[test]
+fn tightening_maximal_dead_head_duration_increases_fleet_size() {
+    // ARRANGE
+    let without_cap = dead_head_cap_instance(None);
+    let loose_cap = dead_head_cap_instance(Some(3600));
+    let tight_cap = dead_head_cap_instance(Some(1800));
+
+    // ACT
+    let output_without_cap = solve_instance(without_cap);
+    let output_loose_cap = solve_instance(loose_cap);
+    let output_tight_cap = solve_instance(tight_cap);
+
+    // ASSERT
+    assert_eq!(output_without_cap["objectiveValue"]["vehicleCount"], 1);
+    assert_eq!(output_loose_cap["objectiveValue"]["vehicleCount"], 1);
+    assert_eq!(output_tight_cap["objectiveValue"]["vehicleCount"], 2);
+    assert_eq!(
+        output_tight_cap["info"]["configuration"]["parameters"]["maximalDeadHeadDuration"],
+        1800
+    );
+}
+
+#[test]
+fn service_trips_isolated_by_maximal_dead_head_duration_are_reported() {
+    // ARRANGE
+    let loose_cap = dead_head_cap_instance(Some(3600));
+    let tight_cap = dead_head_cap_instance(Some(1800));
+
+    // ACT
+    let loose_network = load_rolling_stock_problem_instance_from_json(loose_cap);
+    let tight_network = load_rolling_stock_problem_instance_from_json(tight_cap);
+
+    // ASSERT
+    let isolated_warnings = |warnings: &[Warning]| -> Vec<Warning> {
+        warnings
+            .iter()
+            .filter(|warning| warning.code == "serviceTripIsolatedByDeadHeadCap")
+            .cloned()
+            .collect()
+    };
+    assert!(isolated_warnings(loose_network.warnings()).is_empty());
+    let tight_warnings = isolated_warnings(tight_network.warnings());
+    assert_eq!(tight_warnings.len(), 2);
+    // service trips are identified by their segment ids
+    assert_eq!(tight_warnings[0].context, vec!["trip_1_seg".to_string()]);
+    assert_eq!(tight_warnings[1].context, vec!["trip_2_seg".to_string()]);
+}
+
+#[test]
+fn vehicle_type_without_depot_is_solved_with_overflow_depot() {
+    // ARRANGE
+    // depot_ZH no longer admits IR and depot_BN never did
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["depots"][0]["allowedTypes"] =
+        serde_json::json!([{"vehicleType": "IC", "upperBound": 7}]);
+
+    // ACT
+    let analysis = analyze_instance(input_data.clone(), false).unwrap();
+    let output = solve_instance(input_data);
+
+    // ASSERT
+    assert_eq!(
+        analysis["depotAccessibility"]["vehicleTypesWithoutDepot"],
+        serde_json::json!(["IR"])
+    );
+    assert!(output["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|warning| warning["code"] == "vehicleTypeWithoutDepot"
+            && warning["context"] == serde_json::json!(["IR"])));
+    assert!(output["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|warning| warning["code"] == "overflowDepotUsed"
+            && warning["context"] == serde_json::json!(["IR"])));
+    let only_overflow_depot = |vehicle_type: &str| {
+        output["schedule"]["fleet"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|fleet| fleet["vehicleType"] == vehicle_type)
+            .unwrap()["onlyOverflowDepot"]
+            .clone()
+    };
+    assert_eq!(only_overflow_depot("IR"), serde_json::json!(true));
+    assert_eq!(only_overflow_depot("IC"), serde_json::json!(false));
+}
+
+#[test]
+fn likely_infeasible_instance_is_aborted_after_the_min_cost_flow() {
+    // ARRANGE
+    // no depot admits IR, so its passengers are only carried by vehicles of the overflow depot
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    input_data["depots"][0]["allowedTypes"] =
+        serde_json::json!([{"vehicleType": "IC", "upperBound": 7}]);
+    input_data["parameters"]["infeasibilityThreshold"] = serde_json::json!(0.0);
+    let mut warn_input_data = input_data.clone();
+    warn_input_data["parameters"]["infeasibilityPolicy"] = serde_json::json!("warn");
+    input_data["parameters"]["infeasibilityPolicy"] = serde_json::json!("abort");
+
+    // ACT
+    let aborted_output = solve_instance(input_data);
+    let warned_output = solve_instance(warn_input_data);
+
+    // ASSERT
+    let assessment = &aborted_output["info"]["phaseTimings"]["feasibilityAssessment"];
+    assert_eq!(assessment["likelyInfeasible"], true);
+    assert_eq!(assessment["aborted"], true);
+    assert!(assessment["uncoveredDemand"]["share"].as_f64().unwrap() > 0.0);
+    let bottleneck_types = assessment["bottlenecks"]["vehicleTypes"]
+        .as_array()
+        .unwrap();
+    assert!(bottleneck_types
+        .iter()
+        .any(|bottleneck| bottleneck["vehicleType"] == "IR"
+            && bottleneck["overflowVehicles"].as_u64().unwrap() > 0));
+    assert!(aborted_output["info"]["phaseTimings"]["localSearch"].is_null());
+    assert!(aborted_output["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|warning| warning["code"] == "likelyInfeasible"
+            && warning["context"]
+                .as_array()
+                .unwrap()
+                .contains(&serde_json::json!("IR"))));
+
+    let assessment = &warned_output["info"]["phaseTimings"]["feasibilityAssessment"];
+    assert_eq!(assessment["likelyInfeasible"], true);
+    assert_eq!(assessment["aborted"], false);
+    assert!(!warned_output["info"]["phaseTimings"]["localSearch"].is_null());
+    assert_eq!(
+        warned_output["info"]["configuration"]["parameters"]["infeasibilityPolicy"],
+        "warn"
+    );
+}
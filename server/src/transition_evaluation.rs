@@ -0,0 +1,161 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+
+use model::base_types::{MaintenanceCounter, VehicleIdx, VehicleTypeIdx};
+use model::json_serialisation::{
+    try_load_rolling_stock_problem_instance_from_json, ValidationError,
+};
+use serde::{Deserialize, Serialize};
+use solution::json_serialisation::schedule_from_json;
+
+use crate::check_instance_hash;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransitionInput {
+    instance: serde_json::Value,
+    schedule: serde_json::Value,
+    instance_hash: Option<String>, // info.instanceHash of the output the schedule was taken from
+    #[serde(default)]
+    force: bool, // use the schedule even if instance_hash does not match the instance
+    successors: HashMap<String, String>, // vehicle -> successor in the next period
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonTransition {
+    vehicle_type: String,
+    overridden: bool,
+    maintenance_violation: MaintenanceCounter,
+    cycles: Vec<JsonTransitionCycle>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonTransitionCycle {
+    vehicles: Vec<String>,
+    maintenance_counter: MaintenanceCounter,
+    maintenance_violation: MaintenanceCounter,
+}
+
+/// Evaluates the maintenance violations of a schedule under the given next-period transitions
+/// (see Schedule::with_transition_overrides), the tours are not touched.
+/// The input consists of the instance, the schedule (as in the output of /solve, only the fleet
+/// is used) and the successors: {vehicle: successor}. For each vehicle type with a vehicle in
+/// successors, all its vehicles must be mapped and the mapping must be a permutation. The other
+/// vehicle types keep the transition computed for the rebuilt schedule. With the optional
+/// instanceHash (info.instanceHash of the output of /solve) the schedule is checked to belong to
+/// the instance (see check_instance_hash).
+/// Returns the transitions of all vehicle types with their cycles, where the vehicle ids are the
+/// ones of the given schedule.
+pub fn evaluate_transition(
+    input_data: serde_json::Value,
+) -> Result<serde_json::Value, Vec<ValidationError>> {
+    let input: TransitionInput = serde_json::from_value(input_data)
+        .map_err(|e| vec![transition_error("invalidTransitionInput", e.to_string())])?;
+    let network = try_load_rolling_stock_problem_instance_from_json(input.instance)?;
+    check_instance_hash(&network, input.instance_hash.as_deref(), input.force)
+        .map_err(|error| vec![error])?;
+    let (schedule, vehicle_ids) = schedule_from_json(input.schedule, network.clone())
+        .map_err(|message| vec![transition_error("invalidSchedule", message)])?;
+
+    let vehicles: HashMap<&str, VehicleIdx> = vehicle_ids
+        .iter()
+        .map(|(id, vehicle)| (id.as_str(), *vehicle))
+        .collect();
+    let lookup = |id: &String| {
+        vehicles
+            .get(id.as_str())
+            .copied()
+            .ok_or_else(|| transition_error("unknownVehicle", format!("Unknown vehicle {}.", id)))
+    };
+    let mut successors: HashMap<VehicleTypeIdx, HashMap<VehicleIdx, VehicleIdx>> = HashMap::new();
+    let mut errors = Vec::new();
+    for (vehicle_id, successor_id) in input.successors.iter() {
+        match (lookup(vehicle_id), lookup(successor_id)) {
+            (Ok(vehicle), Ok(successor)) => {
+                successors
+                    .entry(schedule.vehicle_type_of(vehicle).unwrap())
+                    .or_default()
+                    .insert(vehicle, successor);
+            }
+            (vehicle, successor) => {
+                errors.extend(vehicle.err());
+                errors.extend(successor.err());
+            }
+        }
+    }
+    if !errors.is_empty() {
+        errors.sort_by(|e1, e2| e1.message.cmp(&e2.message));
+        errors.dedup_by(|e1, e2| e1.message == e2.message);
+        return Err(errors);
+    }
+
+    let original_ids: HashMap<String, &str> = vehicle_ids
+        .iter()
+        .map(|(id, vehicle)| (vehicle.to_string(), id.as_str()))
+        .collect();
+    let original_id = |vehicle: VehicleIdx| original_ids[&vehicle.to_string()].to_string();
+    let schedule = schedule
+        .with_transition_overrides(&successors)
+        .map_err(|message| {
+            // the messages reference the internal vehicle ids
+            let message = message
+                .split(' ')
+                .map(|word| original_ids.get(word).copied().unwrap_or(word))
+                .collect::<Vec<_>>()
+                .join(" ");
+            vec![transition_error("invalidTransition", message)]
+        })?;
+
+    let transitions: Vec<JsonTransition> = network
+        .vehicle_types()
+        .iter()
+        .filter(|&vehicle_type| schedule.vehicles_iter(vehicle_type).next().is_some())
+        .map(|vehicle_type| {
+            let transition = schedule.next_day_transition_of(vehicle_type);
+            JsonTransition {
+                vehicle_type: network
+                    .vehicle_types()
+                    .get(vehicle_type)
+                    .unwrap()
+                    .id()
+                    .clone(),
+                overridden: successors.contains_key(&vehicle_type),
+                maintenance_violation: transition.maintenance_violation(),
+                cycles: transition
+                    .cycles_iter()
+                    .filter(|cycle| !cycle.is_empty())
+                    .map(|cycle| JsonTransitionCycle {
+                        vehicles: cycle.iter().map(original_id).collect(),
+                        maintenance_counter: cycle.maintenance_counter(),
+                        maintenance_violation: cycle.maintenance_counter().max(0),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "maintenanceViolation": schedule.maintenance_violation(),
+        "transitions": transitions,
+    }))
+}
+
+fn transition_error(code: &'static str, message: String) -> ValidationError {
+    ValidationError { code, message }
+}
@@ -0,0 +1,171 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fs::File, io::Read};
+
+use crate::solve_instance;
+
+use super::evaluate_transition;
+
+fn small_test_input() -> serde_json::Value {
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    serde_json::from_str(&input_data).unwrap()
+}
+
+/// The vehicle type and the vehicle ids of the first fleet with at least two vehicles.
+fn fleet_with_two_vehicles(schedule: &serde_json::Value) -> (String, Vec<String>) {
+    schedule["fleet"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find_map(|fleet| {
+            let vehicles: Vec<String> = fleet["vehicles"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|vehicle| vehicle["id"].as_str().unwrap().to_string())
+                .collect();
+            (vehicles.len() >= 2)
+                .then(|| (fleet["vehicleType"].as_str().unwrap().to_string(), vehicles))
+        })
+        .unwrap()
+}
+
+#[test]
+fn rotated_successors_form_one_cycle() {
+    // ARRANGE
+    let instance = small_test_input();
+    let schedule = solve_instance(instance.clone())["schedule"].clone();
+    let (vehicle_type, vehicles) = fleet_with_two_vehicles(&schedule);
+    let successors: serde_json::Map<String, serde_json::Value> = vehicles
+        .iter()
+        .zip(vehicles.iter().cycle().skip(1))
+        .map(|(vehicle, successor)| (vehicle.clone(), serde_json::json!(successor)))
+        .collect();
+    let input = serde_json::json!({
+        "instance": instance,
+        "schedule": schedule,
+        "successors": successors,
+    });
+
+    // ACT
+    let result = evaluate_transition(input).unwrap();
+
+    // ASSERT
+    let transitions = result["transitions"].as_array().unwrap();
+    let transition = transitions
+        .iter()
+        .find(|transition| transition["vehicleType"] == vehicle_type)
+        .unwrap();
+    assert_eq!(transition["overridden"], true);
+    let cycles = transition["cycles"].as_array().unwrap();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(
+        cycles[0]["maintenanceViolation"],
+        transition["maintenanceViolation"]
+    );
+    let cycle = cycles[0]["vehicles"].as_array().unwrap();
+    assert_eq!(cycle.len(), vehicles.len());
+    for (vehicle, successor) in cycle.iter().zip(cycle.iter().cycle().skip(1)) {
+        assert_eq!(&successors[vehicle.as_str().unwrap()], successor);
+    }
+    assert!(transitions
+        .iter()
+        .filter(|transition| transition["vehicleType"] != vehicle_type)
+        .all(|transition| transition["overridden"] == false));
+    assert_eq!(
+        result["maintenanceViolation"],
+        transitions
+            .iter()
+            .map(|transition| transition["maintenanceViolation"].as_i64().unwrap())
+            .sum::<i64>()
+    );
+}
+
+#[test]
+fn own_successors_form_single_vehicle_cycles() {
+    // ARRANGE
+    let instance = small_test_input();
+    let schedule = solve_instance(instance.clone())["schedule"].clone();
+    let (vehicle_type, vehicles) = fleet_with_two_vehicles(&schedule);
+    let successors: serde_json::Map<String, serde_json::Value> = vehicles
+        .iter()
+        .map(|vehicle| (vehicle.clone(), serde_json::json!(vehicle)))
+        .collect();
+    let input = serde_json::json!({
+        "instance": instance,
+        "schedule": schedule,
+        "successors": successors,
+    });
+
+    // ACT
+    let result = evaluate_transition(input).unwrap();
+
+    // ASSERT
+    let transition = result["transitions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|transition| transition["vehicleType"] == vehicle_type)
+        .unwrap();
+    let cycles = transition["cycles"].as_array().unwrap();
+    let mut cycle_vehicles: Vec<String> = cycles
+        .iter()
+        .map(|cycle| {
+            let cycle = cycle["vehicles"].as_array().unwrap();
+            assert_eq!(cycle.len(), 1);
+            cycle[0].as_str().unwrap().to_string()
+        })
+        .collect();
+    cycle_vehicles.sort();
+    let mut vehicles = vehicles;
+    vehicles.sort();
+    assert_eq!(cycle_vehicles, vehicles);
+}
+
+#[test]
+fn invalid_successors_are_rejected() {
+    // ARRANGE
+    let instance = small_test_input();
+    let schedule = solve_instance(instance.clone())["schedule"].clone();
+    let (_, vehicles) = fleet_with_two_vehicles(&schedule);
+    let input = |successors: serde_json::Value| {
+        serde_json::json!({
+            "instance": instance,
+            "schedule": schedule,
+            "successors": successors,
+        })
+    };
+
+    // ACT
+    let unknown_vehicle = evaluate_transition(input(serde_json::json!({"unknown": vehicles[0]})));
+    let missing_vehicle = evaluate_transition(input(serde_json::json!({
+        (vehicles[0].clone()): vehicles[0]
+    })));
+
+    // ASSERT
+    let errors = unknown_vehicle.unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "unknownVehicle");
+    assert!(errors[0].message.contains("unknown"));
+
+    let errors = missing_vehicle.unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "invalidTransition");
+    assert!(errors[0].message.ends_with("has no successor"));
+    assert!(vehicles[1..]
+        .iter()
+        .any(|vehicle| errors[0].message == format!("Vehicle {} has no successor", vehicle)));
+}
@@ -0,0 +1,140 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use model::base_types::NodeIdx;
+use model::network::Network;
+use solution::Schedule;
+
+/// The tours of a solved schedule in terms of the ids of the input (vehicle types, departure
+/// segments and maintenance slots), so that they can seed the solve of a changed instance (see
+/// SolveOptions::warm_start).
+#[derive(Clone, Debug, Default)]
+pub struct WarmStart {
+    tours: Vec<(String, Vec<String>)>, // vehicle type id and the ids of the non-depot nodes
+    objective_value: serde_json::Value, // of the solved schedule (Null if unknown)
+}
+
+impl WarmStart {
+    pub fn from_schedule(schedule: &Schedule) -> WarmStart {
+        let network = schedule.get_network();
+        WarmStart {
+            tours: schedule
+                .vehicles_iter_all()
+                .map(|vehicle| {
+                    let vehicle_type = schedule.vehicle_type_of(vehicle).unwrap();
+                    (
+                        network
+                            .vehicle_types()
+                            .get(vehicle_type)
+                            .unwrap()
+                            .id()
+                            .clone(),
+                        schedule
+                            .tour_of(vehicle)
+                            .unwrap()
+                            .all_non_depot_nodes_iter()
+                            .map(|node| network.node(node).id().to_string())
+                            .collect(),
+                    )
+                })
+                .collect(),
+            objective_value: serde_json::Value::Null,
+        }
+    }
+
+    /// Keeps the objective value of the solved schedule (as in the output json), e.g., to compare
+    /// follow-up solves with it.
+    pub fn with_objective_value(self, objective_value: serde_json::Value) -> WarmStart {
+        WarmStart {
+            objective_value,
+            ..self
+        }
+    }
+
+    pub fn objective_value(&self) -> &serde_json::Value {
+        &self.objective_value
+    }
+
+    pub fn number_of_tours(&self) -> usize {
+        self.tours.len()
+    }
+
+    /// Builds a schedule for the given network from the stored tours.
+    /// Ids that do not exist anymore (or whose vehicle type changed) are skipped, as well as
+    /// nodes that cannot be reached from the previous node of the tour. Afterwards, service trips
+    /// that are still not fully covered (e.g., added departures) get new vehicles and the depots
+    /// are improved.
+    /// Returns the schedule and the number of skipped nodes.
+    pub fn build_schedule(&self, network: Arc<Network>) -> (Schedule, usize) {
+        let node_lookup: HashMap<&str, NodeIdx> = network
+            .coverable_nodes()
+            .map(|node| (network.node(node).id(), node))
+            .collect();
+        let vehicle_types = network.vehicle_types();
+
+        let mut schedule = Schedule::empty(network.clone());
+        let mut skipped_nodes = 0;
+        for (vehicle_type_id, node_ids) in self.tours.iter() {
+            let Some(vehicle_type) = vehicle_types.iter().find(|&vehicle_type| {
+                vehicle_types.get(vehicle_type).unwrap().id() == vehicle_type_id
+            }) else {
+                skipped_nodes += node_ids.len();
+                continue;
+            };
+            let mut path: Vec<NodeIdx> = Vec::new();
+            for node_id in node_ids {
+                match node_lookup.get(node_id.as_str()) {
+                    Some(&node)
+                        if network.compatible_with_vehicle_type(node, vehicle_type)
+                            && path
+                                .last()
+                                .is_none_or(|&last| network.can_reach(last, node)) =>
+                    {
+                        path.push(node)
+                    }
+                    _ => skipped_nodes += 1,
+                }
+            }
+            if path.is_empty() {
+                continue;
+            }
+            match schedule.spawn_vehicle_for_path(vehicle_type, path.clone()) {
+                Ok((new_schedule, _)) => schedule = new_schedule,
+                Err(_) => skipped_nodes += path.len(),
+            }
+        }
+
+        // cover the remaining demand with new vehicles
+        for service_trip in network.all_service_nodes() {
+            for _ in 0..network.minimal_number_of_vehicles_for(service_trip) {
+                if schedule.is_fully_covered(service_trip)
+                    && schedule.formation_shortfall_at(service_trip) == 0
+                {
+                    break;
+                }
+                match schedule.spawn_vehicle_for_path(
+                    network.vehicle_type_for(service_trip),
+                    vec![service_trip],
+                ) {
+                    Ok((new_schedule, _)) => schedule = new_schedule,
+                    Err(_) => break,
+                }
+            }
+        }
+
+        (schedule.improve_depots(None), skipped_nodes)
+    }
+}
@@ -0,0 +1,241 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use model::config::{WebhookConfig, WebhookEvent};
+use model::warnings::Warning;
+use solver::progress::{Improvement, ProgressSink};
+use tracing::warn;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends a notification to the url of the webhook.
+pub trait Transport: Send + 'static {
+    fn post(&self, url: &str, payload: &serde_json::Value) -> Result<(), String>;
+}
+
+/// Posts the notification as json. Responses with an error status count as failed.
+pub struct HttpTransport;
+
+impl Transport for HttpTransport {
+    fn post(&self, url: &str, payload: &serde_json::Value) -> Result<(), String> {
+        ureq::post(url)
+            .timeout(REQUEST_TIMEOUT)
+            .send_json(payload)
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+    }
+}
+
+/// How failed notifications are handled. A notification is retried after each of the retry
+/// delays. After failure_threshold notifications in a row failed (after all retries), the circuit
+/// opens: notifications are dropped for the cooldown, then a single notification is tried again.
+#[derive(Clone)]
+pub struct DeliveryPolicy {
+    pub retry_delays: Vec<Duration>,
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl Default for DeliveryPolicy {
+    fn default() -> DeliveryPolicy {
+        DeliveryPolicy {
+            retry_delays: vec![Duration::from_secs(1), Duration::from_secs(5)],
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Notifies the webhook of parameters.webhook about the selected events of a job. The
+/// notifications are delivered by a background thread, so a slow or failing webhook never
+/// delays or fails the solve.
+/// Improvements are rate-limited: an improvement within the minimal interval after the last
+/// notified improvement is dropped.
+/// If the notifier is dropped before finished was called (e.g., the solve panicked), the failed
+/// event is sent.
+pub struct WebhookNotifier {
+    job_id: String,
+    events: Vec<WebhookEvent>,
+    min_interval: Duration,
+    start_time: Instant,
+    last_improvement: Mutex<Option<Instant>>,
+    last_iteration: AtomicU32,
+    terminated: AtomicBool, // finished or failed was sent
+    sender: Mutex<mpsc::Sender<serde_json::Value>>,
+}
+
+impl WebhookNotifier {
+    pub fn spawn(config: &WebhookConfig, job_id: &str) -> Arc<WebhookNotifier> {
+        WebhookNotifier::with_transport(config, job_id, HttpTransport, DeliveryPolicy::default())
+    }
+
+    pub fn with_transport(
+        config: &WebhookConfig,
+        job_id: &str,
+        transport: impl Transport,
+        policy: DeliveryPolicy,
+    ) -> Arc<WebhookNotifier> {
+        let (sender, receiver) = mpsc::channel();
+        let url = config.url.clone();
+        thread::spawn(move || deliver(transport, &url, &policy, receiver));
+        Arc::new(WebhookNotifier {
+            job_id: job_id.to_string(),
+            events: config.events.clone(),
+            min_interval: stdtime_of(config.min_interval),
+            start_time: Instant::now(),
+            last_improvement: Mutex::new(None),
+            last_iteration: AtomicU32::new(0),
+            terminated: AtomicBool::new(false),
+            sender: Mutex::new(sender),
+        })
+    }
+
+    /// Forwards the improvements of the local search to this notifier.
+    pub fn progress_sink(self: &Arc<Self>) -> ProgressSink {
+        let notifier = self.clone();
+        ProgressSink::new(move |improvement, _| notifier.improvement(improvement))
+    }
+
+    pub fn improvement(&self, improvement: Improvement) {
+        self.improvement_at(improvement, Instant::now())
+    }
+
+    pub fn finished(&self, objective_value: &serde_json::Value, warnings: &[Warning]) {
+        self.terminate(
+            WebhookEvent::Finished,
+            serde_json::json!({ "objectiveValue": objective_value, "warnings": warnings }),
+        );
+    }
+
+    pub fn failed(&self, reason: &str) {
+        self.terminate(
+            WebhookEvent::Failed,
+            serde_json::json!({ "reason": reason }),
+        );
+    }
+
+    fn improvement_at(&self, improvement: Improvement, now: Instant) {
+        self.last_iteration
+            .store(improvement.iteration, Ordering::Relaxed);
+        {
+            let mut last_improvement = self.last_improvement.lock().unwrap();
+            if last_improvement.is_some_and(|last| now < last + self.min_interval) {
+                return;
+            }
+            *last_improvement = Some(now);
+        }
+        self.send(
+            WebhookEvent::Improvement,
+            serde_json::json!({ "objectiveValue": improvement.objective_value }),
+            now,
+        );
+    }
+
+    fn terminate(&self, event: WebhookEvent, details: serde_json::Value) {
+        if !self.terminated.swap(true, Ordering::Relaxed) {
+            self.send(event, details, Instant::now());
+        }
+    }
+
+    fn send(&self, event: WebhookEvent, details: serde_json::Value, now: Instant) {
+        if !self.events.contains(&event) {
+            return;
+        }
+        let mut payload = serde_json::json!({
+            "jobId": self.job_id,
+            "event": event_name(event),
+            "iteration": self.last_iteration.load(Ordering::Relaxed),
+            "elapsedSeconds": now.duration_since(self.start_time).as_secs_f64(),
+        });
+        if let serde_json::Value::Object(details) = details {
+            payload.as_object_mut().unwrap().extend(details);
+        }
+        // the delivery thread only stops when the notifier is dropped
+        let _ = self.sender.lock().unwrap().send(payload);
+    }
+}
+
+impl Drop for WebhookNotifier {
+    fn drop(&mut self) {
+        self.failed("The solve was aborted.");
+    }
+}
+
+fn deliver(
+    transport: impl Transport,
+    url: &str,
+    policy: &DeliveryPolicy,
+    receiver: mpsc::Receiver<serde_json::Value>,
+) {
+    let mut consecutive_failures = 0;
+    let mut open_until: Option<Instant> = None;
+    for payload in receiver {
+        if open_until.is_some_and(|open_until| Instant::now() < open_until) {
+            continue;
+        }
+        match post_with_retries(&transport, url, &payload, &policy.retry_delays) {
+            Ok(()) => {
+                consecutive_failures = 0;
+                open_until = None;
+            }
+            Err(error) => {
+                consecutive_failures += 1;
+                warn!("Webhook notification to {} failed: {}", url, error);
+                if consecutive_failures >= policy.failure_threshold {
+                    warn!(
+                        "Webhook notifications are paused for {}sec",
+                        policy.cooldown.as_secs()
+                    );
+                    open_until = Some(Instant::now() + policy.cooldown);
+                }
+            }
+        }
+    }
+}
+
+fn post_with_retries(
+    transport: &impl Transport,
+    url: &str,
+    payload: &serde_json::Value,
+    retry_delays: &[Duration],
+) -> Result<(), String> {
+    let mut result = transport.post(url, payload);
+    for delay in retry_delays {
+        if result.is_ok() {
+            break;
+        }
+        thread::sleep(*delay);
+        result = transport.post(url, payload);
+    }
+    result
+}
+
+pub fn event_name(event: WebhookEvent) -> &'static str {
+    match event {
+        WebhookEvent::Improvement => "improvement",
+        WebhookEvent::Finished => "finished",
+        WebhookEvent::Failed => "failed",
+    }
+}
+
+fn stdtime_of(duration: rapid_time::Duration) -> Duration {
+    Duration::from_secs(duration.in_sec().unwrap())
+}
@@ -0,0 +1,177 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use model::config::{WebhookConfig, WebhookEvent};
+use model::warnings::{Severity, Warning};
+use solver::local_search::neighborhood::swaps::SwapInfo;
+use solver::progress::Improvement;
+
+use super::{DeliveryPolicy, Transport, WebhookNotifier};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reports every attempt. The receiver is disconnected when the delivery thread stops.
+struct RecordingTransport {
+    attempts: mpsc::Sender<serde_json::Value>,
+    failing: bool,
+}
+
+impl Transport for RecordingTransport {
+    fn post(&self, _: &str, payload: &serde_json::Value) -> Result<(), String> {
+        self.attempts.send(payload.clone()).unwrap();
+        if self.failing {
+            return Err("unavailable".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn webhook_config(events: Vec<WebhookEvent>, min_interval: u64) -> WebhookConfig {
+    WebhookConfig {
+        url: "http://localhost/webhook".to_string(),
+        events,
+        min_interval: rapid_time::Duration::from_seconds(min_interval),
+    }
+}
+
+fn improvement(iteration: u32) -> Improvement {
+    Improvement {
+        iteration,
+        objective_value: serde_json::json!({ "costs": 100 - iteration }),
+        swap_info: SwapInfo::NoSwap,
+        description: String::new(),
+    }
+}
+
+fn without_retries() -> DeliveryPolicy {
+    DeliveryPolicy {
+        retry_delays: Vec::new(),
+        failure_threshold: 2,
+        cooldown: Duration::from_secs(3600),
+    }
+}
+
+fn received(attempts: &mpsc::Receiver<serde_json::Value>) -> Vec<serde_json::Value> {
+    let mut payloads = Vec::new();
+    while let Ok(payload) = attempts.recv_timeout(TIMEOUT) {
+        payloads.push(payload);
+    }
+    payloads
+}
+
+#[test]
+fn improvements_are_rate_limited() {
+    // ARRANGE
+    let (sender, attempts) = mpsc::channel();
+    let notifier = WebhookNotifier::with_transport(
+        &webhook_config(vec![WebhookEvent::Improvement, WebhookEvent::Finished], 10),
+        "7",
+        RecordingTransport {
+            attempts: sender,
+            failing: false,
+        },
+        without_retries(),
+    );
+    let now = Instant::now();
+
+    // ACT
+    notifier.improvement_at(improvement(1), now);
+    notifier.improvement_at(improvement(2), now + Duration::from_secs(5));
+    notifier.improvement_at(improvement(3), now + Duration::from_secs(11));
+    notifier.finished(
+        &serde_json::json!({ "costs": 97 }),
+        &[Warning::new(
+            "overflowDepotUsed",
+            Severity::Warning,
+            "vehicle type IC uses the overflow depot (1 vehicles).".to_string(),
+        )],
+    );
+    drop(notifier);
+
+    // ASSERT
+    let payloads = received(&attempts);
+    let events: Vec<_> = payloads
+        .iter()
+        .map(|payload| payload["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(events, vec!["improvement", "improvement", "finished"]);
+    assert_eq!(payloads[0]["jobId"], "7");
+    assert_eq!(payloads[1]["iteration"], 3);
+    assert_eq!(payloads[1]["objectiveValue"]["costs"], 97);
+    assert_eq!(payloads[2]["iteration"], 3);
+    assert_eq!(payloads[2]["warnings"][0]["code"], "overflowDepotUsed");
+    assert_eq!(payloads[2]["warnings"][0]["severity"], "warning");
+}
+
+#[test]
+fn dropped_notifier_reports_failure_once() {
+    // ARRANGE
+    let (sender, attempts) = mpsc::channel();
+    let notifier = WebhookNotifier::with_transport(
+        &webhook_config(vec![WebhookEvent::Finished, WebhookEvent::Failed], 0),
+        "7",
+        RecordingTransport {
+            attempts: sender,
+            failing: false,
+        },
+        without_retries(),
+    );
+
+    // ACT
+    notifier.improvement_at(improvement(1), Instant::now());
+    drop(notifier);
+
+    // ASSERT
+    let payloads = received(&attempts);
+    assert_eq!(payloads.len(), 1);
+    assert_eq!(payloads[0]["event"], "failed");
+    assert!(payloads[0]["reason"].is_string());
+}
+
+#[test]
+fn failing_webhook_opens_the_circuit() {
+    // ARRANGE
+    let (sender, attempts) = mpsc::channel();
+    let notifier = WebhookNotifier::with_transport(
+        &webhook_config(vec![WebhookEvent::Improvement, WebhookEvent::Finished], 0),
+        "7",
+        RecordingTransport {
+            attempts: sender,
+            failing: true,
+        },
+        DeliveryPolicy {
+            retry_delays: vec![Duration::ZERO],
+            ..without_retries()
+        },
+    );
+    let now = Instant::now();
+
+    // ACT
+    for iteration in 1..=4 {
+        notifier.improvement_at(improvement(iteration), now);
+    }
+    notifier.finished(&serde_json::json!({ "costs": 96 }), &[]);
+    drop(notifier);
+
+    // ASSERT
+    // two notifications with one retry each, then the circuit is open
+    let payloads = received(&attempts);
+    let iterations: Vec<_> = payloads
+        .iter()
+        .map(|payload| payload["iteration"].as_u64().unwrap())
+        .collect();
+    assert_eq!(iterations, vec![1, 1, 2, 2]);
+}
@@ -0,0 +1,272 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::Arc;
+use std::time as stdtime;
+
+use model::json_serialisation::{
+    try_load_rolling_stock_problem_instance_from_json, ValidationError,
+};
+use rapid_time::{DateTime, Duration};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solution::Schedule;
+use solver::objective::json_comparison;
+use tracing::info;
+
+use crate::api::{solve, SolveOptions};
+use crate::delta::apply_delta;
+use crate::jobs::StoredJob;
+use crate::warm_start::WarmStart;
+
+/// Time limit of the local search of a what-if if none is given.
+pub const DEFAULT_TIME_LIMIT: stdtime::Duration = stdtime::Duration::from_secs(10);
+
+/// The local search around a new maintenance slot only modifies nodes within this margin before
+/// its start and after its end.
+fn focus_margin() -> Duration {
+    Duration::new("6:00:00")
+}
+
+/// A single hypothetical resource that is added to the instance of a finished job.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum ResourceAddition {
+    /// Increments the capacity of an existing depot (and its spawn and despawn capacity if
+    /// given). With a vehicle type, the bounded capacities of this type at the depot are
+    /// incremented as well (the type is allowed with the increment as capacity if it was not
+    /// allowed before).
+    #[serde(rename_all = "camelCase")]
+    DepotCapacity {
+        depot: String,
+        increment: u64,
+        vehicle_type: Option<String>,
+    },
+    /// A new maintenance slot in the format of the input.
+    MaintenanceSlot(Value),
+}
+
+/// Re-optimizes the instance of a finished job with a single resource addition for a fast
+/// turnaround: the result of the job is the warm start, the local search is limited by the time
+/// limit and, for a maintenance slot, only modifies nodes around the new slot. (Depots are
+/// reassigned by the depot improvement of every solve, so the local search is not restricted
+/// for a depot capacity.)
+/// Returns a report (objective value of the job compared with the new one and the vehicles using
+/// the new resource) and the job of the changed instance, which can be the base of further
+/// differential solves.
+pub fn evaluate_what_if(
+    base_job: &StoredJob,
+    addition: Value,
+    time_limit: stdtime::Duration,
+) -> Result<(Value, StoredJob), Vec<ValidationError>> {
+    let addition: ResourceAddition = serde_json::from_value(addition).map_err(|e| {
+        vec![invalid_resource_addition(format!(
+            "Invalid resource addition: {}.",
+            e
+        ))]
+    })?;
+    let delta = resource_addition_to_delta(&base_job.instance, &addition)?;
+    let instance = apply_delta(&base_job.instance, &delta)?;
+    let network = try_load_rolling_stock_problem_instance_from_json(instance.clone())?;
+
+    let focus_window = focus_window_of(&addition);
+    if let Some((start, end)) = focus_window {
+        info!(
+            "Local search restricted to {} - {}",
+            start.as_iso(),
+            end.as_iso()
+        );
+    }
+    let result = solve(
+        network,
+        SolveOptions {
+            time_limit: Some(time_limit),
+            warm_start: Some(base_job.result.clone()),
+            focus_window,
+            ..SolveOptions::default()
+        },
+    );
+
+    let objective_value = result
+        .objective()
+        .objective_value_to_json(result.solution().objective_value());
+    let report = json!({
+        "objectiveValue": json_comparison(base_job.result.objective_value(), &objective_value),
+        "resourceUsage": resource_usage_to_json(result.schedule(), &addition, &base_job.instance),
+        "runningTime": format!("{:0.2}sec", result.runtime_duration().as_secs_f32()),
+    });
+    let job = StoredJob::new(
+        Arc::new(instance),
+        Arc::new(WarmStart::from_schedule(result.schedule()).with_objective_value(objective_value)),
+    );
+    Ok((report, job))
+}
+
+/// The resource addition as delta to the instance (see apply_delta).
+pub fn resource_addition_to_delta(
+    base_instance: &Value,
+    addition: &ResourceAddition,
+) -> Result<Value, Vec<ValidationError>> {
+    match addition {
+        ResourceAddition::DepotCapacity {
+            depot,
+            increment,
+            vehicle_type,
+        } => {
+            let existing = depot_of(base_instance, depot).ok_or_else(|| {
+                vec![invalid_resource_addition(format!(
+                    "Unknown depot {}.",
+                    depot
+                ))]
+            })?;
+            let mut patch = json!({ "id": depot });
+            increment_capacities(existing, &mut patch, *increment);
+            if let Some(vehicle_type) = vehicle_type {
+                let mut allowed_types = existing["allowedTypes"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                match allowed_types
+                    .iter_mut()
+                    .find(|allowed_type| allowed_type["vehicleType"] == vehicle_type.as_str())
+                {
+                    Some(allowed_type) => {
+                        let type_capacities = allowed_type.clone();
+                        increment_capacities(&type_capacities, allowed_type, *increment)
+                    }
+                    None => allowed_types.push(json!({
+                        "vehicleType": vehicle_type,
+                        "capacity": increment,
+                    })),
+                }
+                patch["allowedTypes"] = Value::Array(allowed_types);
+            }
+            Ok(json!({ "depots": { "modified": [patch] } }))
+        }
+        ResourceAddition::MaintenanceSlot(slot) => {
+            Ok(json!({ "maintenanceSlots": { "added": [slot] } }))
+        }
+    }
+}
+
+/// Sets the given capacities (capacity, spawnCapacity, despawnCapacity) of existing that are
+/// bounded to their incremented value in target.
+fn increment_capacities(existing: &Value, target: &mut Value, increment: u64) {
+    for field in ["capacity", "spawnCapacity", "despawnCapacity"] {
+        if let Some(capacity) = existing[field].as_u64() {
+            target[field] = json!(capacity + increment);
+        }
+    }
+}
+
+fn depot_of<'a>(instance: &'a Value, depot: &str) -> Option<&'a Value> {
+    instance["depots"]
+        .as_array()?
+        .iter()
+        .find(|existing| existing["id"] == depot)
+}
+
+/// For a maintenance slot: the slot extended by the focus margin (None for recurring or
+/// open-ended slots, the search is then not restricted).
+fn focus_window_of(addition: &ResourceAddition) -> Option<(DateTime, DateTime)> {
+    match addition {
+        ResourceAddition::DepotCapacity { .. } => None,
+        ResourceAddition::MaintenanceSlot(slot) => {
+            if !slot["recurrence"].is_null() {
+                return None;
+            }
+            let start = DateTime::new(slot["start"].as_str()?);
+            let end = DateTime::new(slot["end"].as_str()?);
+            Some((start - focus_margin(), end + focus_margin()))
+        }
+    }
+}
+
+/// The vehicles of the schedule using the new resource: for a depot the vehicles starting and
+/// ending there, for a maintenance slot the vehicles visiting it.
+fn resource_usage_to_json(
+    schedule: &Schedule,
+    addition: &ResourceAddition,
+    base_instance: &Value,
+) -> Value {
+    let network = schedule.get_network();
+    match addition {
+        ResourceAddition::DepotCapacity { depot, .. } => {
+            let depot_id_of = |node| network.get_depot(network.get_depot_idx(node)).id();
+            let mut spawned_vehicles = Vec::new();
+            let mut despawned_vehicles = Vec::new();
+            for vehicle in schedule.vehicles_iter_all() {
+                let tour = schedule.tour_of(vehicle).unwrap();
+                if depot_id_of(tour.first_node()) == depot.as_str() {
+                    spawned_vehicles.push(vehicle.to_string());
+                }
+                if depot_id_of(tour.last_node()) == depot.as_str() {
+                    despawned_vehicles.push(vehicle.to_string());
+                }
+            }
+            json!({
+                "depot": depot,
+                "previousCapacity": depot_of(base_instance, depot)
+                    .map(|existing| existing["capacity"].clone()),
+                "spawnedVehicles": spawned_vehicles,
+                "despawnedVehicles": despawned_vehicles,
+            })
+        }
+        ResourceAddition::MaintenanceSlot(slot) => {
+            let slot_id = slot["id"].as_str().unwrap_or_default();
+            let vehicles: Vec<String> = schedule
+                .vehicles_iter_all()
+                .filter(|&vehicle| {
+                    schedule
+                        .tour_of(vehicle)
+                        .unwrap()
+                        .all_non_depot_nodes_iter()
+                        .any(|node| {
+                            network.node(node).is_maintenance()
+                                && is_node_of_slot(network.node(node).id(), slot_id)
+                        })
+                })
+                .map(|vehicle| vehicle.to_string())
+                .collect();
+            json!({
+                "maintenanceSlot": slot_id,
+                "vehicles": vehicles,
+            })
+        }
+    }
+}
+
+/// The nodes of a maintenance slot carry the id of the slot, possibly followed by the occurrence
+/// of its recurrence (_k) and the sub-slot of its service duration (#k).
+fn is_node_of_slot(node_id: &str, slot_id: &str) -> bool {
+    let Some(suffix) = node_id.strip_prefix(slot_id) else {
+        return false;
+    };
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let (occurrence, sub_slot) = match suffix.split_once('#') {
+        Some((occurrence, sub_slot)) => (occurrence, Some(sub_slot)),
+        None => (suffix, None),
+    };
+    (occurrence.is_empty() || occurrence.strip_prefix('_').is_some_and(is_number))
+        && sub_slot.is_none_or(is_number)
+}
+
+fn invalid_resource_addition(message: String) -> ValidationError {
+    ValidationError {
+        code: "invalidResourceAddition",
+        message,
+    }
+}
@@ -0,0 +1,140 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fs::File, io::Read};
+
+use crate::jobs::StoredJob;
+use crate::try_solve_instance_with_warm_start_to_writer;
+
+use super::{evaluate_what_if, is_node_of_slot, resource_addition_to_delta, ResourceAddition};
+
+fn small_test_input() -> serde_json::Value {
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    serde_json::from_str(&input_data).unwrap()
+}
+
+#[test]
+fn depot_capacity_as_delta() {
+    // ARRANGE
+    let instance = small_test_input();
+    // depot_BN has a capacity of 500 and only allows IC
+    let addition = ResourceAddition::DepotCapacity {
+        depot: "depot_BN".to_string(),
+        increment: 2,
+        vehicle_type: Some("IR".to_string()),
+    };
+
+    // ACT
+    let delta = resource_addition_to_delta(&instance, &addition).unwrap();
+
+    // ASSERT
+    assert_eq!(
+        delta,
+        serde_json::json!({
+            "depots": {
+                "modified": [{
+                    "id": "depot_BN",
+                    "capacity": 502,
+                    "allowedTypes": [
+                        {"vehicleType": "IC"},
+                        {"vehicleType": "IR", "capacity": 2}
+                    ]
+                }]
+            }
+        })
+    );
+}
+
+#[test]
+fn unknown_depot_is_rejected() {
+    // ARRANGE
+    let instance = small_test_input();
+    let addition = ResourceAddition::DepotCapacity {
+        depot: "depot_XY".to_string(),
+        increment: 1,
+        vehicle_type: None,
+    };
+
+    // ACT
+    let errors = resource_addition_to_delta(&instance, &addition).unwrap_err();
+
+    // ASSERT
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, "invalidResourceAddition");
+    assert!(errors[0].message.contains("depot_XY"));
+}
+
+#[test]
+fn nodes_of_slot() {
+    assert!(is_node_of_slot("night_ZH", "night_ZH"));
+    assert!(is_node_of_slot("night_ZH_3", "night_ZH"));
+    assert!(is_node_of_slot("night_ZH#1", "night_ZH"));
+    assert!(is_node_of_slot("night_ZH_3#12", "night_ZH"));
+    assert!(!is_node_of_slot("night_ZH_old", "night_ZH"));
+    assert!(!is_node_of_slot("night_ZH#", "night_ZH"));
+    assert!(!is_node_of_slot("night", "night_ZH"));
+}
+
+#[test]
+fn what_if_with_maintenance_slot() {
+    // ARRANGE
+    let instance = small_test_input();
+    let mut body: Vec<u8> = Vec::new();
+    let result =
+        try_solve_instance_with_warm_start_to_writer(instance.clone(), None, &mut body).unwrap();
+    let base_job = StoredJob::new(Arc::new(instance), Arc::new(result));
+    let addition = serde_json::json!({
+        "maintenanceSlot": {
+            "id": "night_ZH",
+            "location": "ZH",
+            "start": "2023-07-24T20:00:00",
+            "end": "2023-07-25T04:00:00",
+            "trackCount": 1
+        }
+    });
+
+    // ACT
+    let (report, job) = evaluate_what_if(&base_job, addition, Duration::from_secs(1)).unwrap();
+
+    // ASSERT
+    assert_eq!(report["resourceUsage"]["maintenanceSlot"], "night_ZH");
+    assert!(
+        report["resourceUsage"]["vehicles"]
+            .as_array()
+            .unwrap()
+            .len()
+            <= 1
+    );
+    assert!(report["objectiveValue"].is_object());
+    assert!(job.result.objective_value().is_object());
+    assert_eq!(
+        job.instance["maintenanceSlots"]
+            .as_array()
+            .unwrap()
+            .last()
+            .unwrap()["id"],
+        "night_ZH"
+    );
+    assert_eq!(
+        base_job.instance["maintenanceSlots"]
+            .as_array()
+            .unwrap()
+            .len()
+            + 1,
+        job.instance["maintenanceSlots"].as_array().unwrap().len()
+    );
+}
@@ -15,6 +15,7 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use model::base_types::VehicleIdx;
 use solution::{
     path::Path,
+    segment::Segment,
     test_utilities::{default_schedule, init_test_data},
 };
 
@@ -52,6 +53,26 @@ pub fn schedule_modification_benchmarks(c: &mut Criterion) {
         })
     });
 
+    // trip14 moves from veh1 (its only vehicle) to the tail of the empty formation
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    c.bench_function("fit_reassign", |b| {
+        b.iter(|| {
+            schedule
+                .fit_reassign(Segment::new(d.trip14, d.trip14), veh1, veh2)
+                .unwrap();
+        })
+    });
+
+    // the conflicting trip34 and trip45 of veh0 are moved to a dummy
+    c.bench_function("override_reassign", |b| {
+        b.iter(|| {
+            schedule
+                .override_reassign(Segment::new(d.trip31, d.trip14), veh1, veh0)
+                .unwrap();
+        })
+    });
+
     // TODO implement benchmarks for all modifications, using a small instance and a large instance
 }
 
@@ -0,0 +1,152 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use model::{
+    base_types::{NodeIdx, VehicleIdx},
+    json_serialisation::load_rolling_stock_problem_instance_from_json,
+    network::Network,
+};
+use solution::{path::Path, segment::Segment, Schedule};
+
+/// Compares the latency of applying moves to a tour of a short-haul vehicle with the one of a
+/// vehicle serving a full week (e.g., 500 nodes).
+pub fn tour_modification_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tour_modification");
+    for tour_length in [50, 500] {
+        let network = synthetic_shuttle_network(tour_length - 2);
+        let vehicle_type = network.vehicle_types().iter().next().unwrap();
+        let service_trips: Vec<NodeIdx> = network.all_service_nodes().collect();
+        let (schedule, vehicle) = Schedule::empty(network.clone())
+            .spawn_vehicle_for_path(vehicle_type, service_trips.clone())
+            .unwrap();
+        assert_eq!(schedule.tour_of(vehicle).unwrap().length(), tour_length);
+
+        // two trips in the middle of the tour (the vehicle returns to the same location)
+        let middle = service_trips.len() / 2;
+        let segment = Segment::new(service_trips[middle], service_trips[middle + 1]);
+        let schedule_with_gap = schedule.remove_segment(segment, vehicle).unwrap();
+        let dummy: VehicleIdx = schedule_with_gap.dummy_iter().next().unwrap();
+
+        group.bench_with_input(
+            BenchmarkId::new("remove_segment", tour_length),
+            &schedule,
+            |b, schedule| b.iter(|| schedule.remove_segment(segment, vehicle).unwrap()),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("override_reassign", tour_length),
+            &schedule_with_gap,
+            |b, schedule| b.iter(|| schedule.override_reassign(segment, dummy, vehicle).unwrap()),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("add_path_to_vehicle_tour", tour_length),
+            &schedule_with_gap,
+            |b, schedule| {
+                b.iter(|| {
+                    schedule
+                        .add_path_to_vehicle_tour(
+                            vehicle,
+                            Path::new(
+                                vec![service_trips[middle], service_trips[middle + 1]],
+                                network.clone(),
+                            )
+                            .unwrap()
+                            .unwrap(),
+                        )
+                        .unwrap();
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("sub_path", tour_length),
+            &schedule,
+            |b, schedule| {
+                b.iter(|| {
+                    schedule
+                        .tour_of(vehicle)
+                        .unwrap()
+                        .sub_path(segment)
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// A shuttle between two locations: one departure every 20 minutes, alternating in direction.
+fn synthetic_shuttle_network(number_of_trips: usize) -> Arc<Network> {
+    let repetitions = number_of_trips.div_ceil(2);
+    let departure = |id: &str, route: &str, departure: &str| {
+        serde_json::json!({
+            "id": id,
+            "route": route,
+            "segments": [{
+                "id": format!("{}_seg", id),
+                "routeSegment": format!("{}_seg", route),
+                "departure": departure,
+                "passengers": 50,
+                "seated": 30,
+            }],
+            "headwayRepetitions": {"count": repetitions, "interval": 2400},
+        })
+    };
+    let route = |id: &str, origin: &str, destination: &str| {
+        serde_json::json!({
+            "id": id,
+            "vehicleType": "vt",
+            "segments": [{
+                "id": format!("{}_seg", id),
+                "order": 0,
+                "origin": origin,
+                "destination": destination,
+                "distance": 10000,
+                "duration": 600,
+            }],
+        })
+    };
+    let mut departures = vec![departure("shuttle_ab", "route_ab", "2020-01-06T00:00:00")];
+    if number_of_trips > 1 {
+        departures.push(departure("shuttle_ba", "route_ba", "2020-01-06T00:20:00"));
+    }
+    load_rolling_stock_problem_instance_from_json(serde_json::json!({
+        "vehicleTypes": [{"id": "vt", "capacity": 100, "seats": 50}],
+        "locations": [{"id": "a"}, {"id": "b"}],
+        "depots": [{
+            "id": "depot_a",
+            "location": "a",
+            "capacity": 10,
+            "allowedTypes": [{"vehicleType": "vt"}],
+        }],
+        "routes": [route("route_ab", "a", "b"), route("route_ba", "b", "a")],
+        "departures": departures,
+        "maintenanceSlots": [],
+        "deadHeadTrips": {
+            "indices": ["a", "b"],
+            "durations": [[0, 1200], [1200, 0]],
+            "distances": [[0, 10000], [10000, 0]],
+        },
+        "parameters": {
+            "shunting": {"minimalDuration": 120, "deadHeadTripDuration": 300},
+            "costs": {"staff": 100, "serviceTrip": 50, "deadHeadTrip": 500, "idle": 20},
+        },
+    }))
+}
+
+criterion_group!(benches, tour_modification_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use model::base_types::VehicleIdx;
+use solution::{
+    segment::Segment,
+    test_utilities::{default_schedule, init_test_data},
+    transition::Transition,
+};
+
+pub fn transition_benchmarks(c: &mut Criterion) {
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let tours = schedule.get_tours().clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let transition =
+        Transition::from_cycles(vec![vec![veh0, veh1], vec![veh2]], &tours, &d.network);
+
+    // trip14 moves from veh1 to veh2, both tours change
+    let new_tours = schedule
+        .fit_reassign(Segment::new(d.trip14, d.trip14), veh1, veh2)
+        .unwrap()
+        .get_tours()
+        .clone();
+    c.bench_function("transition_update_vehicle", |b| {
+        b.iter(|| {
+            transition.update_vehicle(
+                veh1,
+                tours.get(&veh1).unwrap(),
+                new_tours.get(&veh1).unwrap(),
+                |v| tours.get(&v).unwrap(),
+                &d.network,
+            )
+        })
+    });
+
+    c.bench_function("transition_update_vehicle_unchanged", |b| {
+        b.iter(|| {
+            transition.update_vehicle(
+                veh0,
+                tours.get(&veh0).unwrap(),
+                tours.get(&veh0).unwrap(),
+                |v| tours.get(&v).unwrap(),
+                &d.network,
+            )
+        })
+    });
+
+    c.bench_function("transition_remove_and_add_vehicle", |b| {
+        b.iter(|| {
+            transition
+                .remove_vehicle(
+                    veh1,
+                    tours.get(&veh1).unwrap(),
+                    |v| tours.get(&v).unwrap(),
+                    &d.network,
+                )
+                .add_vehicle_at_the_end(veh1, 1, |v| tours.get(&v).unwrap(), &d.network)
+        })
+    });
+
+    c.bench_function("transition_add_vehicle_to_own_cycle", |b| {
+        let transition = Transition::from_cycles(vec![vec![veh0, veh1]], &tours, &d.network);
+        b.iter(|| transition.add_vehicle_to_own_cycle(veh2, tours.get(&veh2).unwrap(), &d.network))
+    });
+
+    // full recomputation for comparison
+    c.bench_function("transition_from_cycles", |b| {
+        b.iter(|| {
+            Transition::from_cycles(vec![vec![veh0, veh1], vec![veh2]], &new_tours, &d.network)
+        })
+    });
+}
+
+criterion_group!(benches, transition_benchmarks);
+criterion_main!(benches);
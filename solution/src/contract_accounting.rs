@@ -0,0 +1,105 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, HashSet};
+
+use model::base_types::{Distance, Meter, NodeIdx, VehicleCount, VehicleIdx};
+use model::config::ContractDeadHeadAllocation;
+
+use crate::Schedule;
+
+/// Vehicle-km and vehicles of one transport contract (None for routes without a contract).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractKilometers {
+    pub contract: Option<String>,
+    pub service_distance: Distance,
+    pub dead_head_distance: Distance,
+    pub vehicle_count: VehicleCount, // vehicles serving at least one trip of the contract
+}
+
+#[derive(Default)]
+struct ContractTotals {
+    service_meters: Meter,
+    dead_head_meters: Meter,
+    vehicles: HashSet<VehicleIdx>,
+}
+
+/// Aggregates the vehicle-km of the schedule per contract of the routes (sorted by contract).
+/// Each vehicle on a service trip counts the distance of the trip. Dead-head trips are allocated
+/// to the contracts of the service trips around them in the tour of the vehicle according to
+/// config.contract_dead_head_allocation (if there is only one of them, it gets everything).
+/// Synthetic legs from and to the overflow depot are ignored, dead-head trips of tours without
+/// service trips count for no contract.
+pub fn vehicle_kilometers_per_contract(schedule: &Schedule) -> Vec<ContractKilometers> {
+    let network = schedule.get_network();
+    let allocation = network.config().contract_dead_head_allocation;
+    let contract_of = |node: NodeIdx| network.node(node).as_service_trip().contract().cloned();
+
+    let mut totals: BTreeMap<Option<String>, ContractTotals> = BTreeMap::new();
+    for vehicle in schedule.vehicles_iter_all() {
+        let nodes: Vec<NodeIdx> = schedule
+            .tour_of(vehicle)
+            .unwrap()
+            .all_nodes_iter()
+            .collect();
+        let is_service = |&node: &NodeIdx| network.node(node).is_service();
+
+        for &node in nodes.iter().filter(|node| is_service(node)) {
+            let contract_totals = totals.entry(contract_of(node)).or_default();
+            contract_totals.service_meters +=
+                network.node(node).travel_distance().in_meter().unwrap();
+            contract_totals.vehicles.insert(vehicle);
+        }
+
+        for (position, (&node1, &node2)) in nodes.iter().zip(nodes.iter().skip(1)).enumerate() {
+            if network.is_overflow_depot_node(node1) || network.is_overflow_depot_node(node2) {
+                continue;
+            }
+            let meters = match network.dead_head_distance_between(node1, node2).in_meter() {
+                Ok(0) | Err(_) => continue,
+                Ok(meters) => meters,
+            };
+            let preceding = nodes[..=position]
+                .iter()
+                .rev()
+                .find(|node| is_service(node));
+            let following = nodes[position + 1..].iter().find(|node| is_service(node));
+            let shares: Vec<(Option<NodeIdx>, Meter)> = match (allocation, preceding, following) {
+                (ContractDeadHeadAllocation::Split, Some(&preceding), Some(&following)) => vec![
+                    (Some(preceding), meters / 2),
+                    (Some(following), meters - meters / 2),
+                ],
+                (_, _, Some(&following)) => vec![(Some(following), meters)],
+                (_, preceding, None) => vec![(preceding.copied(), meters)],
+            };
+            for (service_trip, meters) in shares {
+                totals
+                    .entry(service_trip.and_then(contract_of))
+                    .or_default()
+                    .dead_head_meters += meters;
+            }
+        }
+    }
+
+    totals
+        .into_iter()
+        .map(|(contract, contract_totals)| ContractKilometers {
+            contract,
+            service_distance: Distance::from_meter(contract_totals.service_meters),
+            dead_head_distance: Distance::from_meter(contract_totals.dead_head_meters),
+            vehicle_count: contract_totals.vehicles.len() as VehicleCount,
+        })
+        .collect()
+}
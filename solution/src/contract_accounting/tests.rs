@@ -0,0 +1,128 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use model::base_types::Distance;
+
+use crate::test_utilities::{init_test_data_with, TestData};
+use crate::Schedule;
+
+use super::{vehicle_kilometers_per_contract, ContractKilometers};
+
+/// Routes 1-2, 2-3 and 3-4 belong to contract A, routes 5-1 and 3-1 to contract B.
+fn two_contract_test_data(dead_head_allocation: Option<&str>) -> TestData {
+    init_test_data_with(|instance| {
+        for route in instance["routes"].as_array_mut().unwrap() {
+            let contract = match route["id"].as_str().unwrap() {
+                "route_1-2" | "route_2-3" | "route_3-4" => "A",
+                "route_51" | "route_3-1" => "B",
+                _ => continue,
+            };
+            route["contract"] = contract.into();
+        }
+        if let Some(dead_head_allocation) = dead_head_allocation {
+            instance["parameters"]["output"] =
+                serde_json::json!({"contractDeadHeadAllocation": dead_head_allocation});
+        }
+    })
+}
+
+/// veh0: depot1, trip12 (A), trip23 (A), trip31 (B), depot1 (no dead-head trips)
+/// veh1: depot2, 23km, trip34 (A), 45km, trip51 (B), 12km, depot2
+fn two_contract_schedule(d: &TestData) -> Schedule {
+    let (schedule, _) = Schedule::empty(d.network.clone())
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.start_depot1, d.trip12, d.trip23, d.trip31, d.end_depot1],
+        )
+        .unwrap();
+    let (schedule, _) = schedule
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.start_depot2, d.trip34, d.trip51, d.end_depot2],
+        )
+        .unwrap();
+    schedule
+}
+
+fn contract_kilometers(
+    contract: &str,
+    service_meters: u64,
+    dead_head_meters: u64,
+) -> ContractKilometers {
+    ContractKilometers {
+        contract: Some(contract.to_string()),
+        service_distance: Distance::from_meter(service_meters),
+        dead_head_distance: Distance::from_meter(dead_head_meters),
+        vehicle_count: 2,
+    }
+}
+
+#[test]
+fn dead_head_trips_are_allocated_to_following_trip_test() {
+    // ARRANGE
+    let d = two_contract_test_data(None);
+    let schedule = two_contract_schedule(&d);
+
+    // ACT
+    let kilometers = vehicle_kilometers_per_contract(&schedule);
+
+    // ASSERT
+    // the last dead-head trip of veh1 has no following trip and goes to trip51 (B)
+    assert_eq!(
+        kilometers,
+        vec![
+            contract_kilometers("A", 1000 + 2000 + 3000, 23000),
+            contract_kilometers("B", 6000 + 5000, 45000 + 12000),
+        ]
+    );
+}
+
+#[test]
+fn dead_head_trips_are_split_between_trips_test() {
+    // ARRANGE
+    let d = two_contract_test_data(Some("split"));
+    let schedule = two_contract_schedule(&d);
+
+    // ACT
+    let kilometers = vehicle_kilometers_per_contract(&schedule);
+
+    // ASSERT
+    // only the dead-head trip between trip34 (A) and trip51 (B) lies between two service trips
+    assert_eq!(
+        kilometers,
+        vec![
+            contract_kilometers("A", 1000 + 2000 + 3000, 23000 + 22500),
+            contract_kilometers("B", 6000 + 5000, 22500 + 12000),
+        ]
+    );
+}
+
+#[test]
+fn routes_without_contract_are_grouped_test() {
+    // ARRANGE
+    let d = init_test_data_with(|_| {});
+    let schedule = two_contract_schedule(&d);
+
+    // ACT
+    let kilometers = vehicle_kilometers_per_contract(&schedule);
+
+    // ASSERT
+    assert_eq!(
+        kilometers,
+        vec![ContractKilometers {
+            contract: None,
+            service_distance: Distance::from_meter(17000),
+            dead_head_distance: Distance::from_meter(80000),
+            vehicle_count: 2,
+        }]
+    );
+}
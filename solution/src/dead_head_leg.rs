@@ -0,0 +1,95 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use model::{
+    base_types::{Location, NodeIdx, VehicleIdx},
+    network::Network,
+};
+use rapid_time::{DateTime, Duration};
+
+/// A dead-head trip of a vehicle between two consecutive nodes of its tour (with different
+/// locations).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeadHeadLeg {
+    vehicle: VehicleIdx,
+    origin: Location,
+    destination: Location,
+    departure: DateTime,
+    arrival: DateTime,
+}
+
+impl DeadHeadLeg {
+    /// Returns None if node1 ends at the start location of node2.
+    /// Dead-head trips from a start depot arrive just in time for node2, all other dead-head trips
+    /// depart right at the end of node1.
+    pub(crate) fn between(
+        vehicle: VehicleIdx,
+        node1_idx: NodeIdx,
+        node2_idx: NodeIdx,
+        network: &Network,
+    ) -> Option<DeadHeadLeg> {
+        let node1 = network.node(node1_idx);
+        let node2 = network.node(node2_idx);
+        if node1.end_location() == node2.start_location() {
+            return None;
+        }
+        let (departure, arrival) = if node1.is_depot() {
+            (
+                node2.start_time() - network.minimal_duration_between_nodes(node1_idx, node2_idx),
+                node2.start_time(),
+            )
+        } else {
+            (
+                node1.end_time(),
+                node1.end_time() + network.minimal_duration_between_nodes(node1_idx, node2_idx),
+            )
+        };
+        Some(DeadHeadLeg {
+            vehicle,
+            origin: node1.end_location(),
+            destination: node2.start_location(),
+            departure,
+            arrival,
+        })
+    }
+
+    pub fn vehicle(&self) -> VehicleIdx {
+        self.vehicle
+    }
+
+    pub fn origin(&self) -> Location {
+        self.origin
+    }
+
+    pub fn destination(&self) -> Location {
+        self.destination
+    }
+
+    pub fn departure(&self) -> DateTime {
+        self.departure
+    }
+
+    pub fn arrival(&self) -> DateTime {
+        self.arrival
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.arrival - self.departure
+    }
+
+    /// true iff the dead-head trip starts or ends at the overflow depot (which is located Nowhere).
+    /// Its distance and duration are infinite.
+    pub fn is_synthetic(&self) -> bool {
+        self.origin == Location::Nowhere || self.destination == Location::Nowhere
+    }
+}
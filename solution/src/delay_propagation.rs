@@ -0,0 +1,140 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap as StdHashMap;
+
+use model::base_types::{checked_duration_between, NodeIdx, VehicleIdx};
+use model::network::Network;
+use rapid_time::Duration;
+
+use crate::Schedule;
+
+/// A connection of a vehicle between two consecutive activities where the minimal turnaround
+/// (shunting and dead-head trip) cannot be kept with the delayed arrival. The arrival delay minus
+/// the buffer is passed on to the next activity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenConnection {
+    pub vehicle: VehicleIdx,
+    pub from: NodeIdx,
+    pub to: NodeIdx,
+    pub arrival_delay: Duration,
+    pub buffer: Duration, // scheduled time between the activities beyond the minimal turnaround
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelayPropagation {
+    /// expected delay of each delayed activity (service trip or maintenance slot), ordered by the
+    /// scheduled start
+    pub delays: Vec<(NodeIdx, Duration)>,
+    /// ordered by the scheduled start of the activity the delay is passed on to
+    pub broken_connections: Vec<BrokenConnection>,
+}
+
+/// Propagates primary delays (an activity starts and ends later by the given duration) along the
+/// tours of the real vehicles. An activity is delayed by the largest of its primary delay and the
+/// delays passed on by the connections of all vehicles of its formation. Hence, the delay of a
+/// vehicle also delays its formation partners. A delay dies out as soon as the buffers absorb
+/// it. The schedule is not modified.
+pub fn propagate_delays(
+    schedule: &Schedule,
+    primary_delays: &[(NodeIdx, Duration)],
+) -> DelayPropagation {
+    let network = schedule.get_network();
+
+    // predecessors of each activity in the tours of the vehicles serving it
+    let mut predecessors: StdHashMap<NodeIdx, Vec<(VehicleIdx, NodeIdx)>> = StdHashMap::new();
+    let mut nodes: Vec<NodeIdx> = Vec::new();
+    for vehicle in schedule.vehicles_iter_all() {
+        let mut previous: Option<NodeIdx> = None;
+        for node in schedule
+            .tour_of(vehicle)
+            .unwrap()
+            .all_non_depot_nodes_iter()
+        {
+            if let Some(previous) = previous {
+                predecessors
+                    .entry(node)
+                    .or_default()
+                    .push((vehicle, previous));
+            }
+            nodes.push(node);
+            previous = Some(node);
+        }
+    }
+    // predecessors end before their successors start, so they are handled first
+    nodes.sort_by_key(|&node| {
+        (
+            network.node(node).start_time(),
+            network.node(node).end_time(),
+            node,
+        )
+    });
+    nodes.dedup();
+
+    let mut primary: StdHashMap<NodeIdx, Duration> = StdHashMap::new();
+    for &(node, delay) in primary_delays {
+        let entry = primary.entry(node).or_insert(Duration::ZERO);
+        *entry = (*entry).max(delay);
+    }
+
+    let mut delays: StdHashMap<NodeIdx, Duration> = StdHashMap::new();
+    let mut ordered_delays = Vec::new();
+    let mut broken_connections = Vec::new();
+    for node in nodes {
+        let mut delay = primary.get(&node).copied().unwrap_or(Duration::ZERO);
+        for &(vehicle, previous) in predecessors.get(&node).into_iter().flatten() {
+            let Some(&arrival_delay) = delays.get(&previous) else {
+                continue;
+            };
+            let buffer = buffer_between(&network, previous, node);
+            if arrival_delay > buffer {
+                delay = delay.max(arrival_delay - buffer);
+                broken_connections.push(BrokenConnection {
+                    vehicle,
+                    from: previous,
+                    to: node,
+                    arrival_delay,
+                    buffer,
+                });
+            }
+        }
+        if delay > Duration::ZERO {
+            delays.insert(node, delay);
+            ordered_delays.push((node, delay));
+        }
+    }
+
+    DelayPropagation {
+        delays: ordered_delays,
+        broken_connections,
+    }
+}
+
+/// The scheduled time between the end of node1 and the start of node2 minus the minimal duration
+/// between them (shunting and dead-head trip).
+fn buffer_between(network: &Network, node1: NodeIdx, node2: NodeIdx) -> Duration {
+    let gap = checked_duration_between(
+        network.node(node1).end_time(),
+        network.node(node2).start_time(),
+    )
+    .unwrap_or(Duration::ZERO);
+    let minimal_duration = network.minimal_duration_between_nodes(node1, node2);
+    if gap > minimal_duration {
+        gap - minimal_duration
+    } else {
+        Duration::ZERO
+    }
+}
@@ -0,0 +1,110 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use model::base_types::VehicleIdx;
+use rapid_time::Duration;
+
+use crate::test_utilities::{default_schedule, init_test_data};
+
+use super::{propagate_delays, BrokenConnection};
+
+#[test]
+fn delay_absorbed_by_buffer_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+
+    // ACT
+    // trip12 arrives at 6:50 instead of 6:30, trip23 departs at 7:00 (shunting takes 2 minutes)
+    let propagation = propagate_delays(&schedule, &[(d.trip12, Duration::new("0:20"))]);
+
+    // ASSERT
+    assert_eq!(propagation.delays, vec![(d.trip12, Duration::new("0:20"))]);
+    assert!(propagation.broken_connections.is_empty());
+}
+
+#[test]
+fn delay_propagated_to_formation_partners_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh2 = VehicleIdx::vehicle_from(2);
+
+    // ACT
+    // trip12 (veh0 and veh2) arrives at 7:10 instead of 6:30, trip23 can only depart at 7:12
+    let propagation = propagate_delays(&schedule, &[(d.trip12, Duration::new("0:40"))]);
+
+    // ASSERT
+    // the remaining 12 minutes are absorbed by the 28 minutes buffer before trip34 (veh0) and
+    // trip31 (veh2)
+    assert_eq!(
+        propagation.delays,
+        vec![
+            (d.trip12, Duration::new("0:40")),
+            (d.trip23, Duration::new("0:12"))
+        ]
+    );
+    assert_eq!(
+        propagation.broken_connections,
+        vec![
+            BrokenConnection {
+                vehicle: veh0,
+                from: d.trip12,
+                to: d.trip23,
+                arrival_delay: Duration::new("0:40"),
+                buffer: Duration::new("0:28"),
+            },
+            BrokenConnection {
+                vehicle: veh2,
+                from: d.trip12,
+                to: d.trip23,
+                arrival_delay: Duration::new("0:40"),
+                buffer: Duration::new("0:28"),
+            }
+        ]
+    );
+}
+
+#[test]
+fn primary_delay_of_later_trip_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+
+    // ACT
+    // trip31 (veh1 and veh2) arrives at loc1 at 9:30 instead of 8:30, trip14 (veh1) departs at
+    // 9:00 and the propagated delay of trip23 is smaller than its primary delay
+    let propagation = propagate_delays(
+        &schedule,
+        &[
+            (d.trip12, Duration::new("0:40")),
+            (d.trip23, Duration::new("0:15")),
+            (d.trip31, Duration::new("1:00")),
+        ],
+    );
+
+    // ASSERT
+    assert_eq!(
+        propagation.delays,
+        vec![
+            (d.trip12, Duration::new("0:40")),
+            (d.trip23, Duration::new("0:15")),
+            (d.trip31, Duration::new("1:00")),
+            (d.trip14, Duration::new("0:32"))
+        ]
+    );
+    assert_eq!(propagation.broken_connections.len(), 3);
+    assert_eq!(propagation.broken_connections[2].from, d.trip31);
+    assert_eq!(propagation.broken_connections[2].to, d.trip14);
+}
@@ -11,14 +11,28 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+#[cfg(test)]
+mod tests;
+
 use itertools::Itertools;
 use model::{
-    base_types::{DepotIdx, NodeIdx, VehicleIdx, VehicleTypeIdx},
-    network::{nodes::Node, Network},
+    base_types::{
+        Cost, DepotIdx, Idx, Meter, NodeIdx, PassengerCount, VehicleCount, VehicleIdx,
+        VehicleTypeIdx,
+    },
+    network::{nodes::Node, Network, PlatformIdle},
 };
-use rapid_time::DateTime;
-use serde::{Deserialize, Serialize};
+use rapid_time::Duration;
+use serde::ser::{SerializeSeq, SerializeStruct};
+use serde::{Deserialize, Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
 
+use crate::dead_head_leg::DeadHeadLeg;
+use crate::overflow_analysis::overflow_usage;
+use crate::tour::Tour;
 use crate::Schedule;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -29,12 +43,25 @@ struct ScheduleJson {
     departure_segments: Vec<JsonDepartureSegmentWithFormation>,
     maintenance_slots: Vec<JsonFleetMaintenanceSlotWithFormation>,
     dead_head_trips: Vec<JsonFleetDeadHeadTripWithFormation>,
+    #[serde(default)]
+    projected_maintenance_counters: Vec<JsonProjectedMaintenanceCounter>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trip_families: Vec<JsonTripFamily>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    formation_order_shunts: Option<Vec<JsonFormationOrderShunt>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    platform_idles: Option<Vec<JsonPlatformIdle>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    splits: Option<Vec<JsonSplit>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct DepotLoad {
     depot: String,
+    /// true for the overflow depot
+    #[serde(default)]
+    synthetic: bool,
     load: Vec<Load>,
 }
 
@@ -43,12 +70,31 @@ struct DepotLoad {
 struct Load {
     vehicle_type: String,
     spawn_count: u32,
+    /// only for the overflow depot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    overflow_buckets: Option<Vec<JsonOverflowBucket>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonOverflowBucket {
+    start: String,
+    end: String,
+    spawn_count: u32,
+    despawn_count: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 struct JsonFleet {
     vehicle_type: String,
+    /// true if all vehicles of the type start at the overflow depot
+    #[serde(default)]
+    only_overflow_depot: bool,
+    /// false if the vehicle type is not in parameters.activeVehicleTypes, i.e., its tours are
+    /// the ones of the start schedule
+    #[serde(default = "default_optimized")]
+    optimized: bool,
     vehicles: Vec<JsonVehicle>,
     vehicle_cycles: Vec<Vec<String>>,
 }
@@ -57,11 +103,27 @@ struct JsonFleet {
 #[serde(rename_all = "camelCase")]
 struct JsonVehicle {
     id: String,
+    /// internal index of the vehicle (the number of the id), only used for the import
+    #[serde(default)]
+    index: Option<Idx>,
     start_depot: String,
     end_depot: String,
+    /// true iff the vehicle starts or ends at the overflow depot
+    #[serde(default)]
+    synthetic: bool,
     departure_segments: Vec<JsonFleetDepartureSegment>,
     maintenance_slots: Vec<JsonFleetMaintenanceSlot>,
     dead_head_trips: Vec<JsonFleetDeadHeadTrip>,
+    /// only present if staff time factors are configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    costs: Option<JsonVehicleCosts>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonVehicleCosts {
+    base: Cost,
+    staff_time_surcharge: Cost,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -91,6 +153,9 @@ struct JsonFleetDeadHeadTrip {
     destination: String,
     departure: String,
     arrival: String,
+    /// true iff the dead-head trip starts or ends at the overflow depot
+    #[serde(default)]
+    synthetic: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -102,7 +167,43 @@ struct JsonDepartureSegmentWithFormation {
     departure: String,
     arrival: String,
     vehicle_type: String,
-    formation: Vec<String>,
+    formation: Vec<JsonFormationMember>,
+    capacity: PassengerCount,
+    seats: PassengerCount,
+    passengers: PassengerCount,
+    seated_passengers: PassengerCount,
+    load_factor: Option<f64>,      // None if the formation has no capacity
+    seat_load_factor: Option<f64>, // None if the formation has no seats
+    unserved_passengers: PassengerCount,
+    unserved_seated_passengers: PassengerCount,
+    crowding: JsonCrowding,
+}
+
+/// Overcrowded if some passengers are unserved (see Schedule::unserved_passengers_at),
+/// otherwise given by the larger of the two load factors and the configured thresholds.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+enum JsonCrowding {
+    Low,
+    Moderate,
+    High,
+    Overcrowded,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonFormationMember {
+    vehicle: String,
+    role: JsonFormationRole,
+}
+
+/// Vehicles of a formation that are needed for the passenger demand have the role service.
+/// The remaining vehicles (tail-first) only reposition and are labeled as deadheadOnFormation.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+enum JsonFormationRole {
+    Service,
+    DeadheadOnFormation,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -123,13 +224,76 @@ struct JsonFleetDeadHeadTripWithFormation {
     destination: String,
     departure: String,
     arrival: String,
+    #[serde(default)]
+    synthetic: bool,
     formation: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonTripFamily {
+    family: String,
+    interval: u64,
+    repetitions: Vec<JsonTripFamilyRepetition>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonTripFamilyRepetition {
+    repetition: u32,
+    departure: String,
+    vehicles: Vec<String>,
+}
+
+/// A vehicle that is an inner unit on from_departure_segment and the lead unit on
+/// to_departure_segment (its next service trip).
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonFormationOrderShunt {
+    vehicle: String,
+    from_departure_segment: String,
+    to_departure_segment: String,
+}
+
+/// An idle time exceeding the platform idle limit of the location. The vehicle moves to the siding
+/// and back, None if no siding can be reached and left again in time (a violation).
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonPlatformIdle {
+    vehicle: String,
+    location: String,
+    start: String,
+    end: String,
+    siding: Option<String>,
+}
+
+/// A departure served by different formations before and after an allowed split point.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonSplit {
+    location: String,
+    departure_segment_before: String,
+    departure_segment_after: String,
+    vehicles_before: Vec<String>,
+    vehicles_after: Vec<String>,
+}
+
+/// All distances in meter, None if infinite (i.e., the tour starts or ends at the overflow depot).
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonProjectedMaintenanceCounter {
+    vehicle: String,
+    successor: String,
+    total_distance: Option<Meter>,
+    distance_before_maintenance: Option<Meter>,
+    distance_after_maintenance: Option<Meter>,
+    projected_counter: Option<Meter>,
+}
+
 pub fn schedule_to_json(schedule: &Schedule) -> serde_json::Value {
     let mut dead_head_trips: Vec<JsonFleetDeadHeadTripWithFormation> = vec![];
     let mut fleet = vec![];
-    for vehicle_type in schedule.get_network().vehicle_types().iter() {
+    for vehicle_type in vehicle_types_in_output_order(&schedule.get_network()) {
         fleet.push(fleet_to_json(schedule, vehicle_type, &mut dead_head_trips));
     }
     let schedule_json = ScheduleJson {
@@ -138,10 +302,433 @@ pub fn schedule_to_json(schedule: &Schedule) -> serde_json::Value {
         departure_segments: departure_segments_to_json(schedule),
         maintenance_slots: maintenance_slots_to_json(schedule),
         dead_head_trips,
+        projected_maintenance_counters: projected_maintenance_counters_to_json(schedule),
+        trip_families: trip_families_to_json(schedule),
+        formation_order_shunts: formation_order_shunts_to_json(schedule),
+        platform_idles: platform_idles_to_json(schedule),
+        splits: splits_to_json(schedule),
     };
     serde_json::to_value(schedule_json).unwrap()
 }
 
+/// Writes the same json as schedule_to_json directly into the writer.
+/// The output is produced vehicle by vehicle, so the full json tree is never held in memory.
+pub fn write_schedule_json<W: io::Write>(schedule: &Schedule, writer: W) -> serde_json::Result<()> {
+    serde_json::to_writer(writer, &StreamedScheduleJson::new(schedule))
+}
+
+/// Only the fleet of a schedule json is needed to rebuild the schedule.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ImportedScheduleJson {
+    fleet: Vec<JsonFleet>,
+}
+
+/// Rebuilds a schedule (e.g. the output of schedule_to_json) on the given network.
+/// The tour of each vehicle consists of its start depot, its departure segments and maintenance
+/// slots (sorted by start time) and its end depot. Dead-head trips are recomputed, all other
+/// fields of the json are ignored.
+/// The vehicles are renumbered in the order of their index field (vehicles without index last, in
+/// the order of the json), hence the ids of the json are returned together with the new vehicle
+/// indices (in the order of the renumbering).
+///
+/// # Errors
+/// If the json is malformed or references an unknown vehicle type, depot, departure segment or
+/// maintenance slot, if a departure segment belongs to another vehicle type than its vehicle, or
+/// if a tour is not feasible on the network.
+pub fn schedule_from_json(
+    schedule_json: serde_json::Value,
+    network: Arc<Network>,
+) -> Result<(Schedule, Vec<(String, VehicleIdx)>), String> {
+    let imported: ImportedScheduleJson =
+        serde_json::from_value(schedule_json).map_err(|e| format!("Invalid schedule: {}", e))?;
+
+    let vehicle_types: HashMap<String, VehicleTypeIdx> = network
+        .vehicle_types()
+        .iter()
+        .map(|vt| (vehicle_type_id_of(&network, vt), vt))
+        .collect();
+    let depots: HashMap<String, DepotIdx> = network
+        .depots_iter()
+        .map(|depot| (network.get_depot(depot).id().to_string(), depot))
+        .collect();
+    let coverable_nodes: HashMap<String, NodeIdx> = network
+        .coverable_nodes()
+        .map(|node| (network.node(node).id().to_string(), node))
+        .collect();
+
+    let mut vehicles = Vec::new();
+    for fleet in imported.fleet {
+        let vehicle_type = *vehicle_types
+            .get(&fleet.vehicle_type)
+            .ok_or_else(|| format!("Unknown vehicle type {}.", fleet.vehicle_type))?;
+        vehicles.extend(
+            fleet
+                .vehicles
+                .into_iter()
+                .map(|vehicle| (vehicle_type, vehicle)),
+        );
+    }
+    // spawn in the order of the indices, so that the output of schedule_to_json gets its ids back
+    vehicles.sort_by_key(|(_, vehicle)| (vehicle.index.is_none(), vehicle.index));
+
+    let mut schedule = Schedule::empty(network.clone());
+    let mut vehicle_ids = Vec::new();
+    for (vehicle_type, vehicle) in vehicles {
+        let lookup_depot = |depot_id: &String| {
+            depots
+                .get(depot_id)
+                .copied()
+                .ok_or_else(|| format!("Unknown depot {} of vehicle {}.", depot_id, vehicle.id))
+        };
+        let start_depot = network.get_start_depot_node(lookup_depot(&vehicle.start_depot)?);
+        let end_depot = network.get_end_depot_node(lookup_depot(&vehicle.end_depot)?);
+
+        let mut nodes = vehicle
+            .departure_segments
+            .iter()
+            .map(|segment| &segment.departure_segment)
+            .chain(
+                vehicle
+                    .maintenance_slots
+                    .iter()
+                    .map(|slot| &slot.maintenance_slot),
+            )
+            .map(|id| {
+                let node = coverable_nodes.get(id).copied().ok_or_else(|| {
+                    format!(
+                        "Unknown departure segment or maintenance slot {} of vehicle {}.",
+                        id, vehicle.id
+                    )
+                })?;
+                if !network.compatible_with_vehicle_type(node, vehicle_type) {
+                    return Err(format!(
+                        "Departure segment or maintenance slot {} of vehicle {} cannot be served \
+                         by vehicle type {}.",
+                        id,
+                        vehicle.id,
+                        vehicle_type_id_of(&network, vehicle_type)
+                    ));
+                }
+                Ok(node)
+            })
+            .collect::<Result<Vec<NodeIdx>, String>>()?;
+        nodes.sort_by_key(|&node| (network.node(node).start_time(), node));
+
+        let path = std::iter::once(start_depot)
+            .chain(nodes)
+            .chain(std::iter::once(end_depot))
+            .collect();
+        let (new_schedule, vehicle_idx) = schedule
+            .spawn_vehicle_for_path(vehicle_type, path)
+            .map_err(|e| format!("Cannot rebuild vehicle {}: {}", vehicle.id, e))?;
+        schedule = new_schedule;
+        vehicle_ids.push((vehicle.id, vehicle_idx));
+    }
+    Ok((schedule, vehicle_ids))
+}
+
+/// Serializable view on a schedule that is serialized tour by tour.
+/// Can be embedded into other serializable structs (e.g. the output json of the server).
+pub struct StreamedScheduleJson<'a> {
+    schedule: &'a Schedule,
+}
+
+impl<'a> StreamedScheduleJson<'a> {
+    pub fn new(schedule: &'a Schedule) -> StreamedScheduleJson<'a> {
+        StreamedScheduleJson { schedule }
+    }
+}
+
+impl Serialize for StreamedScheduleJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // the dead head trips are collected while the vehicles are serialized (as in
+        // schedule_to_json), so that each tour is only converted once
+        let dead_head_trips = RefCell::new(vec![]);
+        let trip_families = trip_families_to_json(self.schedule);
+        let formation_order_shunts = formation_order_shunts_to_json(self.schedule);
+        let platform_idles = platform_idles_to_json(self.schedule);
+        let splits = splits_to_json(self.schedule);
+        let mut state = serializer.serialize_struct("ScheduleJson", 10)?;
+        state.serialize_field("depotLoads", &depots_usage_to_json(self.schedule))?;
+        state.serialize_field(
+            "fleet",
+            &StreamedFleets {
+                schedule: self.schedule,
+                dead_head_trips: &dead_head_trips,
+            },
+        )?;
+        state.serialize_field(
+            "departureSegments",
+            &StreamedDepartureSegments {
+                schedule: self.schedule,
+            },
+        )?;
+        state.serialize_field(
+            "maintenanceSlots",
+            &maintenance_slots_to_json(self.schedule),
+        )?;
+        state.serialize_field("deadHeadTrips", &*dead_head_trips.borrow())?;
+        state.serialize_field(
+            "projectedMaintenanceCounters",
+            &projected_maintenance_counters_to_json(self.schedule),
+        )?;
+        if trip_families.is_empty() {
+            state.skip_field("tripFamilies")?;
+        } else {
+            state.serialize_field("tripFamilies", &trip_families)?;
+        }
+        match formation_order_shunts {
+            Some(formation_order_shunts) => {
+                state.serialize_field("formationOrderShunts", &formation_order_shunts)?
+            }
+            None => state.skip_field("formationOrderShunts")?,
+        }
+        match platform_idles {
+            Some(platform_idles) => state.serialize_field("platformIdles", &platform_idles)?,
+            None => state.skip_field("platformIdles")?,
+        }
+        match splits {
+            Some(splits) => state.serialize_field("splits", &splits)?,
+            None => state.skip_field("splits")?,
+        }
+        state.end()
+    }
+}
+
+struct StreamedFleets<'a> {
+    schedule: &'a Schedule,
+    dead_head_trips: &'a RefCell<Vec<JsonFleetDeadHeadTripWithFormation>>,
+}
+
+impl Serialize for StreamedFleets<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let vehicle_types = vehicle_types_in_output_order(&self.schedule.get_network());
+        let mut seq = serializer.serialize_seq(Some(vehicle_types.len()))?;
+        for vehicle_type in vehicle_types {
+            seq.serialize_element(&StreamedFleet {
+                schedule: self.schedule,
+                vehicle_type,
+                dead_head_trips: self.dead_head_trips,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct StreamedFleet<'a> {
+    schedule: &'a Schedule,
+    vehicle_type: VehicleTypeIdx,
+    dead_head_trips: &'a RefCell<Vec<JsonFleetDeadHeadTripWithFormation>>,
+}
+
+impl Serialize for StreamedFleet<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("JsonFleet", 5)?;
+        state.serialize_field(
+            "vehicleType",
+            &vehicle_type_id(self.schedule, self.vehicle_type),
+        )?;
+        state.serialize_field(
+            "onlyOverflowDepot",
+            &self.schedule.uses_only_overflow_depot(self.vehicle_type),
+        )?;
+        state.serialize_field("optimized", &is_optimized(self.schedule, self.vehicle_type))?;
+        state.serialize_field(
+            "vehicles",
+            &StreamedVehicles {
+                schedule: self.schedule,
+                vehicle_type: self.vehicle_type,
+                dead_head_trips: self.dead_head_trips,
+            },
+        )?;
+        state.serialize_field(
+            "vehicleCycles",
+            &vehicle_cycles_to_json(self.schedule, self.vehicle_type),
+        )?;
+        state.end()
+    }
+}
+
+struct StreamedVehicles<'a> {
+    schedule: &'a Schedule,
+    vehicle_type: VehicleTypeIdx,
+    dead_head_trips: &'a RefCell<Vec<JsonFleetDeadHeadTripWithFormation>>,
+}
+
+impl Serialize for StreamedVehicles<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let schedule = self.schedule;
+        let mut seq = serializer.serialize_seq(None)?;
+        for vehicle_idx in vehicles_in_output_order(schedule, self.vehicle_type) {
+            seq.serialize_element(&vehicle_to_json(
+                schedule,
+                vehicle_idx,
+                &mut self.dead_head_trips.borrow_mut(),
+            ))?;
+        }
+        seq.end()
+    }
+}
+
+struct StreamedDepartureSegments<'a> {
+    schedule: &'a Schedule,
+}
+
+impl Serialize for StreamedDepartureSegments<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let network = self.schedule.get_network();
+        let mut seq = serializer.serialize_seq(None)?;
+        for vehicle_type in network.vehicle_types().iter() {
+            for service_trip_node_idx in network.service_nodes(vehicle_type) {
+                seq.serialize_element(&departure_segment_to_json(
+                    self.schedule,
+                    vehicle_type,
+                    service_trip_node_idx,
+                ))?;
+            }
+        }
+        seq.end()
+    }
+}
+
+/// Compact summary of the departures with headwayRepetitions: which vehicles serve which
+/// repetition. Empty if there are no trip families.
+fn trip_families_to_json(schedule: &Schedule) -> Vec<JsonTripFamily> {
+    let network = schedule.get_network();
+    network
+        .trip_families()
+        .map(|(family, trip_family)| JsonTripFamily {
+            family: family.clone(),
+            interval: trip_family.interval().in_sec().unwrap(),
+            repetitions: trip_family
+                .repetitions()
+                .enumerate()
+                .map(|(repetition, service_trips)| JsonTripFamilyRepetition {
+                    repetition: repetition as u32,
+                    departure: network.node(service_trips[0]).start_time().as_iso(),
+                    vehicles: service_trips
+                        .iter()
+                        .flat_map(|&node| schedule.train_formation_of(node).ids())
+                        .unique()
+                        .map(|vehicle| vehicle.to_string())
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn projected_maintenance_counters_to_json(
+    schedule: &Schedule,
+) -> Vec<JsonProjectedMaintenanceCounter> {
+    let position: HashMap<VehicleIdx, usize> =
+        vehicle_types_in_output_order(&schedule.get_network())
+            .into_iter()
+            .flat_map(|vehicle_type| vehicles_in_output_order(schedule, vehicle_type))
+            .enumerate()
+            .map(|(position, vehicle)| (vehicle, position))
+            .collect();
+    schedule
+        .projected_counters()
+        .into_iter()
+        .sorted_by_key(|projected_counter| position[&projected_counter.vehicle()])
+        .map(|projected_counter| {
+            let split = projected_counter.maintenance_split();
+            JsonProjectedMaintenanceCounter {
+                vehicle: projected_counter.vehicle().to_string(),
+                successor: projected_counter.successor().to_string(),
+                total_distance: projected_counter.total_distance().in_meter().ok(),
+                distance_before_maintenance: split.and_then(|(before, _)| before.in_meter().ok()),
+                distance_after_maintenance: split.and_then(|(_, after)| after.in_meter().ok()),
+                projected_counter: projected_counter
+                    .handed_to_successor()
+                    .and_then(|counter| counter.in_meter().ok()),
+            }
+        })
+        .collect()
+}
+
+/// Only reported if formation order continuity is enabled.
+fn formation_order_shunts_to_json(schedule: &Schedule) -> Option<Vec<JsonFormationOrderShunt>> {
+    let network = schedule.get_network();
+    if !network.config().formation_order_continuity {
+        return None;
+    }
+    Some(
+        schedule
+            .formation_order_shunts()
+            .into_iter()
+            .map(|(vehicle, from, to)| JsonFormationOrderShunt {
+                vehicle: vehicle.to_string(),
+                from_departure_segment: network.node(from).id().to_string(),
+                to_departure_segment: network.node(to).id().to_string(),
+            })
+            .collect(),
+    )
+}
+
+fn platform_idles_to_json(schedule: &Schedule) -> Option<Vec<JsonPlatformIdle>> {
+    let network = schedule.get_network();
+    if !network.locations().has_platform_idle_limits() {
+        return None;
+    }
+    Some(
+        schedule
+            .platform_idles()
+            .into_iter()
+            .map(|(vehicle, node1, node2, platform_idle)| JsonPlatformIdle {
+                vehicle: vehicle.to_string(),
+                location: network
+                    .locations()
+                    .get_id(network.node(node2).start_location())
+                    .unwrap(),
+                start: (network.node(node1).end_time()
+                    + network.dead_head_time_between(node1, node2))
+                .as_iso(),
+                end: network.node(node2).start_time().as_iso(),
+                siding: match platform_idle {
+                    PlatformIdle::MovedToSiding(siding, _) => {
+                        Some(network.locations().get_id(siding).unwrap())
+                    }
+                    PlatformIdle::Violation => None,
+                },
+            })
+            .collect(),
+    )
+}
+
+fn splits_to_json(schedule: &Schedule) -> Option<Vec<JsonSplit>> {
+    let network = schedule.get_network();
+    if !network.has_split_points() {
+        return None;
+    }
+    let vehicle_ids = |node: NodeIdx| -> Vec<String> {
+        schedule
+            .train_formation_of(node)
+            .ids()
+            .iter()
+            .map(|vehicle| vehicle.to_string())
+            .collect()
+    };
+    Some(
+        schedule
+            .splits()
+            .into_iter()
+            .map(|(before, after)| JsonSplit {
+                location: network
+                    .locations()
+                    .get_id(network.node(before).end_location())
+                    .unwrap(),
+                departure_segment_before: network.node(before).id().to_string(),
+                departure_segment_after: network.node(after).id().to_string(),
+                vehicles_before: vehicle_ids(before),
+                vehicles_after: vehicle_ids(after),
+            })
+            .collect(),
+    )
+}
+
 fn depots_usage_to_json(schedule: &Schedule) -> Vec<DepotLoad> {
     let mut depot_loads = vec![];
     let network = schedule.get_network();
@@ -149,6 +736,7 @@ fn depots_usage_to_json(schedule: &Schedule) -> Vec<DepotLoad> {
         let depot = network.get_depot(depot_idx);
         depot_loads.push(DepotLoad {
             depot: depot.id().to_string(),
+            synthetic: depot_idx == network.overflow_depot_idxs().0,
             load: depot_usage_to_json(schedule, depot_idx),
         });
     }
@@ -158,10 +746,26 @@ fn depots_usage_to_json(schedule: &Schedule) -> Vec<DepotLoad> {
 fn depot_usage_to_json(schedule: &Schedule, depot_idx: DepotIdx) -> Vec<Load> {
     let mut loads = vec![];
     let network = schedule.get_network();
+    let is_overflow_depot = depot_idx == network.overflow_depot_idxs().0;
+    let overflow_buckets = if is_overflow_depot {
+        overflow_usage(schedule, overflow_bucket_length())
+    } else {
+        vec![]
+    };
     for vehicle_type in schedule.get_vehicle_types().iter() {
         let spawn_count =
             schedule.number_of_vehicles_of_same_type_spawned_at(depot_idx, vehicle_type);
-        if spawn_count > 0 {
+        let buckets_of_type: Vec<JsonOverflowBucket> = overflow_buckets
+            .iter()
+            .filter(|bucket| bucket.vehicle_type == vehicle_type)
+            .map(|bucket| JsonOverflowBucket {
+                start: bucket.start.as_iso(),
+                end: (bucket.start + overflow_bucket_length()).as_iso(),
+                spawn_count: bucket.spawn_count,
+                despawn_count: bucket.despawn_count,
+            })
+            .collect();
+        if spawn_count > 0 || !buckets_of_type.is_empty() {
             loads.push(Load {
                 vehicle_type: network
                     .vehicle_types()
@@ -170,25 +774,94 @@ fn depot_usage_to_json(schedule: &Schedule, depot_idx: DepotIdx) -> Vec<Load> {
                     .id()
                     .clone(),
                 spawn_count,
+                overflow_buckets: is_overflow_depot.then_some(buckets_of_type),
             })
         }
     }
     loads
 }
 
+/// The overflow usage is reported in buckets of one hour.
+fn overflow_bucket_length() -> Duration {
+    Duration::new("1:00:00")
+}
+
+fn is_optimized(schedule: &Schedule, vehicle_type: VehicleTypeIdx) -> bool {
+    schedule
+        .get_network()
+        .config()
+        .is_active_vehicle_type(vehicle_type)
+}
+
+fn default_optimized() -> bool {
+    true
+}
+
 fn fleet_to_json(
     schedule: &Schedule,
     vehicle_type: VehicleTypeIdx,
     dead_head_trips_with_formation: &mut Vec<JsonFleetDeadHeadTripWithFormation>,
 ) -> JsonFleet {
     let mut vehicles = vec![];
-    for vehicle_idx in schedule.vehicles_iter(vehicle_type) {
+    for vehicle_idx in vehicles_in_output_order(schedule, vehicle_type) {
         vehicles.push(vehicle_to_json(
             schedule,
             vehicle_idx,
             dead_head_trips_with_formation,
         ));
     }
+    JsonFleet {
+        vehicle_type: vehicle_type_id(schedule, vehicle_type),
+        only_overflow_depot: schedule.uses_only_overflow_depot(vehicle_type),
+        optimized: is_optimized(schedule, vehicle_type),
+        vehicles,
+        vehicle_cycles: vehicle_cycles_to_json(schedule, vehicle_type),
+    }
+}
+
+/// The fleets are ordered by the id of their vehicle type.
+fn vehicle_types_in_output_order(network: &Network) -> Vec<VehicleTypeIdx> {
+    network
+        .vehicle_types()
+        .iter()
+        .sorted_by_key(|&vehicle_type| vehicle_type_id_of(network, vehicle_type))
+        .collect()
+}
+
+/// The vehicles of a fleet are ordered by the start of their first activity (departure segment or
+/// maintenance slot), ties are broken by the vehicle id. Unlike the internal index order, this
+/// order does not depend on the vehicles created and deleted during the optimization.
+fn vehicles_in_output_order(schedule: &Schedule, vehicle_type: VehicleTypeIdx) -> Vec<VehicleIdx> {
+    let network = schedule.get_network();
+    schedule
+        .vehicles_iter(vehicle_type)
+        .map(|vehicle| {
+            let first_activity = schedule.tour_of(vehicle).unwrap().first_non_depot();
+            (
+                first_activity.map(|node| network.node(node).start_time()),
+                vehicle.to_string(),
+                vehicle,
+            )
+        })
+        .sorted()
+        .map(|(_, _, vehicle)| vehicle)
+        .collect()
+}
+
+fn vehicle_type_id(schedule: &Schedule, vehicle_type: VehicleTypeIdx) -> String {
+    vehicle_type_id_of(&schedule.get_network(), vehicle_type)
+}
+
+fn vehicle_type_id_of(network: &Network, vehicle_type: VehicleTypeIdx) -> String {
+    network
+        .vehicle_types()
+        .get(vehicle_type)
+        .unwrap()
+        .id()
+        .clone()
+}
+
+fn vehicle_cycles_to_json(schedule: &Schedule, vehicle_type: VehicleTypeIdx) -> Vec<Vec<String>> {
     let mut vehicle_cycles = vec![];
     for transtion_cylce in schedule.next_day_transition_of(vehicle_type).cycles_iter() {
         vehicle_cycles.push(
@@ -198,17 +871,7 @@ fn fleet_to_json(
                 .collect(),
         );
     }
-    JsonFleet {
-        vehicle_type: schedule
-            .get_network()
-            .vehicle_types()
-            .get(vehicle_type)
-            .unwrap()
-            .id()
-            .clone(),
-        vehicles,
-        vehicle_cycles,
-    }
+    vehicle_cycles
 }
 
 fn vehicle_to_json(
@@ -233,17 +896,15 @@ fn vehicle_to_json(
         .all_nodes_iter()
         .tuple_windows()
     {
-        let node1 = network.node(node1_idx);
         let node2 = network.node(node2_idx);
-        if network.node(node1_idx).end_location() != node2.start_location() {
-            let (departure_time, arrival_time) =
-                schedule_dead_head_trip(node1_idx, node2_idx, &network);
+        if let Some(leg) = DeadHeadLeg::between(vehicle_idx, node1_idx, node2_idx, &network) {
             let dead_head_trip = JsonFleetDeadHeadTrip {
                 id: "dht_".to_string() + &dead_head_trips_counter.to_string(),
-                origin: network.locations().get_id(node1.end_location()).unwrap(),
-                destination: network.locations().get_id(node2.start_location()).unwrap(),
-                departure: departure_time.as_iso(),
-                arrival: arrival_time.as_iso(),
+                origin: network.locations().get_id(leg.origin()).unwrap(),
+                destination: network.locations().get_id(leg.destination()).unwrap(),
+                departure: leg.departure().as_iso(),
+                arrival: leg.arrival().as_iso(),
+                synthetic: leg.is_synthetic(),
             };
             let dead_head_trip_with_formation = JsonFleetDeadHeadTripWithFormation {
                 id: dead_head_trip.id.clone(),
@@ -251,6 +912,7 @@ fn vehicle_to_json(
                 destination: dead_head_trip.destination.clone(),
                 departure: dead_head_trip.departure.clone(),
                 arrival: dead_head_trip.arrival.clone(),
+                synthetic: dead_head_trip.synthetic,
                 formation: vec![vehicle_idx.to_string()],
             };
             dead_head_trips_counter += 1;
@@ -282,12 +944,28 @@ fn vehicle_to_json(
     }
     JsonVehicle {
         id: vehicle_idx.to_string(),
+        index: Some(vehicle_idx.idx()),
         start_depot: start_depot.id().to_string(),
         end_depot: end_depot.id().to_string(),
+        synthetic: network.is_overflow_depot_node(start_depot_node)
+            || network.is_overflow_depot_node(end_depot_node),
         departure_segments,
         maintenance_slots,
         dead_head_trips,
+        costs: vehicle_costs_to_json(schedule.tour_of(vehicle_idx).unwrap(), &network),
+    }
+}
+
+/// The costs of the tour split into the base costs and the surcharge of the staff time factors.
+fn vehicle_costs_to_json(tour: &Tour, network: &Network) -> Option<JsonVehicleCosts> {
+    if network.config().costs.staff_time_factors.is_empty() {
+        return None;
     }
+    let staff_time_surcharge = tour.staff_time_surcharge();
+    Some(JsonVehicleCosts {
+        base: tour.costs() - staff_time_surcharge,
+        staff_time_surcharge,
+    })
 }
 
 fn departure_segments_to_json(schedule: &Schedule) -> Vec<JsonDepartureSegmentWithFormation> {
@@ -295,39 +973,101 @@ fn departure_segments_to_json(schedule: &Schedule) -> Vec<JsonDepartureSegmentWi
     let mut departure_segments = vec![];
     for vehicle_type in network.vehicle_types().iter() {
         for service_trip_node_idx in network.service_nodes(vehicle_type) {
-            let service_trip_node = network.node(service_trip_node_idx);
-            let service_trip = service_trip_node.as_service_trip();
-
-            let formation = schedule.train_formation_of(service_trip_node_idx);
-            let departure_segment = JsonDepartureSegmentWithFormation {
-                departure_segment: service_trip.id().to_string(),
-                origin: network
-                    .locations()
-                    .get_id(service_trip_node.start_location())
-                    .unwrap(),
-                destination: network
-                    .locations()
-                    .get_id(service_trip_node.end_location())
-                    .unwrap(),
-                departure: service_trip_node.start_time().as_iso(),
-                arrival: service_trip_node.end_time().as_iso(),
-                vehicle_type: network
-                    .vehicle_types()
-                    .get(vehicle_type)
-                    .unwrap()
-                    .id()
-                    .clone(),
-                formation: formation
-                    .iter()
-                    .map(|vehicle| vehicle.idx().to_string())
-                    .collect(),
-            };
-            departure_segments.push(departure_segment);
+            departure_segments.push(departure_segment_to_json(
+                schedule,
+                vehicle_type,
+                service_trip_node_idx,
+            ));
         }
     }
     departure_segments
 }
 
+fn departure_segment_to_json(
+    schedule: &Schedule,
+    vehicle_type: VehicleTypeIdx,
+    service_trip_node_idx: NodeIdx,
+) -> JsonDepartureSegmentWithFormation {
+    let network = schedule.get_network();
+    let service_trip_node = network.node(service_trip_node_idx);
+    let service_trip = service_trip_node.as_service_trip();
+
+    let formation = schedule.train_formation_of(service_trip_node_idx);
+    let service_vehicle_count =
+        formation.vehicle_count() - schedule.hitch_hikers_at(service_trip_node_idx);
+    let passengers = network.passengers_of(service_trip_node_idx);
+    let seated_passengers = network.seated_passengers_of(service_trip_node_idx);
+    let load_factor = load_factor_of(passengers, formation.capacity());
+    let seat_load_factor = load_factor_of(seated_passengers, formation.seats());
+    let (unserved_passengers, unserved_seated_passengers) =
+        schedule.unserved_passengers_at(service_trip_node_idx);
+    JsonDepartureSegmentWithFormation {
+        departure_segment: service_trip.id().to_string(),
+        origin: network
+            .locations()
+            .get_id(service_trip_node.start_location())
+            .unwrap(),
+        destination: network
+            .locations()
+            .get_id(service_trip_node.end_location())
+            .unwrap(),
+        departure: service_trip_node.start_time().as_iso(),
+        arrival: service_trip_node.end_time().as_iso(),
+        vehicle_type: vehicle_type_id(schedule, vehicle_type),
+        formation: formation
+            .iter()
+            .enumerate()
+            .map(|(position, vehicle)| JsonFormationMember {
+                vehicle: vehicle.idx().to_string(),
+                role: if (position as VehicleCount) < service_vehicle_count {
+                    JsonFormationRole::Service
+                } else {
+                    JsonFormationRole::DeadheadOnFormation
+                },
+            })
+            .collect(),
+        capacity: formation.capacity(),
+        seats: formation.seats(),
+        passengers,
+        seated_passengers,
+        load_factor,
+        seat_load_factor,
+        unserved_passengers,
+        unserved_seated_passengers,
+        crowding: if (unserved_passengers, unserved_seated_passengers) != (0, 0) {
+            JsonCrowding::Overcrowded
+        } else {
+            crowding_class(
+                &network,
+                load_factor
+                    .unwrap_or(0.0)
+                    .max(seat_load_factor.unwrap_or(0.0)),
+            )
+        },
+    }
+}
+
+/// None if there is no capacity (demand is zero or unserved in this case).
+fn load_factor_of(demand: PassengerCount, capacity: PassengerCount) -> Option<f64> {
+    if capacity == 0 {
+        None
+    } else {
+        Some(demand as f64 / capacity as f64)
+    }
+}
+
+/// A load factor exactly at a threshold belongs to the higher class.
+fn crowding_class(network: &Network, load_factor: f64) -> JsonCrowding {
+    let thresholds = &network.config().crowding_thresholds;
+    if load_factor >= thresholds.high {
+        JsonCrowding::High
+    } else if load_factor >= thresholds.moderate {
+        JsonCrowding::Moderate
+    } else {
+        JsonCrowding::Low
+    }
+}
+
 fn maintenance_slots_to_json(schedule: &Schedule) -> Vec<JsonFleetMaintenanceSlotWithFormation> {
     let network = schedule.get_network();
     let mut maintenance_slots = vec![];
@@ -352,21 +1092,3 @@ fn maintenance_slots_to_json(schedule: &Schedule) -> Vec<JsonFleetMaintenanceSlo
     }
     maintenance_slots
 }
-
-fn schedule_dead_head_trip(
-    node1_idx: NodeIdx,
-    node2_idx: NodeIdx,
-    nw: &Network,
-) -> (DateTime, DateTime) {
-    let node1 = nw.node(node1_idx);
-    let node2 = nw.node(node2_idx);
-    if node1.is_depot() {
-        let departure_time =
-            node2.start_time() - nw.minimal_duration_between_nodes(node1_idx, node2_idx);
-        let arrival_time = node2.start_time();
-        return (departure_time, arrival_time);
-    }
-    let departure_time = node1.end_time();
-    let arrival_time = node1.end_time() + nw.minimal_duration_between_nodes(node1_idx, node2_idx);
-    (departure_time, arrival_time)
-}
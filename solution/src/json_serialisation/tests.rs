@@ -0,0 +1,374 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::{fs::File, io::Read};
+
+use itertools::Itertools;
+use model::base_types::{NodeIdx, VehicleIdx};
+
+use crate::test_utilities::{
+    default_schedule, init_test_data, init_test_data_with, init_test_data_with_split_point,
+};
+use crate::Schedule;
+
+use super::{schedule_from_json, schedule_to_json, write_schedule_json};
+
+#[test]
+fn streamed_output_equals_value_output_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let mut buffer: Vec<u8> = Vec::new();
+
+    // ACT
+    write_schedule_json(&schedule, &mut buffer).unwrap();
+
+    // ASSERT
+    let streamed: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+    assert_eq!(streamed, schedule_to_json(&schedule));
+    assert_eq!(
+        serde_json::to_string(&streamed).unwrap(),
+        String::from_utf8(buffer).unwrap()
+    );
+}
+
+#[test]
+fn streamed_output_with_dummy_tours_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d)
+        .replace_vehicle_by_dummy(VehicleIdx::vehicle_from(1))
+        .unwrap();
+    let mut buffer: Vec<u8> = Vec::new();
+
+    // ACT
+    write_schedule_json(&schedule, &mut buffer).unwrap();
+
+    // ASSERT
+    let streamed: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+    assert_eq!(streamed, schedule_to_json(&schedule));
+}
+
+#[test]
+fn trip_families_summary_test() {
+    // ARRANGE
+    // trip14 (departure 7) is repeated one hour later (10:00 - 10:30)
+    let d = init_test_data_with(|instance| {
+        instance["departures"][7]["headwayRepetitions"] =
+            serde_json::json!({"count": 2, "interval": 3600});
+    });
+    let trip14_repeated = NodeIdx::service_from(20);
+    let schedule = default_schedule(&d);
+    let mut buffer: Vec<u8> = Vec::new();
+
+    // ACT
+    let json = schedule_to_json(&schedule);
+    write_schedule_json(&schedule, &mut buffer).unwrap();
+
+    // ASSERT
+    assert_eq!(
+        json["tripFamilies"],
+        serde_json::json!([
+            {
+                "family": "trip_1-4",
+                "interval": 3600,
+                "repetitions": [
+                    {
+                        "repetition": 0,
+                        "departure": d.network.node(d.trip14).start_time().as_iso(),
+                        "vehicles": [VehicleIdx::vehicle_from(1).to_string()],
+                    },
+                    {
+                        "repetition": 1,
+                        "departure": d.network.node(trip14_repeated).start_time().as_iso(),
+                        "vehicles": [],
+                    },
+                ],
+            }
+        ])
+    );
+    let streamed: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+    assert_eq!(streamed, json);
+
+    // without trip families the summary is omitted
+    assert!(schedule_to_json(&default_schedule(&init_test_data()))
+        .get("tripFamilies")
+        .is_none());
+}
+
+#[test]
+fn crowding_at_thresholds_test() {
+    // ARRANGE
+    // capacity of vt1 is 50, trip12 and trip31 are served by two vehicles, trip34, trip45 and
+    // trip14 by one.
+    let d = init_test_data_with(|instance| {
+        instance["parameters"]["output"] =
+            serde_json::json!({"crowdingThresholds": {"moderate": 0.5, "high": 0.8}});
+        instance["departures"][0]["segments"][0]["passengers"] = serde_json::json!(50);
+        instance["departures"][2]["segments"][0]["passengers"] = serde_json::json!(40);
+        instance["departures"][3]["segments"][0]["passengers"] = serde_json::json!(51);
+        instance["departures"][6]["segments"][0]["passengers"] = serde_json::json!(49);
+        instance["departures"][7]["segments"][0]["passengers"] = serde_json::json!(50);
+    });
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let json = schedule_to_json(&schedule);
+
+    // ASSERT
+    let departure_segment = |id: &str| {
+        json["departureSegments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|segment| segment["departureSegment"] == id)
+            .unwrap()
+            .clone()
+    };
+    let trip12 = departure_segment("dep_segment_1-2");
+    assert_eq!(trip12["capacity"], 100);
+    assert_eq!(trip12["seats"], 60);
+    assert_eq!(trip12["passengers"], 50);
+    assert_eq!(trip12["loadFactor"], 0.5);
+    assert_eq!(trip12["seatLoadFactor"], 0.0);
+    assert_eq!(trip12["crowding"], "moderate");
+    assert_eq!(departure_segment("dep_segment_3-1")["crowding"], "low");
+    assert_eq!(departure_segment("dep_segment_3-4")["crowding"], "high");
+
+    let trip14 = departure_segment("dep_segment_1-4");
+    assert_eq!(trip14["loadFactor"], 1.0);
+    assert_eq!(trip14["unservedPassengers"], 0);
+    assert_eq!(trip14["crowding"], "high");
+
+    let trip45 = departure_segment("dep_segment_4-5");
+    assert_eq!(
+        trip45["unservedPassengers"],
+        schedule.unserved_passengers_at(d.trip45).0
+    );
+    assert_eq!(trip45["unservedPassengers"], 1);
+    assert_eq!(trip45["crowding"], "overcrowded");
+
+    // the uncovered trip45_fast has one passenger (repaired from zero) but no capacity
+    let fast_trip = departure_segment("dep_segment_4-5fast");
+    assert!(fast_trip["loadFactor"].is_null());
+    assert_eq!(fast_trip["unservedPassengers"], 1);
+    assert_eq!(fast_trip["crowding"], "overcrowded");
+}
+
+#[test]
+fn overflow_depot_is_tagged_synthetic_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        instance["parameters"]["fixedFleet"] = serde_json::json!({ "vt1": 3 });
+    });
+    let (schedule, new_vehicle) = default_schedule(&d)
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip34])
+        .unwrap();
+
+    // ACT
+    let json = schedule_to_json(&schedule);
+
+    // ASSERT
+    let vehicles = json["fleet"][0]["vehicles"].as_array().unwrap();
+    for vehicle in vehicles {
+        let is_new_vehicle = vehicle["id"] == new_vehicle.to_string();
+        assert_eq!(vehicle["synthetic"], is_new_vehicle);
+        for dead_head_trip in vehicle["deadHeadTrips"].as_array().unwrap() {
+            let touches_overflow_depot =
+                dead_head_trip["origin"] == "NOWHERE" || dead_head_trip["destination"] == "NOWHERE";
+            assert_eq!(dead_head_trip["synthetic"], touches_overflow_depot);
+        }
+    }
+    let synthetic_dead_head_trips: Vec<_> = json["deadHeadTrips"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|dead_head_trip| dead_head_trip["synthetic"] == true)
+        .collect();
+    assert_eq!(synthetic_dead_head_trips.len(), 1);
+    assert_eq!(synthetic_dead_head_trips[0]["origin"], "NOWHERE");
+    assert_eq!(
+        synthetic_dead_head_trips[0]["formation"],
+        serde_json::json!([new_vehicle.to_string()])
+    );
+
+    let synthetic_depots: Vec<_> = json["depotLoads"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|depot_load| depot_load["synthetic"] == true)
+        .collect();
+    assert_eq!(synthetic_depots.len(), 1);
+    assert_eq!(synthetic_depots[0]["depot"], "OVERFLOW_DEPOT");
+}
+
+#[test]
+fn schedule_from_json_round_trip_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let json = schedule_to_json(&schedule);
+
+    // ACT
+    let (imported, vehicle_ids) = schedule_from_json(json.clone(), d.network.clone()).unwrap();
+
+    // ASSERT
+    assert_eq!(schedule_to_json(&imported), json);
+    assert_eq!(
+        vehicle_ids,
+        (0..3)
+            .map(|i| {
+                let vehicle = VehicleIdx::vehicle_from(i);
+                (vehicle.to_string(), vehicle)
+            })
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn schedule_from_json_with_unknown_departure_segment_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let mut json = schedule_to_json(&default_schedule(&d));
+    json["fleet"][0]["vehicles"][1]["departureSegments"][0]["departureSegment"] =
+        serde_json::json!("unknown");
+
+    // ACT
+    let result = schedule_from_json(json, d.network.clone());
+
+    // ASSERT
+    let error = result.err().unwrap();
+    assert!(error.contains("unknown"));
+    // the second vehicle of the fleet (veh_2 starts before veh_1)
+    assert!(error.contains(&VehicleIdx::vehicle_from(2).to_string()));
+}
+
+#[test]
+fn schedule_from_json_with_other_vehicle_type_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let mut json = schedule_to_json(&default_schedule(&d));
+    // all departures of the test instance are served by vt1
+    json["fleet"][0]["vehicleType"] = serde_json::json!("vt2");
+
+    // ACT
+    let result = schedule_from_json(json, d.network.clone());
+
+    // ASSERT
+    let error = result.err().unwrap();
+    assert!(error.contains("cannot be served by vehicle type vt2"));
+}
+
+#[test]
+fn fleet_order_matches_golden_file_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let mut file = File::open("resources/test_schedule_fleet_order.json").unwrap();
+    let mut golden = String::new();
+    file.read_to_string(&mut golden).unwrap();
+    let golden: serde_json::Value = serde_json::from_str(&golden).unwrap();
+
+    // ACT
+    let json = schedule_to_json(&schedule);
+
+    // ASSERT
+    // only the fields that determine the order are compared
+    let fleet: Vec<serde_json::Value> = json["fleet"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|fleet| {
+            serde_json::json!({
+                "vehicleType": fleet["vehicleType"],
+                "vehicles": fleet["vehicles"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|vehicle| serde_json::json!({
+                        "id": vehicle["id"],
+                        "index": vehicle["index"],
+                        "startDepot": vehicle["startDepot"],
+                        "endDepot": vehicle["endDepot"],
+                        "departureSegments": vehicle["departureSegments"]
+                            .as_array()
+                            .unwrap()
+                            .iter()
+                            .map(|segment| segment["departureSegment"].clone())
+                            .collect::<Vec<_>>(),
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    assert_eq!(serde_json::Value::from(fleet), golden);
+    let vehicle_order: Vec<&serde_json::Value> = golden[0]["vehicles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|vehicle| &vehicle["id"])
+        .collect();
+    let dead_head_trip_order: Vec<&serde_json::Value> = json["deadHeadTrips"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|dead_head_trip| &dead_head_trip["formation"][0])
+        .dedup()
+        .collect();
+    assert_eq!(dead_head_trip_order, vehicle_order);
+    let projected_counter_order: Vec<&serde_json::Value> = json["projectedMaintenanceCounters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|projected_counter| &projected_counter["vehicle"])
+        .collect();
+    assert_eq!(projected_counter_order, vehicle_order);
+}
+
+#[test]
+fn splits_output_test() {
+    // ARRANGE
+    let d = init_test_data_with_split_point();
+    let schedule = Schedule::empty(d.network.clone())
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.start_depot1, d.trip12, d.trip23, d.end_depot3],
+        )
+        .unwrap()
+        .0
+        .spawn_vehicle_for_path(d.vt1, vec![d.start_depot1, d.trip12, d.end_depot2])
+        .unwrap()
+        .0;
+    let mut buffer: Vec<u8> = Vec::new();
+
+    // ACT
+    let json = schedule_to_json(&schedule);
+    write_schedule_json(&schedule, &mut buffer).unwrap();
+
+    // ASSERT
+    assert_eq!(
+        json["splits"],
+        serde_json::json!([{
+            "location": "loc2",
+            "departureSegmentBefore": "dep_segment_1-2",
+            "departureSegmentAfter": "dep_segment_2-3",
+            "vehiclesBefore": ["veh_0", "veh_1"],
+            "vehiclesAfter": ["veh_0"],
+        }])
+    );
+    let streamed: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+    assert_eq!(streamed, json);
+    assert!(schedule_to_json(&default_schedule(&init_test_data()))
+        .get("splits")
+        .is_none());
+}
@@ -11,8 +11,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod contract_accounting;
+pub mod dead_head_leg;
+pub mod delay_propagation;
 pub mod json_serialisation;
+pub mod overflow_analysis;
 pub mod path;
+pub mod projected_counter;
 mod schedule;
 pub mod segment;
 pub mod test_utilities;
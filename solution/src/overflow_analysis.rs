@@ -0,0 +1,311 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use model::base_types::{
+    DepotIdx, NodeIdx, PassengerCount, VehicleCount, VehicleIdx, VehicleTypeIdx,
+};
+use rapid_time::{DateTime, Duration};
+
+use crate::Schedule;
+
+/// Number of vehicles of one type that are spawned and despawned at the overflow depot within one
+/// time bucket [start, start + bucket_length), i.e., vehicles that would have needed capacity of
+/// a regular depot at that time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OverflowBucket {
+    pub vehicle_type: VehicleTypeIdx,
+    pub start: DateTime,
+    pub spawn_count: VehicleCount,
+    pub despawn_count: VehicleCount,
+}
+
+/// Extra spawn and despawn capacity of a regular depot for one vehicle type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MissingCapacity {
+    pub depot: DepotIdx,
+    pub vehicle_type: VehicleTypeIdx,
+    pub spawn: VehicleCount,
+    pub despawn: VehicleCount,
+}
+
+/// Passenger demand of the service trips that is not carried by vehicles of regular depots, i.e.,
+/// it is unserved or only fits thanks to vehicles of the overflow depot (which do not exist in
+/// reality).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UncoveredDemand {
+    pub passengers: u64, // uncovered passengers summed over all service trips
+    pub total_passengers: u64,
+    pub per_vehicle_type: BTreeMap<VehicleTypeIdx, u64>, // types without uncovered demand omitted
+    pub service_trips: Vec<NodeIdx>,                     // with uncovered passengers, sorted
+}
+
+impl UncoveredDemand {
+    /// share of the uncovered passengers in all passengers (0.0 without passengers).
+    pub fn share(&self) -> f64 {
+        if self.total_passengers == 0 {
+            0.0
+        } else {
+            self.passengers as f64 / self.total_passengers as f64
+        }
+    }
+}
+
+/// Returns the usage of the overflow depot per vehicle type and time bucket (sorted by vehicle
+/// type and start, empty buckets are omitted).
+/// A vehicle is counted at the start of its first non-depot node if it is spawned at the overflow
+/// depot and at the end of its last non-depot node if it is despawned there. The buckets are
+/// aligned with the earliest start of all service trips and maintenance slots.
+pub fn overflow_usage(schedule: &Schedule, bucket_length: Duration) -> Vec<OverflowBucket> {
+    let network = schedule.get_network();
+    let horizon_start = network
+        .coverable_nodes()
+        .map(|node| network.node(node).start_time())
+        .min()
+        .unwrap_or(DateTime::Earliest);
+    let bucket_length_in_sec = bucket_length.in_sec().unwrap().max(1);
+    let bucket_of =
+        |time: DateTime| (time - horizon_start).in_sec().unwrap() / bucket_length_in_sec;
+
+    let mut buckets: BTreeMap<(VehicleTypeIdx, u64), (VehicleCount, VehicleCount)> =
+        BTreeMap::new();
+    for vehicle in schedule.vehicles_using_overflow_depot() {
+        let vehicle_type = schedule.vehicle_type_of(vehicle).unwrap();
+        let tour = schedule.tour_of(vehicle).unwrap();
+        if network.is_overflow_depot_node(tour.first_node()) {
+            let spawn_time = network.node(tour.first_non_depot().unwrap()).start_time();
+            buckets
+                .entry((vehicle_type, bucket_of(spawn_time)))
+                .or_default()
+                .0 += 1;
+        }
+        if network.is_overflow_depot_node(tour.last_node()) {
+            let despawn_time = network.node(tour.last_non_depot().unwrap()).end_time();
+            buckets
+                .entry((vehicle_type, bucket_of(despawn_time)))
+                .or_default()
+                .1 += 1;
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(
+            |((vehicle_type, bucket), (spawn_count, despawn_count))| OverflowBucket {
+                vehicle_type,
+                start: horizon_start + Duration::from_seconds(bucket * bucket_length_in_sec),
+                spawn_count,
+                despawn_count,
+            },
+        )
+        .collect()
+}
+
+/// Returns the minimal extra capacity per regular depot and vehicle type that would have
+/// eliminated the usage of the overflow depot (sorted by depot and vehicle type, only entries
+/// with some missing capacity).
+/// The depot assignment is re-run greedily for all vehicles using the overflow depot (ordered by
+/// spawn and despawn time): each vehicle is assigned to the nearest regular depot that admits its
+/// type and has capacity left (as find_best_start_depot_for_spawning does with the nearest
+/// depot assignment policy). If no such depot exists, the capacity of the nearest depot
+/// admitting the type is incremented.
+/// Vehicle types with a fixed fleet are skipped, as their vehicles beyond the fleet size are
+/// spawned at the overflow depot regardless of the depot capacities. Vehicles whose type is
+/// admitted at no regular depot cannot be fixed by capacity and are skipped as well.
+pub fn missing_capacities(schedule: &Schedule) -> Vec<MissingCapacity> {
+    let network = schedule.get_network();
+    let overflow_vehicles: Vec<VehicleIdx> = schedule
+        .vehicles_using_overflow_depot()
+        .into_iter()
+        .filter(|&vehicle| {
+            network
+                .fixed_fleet_size_of(schedule.vehicle_type_of(vehicle).unwrap())
+                .is_none()
+        })
+        .collect();
+
+    let mut spawning: Vec<(VehicleIdx, NodeIdx)> = overflow_vehicles
+        .iter()
+        .map(|&vehicle| (vehicle, schedule.tour_of(vehicle).unwrap()))
+        .filter(|(_, tour)| network.is_overflow_depot_node(tour.first_node()))
+        .map(|(vehicle, tour)| (vehicle, tour.first_non_depot().unwrap()))
+        .collect();
+    spawning.sort_by_key(|&(vehicle, node)| (network.node(node).start_time(), vehicle));
+    let mut despawning: Vec<(VehicleIdx, NodeIdx)> = overflow_vehicles
+        .iter()
+        .map(|&vehicle| (vehicle, schedule.tour_of(vehicle).unwrap()))
+        .filter(|(_, tour)| network.is_overflow_depot_node(tour.last_node()))
+        .map(|(vehicle, tour)| (vehicle, tour.last_non_depot().unwrap()))
+        .collect();
+    despawning.sort_by_key(|&(vehicle, node)| (network.node(node).end_time(), vehicle));
+
+    let mut missing_capacities: BTreeMap<(DepotIdx, VehicleTypeIdx), MissingCapacity> =
+        BTreeMap::new();
+    for ((depot, vehicle_type), count) in assign_greedily(schedule, &spawning, true) {
+        missing_capacities
+            .entry((depot, vehicle_type))
+            .or_insert(MissingCapacity {
+                depot,
+                vehicle_type,
+                spawn: 0,
+                despawn: 0,
+            })
+            .spawn = count;
+    }
+    for ((depot, vehicle_type), count) in assign_greedily(schedule, &despawning, false) {
+        missing_capacities
+            .entry((depot, vehicle_type))
+            .or_insert(MissingCapacity {
+                depot,
+                vehicle_type,
+                spawn: 0,
+                despawn: 0,
+            })
+            .despawn = count;
+    }
+    missing_capacities.into_values().collect()
+}
+
+/// Returns the passengers of each service trip that exceed the capacity of the vehicles of regular
+/// depots in its train formation (seats are not considered).
+pub fn uncovered_demand(schedule: &Schedule) -> UncoveredDemand {
+    let network = schedule.get_network();
+    let overflow_vehicles: HashSet<VehicleIdx> = schedule
+        .vehicles_using_overflow_depot()
+        .into_iter()
+        .collect();
+    let mut uncovered_demand = UncoveredDemand {
+        passengers: 0,
+        total_passengers: 0,
+        per_vehicle_type: BTreeMap::new(),
+        service_trips: Vec::new(),
+    };
+    for service_trip in network.all_service_nodes() {
+        let passengers = network.passengers_of(service_trip);
+        let regular_capacity = schedule
+            .train_formation_of(service_trip)
+            .iter()
+            .filter(|vehicle| !overflow_vehicles.contains(&vehicle.idx()))
+            .fold(0, |capacity: PassengerCount, vehicle| {
+                capacity.saturating_add(vehicle.capacity())
+            });
+        let uncovered = passengers.saturating_sub(regular_capacity) as u64;
+        uncovered_demand.total_passengers += passengers as u64;
+        if uncovered > 0 {
+            uncovered_demand.passengers += uncovered;
+            *uncovered_demand
+                .per_vehicle_type
+                .entry(network.vehicle_type_for(service_trip))
+                .or_insert(0) += uncovered;
+            uncovered_demand.service_trips.push(service_trip);
+        }
+    }
+    uncovered_demand.service_trips.sort();
+    uncovered_demand
+}
+
+/// Assigns each vehicle (given with its first or last non-depot node) to the nearest regular
+/// depot with spawn (resp. despawn) capacity left and returns the number of times the capacity of
+/// a depot had to be incremented.
+fn assign_greedily(
+    schedule: &Schedule,
+    vehicles: &[(VehicleIdx, NodeIdx)],
+    spawning: bool,
+) -> HashMap<(DepotIdx, VehicleTypeIdx), VehicleCount> {
+    let network = schedule.get_network();
+    let mut additional_usage_of_type: HashMap<(DepotIdx, VehicleTypeIdx), VehicleCount> =
+        HashMap::new();
+    let mut additional_usage: HashMap<DepotIdx, VehicleCount> = HashMap::new();
+    let mut incremented: HashMap<(DepotIdx, VehicleTypeIdx), VehicleCount> = HashMap::new();
+
+    for &(vehicle, node) in vehicles {
+        let vehicle_type = schedule.vehicle_type_of(vehicle).unwrap();
+        let depot_nodes = if spawning {
+            network.start_depots_sorted_by_distance_to(network.node(node).start_location())
+        } else {
+            network.end_depots_sorted_by_distance_from(network.node(node).end_location())
+        };
+        let candidates: Vec<DepotIdx> = depot_nodes
+            .into_iter()
+            .filter(|&depot_node| {
+                !network.is_overflow_depot_node(depot_node)
+                    && network
+                        .get_depot(network.get_depot_idx(depot_node))
+                        .allows(vehicle_type)
+                    && network.compatible_with_vehicle_type(depot_node, vehicle_type)
+            })
+            .map(|depot_node| network.get_depot_idx(depot_node))
+            .collect();
+
+        let has_capacity_left = |depot: DepotIdx| {
+            let (used_of_type, used, capacity_of_type, capacity) = if spawning {
+                (
+                    schedule.number_of_vehicles_of_same_type_spawned_at(depot, vehicle_type),
+                    schedule.number_of_vehicles_spawned_at(depot),
+                    network.capacity_of(depot, vehicle_type),
+                    network.total_capacity_of(depot),
+                )
+            } else {
+                (
+                    schedule.number_of_vehicles_of_same_type_despawned_at(depot, vehicle_type),
+                    schedule.number_of_vehicles_despawned_at(depot),
+                    network.despawn_capacity_of(depot, vehicle_type),
+                    network.total_despawn_capacity_of(depot),
+                )
+            };
+            let incremented_of_type = incremented
+                .get(&(depot, vehicle_type))
+                .copied()
+                .unwrap_or(0);
+            let incremented_in_total: VehicleCount = incremented
+                .iter()
+                .filter(|((d, _), _)| *d == depot)
+                .map(|(_, count)| count)
+                .sum();
+            used_of_type
+                + additional_usage_of_type
+                    .get(&(depot, vehicle_type))
+                    .copied()
+                    .unwrap_or(0)
+                < capacity_of_type + incremented_of_type
+                && used + additional_usage.get(&depot).copied().unwrap_or(0)
+                    < capacity + incremented_in_total
+        };
+
+        let depot_with_capacity_left = candidates
+            .iter()
+            .copied()
+            .find(|&depot| has_capacity_left(depot));
+        let depot = match depot_with_capacity_left {
+            Some(depot) => depot,
+            None => match candidates.first() {
+                Some(&nearest_depot) => {
+                    *incremented
+                        .entry((nearest_depot, vehicle_type))
+                        .or_insert(0) += 1;
+                    nearest_depot
+                }
+                None => continue,
+            },
+        };
+        *additional_usage_of_type
+            .entry((depot, vehicle_type))
+            .or_insert(0) += 1;
+        *additional_usage.entry(depot).or_insert(0) += 1;
+    }
+    incremented
+}
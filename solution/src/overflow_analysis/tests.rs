@@ -0,0 +1,143 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use rapid_time::Duration;
+
+use crate::test_utilities::{default_schedule, init_test_data, init_test_data_with};
+
+use super::{
+    missing_capacities, overflow_usage, uncovered_demand, MissingCapacity, OverflowBucket,
+};
+
+#[test]
+fn overflow_usage_test() {
+    // ARRANGE
+    // vt1 is only admitted at depot1 and depot2, both are full in the default schedule
+    let d = init_test_data_with(|instance| {
+        let vt2_only =
+            serde_json::json!([{"vehicleType": "vt2", "capacity": 1, "despawnCapacity": 10}]);
+        instance["depots"][2]["allowedTypes"] = vt2_only.clone();
+        instance["depots"][3]["allowedTypes"] = serde_json::json!([]);
+        instance["depots"][4]["allowedTypes"] = vt2_only;
+    });
+    let (schedule, _) = default_schedule(&d)
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip34])
+        .unwrap();
+    let (schedule, _) = schedule
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip45])
+        .unwrap();
+
+    // ACT
+    let buckets = overflow_usage(&schedule, Duration::from_seconds(1));
+    let single_bucket = overflow_usage(&schedule, Duration::new("24:00:00"));
+
+    // ASSERT
+    // both vehicles can despawn at depot1
+    assert_eq!(schedule.vehicles_using_overflow_depot().len(), 2);
+    assert_eq!(
+        buckets,
+        vec![
+            OverflowBucket {
+                vehicle_type: d.vt1,
+                start: d.network.node(d.trip34).start_time(),
+                spawn_count: 1,
+                despawn_count: 0,
+            },
+            OverflowBucket {
+                vehicle_type: d.vt1,
+                start: d.network.node(d.trip45).start_time(),
+                spawn_count: 1,
+                despawn_count: 0,
+            },
+        ]
+    );
+    assert_eq!(single_bucket.len(), 1);
+    assert_eq!(single_bucket[0].spawn_count, 2);
+}
+
+#[test]
+fn missing_capacities_test() {
+    // ARRANGE
+    // vt1 is only admitted at depot1 and depot2, both are full in the default schedule
+    let d = init_test_data_with(|instance| {
+        let vt2_only =
+            serde_json::json!([{"vehicleType": "vt2", "capacity": 1, "despawnCapacity": 10}]);
+        instance["depots"][2]["allowedTypes"] = vt2_only.clone();
+        instance["depots"][3]["allowedTypes"] = serde_json::json!([]);
+        instance["depots"][4]["allowedTypes"] = vt2_only;
+    });
+    // trip34 starts at loc3 and trip45 at loc4, depot1 (loc1) is nearer to both than depot2
+    let (schedule, _) = default_schedule(&d)
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip34])
+        .unwrap();
+    let (schedule, _) = schedule
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip45])
+        .unwrap();
+
+    // ACT
+    let missing = missing_capacities(&schedule);
+
+    // ASSERT
+    assert_eq!(
+        missing,
+        vec![MissingCapacity {
+            depot: d.depot1,
+            vehicle_type: d.vt1,
+            spawn: 2,
+            despawn: 0,
+        }]
+    );
+
+    // free capacity at another depot (depot3 at loc3) needs no extra capacity
+    let d = init_test_data();
+    let (schedule, _) = default_schedule(&d)
+        .spawn_vehicle_for_path(d.vt1, vec![d.start_depot1, d.trip34, d.end_depot1])
+        .unwrap();
+    assert_eq!(schedule.vehicles_using_overflow_depot().len(), 1);
+    assert!(missing_capacities(&schedule).is_empty());
+}
+
+#[test]
+fn uncovered_demand_test() {
+    // ARRANGE
+    // all trips have 80 passengers (except trip_4-5_fast, whose 0 passengers are loaded as 1), a
+    // vehicle of vt1 carries 50
+    let d = init_test_data_with(|instance| {
+        let vt2_only =
+            serde_json::json!([{"vehicleType": "vt2", "capacity": 1, "despawnCapacity": 10}]);
+        instance["depots"][2]["allowedTypes"] = vt2_only.clone();
+        instance["depots"][3]["allowedTypes"] = serde_json::json!([]);
+        instance["depots"][4]["allowedTypes"] = vt2_only;
+    });
+    let schedule = default_schedule(&d);
+    let (schedule_with_overflow, _) = schedule
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip34])
+        .unwrap();
+
+    // ACT
+    let uncovered = uncovered_demand(&schedule);
+    let uncovered_with_overflow = uncovered_demand(&schedule_with_overflow);
+
+    // ASSERT
+    // trip34, trip45, trip51 and trip14 are served by a single vehicle, trip45_fast is not served
+    assert_eq!(uncovered.passengers, 4 * 30 + 1);
+    assert_eq!(uncovered.total_passengers, 7 * 80 + 1);
+    assert_eq!(uncovered.per_vehicle_type.get(&d.vt1), Some(&121));
+    let mut expected_trips = vec![d.trip34, d.trip45, d.trip45_fast, d.trip51, d.trip14];
+    expected_trips.sort();
+    assert_eq!(uncovered.service_trips, expected_trips);
+    assert!((uncovered.share() - 121.0 / 561.0).abs() < 1e-9);
+    // the vehicle of the overflow depot covers trip34, but it does not exist in reality
+    assert!(schedule_with_overflow.is_fully_covered(d.trip34));
+    assert_eq!(uncovered_with_overflow, uncovered);
+}
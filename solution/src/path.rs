@@ -43,7 +43,11 @@ impl Path {
     pub fn new(node_sequence: Vec<NodeIdx>, nw: Arc<Network>) -> Result<Option<Path>, String> {
         for (&a, &b) in node_sequence.iter().tuple_windows() {
             if !nw.can_reach(a, b) {
-                return Err(format!("Not a valid Path: {} cannot reach {}.", a, b));
+                return Err(format!(
+                    "Not a valid Path: {} cannot reach {}.",
+                    nw.node(a),
+                    nw.node(b)
+                ));
             };
         }
         Ok(Path::new_trusted(node_sequence, nw))
@@ -107,12 +111,13 @@ impl Path {
     }
 }
 
+/// The ids of the nodes (see Tour).
 impl fmt::Display for Path {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut nodes_iter = self.node_sequence.iter();
-        write!(f, "{}", self.network.node(*nodes_iter.next().unwrap()))?;
+        write!(f, "{}", self.network.node(*nodes_iter.next().unwrap()).id())?;
         for node in nodes_iter {
-            write!(f, " - {}", self.network.node(*node))?;
+            write!(f, " - {}", self.network.node(*node).id())?;
         }
         Ok(())
     }
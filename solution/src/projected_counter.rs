@@ -0,0 +1,75 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use model::base_types::{Distance, VehicleIdx};
+
+/// The distances of the tour of a vehicle with respect to maintenance, as used for reconciling
+/// the schedule with the odometers of the fleet.
+/// A maintenance visit (a maintenance slot or an end depot providing maintenance) resets the
+/// counter of the vehicle. The counter is carried along the transition cycle of the vehicle, i.e.,
+/// the vehicle takes over the tour of its successor in the next period.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProjectedCounter {
+    vehicle: VehicleIdx,
+    successor: VehicleIdx,
+    total_distance: Distance,
+    maintenance_split: Option<(Distance, Distance)>,
+    handed_to_successor: Option<Distance>,
+}
+
+impl ProjectedCounter {
+    pub(crate) fn new(
+        vehicle: VehicleIdx,
+        successor: VehicleIdx,
+        total_distance: Distance,
+        maintenance_split: Option<(Distance, Distance)>,
+        handed_to_successor: Option<Distance>,
+    ) -> ProjectedCounter {
+        ProjectedCounter {
+            vehicle,
+            successor,
+            total_distance,
+            maintenance_split,
+            handed_to_successor,
+        }
+    }
+
+    pub fn vehicle(&self) -> VehicleIdx {
+        self.vehicle
+    }
+
+    /// The vehicle whose tour this vehicle takes over in the next period.
+    pub fn successor(&self) -> VehicleIdx {
+        self.successor
+    }
+
+    /// The expected total distance of the tour (service trips and dead-head trips).
+    pub fn total_distance(&self) -> Distance {
+        self.total_distance
+    }
+
+    /// The distance traveled before and after the first maintenance visit of the tour (None if
+    /// the tour visits no maintenance).
+    pub fn maintenance_split(&self) -> Option<(Distance, Distance)> {
+        self.maintenance_split
+    }
+
+    /// The counter (distance since the last maintenance visit) with which the vehicle starts the
+    /// tour of its successor, including the dead-head trip between the end depot and the start
+    /// depot of the successor.
+    /// None if the transition cycle contains no maintenance visit (the counter grows without
+    /// bound).
+    pub fn handed_to_successor(&self) -> Option<Distance> {
+        self.handed_to_successor
+    }
+}
@@ -11,6 +11,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+mod hints;
 mod modifications;
 #[cfg(test)]
 mod tests;
@@ -19,6 +20,7 @@ use itertools::Itertools;
 use model::base_types::Cost;
 use model::base_types::DepotIdx;
 use model::base_types::Distance;
+use model::base_types::Location;
 use model::base_types::MaintenanceCounter;
 use model::base_types::NodeIdx;
 use model::base_types::PassengerCount;
@@ -26,9 +28,11 @@ use model::base_types::VehicleCount;
 use model::base_types::VehicleIdx;
 use model::base_types::VehicleTypeIdx;
 use model::network::nodes::Node;
-use model::network::Network;
+use model::network::{Network, PlatformIdle};
 use model::vehicle_types::VehicleTypes;
 
+use crate::dead_head_leg::DeadHeadLeg;
+use crate::projected_counter::ProjectedCounter;
 use crate::tour::Tour;
 use crate::train_formation::TrainFormation;
 use crate::transition::Transition;
@@ -36,8 +40,11 @@ use crate::vehicle::Vehicle;
 
 use im::HashMap;
 use im::HashSet;
+use rapid_time::{DateTime, Duration};
 use std::cmp::Ordering;
 use std::collections::HashMap as StdHashMap;
+use std::collections::HashSet as StdHashSet;
+use std::fmt;
 use std::sync::Arc;
 
 type DepotUsage = HashMap<(DepotIdx, VehicleTypeIdx), (HashSet<VehicleIdx>, HashSet<VehicleIdx>)>;
@@ -74,7 +81,8 @@ pub struct Schedule {
     // include service trips that are fully covered.
     dummy_tours: HashMap<VehicleIdx, Tour>,
 
-    // counter for vehicle or dummy ids
+    // counter for vehicle or dummy ids. It never decreases, so an id is never reused (not even
+    // after the vehicle or dummy has been removed).
     vehicle_counter: usize,
 
     // redundant information for faster access
@@ -157,6 +165,126 @@ impl Schedule {
         self.maintenance_violation
     }
 
+    /// Returns the number of vehicles of the given type beyond its fixed fleet size (0 if the fleet
+    /// of this type is not fixed). These vehicles are spawned from the overflow depot.
+    pub fn fixed_fleet_violation_of(&self, vehicle_type: VehicleTypeIdx) -> VehicleCount {
+        self.network
+            .fixed_fleet_size_of(vehicle_type)
+            .map(|fleet_size| {
+                (self.vehicle_ids_grouped_and_sorted[&vehicle_type].len() as VehicleCount)
+                    .saturating_sub(fleet_size)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of vehicles beyond the fixed fleet sizes summed over all vehicle types.
+    pub fn fixed_fleet_violation(&self) -> VehicleCount {
+        self.network
+            .vehicle_types()
+            .iter()
+            .map(|vehicle_type| self.fixed_fleet_violation_of(vehicle_type))
+            .sum()
+    }
+
+    /// Returns the number of vehicles whose transition cycle contains no maintenance visit
+    /// (summed over all vehicle types, see Transition::cycles_without_maintenance).
+    pub fn number_of_vehicles_without_maintenance(&self) -> usize {
+        self.next_period_transitions
+            .values()
+            .map(|transition| transition.number_of_vehicles_without_maintenance(&self.tours))
+            .sum()
+    }
+
+    /// Returns the vehicles whose transition cycle contains no maintenance visit.
+    pub fn vehicles_without_maintenance(&self) -> impl Iterator<Item = VehicleIdx> + '_ {
+        self.next_period_transitions
+            .values()
+            .flat_map(|transition| transition.cycles_without_maintenance(&self.tours))
+            .flat_map(|transition_cycle| transition_cycle.iter())
+    }
+
+    /// Returns for each vehicle (in the order of vehicles_iter_all) the total distance of its
+    /// tour, the split at its first maintenance visit and the projected counter handed to its
+    /// transition successor (see ProjectedCounter).
+    pub fn projected_counters(&self) -> Vec<ProjectedCounter> {
+        let mut projected_counters: StdHashMap<VehicleIdx, ProjectedCounter> = StdHashMap::new();
+        for transition in self.next_period_transitions.values() {
+            for transition_cycle in transition.cycles_iter() {
+                let vehicles = transition_cycle.get_vec();
+                let tours: Vec<&Tour> = vehicles
+                    .iter()
+                    .map(|vehicle| self.tours.get(vehicle).unwrap())
+                    .collect();
+                // counter at the end of the tour if the tour resets it (None if it depends on
+                // the predecessor)
+                let reset_counters: Vec<Option<Distance>> = tours
+                    .iter()
+                    .map(|tour| {
+                        if tour.ends_at_maintenance_depot() {
+                            Some(Distance::ZERO)
+                        } else {
+                            tour.distances_to_maintenance_slots()
+                                .last()
+                                .map(|&distance| tour.total_distance().sub_max_zero(distance))
+                        }
+                    })
+                    .collect();
+                let transfer_distances: Vec<Distance> = tours
+                    .iter()
+                    .zip(tours.iter().cycle().skip(1))
+                    .map(|(tour, successor_tour)| {
+                        self.network.dead_head_distance_between(
+                            tour.end_depot().unwrap(),
+                            successor_tour.start_depot().unwrap(),
+                        )
+                    })
+                    .collect();
+
+                // walk once around the cycle starting right after the last tour resetting the
+                // counter
+                let mut handed_to_successor: Vec<Option<Distance>> = vec![None; vehicles.len()];
+                if let Some(last_reset) = reset_counters.iter().rposition(Option::is_some) {
+                    let mut counter =
+                        reset_counters[last_reset].unwrap() + transfer_distances[last_reset];
+                    for offset in 1..=vehicles.len() {
+                        let position = (last_reset + offset) % vehicles.len();
+                        counter = reset_counters[position]
+                            .unwrap_or(counter + tours[position].total_distance())
+                            + transfer_distances[position];
+                        handed_to_successor[position] = Some(counter);
+                    }
+                }
+
+                for (position, (vehicle, tour)) in vehicles.iter().zip(tours.iter()).enumerate() {
+                    let maintenance_split = if tour.visits_maintenance_slot() {
+                        tour.distances_to_maintenance_slots()
+                            .first()
+                            .map(|&distance| {
+                                (distance, tour.total_distance().sub_max_zero(distance))
+                            })
+                    } else if tour.ends_at_maintenance_depot() {
+                        Some((tour.total_distance(), Distance::ZERO))
+                    } else {
+                        None
+                    };
+                    projected_counters.insert(
+                        *vehicle,
+                        ProjectedCounter::new(
+                            *vehicle,
+                            vehicles[(position + 1) % vehicles.len()],
+                            tour.total_distance(),
+                            maintenance_split,
+                            handed_to_successor[position],
+                        ),
+                    );
+                }
+            }
+        }
+        self.vehicles_iter_all()
+            .map(|vehicle| projected_counters.remove(&vehicle).unwrap())
+            .collect()
+    }
+
     pub fn next_day_transition_of(&self, vehicle_type: VehicleTypeIdx) -> &Transition {
         self.next_period_transitions.get(&vehicle_type).unwrap()
     }
@@ -170,6 +298,79 @@ impl Schedule {
         new_schedule
     }
 
+    /// Replaces the next-period transitions of the given vehicle types by the given successor
+    /// mappings (vehicle -> vehicle that takes over its tour in the next period), tours stay
+    /// untouched. The mapping of each given type must be a permutation of the vehicles of that
+    /// type. Transitions of types that are not given are kept.
+    pub fn with_transition_overrides(
+        &self,
+        successors: &StdHashMap<VehicleTypeIdx, StdHashMap<VehicleIdx, VehicleIdx>>,
+    ) -> Result<Schedule, String> {
+        let mut new_next_period_transitions = self.next_period_transitions.clone();
+        for (&vehicle_type, successor_of) in successors.iter() {
+            if !self
+                .vehicle_ids_grouped_and_sorted
+                .contains_key(&vehicle_type)
+            {
+                return Err(format!("Unknown vehicle type {}", vehicle_type));
+            }
+            let vehicles: Vec<VehicleIdx> = self.vehicles_iter(vehicle_type).collect();
+            let vehicle_set: StdHashSet<VehicleIdx> = vehicles.iter().copied().collect();
+            for (vehicle, successor) in successor_of.iter() {
+                for v in [vehicle, successor] {
+                    if !vehicle_set.contains(v) {
+                        return Err(format!(
+                            "Vehicle {} is not a vehicle of type {}",
+                            v, vehicle_type
+                        ));
+                    }
+                }
+            }
+            if let Some(vehicle) = vehicles.iter().find(|v| !successor_of.contains_key(*v)) {
+                return Err(format!("Vehicle {} has no successor", vehicle));
+            }
+            let mut predecessor_of: StdHashMap<VehicleIdx, VehicleIdx> = StdHashMap::new();
+            for (&vehicle, &successor) in successor_of.iter() {
+                if let Some(other) = predecessor_of.insert(successor, vehicle) {
+                    return Err(format!(
+                        "Vehicle {} is the successor of both {} and {}",
+                        successor,
+                        vehicle.min(other),
+                        vehicle.max(other)
+                    ));
+                }
+            }
+
+            // decompose the permutation into its cycles
+            let mut visited: StdHashSet<VehicleIdx> = StdHashSet::new();
+            let mut cycles: Vec<Vec<VehicleIdx>> = Vec::new();
+            for &first in vehicles.iter() {
+                let mut cycle = Vec::new();
+                let mut vehicle = first;
+                while visited.insert(vehicle) {
+                    cycle.push(vehicle);
+                    vehicle = successor_of[&vehicle];
+                }
+                if !cycle.is_empty() {
+                    cycles.push(cycle);
+                }
+            }
+
+            new_next_period_transitions.insert(
+                vehicle_type,
+                Transition::from_cycles(cycles, &self.tours, &self.network),
+            );
+        }
+
+        let mut new_schedule = self.clone();
+        new_schedule.maintenance_violation = new_next_period_transitions
+            .values()
+            .map(|transition| transition.maintenance_violation())
+            .sum();
+        new_schedule.next_period_transitions = new_next_period_transitions;
+        Ok(new_schedule)
+    }
+
     pub fn train_formation_of(&self, node: NodeIdx) -> &TrainFormation {
         self.train_formations.get(&node).unwrap()
     }
@@ -191,6 +392,23 @@ impl Schedule {
         self.number_of_vehicles_spawned_at_custom_usage(depot, &self.depot_usage)
     }
 
+    /// Returns the number of vehicles of the given type that are despawned at the given depot
+    pub fn number_of_vehicles_of_same_type_despawned_at(
+        &self,
+        depot: DepotIdx,
+        vehicle_type: VehicleTypeIdx,
+    ) -> VehicleCount {
+        self.number_of_vehicles_of_same_type_despawned_at_custom_usage(
+            depot,
+            vehicle_type,
+            &self.depot_usage,
+        )
+    }
+
+    pub fn number_of_vehicles_despawned_at(&self, depot: DepotIdx) -> VehicleCount {
+        self.number_of_vehicles_despawned_at_custom_usage(depot, &self.depot_usage)
+    }
+
     /// Returns the number of vehicles of the given type that are spawned at the given depot - the
     /// number of vehicles of the given type that despawn at the given depot.
     /// Hence, negative values mean that there are more vehicles despawning than spawning.
@@ -218,6 +436,14 @@ impl Schedule {
         self.can_depot_spawn_vehicle_custom_usage(start_depot, vehicle_type, &self.depot_usage)
     }
 
+    pub fn can_depot_despawn_vehicle(
+        &self,
+        end_depot: NodeIdx,
+        vehicle_type: VehicleTypeIdx,
+    ) -> bool {
+        self.can_depot_despawn_vehicle_custom_usage(end_depot, vehicle_type, &self.depot_usage)
+    }
+
     pub fn reduces_spawning_at_depot_violation(
         &self,
         vehicle_type: VehicleTypeIdx,
@@ -250,14 +476,398 @@ impl Schedule {
         self.unserved_passengers
     }
 
+    /// Returns the unserved passengers (see unserved_passengers_at) summed over all service trips
+    /// of the given vehicle type.
+    pub fn unserved_passengers_of_type(
+        &self,
+        vehicle_type: VehicleTypeIdx,
+    ) -> (PassengerCount, PassengerCount) {
+        self.network
+            .service_nodes(vehicle_type)
+            .fold((0, 0), |(unserved, seated), service_trip| {
+                let (unserved_trip, seated_trip) = self.unserved_passengers_at(service_trip);
+                (unserved + unserved_trip, seated + seated_trip)
+            })
+    }
+
     pub fn is_fully_covered(&self, service_trip: NodeIdx) -> bool {
         self.unserved_passengers_at(service_trip) == (0, 0)
     }
 
+    /// Returns the number of vehicles of the train formation of the service trip that are not
+    /// needed to serve the passenger demand (vehicles that only reposition).
+    /// These are always the last vehicles of the formation (tail-first).
+    /// If the demand is not fully covered, there are no hitch-hikers.
+    /// Vehicles needed for the minimal formation count of the trip are no hitch-hikers either.
+    pub fn hitch_hikers_at(&self, service_trip: NodeIdx) -> VehicleCount {
+        let train_formation = self.train_formation_of(service_trip);
+        let surplus = train_formation.surplus_vehicle_count(
+            self.network.passengers_of(service_trip),
+            self.network.seated_passengers_of(service_trip),
+        );
+        match self.network.minimal_formation_count_for(service_trip) {
+            Some(minimal) => surplus.min(train_formation.vehicle_count().saturating_sub(minimal)),
+            None => surplus,
+        }
+    }
+
+    /// Returns the total number of hitch-hiking vehicles over all service trips.
+    pub fn count_hitch_hikers(&self) -> VehicleCount {
+        self.network
+            .all_service_nodes()
+            .map(|service_trip| self.hitch_hikers_at(service_trip))
+            .sum()
+    }
+
+    /// Returns the total time (in seconds) vehicles hitch-hike on service trips, i.e., the
+    /// duration of each service trip multiplied by its number of hitch-hikers.
+    pub fn hitch_hiking_seconds(&self) -> Cost {
+        self.network
+            .all_service_nodes()
+            .map(|service_trip| {
+                self.hitch_hikers_at(service_trip) as Cost
+                    * self.network.node(service_trip).duration().in_sec().unwrap()
+            })
+            .sum()
+    }
+
+    /// Returns the number of vehicles missing in the train formation of the service trip to
+    /// reach its minimal formation count (0 if the trip has no minimal formation count).
+    pub fn formation_shortfall_at(&self, service_trip: NodeIdx) -> VehicleCount {
+        self.network
+            .minimal_formation_count_for(service_trip)
+            .map(|minimal| {
+                minimal.saturating_sub(self.train_formation_of(service_trip).vehicle_count())
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns the total number of missing vehicles over all service trips with a minimal
+    /// formation count.
+    pub fn formation_shortfall(&self) -> VehicleCount {
+        self.network
+            .service_nodes_with_minimal_formation_count()
+            .map(|service_trip| self.formation_shortfall_at(service_trip))
+            .sum()
+    }
+
+    /// Returns the service trips (sorted by start time) whose train formation is shorter than
+    /// their minimal formation count.
+    pub fn service_trips_below_minimal_formation_count(
+        &self,
+    ) -> impl Iterator<Item = NodeIdx> + '_ {
+        self.network
+            .service_nodes_with_minimal_formation_count()
+            .filter(|&service_trip| self.formation_shortfall_at(service_trip) > 0)
+    }
+
+    /// Returns the nodes (with their vehicle, grouped by vehicle) overlapping an out-of-service
+    /// window of the vehicle type (see Network::out_of_service_window_of). Only possible if
+    /// parameters.outOfService.hard is not set.
+    pub fn out_of_service_violations(&self) -> Vec<(VehicleIdx, NodeIdx)> {
+        self.vehicles_iter_all()
+            .flat_map(|vehicle| {
+                let vehicle_type = self.vehicle_type_of(vehicle).unwrap();
+                self.tour_of(vehicle)
+                    .unwrap()
+                    .all_nodes_iter()
+                    .filter(move |&node| {
+                        self.network
+                            .out_of_service_window_of(node, vehicle_type)
+                            .is_some()
+                    })
+                    .map(move |node| (vehicle, node))
+            })
+            .collect()
+    }
+
+    /// true iff the vehicle type has vehicles and all of them start at the overflow depot (e.g.,
+    /// because no regular depot can spawn the type, see Network::vehicle_types_without_depot).
+    pub fn uses_only_overflow_depot(&self, vehicle_type: VehicleTypeIdx) -> bool {
+        let overflow_start_depot = self.network.overflow_depot_idxs().1;
+        let mut vehicles = self.vehicles_iter(vehicle_type).peekable();
+        vehicles.peek().is_some()
+            && vehicles
+                .all(|vehicle| self.tour_of(vehicle).unwrap().first_node() == overflow_start_depot)
+    }
+
+    /// Number of vehicles of the type in service over time: a vehicle is in service from the start
+    /// of its first non-depot node until the end of its last non-depot node (spares, i.e.,
+    /// vehicles that are not spawned, are not counted). Returned as a step function: each entry
+    /// (time, count) holds from time until the next entry, before the first entry no vehicle is in
+    /// service.
+    pub fn in_service_curve(&self, vehicle_type: VehicleTypeIdx) -> Vec<(DateTime, VehicleCount)> {
+        let mut changes: Vec<(DateTime, i64)> = Vec::new();
+        for vehicle in self.vehicles_iter(vehicle_type) {
+            let tour = self.tour_of(vehicle).unwrap();
+            if let (Some(first), Some(last)) = (tour.first_non_depot(), tour.last_non_depot()) {
+                changes.push((self.network.node(first).start_time(), 1));
+                changes.push((self.network.node(last).end_time(), -1));
+            }
+        }
+        changes.sort();
+        let mut curve: Vec<(DateTime, VehicleCount)> = Vec::new();
+        let mut count: i64 = 0;
+        for (time, change) in changes {
+            count += change;
+            match curve.last_mut() {
+                Some((last_time, last_count)) if *last_time == time => {
+                    *last_count = count.max(0) as VehicleCount
+                }
+                _ => curve.push((time, count.max(0) as VehicleCount)),
+            }
+        }
+        curve
+    }
+
+    /// The in-service curve (see in_service_curve) restricted to the window [start, end): the
+    /// count at start followed by all changes within the window.
+    pub fn in_service_curve_within(
+        &self,
+        vehicle_type: VehicleTypeIdx,
+        start: DateTime,
+        end: DateTime,
+    ) -> Vec<(DateTime, VehicleCount)> {
+        let curve = self.in_service_curve(vehicle_type);
+        let count_at_start = curve
+            .iter()
+            .take_while(|(time, _)| *time <= start)
+            .last()
+            .map_or(0, |&(_, count)| count);
+        std::iter::once((start, count_at_start))
+            .chain(
+                curve
+                    .into_iter()
+                    .filter(|&(time, _)| start < time && time < end),
+            )
+            .collect()
+    }
+
+    /// The largest number of vehicles of the type in service at the same time, together with the
+    /// first time it is reached (None if no vehicle of the type is in service).
+    pub fn peak_in_service(
+        &self,
+        vehicle_type: VehicleTypeIdx,
+    ) -> (VehicleCount, Option<DateTime>) {
+        self.in_service_curve(vehicle_type)
+            .into_iter()
+            .fold((0, None), |peak, (time, count)| {
+                if count > peak.0 {
+                    (count, Some(time))
+                } else {
+                    peak
+                }
+            })
+    }
+
+    /// Number of vehicles missing to reach the minimum-in-service count of the vehicle type
+    /// within the window (the maximum over the window).
+    pub fn in_service_shortfall_within(
+        &self,
+        vehicle_type: VehicleTypeIdx,
+        start: DateTime,
+        end: DateTime,
+        required: VehicleCount,
+    ) -> VehicleCount {
+        self.in_service_curve_within(vehicle_type, start, end)
+            .into_iter()
+            .map(|(_, count)| required.saturating_sub(count))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Sum over all minimum-in-service requirements of all vehicle types of their shortfall (see
+    /// in_service_shortfall_within).
+    pub fn minimum_in_service_shortfall(&self) -> VehicleCount {
+        let vehicle_types = self.network.vehicle_types();
+        vehicle_types
+            .iter()
+            .flat_map(|vehicle_type| {
+                vehicle_types
+                    .minimum_in_service_of(vehicle_type)
+                    .iter()
+                    .map(move |&(start, end, required)| {
+                        self.in_service_shortfall_within(vehicle_type, start, end, required)
+                    })
+            })
+            .sum()
+    }
+
+    /// Returns the number of distinct vehicles appearing in the train formations of the given
+    /// service trips (e.g., all trips of a route).
+    pub fn distinct_vehicle_count(&self, service_trips: &[NodeIdx]) -> VehicleCount {
+        service_trips
+            .iter()
+            .flat_map(|&service_trip| self.train_formation_of(service_trip).iter())
+            .map(|vehicle| vehicle.idx())
+            .unique()
+            .count() as VehicleCount
+    }
+
+    /// Returns the number of distinct vehicles serving a route summed over all routes. The
+    /// smaller this value the more line-dedicated the fleet is.
+    pub fn line_purity(&self) -> VehicleCount {
+        self.network
+            .service_nodes_by_route()
+            .map(|(_, service_trips)| self.distinct_vehicle_count(service_trips))
+            .sum()
+    }
+
+    /// Transitions between two consecutive service trips of a real vehicle's tour where the
+    /// vehicle is an inner unit of the first formation but the lead unit of the second one. Such a
+    /// transition is not possible without an intermediate shunting move. Returned as (vehicle,
+    /// first service trip, second service trip).
+    pub fn formation_order_shunts(&self) -> Vec<(VehicleIdx, NodeIdx, NodeIdx)> {
+        self.vehicles_iter_all()
+            .flat_map(|vehicle| {
+                self.tour_of(vehicle)
+                    .unwrap()
+                    .all_non_depot_nodes_iter()
+                    .tuple_windows()
+                    .filter(move |&(first, second)| {
+                        self.network.node(first).is_service()
+                            && self.network.node(second).is_service()
+                            && self.train_formation_of(first).is_inner_position(vehicle)
+                            && self.train_formation_of(second).position_of(vehicle) == Some(0)
+                    })
+                    .map(move |(first, second)| (vehicle, first, second))
+            })
+            .collect()
+    }
+
+    /// Pairs (before, after) of service trips meeting at an allowed split point (see
+    /// Network::split_successor) that are served by different formations, i.e., where the
+    /// departure is split (or joined).
+    pub fn splits(&self) -> Vec<(NodeIdx, NodeIdx)> {
+        self.network
+            .split_points()
+            .filter(|&(before, after)| {
+                !self
+                    .train_formation_of(before)
+                    .ids()
+                    .into_iter()
+                    .sorted()
+                    .eq(self.train_formation_of(after).ids().into_iter().sorted())
+            })
+            .collect()
+    }
+
+    /// Idle times of real vehicles exceeding the platform idle limit of their location (see
+    /// Network::platform_idle_between), returned as (vehicle, node before the idle time, node after
+    /// the idle time, how the idle time is spent). Ordered by vehicle and time.
+    pub fn platform_idles(&self) -> Vec<(VehicleIdx, NodeIdx, NodeIdx, PlatformIdle)> {
+        if !self.network.locations().has_platform_idle_limits() {
+            return Vec::new();
+        }
+        self.vehicles_iter_all()
+            .flat_map(|vehicle| {
+                self.tour_of(vehicle)
+                    .unwrap()
+                    .all_nodes_iter()
+                    .tuple_windows()
+                    .filter_map(move |(node1, node2)| {
+                        self.network
+                            .platform_idle_between(node1, node2)
+                            .map(|platform_idle| (vehicle, node1, node2, platform_idle))
+                    })
+            })
+            .collect()
+    }
+
+    /// Number of idle times exceeding the platform idle limit without a reachable siding.
+    pub fn platform_idle_violations(&self) -> usize {
+        self.platform_idles()
+            .iter()
+            .filter(|(_, _, _, platform_idle)| *platform_idle == PlatformIdle::Violation)
+            .count()
+    }
+
+    /// All dead-head trips of real vehicles (ordered by vehicle and departure).
+    pub fn dead_head_legs(&self) -> Vec<DeadHeadLeg> {
+        self.vehicles_iter_all()
+            .flat_map(|vehicle| {
+                self.tour_of(vehicle)
+                    .unwrap()
+                    .all_nodes_iter()
+                    .tuple_windows()
+                    .filter_map(move |(node1, node2)| {
+                        DeadHeadLeg::between(vehicle, node1, node2, &self.network)
+                    })
+            })
+            .collect()
+    }
+
+    /// Pairs of dead-head trips of different vehicles with the same origin and destination whose
+    /// departures differ by at most the tolerance. Such vehicles could be coupled and run as one
+    /// movement. Each dead-head trip is part of at most one pair, as many pairs as possible are
+    /// built. Ordered by the departure of the first dead-head trip. Synthetic dead-head trips (from
+    /// or to the overflow depot) are never paired.
+    pub fn dead_head_pairs(&self, tolerance: Duration) -> Vec<(DeadHeadLeg, DeadHeadLeg)> {
+        let mut legs_by_locations: StdHashMap<(Location, Location), Vec<DeadHeadLeg>> =
+            StdHashMap::new();
+        for leg in self
+            .dead_head_legs()
+            .into_iter()
+            .filter(|leg| !leg.is_synthetic())
+        {
+            legs_by_locations
+                .entry((leg.origin(), leg.destination()))
+                .or_default()
+                .push(leg);
+        }
+
+        let mut pairs = Vec::new();
+        for mut legs in legs_by_locations.into_values() {
+            legs.sort_by_key(|leg| (leg.departure(), leg.vehicle()));
+            // pairing neighbors greedily is optimal as the departures are sorted
+            let mut i = 0;
+            while i + 1 < legs.len() {
+                let (first, second) = (legs[i], legs[i + 1]);
+                if first.vehicle() != second.vehicle()
+                    && second.departure() - first.departure() <= tolerance
+                {
+                    pairs.push((first, second));
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        pairs.sort_by_key(|(first, _)| (first.departure(), first.vehicle()));
+        pairs
+    }
+
     pub fn costs(&self) -> Cost {
         self.costs
     }
 
+    /// Compares the incrementally updated costs of the schedule and of each real tour with a
+    /// recomputation from scratch. Linear in the number of nodes, so it is cheap enough to be run
+    /// after each local search step (see `checkCosts` in the parameters).
+    pub fn check_costs(&self) -> Result<(), String> {
+        let mut recomputed_costs =
+            self.network.number_of_service_nodes() as Cost * self.network.config().costs.staff;
+        for (vehicle, tour) in self.tours.iter() {
+            let recomputed_tour_costs = tour.compute_costs();
+            if tour.costs() != recomputed_tour_costs {
+                return Err(format!(
+                    "Costs of tour of {} are {} but recomputation gives {}.",
+                    vehicle,
+                    tour.costs(),
+                    recomputed_tour_costs
+                ));
+            }
+            recomputed_costs += recomputed_tour_costs;
+        }
+        if self.costs != recomputed_costs {
+            return Err(format!(
+                "Costs of schedule are {} but recomputation gives {}.",
+                self.costs, recomputed_costs
+            ));
+        }
+        Ok(())
+    }
+
     pub fn print_tours_long(&self) {
         println!(
             "** schedule with {} tours and {} dummy-tours:",
@@ -274,6 +884,9 @@ impl Schedule {
         }
     }
 
+    /// Dead-head distance of all real vehicles, including the synthetic legs from and to the
+    /// overflow depot (which are infinite). The costs and the maintenance counters are based on
+    /// the same distances, so the usage of the overflow depot is penalized in the objective.
     pub fn total_dead_head_distance(&self) -> Distance {
         self.tours
             .values()
@@ -281,6 +894,100 @@ impl Schedule {
             .sum()
     }
 
+    /// Number of dead-head trips of all real vehicles (see Tour::dead_head_trip_count).
+    pub fn dead_head_trip_count(&self) -> usize {
+        self.tours
+            .values()
+            .map(|tour| tour.dead_head_trip_count())
+            .sum()
+    }
+
+    /// For each vehicle type, the measure of each tour of its real vehicles (in no particular
+    /// order). Statistics that need several measures should take them in one call, so that the
+    /// tours are traversed only once.
+    pub fn tour_measures_per_vehicle_type<T>(
+        &self,
+        measure: impl Fn(&Tour) -> T,
+    ) -> StdHashMap<VehicleTypeIdx, Vec<T>> {
+        let mut measures: StdHashMap<VehicleTypeIdx, Vec<T>> = StdHashMap::new();
+        for (vehicle, tour) in self.tours.iter() {
+            measures
+                .entry(self.vehicles.get(vehicle).unwrap().type_idx())
+                .or_default()
+                .push(measure(tour));
+        }
+        measures
+    }
+
+    /// The duty lengths (see Tour::useful_duration) of the real vehicles per vehicle type.
+    pub fn duty_lengths_per_vehicle_type(&self) -> StdHashMap<VehicleTypeIdx, Vec<Duration>> {
+        self.tour_measures_per_vehicle_type(|tour| tour.useful_duration())
+    }
+
+    /// Sum over all vehicle types of the variance of the duty lengths of its vehicles (in
+    /// minutes², rounded). Crews prefer tours of similar lengths.
+    pub fn duty_length_variance(&self) -> Cost {
+        self.duty_lengths_per_vehicle_type()
+            .values()
+            .map(|duty_lengths| Schedule::variance_in_minutes(duty_lengths))
+            .sum::<f64>()
+            .round() as Cost
+    }
+
+    /// Population variance of the given durations in minutes² (0 for less than two durations).
+    pub fn variance_in_minutes(durations: &[Duration]) -> f64 {
+        if durations.len() < 2 {
+            return 0.0;
+        }
+        let minutes: Vec<f64> = durations
+            .iter()
+            .map(|duration| duration.in_sec().unwrap() as f64 / 60.0)
+            .collect();
+        let mean = minutes.iter().sum::<f64>() / minutes.len() as f64;
+        minutes
+            .iter()
+            .map(|minute| (minute - mean) * (minute - mean))
+            .sum::<f64>()
+            / minutes.len() as f64
+    }
+
+    /// Dead-head distance of all real vehicles without the synthetic legs from and to the overflow
+    /// depot. Only used for reporting, the objective is not affected.
+    pub fn dead_head_distance_without_synthetic_legs(&self) -> Distance {
+        self.dead_head_distance_of_legs(false)
+    }
+
+    /// Dead-head distance of the synthetic legs from and to the overflow depot (infinite as soon
+    /// as one vehicle uses the overflow depot).
+    pub fn synthetic_dead_head_distance(&self) -> Distance {
+        self.dead_head_distance_of_legs(true)
+    }
+
+    /// Real vehicles that start or end at the overflow depot, i.e., vehicles that could not be
+    /// placed at a regular depot (same order as vehicles_iter_all).
+    pub fn vehicles_using_overflow_depot(&self) -> Vec<VehicleIdx> {
+        self.vehicles_iter_all()
+            .filter(|&vehicle| {
+                let tour = self.tour_of(vehicle).unwrap();
+                self.network.is_overflow_depot_node(tour.first_node())
+                    || self.network.is_overflow_depot_node(tour.last_node())
+            })
+            .collect()
+    }
+
+    fn dead_head_distance_of_legs(&self, synthetic: bool) -> Distance {
+        self.tours
+            .values()
+            .flat_map(|tour| tour.all_nodes_iter().tuple_windows())
+            .filter(|&(node1, node2)| {
+                (self.network.is_overflow_depot_node(node1)
+                    || self.network.is_overflow_depot_node(node2))
+                    == synthetic
+            })
+            .map(|(node1, node2)| self.network.dead_head_distance_between(node1, node2))
+            .sum()
+    }
+
     pub fn print_tours(&self) {
         for vehicle in self.vehicles_iter_all() {
             println!(
@@ -319,6 +1026,9 @@ impl Schedule {
 
     pub fn print_next_day_transitions(&self) {
         for (vehicle_type, transition) in self.next_period_transitions.iter() {
+            if self.vehicles_iter(*vehicle_type).next().is_none() {
+                continue;
+            }
             println!(
                 "\nNextDayTransitions for {}:",
                 self.network.vehicle_types().get(*vehicle_type).unwrap()
@@ -386,6 +1096,18 @@ impl Schedule {
             assert!(dummy1 < dummy2);
         }
 
+        // check that ids are unique, i.e., taken from the vehicle_counter and not shared between
+        // a vehicle and a dummy
+        let ids: Vec<_> = vehicles
+            .iter()
+            .chain(dummy_vehicles.iter())
+            .map(|vehicle| vehicle.idx())
+            .collect();
+        assert_eq!(ids.iter().unique().count(), ids.len());
+        for id in ids {
+            assert!((id as usize) < self.vehicle_counter);
+        }
+
         // check tours
         for vehicle in self.vehicles.keys() {
             let tour = self.tours.get(vehicle).unwrap();
@@ -426,6 +1148,22 @@ impl Schedule {
                 if let Some(maximal_formation_count) = maximal_formation_count_opt {
                     assert!(train_formation.vehicle_count() <= maximal_formation_count,);
                 }
+                if self.network.node(node).is_service() {
+                    for vehicle_type in self.network.vehicle_types().iter() {
+                        if let Some(maximal_formation_count) = self
+                            .network
+                            .vehicle_types()
+                            .get(vehicle_type)
+                            .unwrap()
+                            .maximal_formation_count()
+                        {
+                            assert!(
+                                train_formation.vehicle_count_of_type(vehicle_type)
+                                    <= maximal_formation_count
+                            );
+                        }
+                    }
+                }
             }
 
             // check depots usage
@@ -542,6 +1280,20 @@ impl Schedule {
             assert!(total_spawned <= total_capacity,);
         }
 
+        // check if depot despawning limits are respected
+        for (depot, vehicle_type) in self.depot_usage.keys().cloned() {
+            let number_of_despawned_vehicles =
+                self.number_of_vehicles_of_same_type_despawned_at(depot, vehicle_type);
+            let despawn_capacity = self.network.despawn_capacity_of(depot, vehicle_type);
+            assert!(number_of_despawned_vehicles <= despawn_capacity);
+        }
+
+        for depot in self.network.depots_iter() {
+            let total_despawn_capacity = self.network.total_despawn_capacity_of(depot);
+            let total_despawned = self.number_of_vehicles_despawned_at(depot);
+            assert!(total_despawned <= total_despawn_capacity);
+        }
+
         println!("Debug only: Schedule is consistent");
     }
 }
@@ -608,6 +1360,12 @@ impl PartialEq for Schedule {
 
 impl Eq for Schedule {}
 
+impl fmt::Debug for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Schedule with {} vehicles", self.number_of_vehicles())
+    }
+}
+
 // static methods
 impl Schedule {
     /// initializing an empty schedule
@@ -736,9 +1494,68 @@ impl Schedule {
             return false;
         }
 
+        // if the fleet is fixed, only the overflow depot can spawn vehicles beyond the fleet size
+        if let Some(fleet_size) = self.network.fixed_fleet_size_of(vehicle_type) {
+            if depot != self.network.overflow_depot_idxs().0
+                && self.number_of_vehicles_of_type_spawned_at_regular_depots_custom_usage(
+                    vehicle_type,
+                    depot_usage,
+                ) >= fleet_size
+            {
+                return false;
+            }
+        }
+
         true
     }
 
+    /// The fixed fleet does not need to be considered here, as every despawned vehicle has been
+    /// spawned before.
+    fn can_depot_despawn_vehicle_custom_usage(
+        &self,
+        end_depot: NodeIdx,
+        vehicle_type: VehicleTypeIdx,
+        depot_usage: &DepotUsage,
+    ) -> bool {
+        let depot = self.network.get_depot_idx(end_depot);
+        let despawn_capacity_for_type = self.network.despawn_capacity_of(depot, vehicle_type);
+
+        if despawn_capacity_for_type == 0 {
+            return false;
+        }
+
+        if self.number_of_vehicles_of_same_type_despawned_at_custom_usage(
+            depot,
+            vehicle_type,
+            depot_usage,
+        ) >= despawn_capacity_for_type
+        {
+            return false;
+        }
+
+        self.number_of_vehicles_despawned_at_custom_usage(depot, depot_usage)
+            < self.network.total_despawn_capacity_of(depot)
+    }
+
+    fn number_of_vehicles_of_type_spawned_at_regular_depots_custom_usage(
+        &self,
+        vehicle_type: VehicleTypeIdx,
+        depot_usage: &DepotUsage,
+    ) -> VehicleCount {
+        let overflow_depot = self.network.overflow_depot_idxs().0;
+        self.network
+            .depots_iter()
+            .filter(|&depot| depot != overflow_depot)
+            .map(|depot| {
+                self.number_of_vehicles_of_same_type_spawned_at_custom_usage(
+                    depot,
+                    vehicle_type,
+                    depot_usage,
+                )
+            })
+            .sum()
+    }
+
     fn number_of_vehicles_of_same_type_spawned_at_custom_usage(
         &self,
         depot: DepotIdx,
@@ -768,6 +1585,35 @@ impl Schedule {
             .sum()
     }
 
+    fn number_of_vehicles_of_same_type_despawned_at_custom_usage(
+        &self,
+        depot: DepotIdx,
+        vehicle_type: VehicleTypeIdx,
+        depot_usage: &DepotUsage,
+    ) -> VehicleCount {
+        depot_usage
+            .get(&(depot, vehicle_type))
+            .map(|(_, despawned)| despawned.len())
+            .unwrap_or(0) as VehicleCount
+    }
+
+    fn number_of_vehicles_despawned_at_custom_usage(
+        &self,
+        depot: DepotIdx,
+        depot_usage: &DepotUsage,
+    ) -> VehicleCount {
+        self.network
+            .vehicle_types()
+            .iter()
+            .map(|vt| {
+                depot_usage
+                    .get(&(depot, vt))
+                    .map(|(_, despawned)| despawned.len() as VehicleCount)
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
     fn compute_unserved_passengers_at_node(
         network: &Network,
         node: NodeIdx,
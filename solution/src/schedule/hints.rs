@@ -0,0 +1,154 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use model::base_types::{DepotIdx, NodeIdx, VehicleIdx};
+use model::config::Hint;
+
+use crate::segment::Segment;
+
+use super::Schedule;
+
+impl Schedule {
+    /// Applies the hints one after another, each on the result of the previous ones. A failing
+    /// hint leaves the schedule unchanged. For each hint, the reason is returned if it failed.
+    pub fn apply_hints(&self, hints: &[Hint]) -> (Schedule, Vec<Result<(), String>>) {
+        let mut schedule = self.clone();
+        let mut outcomes = Vec::new();
+        for hint in hints.iter() {
+            match schedule.apply_hint(hint) {
+                Ok(new_schedule) => {
+                    schedule = new_schedule;
+                    outcomes.push(Ok(()));
+                }
+                Err(reason) => outcomes.push(Err(reason)),
+            }
+        }
+        (schedule, outcomes)
+    }
+
+    /// Applies a move suggested by the user (see model::config::Hint).
+    /// - MergeTours: all activities of vehicle are fit into the tour of into_vehicle, which fails if
+    ///   some of them conflict with it.
+    /// - MoveTrip: the trip is fit into the tour of to_vehicle. It is taken from the lead vehicle
+    ///   of its train formation (or from its dummy tour if it is unserved).
+    /// - AssignDepot: see assign_depot.
+    /// # Errors
+    /// If an id is unknown or the move is not possible in this schedule, an error is returned.
+    pub fn apply_hint(&self, hint: &Hint) -> Result<Schedule, String> {
+        match hint {
+            Hint::MergeTours {
+                vehicle,
+                into_vehicle,
+            } => self.merge_tours(
+                self.vehicle_by_id(vehicle)?,
+                self.vehicle_by_id(into_vehicle)?,
+            ),
+            Hint::MoveTrip { trip, to_vehicle } => self.move_trip(
+                self.service_trip_by_id(trip)?,
+                self.vehicle_by_id(to_vehicle)?,
+            ),
+            Hint::AssignDepot { vehicle, depot } => {
+                self.assign_depot(self.vehicle_by_id(vehicle)?, self.depot_by_id(depot)?)
+            }
+        }
+    }
+}
+
+// private methods
+impl Schedule {
+    fn merge_tours(&self, provider: VehicleIdx, receiver: VehicleIdx) -> Result<Schedule, String> {
+        if provider == receiver {
+            return Err(format!(
+                "Cannot merge the tour of vehicle {} into itself.",
+                provider
+            ));
+        }
+        let tour = self.tour_of(provider)?;
+        let segment = Segment::new(
+            tour.first_non_depot().unwrap(),
+            tour.last_non_depot().unwrap(),
+        );
+        let new_schedule = self.fit_reassign(segment, provider, receiver)?;
+        if new_schedule.is_vehicle(provider) {
+            return Err(format!(
+                "Cannot merge the tour of vehicle {} into the tour of vehicle {}. {} activities conflict.",
+                provider,
+                receiver,
+                new_schedule
+                    .tour_of(provider)?
+                    .all_non_depot_nodes_iter()
+                    .count()
+            ));
+        }
+        Ok(new_schedule)
+    }
+
+    fn move_trip(&self, trip: NodeIdx, receiver: VehicleIdx) -> Result<Schedule, String> {
+        let trip_id = self.network.node(trip).id();
+        let formation = self.train_formation_of(trip).ids();
+        if formation.contains(&receiver) {
+            return Err(format!(
+                "Vehicle {} already serves trip {}.",
+                receiver, trip_id
+            ));
+        }
+        let provider = formation
+            .first()
+            .copied()
+            .or_else(|| {
+                self.dummy_iter().find(|&dummy| {
+                    self.tour_of(dummy)
+                        .unwrap()
+                        .all_nodes_iter()
+                        .any(|node| node == trip)
+                })
+            })
+            .ok_or(format!(
+                "Trip {} is neither served by a vehicle nor part of a dummy tour.",
+                trip_id
+            ))?;
+        let new_schedule = self.fit_reassign(Segment::new(trip, trip), provider, receiver)?;
+        if !new_schedule
+            .train_formation_of(trip)
+            .ids()
+            .contains(&receiver)
+        {
+            return Err(format!(
+                "Cannot move trip {} to vehicle {}. The trip conflicts with the tour of the vehicle.",
+                trip_id, receiver
+            ));
+        }
+        Ok(new_schedule)
+    }
+
+    /// Vehicles are referred to by their ids in the output (e.g., "veh_3").
+    fn vehicle_by_id(&self, id: &str) -> Result<VehicleIdx, String> {
+        self.vehicles_iter_all()
+            .find(|vehicle| vehicle.to_string() == id)
+            .ok_or(format!("Unknown vehicle {}.", id))
+    }
+
+    fn service_trip_by_id(&self, id: &str) -> Result<NodeIdx, String> {
+        self.network
+            .all_service_nodes()
+            .find(|&node| self.network.node(node).id() == id)
+            .ok_or(format!("Unknown trip {}.", id))
+    }
+
+    fn depot_by_id(&self, id: &str) -> Result<DepotIdx, String> {
+        self.network
+            .depots_iter()
+            .find(|&depot| self.network.get_depot(depot).id() == id)
+            .ok_or(format!("Unknown depot {}.", id))
+    }
+}
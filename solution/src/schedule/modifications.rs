@@ -14,8 +14,10 @@
 use im::{HashMap, HashSet};
 use itertools::Itertools;
 use model::base_types::{
-    Cost, Idx, MaintenanceCounter, NodeIdx, PassengerCount, VehicleIdx, VehicleTypeIdx,
+    Cost, DepotIdx, Distance, Idx, MaintenanceCounter, NodeIdx, PassengerCount, VehicleIdx,
+    VehicleTypeIdx,
 };
+use model::config::DepotAssignmentPolicy;
 
 use crate::{
     path::Path, segment::Segment, tour::Tour, train_formation::TrainFormation,
@@ -57,9 +59,11 @@ impl Schedule {
     ///
     /// If path does not start with a depot the vehicle is spawned from the nearest availabe depot
     /// (from the start location of the first trip).
-    /// Similarly, if path does not end with a depot the vehicle is spawned to the nearest depot
-    /// (from the end location of the last trip).
-    /// If the depot given in the path is not available, spawn vehicle from overflow depot instead.
+    /// Similarly, if path does not end with a depot the vehicle is despawned at the nearest depot
+    /// with despawn capacity left (from the end location of the last trip).
+    /// If the start depot given in the path is not available, spawn vehicle from overflow depot
+    /// instead. If the end depot given in the path cannot despawn the vehicle, the nearest one that
+    /// can is used.
     ///
     /// # Errors
     /// If no depot is available, an error is returned (should not be possible as there is always
@@ -77,8 +81,12 @@ impl Schedule {
                 .compatible_with_vehicle_type(*n, vehicle_type_idx)
         }) {
             return Err(format!(
-                "Cannot spawn vehicle for path {:?}. Nodes are not compatible with vehicle type {}.",
-                path_as_vec, vehicle_type_idx,
+                "Cannot spawn vehicle for path {}. Nodes are not compatible with vehicle type {}.",
+                path_as_vec
+                    .iter()
+                    .map(|&n| self.network.node(n).id())
+                    .join(" - "),
+                vehicle_type_idx,
             ));
         }
 
@@ -201,7 +209,7 @@ impl Schedule {
             self.add_dummy_tour(
                 &mut dummy_tours,
                 &mut dummy_ids_sorted,
-                VehicleIdx::dummy_from(self.vehicle_counter as Idx),
+                VehicleIdx::dummy_from(vehicle_counter as Idx),
                 dummy_tour,
             );
             vehicle_counter += 1;
@@ -254,58 +262,111 @@ impl Schedule {
             ));
             }
         }
-        if self.network.node(path.first()).is_depot() {
+        if self.is_vehicle(vehicle_idx) && self.network.node(path.first()).is_depot() {
             // path starts with a depot, so we need to ensure that the new depot has capacities
-            // available
+            // available (dummy tours drop depots)
             let new_start_depot = path.first();
             let old_start_depot = self.tour_of(vehicle_idx).unwrap().start_depot().unwrap();
 
-            if new_start_depot != old_start_depot
-                && !self.can_depot_spawn_vehicle(
+            if new_start_depot != old_start_depot {
+                let vehicle_type = self
+                    .vehicle_type_of(vehicle_idx)
+                    .expect("Vehicle must be real, as it starts with a depot");
+                // the vehicle leaves its old start depot, so it does not count towards the usage
+                let mut depot_usage_without_vehicle = self.depot_usage.clone();
+                if let Some((spawned, _)) = depot_usage_without_vehicle
+                    .get_mut(&(self.network.get_depot_idx(old_start_depot), vehicle_type))
+                {
+                    spawned.remove(&vehicle_idx);
+                }
+
+                if !self.can_depot_spawn_vehicle_custom_usage(
                     new_start_depot,
-                    self.vehicle_type_of(vehicle_idx)
-                        .expect("Vehicle must be real, as it starts with a depot"),
-                )
-            {
-                return Err(format!(
-                    "Cannot add path {} to vehicle tour {}. New start depot has no capacity available.",
-                    path, vehicle_idx
-                ));
+                    vehicle_type,
+                    &depot_usage_without_vehicle,
+                ) {
+                    return Err(format!(
+                        "Cannot add path {} to vehicle tour {}. New start depot has no capacity available.",
+                        path, vehicle_idx
+                    ));
+                }
+            }
+        }
+        if self.is_vehicle(vehicle_idx) && self.network.node(path.last()).is_depot() {
+            // same for the end depot
+            let new_end_depot = path.last();
+            let old_end_depot = self.tour_of(vehicle_idx).unwrap().end_depot().unwrap();
+
+            if new_end_depot != old_end_depot {
+                let vehicle_type = self
+                    .vehicle_type_of(vehicle_idx)
+                    .expect("Vehicle must be real, as it ends with a depot");
+                // the vehicle leaves its old end depot, so it does not count towards the usage
+                let mut depot_usage_without_vehicle = self.depot_usage.clone();
+                if let Some((_, despawned)) = depot_usage_without_vehicle
+                    .get_mut(&(self.network.get_depot_idx(old_end_depot), vehicle_type))
+                {
+                    despawned.remove(&vehicle_idx);
+                }
+
+                if !self.can_depot_despawn_vehicle_custom_usage(
+                    new_end_depot,
+                    vehicle_type,
+                    &depot_usage_without_vehicle,
+                ) {
+                    return Err(format!(
+                        "Cannot add path {} to vehicle tour {}. New end depot has no capacity available.",
+                        path, vehicle_idx
+                    ));
+                }
             }
         }
         let mut tours = self.tours.clone();
         let mut next_period_transitions = self.next_period_transitions.clone();
         let mut train_formations = self.train_formations.clone();
         let mut depot_usage = self.depot_usage.clone();
+        let mut dummy_tours = self.dummy_tours.clone();
         let mut unserved_passengers = self.unserved_passengers;
         let mut maintenance_violation = self.maintenance_violation;
         let mut costs = self.costs;
 
-        // add vehicle to train_formations for nodes of new path
-        self.update_train_formation(
-            &mut train_formations,
-            &mut unserved_passengers,
-            None,
-            Some(self.vehicles.get(&vehicle_idx).cloned().unwrap()),
-            path.iter(),
-        )?;
-
-        let (new_tour, removed_path_opt) = tours.get(&vehicle_idx).unwrap().insert_path(path);
+        let old_tour = self.tour_of(vehicle_idx)?;
 
-        // remove vehicle from train formations for nodes of removed path
-        if let Some(ref removed_path) = removed_path_opt {
+        // add vehicle to train_formations for nodes of new path (dummies are not part of train
+        // formations)
+        if let Some(vehicle) = self.vehicles.get(&vehicle_idx) {
             self.update_train_formation(
                 &mut train_formations,
                 &mut unserved_passengers,
-                Some(vehicle_idx),
                 None,
-                removed_path.iter(),
+                Some(vehicle.clone()),
+                path.iter(),
             )?;
         }
 
-        costs = (costs + new_tour.costs()) - self.tours.get(&vehicle_idx).unwrap().costs();
+        let (new_tour, removed_path_opt) = old_tour.insert_path(path);
+
+        // remove vehicle from train formations for nodes of removed path
+        if let Some(ref removed_path) = removed_path_opt {
+            if self.is_vehicle(vehicle_idx) {
+                self.update_train_formation(
+                    &mut train_formations,
+                    &mut unserved_passengers,
+                    Some(vehicle_idx),
+                    None,
+                    removed_path.iter(),
+                )?;
+            }
+        }
 
-        tours.insert(vehicle_idx, new_tour);
+        // only real tours contribute to the costs
+        self.update_tour_and_costs(
+            &mut tours,
+            &mut dummy_tours,
+            &mut costs,
+            vehicle_idx,
+            new_tour,
+        );
 
         self.update_depot_usage(&mut depot_usage, &self.vehicles, &tours, vehicle_idx);
 
@@ -324,7 +385,7 @@ impl Schedule {
                 next_period_transitions,
                 train_formations,
                 depot_usage,
-                self.dummy_tours.clone(),
+                dummy_tours,
                 self.vehicle_counter,
                 self.vehicle_ids_grouped_and_sorted.clone(),
                 self.dummy_ids_sorted.clone(),
@@ -351,7 +412,8 @@ impl Schedule {
         if !self.is_vehicle(vehicle_idx) {
             return Err(format!(
                 "Cannot remove segment {} from vehicle {}. Vehicle is not a real vehicle.",
-                segment, vehicle_idx,
+                segment.describe(&self.network),
+                vehicle_idx,
             ));
         }
         let vehicles = self.vehicles.clone();
@@ -395,7 +457,7 @@ impl Schedule {
                     self.add_dummy_tour(
                         &mut dummy_tours,
                         &mut dummy_ids_sorted,
-                        VehicleIdx::dummy_from(self.vehicle_counter as Idx),
+                        VehicleIdx::dummy_from(vehicle_counter as Idx),
                         new_dummy_tour,
                     );
                     vehicle_counter += 1;
@@ -444,7 +506,7 @@ impl Schedule {
         if !self.check_receiver_type_compatibility(provider, receiver, segment) {
             return Err(format!(
                 "Cannot fit_reassign segment {} from vehicle {} to vehicle {}. Vehicle types do not match and segment contains service trip.",
-                segment, provider, receiver,
+                segment.describe(&self.network), provider, receiver,
             ));
         }
         let mut vehicles = self.vehicles.clone();
@@ -526,7 +588,7 @@ impl Schedule {
         if !self.check_receiver_type_compatibility(provider, receiver, segment) {
             return Err(format!(
                 "Cannot override_reassign segment {} from vehicle {} to vehicle {}. Vehicle types do not match and segment contains service trip.",
-                segment, provider, receiver,
+                segment.describe(&self.network), provider, receiver,
             ));
         }
         let mut vehicles = self.vehicles.clone();
@@ -731,7 +793,8 @@ impl Schedule {
         )
     }
 
-    /// Reassigns the end depots of all vehicles greedily. Capacties of depots are ignored.
+    /// Reassigns the end depots of all vehicles greedily, i.e., each vehicle despawns at the
+    /// nearest depot that has despawn capacity left.
     pub fn reassign_end_depots_greedily(&self) -> Result<Schedule, String> {
         let mut tours = self.tours.clone();
         let mut next_period_transitions = self.next_period_transitions.clone();
@@ -741,15 +804,24 @@ impl Schedule {
 
         for vehicle_id in self.vehicles_iter_all() {
             let tour = self.tour_of(vehicle_id).unwrap();
+            let vehicle_type = self.vehicle_type_of(vehicle_id).unwrap();
             let last_node_location = self
                 .network
                 .node(tour.last_non_depot().unwrap())
                 .end_location();
+            // the vehicle is still counted at its current end depot
             let new_end_depot_node = self
                 .network
                 .end_depots_sorted_by_distance_from(last_node_location)
-                .first()
-                .copied()
+                .into_iter()
+                .find(|&depot| {
+                    depot == tour.end_depot().unwrap()
+                        || self.can_depot_despawn_vehicle_custom_usage(
+                            depot,
+                            vehicle_type,
+                            &depot_usage,
+                        )
+                })
                 .ok_or(format!("Cannot find end depot for vehicle {}.", vehicle_id))?;
 
             let new_tour = tour.replace_end_depot(new_end_depot_node).unwrap();
@@ -786,6 +858,77 @@ impl Schedule {
         ))
     }
 
+    /// Sets the start and the end depot of the vehicle to the given depot.
+    /// # Errors
+    /// If the vehicle is not a real vehicle or the depot cannot spawn or despawn the vehicle (type
+    /// or capacity), an error is returned.
+    pub fn assign_depot(
+        &self,
+        vehicle_id: VehicleIdx,
+        depot: DepotIdx,
+    ) -> Result<Schedule, String> {
+        let vehicle_type = self.vehicle_type_of(vehicle_id)?;
+        let tour = self.tour_of(vehicle_id)?;
+        let new_start_depot = self.network.get_start_depot_node(depot);
+        let new_end_depot = self.network.get_end_depot_node(depot);
+
+        if new_start_depot != tour.start_depot()?
+            && !self.can_depot_spawn_vehicle(new_start_depot, vehicle_type)
+        {
+            return Err(format!(
+                "Cannot assign depot {} to vehicle {}. Depot cannot spawn the vehicle.",
+                self.network.get_depot(depot).id(),
+                vehicle_id
+            ));
+        }
+        if new_end_depot != tour.end_depot()?
+            && !self.can_depot_despawn_vehicle(new_end_depot, vehicle_type)
+        {
+            return Err(format!(
+                "Cannot assign depot {} to vehicle {}. Depot cannot despawn the vehicle.",
+                self.network.get_depot(depot).id(),
+                vehicle_id
+            ));
+        }
+
+        let mut tours = self.tours.clone();
+        let mut next_period_transitions = self.next_period_transitions.clone();
+        let mut depot_usage = self.depot_usage.clone();
+        let mut maintenance_violation = self.maintenance_violation;
+
+        let new_tour = tour
+            .replace_start_depot(new_start_depot)?
+            .replace_end_depot(new_end_depot)?;
+        let costs = (self.costs + new_tour.costs()) - tour.costs();
+        tours.insert(vehicle_id, new_tour);
+
+        self.update_depot_usage(&mut depot_usage, &self.vehicles, &tours, vehicle_id);
+
+        self.update_transitions_and_violation_fast(
+            &mut next_period_transitions,
+            &mut maintenance_violation,
+            vec![vehicle_id],
+            &self.vehicles,
+            &tours,
+        );
+
+        Ok(Schedule::new(
+            self.vehicles.clone(),
+            tours,
+            next_period_transitions,
+            self.train_formations.clone(),
+            depot_usage,
+            self.dummy_tours.clone(),
+            self.vehicle_counter,
+            self.vehicle_ids_grouped_and_sorted.clone(),
+            self.dummy_ids_sorted.clone(),
+            self.unserved_passengers,
+            maintenance_violation,
+            costs,
+            self.network.clone(),
+        ))
+    }
+
     // TEST this
     pub fn recompute_transitions_for(
         &self,
@@ -820,6 +963,7 @@ impl Schedule {
     }
 
     /// Reassign the end depots such that they are consistent with the transition.
+    /// If the depot cannot despawn the vehicle (despawn capacity), the end depot is kept.
     pub fn reassign_end_depots_consistent_with_transitions(&self) -> Schedule {
         let mut tours = self.tours.clone();
         let mut next_day_transitions = self.next_period_transitions.clone();
@@ -839,6 +983,15 @@ impl Schedule {
                 self.tour_of(next_vehicle).unwrap().start_depot().unwrap();
             let depot_idx = self.network.get_depot_idx(start_depot_of_next_vehicle);
             let new_end_depot = self.network.get_end_depot_node(depot_idx);
+            if new_end_depot != tour.end_depot().unwrap()
+                && !self.can_depot_despawn_vehicle_custom_usage(
+                    new_end_depot,
+                    vehicle_type,
+                    &depot_usage,
+                )
+            {
+                continue;
+            }
 
             let new_tour = tour.replace_end_depot(new_end_depot).unwrap();
 
@@ -873,6 +1026,65 @@ impl Schedule {
             self.network.clone(),
         )
     }
+
+    /// Merges the dummy tours into fewer dummy tours.
+    /// The dummy tours are considered by start time and each one is appended to the first merged
+    /// dummy tour whose last node can reach its first node (first-fit). Otherwise, it starts a
+    /// new merged dummy tour, which keeps the id of this dummy.
+    /// As dummy tours do not contribute to train formations, depot usage or costs, the objective
+    /// value of the schedule is not affected.
+    pub fn compact_dummies(&self) -> Schedule {
+        let mut dummies: Vec<VehicleIdx> = self.dummy_iter().collect();
+        dummies.sort_by_key(|dummy| (self.dummy_tours.get(dummy).unwrap().start_time(), *dummy));
+
+        let mut merged_dummies: Vec<(VehicleIdx, Vec<NodeIdx>)> = Vec::new();
+        for dummy in dummies {
+            let tour = self.dummy_tours.get(&dummy).unwrap();
+            match merged_dummies.iter_mut().find(|(_, nodes)| {
+                self.network
+                    .can_reach(*nodes.last().unwrap(), tour.first_node())
+            }) {
+                Some((_, nodes)) => nodes.extend(tour.all_nodes_iter()),
+                None => merged_dummies.push((dummy, tour.all_nodes_iter().collect())),
+            }
+        }
+
+        if merged_dummies.len() == self.dummy_tours.len() {
+            return self.clone();
+        }
+
+        let mut dummy_tours = HashMap::new();
+        let mut dummy_ids_sorted = Vec::new();
+        for (dummy, nodes) in merged_dummies {
+            let old_tour = self.dummy_tours.get(&dummy).unwrap();
+            let new_tour = if nodes.len() == old_tour.length() {
+                old_tour.clone()
+            } else {
+                Tour::new_dummy(
+                    Path::new_trusted(nodes, self.network.clone()).unwrap(),
+                    self.network.clone(),
+                )
+                .unwrap()
+            };
+            self.add_dummy_tour(&mut dummy_tours, &mut dummy_ids_sorted, dummy, new_tour);
+        }
+
+        Schedule::new(
+            self.vehicles.clone(),
+            self.tours.clone(),
+            self.next_period_transitions.clone(),
+            self.train_formations.clone(),
+            self.depot_usage.clone(),
+            dummy_tours,
+            self.vehicle_counter,
+            self.vehicle_ids_grouped_and_sorted.clone(),
+            dummy_ids_sorted,
+            self.unserved_passengers,
+            self.maintenance_violation,
+            self.costs,
+            self.network.clone(),
+        )
+    }
 }
 
 // private methods
@@ -1031,6 +1243,14 @@ impl Schedule {
         receiver_vehicle: Option<Vehicle>, // None: only delete provider
         moved_nodes: impl Iterator<Item = NodeIdx>,
     ) -> Result<(), String> {
+        // fast path: dummies are never part of a train formation
+        if provider.is_none_or(|prov| self.is_dummy(prov))
+            && receiver_vehicle
+                .as_ref()
+                .is_none_or(|receiver| self.is_dummy(receiver.idx()))
+        {
+            return Ok(());
+        }
         for node in moved_nodes {
             if self.network.node(node).is_depot() {
                 continue;
@@ -1077,13 +1297,20 @@ impl Schedule {
     ) -> Result<TrainFormation, String> {
         let old_formation = train_formations
             .get(&node)
-            .unwrap_or_else(|| panic!("Node {} has no train formations.", node));
+            .unwrap_or_else(|| panic!("Node {} has no train formations.", self.network.node(node)));
 
         match receiver_vehicle {
             Some(receiver_vh) if !self.is_dummy(receiver_vh.idx()) => {
                 match provider {
                     Some(prov) if !self.is_dummy(prov) => {
                         // both are real vehicles
+                        if self.vehicle_type_of(prov).ok() != Some(receiver_vh.type_idx()) {
+                            self.check_maximal_formation_count_of_type(
+                                old_formation,
+                                &receiver_vh,
+                                node,
+                            )?;
+                        }
                         old_formation.replace(prov, receiver_vh)
                     }
                     _ => {
@@ -1093,9 +1320,9 @@ impl Schedule {
                                 >= self.network.track_count_of_maintenance_slot(node)
                         {
                             return Err(format!(
-                                "Cannot add vehicle {} to maintenance node {}. Maintenance slot is already full.",
+                                "Cannot add vehicle {} to {}. Maintenance slot is already full.",
                                 receiver_vh.idx(),
-                                node
+                                self.network.node(node)
                             ));
                         }
                         if self.network.node(node).is_service() {
@@ -1103,13 +1330,18 @@ impl Schedule {
                             {
                                 if old_formation.vehicle_count() >= max_length {
                                     return Err(format!(
-                                        "Cannot add vehicle {} to node {}. Formation is full.",
+                                        "Cannot add vehicle {} to {}. Formation is full.",
                                         receiver_vh.idx(),
-                                        node
+                                        self.network.node(node)
                                     ));
                                 }
                             }
                         }
+                        self.check_maximal_formation_count_of_type(
+                            old_formation,
+                            &receiver_vh,
+                            node,
+                        )?;
                         Ok(old_formation.add_at_tail(receiver_vh))
                     }
                 }
@@ -1130,6 +1362,31 @@ impl Schedule {
         }
     }
 
+    /// Checks that adding the vehicle to the formation of the service node does not exceed the
+    /// maximal formation count of its vehicle type.
+    fn check_maximal_formation_count_of_type(
+        &self,
+        formation: &TrainFormation,
+        vehicle: &Vehicle,
+        node: NodeIdx,
+    ) -> Result<(), String> {
+        if !self.network.node(node).is_service() {
+            return Ok(());
+        }
+        if let Some(max_count) = vehicle.maximal_formation_count() {
+            if formation.vehicle_count_of_type(vehicle.type_idx()) >= max_count {
+                return Err(format!(
+                    "Cannot add vehicle {} to {}. Formation already contains {} vehicles of type {}.",
+                    vehicle.idx(),
+                    self.network.node(node),
+                    max_count,
+                    vehicle.type_idx()
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Updates the provided depot_usage data structure.
     /// The vehicle is removed from the old depots (if it was a real vehicle in self).
     /// The vehicle is added to the new depots if vehicle is real in new schedule (given by vehicles) and new_tour is Some.
@@ -1268,29 +1525,35 @@ impl Schedule {
         tours: &HashMap<VehicleIdx, Tour>,
     ) {
         // as we update the transitions one by one we need to keep track of the tours that are
-        // updated so far
-        let mut tours_updated_one_by_one: HashMap<VehicleIdx, &Tour> = HashMap::new();
+        // updated so far (only a few vehicles change per modification, so a linear scan is fast)
+        let mut tours_updated_one_by_one: Vec<(VehicleIdx, &Tour)> = Vec::new();
 
         for vehicle in changed_vehicles.iter().filter(|v| v.is_real()) {
+            let tour_of = |v: VehicleIdx| {
+                tours_updated_one_by_one
+                    .iter()
+                    .find(|(updated, _)| *updated == v)
+                    .map(|(_, tour)| *tour)
+                    .unwrap_or_else(|| self.tours.get(&v).unwrap())
+            };
             let vehicle_type = vehicles
                 .get(vehicle)
                 .unwrap_or_else(|| self.vehicles.get(vehicle).unwrap())
                 .type_idx();
             let old_transition = transitions.get(&vehicle_type).unwrap();
-            let new_transition =
-                match (self.is_vehicle(*vehicle), vehicles.keys().contains(vehicle)) {
+            let (new_transition, new_tour) =
+                match (self.is_vehicle(*vehicle), vehicles.contains_key(vehicle)) {
                     (true, true) => {
                         // vehicle was a vehicle in the old schedule and will be in the new one
                         let new_tour = tours.get(vehicle).unwrap();
                         let new_transition = old_transition.update_vehicle(
                             *vehicle,
+                            self.tours.get(vehicle).unwrap(),
                             new_tour,
-                            &tours_updated_one_by_one,
-                            &self.tours,
+                            tour_of,
                             &self.get_network(),
                         );
-                        tours_updated_one_by_one.insert(*vehicle, new_tour);
-                        new_transition
+                        (new_transition, Some(new_tour))
                     }
                     (false, true) => {
                         // vehicles was newly added
@@ -1300,20 +1563,23 @@ impl Schedule {
                             new_tour,
                             &self.get_network(),
                         );
-                        tours_updated_one_by_one.insert(*vehicle, new_tour);
-                        new_transition
+                        (new_transition, Some(new_tour))
                     }
                     (true, false) => {
-                        old_transition.remove_vehicle(
-                            // vehicles is removed
+                        // vehicles is removed
+                        let new_transition = old_transition.remove_vehicle(
                             *vehicle,
-                            &tours_updated_one_by_one,
-                            &self.tours,
+                            self.tours.get(vehicle).unwrap(),
+                            tour_of,
                             &self.get_network(),
-                        )
+                        );
+                        (new_transition, None)
                     }
                     _ => unreachable!(),
                 };
+            if let Some(new_tour) = new_tour {
+                tours_updated_one_by_one.push((*vehicle, new_tour));
+            }
 
             *maintenance_violation = (*maintenance_violation
                 + new_transition.maintenance_violation())
@@ -1440,7 +1706,12 @@ impl Schedule {
 
         let last_non_depot = intermediate_tour.last_non_depot().unwrap();
         let new_end_depot = self
-            .find_best_end_depot_for_despawning(vehicle_type_idx, last_non_depot)
+            .find_best_end_depot_for_despawning(
+                vehicle_type_idx,
+                last_non_depot,
+                depot_usage,
+                Some(self.network.get_depot_idx(new_start_depot)),
+            )
             .unwrap();
         if new_end_depot != intermediate_tour.end_depot().unwrap() {
             intermediate_tour.replace_end_depot(new_end_depot).unwrap()
@@ -1467,15 +1738,26 @@ impl Schedule {
             let tour_len = nodes.len();
             let _ = std::mem::replace(&mut nodes[tour_len - 1], overflow_depot_ids.2);
 
-            /* println!(
-                "\x1b[93mwarning:\x1b[0m Tour for {} violates depot constraints at {}. Using overflow depot instead.",
-                self.network.vehicle_types().get(vehicle_type_id).unwrap(),
-                self.network.node(old_start_depot)
-            ); */
-
+            // reported in the output with code "overflowDepotUsed" (see server::collect_warnings)
             return Ok(nodes);
         }
 
+        // if the given end depot cannot despawn the vehicle, use the nearest one that can
+        if self.network.node(last_node).is_depot()
+            && !self.can_depot_despawn_vehicle(last_node, vehicle_type_idx)
+        {
+            let tour_len = nodes.len();
+            let start_depot = Some(nodes[0])
+                .filter(|&node| self.network.node(node).is_depot())
+                .map(|node| self.network.get_depot_idx(node));
+            nodes[tour_len - 1] = self.find_best_end_depot_for_despawning(
+                vehicle_type_idx,
+                nodes[tour_len - 2],
+                &self.depot_usage,
+                start_depot,
+            )?;
+        }
+
         // if path does not start with a depot, insert the nearest available start_depot
         if !self.network.node(first_node).is_depot() {
             let new_start_depot = self.find_best_start_depot_for_spawning(
@@ -1488,7 +1770,12 @@ impl Schedule {
 
         // if path does not end with a depot, insert the nearest available end_depot
         if !self.network.node(last_node).is_depot() {
-            match self.find_best_end_depot_for_despawning(vehicle_type_idx, last_node) {
+            match self.find_best_end_depot_for_despawning(
+                vehicle_type_idx,
+                last_node,
+                &self.depot_usage,
+                Some(self.network.get_depot_idx(nodes[0])),
+            ) {
                 Ok(depot) => nodes.push(depot),
                 Err(e) => return Err(e),
             };
@@ -1497,6 +1784,7 @@ impl Schedule {
         Ok(nodes)
     }
 
+    /// The start depot with spawn capacity left that is chosen by the depot assignment policy.
     fn find_best_start_depot_for_spawning(
         &self,
         vehicle_type_idx: VehicleTypeIdx,
@@ -1504,42 +1792,130 @@ impl Schedule {
         depot_usage: &DepotUsage,
     ) -> NodeIdx {
         let start_location = self.network.node(first_node).start_location();
-        let start_depot = self
+        let available_depots: Vec<(NodeIdx, Distance)> = self
             .network
             .start_depots_sorted_by_distance_to(start_location)
-            .iter()
-            .copied()
-            .find(|depot| {
-                self.can_depot_spawn_vehicle_custom_usage(*depot, vehicle_type_idx, depot_usage)
+            .into_iter()
+            .filter(|&depot| {
+                self.can_depot_spawn_vehicle_custom_usage(depot, vehicle_type_idx, depot_usage)
+            })
+            .map(|depot| {
+                (
+                    depot,
+                    self.network.dead_head_distance_between(depot, first_node),
+                )
             })
+            .collect();
+        let start_depot = self
+            .choose_depot(
+                available_depots,
+                |depot| {
+                    balance_violation_change(
+                        depot_usage,
+                        self.network.get_depot_idx(depot),
+                        vehicle_type_idx,
+                        0,
+                        1,
+                    )
+                },
+                |depot| {
+                    self.network
+                        .get_depot(self.network.get_depot_idx(depot))
+                        .spawn_cost()
+                        + self.network.dead_head_costs_between(depot, first_node)
+                },
+            )
             .expect("There should be at least the overflow depot available.");
-        /* if start_depot == self.network.overflow_depot_ids().1 {
-            println!(
-                "\x1b[93mwarning:\x1b[0m Tour for vehicle_type {} violates depot constraints at {}. Using overflow depot instead.",
-                self.network.vehicle_types().get(vehicle_type_id).unwrap(),
-                self.network.node(first_node)
-            );
-        } */
         start_depot
     }
 
+    /// The end depot with despawn capacity left that is chosen by the depot assignment policy.
+    /// start_depot is the start depot of the same tour if it is not contained in depot_usage.
     fn find_best_end_depot_for_despawning(
         &self,
         vehicle_type_idx: VehicleTypeIdx,
         last_node: NodeIdx,
+        depot_usage: &DepotUsage,
+        start_depot: Option<DepotIdx>,
     ) -> Result<NodeIdx, String> {
         let end_location = self.network.node(last_node).end_location();
-        let end_depot = self
+        let available_depots: Vec<(NodeIdx, Distance)> = self
             .network
             .end_depots_sorted_by_distance_from(end_location)
-            .first()
-            .copied();
+            .into_iter()
+            .filter(|&depot| {
+                self.can_depot_despawn_vehicle_custom_usage(depot, vehicle_type_idx, depot_usage)
+            })
+            .map(|depot| {
+                (
+                    depot,
+                    self.network.dead_head_distance_between(last_node, depot),
+                )
+            })
+            .collect();
+        let end_depot = self.choose_depot(
+            available_depots,
+            |depot| {
+                let depot_idx = self.network.get_depot_idx(depot);
+                balance_violation_change(
+                    depot_usage,
+                    depot_idx,
+                    vehicle_type_idx,
+                    if start_depot == Some(depot_idx) { 1 } else { 0 },
+                    -1,
+                )
+            },
+            |depot| self.network.dead_head_costs_between(last_node, depot),
+        );
         match end_depot {
             Some(depot) => Ok(depot),
             None => Err(format!(
                 "Cannot de-spawn vehicle of type {} for end_node {}. No end_depot available.",
-                vehicle_type_idx, last_node,
+                vehicle_type_idx,
+                self.network.node(last_node),
             )),
         }
     }
+
+    /// Chooses one of the available depots (sorted by distance) according to the depot
+    /// assignment policy (see model::config::DepotAssignmentConfig). None if there is no depot.
+    fn choose_depot(
+        &self,
+        available_depots: Vec<(NodeIdx, Distance)>,
+        balance_violation_change: impl Fn(NodeIdx) -> i32,
+        costs: impl Fn(NodeIdx) -> Cost,
+    ) -> Option<NodeIdx> {
+        let config = self.network.config();
+        let &(nearest_depot, nearest_distance) = available_depots.first()?;
+        let candidates = available_depots.into_iter().filter(|&(_, distance)| {
+            distance <= nearest_distance + config.depot_assignment.distance_tolerance
+        });
+        match config.depot_assignment.policy {
+            DepotAssignmentPolicy::Nearest => Some(nearest_depot),
+            DepotAssignmentPolicy::BalanceAware => candidates
+                .min_by_key(|&(depot, distance)| (balance_violation_change(depot), distance))
+                .map(|(depot, _)| depot),
+            DepotAssignmentPolicy::CostAware => candidates
+                .min_by_key(|&(depot, _)| (balance_violation_change(depot), costs(depot)))
+                .map(|(depot, _)| depot),
+        }
+    }
+}
+
+/// The change of the balance violation of the depot (see Schedule::total_depot_balance_violation)
+/// if a vehicle of the given type is spawned (change = 1) or despawned (change = -1) there.
+/// The offset is added to the balance of depot_usage (e.g., for a vehicle not yet contained).
+fn balance_violation_change(
+    depot_usage: &DepotUsage,
+    depot: DepotIdx,
+    vehicle_type_idx: VehicleTypeIdx,
+    offset: i32,
+    change: i32,
+) -> i32 {
+    let balance = depot_usage
+        .get(&(depot, vehicle_type_idx))
+        .map(|(spawned, despawned)| spawned.len() as i32 - despawned.len() as i32)
+        .unwrap_or(0)
+        + offset;
+    (balance + change).abs() - balance.abs()
 }
@@ -11,13 +11,20 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
+
 use itertools::assert_equal;
-use model::base_types::{Distance, VehicleIdx, VehicleTypeIdx};
+use model::base_types::{Distance, NodeIdx, VehicleIdx, VehicleTypeIdx};
+use model::config::Hint;
+use rapid_time::{DateTime, Duration};
 
 use crate::{
     path::Path,
     segment::Segment,
-    test_utilities::{default_schedule, init_test_data},
+    test_utilities::{
+        default_schedule, init_test_data, init_test_data_with, init_test_data_with_split_point,
+    },
+    transition::Transition,
     Schedule,
 };
 
@@ -150,6 +157,45 @@ fn basic_methods_test() {
     schedule.verify_consistency();
 }
 
+#[test]
+fn duty_length_variance_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let single_vehicle_schedule = schedule
+        .replace_vehicle_by_dummy(VehicleIdx::vehicle_from(1))
+        .unwrap()
+        .replace_vehicle_by_dummy(VehicleIdx::vehicle_from(2))
+        .unwrap();
+
+    // ACT
+    let mut duty_lengths = schedule.duty_lengths_per_vehicle_type();
+
+    // ASSERT
+    let mut expected_duty_lengths: Vec<Duration> = (0..3)
+        .map(|vehicle| {
+            schedule
+                .tour_of(VehicleIdx::vehicle_from(vehicle))
+                .unwrap()
+                .useful_duration()
+        })
+        .collect();
+    expected_duty_lengths.sort();
+    let mut vt1_duty_lengths = duty_lengths.remove(&d.vt1).unwrap();
+    vt1_duty_lengths.sort();
+    assert_eq!(vt1_duty_lengths, expected_duty_lengths);
+    assert!(duty_lengths.is_empty());
+    assert_eq!(
+        schedule.duty_length_variance(),
+        Schedule::variance_in_minutes(&expected_duty_lengths).round() as u64
+    );
+    assert_eq!(single_vehicle_schedule.duty_length_variance(), 0);
+    assert_eq!(
+        Schedule::variance_in_minutes(&[Duration::new("1:00"), Duration::new("2:00")]),
+        900.0
+    );
+}
+
 #[test]
 fn scheduling_ordering_test() {
     // ARRANGE
@@ -222,6 +268,54 @@ fn spawn_vehicle_to_repalce_dummy_tour_test() {
     new_schedule.verify_consistency();
 }
 
+#[test]
+fn vehicle_ids_are_never_reused_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let mut seen_ids: Vec<VehicleIdx> = schedule.vehicles_iter_all().collect();
+
+    // ACT
+    // remove a vehicle and respawn it for its dummy tour twice in a row
+    let mut schedules = vec![];
+    let mut current = schedule;
+    let mut removed_vehicle = VehicleIdx::vehicle_from(1);
+    for _ in 0..2 {
+        let intermediate = current.replace_vehicle_by_dummy(removed_vehicle).unwrap();
+        let dummy = intermediate.dummy_iter().next().unwrap();
+        let (next, new_vehicle) = intermediate
+            .spawn_vehicle_to_replace_dummy_tour(dummy, d.vt1)
+            .unwrap();
+        schedules.push((intermediate, dummy, removed_vehicle));
+        schedules.push((next.clone(), new_vehicle, removed_vehicle));
+        current = next;
+        removed_vehicle = new_vehicle;
+    }
+
+    // ASSERT
+    for (schedule, new_id, removed_vehicle) in schedules {
+        schedule.verify_consistency();
+        assert!(!seen_ids.iter().any(|id| id.idx() == new_id.idx()));
+        seen_ids.push(new_id);
+        assert!(!schedule
+            .next_day_transition_of(d.vt1)
+            .cycles_iter()
+            .any(|cycle| cycle.iter().any(|vehicle| vehicle == removed_vehicle)));
+    }
+    assert_eq!(
+        seen_ids,
+        vec![
+            VehicleIdx::vehicle_from(0),
+            VehicleIdx::vehicle_from(1),
+            VehicleIdx::vehicle_from(2),
+            VehicleIdx::dummy_from(3),
+            VehicleIdx::vehicle_from(4),
+            VehicleIdx::dummy_from(5),
+            VehicleIdx::vehicle_from(6),
+        ]
+    );
+}
+
 #[test]
 fn spawn_vehicle_to_repalce_dummy_tour_failure_test() {
     // ARRANGE
@@ -275,6 +369,81 @@ fn spawn_vehicle_for_path_without_depots_test() {
     new_schedule.verify_consistency();
 }
 
+#[test]
+fn spawn_vehicle_for_path_with_full_end_depot_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        // depot1 can only receive one vehicle of vt1 (veh1 of the default schedule)
+        instance["depots"][0]["allowedTypes"][0]["despawnCapacity"] = serde_json::json!(1);
+    });
+    let schedule = default_schedule(&d);
+    let veh3 = VehicleIdx::vehicle_from(3);
+
+    // ACT
+    let (new_schedule, new_vehicle) = schedule
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.trip12, d.trip23, d.trip34, d.trip45, d.trip51],
+        )
+        .unwrap();
+
+    // depot1 is the closest to the end of trip51 but cannot receive the vehicle, so it
+    // de-spawns at depot2 (the next closest).
+
+    // ASSERT
+    assert_eq!(new_vehicle, veh3);
+    assert!(!schedule.can_depot_despawn_vehicle(d.end_depot1, d.vt1));
+    assert_equal(
+        new_schedule.tour_of(veh3).unwrap().all_nodes_iter(),
+        [
+            d.start_depot3,
+            d.trip12,
+            d.trip23,
+            d.trip34,
+            d.trip45,
+            d.trip51,
+            d.end_depot2,
+        ]
+        .iter()
+        .cloned(),
+    );
+    new_schedule.verify_consistency();
+}
+
+#[test]
+fn reassign_end_depots_greedily_with_full_end_depot_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        // depot4 can only receive one vehicle
+        instance["depots"][3]["despawnCapacity"] = serde_json::json!(1);
+    });
+    let schedule = Schedule::empty(d.network.clone())
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.start_depot3, d.trip23, d.trip34, d.end_depot4],
+        )
+        .unwrap()
+        .0
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.start_depot2, d.trip23, d.trip34, d.end_depot2],
+        )
+        .unwrap()
+        .0;
+    let veh1 = VehicleIdx::vehicle_from(1);
+
+    // ACT
+    let new_schedule = schedule.reassign_end_depots_greedily().unwrap();
+
+    // ASSERT
+    // depot4 is the closest to the end of trip34 but already receives veh0, depot1 is next
+    assert_eq!(
+        new_schedule.tour_of(veh1).unwrap().end_depot().unwrap(),
+        d.end_depot1
+    );
+    new_schedule.verify_consistency();
+}
+
 #[test]
 fn replace_vehicle_by_dummy_success_test() {
     // ARRANGE
@@ -1270,6 +1439,62 @@ fn improve_depots_test() {
     new_schedule2.verify_consistency();
 }
 
+#[test]
+fn improve_depots_balance_aware_test() {
+    // ARRANGE
+    let set_policy = |policy: &'static str| {
+        move |instance: &mut serde_json::Value| {
+            instance["parameters"]["depotAssignment"] =
+                serde_json::json!({"policy": policy, "distanceTolerance": 50000});
+        }
+    };
+    let d_nearest = init_test_data_with(set_policy("nearest"));
+    let d_balance_aware = init_test_data_with(set_policy("balanceAware"));
+
+    // ACT
+    let nearest = default_schedule(&d_nearest).improve_depots(None);
+    let balance_aware = default_schedule(&d_balance_aware).improve_depots(None);
+
+    // ASSERT
+    // veh1 (trip31, trip14) starts at depot3 and ends at depot4 (the nearest depots), with
+    // balanceAware it returns to depot3 (43km from loc4 instead of 0km)
+    assert_eq!(nearest.total_depot_balance_violation(), 2);
+    assert_eq!(balance_aware.total_depot_balance_violation(), 0);
+    let d = d_balance_aware;
+    assert_equal(
+        balance_aware
+            .tour_of(VehicleIdx::vehicle_from(1))
+            .unwrap()
+            .all_nodes_iter(),
+        [d.start_depot3, d.trip31, d.trip14, d.end_depot3]
+            .iter()
+            .cloned(),
+    );
+    balance_aware.verify_consistency();
+}
+
+#[test]
+fn improve_depots_cost_aware_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        instance["parameters"]["depotAssignment"] =
+            serde_json::json!({"policy": "costAware", "distanceTolerance": 50000});
+        instance["depots"][2]["spawnCost"] = serde_json::json!(100000000); // depot3
+    });
+
+    // ACT
+    let schedule = default_schedule(&d).improve_depots(None);
+
+    // ASSERT
+    // veh1 (trip31 from loc3) is not spawned at the expensive depot3 at the same location
+    assert_eq!(
+        schedule.number_of_vehicles_of_same_type_spawned_at(d.depot3, d.vt1),
+        0
+    );
+    assert_eq!(schedule.total_depot_balance_violation(), 0);
+    schedule.verify_consistency();
+}
+
 #[test]
 fn reassign_end_depots_greedily_test() {
     // ARRANGE
@@ -1308,3 +1533,1002 @@ fn reassign_end_depots_greedily_test() {
     );
     new_schedule.verify_consistency();
 }
+
+#[test]
+fn formation_order_shunts_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+
+    // ACT
+    let schedule = Schedule::empty(d.network.clone())
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip12])
+        .unwrap()
+        .0
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip12, d.trip23])
+        .unwrap()
+        .0
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip12])
+        .unwrap()
+        .0;
+
+    // ASSERT
+    // veh1 is coupled on both sides on trip12 but leads trip23
+    let train_formation = schedule.train_formation_of(d.trip12);
+    assert_eq!(train_formation.ids(), vec![veh0, veh1, veh2]);
+    assert_eq!(train_formation.position_of(veh2), Some(2));
+    assert!(train_formation.is_inner_position(veh1));
+    assert!(!train_formation.is_inner_position(veh0));
+    assert!(!train_formation.is_inner_position(veh2));
+    assert_eq!(
+        schedule.formation_order_shunts(),
+        vec![(veh1, d.trip12, d.trip23)]
+    );
+    assert!(default_schedule(&d).formation_order_shunts().is_empty());
+}
+
+#[test]
+fn dead_head_pairs_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    // both vehicles dead-head from loc2 to loc4 after trip12 (ending at 06:30), veh1 dead-heads
+    // from loc5 to depot4 (loc4) at the end
+    let schedule = Schedule::empty(d.network.clone())
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.start_depot1, d.trip12, d.trip45, d.end_depot5],
+        )
+        .unwrap()
+        .0
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.start_depot1, d.trip12, d.trip45_fast, d.end_depot4],
+        )
+        .unwrap()
+        .0;
+
+    // ACT
+    let legs = schedule.dead_head_legs();
+    let pairs = schedule.dead_head_pairs(Duration::ZERO);
+
+    // ASSERT
+    assert_eq!(legs.len(), 3);
+    assert_eq!(pairs.len(), 1);
+    let (first, second) = pairs[0];
+    assert_eq!(first.vehicle(), veh0);
+    assert_eq!(second.vehicle(), veh1);
+    assert_eq!(first.origin(), d.network.node(d.trip12).end_location());
+    assert_eq!(
+        first.destination(),
+        d.network.node(d.trip45).start_location()
+    );
+    assert_eq!(first.departure(), DateTime::new("2020-01-01T06:30:00"));
+    assert_eq!(second.departure(), first.departure());
+    assert_eq!(
+        first.duration(),
+        d.network.minimal_duration_between_nodes(d.trip12, d.trip45)
+    );
+}
+
+#[test]
+fn count_hitch_hikers_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let new_schedule = schedule
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip12, d.trip23])
+        .unwrap()
+        .0;
+
+    // ASSERT
+    // trip12 and trip23 need two vehicles (80 passengers, capacity 50), the third one hitch-hikes
+    assert_eq!(schedule.count_hitch_hikers(), 0);
+    assert_eq!(new_schedule.hitch_hikers_at(d.trip12), 1);
+    assert_eq!(new_schedule.hitch_hikers_at(d.trip23), 1);
+    assert_eq!(new_schedule.hitch_hikers_at(d.trip31), 0);
+    assert_eq!(new_schedule.count_hitch_hikers(), 2);
+}
+
+#[test]
+fn count_hitch_hikers_with_short_coverage_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let veh0 = VehicleIdx::vehicle_from(0);
+
+    // ACT
+    let new_schedule = schedule.replace_vehicle_by_dummy(veh0).unwrap();
+
+    // ASSERT
+    // trip34 is not covered at all, trip12 only by one vehicle
+    assert_eq!(new_schedule.hitch_hikers_at(d.trip34), 0);
+    assert_eq!(new_schedule.hitch_hikers_at(d.trip12), 0);
+    assert_eq!(new_schedule.count_hitch_hikers(), 0);
+}
+
+fn set_maximal_formation_counts(
+    instance: &mut serde_json::Value,
+    vt1_limit: Option<u64>,
+    segment_1_2_limit: Option<u64>,
+) {
+    if let Some(limit) = vt1_limit {
+        instance["vehicleTypes"][0]["maximalFormationCount"] = limit.into();
+    }
+    if let Some(limit) = segment_1_2_limit {
+        for route in instance["routes"].as_array_mut().unwrap() {
+            for segment in route["segments"].as_array_mut().unwrap() {
+                if segment["id"] == "segment_1-2" {
+                    segment["maximalFormationCount"] = limit.into();
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn maximal_formation_count_of_segment_only_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| set_maximal_formation_counts(instance, None, Some(2)));
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let result_full = schedule.spawn_vehicle_for_path(d.vt1, vec![d.trip12]);
+    let result_free = schedule.spawn_vehicle_for_path(d.vt1, vec![d.trip23]);
+
+    // ASSERT
+    assert_eq!(d.network.maximal_formation_count_for(d.trip12), Some(2));
+    assert_eq!(d.network.maximal_formation_count_for(d.trip23), None);
+    assert!(result_full.is_err());
+    let (new_schedule, _) = result_free.unwrap();
+    new_schedule.verify_consistency();
+    assert_eq!(new_schedule.train_formation_of(d.trip23).vehicle_count(), 3);
+}
+
+#[test]
+fn maximal_formation_count_of_type_tighter_than_segment_test() {
+    // ARRANGE
+    let d =
+        init_test_data_with(|instance| set_maximal_formation_counts(instance, Some(2), Some(3)));
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let result_segment_limited = schedule.spawn_vehicle_for_path(d.vt1, vec![d.trip12]);
+    let result_type_limited = schedule.spawn_vehicle_for_path(d.vt1, vec![d.trip31]);
+    let result_free = schedule.spawn_vehicle_for_path(d.vt1, vec![d.trip34]);
+
+    // ASSERT
+    assert_eq!(d.network.maximal_formation_count_for(d.trip12), Some(2));
+    assert_eq!(d.network.maximal_formation_count_for(d.trip31), Some(2));
+    assert!(result_segment_limited.is_err());
+    assert!(result_type_limited.is_err());
+    let (new_schedule, _) = result_free.unwrap();
+    new_schedule.verify_consistency();
+    assert_eq!(
+        new_schedule
+            .train_formation_of(d.trip34)
+            .vehicle_count_of_type(d.vt1),
+        2
+    );
+}
+
+#[test]
+fn maximal_formation_count_of_segment_tighter_than_type_test() {
+    // ARRANGE
+    let d =
+        init_test_data_with(|instance| set_maximal_formation_counts(instance, Some(3), Some(2)));
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let result_segment_limited = schedule.spawn_vehicle_for_path(d.vt1, vec![d.trip12]);
+    let result_below_type_limit = schedule.spawn_vehicle_for_path(d.vt1, vec![d.trip31]);
+
+    // ASSERT
+    assert_eq!(d.network.maximal_formation_count_for(d.trip12), Some(2));
+    assert_eq!(d.network.maximal_formation_count_for(d.trip31), Some(3));
+    assert!(result_segment_limited.is_err());
+    let (new_schedule, _) = result_below_type_limit.unwrap();
+    new_schedule.verify_consistency();
+    assert_eq!(new_schedule.train_formation_of(d.trip31).vehicle_count(), 3);
+    assert!(new_schedule
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip31])
+        .is_err());
+}
+
+#[test]
+fn formation_shortfall_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        for route in instance["routes"].as_array_mut().unwrap() {
+            for segment in route["segments"].as_array_mut().unwrap() {
+                if segment["id"] == "segment_1-2" {
+                    segment["minimalFormationCount"] = 3.into();
+                }
+            }
+        }
+    });
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let (new_schedule, _) = schedule
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip12])
+        .unwrap();
+
+    // ASSERT
+    assert_eq!(d.network.minimal_formation_count_for(d.trip12), Some(3));
+    assert_eq!(d.network.minimal_formation_count_for(d.trip23), None);
+
+    assert_eq!(schedule.formation_shortfall_at(d.trip12), 1);
+    assert_eq!(schedule.formation_shortfall_at(d.trip23), 0);
+    assert_eq!(schedule.formation_shortfall(), 1);
+    assert_equal(
+        schedule.service_trips_below_minimal_formation_count(),
+        [d.trip12],
+    );
+
+    new_schedule.verify_consistency();
+    assert_eq!(new_schedule.formation_shortfall(), 0);
+    assert_eq!(
+        new_schedule
+            .service_trips_below_minimal_formation_count()
+            .count(),
+        0
+    );
+    // the third vehicle is needed for the minimal formation count, so it is no hitch-hiker
+    assert_eq!(new_schedule.hitch_hikers_at(d.trip12), 0);
+}
+
+#[test]
+fn fixed_fleet_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        instance["parameters"]["fixedFleet"] = serde_json::json!({ "vt1": 3 });
+    });
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let (new_schedule, new_vehicle) = schedule
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip34])
+        .unwrap();
+
+    // ASSERT
+    assert_eq!(d.network.fixed_fleet_size_of(d.vt1), Some(3));
+    assert_eq!(d.network.fixed_fleet_size_of(d.vt2), None);
+
+    // fleet of vt1 is exhausted, only the overflow depot can spawn further vehicles
+    assert_eq!(schedule.fixed_fleet_violation(), 0);
+    assert!(!schedule.can_depot_spawn_vehicle(d.start_depot4, d.vt1));
+    assert!(schedule.can_depot_spawn_vehicle(d.start_overflow_depot, d.vt1));
+    assert!(schedule.can_depot_spawn_vehicle(d.start_depot2, d.vt2));
+
+    new_schedule.verify_consistency();
+    assert_eq!(
+        new_schedule
+            .tour_of(new_vehicle)
+            .unwrap()
+            .start_depot()
+            .unwrap(),
+        d.start_overflow_depot
+    );
+    assert_eq!(new_schedule.fixed_fleet_violation_of(d.vt1), 1);
+    assert_eq!(new_schedule.fixed_fleet_violation(), 1);
+    assert_eq!(
+        new_schedule.unserved_passengers_of_type(d.vt1),
+        new_schedule.unserved_passengers()
+    );
+}
+
+#[test]
+fn synthetic_dead_head_legs_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        instance["parameters"]["fixedFleet"] = serde_json::json!({ "vt1": 3 });
+    });
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let (new_schedule, new_vehicle) = schedule
+        .spawn_vehicle_for_path(d.vt1, vec![d.trip34])
+        .unwrap();
+
+    // ASSERT
+    assert!(schedule.vehicles_using_overflow_depot().is_empty());
+    assert_eq!(schedule.synthetic_dead_head_distance(), Distance::ZERO);
+    assert_eq!(
+        schedule.dead_head_distance_without_synthetic_legs(),
+        schedule.total_dead_head_distance()
+    );
+
+    let new_tour = new_schedule.tour_of(new_vehicle).unwrap();
+    assert_eq!(new_tour.start_depot().unwrap(), d.start_overflow_depot);
+    assert_eq!(
+        new_schedule.vehicles_using_overflow_depot(),
+        vec![new_vehicle]
+    );
+
+    // the synthetic leg is only reported separately
+    assert_eq!(
+        new_schedule.synthetic_dead_head_distance(),
+        Distance::Infinity
+    );
+    assert_eq!(
+        new_schedule.dead_head_distance_without_synthetic_legs(),
+        schedule.total_dead_head_distance()
+            + d.network
+                .dead_head_distance_between(d.trip34, new_tour.end_depot().unwrap())
+    );
+    assert!(new_schedule
+        .dead_head_legs()
+        .iter()
+        .any(|leg| leg.vehicle() == new_vehicle && leg.is_synthetic()));
+
+    // the totals behind the objective include the synthetic leg
+    assert_eq!(new_schedule.total_dead_head_distance(), Distance::Infinity);
+    assert_eq!(new_schedule.costs(), schedule.costs() + new_tour.costs());
+    assert!(
+        new_tour.costs()
+            >= d.network
+                .dead_head_costs_between(d.start_overflow_depot, d.trip34)
+    );
+    assert_eq!(
+        new_schedule.number_of_vehicles(),
+        schedule.number_of_vehicles() + 1
+    );
+}
+
+#[test]
+fn line_purity_test() {
+    // ARRANGE
+    // move trip_3-1 into route_1-2, so that route_1-2 is served by trip12 and trip31
+    // (segment_3-1 precedes segment_1-2, so that the route is a chain loc3 - loc1 - loc2)
+    let d = init_test_data_with(|instance| {
+        let routes = instance["routes"].as_array_mut().unwrap();
+        let position = routes.iter().position(|r| r["id"] == "route_3-1").unwrap();
+        let route_3_1 = routes.remove(position);
+        let route_1_2 = routes.iter_mut().find(|r| r["id"] == "route_1-2").unwrap();
+        route_1_2["segments"][0]["order"] = 1.into();
+        route_1_2["segments"]
+            .as_array_mut()
+            .unwrap()
+            .push(route_3_1["segments"][0].clone());
+        for departure in instance["departures"].as_array_mut().unwrap() {
+            if departure["route"] == "route_3-1" {
+                departure["route"] = "route_1-2".into();
+            }
+        }
+    });
+
+    // ACT
+    let schedule = default_schedule(&d);
+
+    // ASSERT
+    let routes: Vec<(String, Vec<NodeIdx>)> = d
+        .network
+        .service_nodes_by_route()
+        .map(|(route, service_trips)| (route.clone(), service_trips.to_vec()))
+        .collect();
+    assert_eq!(routes.len(), 7);
+    assert_eq!(
+        routes.iter().find(|(r, _)| r == "route_1-2").unwrap().1,
+        vec![d.trip12, d.trip31]
+    );
+
+    // route_1-2: veh0, veh1, veh2; route_2-3: veh0, veh2; fastroute_4-5: none; all other routes:
+    // one vehicle
+    assert_eq!(schedule.distinct_vehicle_count(&[d.trip12, d.trip31]), 3);
+    assert_eq!(schedule.distinct_vehicle_count(&[d.trip45_fast]), 0);
+    assert_eq!(schedule.line_purity(), 3 + 2 + 1 + 1 + 1 + 1);
+}
+
+#[test]
+fn location_restriction_test() {
+    // ARRANGE
+    // loc6 only admits vt2. Its maintenance slot would be as attractive for veh0 as the one at
+    // loc1.
+    let d = init_test_data_with(|instance| {
+        instance["locations"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({"id": "loc6", "allowedVehicleTypes": ["vt2"]}));
+        let dead_head_trips = &mut instance["deadHeadTrips"];
+        dead_head_trips["indices"]
+            .as_array_mut()
+            .unwrap()
+            .push("loc6".into());
+        for (matrix, value) in [("durations", 2700), ("distances", 1000)] {
+            let rows = dead_head_trips[matrix].as_array_mut().unwrap();
+            for row in rows.iter_mut() {
+                row.as_array_mut().unwrap().push(value.into());
+            }
+            rows.push(serde_json::json!([value, value, value, value, value, 0]));
+        }
+        instance["maintenanceSlots"] = serde_json::json!([
+            {
+                "id": "maintenance_loc6",
+                "location": "loc6",
+                "start": "2020-01-01T12:00:00",
+                "end": "2020-01-01T20:00:00",
+                "trackCount": 1
+            },
+            {
+                "id": "maintenance_loc1",
+                "location": "loc1",
+                "start": "2020-01-01T12:00:00",
+                "end": "2020-01-01T20:00:00",
+                "trackCount": 1
+            }
+        ]);
+    });
+    let maintenance_loc6 = NodeIdx::maintenance_from(20);
+    let maintenance_loc1 = NodeIdx::maintenance_from(21);
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let blocked = schedule.add_path_to_vehicle_tour(
+        veh0,
+        Path::new_from_single_node(maintenance_loc6, d.network.clone()),
+    );
+    let allowed = schedule.add_path_to_vehicle_tour(
+        veh0,
+        Path::new_from_single_node(maintenance_loc1, d.network.clone()),
+    );
+
+    // ASSERT
+    assert!(!d
+        .network
+        .compatible_with_vehicle_type(maintenance_loc6, d.vt1));
+    assert!(d
+        .network
+        .compatible_with_vehicle_type(maintenance_loc6, d.vt2));
+    assert!(d
+        .network
+        .compatible_with_vehicle_type(maintenance_loc1, d.vt1));
+
+    assert!(blocked.is_err());
+
+    let (new_schedule, conflict) = allowed.unwrap();
+    new_schedule.verify_consistency();
+    assert!(conflict.is_none());
+    assert!(new_schedule.tour_of(veh0).unwrap().visits_maintenance());
+}
+
+#[test]
+fn unused_vehicle_type_test() {
+    // ARRANGE
+    // vt2 is declared in the test instance but no route uses it
+    let d = init_test_data();
+
+    // ACT
+    let empty_schedule = Schedule::empty(d.network.clone());
+    let schedule = default_schedule(&d);
+    let transitions: im::HashMap<VehicleTypeIdx, Transition> = d
+        .network
+        .vehicle_types()
+        .iter()
+        .map(|vt| (vt, schedule.next_day_transition_of(vt).clone()))
+        .collect();
+    let final_schedule = schedule
+        .set_next_day_transitions(transitions)
+        .reassign_end_depots_consistent_with_transitions();
+
+    // ASSERT
+    assert!(d
+        .network
+        .warnings()
+        .iter()
+        .any(|w| w.code == "unusedVehicleType" && w.context == vec!["vt2".to_string()]));
+    assert!(!d
+        .network
+        .warnings()
+        .iter()
+        .any(|w| w.code == "unusedVehicleType" && w.context == vec!["vt1".to_string()]));
+
+    assert_eq!(empty_schedule.vehicles_iter(d.vt2).count(), 0);
+    assert_eq!(
+        empty_schedule
+            .next_day_transition_of(d.vt2)
+            .number_of_cycles(),
+        0
+    );
+
+    final_schedule.verify_consistency();
+    assert_eq!(final_schedule.vehicles_iter(d.vt2).count(), 0);
+    assert_eq!(final_schedule.vehicles_iter(d.vt1).count(), 3);
+    assert_eq!(
+        final_schedule
+            .next_day_transition_of(d.vt2)
+            .number_of_cycles(),
+        0
+    );
+}
+
+#[test]
+fn compact_dummies_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let dummy3 = VehicleIdx::dummy_from(3);
+    let dummy4 = VehicleIdx::dummy_from(4);
+    let dummy5 = VehicleIdx::dummy_from(5);
+    // dummy3: trip12, trip23; dummy4: trip31, trip14; dummy5: trip51
+    let schedule = default_schedule(&d)
+        .remove_segment(Segment::new(d.trip12, d.trip23), veh2)
+        .unwrap()
+        .replace_vehicle_by_dummy(veh1)
+        .unwrap()
+        .remove_segment(Segment::new(d.trip51, d.trip51), veh0)
+        .unwrap();
+    assert_eq!(schedule.number_of_dummy_tours(), 3);
+
+    // ACT
+    let compacted_schedule = schedule.compact_dummies();
+
+    // ASSERT
+    compacted_schedule.verify_consistency();
+    // trip23 can reach trip31, but trip14 (ends at loc4 at 9:30) cannot reach trip51 (starts at
+    // loc5 at 10:00)
+    assert_equal(compacted_schedule.dummy_iter(), vec![dummy3, dummy5]);
+    assert!(!compacted_schedule.is_dummy(dummy4));
+    assert_equal(
+        compacted_schedule.tour_of(dummy3).unwrap().all_nodes_iter(),
+        vec![d.trip12, d.trip23, d.trip31, d.trip14],
+    );
+    assert_equal(
+        compacted_schedule.tour_of(dummy5).unwrap().all_nodes_iter(),
+        vec![d.trip51],
+    );
+
+    // everything the objective is based on stays the same
+    assert_eq!(
+        compacted_schedule.number_of_vehicles(),
+        schedule.number_of_vehicles()
+    );
+    assert_eq!(
+        compacted_schedule.unserved_passengers(),
+        schedule.unserved_passengers()
+    );
+    assert_eq!(
+        compacted_schedule.maintenance_violation(),
+        schedule.maintenance_violation()
+    );
+    assert_eq!(compacted_schedule.costs(), schedule.costs());
+    assert_eq!(
+        compacted_schedule.total_dead_head_distance(),
+        schedule.total_dead_head_distance()
+    );
+    assert_eq!(
+        compacted_schedule.total_depot_balance_violation(),
+        schedule.total_depot_balance_violation()
+    );
+
+    // a compacted schedule cannot be compacted further
+    assert_equal(
+        compacted_schedule.compact_dummies().dummy_iter(),
+        vec![dummy3, dummy5],
+    );
+}
+
+#[test]
+fn costs_stay_exact_under_dummy_real_conversions_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let dummy3 = VehicleIdx::dummy_from(3);
+    let dummy4 = VehicleIdx::dummy_from(4);
+    let veh5 = VehicleIdx::vehicle_from(5);
+    let schedule = default_schedule(&d);
+    assert_eq!(schedule.check_costs(), Ok(()));
+
+    // ACT
+    // real -> dummy: dummy3 gets trip12, trip23
+    let schedule_1 = schedule
+        .remove_segment(Segment::new(d.trip12, d.trip23), veh2)
+        .unwrap();
+    // real -> existing dummy: trip34 moves from veh0 to dummy3
+    let (schedule_2, _) = schedule_1
+        .override_reassign(Segment::new(d.trip34, d.trip34), veh0, dummy3)
+        .unwrap();
+    // whole real tour -> dummy: dummy4 gets trip31, trip14
+    let schedule_3 = schedule_2.replace_vehicle_by_dummy(veh1).unwrap();
+    // dummy -> real: veh5 replaces dummy3
+    let (schedule_4, new_vehicle) = schedule_3
+        .spawn_vehicle_to_replace_dummy_tour(dummy3, d.vt1)
+        .unwrap();
+    // dummy -> existing real: trip14 moves from dummy4 to veh2 (trip31 clashes and stays)
+    let schedule_5 = schedule_4
+        .fit_reassign(Segment::new(d.trip31, d.trip14), dummy4, veh2)
+        .unwrap();
+    // path added to a dummy tour
+    let (schedule_6, _) = schedule_5
+        .add_path_to_vehicle_tour(
+            dummy4,
+            Path::new(vec![d.trip45_fast], d.network.clone())
+                .unwrap()
+                .unwrap(),
+        )
+        .unwrap();
+
+    // ASSERT
+    assert_eq!(new_vehicle, veh5);
+    for intermediate_schedule in [
+        &schedule_1,
+        &schedule_2,
+        &schedule_3,
+        &schedule_4,
+        &schedule_5,
+    ] {
+        intermediate_schedule.verify_consistency();
+    }
+    for intermediate_schedule in [
+        &schedule_1,
+        &schedule_2,
+        &schedule_3,
+        &schedule_4,
+        &schedule_5,
+        &schedule_6,
+    ] {
+        assert_eq!(intermediate_schedule.check_costs(), Ok(()));
+    }
+
+    // dummy tours do not contribute to the costs
+    assert_eq!(
+        schedule_3.costs(),
+        schedule_2.costs() - schedule_2.tour_of(veh1).unwrap().costs()
+    );
+    assert_eq!(
+        schedule_4.costs(),
+        schedule_3.costs() + schedule_4.tour_of(veh5).unwrap().costs()
+    );
+    assert_eq!(schedule_6.costs(), schedule_5.costs());
+    assert_equal(
+        schedule_6.tour_of(dummy4).unwrap().all_nodes_iter(),
+        vec![d.trip31, d.trip45_fast],
+    );
+}
+
+#[test]
+fn check_costs_detects_drift_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let drifted_schedule = Schedule {
+        costs: schedule.costs + 1,
+        ..schedule.clone()
+    };
+
+    // ASSERT
+    assert_eq!(schedule.check_costs(), Ok(()));
+    assert!(drifted_schedule.check_costs().is_err());
+}
+
+#[test]
+fn apply_hints_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let hints = vec![
+        Hint::MoveTrip {
+            trip: "dep_segment_1-4".to_string(),
+            to_vehicle: "veh_2".to_string(),
+        },
+        // conflicts with dep_segment_3-4 of veh_0
+        Hint::MoveTrip {
+            trip: "dep_segment_3-1".to_string(),
+            to_vehicle: "veh_0".to_string(),
+        },
+        // depot1 cannot spawn another vehicle of vt1
+        Hint::AssignDepot {
+            vehicle: "veh_1".to_string(),
+            depot: "depot1".to_string(),
+        },
+        Hint::AssignDepot {
+            vehicle: "veh_1".to_string(),
+            depot: "depot3".to_string(),
+        },
+        Hint::MergeTours {
+            vehicle: "veh_7".to_string(),
+            into_vehicle: "veh_0".to_string(),
+        },
+    ];
+
+    // ACT
+    let (new_schedule, outcomes) = schedule.apply_hints(&hints);
+
+    // ASSERT
+    new_schedule.verify_consistency();
+    assert_eq!(
+        outcomes
+            .iter()
+            .map(|outcome| outcome.is_ok())
+            .collect::<Vec<_>>(),
+        vec![true, false, false, true, false]
+    );
+    assert_eq!(outcomes[4], Err("Unknown vehicle veh_7.".to_string()));
+
+    assert_eq!(new_schedule.train_formation_of(d.trip14).ids(), vec![veh2]);
+    assert_eq!(
+        new_schedule.tour_of(veh1).unwrap().start_depot().unwrap(),
+        d.start_depot3
+    );
+    assert_eq!(
+        new_schedule.tour_of(veh1).unwrap().end_depot().unwrap(),
+        d.end_depot3
+    );
+    assert_equal(
+        new_schedule
+            .tour_of(veh1)
+            .unwrap()
+            .all_non_depot_nodes_iter(),
+        vec![d.trip31],
+    );
+}
+
+#[test]
+fn projected_counters_test() {
+    // ARRANGE
+    // depot2 provides maintenance: veh0 and veh2 end there, veh1 ends at depot1
+    let d = init_test_data_with(|instance| {
+        instance["depots"][1]["providesMaintenance"] = serde_json::json!(true);
+    });
+    let schedule = default_schedule(&d);
+    let tours = schedule.get_tours().clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let transition = Transition::new_fast(&[], &tours, &d.network)
+        .add_vehicle_to_own_cycle(veh0, tours.get(&veh0).unwrap(), &d.network)
+        .add_vehicle_at_the_end(veh1, 0, |v| tours.get(&v).unwrap(), &d.network)
+        .add_vehicle_to_own_cycle(veh2, tours.get(&veh2).unwrap(), &d.network);
+    let schedule = schedule.set_next_day_transitions(im::HashMap::unit(d.vt1, transition));
+
+    // ACT
+    let projected_counters = schedule.projected_counters();
+
+    // ASSERT
+    let total_distance = |vehicle| tours.get(&vehicle).unwrap().total_distance();
+    let end_depot1_to_start_depot1 = d
+        .network
+        .dead_head_distance_between(d.end_depot1, d.start_depot1);
+    let end_depot2_to_start_depot1 = d
+        .network
+        .dead_head_distance_between(d.end_depot2, d.start_depot1);
+    let end_depot2_to_start_depot2 = d
+        .network
+        .dead_head_distance_between(d.end_depot2, d.start_depot2);
+
+    assert_equal(
+        projected_counters
+            .iter()
+            .map(|c| (c.vehicle(), c.successor())),
+        [(veh0, veh1), (veh1, veh0), (veh2, veh2)],
+    );
+    for projected_counter in projected_counters.iter() {
+        assert_eq!(
+            projected_counter.total_distance(),
+            total_distance(projected_counter.vehicle())
+        );
+    }
+    assert_eq!(
+        projected_counters[0].maintenance_split(),
+        Some((total_distance(veh0), Distance::ZERO))
+    );
+    assert_eq!(projected_counters[1].maintenance_split(), None);
+    assert_eq!(
+        projected_counters[0].handed_to_successor(),
+        Some(end_depot2_to_start_depot2)
+    );
+    assert_eq!(
+        projected_counters[1].handed_to_successor(),
+        Some(end_depot2_to_start_depot2 + total_distance(veh1) + end_depot1_to_start_depot1)
+    );
+    assert_eq!(
+        projected_counters[2].handed_to_successor(),
+        Some(end_depot2_to_start_depot1)
+    );
+
+    // without maintenance the counters are not bounded
+    let d = init_test_data();
+    let projected_counters = default_schedule(&d).projected_counters();
+    assert_eq!(projected_counters.len(), 3);
+    assert!(projected_counters
+        .iter()
+        .all(|c| c.maintenance_split().is_none() && c.handed_to_successor().is_none()));
+}
+
+#[test]
+fn with_transition_overrides_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let tours = schedule.get_tours().clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let successors = HashMap::from([(
+        d.vt1,
+        HashMap::from([(veh0, veh1), (veh1, veh0), (veh2, veh2)]),
+    )]);
+
+    // ACT
+    let new_schedule = schedule.with_transition_overrides(&successors).unwrap();
+
+    // ASSERT
+    new_schedule.verify_consistency();
+    let transition = new_schedule.next_day_transition_of(d.vt1);
+    assert_eq!(transition.number_of_cycles(), 2);
+    assert_eq!(transition.get_successor_of(veh0), veh1);
+    assert_eq!(transition.get_successor_of(veh1), veh0);
+    assert_eq!(transition.get_successor_of(veh2), veh2);
+    transition.verify_consistency(&tours, &d.network);
+    assert_eq!(
+        new_schedule.maintenance_violation(),
+        transition.maintenance_violation()
+    );
+    // tours are untouched
+    assert_eq!(new_schedule.costs(), schedule.costs());
+    for vehicle in [veh0, veh1, veh2] {
+        assert_eq!(
+            new_schedule
+                .tour_of(vehicle)
+                .unwrap()
+                .all_nodes_iter()
+                .collect::<Vec<_>>(),
+            schedule
+                .tour_of(vehicle)
+                .unwrap()
+                .all_nodes_iter()
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn with_transition_overrides_rejects_non_permutations_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let veh3 = VehicleIdx::vehicle_from(3);
+    let overrides = |mapping: Vec<(VehicleIdx, VehicleIdx)>| {
+        HashMap::from([(d.vt1, mapping.into_iter().collect::<HashMap<_, _>>())])
+    };
+
+    // ACT
+    let duplicate_successor = schedule.with_transition_overrides(&overrides(vec![
+        (veh0, veh1),
+        (veh1, veh1),
+        (veh2, veh0),
+    ]));
+    let missing_vehicle =
+        schedule.with_transition_overrides(&overrides(vec![(veh0, veh1), (veh1, veh0)]));
+    let foreign_vehicle = schedule.with_transition_overrides(&overrides(vec![
+        (veh0, veh1),
+        (veh1, veh3),
+        (veh2, veh0),
+    ]));
+
+    // ASSERT
+    assert_eq!(
+        duplicate_successor.err().unwrap(),
+        "Vehicle veh_1 is the successor of both veh_0 and veh_1"
+    );
+    assert_eq!(
+        missing_vehicle.err().unwrap(),
+        "Vehicle veh_2 has no successor"
+    );
+    assert!(foreign_vehicle
+        .err()
+        .unwrap()
+        .starts_with("Vehicle veh_3 is not a vehicle of type"));
+}
+
+#[test]
+fn out_of_service_violations_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        // trip12 runs from 06:00 to 06:30
+        instance["vehicleTypes"][0]["outOfServiceWindows"] = serde_json::json!([
+            {"start": "2020-01-01T06:10:00", "end": "2020-01-01T06:20:00"}
+        ]);
+        instance["parameters"]["outOfService"] = serde_json::json!({"hard": false});
+    });
+    let schedule = default_schedule(&d);
+
+    // ACT
+    let mut violations = schedule.out_of_service_violations();
+    violations.sort();
+
+    // ASSERT
+    assert_eq!(
+        violations,
+        vec![
+            (VehicleIdx::vehicle_from(0), d.trip12),
+            (VehicleIdx::vehicle_from(2), d.trip12),
+        ]
+    );
+    assert!(default_schedule(&init_test_data())
+        .out_of_service_violations()
+        .is_empty());
+}
+
+#[test]
+fn splits_test() {
+    // ARRANGE
+    let d = init_test_data_with_split_point();
+    let split_schedule = Schedule::empty(d.network.clone())
+        .spawn_vehicle_for_path(
+            d.vt1,
+            vec![d.start_depot1, d.trip12, d.trip23, d.end_depot3],
+        )
+        .unwrap()
+        .0
+        .spawn_vehicle_for_path(d.vt1, vec![d.start_depot1, d.trip12, d.end_depot2])
+        .unwrap()
+        .0;
+
+    // ACT
+    let splits = split_schedule.splits();
+
+    // ASSERT
+    assert_eq!(d.network.split_successor(d.trip12), Some(d.trip23));
+    assert_eq!(splits, vec![(d.trip12, d.trip23)]);
+    // both vehicles of trip12 continue with trip23
+    assert!(default_schedule(&d).splits().is_empty());
+}
+
+#[test]
+fn in_service_curve_test() {
+    // ARRANGE
+    let d = init_test_data_with(|instance| {
+        instance["vehicleTypes"][0]["minimumInService"] = serde_json::json!([{
+            "window": {"start": "2020-01-01T07:00:00", "end": "2020-01-01T09:00:00"},
+            "count": 3
+        }]);
+    });
+    let schedule = default_schedule(&d);
+    let time = |time: &str| DateTime::new(&format!("2020-01-01T{}:00", time));
+
+    // ACT
+    let curve = schedule.in_service_curve(d.vt1);
+    let curve_within = schedule.in_service_curve_within(d.vt1, time("07:00"), time("09:00"));
+
+    // ASSERT
+    // veh0 is in service from 06:00 to 10:30, veh1 from 08:00 to 09:30, veh2 from 06:00 to 08:30
+    assert_eq!(
+        curve,
+        vec![
+            (time("06:00"), 2),
+            (time("08:00"), 3),
+            (time("08:30"), 2),
+            (time("09:30"), 1),
+            (time("10:30"), 0),
+        ]
+    );
+    assert_eq!(
+        curve_within,
+        vec![(time("07:00"), 2), (time("08:00"), 3), (time("08:30"), 2)]
+    );
+    assert_eq!(schedule.peak_in_service(d.vt1), (3, Some(time("08:00"))));
+    assert_eq!(schedule.peak_in_service(d.vt2), (0, None));
+    assert_eq!(schedule.minimum_in_service_shortfall(), 1);
+    assert_eq!(
+        schedule.in_service_shortfall_within(d.vt1, time("08:00"), time("08:30"), 3),
+        0
+    );
+}
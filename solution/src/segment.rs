@@ -14,6 +14,7 @@
 use std::fmt;
 
 use model::base_types::NodeIdx;
+use model::network::Network;
 
 /// A segment is a pair of non-depot node ids that represent a slice of a tour or path.
 /// Depot nodes can cause unexpected behavior.
@@ -39,6 +40,20 @@ impl Segment {
     pub fn end(&self) -> NodeIdx {
         self.end
     }
+
+    /// Same as the Display, but with the names of the nodes (see model::network::nodes::Node)
+    /// instead of their indices.
+    pub fn describe(&self, network: &Network) -> String {
+        if self.start == self.end {
+            format!("[{}]", network.node(self.start))
+        } else {
+            format!(
+                "[{} .. {}]",
+                network.node(self.start),
+                network.node(self.end)
+            )
+        }
+    }
 }
 
 impl fmt::Display for Segment {
@@ -51,13 +51,19 @@ pub struct TestData {
 }
 
 pub fn init_test_data() -> TestData {
+    init_test_data_with(|_| {})
+}
+
+/// Same as init_test_data but the json instance can be modified before it is loaded.
+pub fn init_test_data_with(modify_instance: impl FnOnce(&mut serde_json::Value)) -> TestData {
     // load file from json
     let path = "resources/test_instance.json";
 
     let mut file = File::open(path).unwrap();
     let mut input_data = String::new();
     file.read_to_string(&mut input_data).unwrap();
-    let input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+    modify_instance(&mut input_data);
     let network = load_rolling_stock_problem_instance_from_json(input_data);
     TestData {
         network,
@@ -91,6 +97,26 @@ pub fn init_test_data() -> TestData {
     }
 }
 
+/// Same as init_test_data but trip_1-2 continues with the segment of trip_2-3 (i.e., trip12 and
+/// trip23 belong to the same departure) and the departure may be split at loc2.
+pub fn init_test_data_with_split_point() -> TestData {
+    init_test_data_with(|instance| {
+        let mut segment = instance["routes"][1]["segments"][0].clone();
+        segment["order"] = serde_json::json!(1);
+        instance["routes"][0]["segments"]
+            .as_array_mut()
+            .unwrap()
+            .push(segment);
+        instance["routes"][0]["allowedSplitPoints"] = serde_json::json!(["loc2"]);
+        let departure_segment = instance["departures"][1]["segments"][0].clone();
+        instance["departures"][0]["segments"]
+            .as_array_mut()
+            .unwrap()
+            .push(departure_segment);
+        instance["departures"].as_array_mut().unwrap().remove(1);
+    })
+}
+
 pub fn default_schedule(d: &TestData) -> Schedule {
     let mut schedule = Schedule::empty(d.network.clone());
 
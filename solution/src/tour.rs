@@ -14,19 +14,25 @@
 mod modifications;
 #[cfg(test)]
 mod tests;
+mod tour_nodes;
 use crate::path::Path;
 use crate::segment::Segment;
-use model::base_types::{Cost, Distance, MaintenanceCounter, NodeIdx, INF_DISTANCE};
+use model::base_types::{
+    checked_duration_between, Cost, Distance, Location, MaintenanceCounter, NodeIdx, INF_DISTANCE,
+};
+use model::config::DepotMaintenanceCredit;
 use model::network::nodes::Node;
 use model::network::Network;
+use rapid_time::{DateTime, Duration};
 use std::cmp::Ordering;
 use std::fmt;
-use rapid_time::{DateTime, Duration};
 
 use itertools::Itertools;
 
 use std::sync::Arc;
 
+use self::tour_nodes::TourNodes;
+
 type Position = usize; // the position within the tour from 0 to nodes.len()-1
 
 /// This represents a tour of a single vehicle (or a dummy tour). The following holds at all times:
@@ -43,7 +49,7 @@ type Position = usize; // the position within the tour from 0 to nodes.len()-1
 /// is created.
 #[derive(Clone)]
 pub struct Tour {
-    nodes: Vec<NodeIdx>, // nodes will always be sorted by start_time; for non-dummy exactly first
+    nodes: TourNodes, // nodes will always be sorted by start_time; for non-dummy exactly first
     // and last node is a depot
     is_dummy: bool, // true if this is a dummy tour
 
@@ -53,8 +59,9 @@ pub struct Tour {
     dead_head_distance: Distance, // distance covered by dead head trips
     // cost = service_trip_duration * costs.service_trip
     // + maintenance_time * costs.maintenance
-    // + dead_head_trip_duration * costs.dead_head_trip
-    // + idle_time * costs.idle
+    // + dead_head_trip_duration * costs.dead_head_trip (+ extra costs of dead-head time factors)
+    // + idle_time * costs.idle (a round trip to a siding is costed as dead-head trip, see
+    // Network::idle_costs_between)
     costs: Cost,
     network: Arc<Network>,
 }
@@ -65,24 +72,34 @@ impl Tour {
         self.is_dummy
     }
 
+    /// true if the tour visits a maintenance node or ends at a depot providing maintenance.
     pub fn visits_maintenance(&self) -> bool {
+        self.visits_maintenance || self.ends_at_maintenance_depot()
+    }
+
+    pub fn visits_maintenance_slot(&self) -> bool {
         self.visits_maintenance
     }
 
+    /// true if the tour ends at a depot providing maintenance (always false for dummy tours).
+    pub fn ends_at_maintenance_depot(&self) -> bool {
+        !self.is_dummy && self.network.is_maintenance_end_depot(self.last_node())
+    }
+
     pub fn length(&self) -> usize {
         self.nodes.len()
     }
 
     pub fn all_nodes_iter(&self) -> impl Iterator<Item = NodeIdx> + '_ {
-        self.nodes.iter().copied()
+        self.nodes.iter()
     }
 
     /// return an iterator over all nodes (by start time) skipping the depot at the start and end
     pub fn all_non_depot_nodes_iter(&self) -> impl Iterator<Item = NodeIdx> + '_ {
         if self.is_dummy {
-            self.nodes.iter().copied()
+            self.nodes.range(0..self.nodes.len())
         } else {
-            self.nodes[1..self.nodes.len() - 1].iter().copied()
+            self.nodes.range(1..self.nodes.len() - 1)
         }
     }
 
@@ -91,6 +108,21 @@ impl Tour {
         self.dead_head_distance
     }
 
+    /// number of dead-head trips of the tour, i.e., consecutive nodes where the second one starts
+    /// at another location than the first one ends (idling at the same location is not counted).
+    /// The synthetic legs from and to the overflow depot are not counted either.
+    pub fn dead_head_trip_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .tuple_windows()
+            .filter(|&(first, second)| {
+                let from = self.network.node(first).end_location();
+                let to = self.network.node(second).start_location();
+                from != to && from != Location::Nowhere && to != Location::Nowhere
+            })
+            .count()
+    }
+
     /// total useful duration of the tour (service time + maintenance time)
     pub fn useful_duration(&self) -> Duration {
         self.useful_duration
@@ -106,27 +138,70 @@ impl Tour {
         self.service_distance + self.dead_head_distance
     }
 
+    /// return the distance traveled from the start of the tour until each maintenance slot of the
+    /// tour (ordered by start time). Empty if the tour visits no maintenance slot.
+    pub fn distances_to_maintenance_slots(&self) -> Vec<Distance> {
+        if !self.visits_maintenance {
+            return Vec::new();
+        }
+        let mut distances = Vec::new();
+        let mut distance = Distance::ZERO;
+        let mut previous: Option<NodeIdx> = None;
+        for node in self.nodes.iter() {
+            if let Some(previous) = previous {
+                distance = distance + self.network.dead_head_distance_between(previous, node);
+            }
+            if self.network.node(node).is_maintenance() {
+                distances.push(distance);
+            }
+            distance = distance + self.network.node(node).travel_distance();
+            previous = Some(node);
+        }
+        distances
+    }
+
     /// return the maintenance counter of the tour which is the total distance traveled minus the
-    /// maximal distance allowed if the tour visits a maintenance node.
+    /// maximal distance allowed if the tour visits a maintenance node. A tour ending at a depot
+    /// providing maintenance is credited according to config.maintenance.depot_credit instead
+    /// (the larger credit counts if both apply).
     pub fn maintenance_counter(&self) -> MaintenanceCounter {
-        if self.visits_maintenance {
-            self.total_distance().in_meter().unwrap_or(INF_DISTANCE) as MaintenanceCounter
-                - self
-                    .network
-                    .config()
-                    .maintenance
-                    .maximal_distance
-                    .in_meter()
-                    .unwrap_or(INF_DISTANCE) as MaintenanceCounter
+        let maintenance_config = &self.network.config().maintenance;
+        let maximal_distance = maintenance_config
+            .maximal_distance
+            .in_meter()
+            .unwrap_or(INF_DISTANCE) as MaintenanceCounter;
+        let slot_credit = if self.visits_maintenance {
+            maximal_distance
         } else {
-            self.total_distance().in_meter().unwrap_or(INF_DISTANCE) as MaintenanceCounter
-        }
+            0
+        };
+        let depot_credit = if self.ends_at_maintenance_depot() {
+            match maintenance_config.depot_credit {
+                DepotMaintenanceCredit::Full => maximal_distance,
+                DepotMaintenanceCredit::Fixed(credit) => {
+                    credit.in_meter().unwrap_or(INF_DISTANCE) as MaintenanceCounter
+                }
+            }
+        } else {
+            0
+        };
+        self.total_distance().in_meter().unwrap_or(INF_DISTANCE) as MaintenanceCounter
+            - slot_credit.max(depot_credit)
     }
 
     pub fn costs(&self) -> Cost {
         self.costs
     }
 
+    /// the part of the costs charged by the staff time factors on the dead-head trips (see
+    /// Network::staff_time_surcharge_between). Computed on demand.
+    pub fn staff_time_surcharge(&self) -> Cost {
+        self.all_nodes_iter()
+            .tuple_windows()
+            .map(|(a, b)| self.network.staff_time_surcharge_between(a, b))
+            .sum()
+    }
+
     /// the overhead time (dead_head + idle) between the predecessor and the node itself
     /// for the first non-depot node, as well as a depot, the overhead time is set to be infinity.
     /// (this is to allow for splitting before the first non-depot node in all cases)
@@ -135,8 +210,19 @@ impl Tour {
             Ok(Duration::Infinity)
         } else {
             let pos = self.position_of(node)?;
-            let predecessor = *self.nodes.get(pos - 1).ok_or("invalid position")?;
-            Ok(self.network.node(node).start_time() - self.network.node(predecessor).end_time())
+            let predecessor = self.nodes.get(pos - 1).ok_or("invalid position")?;
+            overhead_between(
+                self.network.node(predecessor).end_time(),
+                self.network.node(node).start_time(),
+            )
+            .map_err(|error| {
+                format!(
+                    "{} starts before its predecessor {} ends: {}",
+                    self.network.node(node),
+                    self.network.node(predecessor),
+                    error
+                )
+            })
         }
     }
     /// the overhead time (dead_head + idle) between the node itself and its successor
@@ -147,8 +233,19 @@ impl Tour {
             Ok(Duration::Infinity)
         } else {
             let pos = self.position_of(node)?;
-            let successor = *self.nodes.get(pos + 1).ok_or("invalid position")?;
-            Ok(self.network.node(successor).start_time() - self.network.node(node).end_time())
+            let successor = self.nodes.get(pos + 1).ok_or("invalid position")?;
+            overhead_between(
+                self.network.node(node).end_time(),
+                self.network.node(successor).start_time(),
+            )
+            .map_err(|error| {
+                format!(
+                    "{} ends after its successor {} starts: {}",
+                    self.network.node(node),
+                    self.network.node(successor),
+                    error
+                )
+            })
         }
     }
 
@@ -158,15 +255,15 @@ impl Tour {
     }
 
     pub fn first_node(&self) -> NodeIdx {
-        *self.nodes.first().unwrap()
+        self.nodes.first().unwrap()
     }
 
     pub fn last_node(&self) -> NodeIdx {
-        *self.nodes.last().unwrap()
+        self.nodes.last().unwrap()
     }
 
     pub fn nth_node(&self, pos: Position) -> Option<NodeIdx> {
-        self.nodes.get(pos).copied()
+        self.nodes.get(pos)
     }
 
     pub fn first_non_depot(&self) -> Option<NodeIdx> {
@@ -179,8 +276,7 @@ impl Tour {
         self.nodes
             .iter()
             .rev()
-            .find(|&&n| !self.network.node(n).is_depot())
-            .copied()
+            .find(|&n| !self.network.node(n).is_depot())
     }
 
     pub fn start_depot(&self) -> Result<NodeIdx, String> {
@@ -237,7 +333,7 @@ impl Tour {
     /// provided node.
     /// If all nodes can reach the provided node, None is returned.
     pub fn latest_not_reaching_node(&self, node: NodeIdx) -> Option<Position> {
-        if self.network.can_reach(*self.nodes.last().unwrap(), node) {
+        if self.network.can_reach(self.last_node(), node) {
             return None; // all tour-nodes can reach node, even the last
         }
         let candidate =
@@ -256,32 +352,34 @@ impl Tour {
     /// cannot reach segment.start(), or segment.end() cannot reach end node).
     pub fn conflict(&self, segment: Segment) -> Option<Path> {
         let (start_pos, end_pos) = self.get_insert_positions(segment);
-        Path::new_trusted(
-            self.nodes[start_pos..end_pos].to_vec(),
-            self.network.clone(),
-        )
+        Path::new_trusted(self.nodes.sub_vec(start_pos..end_pos), self.network.clone())
     }
 
     /// Return the path given by the segment.
     pub fn sub_path(&self, segment: Segment) -> Result<Path, String> {
+        let not_part_of_tour =
+            |node: NodeIdx| format!("{} is not part of the tour.", self.network.node(node));
         let start_pos = self
             .latest_not_reaching_node(segment.start())
-            .ok_or_else(|| String::from("segment.start() not part of Tour."))?;
+            .ok_or_else(|| not_part_of_tour(segment.start()))?;
         if segment.start() != self.nodes[start_pos] {
-            return Err(String::from("segment.start() not part of Tour."));
+            return Err(not_part_of_tour(segment.start()));
         }
         let end_pos = self
             .latest_not_reaching_node(segment.end())
-            .ok_or_else(|| String::from("segment.end() not part of Tour."))?;
+            .ok_or_else(|| not_part_of_tour(segment.end()))?;
         if segment.end() != self.nodes[end_pos] {
-            return Err(String::from("segment.end() not part of Tour."));
+            return Err(not_part_of_tour(segment.end()));
         }
         if start_pos > end_pos {
-            return Err(String::from("segment.start() is after segment.end()."));
+            return Err(format!(
+                "Segment {} starts after it ends.",
+                segment.describe(&self.network)
+            ));
         }
 
         Ok(Path::new_trusted(
-            self.nodes[start_pos..end_pos + 1].to_vec(),
+            self.nodes.sub_vec(start_pos..end_pos + 1),
             self.network.clone(),
         )
         .expect("segment is empty path."))
@@ -295,14 +393,14 @@ impl Tour {
         );
         for node in self.nodes.iter() {
             print!("\t* ");
-            self.network.node(*node).print();
+            self.network.node(node).print();
         }
     }
 
     pub fn verify_consistency(&self) {
         // check reachability
         for (node1, node2) in self.nodes.iter().tuple_windows() {
-            assert!(self.network.can_reach(*node1, *node2));
+            assert!(self.network.can_reach(node1, node2));
         }
 
         // check if non-dummy tour starts and ends with depots
@@ -315,7 +413,7 @@ impl Tour {
         assert_eq!(
             self.nodes
                 .iter()
-                .any(|&n| self.network.node(n).is_maintenance()),
+                .any(|n| self.network.node(n).is_maintenance()),
             self.visits_maintenance
         );
 
@@ -375,7 +473,12 @@ impl Tour {
                 .network
                 .can_reach(self.nodes[start_position - 1], self.nodes[end_position + 1])
         {
-            return Err(format!("Removing nodes ({} to {}) makes the tour invalid. Dead-head-trip is slower than service-trips.", self.nodes[start_position], self.nodes[end_position]));
+            return Err(format!(
+                "Removing nodes ({} to {}) makes the tour invalid. Dead-head-trip is slower than \
+                service-trips.",
+                self.network.node(self.nodes[start_position]),
+                self.network.node(self.nodes[end_position])
+            ));
         }
         Ok(())
     }
@@ -409,7 +512,7 @@ impl Tour {
     /// computes the position of the latest tour-node that is not reached by node.
     /// if node can reach all tour-nodes, None is returned.
     fn latest_not_reached_by_node(&self, node: NodeIdx) -> Option<Position> {
-        if self.network.can_reach(node, *self.nodes.first().unwrap()) {
+        if self.network.can_reach(node, self.first_node()) {
             return None; // node can reach all nodes, even the first
         }
         // the candidate cannot be reached by node for sure.
@@ -469,19 +572,20 @@ impl Tour {
     }
 
     fn compute_useful_duration(&self) -> Duration {
-        Tour::compute_useful_duration_of_nodes(&self.nodes, &self.network)
+        Tour::compute_useful_duration_of_nodes(&self.nodes.to_vec(), &self.network)
     }
 
     fn compute_service_distance(&self) -> Distance {
-        Tour::compute_service_distance_of_nodes(&self.nodes, &self.network)
+        Tour::compute_service_distance_of_nodes(&self.nodes.to_vec(), &self.network)
     }
 
     fn compute_dead_head_distance(&self) -> Distance {
-        Tour::compute_dead_head_distance_of_nodes(&self.nodes, &self.network)
+        Tour::compute_dead_head_distance_of_nodes(&self.nodes.to_vec(), &self.network)
     }
 
-    fn compute_costs(&self) -> Cost {
-        Self::compute_costs_of_nodes(&self.nodes, &self.network)
+    /// Recomputes the costs from scratch (the stored costs are updated incrementally).
+    pub(crate) fn compute_costs(&self) -> Cost {
+        Self::compute_costs_of_nodes(&self.nodes.to_vec(), &self.network)
     }
 }
 
@@ -497,8 +601,8 @@ impl Ord for Tour {
                 .zip(other.nodes.iter())
                 .map(|(node, other_node)| {
                     self.network
-                        .node(*node)
-                        .cmp_start_time(self.network.node(*other_node))
+                        .node(node)
+                        .cmp_start_time(self.network.node(other_node))
                 })
                 .find(|ord| *ord != Ordering::Equal)
             {
@@ -526,11 +630,14 @@ impl Eq for Tour {}
 
 impl fmt::Display for Tour {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // write!(f, "{}", self.nodes.iter().map(|n| self.network.node(*n).to_string()).join(" - "))?;
+        // the ids only, as the full names of the nodes would make long tours unreadable
         write!(
             f,
             "{}",
-            self.nodes.iter().map(|n| self.network.node(*n)).join(" - ")
+            self.nodes
+                .iter()
+                .map(|n| self.network.node(n).id())
+                .join(" - ")
         )?;
         Ok(())
     }
@@ -543,9 +650,11 @@ impl Tour {
     /// * end with an EndNode
     /// * only Service or MaintenanceNodes in the middle
     /// * each node can reach its successor
+    /// * each node ends after it starts
     /// If one of the checks fails an error message is returned.
     pub(super) fn new(nodes: Vec<NodeIdx>, network: Arc<Network>) -> Result<Tour, String> {
-        Tour::new_allow_invalid(nodes, network).map_err(|(_, error_msg)| error_msg)
+        Tour::check_node_durations(&nodes, &network)?;
+        Tour::new_allow_invalid(nodes, network).map_err(|error| error.1)
     }
 
     /// Creates a new tour from a vector of NodeIds. Checks that the tour is valid:
@@ -558,7 +667,7 @@ impl Tour {
     pub(super) fn new_allow_invalid(
         nodes: Vec<NodeIdx>,
         network: Arc<Network>,
-    ) -> Result<Tour, (Tour, String)> {
+    ) -> Result<Tour, Box<(Tour, String)>> {
         let mut error_msg = String::new();
         if !network.node(nodes[0]).is_start_depot() {
             error_msg.push_str(&format!(
@@ -594,7 +703,10 @@ impl Tour {
             }
         }
         if !error_msg.is_empty() {
-            Err((Tour::new_computing(nodes, false, network), error_msg))
+            Err(Box::new((
+                Tour::new_computing(nodes, false, network),
+                error_msg,
+            )))
         } else {
             Ok(Tour::new_computing(nodes, false, network))
         }
@@ -607,9 +719,18 @@ impl Tour {
         if nodes.is_empty() {
             return Err("Dummy tour needs to have at least one service nodes.".to_string());
         }
+        Tour::check_node_durations(&nodes, &network)?;
         Ok(Tour::new_computing(nodes, true, network))
     }
 
+    /// The durations of the nodes are summed up when computing a tour, so a node ending before it
+    /// starts is reported here instead of letting the subtraction fail later on.
+    fn check_node_durations(nodes: &[NodeIdx], network: &Network) -> Result<(), String> {
+        nodes
+            .iter()
+            .try_for_each(|&node| network.node(node).checked_duration().map(|_| ()))
+    }
+
     fn new_computing(nodes: Vec<NodeIdx>, is_dummy: bool, network: Arc<Network>) -> Tour {
         let useful_duration = Tour::compute_useful_duration_of_nodes(&nodes, &network);
         let service_distance = Tour::compute_service_distance_of_nodes(&nodes, &network);
@@ -618,7 +739,7 @@ impl Tour {
         let visits_maintenance = Tour::compute_visits_maintenance(&nodes, &network);
 
         Tour::new_precomputed(
-            nodes,
+            TourNodes::from(nodes),
             is_dummy,
             visits_maintenance,
             useful_duration,
@@ -672,16 +793,7 @@ impl Tour {
                 .iter()
                 .tuple_windows()
                 .map(|(a, b)| {
-                    network
-                        .dead_head_time_between(*a, *b)
-                        .in_sec()
-                        .unwrap_or(network.planning_days().in_sec().unwrap())
-                        * network.config().costs.dead_head_trip
-                        + network
-                            .idle_time_between(*a, *b)
-                            .in_sec()
-                            .unwrap_or(network.planning_days().in_sec().unwrap())
-                            * network.config().costs.idle
+                    network.dead_head_costs_between(*a, *b) + network.idle_costs_between(*a, *b)
                 })
                 .sum::<Cost>()
     }
@@ -690,10 +802,11 @@ impl Tour {
         nodes.iter().any(|&n| network.node(n).is_maintenance())
     }
 
-    /// Creates a new tour from a vector of NodeIds. Trusts that the vector leads to a valid Tour.
+    /// Creates a new tour from a sequence of NodeIds. Trusts that the sequence leads to a valid
+    /// Tour.
     #[allow(clippy::too_many_arguments)]
     fn new_precomputed(
-        nodes: Vec<NodeIdx>,
+        nodes: TourNodes,
         is_dummy: bool,
         visits_maintenance: bool,
         useful_duration: Duration,
@@ -714,3 +827,15 @@ impl Tour {
         }
     }
 }
+
+/// the time between the end of a node and the start of the next one. If one of them is a depot
+/// (i.e., the span is unbounded) the overhead is infinity, a negative gap is an error.
+fn overhead_between(end: DateTime, start: DateTime) -> Result<Duration, String> {
+    if [end, start]
+        .iter()
+        .any(|&time| time == DateTime::Earliest || time == DateTime::Latest)
+    {
+        return Ok(Duration::Infinity);
+    }
+    checked_duration_between(end, start)
+}
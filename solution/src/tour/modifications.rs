@@ -31,11 +31,10 @@ impl Tour {
         if !self.network.node(new_start_depot).is_start_depot() {
             return Err("node has to be start depot".to_string());
         }
-        let mut nodes = self.nodes.clone();
-        nodes[0] = new_start_depot;
+        let nodes = self.nodes.replace(0, new_start_depot);
         let first_non_depot = nodes[1];
         let new_dead_head_distance = if self.dead_head_distance == Distance::Infinity {
-            Tour::compute_dead_head_distance_of_nodes(&nodes, &self.network)
+            Tour::compute_dead_head_distance_of_nodes(&nodes.to_vec(), &self.network)
         } else {
             self.dead_head_distance
                 - self
@@ -48,16 +47,10 @@ impl Tour {
         let new_costs = self.costs
             - self
                 .network
-                .dead_head_time_between(self.first_node(), first_non_depot)
-                .in_sec()
-                .unwrap_or(self.network.planning_days().in_sec().unwrap())
-                * self.network.config().costs.dead_head_trip
+                .dead_head_costs_between(self.first_node(), first_non_depot)
             + self
                 .network
-                .dead_head_time_between(new_start_depot, first_non_depot)
-                .in_sec()
-                .unwrap_or(self.network.planning_days().in_sec().unwrap())
-                * self.network.config().costs.dead_head_trip;
+                .dead_head_costs_between(new_start_depot, first_non_depot);
         // there is no idle time.
 
         Ok(Tour::new_precomputed(
@@ -80,13 +73,12 @@ impl Tour {
         if !self.network.node(new_end_depot).is_end_depot() {
             return Err("node has to be end depot".to_string());
         }
-        let mut nodes = self.nodes.clone();
-        let end_index = nodes.len() - 1;
-        nodes[end_index] = new_end_depot;
+        let end_index = self.nodes.len() - 1;
+        let nodes = self.nodes.replace(end_index, new_end_depot);
         let last_non_depot = nodes[end_index - 1];
 
         let new_dead_head_distance = if self.dead_head_distance == Distance::Infinity {
-            Tour::compute_dead_head_distance_of_nodes(&nodes, &self.network)
+            Tour::compute_dead_head_distance_of_nodes(&nodes.to_vec(), &self.network)
         } else {
             self.dead_head_distance
                 - self
@@ -100,16 +92,10 @@ impl Tour {
         let new_costs = self.costs
             - self
                 .network
-                .dead_head_time_between(last_non_depot, self.last_node())
-                .in_sec()
-                .unwrap_or(self.network.planning_days().in_sec().unwrap())
-                * self.network.config().costs.dead_head_trip
+                .dead_head_costs_between(last_non_depot, self.last_node())
             + self
                 .network
-                .dead_head_time_between(last_non_depot, new_end_depot)
-                .in_sec()
-                .unwrap_or(self.network.planning_days().in_sec().unwrap())
-                * self.network.config().costs.dead_head_trip;
+                .dead_head_costs_between(last_non_depot, new_end_depot);
         // there is no idle time.
 
         Ok(Tour::new_precomputed(
@@ -162,9 +148,7 @@ impl Tour {
             };
 
         // remove the segment from the tour:
-        let mut tour_nodes: Vec<NodeIdx> = self.nodes[..pos_seg_start].to_vec();
-        tour_nodes.extend(self.nodes[pos_seg_end + 1..].iter().copied());
-        let removed_nodes: Vec<NodeIdx> = self.nodes[pos_seg_start..pos_seg_end + 1].to_vec();
+        let (tour_nodes, removed_nodes) = self.nodes.splice(pos_seg_start..pos_seg_end + 1, vec![]);
         if tour_nodes.is_empty() || (!self.is_dummy() && tour_nodes.len() <= 2) {
             return Ok((
                 None,
@@ -184,7 +168,7 @@ impl Tour {
                 .any(|n| self.network.node(*n).is_maintenance())
                 || tour_nodes
                     .iter()
-                    .any(|n| self.network.node(*n).is_maintenance()));
+                    .any(|n| self.network.node(n).is_maintenance()));
 
         Ok((
             Some(Tour::new_precomputed(
@@ -259,10 +243,7 @@ impl Tour {
 
         // remove all elements in start_pos..end_pos and replace them by
         // node_sequence.
-        let mut new_tour_nodes = self.nodes.clone();
-        let removed_nodes: Vec<NodeIdx> = new_tour_nodes
-            .splice(start_pos..end_pos, new_nodes)
-            .collect();
+        let (new_tour_nodes, removed_nodes) = self.nodes.splice(start_pos..end_pos, new_nodes);
 
         // 1) if new path contains maintenance then the new tour has a maintenance node. Otherwise:
         // 2) if the old tour had no maintenance node than the new tour has no maintenance node either.
@@ -277,7 +258,7 @@ impl Tour {
                     .any(|n| self.network.node(*n).is_maintenance())
                     || new_tour_nodes
                         .iter()
-                        .any(|n| self.network.node(*n).is_maintenance())));
+                        .any(|n| self.network.node(n).is_maintenance())));
 
         (
             Tour::new_precomputed(
@@ -449,32 +430,17 @@ impl Tour {
     /// If pos is the last node, panics.
     fn dead_head_and_idle_costs_after_node_unchecked(&self, pos: Position) -> Cost {
         self.network
-            .dead_head_time_between(self.nodes[pos], self.nodes[pos + 1])
-            .in_sec()
-            .unwrap_or(self.network.planning_days().in_sec().unwrap())
-            * self.network.config().costs.dead_head_trip
+            .dead_head_costs_between(self.nodes[pos], self.nodes[pos + 1])
             + self
                 .network
-                .idle_time_between(self.nodes[pos], self.nodes[pos + 1])
-                .in_sec()
-                .unwrap_or(self.network.planning_days().in_sec().unwrap())
-                * self.network.config().costs.idle
+                .idle_costs_between(self.nodes[pos], self.nodes[pos + 1])
     }
 
     /// Returns the costs for the dead head trip and the idle time between the two nodes assuming
     /// no intermediate stops.
     fn dead_head_and_idle_costs_between_two_nodes(&self, node1: NodeIdx, node2: NodeIdx) -> Cost {
-        self.network
-            .dead_head_time_between(node1, node2)
-            .in_sec()
-            .unwrap_or(self.network.planning_days().in_sec().unwrap())
-            * self.network.config().costs.dead_head_trip
-            + self
-                .network
-                .idle_time_between(node1, node2)
-                .in_sec()
-                .unwrap_or(self.network.planning_days().in_sec().unwrap())
-                * self.network.config().costs.idle
+        self.network.dead_head_costs_between(node1, node2)
+            + self.network.idle_costs_between(node1, node2)
     }
 
     fn service_and_maintenance_costs_by_pos(&self, pos: Position) -> Cost {
@@ -12,16 +12,17 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use itertools::assert_equal;
-use model::base_types::Distance;
+use model::base_types::{Distance, NodeIdx};
+use model::network::PlatformIdle;
 use rapid_time::{DateTime, Duration};
 
 use crate::{
     path::Path,
     segment::Segment,
-    test_utilities::{init_test_data, TestData},
+    test_utilities::{init_test_data, init_test_data_with, TestData},
 };
 
-use super::Tour;
+use super::{tour_nodes::TourNodes, Tour};
 
 fn default_tour(d: &TestData) -> Tour {
     Tour::new(
@@ -253,6 +254,41 @@ fn invalid_constructor_test() {
     assert!(invalid_dummy_tour3.is_err());
 }
 
+#[test]
+fn error_messages_name_nodes_by_input_ids_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let tour = default_tour(&d);
+
+    // ACT
+    let constructor_error = Tour::new(
+        vec![d.start_depot1, d.trip23, d.trip12, d.end_depot2],
+        d.network.clone(),
+    )
+    .err()
+    .unwrap();
+    let sub_path_error = tour
+        .sub_path(Segment::new(d.trip31, d.trip51))
+        .err()
+        .unwrap();
+    let path_error = Path::new(vec![d.trip23, d.trip12], d.network.clone())
+        .err()
+        .unwrap();
+
+    // ASSERT
+    assert!(constructor_error.contains("trip dep_segment_2-3 (route route_2-3, departure "));
+    assert!(constructor_error.contains("dep_segment_1-2"));
+    assert!(sub_path_error.contains("dep_segment_3-1"));
+    assert!(path_error.contains("dep_segment_2-3"));
+    assert!(!path_error.contains("trip_13"));
+    // the display of the tour itself lists the ids only
+    assert_eq!(
+        tour.to_string(),
+        "s_depot1 - dep_segment_1-2 - dep_segment_2-3 - dep_segment_3-4 - dep_segment_4-5 - \
+        dep_segment_5-1 - e_depot2"
+    );
+}
+
 #[test]
 fn conflict_test() {
     // ARRANGE
@@ -440,6 +476,72 @@ fn remove_test() {
     new_tour.verify_consistency();
 }
 
+#[test]
+fn dead_head_trip_count_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let tour = default_tour(&d);
+    let overflow_tour = Tour::new(
+        vec![d.start_overflow_depot, d.trip34, d.end_overflow_depot],
+        d.network.clone(),
+    )
+    .unwrap();
+
+    // ACT
+    // the leg from loc2 to loc3 is split off when trip23 is removed ...
+    let (tour_without_trip23, _) = tour.remove(Segment::new(d.trip23, d.trip23)).unwrap();
+    let tour_without_trip23 = tour_without_trip23.unwrap();
+    // ... and merged again when it is inserted
+    let (tour_with_trip23, _) = tour_without_trip23.insert_path(
+        Path::new(vec![d.trip23], d.network.clone())
+            .unwrap()
+            .unwrap(),
+    );
+    let (short_tour, _) = tour.remove(Segment::new(d.trip12, d.trip45)).unwrap();
+
+    // ASSERT
+    // only the leg from trip51 (ends at loc1) to end_depot2 (loc2)
+    assert_eq!(tour.dead_head_trip_count(), 1);
+    assert_eq!(tour_without_trip23.dead_head_trip_count(), 2);
+    assert_eq!(tour_with_trip23.dead_head_trip_count(), 1);
+    // start_depot1 (loc1) -> trip51 (loc5 -> loc1) -> end_depot2 (loc2)
+    assert_eq!(short_tour.unwrap().dead_head_trip_count(), 2);
+    // the synthetic legs from and to the overflow depot are not counted
+    assert_eq!(overflow_tour.dead_head_trip_count(), 0);
+}
+
+#[test]
+fn same_location_succession_with_dirty_dead_head_diagonal_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let dirty = init_test_data_with(|instance| {
+        for i in 0..5 {
+            instance["deadHeadTrips"]["durations"][i][i] = 3600.into();
+            instance["deadHeadTrips"]["distances"][i][i] = 9000.into();
+        }
+    });
+
+    // ACT
+    // consecutive trips of the default tour meet at the same location
+    let tour = default_tour(&d);
+    let dirty_tour = default_tour(&dirty);
+
+    // ASSERT
+    assert_eq!(
+        dirty.network.dead_head_time_between(d.trip12, d.trip23),
+        Duration::ZERO
+    );
+    assert_eq!(
+        dirty.network.idle_time_between(d.trip12, d.trip23),
+        d.network.idle_time_between(d.trip12, d.trip23)
+    );
+    assert_eq!(dirty_tour.start_time(), tour.start_time());
+    assert_eq!(dirty_tour.end_time(), tour.end_time());
+    assert_eq!(dirty_tour.dead_head_distance(), tour.dead_head_distance());
+    assert_eq!(dirty_tour.costs(), tour.costs());
+    assert_eq!(dirty_tour.dead_head_trip_count(), 1);
+}
+
 #[test]
 fn sub_path_test() {
     // ARRANGE
@@ -844,3 +946,155 @@ fn remove_invalid_segment() {
     assert!(start_depot_result.is_err());
     assert!(end_depot_result.is_err());
 }
+
+#[test]
+fn dead_head_time_factor_test() {
+    // ARRANGE
+    // dead-head trips from loc2 to loc3 or loc4 take twice as long and cost 1000 more if they
+    // depart within the given window
+    let init_with_window = |start: &str, end: &str| {
+        init_test_data_with(|instance| {
+            instance["deadHeadTimeFactors"] = serde_json::json!([{
+                "origins": ["loc2"],
+                "destinations": ["loc3", "loc4"],
+                "start": start,
+                "end": end,
+                "durationFactor": 2.0,
+                "extraCost": 1000
+            }]);
+        })
+    };
+    let off_peak = init_with_window("2020-01-01T16:00:00", "2020-01-01T19:00:00");
+    let peak = init_with_window("2020-01-01T06:00:00", "2020-01-01T07:00:00");
+    let tour_nodes = |d: &TestData| vec![d.start_depot1, d.trip12, d.trip45, d.end_depot5];
+
+    // ACT
+    let off_peak_tour = Tour::new(tour_nodes(&off_peak), off_peak.network.clone()).unwrap();
+    let peak_tour = Tour::new(tour_nodes(&peak), peak.network.clone()).unwrap();
+
+    // ASSERT
+    // trip12 ends at 6:30 at loc2, trip34 starts at 8:00 at loc3.
+    // off-peak: 6:30 + 45min (dead-head) + 5min (shunting) <= 8:00
+    // peak: 6:30 + 90min (dead-head) + 5min (shunting) > 8:00
+    assert!(off_peak.network.can_reach(off_peak.trip12, off_peak.trip34));
+    assert!(!peak.network.can_reach(peak.trip12, peak.trip34));
+    assert_eq!(
+        off_peak
+            .network
+            .dead_head_time_between(off_peak.trip12, off_peak.trip34),
+        Duration::new("0:45")
+    );
+    assert_eq!(
+        peak.network
+            .dead_head_time_between(peak.trip12, peak.trip34),
+        Duration::new("1:30")
+    );
+    assert_eq!(
+        peak.network
+            .dead_head_costs_between(peak.trip12, peak.trip34),
+        500 * 90 * 60 + 1000
+    );
+    // trips departing outside of the window or at other locations are not affected
+    assert_eq!(
+        peak.network
+            .dead_head_time_between(peak.trip23, peak.trip45),
+        Duration::new("0:45")
+    );
+
+    // the dead-head trip from trip12 to trip45 is 45min longer (and the idle time 45min shorter)
+    assert_eq!(
+        peak_tour.costs() - off_peak_tour.costs(),
+        500 * 45 * 60 + 1000 - 20 * 45 * 60
+    );
+}
+
+#[test]
+fn platform_idle_test() {
+    // ARRANGE
+    // loc2 and loc4 are 10 minutes apart
+    let init = |platform_idle_limit: Option<u64>, siding: bool| {
+        init_test_data_with(|instance| {
+            instance["deadHeadTrips"]["durations"][1][3] = 600.into();
+            instance["deadHeadTrips"]["durations"][3][1] = 600.into();
+            if let Some(limit) = platform_idle_limit {
+                instance["locations"][1]["platformIdleLimit"] = limit.into();
+            }
+            instance["locations"][3]["isSiding"] = siding.into();
+        })
+    };
+    let unlimited = init(None, true);
+    let with_siding = init(Some(1200), true);
+    let without_siding = init(Some(1200), false);
+    let long_limit = init(Some(1800), true);
+
+    // ACT
+    let unlimited_tour = default_tour(&unlimited);
+    let with_siding_tour = default_tour(&with_siding);
+    let without_siding_tour = default_tour(&without_siding);
+    let long_limit_tour = default_tour(&long_limit);
+
+    // ASSERT
+    // trip12 arrives at loc2 at 6:30, trip23 departs from loc2 at 7:00. The vehicle idles for 30
+    // minutes, the round trip to the siding at loc4 takes 20 minutes.
+    assert_eq!(
+        with_siding
+            .network
+            .platform_idle_between(with_siding.trip12, with_siding.trip23),
+        Some(PlatformIdle::MovedToSiding(
+            with_siding.network.node(with_siding.trip34).end_location(),
+            Duration::new("0:20")
+        ))
+    );
+    assert_eq!(
+        without_siding
+            .network
+            .platform_idle_between(without_siding.trip12, without_siding.trip23),
+        Some(PlatformIdle::Violation)
+    );
+    assert_eq!(
+        long_limit
+            .network
+            .platform_idle_between(long_limit.trip12, long_limit.trip23),
+        None
+    );
+
+    // the round trip is costed as dead-head trip instead of idle time
+    assert_eq!(
+        with_siding_tour.costs() - unlimited_tour.costs(),
+        (500 - 20) * 20 * 60
+    );
+    // violations and idle times within the limit are costed as idle time
+    assert_eq!(without_siding_tour.costs(), unlimited_tour.costs());
+    assert_eq!(long_limit_tour.costs(), unlimited_tour.costs());
+}
+
+#[test]
+fn long_tour_nodes_splice_test() {
+    // ARRANGE
+    let nodes: Vec<NodeIdx> = (0..100).map(NodeIdx::service_from).collect();
+    let long_nodes = TourNodes::from(nodes.clone());
+    let inserted: Vec<NodeIdx> = (200..203).map(NodeIdx::service_from).collect();
+
+    // ACT
+    let (spliced, removed) = long_nodes.splice(10..20, inserted.clone());
+    let (shrinked, removed_bulk) = spliced.splice(5..60, vec![]);
+    let replaced = shrinked.replace(3, NodeIdx::service_from(300));
+
+    // ASSERT
+    let mut expected = nodes.clone();
+    let expected_removed: Vec<NodeIdx> = expected.splice(10..20, inserted).collect();
+    assert!(matches!(long_nodes, TourNodes::Long(_)));
+    assert!(matches!(spliced, TourNodes::Long(_)));
+    assert_eq!(spliced.to_vec(), expected);
+    assert_eq!(removed, expected_removed);
+
+    let expected_removed_bulk: Vec<NodeIdx> = expected.splice(5..60, vec![]).collect();
+    assert!(matches!(shrinked, TourNodes::Short(_)));
+    assert_eq!(shrinked.to_vec(), expected);
+    assert_eq!(removed_bulk, expected_removed_bulk);
+
+    expected[3] = NodeIdx::service_from(300);
+    assert_equal(replaced.iter(), expected.iter().copied());
+    assert_equal(replaced.range(2..5), expected[2..5].iter().copied());
+    assert_eq!(shrinked.len(), 38);
+}
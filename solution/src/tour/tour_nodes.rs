@@ -0,0 +1,169 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+use std::ops::{Index, Range};
+
+use itertools::Either;
+use model::base_types::NodeIdx;
+
+/// Tours with at least this many nodes store their nodes in a persistent vector.
+pub(crate) const LONG_TOUR_THRESHOLD: usize = 64;
+
+/// The node sequence of a tour.
+///
+/// As tours are immutable, each modification copies the node sequence. For short tours a plain
+/// Vec is fastest. Long tours (e.g., a full week with hundreds of nodes) use a persistent vector
+/// (RRB-tree of the im crate) instead, so that a copy is O(1) and splicing a segment in or out is
+/// O(log n) instead of O(n).
+#[derive(Clone)]
+pub(crate) enum TourNodes {
+    Short(Vec<NodeIdx>),
+    Long(im::Vector<NodeIdx>),
+}
+
+// methods
+impl TourNodes {
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            TourNodes::Short(nodes) => nodes.len(),
+            TourNodes::Long(nodes) => nodes.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn get(&self, pos: usize) -> Option<NodeIdx> {
+        match self {
+            TourNodes::Short(nodes) => nodes.get(pos).copied(),
+            TourNodes::Long(nodes) => nodes.get(pos).copied(),
+        }
+    }
+
+    pub(crate) fn first(&self) -> Option<NodeIdx> {
+        self.get(0)
+    }
+
+    pub(crate) fn last(&self) -> Option<NodeIdx> {
+        self.len().checked_sub(1).and_then(|pos| self.get(pos))
+    }
+
+    pub(crate) fn iter(&self) -> impl DoubleEndedIterator<Item = NodeIdx> + '_ {
+        match self {
+            TourNodes::Short(nodes) => Either::Left(nodes.iter().copied()),
+            TourNodes::Long(nodes) => Either::Right(nodes.iter().copied()),
+        }
+    }
+
+    /// iterator over the nodes at the positions in range.
+    pub(crate) fn range(
+        &self,
+        range: Range<usize>,
+    ) -> impl DoubleEndedIterator<Item = NodeIdx> + '_ {
+        match self {
+            TourNodes::Short(nodes) => Either::Left(nodes[range].iter().copied()),
+            TourNodes::Long(nodes) => Either::Right(nodes.clone().slice(range).into_iter()),
+        }
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<NodeIdx> {
+        self.iter().collect()
+    }
+
+    pub(crate) fn sub_vec(&self, range: Range<usize>) -> Vec<NodeIdx> {
+        self.range(range).collect()
+    }
+
+    pub(crate) fn binary_search_by(
+        &self,
+        f: impl FnMut(&NodeIdx) -> Ordering,
+    ) -> Result<usize, usize> {
+        match self {
+            TourNodes::Short(nodes) => nodes.binary_search_by(f),
+            TourNodes::Long(nodes) => nodes.binary_search_by(f),
+        }
+    }
+
+    /// returns a copy where the node at pos is replaced by node.
+    pub(crate) fn replace(&self, pos: usize, node: NodeIdx) -> TourNodes {
+        match self {
+            TourNodes::Short(nodes) => {
+                let mut nodes = nodes.clone();
+                nodes[pos] = node;
+                TourNodes::Short(nodes)
+            }
+            TourNodes::Long(nodes) => TourNodes::Long(nodes.update(pos, node)),
+        }
+    }
+
+    /// returns a copy where the nodes at the positions in range are replaced by new_nodes, as
+    /// well as the replaced nodes.
+    pub(crate) fn splice(
+        &self,
+        range: Range<usize>,
+        new_nodes: Vec<NodeIdx>,
+    ) -> (TourNodes, Vec<NodeIdx>) {
+        match self {
+            TourNodes::Short(nodes) => {
+                let mut nodes = nodes.clone();
+                let removed_nodes = nodes.splice(range, new_nodes).collect();
+                (TourNodes::from(nodes), removed_nodes)
+            }
+            TourNodes::Long(nodes) => {
+                let mut nodes = nodes.clone();
+                let mut removed_nodes = nodes.split_off(range.start);
+                let suffix = removed_nodes.split_off(range.end - range.start);
+                nodes.extend(new_nodes);
+                nodes.append(suffix);
+                (
+                    TourNodes::from_vector(nodes),
+                    removed_nodes.into_iter().collect(),
+                )
+            }
+        }
+    }
+}
+
+// static functions
+impl TourNodes {
+    fn from_vector(nodes: im::Vector<NodeIdx>) -> TourNodes {
+        if nodes.len() < LONG_TOUR_THRESHOLD {
+            TourNodes::Short(nodes.into_iter().collect())
+        } else {
+            TourNodes::Long(nodes)
+        }
+    }
+}
+
+impl From<Vec<NodeIdx>> for TourNodes {
+    fn from(nodes: Vec<NodeIdx>) -> TourNodes {
+        if nodes.len() < LONG_TOUR_THRESHOLD {
+            TourNodes::Short(nodes)
+        } else {
+            TourNodes::Long(nodes.into_iter().collect())
+        }
+    }
+}
+
+impl Index<usize> for TourNodes {
+    type Output = NodeIdx;
+
+    fn index(&self, pos: usize) -> &NodeIdx {
+        match self {
+            TourNodes::Short(nodes) => &nodes[pos],
+            TourNodes::Long(nodes) => &nodes[pos],
+        }
+    }
+}
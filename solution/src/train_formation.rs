@@ -11,98 +11,179 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+#[cfg(test)]
+mod tests;
+
 use std::fmt;
+use std::sync::{Arc, OnceLock};
 
-use model::base_types::{PassengerCount, VehicleCount, VehicleIdx};
+use model::base_types::{PassengerCount, VehicleCount, VehicleIdx, VehicleTypeIdx};
 
 use crate::vehicle::Vehicle;
 use std::iter::Iterator;
 
+/// The members are shared between all clones (copy-on-write): a modification builds a new member
+/// list, unchanged formations are never copied. All empty formations share the same member list.
 #[derive(Clone)]
 pub struct TrainFormation {
-    formation: Vec<Vehicle>, // index 0 is at front, index len()-1 is tail
+    formation: Arc<[Vehicle]>, // index 0 is at front, index len()-1 is tail
 }
 
 // static functions
 impl TrainFormation {
     pub(crate) fn empty() -> TrainFormation {
+        static EMPTY: OnceLock<Arc<[Vehicle]>> = OnceLock::new();
+        TrainFormation {
+            formation: EMPTY.get_or_init(|| Arc::from(Vec::new())).clone(),
+        }
+    }
+
+    fn from_members(members: impl Iterator<Item = Vehicle>) -> TrainFormation {
         TrainFormation {
-            formation: Vec::new(),
+            formation: members.collect(),
         }
     }
 }
 
 // methods
 impl TrainFormation {
+    /// The new vehicle takes the position of the old one.
     pub(crate) fn replace(&self, old: VehicleIdx, new: Vehicle) -> Result<TrainFormation, String> {
-        let mut new_formation = self.formation.clone();
-        let pos = new_formation
-            .iter()
-            .position(|u| u.idx() == old)
-            .ok_or_else(|| {
-                format!(
-                    "vehicle {} was not part of the TrainFormation and cannot be replaced",
-                    old
-                )
-            })?;
+        let pos = self.position_of(old).ok_or_else(|| {
+            format!(
+                "vehicle {} was not part of the TrainFormation and cannot be replaced",
+                old
+            )
+        })?;
+        if old == new.idx() {
+            return Ok(self.clone());
+        }
 
         // replace old by new:
-        new_formation.push(new);
-        new_formation.swap_remove(pos);
-
-        Ok(TrainFormation {
-            formation: new_formation,
-        })
+        let mut new = Some(new);
+        Ok(TrainFormation::from_members(
+            self.formation.iter().enumerate().map(|(i, vehicle)| {
+                if i == pos {
+                    new.take().unwrap()
+                } else {
+                    vehicle.clone()
+                }
+            }),
+        ))
     }
 
     pub(crate) fn remove(&self, vehicle: VehicleIdx) -> Result<TrainFormation, String> {
-        let mut new_formation = self.formation.clone();
-        let pos = new_formation
-            .iter()
-            .position(|u| u.idx() == vehicle)
-            .ok_or_else(|| {
-                format!(
-                    "vehicle {} was not part of the TrainFormation and cannot be removed",
-                    vehicle
-                )
-            })?;
+        let pos = self.position_of(vehicle).ok_or_else(|| {
+            format!(
+                "vehicle {} was not part of the TrainFormation and cannot be removed",
+                vehicle
+            )
+        })?;
+
+        // fast path: removing the only vehicle
+        if self.formation.len() == 1 {
+            return Ok(TrainFormation::empty());
+        }
 
         // remove vehicle:
-        new_formation.remove(pos);
-
-        Ok(TrainFormation {
-            formation: new_formation,
-        })
+        Ok(TrainFormation::from_members(
+            self.formation
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != pos)
+                .map(|(_, vehicle)| vehicle.clone()),
+        ))
     }
 
     pub(crate) fn add_at_tail(&self, vehicle: Vehicle) -> TrainFormation {
-        let mut new_formation = self.formation.clone();
-        new_formation.push(vehicle);
+        TrainFormation::from_members(
+            self.formation
+                .iter()
+                .cloned()
+                .chain(std::iter::once(vehicle)),
+        )
+    }
 
-        TrainFormation {
-            formation: new_formation,
-        }
+    /// True if both formations share the same member list (e.g., one is an unmodified clone of
+    /// the other).
+    #[cfg(test)]
+    pub(crate) fn shares_members_with(&self, other: &TrainFormation) -> bool {
+        Arc::ptr_eq(&self.formation, &other.formation)
     }
 
     pub fn ids(&self) -> Vec<VehicleIdx> {
         self.formation.iter().map(|v| v.idx()).collect()
     }
 
+    /// Position of the vehicle in the formation (0 is the lead vehicle). None if the vehicle is
+    /// not part of the formation.
+    pub fn position_of(&self, vehicle: VehicleIdx) -> Option<usize> {
+        self.formation.iter().position(|v| v.idx() == vehicle)
+    }
+
+    /// True if the vehicle is neither the lead nor the tail vehicle of the formation, i.e., it is
+    /// coupled on both sides.
+    pub fn is_inner_position(&self, vehicle: VehicleIdx) -> bool {
+        self.position_of(vehicle)
+            .is_some_and(|pos| pos > 0 && pos + 1 < self.formation.len())
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Vehicle> {
         self.formation.iter()
     }
 
+    /// Saturates at PassengerCount::MAX (only reachable for extremely long formations, as the
+    /// capacity of a vehicle type is bounded by MAX_VEHICLE_CAPACITY).
     pub fn capacity(&self) -> PassengerCount {
-        self.formation.iter().map(|v| v.capacity()).sum()
+        self.formation
+            .iter()
+            .fold(0, |capacity: PassengerCount, v| {
+                capacity.saturating_add(v.capacity())
+            })
     }
 
+    /// Saturates at PassengerCount::MAX (see capacity).
     pub fn seats(&self) -> PassengerCount {
-        self.formation.iter().map(|v| v.seats()).sum()
+        self.formation.iter().fold(0, |seats: PassengerCount, v| {
+            seats.saturating_add(v.seats())
+        })
     }
 
     pub fn vehicle_count(&self) -> VehicleCount {
         self.formation.len() as VehicleCount
     }
+
+    pub fn vehicle_count_of_type(&self, vehicle_type: VehicleTypeIdx) -> VehicleCount {
+        self.formation
+            .iter()
+            .filter(|v| v.type_idx() == vehicle_type)
+            .count() as VehicleCount
+    }
+
+    /// Number of vehicles at the tail of the formation that can be removed while the remaining
+    /// vehicles still provide enough capacity and seats for the given demand.
+    pub fn surplus_vehicle_count(
+        &self,
+        passengers: PassengerCount,
+        seated: PassengerCount,
+    ) -> VehicleCount {
+        let mut capacity = self.capacity();
+        let mut seats = self.seats();
+        if capacity < passengers || seats < seated {
+            return 0;
+        }
+        let mut surplus = 0;
+        for vehicle in self.formation.iter().rev() {
+            // capacity >= vehicle.capacity() holds as it is a sum containing the vehicle
+            if capacity - vehicle.capacity() < passengers || seats - vehicle.seats() < seated {
+                break;
+            }
+            capacity -= vehicle.capacity();
+            seats -= vehicle.seats();
+            surplus += 1;
+        }
+        surplus
+    }
 }
 
 impl fmt::Display for TrainFormation {
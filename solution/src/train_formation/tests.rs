@@ -0,0 +1,123 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use model::base_types::{Idx, VehicleIdx};
+
+use crate::test_utilities::{default_schedule, init_test_data, TestData};
+use crate::vehicle::Vehicle;
+
+use super::TrainFormation;
+
+fn vehicle(d: &TestData, idx: Idx) -> Vehicle {
+    Vehicle::new(
+        VehicleIdx::vehicle_from(idx),
+        d.vt1,
+        d.network.vehicle_types(),
+    )
+}
+
+fn formation_of(d: &TestData, indices: &[Idx]) -> TrainFormation {
+    indices
+        .iter()
+        .fold(TrainFormation::empty(), |formation, &idx| {
+            formation.add_at_tail(vehicle(d, idx))
+        })
+}
+
+fn ids(indices: &[Idx]) -> Vec<VehicleIdx> {
+    indices
+        .iter()
+        .map(|&idx| VehicleIdx::vehicle_from(idx))
+        .collect()
+}
+
+#[test]
+fn add_at_tail_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let formation = formation_of(&d, &[0, 1]);
+
+    // ACT
+    let new_formation = formation.add_at_tail(vehicle(&d, 2));
+
+    // ASSERT
+    assert_eq!(new_formation.ids(), ids(&[0, 1, 2]));
+    assert_eq!(formation.ids(), ids(&[0, 1]));
+}
+
+#[test]
+fn replace_keeps_position_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let formation = formation_of(&d, &[0, 1, 2]);
+
+    // ACT
+    let new_formation = formation
+        .replace(VehicleIdx::vehicle_from(0), vehicle(&d, 5))
+        .unwrap();
+    let unchanged_formation = formation
+        .replace(VehicleIdx::vehicle_from(1), vehicle(&d, 1))
+        .unwrap();
+    let error = formation.replace(VehicleIdx::vehicle_from(7), vehicle(&d, 5));
+
+    // ASSERT
+    assert_eq!(new_formation.ids(), ids(&[5, 1, 2]));
+    assert_eq!(formation.ids(), ids(&[0, 1, 2]));
+    assert!(unchanged_formation.shares_members_with(&formation));
+    assert!(error.is_err());
+}
+
+#[test]
+fn remove_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let formation = formation_of(&d, &[0, 1, 2]);
+    let single_formation = formation_of(&d, &[3]);
+
+    // ACT
+    let new_formation = formation.remove(VehicleIdx::vehicle_from(1)).unwrap();
+    let emptied_formation = single_formation
+        .remove(VehicleIdx::vehicle_from(3))
+        .unwrap();
+    let error = formation.remove(VehicleIdx::vehicle_from(3));
+
+    // ASSERT
+    assert_eq!(new_formation.ids(), ids(&[0, 2]));
+    assert_eq!(formation.ids(), ids(&[0, 1, 2]));
+    assert_eq!(emptied_formation.vehicle_count(), 0);
+    assert!(emptied_formation.shares_members_with(&TrainFormation::empty()));
+    assert!(error.is_err());
+}
+
+#[test]
+fn unmodified_formations_are_shared_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let veh1 = VehicleIdx::vehicle_from(1);
+
+    // ACT
+    // veh1 serves trip31 and trip14
+    let new_schedule = schedule.replace_vehicle_by_dummy(veh1).unwrap();
+
+    // ASSERT
+    assert_eq!(new_schedule.train_formation_of(d.trip31).ids(), ids(&[2]));
+    assert!(new_schedule
+        .train_formation_of(d.trip14)
+        .shares_members_with(&TrainFormation::empty()));
+    for node in [d.trip12, d.trip23, d.trip34, d.trip45, d.trip51] {
+        assert!(new_schedule
+            .train_formation_of(node)
+            .shares_members_with(schedule.train_formation_of(node)));
+    }
+}
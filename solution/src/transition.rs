@@ -11,7 +11,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod json_serialisation;
 pub mod modifications;
+#[cfg(test)]
+mod tests;
 pub mod transition_cycle;
 
 use std::collections::HashSet;
@@ -19,7 +22,7 @@ use std::collections::HashSet;
 use im::HashMap;
 use itertools::Itertools;
 use model::{
-    base_types::{MaintenanceCounter, VehicleIdx, INF_DISTANCE},
+    base_types::{MaintenanceCounter, Meter, VehicleIdx, INF_DISTANCE},
     network::Network,
 };
 
@@ -51,6 +54,55 @@ impl Transition {
         Transition::one_cluster_per_maintenance(vehicles, tours, network)
     }
 
+    /// Builds the transition with the given cycles (each vehicle in the given order is followed
+    /// by the next one, the last one by the first one). Empty cycles are dropped.
+    /// It is assumed that each vehicle appears in at most one cycle and has a tour.
+    pub fn from_cycles(
+        cycles: Vec<Vec<VehicleIdx>>,
+        tours: &HashMap<VehicleIdx, Tour>,
+        network: &Network,
+    ) -> Transition {
+        let cycles: Vec<TransitionCycle> = cycles
+            .into_iter()
+            .filter(|vehicles| !vehicles.is_empty())
+            .map(|vehicles| {
+                let maintenance_counter: MaintenanceCounter = vehicles
+                    .iter()
+                    .zip(vehicles.iter().cycle().skip(1))
+                    .map(|(vehicle, successor)| {
+                        tours.get(vehicle).unwrap().maintenance_counter()
+                            + network
+                                .dead_head_distance_between(
+                                    tours.get(vehicle).unwrap().end_depot().unwrap(),
+                                    tours.get(successor).unwrap().start_depot().unwrap(),
+                                )
+                                .in_meter()
+                                .unwrap_or(INF_DISTANCE)
+                                as MaintenanceCounter
+                    })
+                    .sum();
+                TransitionCycle::new(vehicles, maintenance_counter)
+            })
+            .collect();
+
+        let cycle_lookup = cycles
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, cycle)| cycle.iter().map(move |vehicle| (vehicle, idx)))
+            .collect();
+
+        Transition {
+            total_maintenance_violation: cycles
+                .iter()
+                .map(|cycle| cycle.maintenance_counter().max(0))
+                .sum(),
+            total_maintenance_counter: cycles.iter().map(|cycle| cycle.maintenance_counter()).sum(),
+            cycles,
+            cycle_lookup,
+            empty_cycles: Vec::new(),
+        }
+    }
+
     // TEST this function
     /// Assigns each vehicle greedily to a cluster with the goal of minimizing the total maintenance violation.
     /// It is assumed that all vehicles are of the same type.
@@ -197,6 +249,66 @@ impl Transition {
         self.total_maintenance_counter
     }
 
+    /// Returns the (non-empty) cycles in which no tour visits maintenance (neither a maintenance
+    /// slot nor a depot providing maintenance). The counters of their vehicles grow without bound
+    /// over the periods.
+    /// Tours must contain the tours of all vehicles of this transition.
+    pub fn cycles_without_maintenance<'a>(
+        &'a self,
+        tours: &'a HashMap<VehicleIdx, Tour>,
+    ) -> impl Iterator<Item = &'a TransitionCycle> + 'a {
+        self.cycles.iter().filter(move |transition_cycle| {
+            !transition_cycle.is_empty()
+                && transition_cycle
+                    .iter()
+                    .all(|vehicle| !tours.get(&vehicle).unwrap().visits_maintenance())
+        })
+    }
+
+    /// Returns the number of vehicles in cycles without maintenance (see
+    /// cycles_without_maintenance).
+    pub fn number_of_vehicles_without_maintenance(
+        &self,
+        tours: &HashMap<VehicleIdx, Tour>,
+    ) -> usize {
+        self.cycles_without_maintenance(tours)
+            .map(|transition_cycle| transition_cycle.len())
+            .sum()
+    }
+
+    /// Returns the total dead-head distance (in meter) that arises if the end depots are made
+    /// consistent with this transition, i.e., each vehicle drives from the end location of its
+    /// last non-depot node to the start depot of its successor.
+    /// Tours must contain the tours of all vehicles of this transition.
+    pub fn depot_consistency_dead_head_distance(
+        &self,
+        tours: &HashMap<VehicleIdx, Tour>,
+        network: &Network,
+    ) -> Meter {
+        self.cycles
+            .iter()
+            .flat_map(|transition_cycle| {
+                let vehicles = transition_cycle.get_vec();
+                vehicles
+                    .iter()
+                    .zip(vehicles.iter().cycle().skip(1))
+                    .map(|(vehicle, successor)| {
+                        let tour = tours.get(vehicle).unwrap();
+                        let last_node = tour
+                            .last_non_depot()
+                            .unwrap_or_else(|| tour.start_depot().unwrap());
+                        network
+                            .dead_head_distance_between(
+                                last_node,
+                                tours.get(successor).unwrap().start_depot().unwrap(),
+                            )
+                            .in_meter()
+                            .unwrap_or(INF_DISTANCE)
+                    })
+            })
+            .sum()
+    }
+
     pub fn print(&self) {
         for transition_cycle in self.cycles.iter() {
             if !transition_cycle.is_empty() {
@@ -223,6 +335,14 @@ impl Transition {
             .flat_map(|transition_cycle| transition_cycle.iter())
             .collect();
         assert_eq!(cycles.len(), tours.len());
+        // all referenced vehicles exist (a removed vehicle must not be left in a cycle)
+        for vehicle in cycles.iter().chain(self.cycle_lookup.keys()) {
+            assert!(
+                vehicle.is_real() && tours.contains_key(vehicle),
+                "transition references vehicle {} which does not exist",
+                vehicle
+            );
+        }
         let vehicles_from_tours: HashSet<VehicleIdx> = tours.keys().cloned().collect();
         let vehicles_from_cycles: HashSet<VehicleIdx> = cycles.iter().cloned().collect();
         assert_eq!(vehicles_from_tours, vehicles_from_cycles);
@@ -0,0 +1,103 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use im::HashMap;
+use model::{
+    base_types::{MaintenanceCounter, VehicleIdx},
+    network::Network,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::tour::Tour;
+
+use super::Transition;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TransitionJson {
+    cycles: Vec<TransitionCycleJson>,
+    /// ignored on import, the violation is recomputed from the tours
+    #[serde(default)]
+    maintenance_violation: MaintenanceCounter,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TransitionCycleJson {
+    vehicles: Vec<String>,
+    /// ignored on import, the counter is recomputed from the tours
+    #[serde(default)]
+    maintenance_counter: MaintenanceCounter,
+}
+
+/// Exports the non-empty cycles of the transition. Vehicles are referenced by their ids in the
+/// schedule output (e.g. "veh_3").
+pub fn transition_to_json(transition: &Transition) -> serde_json::Value {
+    let transition_json = TransitionJson {
+        cycles: transition
+            .cycles_iter()
+            .filter(|cycle| !cycle.is_empty())
+            .map(|cycle| TransitionCycleJson {
+                vehicles: cycle.iter().map(|vehicle| vehicle.to_string()).collect(),
+                maintenance_counter: cycle.maintenance_counter(),
+            })
+            .collect(),
+        maintenance_violation: transition.maintenance_violation(),
+    };
+    serde_json::to_value(transition_json).unwrap()
+}
+
+/// Imports a transition exported by transition_to_json. Each vehicle must have a tour in tours
+/// and must appear at most once. Maintenance counters are recomputed from the tours, hence the
+/// transition can be imported for a schedule with different tours.
+/// Note that it is not checked that all vehicles of tours are covered.
+pub fn transition_from_json(
+    transition_json: &serde_json::Value,
+    tours: &HashMap<VehicleIdx, Tour>,
+    network: &Network,
+) -> Result<Transition, String> {
+    let transition_json: TransitionJson = serde_json::from_value(transition_json.clone())
+        .map_err(|e| format!("Invalid transition: {}", e))?;
+
+    let vehicle_lookup: std::collections::HashMap<String, VehicleIdx> = tours
+        .keys()
+        .map(|vehicle| (vehicle.to_string(), *vehicle))
+        .collect();
+
+    let mut seen: HashSet<VehicleIdx> = HashSet::new();
+    let cycles = transition_json
+        .cycles
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .vehicles
+                .iter()
+                .map(|id| {
+                    let vehicle = *vehicle_lookup
+                        .get(id)
+                        .ok_or_else(|| format!("Invalid transition: unknown vehicle '{}'", id))?;
+                    if !seen.insert(vehicle) {
+                        return Err(format!(
+                            "Invalid transition: vehicle '{}' appears more than once",
+                            id
+                        ));
+                    }
+                    Ok(vehicle)
+                })
+                .collect::<Result<Vec<VehicleIdx>, String>>()
+        })
+        .collect::<Result<Vec<Vec<VehicleIdx>>, String>>()?;
+
+    Ok(Transition::from_cycles(cycles, tours, network))
+}
@@ -22,50 +22,55 @@ use crate::tour::Tour;
 use super::{CycleIdx, Transition, TransitionCycle};
 
 impl Transition {
-    // TEST this function (with multiple vehicles being updated in a raw)
-    // old_tours are the tours of the old schedule, updated_tours are the tours of vehicle that
-    // have already been updated in the current transition. Hence, for determining the tours of the
-    // predecessor and the successor first take the updated_tours and if they are not present, then
-    // take the old_tours.
-    pub fn update_vehicle(
+    /// Replaces the old_tour of the vehicle by new_tour. tour_of resolves the current tour of the
+    /// other vehicles (as several vehicles are updated one after another, these are the already
+    /// updated tours or the tours of the old schedule).
+    /// If the depots and the maintenance counter of the tour stay the same, the cycles are not
+    /// touched at all.
+    pub fn update_vehicle<'a>(
         &self,
         vehicle: VehicleIdx,
+        old_tour: &Tour,
         new_tour: &Tour,
-        updated_tours: &HashMap<VehicleIdx, &Tour>,
-        old_tours: &HashMap<VehicleIdx, Tour>,
+        tour_of: impl Fn(VehicleIdx) -> &'a Tour,
         network: &Network,
     ) -> Transition {
-        let old_tour = old_tours.get(&vehicle).unwrap();
+        let old_tour_counter = old_tour.maintenance_counter();
+        let new_tour_counter = new_tour.maintenance_counter();
+        let new_start_depot = new_tour.start_depot().unwrap();
+        let new_end_depot = new_tour.end_depot().unwrap();
+        if old_tour_counter == new_tour_counter
+            && old_tour.start_depot().unwrap() == new_start_depot
+            && old_tour.end_depot().unwrap() == new_end_depot
+        {
+            return self.clone();
+        }
+
         let mut cycles = self.cycles.clone();
         let cycle_idx = self.cycle_lookup.get(&vehicle).unwrap();
-        let old_cycle = &mut cycles.get(*cycle_idx).unwrap();
+        let old_cycle = &cycles[*cycle_idx];
 
         let new_maintenance_counter = if old_cycle.len() == 1 {
-            new_tour.maintenance_counter()
+            new_tour_counter
                 + network
-                    .dead_head_distance_between(
-                        new_tour.end_depot().unwrap(),
-                        new_tour.start_depot().unwrap(),
-                    )
+                    .dead_head_distance_between(new_end_depot, new_start_depot)
                     .in_meter()
                     .unwrap_or(INF_DISTANCE) as MaintenanceCounter
         } else {
-            let (end_depot_of_predecessor, start_depot_of_successor) = self
-                .end_depot_of_predecessor_and_start_depot_of_successor(
-                    vehicle,
-                    updated_tours,
-                    old_tours,
-                );
+            let (end_depot_of_predecessor, start_depot_of_successor) =
+                self.end_depot_of_predecessor_and_start_depot_of_successor(vehicle, tour_of);
 
-            let maintenance_counter_for_removal = self
-                .maintenance_counter_of_tour_plus_dead_head_trips_before_and_after(
+            let maintenance_counter_for_removal =
+                Transition::maintenance_counter_plus_dead_head_trips_before_and_after(
+                    old_tour_counter,
                     old_tour,
                     end_depot_of_predecessor,
                     start_depot_of_successor,
                     network,
                 );
-            let maintenance_counter_for_addition = self
-                .maintenance_counter_of_tour_plus_dead_head_trips_before_and_after(
+            let maintenance_counter_for_addition =
+                Transition::maintenance_counter_plus_dead_head_trips_before_and_after(
+                    new_tour_counter,
                     new_tour,
                     end_depot_of_predecessor,
                     start_depot_of_successor,
@@ -76,7 +81,8 @@ impl Transition {
                 + maintenance_counter_for_addition
         };
 
-        let new_cycle = TransitionCycle::new(old_cycle.get_vec().clone(), new_maintenance_counter);
+        // the vehicles of the cycle stay the same, only the counter changes
+        let new_cycle = old_cycle.with_maintenance_counter(new_maintenance_counter);
 
         let total_maintenance_violation = (self.total_maintenance_violation
             + new_maintenance_counter.max(0))
@@ -140,13 +146,13 @@ impl Transition {
         }
     }
 
-    // TEST this function (with multiple vehicles being removed in a raw) and with a
-    // maintenance vehicle removed
-    pub fn remove_vehicle(
+    /// Removes the vehicle with the given (old) tour. tour_of resolves the current tour of the
+    /// other vehicles (see update_vehicle).
+    pub fn remove_vehicle<'a>(
         &self,
         vehicle: VehicleIdx,
-        updated_tours: &HashMap<VehicleIdx, &Tour>,
-        old_tours: &HashMap<VehicleIdx, Tour>,
+        old_tour: &Tour,
+        tour_of: impl Fn(VehicleIdx) -> &'a Tour,
         network: &Network,
     ) -> Transition {
         let mut cycles = self.cycles.clone();
@@ -160,16 +166,13 @@ impl Transition {
             empty_cycles.push(*cycle_idx);
             0
         } else {
-            let (end_depot_of_predecessor, start_depot_of_successor) = self
-                .end_depot_of_predecessor_and_start_depot_of_successor(
-                    vehicle,
-                    updated_tours,
-                    old_tours,
-                );
+            let (end_depot_of_predecessor, start_depot_of_successor) =
+                self.end_depot_of_predecessor_and_start_depot_of_successor(vehicle, tour_of);
 
-            let maintenance_counter_for_removal = self
-                .maintenance_counter_of_tour_plus_dead_head_trips_before_and_after(
-                    old_tours.get(&vehicle).unwrap(),
+            let maintenance_counter_for_removal =
+                Transition::maintenance_counter_plus_dead_head_trips_before_and_after(
+                    old_tour.maintenance_counter(),
+                    old_tour,
                     end_depot_of_predecessor,
                     start_depot_of_successor,
                     network,
@@ -206,13 +209,13 @@ impl Transition {
         }
     }
 
-    // TEST this function with non-maintenance vehicle and maintenance vehicle
-    pub fn add_vehicle_at_the_end(
+    /// Appends the vehicle to the cycle with the given index. tour_of resolves the current tour of
+    /// all vehicles (see update_vehicle).
+    pub fn add_vehicle_at_the_end<'a>(
         &self,
         vehicle: VehicleIdx,
         new_cycle_idx: CycleIdx,
-        updated_tours: &HashMap<VehicleIdx, &Tour>,
-        old_tours: &HashMap<VehicleIdx, Tour>,
+        tour_of: impl Fn(VehicleIdx) -> &'a Tour,
         network: &Network,
     ) -> Transition {
         let mut cycles = self.cycles.clone();
@@ -223,10 +226,7 @@ impl Transition {
 
         let new_cycle_vec: Vec<_> = old_cycle.iter().chain(std::iter::once(vehicle)).collect();
 
-        let tour_of_vehicle = updated_tours
-            .get(&vehicle)
-            .copied()
-            .unwrap_or_else(|| old_tours.get(&vehicle).unwrap());
+        let tour_of_vehicle = tour_of(vehicle);
 
         let new_maintenance_counter = if new_cycle_vec.len() == 1 {
             empty_cycles.retain(|&idx| idx != new_cycle_idx);
@@ -239,29 +239,10 @@ impl Transition {
                     .in_meter()
                     .unwrap_or(INF_DISTANCE) as MaintenanceCounter
         } else {
-            let end_depot_of_predecessor = new_cycle_vec
-                .get(new_cycle_vec.len() - 2)
-                .map(|&v| {
-                    updated_tours
-                        .get(&v)
-                        .copied()
-                        .unwrap_or_else(|| old_tours.get(&v).unwrap())
-                        .end_depot()
-                        .unwrap()
-                })
-                .unwrap();
-
-            let start_depot_of_successor = new_cycle_vec
-                .first()
-                .map(|&v| {
-                    updated_tours
-                        .get(&v)
-                        .copied()
-                        .unwrap_or_else(|| old_tours.get(&v).unwrap())
-                        .start_depot()
-                        .unwrap()
-                })
+            let end_depot_of_predecessor = tour_of(new_cycle_vec[new_cycle_vec.len() - 2])
+                .end_depot()
                 .unwrap();
+            let start_depot_of_successor = tour_of(new_cycle_vec[0]).start_depot().unwrap();
 
             let maintenance_counter_for_removal = network
                 .dead_head_distance_between(end_depot_of_predecessor, start_depot_of_successor)
@@ -269,8 +250,9 @@ impl Transition {
                 .unwrap_or(INF_DISTANCE)
                 as MaintenanceCounter;
 
-            let maintenance_counter_for_addtion = self
-                .maintenance_counter_of_tour_plus_dead_head_trips_before_and_after(
+            let maintenance_counter_for_addtion =
+                Transition::maintenance_counter_plus_dead_head_trips_before_and_after(
+                    tour_of_vehicle.maintenance_counter(),
                     tour_of_vehicle,
                     end_depot_of_predecessor,
                     start_depot_of_successor,
@@ -310,8 +292,9 @@ impl Transition {
         tours: &HashMap<VehicleIdx, Tour>,
         network: &Network,
     ) -> Transition {
-        let transition = self.remove_vehicle(vehicle, &HashMap::new(), tours, network);
-        transition.add_vehicle_at_the_end(vehicle, new_cycle_idx, &HashMap::new(), tours, network)
+        let tour_of = |v: VehicleIdx| tours.get(&v).unwrap();
+        let transition = self.remove_vehicle(vehicle, tour_of(vehicle), tour_of, network);
+        transition.add_vehicle_at_the_end(vehicle, new_cycle_idx, tour_of, network)
     }
 
     pub fn replace_cycle(&self, cycle_idx: CycleIdx, new_cycle: TransitionCycle) -> Transition {
@@ -340,58 +323,35 @@ impl Transition {
 }
 
 impl Transition {
-    // as we do multiple transition updates for the same old_schedule, the tours must be updated
-    // one-by-one. To speed up the process, have the old_tours of the old_schedule and the tour
-    // updates are contained in a separate hashmap. So as tours consider first the tours in
-    // updated_tours and if they are not present, then consider the tours in old_tours.
-    fn end_depot_of_predecessor_and_start_depot_of_successor(
+    fn end_depot_of_predecessor_and_start_depot_of_successor<'a>(
         &self,
         vehicle: VehicleIdx,
-        updated_tours: &HashMap<VehicleIdx, &Tour>,
-        old_tours: &HashMap<VehicleIdx, Tour>,
+        tour_of: impl Fn(VehicleIdx) -> &'a Tour,
     ) -> (NodeIdx, NodeIdx) {
-        let cycle_idx = self.cycle_lookup.get(&vehicle).unwrap();
+        let cycle = &self.cycles[*self.cycle_lookup.get(&vehicle).unwrap()];
 
-        let vehicle_idx = self.cycles[*cycle_idx]
-            .iter()
-            .position(|v| v == vehicle)
+        let position = cycle.iter().position(|v| v == vehicle).unwrap();
+        let predecessor = cycle
+            .get((position + cycle.len() - 1) % cycle.len())
             .unwrap();
-        let predecessor = if vehicle_idx == 0 {
-            self.cycles[*cycle_idx].last().unwrap()
-        } else {
-            self.cycles[*cycle_idx].get(vehicle_idx - 1).unwrap()
-        };
-
-        let successor = if vehicle_idx == self.cycles[*cycle_idx].len() - 1 {
-            self.cycles[*cycle_idx].first().unwrap()
-        } else {
-            self.cycles[*cycle_idx].get(vehicle_idx + 1).unwrap()
-        };
+        let successor = cycle.get((position + 1) % cycle.len()).unwrap();
 
         (
-            updated_tours
-                .get(&predecessor)
-                .copied()
-                .unwrap_or_else(|| old_tours.get(&predecessor).unwrap())
-                .end_depot()
-                .unwrap(),
-            updated_tours
-                .get(&successor)
-                .copied()
-                .unwrap_or_else(|| old_tours.get(&successor).unwrap())
-                .start_depot()
-                .unwrap(),
+            tour_of(predecessor).end_depot().unwrap(),
+            tour_of(successor).start_depot().unwrap(),
         )
     }
 
-    fn maintenance_counter_of_tour_plus_dead_head_trips_before_and_after(
-        &self,
+    /// maintenance_counter must be the maintenance counter of tour (passed in as it is not cached
+    /// by the tour).
+    fn maintenance_counter_plus_dead_head_trips_before_and_after(
+        maintenance_counter: MaintenanceCounter,
         tour: &Tour,
         end_depot_of_predecessor: NodeIdx,
         start_depot_of_successor: NodeIdx,
         network: &Network,
     ) -> MaintenanceCounter {
-        tour.maintenance_counter()
+        maintenance_counter
             + network
                 .dead_head_distance_between(end_depot_of_predecessor, tour.start_depot().unwrap())
                 .in_meter()
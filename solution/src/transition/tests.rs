@@ -0,0 +1,385 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use im::HashMap;
+use model::base_types::{MaintenanceCounter, VehicleIdx};
+use model::network::Network;
+
+use crate::segment::Segment;
+use crate::test_utilities::{default_schedule, init_test_data, init_test_data_with};
+use crate::tour::Tour;
+
+use super::json_serialisation::{transition_from_json, transition_to_json};
+use super::Transition;
+
+#[test]
+fn depot_consistency_dead_head_distance_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let tours = schedule.get_tours().clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let empty_transition = Transition::new_fast(&[], &tours, &d.network);
+
+    // ACT
+    // one cycle: veh0 -> veh1 -> veh2 -> veh0
+    let one_cycle = empty_transition
+        .add_vehicle_to_own_cycle(veh0, tours.get(&veh0).unwrap(), &d.network)
+        .add_vehicle_at_the_end(veh1, 0, |v| tours.get(&v).unwrap(), &d.network)
+        .add_vehicle_at_the_end(veh2, 0, |v| tours.get(&v).unwrap(), &d.network);
+    // each vehicle is its own successor
+    let own_cycles = empty_transition
+        .add_vehicle_to_own_cycle(veh0, tours.get(&veh0).unwrap(), &d.network)
+        .add_vehicle_to_own_cycle(veh1, tours.get(&veh1).unwrap(), &d.network)
+        .add_vehicle_to_own_cycle(veh2, tours.get(&veh2).unwrap(), &d.network);
+
+    // ASSERT
+    one_cycle.verify_consistency(&tours, &d.network);
+    own_cycles.verify_consistency(&tours, &d.network);
+
+    // veh0: loc1 -> depot2 (12km), veh1: loc4 -> depot1 (41km), veh2: loc1 -> depot1 (0km)
+    assert_eq!(
+        one_cycle.depot_consistency_dead_head_distance(&tours, &d.network),
+        53000
+    );
+    // veh0: loc1 -> depot1 (0km), veh1: loc4 -> depot2 (42km), veh2: loc1 -> depot1 (0km)
+    assert_eq!(
+        own_cycles.depot_consistency_dead_head_distance(&tours, &d.network),
+        42000
+    );
+
+    // own cycles have a worse maintenance violation (dead-head trips between the depots of
+    // the current tours) but need less repositioning
+    assert_eq!(
+        own_cycles.maintenance_violation() - one_cycle.maintenance_violation(),
+        33000
+    );
+    let weighted = |transition: &Transition, weight: MaintenanceCounter| {
+        transition.maintenance_violation()
+            + weight
+                * transition.depot_consistency_dead_head_distance(&tours, &d.network)
+                    as MaintenanceCounter
+    };
+    assert!(weighted(&one_cycle, 1) < weighted(&own_cycles, 1));
+    assert!(weighted(&own_cycles, 4) < weighted(&one_cycle, 4));
+}
+
+#[test]
+fn maintenance_depot_full_credit_test() {
+    // ARRANGE
+    // maximalDistance is 30000km, veh0 and veh2 end at depot2, veh1 ends at depot1
+    let d = init_test_data();
+    let d_with_maintenance_depot = init_test_data_with(|instance| {
+        instance["depots"][1]["providesMaintenance"] = serde_json::json!(true);
+    });
+    let tours = default_schedule(&d).get_tours().clone();
+    let tours_with_credit = default_schedule(&d_with_maintenance_depot)
+        .get_tours()
+        .clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let own_cycles = |tours: &HashMap<VehicleIdx, Tour>, network: &Network| {
+        Transition::new_fast(&[], tours, network)
+            .add_vehicle_to_own_cycle(veh0, tours.get(&veh0).unwrap(), network)
+            .add_vehicle_to_own_cycle(veh1, tours.get(&veh1).unwrap(), network)
+            .add_vehicle_to_own_cycle(veh2, tours.get(&veh2).unwrap(), network)
+    };
+
+    // ACT
+    let transition = own_cycles(&tours, &d.network);
+    let transition_with_credit = own_cycles(&tours_with_credit, &d_with_maintenance_depot.network);
+
+    // ASSERT
+    transition_with_credit
+        .verify_consistency(&tours_with_credit, &d_with_maintenance_depot.network);
+    let tour_with_credit = tours_with_credit.get(&veh0).unwrap();
+    assert!(tour_with_credit.visits_maintenance());
+    assert!(!tour_with_credit.visits_maintenance_slot());
+    assert!(!tours_with_credit.get(&veh1).unwrap().visits_maintenance());
+    assert_eq!(
+        tour_with_credit.maintenance_counter(),
+        tours.get(&veh0).unwrap().maintenance_counter() - 30_000_000
+    );
+    assert_eq!(
+        transition_with_credit.maintenance_counter(),
+        transition.maintenance_counter() - 2 * 30_000_000
+    );
+    // only the cycle of veh1 (the second cycle) still violates the maintenance limit
+    assert_eq!(
+        transition_with_credit.maintenance_violation(),
+        transition.get_cycle(1).maintenance_counter()
+    );
+}
+
+#[test]
+fn maintenance_depot_fixed_credit_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let d_with_maintenance_depot = init_test_data_with(|instance| {
+        instance["depots"][1]["providesMaintenance"] = serde_json::json!(true);
+        instance["parameters"]["maintenance"]["depotCredit"] = serde_json::json!(10_000);
+    });
+    let tours = default_schedule(&d).get_tours().clone();
+    let tours_with_credit = default_schedule(&d_with_maintenance_depot)
+        .get_tours()
+        .clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+
+    // ACT
+    // one cycle passing through depot2 twice: veh0 -> veh1 -> veh2 -> veh0
+    let one_cycle = |tours: &HashMap<VehicleIdx, Tour>, network: &Network| {
+        Transition::new_fast(&[], tours, network)
+            .add_vehicle_to_own_cycle(veh0, tours.get(&veh0).unwrap(), network)
+            .add_vehicle_at_the_end(veh1, 0, |v| tours.get(&v).unwrap(), network)
+            .add_vehicle_at_the_end(veh2, 0, |v| tours.get(&v).unwrap(), network)
+    };
+    let transition = one_cycle(&tours, &d.network);
+    let transition_with_credit = one_cycle(&tours_with_credit, &d_with_maintenance_depot.network);
+
+    // ASSERT
+    transition_with_credit
+        .verify_consistency(&tours_with_credit, &d_with_maintenance_depot.network);
+    for (vehicle, credit) in [(veh0, 10_000), (veh1, 0), (veh2, 10_000)] {
+        assert_eq!(
+            tours_with_credit
+                .get(&vehicle)
+                .unwrap()
+                .maintenance_counter(),
+            tours.get(&vehicle).unwrap().maintenance_counter() - credit
+        );
+    }
+    assert_eq!(
+        transition_with_credit.get_cycle(0).maintenance_counter(),
+        transition.get_cycle(0).maintenance_counter() - 2 * 10_000
+    );
+    assert_eq!(
+        transition_with_credit.maintenance_violation(),
+        transition.maintenance_violation() - 2 * 10_000
+    );
+}
+
+#[test]
+fn cycles_without_maintenance_test() {
+    // ARRANGE
+    // depot2 provides maintenance: veh0 and veh2 end there, veh1 ends at depot1
+    let d = init_test_data_with(|instance| {
+        instance["depots"][1]["providesMaintenance"] = serde_json::json!(true);
+    });
+    let tours = default_schedule(&d).get_tours().clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let two_cycles = |first: VehicleIdx, second: VehicleIdx, alone: VehicleIdx| {
+        Transition::new_fast(&[], &tours, &d.network)
+            .add_vehicle_to_own_cycle(first, tours.get(&first).unwrap(), &d.network)
+            .add_vehicle_at_the_end(second, 0, |v| tours.get(&v).unwrap(), &d.network)
+            .add_vehicle_to_own_cycle(alone, tours.get(&alone).unwrap(), &d.network)
+    };
+
+    // ACT
+    let veh1_alone = two_cycles(veh0, veh2, veh1);
+    let veh1_with_veh0 = two_cycles(veh0, veh1, veh2);
+
+    // ASSERT
+    veh1_alone.verify_consistency(&tours, &d.network);
+    veh1_with_veh0.verify_consistency(&tours, &d.network);
+
+    let cycles: Vec<Vec<VehicleIdx>> = veh1_alone
+        .cycles_without_maintenance(&tours)
+        .map(|cycle| cycle.get_vec().clone())
+        .collect();
+    assert_eq!(cycles, vec![vec![veh1]]);
+    assert_eq!(veh1_alone.number_of_vehicles_without_maintenance(&tours), 1);
+
+    assert_eq!(veh1_with_veh0.cycles_without_maintenance(&tours).count(), 0);
+    assert_eq!(
+        veh1_with_veh0.number_of_vehicles_without_maintenance(&tours),
+        0
+    );
+}
+
+#[test]
+fn transition_json_round_trip_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let tours = default_schedule(&d).get_tours().clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    let transition = Transition::new_fast(&[], &tours, &d.network)
+        .add_vehicle_to_own_cycle(veh2, tours.get(&veh2).unwrap(), &d.network)
+        .add_vehicle_at_the_end(veh0, 0, |v| tours.get(&v).unwrap(), &d.network)
+        .add_vehicle_to_own_cycle(veh1, tours.get(&veh1).unwrap(), &d.network);
+
+    // ACT
+    let transition_json = transition_to_json(&transition);
+    let imported = transition_from_json(&transition_json, &tours, &d.network).unwrap();
+
+    // ASSERT
+    assert_eq!(
+        transition_json["cycles"][0]["vehicles"],
+        serde_json::json!(["veh_2", "veh_0"])
+    );
+    assert_eq!(
+        transition_json["cycles"][1]["vehicles"],
+        serde_json::json!(["veh_1"])
+    );
+    imported.verify_consistency(&tours, &d.network);
+    let cycles = |transition: &Transition| -> Vec<Vec<VehicleIdx>> {
+        transition
+            .cycles_iter()
+            .map(|cycle| cycle.get_vec().clone())
+            .collect()
+    };
+    assert_eq!(cycles(&imported), cycles(&transition));
+    assert_eq!(
+        imported.maintenance_violation(),
+        transition.maintenance_violation()
+    );
+    assert_eq!(transition_to_json(&imported), transition_json);
+}
+
+#[test]
+fn transition_from_json_rejects_invalid_input_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let tours = default_schedule(&d).get_tours().clone();
+    let import = |cycles: serde_json::Value| {
+        transition_from_json(&serde_json::json!({ "cycles": cycles }), &tours, &d.network)
+    };
+
+    // ACT
+    let unknown_vehicle = import(serde_json::json!([{ "vehicles": ["veh_0", "veh_7"] }]));
+    let duplicate_vehicle = import(serde_json::json!([
+        { "vehicles": ["veh_0", "veh_1"] },
+        { "vehicles": ["veh_1"] }
+    ]));
+    let malformed = import(serde_json::json!(["veh_0"]));
+
+    // ASSERT
+    assert_eq!(
+        unknown_vehicle.err().unwrap(),
+        "Invalid transition: unknown vehicle 'veh_7'"
+    );
+    assert_eq!(
+        duplicate_vehicle.err().unwrap(),
+        "Invalid transition: vehicle 'veh_1' appears more than once"
+    );
+    assert!(malformed.err().unwrap().starts_with("Invalid transition:"));
+}
+
+#[test]
+fn incremental_updates_match_full_recomputation_test() {
+    // ARRANGE
+    let d = init_test_data();
+    let schedule = default_schedule(&d);
+    let tours = schedule.get_tours().clone();
+    let veh0 = VehicleIdx::vehicle_from(0);
+    let veh1 = VehicleIdx::vehicle_from(1);
+    let veh2 = VehicleIdx::vehicle_from(2);
+    // trip14 moves from veh1 to veh2
+    let new_tours = schedule
+        .fit_reassign(Segment::new(d.trip14, d.trip14), veh1, veh2)
+        .unwrap()
+        .get_tours()
+        .clone();
+    let transition =
+        Transition::from_cycles(vec![vec![veh0, veh1], vec![veh2]], &tours, &d.network);
+    let assert_same_counters = |incremental: &Transition, full: &Transition| {
+        assert_eq!(
+            incremental.maintenance_violation(),
+            full.maintenance_violation()
+        );
+        assert_eq!(
+            incremental.maintenance_counter(),
+            full.maintenance_counter()
+        );
+        for cycle in full.cycles_iter() {
+            let first = cycle.first().unwrap();
+            let incremental_cycle = incremental
+                .cycles_iter()
+                .find(|c| c.first() == Some(first))
+                .unwrap();
+            assert_eq!(incremental_cycle.get_vec(), cycle.get_vec());
+            assert_eq!(
+                incremental_cycle.maintenance_counter(),
+                cycle.maintenance_counter()
+            );
+        }
+    };
+
+    // ACT
+    // the vehicles are updated one after another, so veh2 already sees the new tour of veh1
+    let updated = transition
+        .update_vehicle(
+            veh1,
+            tours.get(&veh1).unwrap(),
+            new_tours.get(&veh1).unwrap(),
+            |v| tours.get(&v).unwrap(),
+            &d.network,
+        )
+        .update_vehicle(
+            veh2,
+            tours.get(&veh2).unwrap(),
+            new_tours.get(&veh2).unwrap(),
+            |v| {
+                if v == veh1 {
+                    new_tours.get(&veh1).unwrap()
+                } else {
+                    tours.get(&v).unwrap()
+                }
+            },
+            &d.network,
+        );
+    let unchanged = transition.update_vehicle(
+        veh0,
+        tours.get(&veh0).unwrap(),
+        tours.get(&veh0).unwrap(),
+        |v| tours.get(&v).unwrap(),
+        &d.network,
+    );
+    let removed = transition.remove_vehicle(
+        veh1,
+        tours.get(&veh1).unwrap(),
+        |v| tours.get(&v).unwrap(),
+        &d.network,
+    );
+    let added = removed.add_vehicle_at_the_end(
+        veh1,
+        *removed.cycle_lookup.get(&veh2).unwrap(),
+        |v| tours.get(&v).unwrap(),
+        &d.network,
+    );
+
+    // ASSERT
+    let full = Transition::from_cycles(vec![vec![veh0, veh1], vec![veh2]], &new_tours, &d.network);
+    updated.verify_consistency(&new_tours, &d.network);
+    assert_same_counters(&updated, &full);
+
+    unchanged.verify_consistency(&tours, &d.network);
+    assert_same_counters(&unchanged, &transition);
+
+    let tours_without_veh1 = tours.without(&veh1);
+    let full = Transition::from_cycles(vec![vec![veh0], vec![veh2]], &tours, &d.network);
+    removed.verify_consistency(&tours_without_veh1, &d.network);
+    assert_same_counters(&removed, &full);
+
+    let full = Transition::from_cycles(vec![vec![veh0], vec![veh2, veh1]], &tours, &d.network);
+    added.verify_consistency(&tours, &d.network);
+    assert_same_counters(&added, &full);
+}
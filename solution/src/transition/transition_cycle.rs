@@ -11,6 +11,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
+
 use im::HashMap;
 use model::{
     base_types::{MaintenanceCounter, VehicleIdx, INF_DISTANCE},
@@ -21,14 +23,25 @@ use crate::tour::Tour;
 
 #[derive(Debug, Clone)]
 pub struct TransitionCycle {
-    cycle: Vec<VehicleIdx>,
+    cycle: Arc<Vec<VehicleIdx>>, // shared, as most updates only change the maintenance counter
     maintenance_counter: MaintenanceCounter,
 }
 
 impl TransitionCycle {
     pub fn new(cycle: Vec<VehicleIdx>, maintenance_counter: MaintenanceCounter) -> TransitionCycle {
         TransitionCycle {
-            cycle,
+            cycle: Arc::new(cycle),
+            maintenance_counter,
+        }
+    }
+
+    /// Same vehicles (without copying them) with a different maintenance counter.
+    pub fn with_maintenance_counter(
+        &self,
+        maintenance_counter: MaintenanceCounter,
+    ) -> TransitionCycle {
+        TransitionCycle {
+            cycle: self.cycle.clone(),
             maintenance_counter,
         }
     }
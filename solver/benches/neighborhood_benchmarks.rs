@@ -0,0 +1,76 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::Read;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use model::json_serialisation::{
+    load_rolling_stock_problem_instance_from_json, perturb_instance, PerturbationConfig,
+};
+use rapid_solve::heuristics::common::ParallelNeighborhood;
+use rapid_time::Duration;
+use rayon::iter::ParallelIterator;
+use solver::{
+    local_search::{
+        neighborhood::{swaps::SwapInfo, RSSchedParallelNeighborhood},
+        ScheduleWithInfo,
+    },
+    min_cost_flow_solver::MinCostFlowSolver,
+};
+
+/// The small test input with its departures doubled a few times (by adding shifted copies).
+fn medium_instance() -> serde_json::Value {
+    let mut file = File::open("../model/resources/small_test_input.json").unwrap();
+    let mut input_data = String::new();
+    file.read_to_string(&mut input_data).unwrap();
+    let mut input_data: serde_json::Value = serde_json::from_str(&input_data).unwrap();
+
+    let perturbation_config = PerturbationConfig {
+        shift_fraction: 1.0,
+        maximal_shift: Duration::new("2:00:00"),
+        demand_deviation: 0.0,
+        drop_fraction: 0.0,
+        add_fraction: 1.0,
+    };
+    for seed in 0..4 {
+        input_data = perturb_instance(input_data, &perturbation_config, seed);
+    }
+    input_data
+}
+
+pub fn neighborhood_benchmarks(c: &mut Criterion) {
+    let network = load_rolling_stock_problem_instance_from_json(medium_instance());
+    let schedule = MinCostFlowSolver::initialize(network.clone()).solve();
+    let schedule_with_info = ScheduleWithInfo::new(schedule, SwapInfo::NoSwap, String::new());
+    let neighborhood = RSSchedParallelNeighborhood::new(
+        Some(Duration::new("3:00:00")),
+        Some(Duration::new("0:10:00")),
+        network,
+    );
+
+    c.bench_function("segment_exchange_iterator", |b| {
+        b.iter(|| {
+            neighborhood
+                .segment_exchange_iterator(&schedule_with_info)
+                .count()
+        })
+    });
+
+    c.bench_function("neighbors_of", |b| {
+        b.iter(|| neighborhood.neighbors_of(&schedule_with_info).count())
+    });
+}
+
+criterion_group!(benches, neighborhood_benchmarks);
+criterion_main!(benches);
@@ -15,5 +15,8 @@ pub mod local_search;
 pub mod min_cost_flow_solver;
 pub mod objective;
 pub mod one_node_per_tour;
+pub mod phase_timings;
+pub mod progress;
+pub mod trace;
 pub mod transition_cycle_tsp;
 pub mod transition_local_search;
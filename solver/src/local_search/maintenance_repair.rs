@@ -0,0 +1,82 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::atomic::Ordering;
+
+use model::base_types::VehicleIdx;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use solution::Schedule;
+use tracing::info;
+
+use crate::phase_timings::IterationCounter;
+
+use super::neighborhood::swaps::{SpawnVehicleForMaintenance, Swap};
+
+/// Repair loop for parameters.maintenance.hard, run if the local search ends with a positive
+/// maintenance violation.
+/// In each step, every vehicle of a type with positive violation is forced onto every maintenance
+/// slot (see SpawnVehicleForMaintenance) and the schedule with the smallest violation (ties are
+/// broken by the number of vehicles and the costs) is taken. Stops as soon as the violation is
+/// zero or no spawn reduces it anymore.
+/// Each step increments the steps counter.
+pub fn repair_maintenance_violation(schedule: Schedule, steps: IterationCounter) -> Schedule {
+    let mut schedule = schedule;
+    while schedule.maintenance_violation() > 0 {
+        let network = schedule.get_network();
+        let violating_vehicles: Vec<VehicleIdx> = network
+            .vehicle_types()
+            .iter()
+            .filter(|&vehicle_type| {
+                schedule
+                    .next_day_transition_of(vehicle_type)
+                    .maintenance_violation()
+                    > 0
+            })
+            .flat_map(|vehicle_type| schedule.vehicles_iter(vehicle_type))
+            .collect();
+        let candidates: Vec<_> = network
+            .maintenance_nodes()
+            .flat_map(|maintenance_slot| {
+                violating_vehicles
+                    .iter()
+                    .map(move |&vehicle| SpawnVehicleForMaintenance::new(maintenance_slot, vehicle))
+            })
+            .collect();
+
+        let best_neighbor = candidates
+            .into_par_iter()
+            .filter_map(|swap| swap.apply(&schedule).ok())
+            .min_by_key(|neighbor| {
+                (
+                    neighbor.maintenance_violation(),
+                    neighbor.number_of_vehicles(),
+                    neighbor.costs(),
+                )
+            });
+        match best_neighbor {
+            Some(neighbor)
+                if neighbor.maintenance_violation() < schedule.maintenance_violation() =>
+            {
+                info!(
+                    "Maintenance repair: violation reduced from {} to {}",
+                    schedule.maintenance_violation(),
+                    neighbor.maintenance_violation()
+                );
+                steps.fetch_add(1, Ordering::Relaxed);
+                schedule = neighbor;
+            }
+            _ => break,
+        }
+    }
+    schedule
+}
@@ -11,18 +11,27 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod maintenance_repair;
 pub mod neighborhood;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{self as stdtime, Instant};
 
 use crate::objective;
+use crate::phase_timings::IterationCounter;
+use crate::progress::{Improvement, ProgressSink};
+use model::base_types::VehicleIdx;
 use model::network::Network;
 use rapid_solve::heuristics::parallel_local_search::ParallelLocalSearchSolver;
+use rapid_solve::heuristics::Solver;
 use rapid_solve::objective::{EvaluatedSolution, Objective};
 use solution::Schedule;
 
-use rapid_time::Duration;
+use rapid_time::{DateTime, Duration};
+use tracing::info;
 
+use self::neighborhood::operator_weights::OperatorWeights;
 use self::neighborhood::swaps::SwapInfo;
 use self::neighborhood::RSSchedParallelNeighborhood;
 
@@ -59,63 +68,399 @@ impl ScheduleWithInfo {
     }
 }
 
+/// Each step of the local search increments the steps counter and is reported to the progress
+/// sink (if given).
+/// If operator weights are given, they are adapted during the search (see OperatorWeights).
+/// Without time limit the search runs until no improving neighbor is found.
+/// Once the cancellation flag (if given) is set, the search stops after the current step and
+/// returns the best schedule found so far.
 pub fn build_local_search_solver(
     network: Arc<Network>,
+    steps: IterationCounter,
+    operator_weights: Option<Arc<OperatorWeights>>,
+    time_limit: Option<stdtime::Duration>,
+    progress_sink: Option<ProgressSink>,
+    cancellation: Option<Arc<AtomicBool>>,
 ) -> ParallelLocalSearchSolver<ScheduleWithInfo> {
-    let objective = Arc::new(objective::build());
+    let objective = objective::build(&network);
+    build_local_search_solver_with_neighborhood(
+        build_neighborhood(network, operator_weights, cancellation),
+        objective,
+        time_limit,
+        steps,
+        progress_sink,
+    )
+}
+
+/// Same as build_local_search_solver, but only nodes that lie completely inside [start, end] are
+/// modified (e.g., for a quick re-optimization around a new resource).
+pub fn build_focused_local_search_solver(
+    network: Arc<Network>,
+    steps: IterationCounter,
+    operator_weights: Option<Arc<OperatorWeights>>,
+    time_limit: Option<stdtime::Duration>,
+    (start, end): (DateTime, DateTime),
+    progress_sink: Option<ProgressSink>,
+    cancellation: Option<Arc<AtomicBool>>,
+) -> ParallelLocalSearchSolver<ScheduleWithInfo> {
+    let objective = objective::build(&network);
+    build_local_search_solver_with_neighborhood(
+        build_neighborhood(network, operator_weights, cancellation).restrict_to_time_window(
+            start,
+            end,
+            HashSet::new(),
+        ),
+        objective,
+        time_limit,
+        steps,
+        progress_sink,
+    )
+}
+
+/// The operator weights as configured by parameters.adaptiveOperatorWeights (None if not
+/// configured).
+pub fn build_operator_weights(network: &Network) -> Option<Arc<OperatorWeights>> {
+    network
+        .config()
+        .adaptive_operator_weights
+        .as_ref()
+        .map(|config| Arc::new(OperatorWeights::new(config)))
+}
+
+/// Decomposition for very large instances:
+/// The planning horizon is partitioned into overlapping time windows. The local search is run on
+/// each window one after another, where only nodes inside the window are modified and tours
+/// having a node that crosses a window boundary are frozen. As all windows work on the same
+/// schedule, the result of one window is the start of the next one.
+/// Finally, a global local search (limited by the polish time limit) polishes the schedule.
+/// The operator weights are carried over from one window to the next.
+/// The time limit bounds all windows and the polish together: each local search gets the
+/// remaining time, once it is used up the remaining windows and the polish are skipped.
+/// The same holds once the cancellation flag (if given) is set.
+/// The windows are configured by parameters.timePartitioning, which must be present.
+pub fn solve_time_partitioned(
+    start_solution: ScheduleWithInfo,
+    network: Arc<Network>,
+    steps: IterationCounter,
+    operator_weights: Option<Arc<OperatorWeights>>,
+    time_limit: Option<stdtime::Duration>,
+    progress_sink: Option<ProgressSink>,
+    cancellation: Option<Arc<AtomicBool>>,
+) -> EvaluatedSolution<ScheduleWithInfo> {
+    let config = network.config();
+    let time_partitioning = config
+        .time_partitioning
+        .as_ref()
+        .expect("solve_time_partitioned needs parameters.timePartitioning");
+    let (horizon_start, horizon_end) = planning_horizon(&network);
+    let step = time_partitioning.window_length - time_partitioning.overlap;
+    let deadline = time_limit.map(|time_limit| Instant::now() + time_limit);
+    let remaining_time =
+        || deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+    let is_cancelled = || {
+        cancellation
+            .as_ref()
+            .is_some_and(|cancellation| cancellation.load(Ordering::Relaxed))
+    };
 
-    let segment_limit = Duration::new("3:00:00");
-    let overhead_threshold = Duration::new("0:10:00"); // tours of real-vehicle-providers are not splitted at nodes under these duration
+    let mut current_solution = start_solution;
+    let mut window_start = horizon_start;
+    loop {
+        if remaining_time() == Some(stdtime::Duration::ZERO) {
+            info!("Time limit reached, skipping the remaining time windows");
+            break;
+        }
+        if is_cancelled() {
+            info!("Cancelled, skipping the remaining time windows");
+            break;
+        }
+        let window_end = window_start + time_partitioning.window_length;
+        info!(
+            "Local search on time window {} - {}:",
+            window_start.as_iso(),
+            window_end.as_iso()
+        );
+        let frozen_vehicles = vehicles_crossing_window_boundary(
+            current_solution.get_schedule(),
+            window_start,
+            window_end,
+        );
+        let neighborhood = build_neighborhood(
+            network.clone(),
+            operator_weights.clone(),
+            cancellation.clone(),
+        )
+        .restrict_to_time_window(window_start, window_end, frozen_vehicles);
+        current_solution = build_local_search_solver_with_neighborhood(
+            neighborhood,
+            objective::build(&network),
+            remaining_time(),
+            steps.clone(),
+            progress_sink.clone(),
+        )
+        .solve(current_solution)
+        .unwrap();
 
-    let neighborhood = Arc::new(RSSchedParallelNeighborhood::new(
-        Some(segment_limit),
-        Some(overhead_threshold),
+        if window_end >= horizon_end {
+            break;
+        }
+        window_start = window_start + step;
+    }
+
+    let polish_time_limit = time_partitioning
+        .polish_time_limit
+        .map(|limit| stdtime::Duration::from_secs(limit.in_sec().unwrap()))
+        .into_iter()
+        .chain(remaining_time())
+        .min();
+    let objective = objective::build(&network);
+    if polish_time_limit == Some(stdtime::Duration::ZERO) {
+        info!("Time limit reached, skipping the global polish");
+        return objective.evaluate(current_solution);
+    }
+    if is_cancelled() {
+        info!("Cancelled, skipping the global polish");
+        return objective.evaluate(current_solution);
+    }
+    info!("Global polish of the stitched schedule:");
+    build_local_search_solver_with_neighborhood(
+        build_neighborhood(network, operator_weights, cancellation),
+        objective,
+        polish_time_limit,
+        steps,
+        progress_sink,
+    )
+    .solve(current_solution)
+}
+
+/// Two-phase local search (parameters.solver.twoPhase):
+/// The fleet phase runs the local search with the vehicle count dominating the maintenance (see
+/// objective::build_fleet_first) until no improving neighbor is found. Then the number of
+/// vehicles is frozen and the quality phase optimizes the remaining levels of the usual
+/// objective within the fixed fleet (no vehicle is spawned or retired).
+/// The schedule of the fleet phase is returned together with the final solution.
+/// The time limit bounds both phases together, the quality phase is skipped once the time limit
+/// is used up or the cancellation flag (if given) is set.
+pub fn solve_two_phase(
+    start_solution: ScheduleWithInfo,
+    network: Arc<Network>,
+    steps: IterationCounter,
+    operator_weights: Option<Arc<OperatorWeights>>,
+    time_limit: Option<stdtime::Duration>,
+    progress_sink: Option<ProgressSink>,
+    cancellation: Option<Arc<AtomicBool>>,
+) -> (EvaluatedSolution<ScheduleWithInfo>, Schedule) {
+    let deadline = time_limit.map(|time_limit| Instant::now() + time_limit);
+    let remaining_time =
+        || deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+    info!("Fleet phase of the two-phase local search:");
+    let fleet_phase_solution = build_local_search_solver_with_neighborhood(
+        build_neighborhood(
+            network.clone(),
+            operator_weights.clone(),
+            cancellation.clone(),
+        ),
+        objective::build_fleet_first(&network),
+        remaining_time(),
+        steps.clone(),
+        progress_sink.clone(),
+    )
+    .solve(start_solution)
+    .unwrap();
+    let fleet_phase_schedule = fleet_phase_solution.get_schedule().clone();
+
+    let objective = objective::build(&network);
+    if remaining_time() == Some(stdtime::Duration::ZERO) {
+        info!("Time limit reached, skipping the quality phase");
+        return (
+            objective.evaluate(fleet_phase_solution),
+            fleet_phase_schedule,
+        );
+    }
+    if cancellation
+        .as_ref()
+        .is_some_and(|cancellation| cancellation.load(Ordering::Relaxed))
+    {
+        info!("Cancelled, skipping the quality phase");
+        return (
+            objective.evaluate(fleet_phase_solution),
+            fleet_phase_schedule,
+        );
+    }
+    info!(
+        "Quality phase of the two-phase local search (fleet of {} vehicles):",
+        fleet_phase_schedule.number_of_vehicles()
+    );
+    let solution = build_local_search_solver_with_neighborhood(
+        build_neighborhood(network, operator_weights, cancellation).with_fixed_vehicle_count(),
+        objective,
+        remaining_time(),
+        steps,
+        progress_sink,
+    )
+    .solve(fleet_phase_solution);
+    (solution, fleet_phase_schedule)
+}
+
+fn build_neighborhood(
+    network: Arc<Network>,
+    operator_weights: Option<Arc<OperatorWeights>>,
+    cancellation: Option<Arc<AtomicBool>>,
+) -> RSSchedParallelNeighborhood {
+    let neighborhood = RSSchedParallelNeighborhood::new(
+        Some(segment_length_limit()),
+        Some(overhead_threshold()),
         network,
-    ));
+    );
+    let neighborhood = match operator_weights {
+        Some(operator_weights) => neighborhood.with_operator_weights(operator_weights),
+        None => neighborhood,
+    };
+    match cancellation {
+        Some(cancellation) => neighborhood.with_cancellation(cancellation),
+        None => neighborhood,
+    }
+}
+
+fn segment_length_limit() -> Duration {
+    Duration::new("3:00:00")
+}
+
+/// tours of real-vehicle-providers are not splitted at nodes under these duration
+fn overhead_threshold() -> Duration {
+    Duration::new("0:10:00")
+}
+
+/// The settings of the local search neighborhood (durations in seconds).
+pub fn neighborhood_settings_to_json() -> serde_json::Value {
+    serde_json::json!({
+        "segmentLengthLimit": segment_length_limit().in_sec().unwrap(),
+        "overheadThreshold": overhead_threshold().in_sec().unwrap(),
+        "dummyCompactionThreshold": neighborhood::DUMMY_COMPACTION_THRESHOLD,
+    })
+}
+
+/// Earliest start and latest end of all service trips and maintenance slots.
+fn planning_horizon(network: &Network) -> (DateTime, DateTime) {
+    network.coverable_nodes().fold(
+        (DateTime::Latest, DateTime::Earliest),
+        |(start, end), node| {
+            (
+                start.min(network.node(node).start_time()),
+                end.max(network.node(node).end_time()),
+            )
+        },
+    )
+}
+
+/// All vehicles (including dummies) having a non-depot node that starts before and ends after
+/// the start or the end of the window.
+fn vehicles_crossing_window_boundary(
+    schedule: &Schedule,
+    window_start: DateTime,
+    window_end: DateTime,
+) -> HashSet<VehicleIdx> {
+    let network = schedule.get_network();
+    schedule
+        .vehicles_iter_all()
+        .chain(schedule.dummy_iter())
+        .filter(|&vehicle| {
+            schedule
+                .tour_of(vehicle)
+                .unwrap()
+                .all_non_depot_nodes_iter()
+                .any(|node| {
+                    let node = network.node(node);
+                    [window_start, window_end]
+                        .iter()
+                        .any(|&boundary| node.start_time() < boundary && node.end_time() > boundary)
+                })
+        })
+        .collect()
+}
+
+fn build_local_search_solver_with_neighborhood(
+    neighborhood: RSSchedParallelNeighborhood,
+    objective: Objective<ScheduleWithInfo>,
+    time_limit: Option<stdtime::Duration>,
+    steps: IterationCounter,
+    progress_sink: Option<ProgressSink>,
+) -> ParallelLocalSearchSolver<ScheduleWithInfo> {
+    let objective = Arc::new(objective);
+    let operator_weights = neighborhood.operator_weights();
 
     let function_between_steps = Box::new(
-        |iteration_counter: u32,
-         current_solution: &EvaluatedSolution<ScheduleWithInfo>,
-         previous_solution: Option<&EvaluatedSolution<ScheduleWithInfo>>,
-         objective: Arc<Objective<ScheduleWithInfo>>,
-         start_time: Option<Instant>,
-         _: Option<stdtime::Duration>,
-         _: Option<u32>| {
-            println!(
+        move |iteration_counter: u32,
+              current_solution: &EvaluatedSolution<ScheduleWithInfo>,
+              previous_solution: Option<&EvaluatedSolution<ScheduleWithInfo>>,
+              objective: Arc<Objective<ScheduleWithInfo>>,
+              start_time: Option<Instant>,
+              _: Option<stdtime::Duration>,
+              _: Option<u32>| {
+            let iteration = steps.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(progress_sink) = &progress_sink {
+                progress_sink.report(
+                    Improvement {
+                        iteration,
+                        objective_value: objective
+                            .objective_value_to_json(current_solution.objective_value()),
+                        swap_info: current_solution.solution().get_last_swap_info(),
+                        description: current_solution.solution().get_print_text().to_string(),
+                    },
+                    current_solution.solution().get_schedule(),
+                );
+            }
+            if let Some(operator_weights) = &operator_weights {
+                operator_weights.record_step(current_solution.solution().get_last_swap_info());
+            }
+            info!(
                 "Iteration {} - Swap: {}",
                 iteration_counter,
                 current_solution.solution().get_print_text()
             );
-            println!("Objective value:");
-            match previous_solution {
-                Some(prev_solution) => {
-                    objective.print_objective_value_with_comparison(
-                        current_solution.objective_value(),
-                        prev_solution.objective_value(),
+            let schedule = current_solution.solution().get_schedule();
+            if schedule.get_network().config().check_costs {
+                if let Err(error) = schedule.check_costs() {
+                    panic!(
+                        "Cost invariant violated after iteration {}: {}",
+                        iteration_counter, error
                     );
                 }
-                None => {
-                    objective.print_objective_value(current_solution.objective_value());
-                }
+                // includes that the transitions only reference existing vehicles
+                schedule.verify_consistency();
+            }
+            match previous_solution {
+                Some(previous_solution) => info!(
+                    "Objective value: {}",
+                    crate::objective::objective_value_comparison_to_json(
+                        &objective,
+                        previous_solution.objective_value(),
+                        current_solution.objective_value()
+                    )
+                ),
+                None => info!(
+                    "Objective value: {}",
+                    objective.objective_value_to_json(current_solution.objective_value())
+                ),
             }
             if let Some(start_time) = start_time {
-                println!(
+                info!(
                     "elapsed time for local search: {:0.2}sec",
                     stdtime::Instant::now()
                         .duration_since(start_time)
                         .as_secs_f32()
                 );
             }
-            println!();
         },
     );
 
     ParallelLocalSearchSolver::with_options(
-        neighborhood,
+        Arc::new(neighborhood),
         objective,
         None,
         Some(function_between_steps),
-        None,
+        time_limit,
         None,
     )
 }
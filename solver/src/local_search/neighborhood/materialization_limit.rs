@@ -0,0 +1,89 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::{Condvar, Mutex};
+
+use model::network::Network;
+use tracing::info;
+
+/// Rough size of the data of a materialized neighbor per node of the network (tours, train
+/// formations and transitions that are not shared with the current schedule).
+const ESTIMATED_BYTES_PER_NODE: u64 = 512;
+
+/// Memory model of the local search:
+/// The network and the current schedule are held once. Each neighbor is a copy of the current
+/// schedule, where the persistent data structures share all unchanged parts, so a neighbor costs
+/// roughly ESTIMATED_BYTES_PER_NODE per node of the network. Without a limit, every rayon thread
+/// materializes neighbors as fast as it can, so the number of neighbors alive at the same time
+/// grows with the number of threads.
+/// With parameters.memoryBudget, at most permits neighbors are materialized at the same time,
+/// where half of the budget is reserved for the network and the current schedule. Threads
+/// exceeding the limit wait for a permit.
+/// The permit is released as soon as the neighbor is built. The improver of the local search
+/// keeps at most the best neighbor of each of its parallel reductions, which is not bounded
+/// by the limit.
+pub struct MaterializationLimit {
+    permits: usize,
+    in_use: Mutex<usize>,
+    released: Condvar,
+}
+
+impl MaterializationLimit {
+    pub fn new(permits: usize) -> MaterializationLimit {
+        MaterializationLimit {
+            permits: permits.max(1),
+            in_use: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// The limit for the given memory budget (in megabytes), at least one permit.
+    pub fn for_memory_budget(memory_budget: u64, network: &Network) -> MaterializationLimit {
+        let bytes_per_neighbor = ESTIMATED_BYTES_PER_NODE * network.size().max(1) as u64;
+        let permits = (memory_budget * 1024 * 1024 / 2 / bytes_per_neighbor) as usize;
+        let limit = MaterializationLimit::new(permits);
+        info!(
+            "Memory budget of {}MB: at most {} neighbors are materialized at the same time",
+            memory_budget, limit.permits
+        );
+        limit
+    }
+
+    pub fn permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Runs f as soon as a permit is available. The permit is released when f returns (or
+    /// panics).
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        let mut in_use = self.in_use.lock().unwrap();
+        while *in_use >= self.permits {
+            in_use = self.released.wait(in_use).unwrap();
+        }
+        *in_use += 1;
+        drop(in_use);
+
+        let _permit = Permit(self);
+        f()
+    }
+}
+
+struct Permit<'a>(&'a MaterializationLimit);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut in_use = self.0.in_use.lock().unwrap();
+        *in_use -= 1;
+        self.0.released.notify_one();
+    }
+}
@@ -11,25 +11,45 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod materialization_limit;
+pub mod operator_weights;
 pub mod swaps;
-use model::base_types::{NodeIdx, VehicleIdx};
+pub mod tour_interval_index;
+use model::base_types::{NodeIdx, VehicleIdx, VehicleTypeIdx};
 use model::network::Network;
 use rapid_solve::heuristics::common::ParallelNeighborhood;
-use rapid_time::Duration;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rapid_time::{DateTime, Duration};
+use rayon::iter::{Either, IntoParallelIterator, ParallelIterator};
 use solution::{segment::Segment, Schedule};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use std::iter;
 
-use self::swaps::{PathExchange, SpawnVehicleForMaintenance, Swap, SwapInfo};
+use self::materialization_limit::MaterializationLimit;
+use self::operator_weights::{Operator, OperatorWeights};
+use self::swaps::{
+    PathExchange, SpawnVehicleForMaintenance, SpawnVehicleForMinimalFormation, Swap, SwapInfo,
+};
+use self::tour_interval_index::TourIntervalIndex;
 
 use super::ScheduleWithInfo;
 
+/// Swaps that create a new dummy tour while the schedule has more dummy tours than this are
+/// followed by a compaction of the dummy tours (see Schedule::compact_dummies()).
+pub const DUMMY_COMPACTION_THRESHOLD: usize = 50;
+
 #[derive(Clone)]
 pub struct RSSchedParallelNeighborhood {
     segment_length_limit: Option<Duration>,
     overhead_threshold: Option<Duration>,
+    time_window: Option<(DateTime, DateTime)>, // only nodes inside this window are modified
+    frozen_vehicles: Arc<HashSet<VehicleIdx>>, // tours of these vehicles are not modified
+    operator_weights: Option<Arc<OperatorWeights>>, // None means all candidates are applied
+    materialization_limit: Option<Arc<MaterializationLimit>>, // from parameters.memoryBudget
+    cancellation: Option<Arc<AtomicBool>>,     // no neighbors once the flag is set
+    fixed_vehicle_count: bool, // neighbors keep the number of vehicles of the schedule
     network: Arc<Network>,
 }
 
@@ -39,12 +59,143 @@ impl RSSchedParallelNeighborhood {
         overhead_threshold: Option<Duration>,
         network: Arc<Network>,
     ) -> RSSchedParallelNeighborhood {
+        let materialization_limit = network.config().memory_budget.map(|memory_budget| {
+            Arc::new(MaterializationLimit::for_memory_budget(
+                memory_budget,
+                &network,
+            ))
+        });
         RSSchedParallelNeighborhood {
             segment_length_limit,
             overhead_threshold,
+            time_window: None,
+            frozen_vehicles: Arc::new(HashSet::new()),
+            operator_weights: None,
+            materialization_limit,
+            cancellation: None,
+            fixed_vehicle_count: false,
             network,
         }
     }
+
+    /// Only a share of the candidate swaps of each operator is applied (see OperatorWeights).
+    pub fn with_operator_weights(
+        self,
+        operator_weights: Arc<OperatorWeights>,
+    ) -> RSSchedParallelNeighborhood {
+        RSSchedParallelNeighborhood {
+            operator_weights: Some(operator_weights),
+            ..self
+        }
+    }
+
+    pub fn operator_weights(&self) -> Option<Arc<OperatorWeights>> {
+        self.operator_weights.clone()
+    }
+
+    /// Restricts the neighborhood to nodes that lie completely inside [start, end].
+    /// The tours of the frozen vehicles are neither used as provider nor as receiver.
+    pub fn restrict_to_time_window(
+        self,
+        start: DateTime,
+        end: DateTime,
+        frozen_vehicles: HashSet<VehicleIdx>,
+    ) -> RSSchedParallelNeighborhood {
+        RSSchedParallelNeighborhood {
+            time_window: Some((start, end)),
+            frozen_vehicles: Arc::new(frozen_vehicles),
+            ..self
+        }
+    }
+
+    /// Once the flag is set, schedules have no neighbors, so the local search stops after the
+    /// current step and returns the best schedule found so far.
+    pub fn with_cancellation(self, cancellation: Arc<AtomicBool>) -> RSSchedParallelNeighborhood {
+        RSSchedParallelNeighborhood {
+            cancellation: Some(cancellation),
+            ..self
+        }
+    }
+
+    /// Neighbors have the same number of vehicles as the schedule: the spawning operators are
+    /// disabled and swaps that retire a vehicle (e.g., by moving its whole tour away) are
+    /// discarded.
+    pub fn with_fixed_vehicle_count(self) -> RSSchedParallelNeighborhood {
+        RSSchedParallelNeighborhood {
+            fixed_vehicle_count: true,
+            ..self
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(|cancellation| cancellation.load(Ordering::Relaxed))
+    }
+
+    fn is_inside_time_window(&self, node: NodeIdx) -> bool {
+        match self.time_window {
+            None => true,
+            Some((start, end)) => {
+                let node = self.network.node(node);
+                node.start_time() >= start && node.end_time() <= end
+            }
+        }
+    }
+
+    /// Real vehicles of inactive vehicle types (see parameters.activeVehicleTypes) are frozen as
+    /// well.
+    fn is_frozen(&self, vehicle: VehicleIdx, schedule: &Schedule) -> bool {
+        self.frozen_vehicles.contains(&vehicle)
+            || schedule.vehicle_type_of(vehicle).is_ok_and(|vehicle_type| {
+                !self.network.config().is_active_vehicle_type(vehicle_type)
+            })
+    }
+
+    /// Nodes outside the time window and service trips of inactive vehicle types are kept where
+    /// they are (the latter might be on dummy tours).
+    fn is_modifiable(&self, node: NodeIdx) -> bool {
+        self.is_inside_time_window(node) && self.is_of_active_vehicle_type(node)
+    }
+
+    fn is_of_active_vehicle_type(&self, node: NodeIdx) -> bool {
+        !self.network.node(node).is_service()
+            || self
+                .network
+                .config()
+                .is_active_vehicle_type(self.network.vehicle_type_for(node))
+    }
+
+    /// With parameters.maintenance.hard, a schedule without maintenance violation only has
+    /// neighbors without maintenance violation.
+    fn must_keep_maintenance_feasible(&self, schedule: &Schedule) -> bool {
+        self.network.config().maintenance.hard && schedule.maintenance_violation() == 0
+    }
+
+    /// Applies the swap unless it is skipped by the sampling of the operator. Generated neighbors
+    /// are counted for the adaption of the operator weights.
+    /// With a memory budget, the swap waits for a permit of the materialization limit.
+    fn apply_sampled(
+        &self,
+        operator: Operator,
+        swap: &impl Swap,
+        schedule: &Schedule,
+    ) -> Option<Schedule> {
+        if let Some(operator_weights) = &self.operator_weights {
+            if !operator_weights.sample(operator) {
+                return None;
+            }
+        }
+        let new_schedule = match &self.materialization_limit {
+            Some(limit) => limit.run(|| swap.apply(schedule)),
+            None => swap.apply(schedule),
+        }
+        .ok()?;
+        if let Some(operator_weights) = &self.operator_weights {
+            operator_weights.record_generated(operator);
+        }
+        Some(new_schedule)
+    }
 }
 
 impl ParallelNeighborhood<ScheduleWithInfo> for RSSchedParallelNeighborhood {
@@ -52,17 +203,58 @@ impl ParallelNeighborhood<ScheduleWithInfo> for RSSchedParallelNeighborhood {
         &'a self,
         schedule_with_info: &'a ScheduleWithInfo,
     ) -> impl ParallelIterator<Item = ScheduleWithInfo> + 'a {
+        if self.is_cancelled() {
+            return Either::Left(rayon::iter::empty());
+        }
         let spawning_iterator = self.spawn_vehicle_for_maintenance_iterator(schedule_with_info);
+        let minimal_formation_iterator =
+            self.spawn_vehicle_for_minimal_formation_iterator(schedule_with_info);
         let segment_exchange_iterator = self.segment_exchange_iterator(schedule_with_info);
         let hitch_hiking_iterator = self.hitch_hiking_iterator(schedule_with_info);
         let remove_single_node_iterator = self.remove_single_node_iterator(schedule_with_info);
-        spawning_iterator
-            .chain(segment_exchange_iterator)
-            .chain(hitch_hiking_iterator)
-            .chain(remove_single_node_iterator)
+        let keep_maintenance_feasible =
+            self.must_keep_maintenance_feasible(schedule_with_info.get_schedule());
+        let fixed_vehicle_count = self
+            .fixed_vehicle_count
+            .then(|| schedule_with_info.get_schedule().number_of_vehicles());
+        Either::Right(
+            spawning_iterator
+                .chain(minimal_formation_iterator)
+                .chain(segment_exchange_iterator)
+                .chain(hitch_hiking_iterator)
+                .chain(remove_single_node_iterator)
+                .filter(move |neighbor| {
+                    !keep_maintenance_feasible
+                        || neighbor.get_schedule().maintenance_violation() == 0
+                })
+                .filter(move |neighbor| {
+                    fixed_vehicle_count.is_none_or(|number_of_vehicles| {
+                        neighbor.get_schedule().number_of_vehicles() == number_of_vehicles
+                    })
+                })
+                .map(move |neighbor| {
+                    compact_dummies_if_needed(schedule_with_info.get_schedule(), neighbor)
+                }),
+        )
     }
 }
 
+/// Long searches displace many trips into tiny dummy tours, which are cloned with every
+/// schedule. Hence, they are merged as soon as there are too many of them.
+fn compact_dummies_if_needed(schedule: &Schedule, neighbor: ScheduleWithInfo) -> ScheduleWithInfo {
+    let number_of_dummy_tours = neighbor.get_schedule().number_of_dummy_tours();
+    if number_of_dummy_tours <= DUMMY_COMPACTION_THRESHOLD
+        || number_of_dummy_tours <= schedule.number_of_dummy_tours()
+    {
+        return neighbor;
+    }
+    ScheduleWithInfo::new(
+        neighbor.get_schedule().compact_dummies(),
+        neighbor.get_last_swap_info(),
+        neighbor.get_print_text().to_string(),
+    )
+}
+
 impl RSSchedParallelNeighborhood {
     pub fn spawn_vehicle_for_maintenance_iterator<'a>(
         &'a self,
@@ -74,53 +266,109 @@ impl RSSchedParallelNeighborhood {
             .network
             .maintenance_nodes()
             .filter(|&m| {
-                schedule.train_formation_of(m).vehicle_count()
-                    < self.network.track_count_of_maintenance_slot(m)
+                !self.fixed_vehicle_count
+                    && self.is_modifiable(m)
+                    && schedule.train_formation_of(m).vehicle_count()
+                        < self.network.track_count_of_maintenance_slot(m)
             })
             .collect();
 
-        // sort maintenance nodes by workload
+        // sort maintenance nodes by workload (occupied share of the tracks). A slot with a
+        // service duration consists of sub-slot nodes, each having the full track count.
         maintenance_nodes.sort_by_key(|&m| {
             (schedule.train_formation_of(m).vehicle_count() * 10000)
                 / self.network.track_count_of_maintenance_slot(m)
         });
 
+        // vehicles in transition cycles without any maintenance visit are tried first
+        let vehicles_without_maintenance: HashSet<VehicleIdx> =
+            schedule.vehicles_without_maintenance().collect();
+
         maintenance_nodes
             .into_par_iter()
             .flat_map(move |maintenance| {
-                let receivers: Vec<_> = schedule.vehicles_iter_all().collect();
+                let mut receivers: Vec<_> = schedule
+                    .vehicles_iter_all()
+                    .filter(|&v| !self.is_frozen(v, schedule))
+                    .collect();
+                receivers.sort_by_key(|v| !vehicles_without_maintenance.contains(v));
                 receivers.into_par_iter().filter_map(move |receiver| {
                     let swap = SpawnVehicleForMaintenance::new(maintenance, receiver);
-                    match swap.apply(schedule) {
-                        Ok(new_schedule) => Some(ScheduleWithInfo::new(
-                            new_schedule,
-                            SwapInfo::SpawnVehicleForMaintenance(receiver),
-                            format!(
-                                "{} ({})",
-                                swap,
-                                self.network
-                                    .vehicle_types()
-                                    .get(schedule.vehicle_type_of(receiver).unwrap())
-                                    .unwrap(),
-                            ),
-                        )),
-                        Err(_) => None,
-                    }
+                    self.apply_sampled(Operator::SpawnVehicleForMaintenance, &swap, schedule)
+                        .map(|new_schedule| {
+                            ScheduleWithInfo::new(
+                                new_schedule,
+                                SwapInfo::SpawnVehicleForMaintenance(receiver),
+                                format!(
+                                    "{} ({})",
+                                    swap.describe(&self.network),
+                                    self.network
+                                        .vehicle_types()
+                                        .get(schedule.vehicle_type_of(receiver).unwrap())
+                                        .unwrap(),
+                                ),
+                            )
+                        })
                 })
             })
     }
 
+    /// Targets the service trips whose train formation is below the minimal formation count.
+    /// Each such trip is forced onto every compatible vehicle, either as hitch-hiker (if the trip
+    /// fits into the tour) or by spawning a new vehicle for the conflicting part of the tour.
+    pub fn spawn_vehicle_for_minimal_formation_iterator<'a>(
+        &'a self,
+        schedule_with_info: &'a ScheduleWithInfo,
+    ) -> impl ParallelIterator<Item = ScheduleWithInfo> + 'a {
+        let schedule = schedule_with_info.get_schedule();
+
+        let service_trips: Vec<NodeIdx> = schedule
+            .service_trips_below_minimal_formation_count()
+            .filter(|&n| !self.fixed_vehicle_count && self.is_modifiable(n))
+            .collect();
+
+        service_trips.into_par_iter().flat_map(move |service_trip| {
+            let vehicle_type = self.network.vehicle_type_for(service_trip);
+            let receivers: Vec<_> = schedule
+                .vehicles_iter(vehicle_type)
+                .filter(|&v| !self.is_frozen(v, schedule))
+                .collect();
+            receivers.into_par_iter().filter_map(move |receiver| {
+                let swap = SpawnVehicleForMinimalFormation::new(service_trip, receiver);
+                self.apply_sampled(Operator::SpawnVehicleForMinimalFormation, &swap, schedule)
+                    .map(|new_schedule| {
+                        ScheduleWithInfo::new(
+                            new_schedule,
+                            SwapInfo::SpawnVehicleForMinimalFormation(receiver),
+                            swap.describe(&self.network),
+                        )
+                    })
+            })
+        })
+    }
+
     /// Creates all PathExchanges where every vehicle is receiver and every other vehicle is provider.
     /// All segments starting and ending at a non-depot node are considered.
     /// The segment time length is smaller than the threshold. (None means unlimite.)
     /// The length of a segment is measured from the start_time of the first node to the end_time of
     /// the last node.
+    /// Receivers that cannot take the segment due to their vehicle type are skipped (see
+    /// receivers_of_segment).
     pub fn segment_exchange_iterator<'a>(
         &'a self,
         schedule_with_info: &'a ScheduleWithInfo,
     ) -> impl ParallelIterator<Item = ScheduleWithInfo> + 'a {
         let schedule = schedule_with_info.get_schedule();
         let mut providers: Vec<VehicleIdx> = self.dummy_and_real_vehicles(schedule).collect();
+        let receivers: Arc<Vec<VehicleIdx>> = Arc::new(
+            schedule
+                .vehicles_iter_all()
+                .chain(schedule.dummy_iter())
+                .filter(|&v| !self.is_frozen(v, schedule))
+                .collect(),
+        );
+        let tour_interval_index =
+            Arc::new(TourIntervalIndex::new(schedule, receivers.iter().copied()));
 
         // rotate providers such that start_provider is the first provider
         // e.g. start_provider = v5
@@ -136,33 +384,85 @@ impl RSSchedParallelNeighborhood {
         providers.into_par_iter().flat_map(move |provider|
             // create segment of provider's tour
             self.segments(provider, schedule)
-            .flat_map(move |seg|
-                // as receiver first take the real Vehicles then the dummies
-                self.real_and_dummy_vehicles(schedule)
-                // skip provider as receiver
-                .filter(move |&u| u != provider)
+            .flat_map({
+                let receivers = receivers.clone();
+                let tour_interval_index = tour_interval_index.clone();
+                move |seg|
+                self.receivers_of_segment(seg, provider, schedule, &receivers, &tour_interval_index)
+                .into_par_iter()
                 // create the swap
                 .filter_map(move |receiver|{
                     let swap = PathExchange::new(seg, provider, receiver);
-                    match swap.apply(schedule) {
-                        Ok(new_schedule) => {
-                            Some(ScheduleWithInfo::new(
-                                new_schedule,
-                                SwapInfo::PathExchange(provider),
-                                format!(
-                                    "PathExchange {} from {}{} to {}{}",
-                                    seg,
-                                    provider,
-                                    schedule.vehicle_type_of(provider).map(|vt| format!(" ({})", self.network.vehicle_types().get(vt).unwrap())).unwrap_or("".to_string()),
-                                    receiver,
-                                    schedule.vehicle_type_of(receiver).map(|vt| format!(" ({})", self.network.vehicle_types().get(vt).unwrap())).unwrap_or("".to_string()),
-                                )
-                            ))
-                        }
-                        Err(_) => None,
-                    }
+                    self.apply_sampled(Operator::PathExchange, &swap, schedule).map(|new_schedule|
+                        ScheduleWithInfo::new(
+                            new_schedule,
+                            SwapInfo::PathExchange(provider),
+                            format!(
+                                "PathExchange {} from {}{} to {}{}",
+                                seg.describe(&self.network),
+                                provider,
+                                schedule.vehicle_type_of(provider).map(|vt| format!(" ({})", self.network.vehicle_types().get(vt).unwrap())).unwrap_or("".to_string()),
+                                receiver,
+                                schedule.vehicle_type_of(receiver).map(|vt| format!(" ({})", self.network.vehicle_types().get(vt).unwrap())).unwrap_or("".to_string()),
+                            )
+                        )
+                    )
                 })
-            ))
+            }))
+    }
+
+    /// The receivers (all non-frozen vehicles except the provider) for which the vehicle types
+    /// allow the segment to be moved, i.e., exactly the receivers passing the type check of
+    /// Schedule::override_reassign: dummies, vehicles of the provider's type and vehicles whose
+    /// type is compatible with all nodes of the segment.
+    /// Receivers with an idle gap around the segment (see TourIntervalIndex) come first, as they
+    /// take the segment without displacing any node. Otherwise, real vehicles come before dummies.
+    fn receivers_of_segment(
+        &self,
+        segment: Segment,
+        provider: VehicleIdx,
+        schedule: &Schedule,
+        receivers: &[VehicleIdx],
+        tour_interval_index: &TourIntervalIndex,
+    ) -> Vec<VehicleIdx> {
+        let provider_type = schedule.vehicle_type_of(provider).ok();
+        let nodes: Vec<NodeIdx> = schedule
+            .tour_of(provider)
+            .unwrap()
+            .sub_path(segment)
+            .unwrap()
+            .iter()
+            .collect();
+        let compatible_types: HashSet<VehicleTypeIdx> = self
+            .network
+            .vehicle_types()
+            .iter()
+            .filter(|&vehicle_type| {
+                nodes.iter().all(|&node| {
+                    self.network
+                        .compatible_with_vehicle_type(node, vehicle_type)
+                })
+            })
+            .collect();
+        let conflict_free = tour_interval_index.vehicles_with_gap_containing(
+            self.network.node(segment.start()).start_time(),
+            self.network.node(segment.end()).end_time(),
+        );
+
+        let mut receivers: Vec<VehicleIdx> = receivers
+            .iter()
+            .copied()
+            .filter(|&receiver| receiver != provider)
+            .filter(|&receiver| match schedule.vehicle_type_of(receiver) {
+                Err(_) => true, // dummy
+                Ok(vehicle_type) => {
+                    Some(vehicle_type) == provider_type || compatible_types.contains(&vehicle_type)
+                }
+            })
+            .collect();
+        // stable, so real vehicles stay before dummies
+        receivers.sort_by_key(|receiver| !conflict_free.contains(receiver));
+        receivers
     }
 
     pub fn hitch_hiking_iterator<'a>(
@@ -171,21 +471,28 @@ impl RSSchedParallelNeighborhood {
     ) -> impl ParallelIterator<Item = ScheduleWithInfo> + 'a {
         let schedule = schedule_with_info.get_schedule();
 
-        let vehicles: Vec<_> = schedule.vehicles_iter_all().collect();
+        let vehicles: Vec<_> = schedule
+            .vehicles_iter_all()
+            .filter(|&v| !self.is_frozen(v, schedule))
+            .collect();
 
         vehicles.into_par_iter().flat_map(move |vehicle| {
             let vehicle_type = schedule.vehicle_type_of(vehicle).unwrap();
-            let service_nodes: Vec<_> = self.network.service_nodes(vehicle_type).collect();
+            let service_nodes: Vec<_> = self
+                .network
+                .service_nodes(vehicle_type)
+                .filter(|&n| self.is_modifiable(n))
+                .collect();
             service_nodes.into_par_iter().filter_map(move |node| {
                 let swap = swaps::AddTripForHitchHiking::new(node, vehicle);
-                match swap.apply(schedule) {
-                    Ok(new_schedule) => Some(ScheduleWithInfo::new(
-                        new_schedule,
-                        SwapInfo::AddTripForHitchHiking(vehicle),
-                        format!("{}", swap),
-                    )),
-                    Err(_) => None,
-                }
+                self.apply_sampled(Operator::AddTripForHitchHiking, &swap, schedule)
+                    .map(|new_schedule| {
+                        ScheduleWithInfo::new(
+                            new_schedule,
+                            SwapInfo::AddTripForHitchHiking(vehicle),
+                            swap.describe(&self.network),
+                        )
+                    })
             })
         })
     }
@@ -195,21 +502,27 @@ impl RSSchedParallelNeighborhood {
         schedule_with_info: &'a ScheduleWithInfo,
     ) -> impl ParallelIterator<Item = ScheduleWithInfo> + 'a {
         let schedule = schedule_with_info.get_schedule();
-        let vehicles: Vec<_> = schedule.vehicles_iter_all().collect();
+        let vehicles: Vec<_> = schedule
+            .vehicles_iter_all()
+            .filter(|&v| !self.is_frozen(v, schedule))
+            .collect();
 
         vehicles.into_par_iter().flat_map(move |vehicle| {
             let tour = schedule.tour_of(vehicle).unwrap();
-            let non_depot_nodes: Vec<_> = tour.all_non_depot_nodes_iter().collect();
+            let non_depot_nodes: Vec<_> = tour
+                .all_non_depot_nodes_iter()
+                .filter(|&n| self.is_modifiable(n))
+                .collect();
             non_depot_nodes.into_par_iter().filter_map(move |node| {
                 let swap = swaps::RemoveSingleNode::new(node, vehicle);
-                match swap.apply(schedule) {
-                    Ok(new_schedule) => Some(ScheduleWithInfo::new(
-                        new_schedule,
-                        SwapInfo::RemoveSingleNode(vehicle),
-                        format!("{}", swap),
-                    )),
-                    Err(_) => None,
-                }
+                self.apply_sampled(Operator::RemoveSingleNode, &swap, schedule)
+                    .map(|new_schedule| {
+                        ScheduleWithInfo::new(
+                            new_schedule,
+                            SwapInfo::RemoveSingleNode(vehicle),
+                            swap.describe(&self.network),
+                        )
+                    })
             })
         })
     }
@@ -230,20 +543,29 @@ impl RSSchedParallelNeighborhood {
         let start_nodes: Vec<_> = tour.all_non_depot_nodes_iter().enumerate().collect();
         start_nodes
             .into_par_iter()
-            // only take nodes with enough preceding overhead:
+            // only take nodes with enough preceding overhead or after a split point:
             .filter(move |(_, n)| {
-                schedule.is_dummy(provider) || tour.preceding_overhead(*n).unwrap() >= threshold
+                schedule.is_dummy(provider)
+                    || self.network.split_predecessor(*n).is_some()
+                    || tour.preceding_overhead(*n).unwrap() >= threshold
             })
+            // only take modifiable nodes:
+            .filter(move |(_, n)| self.is_modifiable(*n))
             .flat_map(move |(i, seg_start)| {
                 // all non-depot nodes (after the start) could be the end of the segment
                 let end_nodes: Vec<_> = tour
                     .all_non_depot_nodes_iter()
                     .skip(i)
-                    // only take nodes with enough subsequent overhead:
+                    // a segment must not contain service trips of inactive vehicle types:
+                    .take_while(move |n| self.is_of_active_vehicle_type(*n))
+                    // only take nodes with enough subsequent overhead or before a split point:
                     .filter(move |n| {
                         schedule.is_dummy(provider)
+                            || self.network.split_successor(*n).is_some()
                             || tour.subsequent_overhead(*n).unwrap() >= threshold
                     })
+                    // only take modifiable nodes:
+                    .filter(move |n| self.is_modifiable(*n))
                     // only take the nodes such that the segment is not longer than the threshold
                     .take_while(move |seg_end| {
                         self.segment_length_limit.is_none()
@@ -254,8 +576,16 @@ impl RSSchedParallelNeighborhood {
                     // if provider is a real vehicle, add the last_node as segment end. (note that is not taken twice as EndNodes
                     // end at time Infinity
                     .chain(
-                        iter::once(schedule.tour_of(provider).unwrap().last_node())
-                            .filter(move |_n| self.segment_length_limit.is_some()),
+                        iter::once(schedule.tour_of(provider).unwrap().last_node()).filter(
+                            move |_n| {
+                                self.segment_length_limit.is_some()
+                                    && self.time_window.is_none()
+                                    && tour
+                                        .all_non_depot_nodes_iter()
+                                        .skip(i)
+                                        .all(|n| self.is_of_active_vehicle_type(n))
+                            },
+                        ),
                     )
                     .collect();
                 // create the segment
@@ -280,17 +610,7 @@ impl RSSchedParallelNeighborhood {
         let vehicles: Vec<_> = schedule
             .dummy_iter()
             .chain(schedule.vehicles_iter_all())
-            .collect();
-        vehicles.into_par_iter()
-    }
-
-    fn real_and_dummy_vehicles<'a>(
-        &'a self,
-        schedule: &'a Schedule,
-    ) -> impl ParallelIterator<Item = VehicleIdx> + 'a {
-        let vehicles: Vec<_> = schedule
-            .vehicles_iter_all()
-            .chain(schedule.dummy_iter())
+            .filter(|&v| !self.is_frozen(v, schedule))
             .collect();
         vehicles.into_par_iter()
     }
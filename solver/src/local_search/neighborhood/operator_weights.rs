@@ -0,0 +1,205 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use model::config::AdaptiveOperatorWeightsConfig;
+use tracing::{info, warn};
+
+use super::swaps::SwapInfo;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    SpawnVehicleForMaintenance,
+    SpawnVehicleForMinimalFormation,
+    PathExchange,
+    AddTripForHitchHiking,
+    RemoveSingleNode,
+}
+
+const NUMBER_OF_OPERATORS: usize = 5;
+
+impl Operator {
+    pub const ALL: [Operator; NUMBER_OF_OPERATORS] = [
+        Operator::SpawnVehicleForMaintenance,
+        Operator::SpawnVehicleForMinimalFormation,
+        Operator::PathExchange,
+        Operator::AddTripForHitchHiking,
+        Operator::RemoveSingleNode,
+    ];
+
+    /// The name used in the configuration and in the output.
+    pub fn name(self) -> &'static str {
+        match self {
+            Operator::SpawnVehicleForMaintenance => "spawnVehicleForMaintenance",
+            Operator::SpawnVehicleForMinimalFormation => "spawnVehicleForMinimalFormation",
+            Operator::PathExchange => "pathExchange",
+            Operator::AddTripForHitchHiking => "addTripForHitchHiking",
+            Operator::RemoveSingleNode => "removeSingleNode",
+        }
+    }
+
+    /// The operator that created the swap (None for NoSwap).
+    pub fn of(swap_info: SwapInfo) -> Option<Operator> {
+        match swap_info {
+            SwapInfo::SpawnVehicleForMaintenance(_) => Some(Operator::SpawnVehicleForMaintenance),
+            SwapInfo::SpawnVehicleForMinimalFormation(_) => {
+                Some(Operator::SpawnVehicleForMinimalFormation)
+            }
+            SwapInfo::PathExchange(_) => Some(Operator::PathExchange),
+            SwapInfo::AddTripForHitchHiking(_) => Some(Operator::AddTripForHitchHiking),
+            SwapInfo::RemoveSingleNode(_) => Some(Operator::RemoveSingleNode),
+            SwapInfo::NoSwap => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Adaptive weighting of the neighborhood operators (similar to the weights of an adaptive large
+/// neighborhood search):
+/// Each operator has a share in [minimal_share, maximal_share], only this share of its candidate
+/// swaps is applied. For every operator the generated neighbors and the accepted steps are
+/// counted. Every interval steps, the shares are moved (by the reaction factor) towards the
+/// success rates (accepted steps / generated neighbors) relative to the best operator.
+/// The weights are shared between the neighborhood (sampling, generated neighbors) and the
+/// function between the steps of the local search (accepted steps, adaption). The final shares
+/// can be given as initial weights of the next run.
+pub struct OperatorWeights {
+    interval: u64,
+    minimal_share: f64,
+    maximal_share: f64,
+    reaction_factor: f64,
+    // the shares are only changed between two steps (stored as bits of the f64)
+    shares: [AtomicU64; NUMBER_OF_OPERATORS],
+    // number of candidate swaps seen by each operator (for sampling)
+    candidates: [AtomicU64; NUMBER_OF_OPERATORS],
+    // counters of the current interval
+    generated: [AtomicU64; NUMBER_OF_OPERATORS],
+    accepted: [AtomicU64; NUMBER_OF_OPERATORS],
+    steps: Mutex<u64>,
+}
+
+impl OperatorWeights {
+    /// Initial weights of unknown operators are ignored (with a warning).
+    pub fn new(config: &AdaptiveOperatorWeightsConfig) -> OperatorWeights {
+        for name in config.initial_weights.keys() {
+            if !Operator::ALL.iter().any(|operator| operator.name() == name) {
+                warn!("Initial weight of unknown operator {} is ignored.", name);
+            }
+        }
+        let shares = Operator::ALL.map(|operator| {
+            let share = config
+                .initial_weights
+                .get(operator.name())
+                .copied()
+                .unwrap_or(config.maximal_share)
+                .clamp(config.minimal_share, config.maximal_share);
+            AtomicU64::new(share.to_bits())
+        });
+        OperatorWeights {
+            interval: config.interval as u64,
+            minimal_share: config.minimal_share,
+            maximal_share: config.maximal_share,
+            reaction_factor: config.reaction_factor,
+            shares,
+            candidates: Default::default(),
+            generated: Default::default(),
+            accepted: Default::default(),
+            steps: Mutex::new(0),
+        }
+    }
+
+    pub fn share_of(&self, operator: Operator) -> f64 {
+        f64::from_bits(self.shares[operator.index()].load(Ordering::Relaxed))
+    }
+
+    /// Whether the next candidate swap of the operator is applied. Exactly a share of the
+    /// candidates is taken, evenly spread over the candidates (no randomness involved).
+    pub fn sample(&self, operator: Operator) -> bool {
+        let share = self.share_of(operator);
+        let candidate = self.candidates[operator.index()].fetch_add(1, Ordering::Relaxed) as f64;
+        ((candidate + 1.0) * share).floor() > (candidate * share).floor()
+    }
+
+    pub fn record_generated(&self, operator: Operator) {
+        self.generated[operator.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Called after each step of the local search with the swap that was accepted. Adapts the
+    /// shares every interval steps.
+    pub fn record_step(&self, swap_info: SwapInfo) {
+        if let Some(operator) = Operator::of(swap_info) {
+            self.accepted[operator.index()].fetch_add(1, Ordering::Relaxed);
+        }
+        let mut steps = self.steps.lock().unwrap();
+        *steps += 1;
+        if steps.is_multiple_of(self.interval) {
+            self.adapt();
+            info!("Adapted operator weights: {}", self.to_json());
+        }
+    }
+
+    /// Moves the shares towards the success rates of the current interval, scaled such that the
+    /// most successful operator gets maximal_share. Operators that did not generate any neighbor
+    /// keep their share. Without any accepted step, all shares are kept.
+    fn adapt(&self) {
+        let success_rates: Vec<Option<f64>> = Operator::ALL
+            .iter()
+            .map(|operator| {
+                let generated = self.generated[operator.index()].swap(0, Ordering::Relaxed);
+                let accepted = self.accepted[operator.index()].swap(0, Ordering::Relaxed);
+                if generated == 0 {
+                    None
+                } else {
+                    Some(accepted as f64 / generated as f64)
+                }
+            })
+            .collect();
+        let best_success_rate = success_rates
+            .iter()
+            .flatten()
+            .fold(0.0, |best: f64, &rate| best.max(rate));
+        if best_success_rate == 0.0 {
+            return;
+        }
+        for (operator, success_rate) in Operator::ALL.iter().zip(success_rates) {
+            if let Some(success_rate) = success_rate {
+                let target = self.minimal_share
+                    + (self.maximal_share - self.minimal_share) * success_rate / best_success_rate;
+                let share = ((1.0 - self.reaction_factor) * self.share_of(*operator)
+                    + self.reaction_factor * target)
+                    .clamp(self.minimal_share, self.maximal_share);
+                self.shares[operator.index()].store(share.to_bits(), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Operator name -> share (can be used as initialWeights).
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Object(
+            Operator::ALL
+                .iter()
+                .map(|&operator| {
+                    (
+                        operator.name().to_string(),
+                        serde_json::json!(self.share_of(operator)),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
@@ -15,27 +15,33 @@ mod add_trip_for_hitch_hiking;
 mod path_exchange;
 mod remove_single_node;
 mod spawn_vehicle_for_maintenance;
+mod spawn_vehicle_for_minimal_formation;
 pub use add_trip_for_hitch_hiking::AddTripForHitchHiking;
 pub use path_exchange::PathExchange;
 pub use remove_single_node::RemoveSingleNode;
 pub use spawn_vehicle_for_maintenance::SpawnVehicleForMaintenance;
-
-use std::fmt;
+pub use spawn_vehicle_for_minimal_formation::SpawnVehicleForMinimalFormation;
 
 use model::base_types::VehicleIdx;
+use model::network::Network;
 use solution::Schedule;
 
 /// An elementary modification. Defining the "neighborhood" for the local search.
-pub trait Swap: fmt::Display + Send + Sync {
+pub trait Swap: Send + Sync {
     fn apply(&self, schedule: &Schedule) -> Result<Schedule, String>;
+
+    /// For the logs, with the names of the nodes (see model::network::nodes::Node), so that the
+    /// swap can be correlated with the input.
+    fn describe(&self, network: &Network) -> String;
 }
 
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub enum SwapInfo {
-    SpawnVehicleForMaintenance(VehicleIdx), // last receiver
-    PathExchange(VehicleIdx),               // last provider
-    AddTripForHitchHiking(VehicleIdx),      // last vehicle
-    RemoveSingleNode(VehicleIdx),           // last vehicle
+    SpawnVehicleForMaintenance(VehicleIdx),      // last receiver
+    SpawnVehicleForMinimalFormation(VehicleIdx), // last receiver
+    PathExchange(VehicleIdx),                    // last provider
+    AddTripForHitchHiking(VehicleIdx),           // last vehicle
+    RemoveSingleNode(VehicleIdx),                // last vehicle
     NoSwap,
 }
 
@@ -11,9 +11,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::fmt;
-
 use model::base_types::{NodeIdx, VehicleIdx};
+use model::network::Network;
 use solution::{path::Path, Schedule};
 
 use super::{improve_depot_and_recompute_transitions, Swap};
@@ -52,10 +51,12 @@ impl Swap for AddTripForHitchHiking {
             )),
         }
     }
-}
 
-impl fmt::Display for AddTripForHitchHiking {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "AddTripForHitchHiking {} to {}", self.node, self.vehicle)
+    fn describe(&self, network: &Network) -> String {
+        format!(
+            "AddTripForHitchHiking {} to {}",
+            network.node(self.node),
+            self.vehicle
+        )
     }
 }
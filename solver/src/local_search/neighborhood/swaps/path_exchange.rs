@@ -11,9 +11,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::fmt;
-
 use model::base_types::VehicleIdx;
+use model::network::Network;
 use solution::{segment::Segment, Schedule};
 
 use super::{improve_depot_and_recompute_transitions, Swap};
@@ -21,6 +20,9 @@ use super::{improve_depot_and_recompute_transitions, Swap};
 /// Removes the path from the provider's tour and insert it into the receiver's tour.
 /// All removed nodes that are removed from receiver's tour (due to conflicts) are tried to insert conflict-free into
 /// the provider's tour.
+/// The depots of the changed tours are improved, i.e., the receiver's and the provider's if nodes
+/// were inserted into it or the tail of its tour was moved (its end depot would otherwise stay
+/// the one of the moved tail).
 pub struct PathExchange {
     segment: Segment,
     provider: VehicleIdx,
@@ -43,6 +45,12 @@ impl PathExchange {
 
 impl Swap for PathExchange {
     fn apply(&self, schedule: &Schedule) -> Result<Schedule, String> {
+        let provider_tail_moved = schedule.is_vehicle(self.provider) && {
+            let provider_tour = schedule.tour_of(self.provider).unwrap();
+            provider_tour.last_non_depot() == Some(self.segment.end())
+                || provider_tour.last_node() == self.segment.end()
+        };
+
         let (first_schedule, new_dummy_opt) =
             schedule.override_reassign(self.segment, self.provider, self.receiver)?;
 
@@ -58,6 +66,9 @@ impl Swap for PathExchange {
         ) {
             (None, _, _) => {
                 // no nodes were removed from receiver's tour -> no need for fit_reassign
+                if provider_tail_moved {
+                    vehicle_of_changed_tours.push(self.provider);
+                }
                 first_schedule
             }
             (Some(_), false, false) => {
@@ -90,14 +101,13 @@ impl Swap for PathExchange {
             vehicle_of_changed_tours,
         ))
     }
-}
 
-impl fmt::Display for PathExchange {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
+    fn describe(&self, network: &Network) -> String {
+        format!(
             "PathExchange {} from {} to {}",
-            self.segment, self.provider, self.receiver
+            self.segment.describe(network),
+            self.provider,
+            self.receiver
         )
     }
 }
@@ -11,9 +11,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::fmt;
-
 use model::base_types::{NodeIdx, VehicleIdx};
+use model::network::Network;
 use solution::{segment::Segment, Schedule};
 
 use super::Swap;
@@ -43,10 +42,12 @@ impl Swap for RemoveSingleNode {
             Ok(first_schedule)
         } */
     }
-}
 
-impl fmt::Display for RemoveSingleNode {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "RemoveSingleNode {} from {}", self.node, self.vehicle)
+    fn describe(&self, network: &Network) -> String {
+        format!(
+            "RemoveSingleNode {} from {}",
+            network.node(self.node),
+            self.vehicle
+        )
     }
 }
@@ -11,9 +11,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::fmt;
-
 use model::base_types::{NodeIdx, VehicleCount, VehicleIdx};
+use model::network::Network;
 use solution::{path::Path, segment::Segment, Schedule};
 
 use super::{improve_depot_and_recompute_transitions, Swap};
@@ -40,7 +39,11 @@ impl SpawnVehicleForMaintenance {
 
 impl Swap for SpawnVehicleForMaintenance {
     fn apply(&self, schedule: &Schedule) -> Result<Schedule, String> {
-        if schedule.tour_of(self.vehicle).unwrap().visits_maintenance() {
+        if schedule
+            .tour_of(self.vehicle)
+            .unwrap()
+            .visits_maintenance_slot()
+        {
             return Err(format!(
                 "Vehicle {} already visits maintenance slot",
                 self.vehicle
@@ -95,14 +98,12 @@ impl Swap for SpawnVehicleForMaintenance {
             changed_vehicles,
         ))
     }
-}
 
-impl fmt::Display for SpawnVehicleForMaintenance {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
+    fn describe(&self, network: &Network) -> String {
+        format!(
             "SpawnVehicleForMaintenance {} forced onto {}",
-            self.maintenance_slot, self.vehicle
+            network.node(self.maintenance_slot),
+            self.vehicle
         )
     }
 }
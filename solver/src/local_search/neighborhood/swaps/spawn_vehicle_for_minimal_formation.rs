@@ -0,0 +1,81 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use model::base_types::{NodeIdx, VehicleIdx};
+use model::network::Network;
+use solution::{path::Path, Schedule};
+
+use super::{improve_depot_and_recompute_transitions, Swap};
+
+/// Forces a service trip that is below its minimal formation count onto a given vehicle and
+/// spawns a new vehicle for the conflict path.
+#[derive(Clone)]
+pub struct SpawnVehicleForMinimalFormation {
+    service_trip: NodeIdx,
+    vehicle: VehicleIdx,
+}
+
+impl SpawnVehicleForMinimalFormation {
+    pub(crate) fn new(
+        service_trip: NodeIdx,
+        vehicle: VehicleIdx,
+    ) -> SpawnVehicleForMinimalFormation {
+        SpawnVehicleForMinimalFormation {
+            service_trip,
+            vehicle,
+        }
+    }
+}
+
+impl Swap for SpawnVehicleForMinimalFormation {
+    fn apply(&self, schedule: &Schedule) -> Result<Schedule, String> {
+        if schedule.formation_shortfall_at(self.service_trip) == 0 {
+            return Err(format!(
+                "{} already reaches its minimal formation count",
+                schedule.get_network().node(self.service_trip)
+            ));
+        }
+
+        let vehicle_type = schedule.vehicle_type_of(self.vehicle)?;
+        let mut changed_vehicles = vec![self.vehicle];
+
+        // add the service trip to the vehicle's tour
+        let (schedule1, conflict_path) = schedule.add_path_to_vehicle_tour(
+            self.vehicle,
+            Path::new_from_single_node(self.service_trip, schedule.get_network()),
+        )?;
+
+        // spawn a new vehicle for the conflict path
+        let schedule2 = if let Some(path) = conflict_path {
+            let (sched, new_vehicle) =
+                schedule1.spawn_vehicle_for_path(vehicle_type, path.consume())?;
+            changed_vehicles.push(new_vehicle);
+            sched
+        } else {
+            schedule1
+        };
+
+        Ok(improve_depot_and_recompute_transitions(
+            schedule2,
+            changed_vehicles,
+        ))
+    }
+
+    fn describe(&self, network: &Network) -> String {
+        format!(
+            "SpawnVehicleForMinimalFormation {} forced onto {}",
+            network.node(self.service_trip),
+            self.vehicle
+        )
+    }
+}
@@ -0,0 +1,78 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use model::base_types::VehicleIdx;
+use rapid_time::DateTime;
+use solution::Schedule;
+
+/// The idle gaps between consecutive nodes of the tours of a schedule, sorted by their start.
+/// A tour with an idle gap containing the time window of a segment can take the segment without
+/// displacing any of its nodes (apart from dead-head and shunting durations).
+/// Dummy tours are open at both ends, i.e., they have a gap before the first and after the last
+/// node.
+pub struct TourIntervalIndex {
+    gaps: Vec<(DateTime, DateTime, VehicleIdx)>, // (start, end, vehicle), sorted by start
+}
+
+impl TourIntervalIndex {
+    pub fn new(
+        schedule: &Schedule,
+        vehicles: impl Iterator<Item = VehicleIdx>,
+    ) -> TourIntervalIndex {
+        let network = schedule.get_network();
+        let mut gaps = Vec::new();
+        for vehicle in vehicles {
+            let tour = schedule.tour_of(vehicle).unwrap();
+            gaps.extend(tour.all_nodes_iter().tuple_windows().map(|(node1, node2)| {
+                (
+                    network.node(node1).end_time(),
+                    network.node(node2).start_time(),
+                    vehicle,
+                )
+            }));
+            if tour.is_dummy() {
+                // real tours start and end at a depot, which is open before and after
+                gaps.push((
+                    DateTime::Earliest,
+                    network.node(tour.first_node()).start_time(),
+                    vehicle,
+                ));
+                gaps.push((
+                    network.node(tour.last_node()).end_time(),
+                    DateTime::Latest,
+                    vehicle,
+                ));
+            }
+        }
+        gaps.sort();
+        TourIntervalIndex { gaps }
+    }
+
+    /// Vehicles whose tour has an idle gap containing [start, end].
+    pub fn vehicles_with_gap_containing(
+        &self,
+        start: DateTime,
+        end: DateTime,
+    ) -> HashSet<VehicleIdx> {
+        let candidates = self
+            .gaps
+            .partition_point(|&(gap_start, _, _)| gap_start <= start);
+        self.gaps[..candidates]
+            .iter()
+            .filter(|&&(_, gap_end, _)| gap_end >= end)
+            .map(|&(_, _, vehicle)| vehicle)
+            .collect()
+    }
+}
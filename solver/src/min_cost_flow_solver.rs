@@ -33,11 +33,10 @@ use rs_graph::IndexGraph;
 use rs_graph::LinkedListGraph;
 
 use std::collections::HashMap;
-use std::io;
-use std::io::Write;
 use std::iter::repeat;
 use std::sync::Arc;
 use std::time;
+use tracing::{info, warn};
 
 #[derive(Clone, Hash, Eq, PartialEq, Debug, Copy)]
 enum TripNode {
@@ -80,8 +79,13 @@ impl MinCostFlowSolver {
         let mut tours: HashMap<VehicleTypeIdx, Vec<Vec<NodeIdx>>> = HashMap::new();
         // PERF: parallelize this
         for vehicle_type in self.vehicle_types.iter() {
-            println!(
-                " solving sub-instance for vehicle type {}",
+            if self.network.service_nodes(vehicle_type).next().is_none() {
+                // vehicle types without service trips need no vehicles
+                tours.insert(vehicle_type, Vec::new());
+                continue;
+            }
+            info!(
+                "solving sub-instance for vehicle type {}",
                 self.network.vehicle_types().get(vehicle_type).unwrap()
             );
             tours.insert(
@@ -135,19 +139,30 @@ impl MinCostFlowSolver {
         // type has enough maintenance slot.
         // The next maintenance slot is assigned to the vehicle type with the smallest priority
         // counter.
+        // Vehicle types without service trips do not receive any maintenance slots.
         let mut priority_counter: Vec<(VehicleTypeIdx, f32)> = self
             .vehicle_types
             .iter()
+            .filter(|&vehicle_type| self.network.service_nodes(vehicle_type).next().is_some())
             .map(|vehicle_type| (vehicle_type, 0.0))
             .collect();
 
+        if priority_counter.is_empty() {
+            return maintenance_slots;
+        }
+
         for maintenance_node in self.network.maintenance_nodes() {
             for _ in 0..self
                 .network
                 .track_count_of_maintenance_slot(maintenance_node)
             {
-                let (vehicle_type, priority) = priority_counter
+                // e.g., a type out of service during the slot cannot use it
+                let Some((vehicle_type, priority)) = priority_counter
                     .iter_mut()
+                    .filter(|(vehicle_type, _)| {
+                        self.network
+                            .compatible_with_vehicle_type(maintenance_node, *vehicle_type)
+                    })
                     .min_by(|a, b| {
                         a.1.partial_cmp(&b.1).unwrap().then(
                             priority_increment[&a.0]
@@ -155,7 +170,9 @@ impl MinCostFlowSolver {
                                 .unwrap(),
                         )
                     })
-                    .unwrap();
+                else {
+                    break;
+                };
 
                 let entry = maintenance_slots
                     .get_mut(vehicle_type)
@@ -182,8 +199,7 @@ impl MinCostFlowSolver {
     ) -> Vec<Vec<NodeIdx>> {
         let start_time_creating_network = time::Instant::now();
 
-        print!("  1) creating min-cost-flow network - \x1b[93m 0%\x1b[0m");
-        io::stdout().flush().unwrap();
+        info!("1) creating min-cost-flow network");
 
         let mut builder = LinkedListGraph::<u32>::new_builder();
 
@@ -220,12 +236,9 @@ impl MinCostFlowSolver {
             left_rsnode_to_node.insert(left_rsnode, trip_node);
             right_rsnode_to_node.insert(right_rsnode, trip_node);
             node_to_rsnode.insert(trip_node, (left_rsnode, right_rsnode));
-            let number_of_vehicles_required = self
-                .network
-                .number_of_vehicles_required_to_serve(vehicle_type, service_trip)
-                as LowerBound;
-
-            let lower_bound = number_of_vehicles_required.min(maximal_formation_count); // if more vehicles are required than maximal_formation_count, use maximal_formation_count
+            let lower_bound = (self.network.minimal_number_of_vehicles_for(service_trip)
+                as LowerBound)
+                .min(maximal_formation_count); // without formation limit at most 100 vehicles
 
             let cost = self.network.node(service_trip).duration().in_sec().unwrap() as Cost
                 * self.config.costs.service_trip as Cost;
@@ -310,21 +323,11 @@ impl MinCostFlowSolver {
                 {
                     0
                 } else {
-                    self.network
-                        .idle_time_between(pred, node_id)
-                        .in_sec()
-                        .unwrap() as Cost
-                        * self.config.costs.idle as Cost
+                    self.network.idle_costs_between(pred, node_id) as Cost
                 };
 
-                let cost: Cost = self
-                    .network
-                    .dead_head_time_between(pred, node_id)
-                    .in_sec()
-                    .unwrap_or(self.network.planning_days().in_sec().unwrap())
-                    as Cost
-                    * self.config.costs.dead_head_trip as Cost
-                    + idle_time_cost;
+                let cost: Cost =
+                    self.network.dead_head_costs_between(pred, node_id) as Cost + idle_time_cost;
 
                 cost_overflow_checker = cost_overflow_checker
                     .checked_add(
@@ -343,17 +346,16 @@ impl MinCostFlowSolver {
                 );
             }
             if time_since_last_print.elapsed().as_secs_f32() >= 5.0 {
-                print!(
-                    "\r  1) creating min-cost-flow network - \x1b[93m{:>2}%\x1b[0m",
+                info!(
+                    "1) creating min-cost-flow network - {:>2}%",
                     (counter + 1) * 100 / trip_node_count,
                 );
-                io::stdout().flush().unwrap();
                 time_since_last_print = time::Instant::now();
             }
         }
 
-        println!(
-            "\r  1) creating min-cost-flow network - \x1b[32mdone ({:0.2}sec)\x1b[0m",
+        info!(
+            "1) creating min-cost-flow network - done ({:0.2}sec)",
             start_time_creating_network.elapsed().as_secs_f32()
         );
 
@@ -377,43 +379,107 @@ impl MinCostFlowSolver {
         // - Service trip duration <= planning days
         // - Dead head trip to the depot <= planning days
         // Hence, each vehicle costs at most costliest activity * 3 * planning days.
+        // Dead-head time factors can prolong the two dead head trips and add extra costs to them,
+        // which is covered by scaling with the maximal duration factor and adding the maximal
+        // extra costs twice. Staff time factors raise the costs per second of dead-head time, which
+        // is covered by scaling with the maximal staff time factor.
+        let locations = self.network.locations();
         let spawning_cost = (max_cost_per_sec as Cost)
             .checked_mul(3)
             .unwrap()
             .checked_mul(self.network.planning_days().in_sec().unwrap() as Cost)
             .unwrap()
+            .checked_mul(locations.maximal_duration_factor().ceil() as Cost)
+            .unwrap()
+            .checked_mul(self.config.costs.maximal_staff_time_factor().ceil() as Cost)
+            .unwrap()
+            .checked_add(
+                (locations.maximal_extra_cost() as Cost)
+                    .checked_mul(2)
+                    .unwrap(),
+            )
+            .unwrap()
             .checked_mul(total_lower_bound)
             .unwrap();
 
+        // If the fleet of this vehicle type is fixed, all regular depots are connected through a
+        // fleet hub, whose edge bounds the total number of vehicles. Vehicles might then end at a
+        // different depot than they start, which is fixed afterwards by the depot improvement.
+        // The overflow depot bypasses the hub.
+        let fleet_hub = self
+            .network
+            .fixed_fleet_size_of(vehicle_type)
+            .map(|fleet_size| {
+                let hub_in = builder.add_node();
+                let hub_out = builder.add_node();
+                edges.insert(
+                    builder.add_edge(hub_in, hub_out),
+                    EdgeLabel {
+                        lower_bound: 0,
+                        upper_bound: fleet_size as UpperBound,
+                        cost: 0,
+                    },
+                );
+                (hub_in, hub_out)
+            });
+        let overflow_depot = self.network.overflow_depot_idxs().0;
+
         for depot in self.network.depots_iter() {
             let (left_rsnode, right_rsnode) = node_to_rsnode[&TripNode::Depot(depot)];
-            let capacity = self.network.get_depot(depot).capacity_for(vehicle_type) as UpperBound;
+            // in the circulation, every vehicle spawned at a depot also despawns there
+            let capacity = VehicleCount::min(
+                self.network.get_depot(depot).capacity_for(vehicle_type),
+                self.network
+                    .get_depot(depot)
+                    .despawn_capacity_for(vehicle_type),
+            ) as UpperBound;
 
             cost_overflow_checker = cost_overflow_checker
                 .checked_add(spawning_cost.checked_mul(capacity).unwrap())
                 .unwrap_or_else(|| {
-                    println!("\x1b[93mwarning:\x1b[0m overflow in min_cost_flow_solver possible. Increase Cost type to i128 in solver/src/min_cost_flow_solver.rs");
+                    warn!("overflow in min_cost_flow_solver possible. Increase Cost type to i128 in solver/src/min_cost_flow_solver.rs");
                     0 as Cost
                 });
 
-            edges.insert(
-                builder.add_edge(left_rsnode, right_rsnode),
-                EdgeLabel {
-                    lower_bound: 0,
-                    upper_bound: capacity,
-                    cost: spawning_cost,
-                },
-            );
+            match fleet_hub {
+                Some((hub_in, hub_out)) if depot != overflow_depot => {
+                    edges.insert(
+                        builder.add_edge(left_rsnode, hub_in),
+                        EdgeLabel {
+                            lower_bound: 0,
+                            upper_bound: capacity,
+                            cost: 0,
+                        },
+                    );
+                    edges.insert(
+                        builder.add_edge(hub_out, right_rsnode),
+                        EdgeLabel {
+                            lower_bound: 0,
+                            upper_bound: capacity,
+                            cost: spawning_cost,
+                        },
+                    );
+                }
+                _ => {
+                    edges.insert(
+                        builder.add_edge(left_rsnode, right_rsnode),
+                        EdgeLabel {
+                            lower_bound: 0,
+                            upper_bound: capacity,
+                            cost: spawning_cost,
+                        },
+                    );
+                }
+            }
         }
         let graph = builder.into_graph();
 
         let start_time_computing_min_cost_flow = time::Instant::now();
-        print!(
-            "  2) computing min-cost-flow in network with {} nodes and {} edges",
+        info!(
+            "2) computing min-cost-flow in network with {} nodes and {} edges",
             graph.num_nodes(),
             graph.num_edges()
         );
-        io::stdout().flush().unwrap();
 
         let (_, flow) = network_simplex(
             &graph,
@@ -424,16 +490,13 @@ impl MinCostFlowSolver {
         )
         .unwrap();
 
-        println!(
-            "\r  2) computing min-cost-flow in network with {} nodes and {} edges - \x1b[32mdone ({:0.2}sec)\x1b[0m",
-            graph.num_nodes(),
-            graph.num_edges(),
+        info!(
+            "2) computing min-cost-flow - done ({:0.2}sec)",
             start_time_computing_min_cost_flow.elapsed().as_secs_f32()
         );
 
         let time_at_building_schedule = time::Instant::now();
-        print!("  3) building schedule");
-        io::stdout().flush().unwrap();
+        info!("3) building schedule");
 
         let mut tours: Vec<Vec<NodeIdx>> = Vec::new();
 
@@ -503,18 +566,18 @@ impl MinCostFlowSolver {
                             .push(tours.len() - 1);
                     }
                     (TripNode::Depot(_), TripNode::Depot(_)) => {
-                        println!("\x1b[93mwarning:\x1b[0m flow should not go from depot to depot");
+                        warn!("flow should not go from depot to depot");
                     }
                 }
             }
         }
-        println!(
-            "\r  3) building schedule - \x1b[32mdone ({:0.2}sec)\x1b[0m",
+        info!(
+            "3) building schedule - done ({:0.2}sec)",
             time_at_building_schedule.elapsed().as_secs_f32()
         );
         if print_overflow_depot_warning {
-            println!(
-                "\x1b[93mwarning:\x1b[0m Flow uses overflow depot for vehicle type {}.",
+            warn!(
+                "Flow uses overflow depot for vehicle type {}.",
                 vehicle_type
             );
         }
@@ -12,7 +12,11 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::local_search::ScheduleWithInfo;
-use rapid_solve::objective::{BaseValue, Coefficient, Indicator, LinearCombination, Objective};
+use model::base_types::Cost;
+use model::network::Network;
+use rapid_solve::objective::{
+    BaseValue, Coefficient, Indicator, LinearCombination, Objective, ObjectiveValue,
+};
 
 /// Sum over all service trips: max{0, passengers - capacity} + max{0, seated_passengers - seats}
 struct UnservedPassengersIndicator;
@@ -28,6 +32,38 @@ impl Indicator<ScheduleWithInfo> for UnservedPassengersIndicator {
     }
 }
 
+/// Sum over all service trips with a minimal formation count: max{0, minimal_formation_count -
+/// vehicle_count}
+struct FormationShortfallIndicator;
+
+impl Indicator<ScheduleWithInfo> for FormationShortfallIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        BaseValue::Integer(schedule_with_info.get_schedule().formation_shortfall() as i64)
+    }
+
+    fn name(&self) -> String {
+        String::from("formationShortfall")
+    }
+}
+
+/// Sum over all minimum-in-service requirements of the vehicle types: the largest number of
+/// vehicles missing within the window (see Schedule::minimum_in_service_shortfall)
+struct MinimumInServiceShortfallIndicator;
+
+impl Indicator<ScheduleWithInfo> for MinimumInServiceShortfallIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        BaseValue::Integer(
+            schedule_with_info
+                .get_schedule()
+                .minimum_in_service_shortfall() as i64,
+        )
+    }
+
+    fn name(&self) -> String {
+        String::from("minimumInServiceShortfall")
+    }
+}
+
 /// Each fleet is partitioned into rotation cycles, if total length exceeds the maintenance limit,
 /// the excess is counted as violation
 struct MaintenanceViolationIndicator;
@@ -42,6 +78,37 @@ impl Indicator<ScheduleWithInfo> for MaintenanceViolationIndicator {
     }
 }
 
+/// Number of vehicles whose transition cycle contains no maintenance visit. Their maintenance
+/// counters grow without bound over the periods, which the violation only captures as distance.
+struct VehiclesWithoutMaintenanceIndicator;
+
+impl Indicator<ScheduleWithInfo> for VehiclesWithoutMaintenanceIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        BaseValue::Integer(
+            schedule_with_info
+                .get_schedule()
+                .number_of_vehicles_without_maintenance() as i64,
+        )
+    }
+
+    fn name(&self) -> String {
+        String::from("vehiclesWithoutMaintenance")
+    }
+}
+
+/// Number of vehicles beyond the fixed fleet sizes (these are spawned from the overflow depot)
+struct FixedFleetViolationIndicator;
+
+impl Indicator<ScheduleWithInfo> for FixedFleetViolationIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        BaseValue::Integer(schedule_with_info.get_schedule().fixed_fleet_violation() as i64)
+    }
+
+    fn name(&self) -> String {
+        String::from("fixedFleetViolation")
+    }
+}
+
 /// Number of vehicles (each type count as 1)
 struct VehicleCountIndicator;
 
@@ -67,7 +134,130 @@ impl Indicator<ScheduleWithInfo> for CostsIndicator {
     }
 }
 
-pub fn build() -> Objective<ScheduleWithInfo> {
+/// Number of distinct vehicles serving a route summed over all routes, multiplied by the line
+/// purity costs
+struct LinePurityIndicator;
+
+impl Indicator<ScheduleWithInfo> for LinePurityIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        let schedule = schedule_with_info.get_schedule();
+        let weight = schedule.get_network().config().costs.line_purity;
+        BaseValue::Integer((weight * schedule.line_purity() as Cost) as i64)
+    }
+
+    fn name(&self) -> String {
+        String::from("linePurity")
+    }
+}
+
+/// Time vehicles hitch-hike on service trips (in seconds) multiplied by the hitch-hike bonus. The
+/// value is negative, so that hitch-hiking reduces the costs level independently of the relation
+/// between dead-head trip costs and service trip costs.
+struct HitchHikeBonusIndicator;
+
+impl Indicator<ScheduleWithInfo> for HitchHikeBonusIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        let schedule = schedule_with_info.get_schedule();
+        let weight = schedule.get_network().config().costs.hitch_hike_bonus;
+        BaseValue::Integer(-((weight * schedule.hitch_hiking_seconds()) as i64))
+    }
+
+    fn name(&self) -> String {
+        String::from("hitchHikeBonus")
+    }
+}
+
+/// Number of dead-head trips (see Tour::dead_head_trip_count) multiplied by their costs. Each
+/// dead-head trip needs a path request, independently of its distance.
+struct DeadHeadTripCountIndicator;
+
+impl Indicator<ScheduleWithInfo> for DeadHeadTripCountIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        let schedule = schedule_with_info.get_schedule();
+        let weight = schedule.get_network().config().costs.dead_head_trip_count;
+        BaseValue::Integer((weight * schedule.dead_head_trip_count() as Cost) as i64)
+    }
+
+    fn name(&self) -> String {
+        String::from("deadHeadTripCount")
+    }
+}
+
+/// Variance of the duty lengths within each vehicle type (see Schedule::duty_length_variance)
+/// multiplied by its costs, so that crews get tours of similar lengths.
+struct DutyLengthVarianceIndicator;
+
+impl Indicator<ScheduleWithInfo> for DutyLengthVarianceIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        let schedule = schedule_with_info.get_schedule();
+        let weight = schedule.get_network().config().costs.duty_length_variance;
+        BaseValue::Integer((weight * schedule.duty_length_variance()) as i64)
+    }
+
+    fn name(&self) -> String {
+        String::from("dutyLengthVariance")
+    }
+}
+
+/// Number of transitions where a vehicle is an inner unit of a formation and the lead unit of the
+/// formation of its next service trip (see Schedule::formation_order_shunts)
+struct FormationOrderShuntsIndicator;
+
+impl Indicator<ScheduleWithInfo> for FormationOrderShuntsIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        BaseValue::Integer(
+            schedule_with_info
+                .get_schedule()
+                .formation_order_shunts()
+                .len() as i64,
+        )
+    }
+
+    fn name(&self) -> String {
+        String::from("formationOrderShunts")
+    }
+}
+
+/// Number of idle times exceeding the platform idle limit of their location without a siding
+/// that can be reached and left again in time (see Schedule::platform_idle_violations)
+struct PlatformIdleViolationsIndicator;
+
+impl Indicator<ScheduleWithInfo> for PlatformIdleViolationsIndicator {
+    fn evaluate(&self, schedule_with_info: &ScheduleWithInfo) -> BaseValue {
+        BaseValue::Integer(schedule_with_info.get_schedule().platform_idle_violations() as i64)
+    }
+
+    fn name(&self) -> String {
+        String::from("platformIdleViolations")
+    }
+}
+
+/// If the fleet size of some vehicle type is fixed, the number of vehicles is no longer a
+/// decision. Then exceeding the fixed fleet becomes the top level, followed by the unserved
+/// passengers, and the vehicle count level is dropped.
+/// If some location has a platform idle limit, the platform idle violations form an additional
+/// level below the vehicle count.
+/// If formation order continuity is enabled, the formation order shunts form an additional level
+/// right above the costs.
+/// If maintenance is considered, the vehicles without maintenance form a level right above the
+/// maintenance violation.
+/// If some vehicle type has a minimum-in-service requirement, its shortfall forms a level right
+/// below the formation shortfall.
+pub fn build(network: &Network) -> Objective<ScheduleWithInfo> {
+    build_with_vehicle_count_position(network, false)
+}
+
+/// Same as build, but the vehicle count is the dominant level right below the coverage levels
+/// (unserved passengers, formation shortfall and minimum-in-service shortfall), i.e., above the
+/// maintenance (used by the fleet phase of parameters.solver.twoPhase).
+pub fn build_fleet_first(network: &Network) -> Objective<ScheduleWithInfo> {
+    build_with_vehicle_count_position(network, true)
+}
+
+fn build_with_vehicle_count_position(
+    network: &Network,
+    fleet_first: bool,
+) -> Objective<ScheduleWithInfo> {
     let maintenance_violation = LinearCombination::new(vec![(
         Coefficient::Integer(1),
         Box::new(MaintenanceViolationIndicator),
@@ -78,17 +268,197 @@ pub fn build() -> Objective<ScheduleWithInfo> {
         Box::new(UnservedPassengersIndicator),
     )]);
 
+    let formation_shortfall = LinearCombination::new(vec![(
+        Coefficient::Integer(1),
+        Box::new(FormationShortfallIndicator),
+    )]);
+
     let vehicle_count = LinearCombination::new(vec![(
         Coefficient::Integer(1),
         Box::new(VehicleCountIndicator),
     )]);
 
-    let costs = LinearCombination::new(vec![(Coefficient::Integer(1), Box::new(CostsIndicator))]);
+    // line purity, the hitch-hike bonus, the dead-head trip count and the duty length variance
+    // are only evaluated if they have a weight, as they are not maintained incrementally
+    let config = network.config();
+    let mut costs: Vec<(Coefficient, Box<dyn Indicator<ScheduleWithInfo>>)> =
+        vec![(Coefficient::Integer(1), Box::new(CostsIndicator))];
+    if config.costs.line_purity > 0 {
+        costs.push((Coefficient::Integer(1), Box::new(LinePurityIndicator)));
+    }
+    if config.costs.hitch_hike_bonus > 0 {
+        costs.push((Coefficient::Integer(1), Box::new(HitchHikeBonusIndicator)));
+    }
+    if config.costs.dead_head_trip_count > 0 {
+        costs.push((
+            Coefficient::Integer(1),
+            Box::new(DeadHeadTripCountIndicator),
+        ));
+    }
+    if config.costs.duty_length_variance > 0 {
+        costs.push((
+            Coefficient::Integer(1),
+            Box::new(DutyLengthVarianceIndicator),
+        ));
+    }
+    let costs = LinearCombination::new(costs);
+
+    let mut levels = if network.has_fixed_fleet() {
+        let fixed_fleet_violation = LinearCombination::new(vec![(
+            Coefficient::Integer(1),
+            Box::new(FixedFleetViolationIndicator),
+        )]);
+
+        vec![
+            fixed_fleet_violation,
+            unserved_passengers,
+            formation_shortfall,
+        ]
+    } else {
+        vec![unserved_passengers, formation_shortfall]
+    };
+
+    // the minimum-in-service shortfall is only evaluated if required, as it is not maintained
+    // incrementally
+    if network.vehicle_types().has_minimum_in_service() {
+        levels.push(LinearCombination::new(vec![(
+            Coefficient::Integer(1),
+            Box::new(MinimumInServiceShortfallIndicator),
+        )]));
+    }
+    let mut vehicle_count = Some(vehicle_count);
+    if fleet_first && !network.has_fixed_fleet() {
+        levels.extend(vehicle_count.take());
+    }
 
-    Objective::new(vec![
-        unserved_passengers,
-        maintenance_violation,
-        vehicle_count,
-        costs,
-    ])
+    // vehicles without maintenance are only evaluated if maintenance is considered, as they are
+    // not maintained incrementally
+    if network.maintenance_considered() {
+        levels.push(LinearCombination::new(vec![(
+            Coefficient::Integer(1),
+            Box::new(VehiclesWithoutMaintenanceIndicator),
+        )]));
+    }
+    levels.push(maintenance_violation);
+    if !network.has_fixed_fleet() {
+        levels.extend(vehicle_count);
+    }
+
+    // platform idle violations and formation order shunts are only evaluated if enabled, as they
+    // are not maintained incrementally
+    if network.locations().has_platform_idle_limits() {
+        levels.push(LinearCombination::new(vec![(
+            Coefficient::Integer(1),
+            Box::new(PlatformIdleViolationsIndicator),
+        )]));
+    }
+    if network.config().formation_order_continuity {
+        levels.push(LinearCombination::new(vec![(
+            Coefficient::Integer(1),
+            Box::new(FormationOrderShuntsIndicator),
+        )]));
+    }
+    levels.push(costs);
+
+    Objective::new(levels)
+}
+
+/// The levels of the objective created by build() (top level first), each given by the names of
+/// its indicators. All coefficients are 1.
+pub fn definition_to_json(network: &Network) -> serde_json::Value {
+    let mut costs = vec![CostsIndicator.name()];
+    if network.config().costs.line_purity > 0 {
+        costs.push(LinePurityIndicator.name());
+    }
+    if network.config().costs.hitch_hike_bonus > 0 {
+        costs.push(HitchHikeBonusIndicator.name());
+    }
+    if network.config().costs.dead_head_trip_count > 0 {
+        costs.push(DeadHeadTripCountIndicator.name());
+    }
+    if network.config().costs.duty_length_variance > 0 {
+        costs.push(DutyLengthVarianceIndicator.name());
+    }
+
+    let mut levels = if network.has_fixed_fleet() {
+        vec![
+            vec![FixedFleetViolationIndicator.name()],
+            vec![UnservedPassengersIndicator.name()],
+            vec![FormationShortfallIndicator.name()],
+        ]
+    } else {
+        vec![
+            vec![UnservedPassengersIndicator.name()],
+            vec![FormationShortfallIndicator.name()],
+        ]
+    };
+    if network.vehicle_types().has_minimum_in_service() {
+        levels.push(vec![MinimumInServiceShortfallIndicator.name()]);
+    }
+    if network.maintenance_considered() {
+        levels.push(vec![VehiclesWithoutMaintenanceIndicator.name()]);
+    }
+    levels.push(vec![MaintenanceViolationIndicator.name()]);
+    if !network.has_fixed_fleet() {
+        levels.push(vec![VehicleCountIndicator.name()]);
+    }
+    if network.locations().has_platform_idle_limits() {
+        levels.push(vec![PlatformIdleViolationsIndicator.name()]);
+    }
+    if network.config().formation_order_continuity {
+        levels.push(vec![FormationOrderShuntsIndicator.name()]);
+    }
+    levels.push(costs);
+    serde_json::json!(levels)
+}
+
+/// Structured counterpart of Objective::print_objective_value_with_comparison: per indicator
+/// (same names as in objective_value_to_json) the old value, the new value and the delta (null
+/// for non-numeric values).
+pub fn objective_value_comparison_to_json<S>(
+    objective: &Objective<S>,
+    old_value: &ObjectiveValue,
+    new_value: &ObjectiveValue,
+) -> serde_json::Value {
+    json_comparison(
+        &objective.objective_value_to_json(old_value),
+        &objective.objective_value_to_json(new_value),
+    )
+}
+
+/// Compares two objective values given as json (e.g., evaluated by the objectives of two
+/// different instances): each indicator of old with its value in new and the delta.
+pub fn json_comparison(old: &serde_json::Value, new: &serde_json::Value) -> serde_json::Value {
+    match (old, new) {
+        (serde_json::Value::Object(old_map), serde_json::Value::Object(new_map)) => {
+            serde_json::Value::Object(
+                old_map
+                    .iter()
+                    .map(|(name, old_value)| {
+                        (
+                            name.clone(),
+                            json_comparison(
+                                old_value,
+                                new_map.get(name).unwrap_or(&serde_json::Value::Null),
+                            ),
+                        )
+                    })
+                    .collect(),
+            )
+        }
+        _ => {
+            let delta = match (old.as_i64(), new.as_i64()) {
+                (Some(old), Some(new)) => serde_json::json!(new - old),
+                _ => match (old.as_f64(), new.as_f64()) {
+                    (Some(old), Some(new)) => serde_json::json!(new - old),
+                    _ => serde_json::Value::Null,
+                },
+            };
+            serde_json::json!({
+                "old": old,
+                "new": new,
+                "delta": delta,
+            })
+        }
+    }
 }
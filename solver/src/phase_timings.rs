@@ -0,0 +1,145 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time as stdtime;
+
+/// Counts the steps of a local search (shared with the function between steps of the solver).
+pub type IterationCounter = Arc<AtomicU32>;
+
+/// Wall-clock time of each phase of a solve (in the order the phases were run), for local
+/// searches together with the number of steps and further diagnostics (e.g., the learned operator
+/// weights). The console output and the info of the output json are both derived from this.
+#[derive(Clone, Default)]
+pub struct PhaseTimings {
+    phases: Vec<PhaseTiming>,
+}
+
+#[derive(Clone)]
+pub struct PhaseTiming {
+    name: &'static str,
+    duration: stdtime::Duration,
+    iterations: Option<u32>,
+    diagnostics: serde_json::Map<String, serde_json::Value>,
+}
+
+impl PhaseTimings {
+    pub fn new() -> PhaseTimings {
+        PhaseTimings { phases: Vec::new() }
+    }
+
+    /// Runs f and records its duration under the given name.
+    pub fn measure<T>(&mut self, name: &'static str, f: impl FnOnce() -> T) -> T {
+        let start_time = stdtime::Instant::now();
+        let result = f();
+        self.phases.push(PhaseTiming {
+            name,
+            duration: start_time.elapsed(),
+            iterations: None,
+            diagnostics: serde_json::Map::new(),
+        });
+        result
+    }
+
+    /// Same as measure, but f gets a counter for its iterations (e.g., to be passed to a local
+    /// search solver), which is recorded as well.
+    pub fn measure_with_iterations<T>(
+        &mut self,
+        name: &'static str,
+        f: impl FnOnce(IterationCounter) -> T,
+    ) -> T {
+        let iteration_counter = IterationCounter::default();
+        let result = self.measure(name, || f(iteration_counter.clone()));
+        self.phases.last_mut().unwrap().iterations =
+            Some(iteration_counter.load(Ordering::Relaxed));
+        result
+    }
+
+    /// Attaches a diagnostic value to the phase that was recorded last.
+    pub fn add_diagnostics(&mut self, key: &str, value: serde_json::Value) {
+        self.phases
+            .last_mut()
+            .expect("no phase recorded")
+            .diagnostics
+            .insert(key.to_string(), value);
+    }
+
+    /// The phase that was recorded last.
+    pub fn last(&self) -> Option<&PhaseTiming> {
+        self.phases.last()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PhaseTiming> {
+        self.phases.iter()
+    }
+
+    /// Phase name -> {"seconds": Float, "iterations": Int (only for local searches), diagnostics}.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::Value::Object(
+            self.phases
+                .iter()
+                .map(|phase| {
+                    let mut timing = serde_json::json!({
+                        "seconds": phase.duration.as_secs_f64(),
+                    });
+                    if let Some(iterations) = phase.iterations {
+                        timing["iterations"] = serde_json::json!(iterations);
+                    }
+                    for (key, value) in phase.diagnostics.iter() {
+                        timing[key] = value.clone();
+                    }
+                    (phase.name.to_string(), timing)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl PhaseTiming {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn duration(&self) -> stdtime::Duration {
+        self.duration
+    }
+
+    pub fn iterations(&self) -> Option<u32> {
+        self.iterations
+    }
+
+    pub fn diagnostics(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.diagnostics
+    }
+}
+
+impl fmt::Display for PhaseTiming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {:0.2}sec", self.name, self.duration.as_secs_f32())?;
+        if let Some(iterations) = self.iterations {
+            write!(f, " ({} iterations)", iterations)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PhaseTimings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for phase in self.phases.iter() {
+            writeln!(f, "{}", phase)?;
+        }
+        Ok(())
+    }
+}
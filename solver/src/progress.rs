@@ -0,0 +1,62 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::local_search::neighborhood::swaps::SwapInfo;
+use solution::Schedule;
+use std::fmt;
+use std::sync::Arc;
+
+/// The incumbent of a local search after an (improving) step.
+#[derive(Clone, Debug)]
+pub struct Improvement {
+    pub iteration: u32, // steps of the local search so far (over all time windows)
+    pub objective_value: serde_json::Value, // see Objective::objective_value_to_json
+    pub swap_info: SwapInfo, // the swap of the step
+    pub description: String, // of the swap of the step, as in the log
+}
+
+type Report = dyn Fn(Improvement, &Schedule) + Send + Sync;
+
+/// Receives each improvement of the local search together with the incumbent schedule (e.g., to
+/// notify a client about the progress of the solve). It is called from the local search thread,
+/// so it should return quickly.
+#[derive(Clone)]
+pub struct ProgressSink {
+    report: Arc<Report>,
+}
+
+impl ProgressSink {
+    pub fn new(report: impl Fn(Improvement, &Schedule) + Send + Sync + 'static) -> ProgressSink {
+        ProgressSink {
+            report: Arc::new(report),
+        }
+    }
+
+    pub fn report(&self, improvement: Improvement, schedule: &Schedule) {
+        (self.report)(improvement, schedule)
+    }
+
+    /// A sink reporting each improvement to both sinks (first to self).
+    pub fn and(self, other: ProgressSink) -> ProgressSink {
+        ProgressSink::new(move |improvement: Improvement, schedule: &Schedule| {
+            self.report(improvement.clone(), schedule);
+            other.report(improvement, schedule);
+        })
+    }
+}
+
+impl fmt::Debug for ProgressSink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProgressSink")
+    }
+}
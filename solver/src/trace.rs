@@ -0,0 +1,130 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use model::base_types::VehicleIdx;
+use model::network::Network;
+use solution::Schedule;
+use tracing::warn;
+
+use crate::local_search::neighborhood::operator_weights::{Operator, OperatorWeights};
+use crate::local_search::neighborhood::swaps::SwapInfo;
+use crate::progress::{Improvement, ProgressSink};
+
+/// The trace writer of parameters.debug.traceFile (None if not configured). If the file cannot
+/// be created, the local search is not traced.
+pub fn build_trace_writer(
+    network: &Network,
+    operator_weights: Option<Arc<OperatorWeights>>,
+    initial_objective_value: impl FnOnce() -> serde_json::Value,
+) -> Option<Arc<TraceWriter>> {
+    let path = network.config().trace_file.clone()?;
+    match TraceWriter::create(&path, operator_weights, initial_objective_value()) {
+        Ok(trace_writer) => Some(trace_writer),
+        Err(error) => {
+            warn!("{}, the local search is not traced", error);
+            None
+        }
+    }
+}
+
+/// Writes one json line per local search step into a file (see parameters.debug.traceFile):
+/// {"iteration", "elapsedSeconds", "operator", "vehicle", "objectiveValue", "operatorShares"}
+/// where operator is the operator of the accepted swap (null for NoSwap), vehicle the internal
+/// index of its last vehicle (see SwapInfo) and operatorShares only present with adaptive
+/// operator weights. The first line (iteration 0, no operator) holds the objective value of the
+/// start schedule. The elapsed time is measured since the creation of the writer.
+/// The lines are buffered, a step only costs the serialization of its line. A failing write is
+/// logged and never affects the solve.
+pub struct TraceWriter {
+    start_time: Instant,
+    operator_weights: Option<Arc<OperatorWeights>>,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl TraceWriter {
+    /// Creates (or truncates) the trace file and writes the first line.
+    pub fn create(
+        path: &str,
+        operator_weights: Option<Arc<OperatorWeights>>,
+        initial_objective_value: serde_json::Value,
+    ) -> Result<Arc<TraceWriter>, String> {
+        let file = File::create(path)
+            .map_err(|error| format!("Cannot create trace file {}: {}", path, error))?;
+        let trace_writer = TraceWriter {
+            start_time: Instant::now(),
+            operator_weights,
+            writer: Mutex::new(BufWriter::new(file)),
+        };
+        trace_writer.record(Improvement {
+            iteration: 0,
+            objective_value: initial_objective_value,
+            swap_info: SwapInfo::NoSwap,
+            description: String::new(),
+        });
+        Ok(Arc::new(trace_writer))
+    }
+
+    /// Writes a line for each step of the local search.
+    pub fn progress_sink(self: &Arc<Self>) -> ProgressSink {
+        let trace_writer = self.clone();
+        ProgressSink::new(move |improvement: Improvement, _: &Schedule| {
+            trace_writer.record(improvement)
+        })
+    }
+
+    /// Flushes the buffered lines (also done when the writer is dropped, but without reporting
+    /// errors).
+    pub fn finish(&self) -> Result<(), String> {
+        self.writer
+            .lock()
+            .unwrap()
+            .flush()
+            .map_err(|error| format!("Cannot write trace file: {}", error))
+    }
+
+    fn record(&self, improvement: Improvement) {
+        let mut line = serde_json::json!({
+            "iteration": improvement.iteration,
+            "elapsedSeconds": self.start_time.elapsed().as_secs_f64(),
+            "operator": Operator::of(improvement.swap_info).map(Operator::name),
+            "vehicle": last_vehicle(improvement.swap_info).map(|vehicle| vehicle.idx()),
+            "objectiveValue": improvement.objective_value,
+        });
+        if let Some(operator_weights) = &self.operator_weights {
+            line["operatorShares"] = operator_weights.to_json();
+        }
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(error) = serde_json::to_writer(&mut *writer, &line)
+            .map_err(|error| error.to_string())
+            .and_then(|()| writeln!(writer).map_err(|error| error.to_string()))
+        {
+            warn!("Cannot write trace line: {}", error);
+        }
+    }
+}
+
+fn last_vehicle(swap_info: SwapInfo) -> Option<VehicleIdx> {
+    match swap_info {
+        SwapInfo::SpawnVehicleForMaintenance(vehicle)
+        | SwapInfo::SpawnVehicleForMinimalFormation(vehicle)
+        | SwapInfo::PathExchange(vehicle)
+        | SwapInfo::AddTripForHitchHiking(vehicle)
+        | SwapInfo::RemoveSingleNode(vehicle) => Some(vehicle),
+        SwapInfo::NoSwap => None,
+    }
+}
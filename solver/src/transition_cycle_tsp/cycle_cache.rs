@@ -0,0 +1,87 @@
+// © 2023-2024 ETH Zurich
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// any later version.
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap as StdHashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+use im::HashMap;
+use model::base_types::{MaintenanceCounter, NodeIdx, VehicleIdx};
+use solution::{tour::Tour, transition::transition_cycle::TransitionCycle};
+
+/// For each vehicle of the cycle (sorted by id): its maintenance counter and the depots of its
+/// tour. The cycle TSP only depends on these, so a changed tour leads to a different key.
+type CycleKey = Vec<(VehicleIdx, MaintenanceCounter, NodeIdx, NodeIdx)>;
+
+/// Memoizes the solutions of the transition cycle TSP, as the transition local search revisits
+/// the same vehicle sets over and over again.
+/// A cached cycle is only returned if no tour of its vehicles changed in the meantime (see
+/// CycleKey). The optimized cycle carries its maintenance counter, so its violation does not
+/// need to be recomputed either.
+#[derive(Default)]
+pub struct TransitionCycleTspCache {
+    cycles: RwLock<StdHashMap<CycleKey, TransitionCycle>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TransitionCycleTspCache {
+    /// Returns the cached optimized cycle for the vehicles of the given cycle. If there is none,
+    /// solve is called and its result is cached.
+    pub fn get_or_solve(
+        &self,
+        cycle: &TransitionCycle,
+        tours: &HashMap<VehicleIdx, Tour>,
+        solve: impl FnOnce() -> TransitionCycle,
+    ) -> TransitionCycle {
+        let key = key_of(cycle, tours);
+        if let Some(cached_cycle) = self.cycles.read().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return cached_cycle.clone();
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let optimized_cycle = solve();
+        self.cycles
+            .write()
+            .unwrap()
+            .insert(key, optimized_cycle.clone());
+        optimized_cycle
+    }
+
+    /// Number of hits and misses as well as the number of cached cycles (for measuring the
+    /// effect of the cache).
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "hits": self.hits.load(Ordering::Relaxed),
+            "misses": self.misses.load(Ordering::Relaxed),
+            "cachedCycles": self.cycles.read().unwrap().len(),
+        })
+    }
+}
+
+fn key_of(cycle: &TransitionCycle, tours: &HashMap<VehicleIdx, Tour>) -> CycleKey {
+    let mut key: CycleKey = cycle
+        .iter()
+        .map(|vehicle| {
+            let tour = tours.get(&vehicle).unwrap();
+            (
+                vehicle,
+                tour.maintenance_counter(),
+                tour.start_depot().unwrap(),
+                tour.end_depot().unwrap(),
+            )
+        })
+        .collect();
+    key.sort();
+    key
+}
@@ -11,6 +11,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod cycle_cache;
 pub mod transition_cycle_neighborhood;
 pub mod transition_cycle_objective;
 
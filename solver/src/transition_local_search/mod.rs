@@ -13,6 +13,7 @@
 
 mod transition_neighborhood;
 mod transition_objective;
+use std::sync::atomic::Ordering;
 use std::time as stdtime;
 use std::{sync::Arc, time::Instant};
 
@@ -21,7 +22,10 @@ use rapid_solve::heuristics::parallel_local_search::ParallelLocalSearchSolver;
 use rapid_solve::objective::{EvaluatedSolution, Objective};
 use solution::{transition::Transition, Schedule};
 
+use crate::phase_timings::IterationCounter;
 use crate::transition_cycle_tsp;
+use crate::transition_cycle_tsp::cycle_cache::TransitionCycleTspCache;
+use tracing::info;
 
 use self::transition_neighborhood::TransitionNeighborhood;
 
@@ -51,55 +55,68 @@ impl TransitionWithInfo {
     }
 }
 
+/// Each step of the local search increments the steps counter.
+/// The solutions of the cycle TSP are memoized in the given cache (which can be shared by the
+/// solves of all vehicle types).
+/// The time limit (None means unlimited) applies to each solve separately.
 pub fn build_transition_local_search_solver(
     schedule: &Schedule,
     network: Arc<Network>,
+    steps: IterationCounter,
+    cycle_cache: Arc<TransitionCycleTspCache>,
+    time_limit: Option<stdtime::Duration>,
 ) -> ParallelLocalSearchSolver<TransitionWithInfo> {
     let transition_cycle_tsp_solver =
         transition_cycle_tsp::build_transition_cycle_tsp_solver(schedule, network.clone());
 
-    let objective = Arc::new(transition_objective::build());
+    let objective = Arc::new(transition_objective::build(
+        schedule.get_tours().clone(),
+        network.clone(),
+    ));
 
     let neighborhood = Arc::new(TransitionNeighborhood::new(
         schedule.get_tours().clone(),
         transition_cycle_tsp_solver,
+        cycle_cache,
         network.clone(),
     ));
 
     let function_between_steps = Box::new(
-        |iteration_counter: u32,
-         current_solution: &EvaluatedSolution<TransitionWithInfo>,
-         previous_solution: Option<&EvaluatedSolution<TransitionWithInfo>>,
-         objective: Arc<Objective<TransitionWithInfo>>,
-         start_time: Option<Instant>,
-         _: Option<stdtime::Duration>,
-         _: Option<u32>| {
-            println!(
+        move |iteration_counter: u32,
+              current_solution: &EvaluatedSolution<TransitionWithInfo>,
+              previous_solution: Option<&EvaluatedSolution<TransitionWithInfo>>,
+              objective: Arc<Objective<TransitionWithInfo>>,
+              start_time: Option<Instant>,
+              _: Option<stdtime::Duration>,
+              _: Option<u32>| {
+            steps.fetch_add(1, Ordering::Relaxed);
+            info!(
                 "Iteration {} - Swap: {}",
                 iteration_counter,
                 current_solution.solution().get_print_text()
             );
-            println!("Objective value:");
             match previous_solution {
-                Some(prev_solution) => {
-                    objective.print_objective_value_with_comparison(
-                        current_solution.objective_value(),
-                        prev_solution.objective_value(),
-                    );
-                }
-                None => {
-                    objective.print_objective_value(current_solution.objective_value());
-                }
+                Some(previous_solution) => info!(
+                    "Objective value: {}",
+                    crate::objective::objective_value_comparison_to_json(
+                        &objective,
+                        previous_solution.objective_value(),
+                        current_solution.objective_value()
+                    )
+                ),
+                None => info!(
+                    "Objective value: {}",
+                    objective.objective_value_to_json(current_solution.objective_value())
+                ),
             }
             if let Some(start_time) = start_time {
-                println!(
+                info!(
                     "elapsed time for local search: {:0.2}sec",
                     stdtime::Instant::now()
                         .duration_since(start_time)
                         .as_secs_f32()
                 );
             }
-            println!();
         },
     );
 
@@ -108,7 +125,7 @@ pub fn build_transition_local_search_solver(
         objective,
         None,
         Some(function_between_steps),
-        None,
+        time_limit,
         None,
     )
 }
@@ -21,6 +21,7 @@ use rapid_solve::heuristics::Solver;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use solution::tour::Tour;
 
+use crate::transition_cycle_tsp::cycle_cache::TransitionCycleTspCache;
 use crate::transition_cycle_tsp::TransitionCycleWithInfo;
 
 use super::TransitionWithInfo;
@@ -28,6 +29,7 @@ use super::TransitionWithInfo;
 pub struct TransitionNeighborhood {
     tours: HashMap<VehicleIdx, Tour>,
     cycle_tsp_solver: LocalSearchSolver<TransitionCycleWithInfo>,
+    cycle_cache: Arc<TransitionCycleTspCache>,
     network: Arc<Network>,
 }
 
@@ -35,11 +37,13 @@ impl TransitionNeighborhood {
     pub fn new(
         tours: HashMap<VehicleIdx, Tour>,
         cycle_tsp_solver: LocalSearchSolver<TransitionCycleWithInfo>,
+        cycle_cache: Arc<TransitionCycleTspCache>,
         network: Arc<Network>,
     ) -> TransitionNeighborhood {
         TransitionNeighborhood {
             tours,
             cycle_tsp_solver,
+            cycle_cache,
             network,
         }
     }
@@ -173,15 +177,17 @@ impl ParallelNeighborhood<TransitionWithInfo> for TransitionNeighborhood {
                     .into_iter()
                     .for_each(|cycle_idx| {
                         let new_cycle = new_transition.get_cycle(cycle_idx);
-                        let start_cycle = TransitionCycleWithInfo::new(
-                            new_cycle.clone(),
-                            format!("Initial cycle {}", new_cycle),
-                        );
-                        let improved_cycle = self
-                            .cycle_tsp_solver
-                            .solve(start_cycle)
-                            .unwrap()
-                            .unwrap_cycle();
+                        let improved_cycle =
+                            self.cycle_cache.get_or_solve(new_cycle, &self.tours, || {
+                                let start_cycle = TransitionCycleWithInfo::new(
+                                    new_cycle.clone(),
+                                    format!("Initial cycle {}", new_cycle),
+                                );
+                                self.cycle_tsp_solver
+                                    .solve(start_cycle)
+                                    .unwrap()
+                                    .unwrap_cycle()
+                            });
                         new_transition = new_transition.replace_cycle(cycle_idx, improved_cycle);
                     });
 
@@ -11,7 +11,12 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
+
+use im::HashMap;
+use model::{base_types::VehicleIdx, network::Network};
 use rapid_solve::objective::{BaseValue, Coefficient, Indicator, LinearCombination, Objective};
+use solution::tour::Tour;
 
 use super::TransitionWithInfo;
 
@@ -43,11 +48,45 @@ impl Indicator<TransitionWithInfo> for MaintenanceCounterIndicator {
     }
 }
 
-pub fn build() -> Objective<TransitionWithInfo> {
-    let maintenance_violation = LinearCombination::new(vec![(
-        Coefficient::Integer(1),
-        Box::new(MaintenanceViolationIndicator),
-    )]);
+/// Dead-head distance from the last location of each vehicle to the start depot of its
+/// successor. This is the repositioning that is needed when the end depots are made consistent
+/// with the transition afterwards.
+struct DepotConsistencyDeadHeadIndicator {
+    tours: HashMap<VehicleIdx, Tour>,
+    network: Arc<Network>,
+}
+
+impl Indicator<TransitionWithInfo> for DepotConsistencyDeadHeadIndicator {
+    fn evaluate(&self, transition_with_info: &TransitionWithInfo) -> BaseValue {
+        BaseValue::Integer(
+            transition_with_info
+                .get_transition()
+                .depot_consistency_dead_head_distance(&self.tours, &self.network)
+                as i64,
+        )
+    }
+
+    fn name(&self) -> String {
+        String::from("depotConsistencyDeadHead")
+    }
+}
+
+/// Maintenance violation and depot-consistency dead-head distance are both measured in meters,
+/// so they are traded against each other one to one.
+pub fn build(
+    tours: HashMap<VehicleIdx, Tour>,
+    network: Arc<Network>,
+) -> Objective<TransitionWithInfo> {
+    let maintenance_violation = LinearCombination::new(vec![
+        (
+            Coefficient::Integer(1),
+            Box::new(MaintenanceViolationIndicator),
+        ),
+        (
+            Coefficient::Integer(1),
+            Box::new(DepotConsistencyDeadHeadIndicator { tours, network }),
+        ),
+    ]);
 
     let maintenance_counter = LinearCombination::new(vec![(
         Coefficient::Integer(1),